@@ -4,6 +4,7 @@ set -eux
 
 cargo check --quiet --workspace --all-targets
 cargo check --quiet --workspace --all-features --lib --target wasm32-unknown-unknown
+cargo check --quiet -p gb-core --no-default-features --features scripting --lib --target wasm32-unknown-unknown
 cargo fmt --all -- --check
 cargo clippy --quiet --workspace --all-targets --all-features --  -D warnings -W clippy::all
 cargo test --quiet --workspace --all-targets --all-features
@@ -0,0 +1,81 @@
+//The CPU's arithmetic. Each operation returns its result along with the whole F register it leaves behind, so the flag
+//rules live in one place instead of being worked out again by every opcode. Everything wraps like the hardware does.
+//Operations that leave some flags alone take the old F to carry them over
+pub const Z_ZERO_FLAG: u8 = 128;
+pub const N_SUBTRACTION_FLAG: u8 = 64;
+pub const H_HALF_CARRY_FLAG: u8 = 32;
+pub const C_CARRY_FLAG: u8 = 16;
+
+fn flags(zero: bool, subtraction: bool, half_carry: bool, carry: bool) -> u8 {
+    (zero as u8 * Z_ZERO_FLAG) | (subtraction as u8 * N_SUBTRACTION_FLAG) | (half_carry as u8 * H_HALF_CARRY_FLAG) | (carry as u8 * C_CARRY_FLAG)
+}
+
+pub fn add8(a: u8, value: u8) -> (u8, u8) {
+    adc8(a, value, false)
+}
+
+pub fn adc8(a: u8, value: u8, carry: bool) -> (u8, u8) {
+    let carry = carry as u8;
+    let result = a.wrapping_add(value).wrapping_add(carry);
+    let half_carry = (a & 0xF) + (value & 0xF) + carry > 0xF;
+    let carry = a as u16 + value as u16 + carry as u16 > 0xFF;
+    (result, flags(result == 0, false, half_carry, carry))
+}
+
+//Also CP, which keeps the flags and throws the result away
+pub fn sub8(a: u8, value: u8) -> (u8, u8) {
+    sbc8(a, value, false)
+}
+
+pub fn sbc8(a: u8, value: u8, carry: bool) -> (u8, u8) {
+    let carry = carry as u8;
+    let result = a.wrapping_sub(value).wrapping_sub(carry);
+    let half_carry = (a & 0xF) < (value & 0xF) + carry;
+    let carry = (a as u16) < value as u16 + carry as u16;
+    (result, flags(result == 0, true, half_carry, carry))
+}
+
+//Leaves C alone
+pub fn inc8(value: u8, old_flags: u8) -> (u8, u8) {
+    let result = value.wrapping_add(1);
+    (result, flags(result == 0, false, value & 0xF == 0xF, old_flags & C_CARRY_FLAG > 0))
+}
+
+//Leaves C alone
+pub fn dec8(value: u8, old_flags: u8) -> (u8, u8) {
+    let result = value.wrapping_sub(1);
+    (result, flags(result == 0, true, value & 0xF == 0, old_flags & C_CARRY_FLAG > 0))
+}
+
+//ADD HL, r16. Leaves Z alone, with the carries out of bits 11 and 15
+pub fn add16(hl: u16, value: u16, old_flags: u8) -> (u16, u8) {
+    let result = hl.wrapping_add(value);
+    let half_carry = (hl & 0xFFF) + (value & 0xFFF) > 0xFFF;
+    let carry = hl as u32 + value as u32 > 0xFFFF;
+    (result, flags(old_flags & Z_ZERO_FLAG > 0, false, half_carry, carry))
+}
+
+//ADD SP, e8 and LD HL, SP + e8. The offset is signed, but the carries come from adding it to the low byte unsigned
+pub fn add_sp_e8(stack_pointer: u16, offset: u8) -> (u16, u8) {
+    let result = stack_pointer.wrapping_add(offset as i8 as u16);
+    let half_carry = (stack_pointer & 0xF) + (offset as u16 & 0xF) > 0xF;
+    let carry = (stack_pointer & 0xFF) + offset as u16 > 0xFF;
+    (result, flags(false, false, half_carry, carry))
+}
+
+//Turns the result of a BCD addition or subtraction back into BCD, going by the flags it left. Leaves N alone
+pub fn daa(a: u8, old_flags: u8) -> (u8, u8) {
+    let is_subtraction = old_flags & N_SUBTRACTION_FLAG > 0;
+    let mut carry = old_flags & C_CARRY_FLAG > 0;
+    let mut correction = 0;
+    if old_flags & H_HALF_CARRY_FLAG > 0 || (!is_subtraction && a & 0xF > 0x9) {
+        correction |= 0x06;
+    }
+    if carry || (!is_subtraction && a > 0x99) {
+        correction |= 0x60;
+        carry = true;
+    }
+
+    let result = if is_subtraction {a.wrapping_sub(correction)} else {a.wrapping_add(correction)};
+    (result, flags(result == 0, is_subtraction, false, carry))
+}
@@ -1,10 +1,69 @@
-use std::{sync::mpsc::{channel, Receiver, Sender}, thread};
+use std::{io::{self, Read, Write}, time::{Duration, Instant}};
 
-use cpal::{traits::{DeviceTrait, HostTrait, StreamTrait}, FromSample, Sample, SizedSample};
+use super::{apu_log::{ApuEvent, ApuEventKind}, apu_output::{OutputStage, PlaybackSettings}, savestate::*};
 
-const T_CYCLE_RATE: f32 = 4194304.0;
 const M_CYCLE_RATE: f32 = 1048576.0;
 
+//Used until an output device shows up
+pub const FALLBACK_SAMPLE_RATE: f32 = 48000.0;
+
+//Sent by the audio thread whenever it switches output devices. No device name means there's nothing to play on
+#[derive(Clone)]
+pub struct AudioDeviceChange {
+    pub device_name: Option<String>,
+    pub sample_rate: f32,
+}
+
+//Ties a point on the console's output timeline to when the sample generated there comes out of the speakers
+#[derive(Clone, Copy)]
+pub struct AudioTimestamp {
+    pub cycle: u64,
+    pub played_at: Instant,
+}
+
+//Where the mixed samples go. A console with a sink that doesn't own an audio thread can run on any thread
+pub trait AudioSink: Send {
+    fn push(&mut self, left: f32, right: f32);
+    fn sample_rate(&self) -> f32;
+    //The output device the sink switched to since the last call, if any
+    fn take_device_change(&mut self) -> Option<AudioDeviceChange>;
+    //The next sample pushed was generated at this cycle of the output timeline
+    fn timestamp(&mut self, cycle: u64);
+    //The latest timestamped sample the device has played, if it reports them
+    fn last_played(&self) -> Option<AudioTimestamp>;
+    //How much audio is waiting to be played, for sinks that play in real time
+    fn queued(&self) -> Option<Duration>;
+    //Volume and filtering for sinks that play on a device
+    fn set_playback_settings(&mut self, settings: PlaybackSettings);
+}
+
+//Throws the samples away, for consoles running headless
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn push(&mut self, _left: f32, _right: f32) {}
+
+    fn sample_rate(&self) -> f32 {
+        FALLBACK_SAMPLE_RATE
+    }
+
+    fn take_device_change(&mut self) -> Option<AudioDeviceChange> {
+        None
+    }
+
+    fn timestamp(&mut self, _cycle: u64) {}
+
+    fn last_played(&self) -> Option<AudioTimestamp> {
+        None
+    }
+
+    fn queued(&self) -> Option<Duration> {
+        None
+    }
+
+    fn set_playback_settings(&mut self, _settings: PlaybackSettings) {}
+}
+
 pub struct APU {
     //Channel 1 registers
     ch_1_0_sweep: u8,           //NR10
@@ -98,23 +157,28 @@ pub struct APU {
     dac_4_signal: f32,
 
     //Sample cycle counter
-    gb_sample_rate: f32,
+    gb_sample_rate: f32, //M-cycles per output sample
     gb_sample_counter: f32,
+    speed: f32, //How many times faster than real time the console is being run
 
     //Variables for sending data to audio library
-    sender: Sender<f32>,
+    sink: Box<dyn AudioSink>,
+    output: OutputStage,
+    powered_dacs: [bool; 5], //NR52 and the four DACs as of the last sample
+    pub is_output_enabled: bool, //Disabled while running frames that will be rolled back
+    pub enabled_channels: [bool; 4], //Channels left out of the mix keep running, they just can't be heard
+
+    //Note events for exporting music, only recorded while logging
+    event_log: Option<Vec<ApuEvent>>,
+    event_cycle: u32,
+
+    //Copy of the mixed output for the visualizer, only recorded while it's open
+    sample_tap: Option<Vec<f32>>,
 }
 
 impl APU {
-    pub fn new() -> Self {
-        let (sender, receiver) = channel();
-        let (sample_send, sample_receive) = channel();
-
-        thread::spawn(move || {
-            Self::init_device(receiver, sample_send);
-        });
-
-        let sample_rate = sample_receive.recv().unwrap();
+    pub fn new(sink: Box<dyn AudioSink>) -> Self {
+        let sample_rate = sink.sample_rate();
 
         Self {
             ch_1_0_sweep: 0x80,
@@ -181,7 +245,15 @@ impl APU {
             dac_4_signal: 0.0,
             gb_sample_rate: (M_CYCLE_RATE / sample_rate),
             gb_sample_counter: 0.0,
-            sender
+            speed: 1.0,
+            sink,
+            output: OutputStage::new(sample_rate),
+            powered_dacs: [false; 5],
+            is_output_enabled: true,
+            enabled_channels: [true; 4],
+            event_log: None,
+            sample_tap: None,
+            event_cycle: 0,
         }
     }
 
@@ -267,6 +339,7 @@ impl APU {
                 0xFF13 => { //NR13
                     self.ch_1_3_period &= 0xFF00;
                     self.ch_1_3_period |= value as u16;
+                    self.log_pitch(1);
                     return;
                 },
                 0xFF14 => { //NR14
@@ -291,6 +364,12 @@ impl APU {
 
                     self.ch_1_4_length_enable = value & 0x40 != 0;
                     self.ch_1_3_period = (self.ch_1_3_period & 0x00FF) | ((value as u16 & 0b111) << 8);
+                    if value & 0x80 != 0 {
+                        self.log_note_on(1);
+                    }
+                    else {
+                        self.log_pitch(1);
+                    }
                     return;
                 },
 
@@ -306,6 +385,7 @@ impl APU {
                 0xFF18 => { //NR23
                     self.ch_2_3_period &= 0xFF00;
                     self.ch_2_3_period |= value as u16;
+                    self.log_pitch(2);
                     return;
                 },
                 0xFF19 => { //NR24
@@ -324,6 +404,12 @@ impl APU {
 
                     self.ch_2_4_length_enable = value & 0x40 != 0;
                     self.ch_2_3_period = (self.ch_2_3_period & 0x00FF) | ((value as u16 & 0b111) << 8);
+                    if value & 0x80 != 0 {
+                        self.log_note_on(2);
+                    }
+                    else {
+                        self.log_pitch(2);
+                    }
                     return;
                 },
 
@@ -339,6 +425,7 @@ impl APU {
                 0xFF1D => { //NR33
                     self.ch_3_3_period &= 0xFF00;
                     self.ch_3_3_period |= value as u16;
+                    self.log_pitch(3);
                     return;
                 }
                 0xFF1E => { //NR34
@@ -361,6 +448,12 @@ impl APU {
 
                     self.ch_3_4_length_enable = value & 0x40 != 0;
                     self.ch_3_3_period = (self.ch_3_3_period & 0x00FF) | ((value as u16 & 0b111) << 8);
+                    if value & 0x80 != 0 {
+                        self.log_note_on(3);
+                    }
+                    else {
+                        self.log_pitch(3);
+                    }
                     return;
                 },
 
@@ -387,6 +480,7 @@ impl APU {
                         self.ch_4_envelope_increases = self.ch_4_2_volume & 0b1000 != 0;
                         self.ch_4_envelope_pace = self.ch_4_2_volume & 0b111;
                         self.ch_4_lfsr = 0xEFFF;
+                        self.log_note_on(4);
                     }
 
                     self.ch_4_4_length_enable = value & 0x40 != 0;
@@ -423,7 +517,167 @@ impl APU {
         }
     }
 
+    pub fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        for register in [
+            self.ch_1_0_sweep,
+            self.ch_1_1_length,
+            self.ch_1_2_volume,
+            self.ch_2_1_length,
+            self.ch_2_2_volume,
+            self.ch_3_1_length,
+            self.ch_3_2_level,
+            self.ch_4_1_length,
+            self.ch_4_2_volume,
+            self.ch_4_3_randomness,
+            self.ch_5_0_volume,
+            self.ch_5_1_panning,
+            self.ch_1_duty_counter,
+            self.ch_1_length_counter,
+            self.ch_1_envelope_counter,
+            self.ch_1_envelope_pace,
+            self.ch_1_volume,
+            self.ch_1_sweep_pace,
+            self.ch_2_duty_counter,
+            self.ch_2_length_counter,
+            self.ch_2_envelope_counter,
+            self.ch_2_envelope_pace,
+            self.ch_2_volume,
+            self.ch_3_sample_index,
+            self.ch_3_length_counter,
+            self.ch_4_length_counter,
+            self.ch_4_envelope_counter,
+            self.ch_4_envelope_pace,
+            self.ch_4_volume,
+        ] {
+            write_u8(w, register)?;
+        }
+        for register in [
+            self.ch_1_3_period,
+            self.ch_2_3_period,
+            self.ch_3_3_period,
+            self.apu_counter,
+            self.ch_1_period_counter,
+            self.ch_1_sweep_period,
+            self.ch_2_period_counter,
+            self.ch_3_period_counter,
+            self.ch_4_lfsr,
+            self.ch_4_period_counter,
+        ] {
+            write_u16(w, register)?;
+        }
+        for flag in [
+            self.ch_1_4_length_enable,
+            self.ch_2_4_length_enable,
+            self.ch_3_4_length_enable,
+            self.ch_4_4_length_enable,
+            self.ch_5_2_enable,
+            self.ch_1_enable,
+            self.ch_2_enable,
+            self.ch_3_enable,
+            self.ch_4_enable,
+            self.dac_1_enable,
+            self.dac_2_enable,
+            self.dac_3_enable,
+            self.dac_4_enable,
+            self.ch_1_envelope_increases,
+            self.ch_1_sweep_enabled,
+            self.ch_2_envelope_increases,
+            self.ch_4_envelope_increases,
+        ] {
+            write_bool(w, flag)?;
+        }
+        for signal in [self.ch_3_volume, self.dac_1_signal, self.dac_2_signal, self.dac_3_signal, self.dac_4_signal, self.gb_sample_counter] {
+            write_f32(w, signal)?;
+        }
+        w.write_all(&self.wave_ram)
+    }
+
+    pub fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        for register in [
+            &mut self.ch_1_0_sweep,
+            &mut self.ch_1_1_length,
+            &mut self.ch_1_2_volume,
+            &mut self.ch_2_1_length,
+            &mut self.ch_2_2_volume,
+            &mut self.ch_3_1_length,
+            &mut self.ch_3_2_level,
+            &mut self.ch_4_1_length,
+            &mut self.ch_4_2_volume,
+            &mut self.ch_4_3_randomness,
+            &mut self.ch_5_0_volume,
+            &mut self.ch_5_1_panning,
+            &mut self.ch_1_duty_counter,
+            &mut self.ch_1_length_counter,
+            &mut self.ch_1_envelope_counter,
+            &mut self.ch_1_envelope_pace,
+            &mut self.ch_1_volume,
+            &mut self.ch_1_sweep_pace,
+            &mut self.ch_2_duty_counter,
+            &mut self.ch_2_length_counter,
+            &mut self.ch_2_envelope_counter,
+            &mut self.ch_2_envelope_pace,
+            &mut self.ch_2_volume,
+            &mut self.ch_3_sample_index,
+            &mut self.ch_3_length_counter,
+            &mut self.ch_4_length_counter,
+            &mut self.ch_4_envelope_counter,
+            &mut self.ch_4_envelope_pace,
+            &mut self.ch_4_volume,
+        ] {
+            *register = read_u8(r)?;
+        }
+        for register in [
+            &mut self.ch_1_3_period,
+            &mut self.ch_2_3_period,
+            &mut self.ch_3_3_period,
+            &mut self.apu_counter,
+            &mut self.ch_1_period_counter,
+            &mut self.ch_1_sweep_period,
+            &mut self.ch_2_period_counter,
+            &mut self.ch_3_period_counter,
+            &mut self.ch_4_lfsr,
+            &mut self.ch_4_period_counter,
+        ] {
+            *register = read_u16(r)?;
+        }
+        for flag in [
+            &mut self.ch_1_4_length_enable,
+            &mut self.ch_2_4_length_enable,
+            &mut self.ch_3_4_length_enable,
+            &mut self.ch_4_4_length_enable,
+            &mut self.ch_5_2_enable,
+            &mut self.ch_1_enable,
+            &mut self.ch_2_enable,
+            &mut self.ch_3_enable,
+            &mut self.ch_4_enable,
+            &mut self.dac_1_enable,
+            &mut self.dac_2_enable,
+            &mut self.dac_3_enable,
+            &mut self.dac_4_enable,
+            &mut self.ch_1_envelope_increases,
+            &mut self.ch_1_sweep_enabled,
+            &mut self.ch_2_envelope_increases,
+            &mut self.ch_4_envelope_increases,
+        ] {
+            *flag = read_bool(r)?;
+        }
+        for signal in [
+            &mut self.ch_3_volume,
+            &mut self.dac_1_signal,
+            &mut self.dac_2_signal,
+            &mut self.dac_3_signal,
+            &mut self.dac_4_signal,
+            &mut self.gb_sample_counter,
+        ] {
+            *signal = read_f32(r)?;
+        }
+        r.read_exact(&mut self.wave_ram)
+    }
+
     fn disable_ch_1(&mut self) {
+        if self.ch_1_enable {
+            self.log_event(1, ApuEventKind::NoteOff);
+        }
         self.ch_1_enable = false;
         self.ch_1_envelope_counter = 0;
         self.ch_1_length_counter = 0;
@@ -435,6 +689,9 @@ impl APU {
     }
 
     fn disable_ch_2(&mut self) {
+        if self.ch_2_enable {
+            self.log_event(2, ApuEventKind::NoteOff);
+        }
         self.ch_2_enable = false;
         self.ch_2_envelope_counter = 0;
         self.ch_2_length_counter = 0;
@@ -443,6 +700,9 @@ impl APU {
     }
 
     fn disable_ch_3(&mut self) {
+        if self.ch_3_enable {
+            self.log_event(3, ApuEventKind::NoteOff);
+        }
         self.ch_3_enable = false;
         self.ch_3_length_counter = 0;
         self.ch_3_period_counter = 0;
@@ -451,6 +711,9 @@ impl APU {
     }
 
     fn disable_ch_4(&mut self) {
+        if self.ch_4_enable {
+            self.log_event(4, ApuEventKind::NoteOff);
+        }
         self.ch_4_enable = false;
         self.ch_4_length_counter = 0;
         self.ch_4_envelope_counter = 0;
@@ -458,100 +721,135 @@ impl APU {
         self.ch_4_volume = 0;
     }
 
-    fn get_ch_4_divisor(&self) -> u16 {
-        let divisor_code = self.ch_4_3_randomness & 0b111;
-        let divisor = if divisor_code == 0 {8} else {16 * divisor_code} as u16;
-        (divisor << (self.ch_4_3_randomness >> 4)) >> 2
+    pub fn set_event_logging(&mut self, is_enabled: bool) {
+        self.event_log = if is_enabled {Some(Vec::new())} else {None};
+        self.event_cycle = 0;
     }
-    
-    pub fn init_device(receiver: Receiver<f32>, sample_send: Sender<f32>) {
-        let host = cpal::default_host();
-        let device = host.default_output_device().expect("ERROR: failed to find output device");
-        let config = device.default_output_config().unwrap();
-
-        match config.sample_format() {
-            cpal::SampleFormat::I8 => Self::run::<i8>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::I16 => Self::run::<i16>(receiver, sample_send, &device, &config.into()),
-            //cpal::SampleFormat::I24 => Self::run::<I24>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::I32 => Self::run::<i32>(receiver, sample_send, &device, &config.into()),
-            //cpal::SampleFormat::I48 => Self::run::<I48>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::I64 => Self::run::<i64>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::U8 => Self::run::<u8>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::U16 => Self::run::<u16>(receiver, sample_send, &device, &config.into()),
-            //cpal::SampleFormat::U24 => Self::run::<U24>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::U32 => Self::run::<u32>(receiver, sample_send, &device, &config.into()),
-            //cpal::SampleFormat::U48 => Self::run::<U48>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::U64 => Self::run::<u64>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::F32 => Self::run::<f32>(receiver, sample_send, &device, &config.into()),
-            cpal::SampleFormat::F64 => Self::run::<f64>(receiver, sample_send, &device, &config.into()),
-            sample_format => panic!("Unsupported sample format '{sample_format}'"),
-        }
-    }
-
-    fn run<T>(receiver: Receiver<f32>, sample_send: Sender<f32>, device: &cpal::Device, config: &cpal::StreamConfig)
-    where 
-        T: SizedSample + FromSample<f32>,
-    {
-        let sample_rate = config.sample_rate.0 as f32;
-        let channels = config.channels as usize;
-        sample_send.send(sample_rate).unwrap();
-
-        let mut left_capacitor = 0.0;
-        let mut right_capacitor = 0.0;
-        let charge_factor = 0.999958_f32.powf(T_CYCLE_RATE / sample_rate);
-        let mut is_left_channel = false;
-        let mut high_pass_filter = move |input: f32, enabled: bool| -> f32 {
-            let capacitor = if is_left_channel {&mut left_capacitor} else {&mut right_capacitor};
-
-            let mut output = 0.0;
-            if enabled {
-                output = input - *capacitor;
-                *capacitor = input - output * charge_factor;
-            }
 
-            output
-        };
+    //Returns the events since the last call and how many m-cycles that covered
+    pub fn take_events(&mut self) -> (Vec<ApuEvent>, u32) {
+        let elapsed_cycles = self.event_cycle;
+        self.event_cycle = 0;
+        match &mut self.event_log {
+            Some(event_log) => (std::mem::take(event_log), elapsed_cycles),
+            None => (Vec::new(), elapsed_cycles)
+        }
+    }
+
+    pub fn set_sample_tap(&mut self, is_enabled: bool) {
+        self.sample_tap = if is_enabled {Some(Vec::new())} else {None};
+    }
 
-        let mut next_value = move || {
-            let sample = receiver.recv().unwrap();
-            //println!("{sample}");
+    //Returns the mixed mono samples since the last call
+    pub fn take_samples(&mut self) -> Vec<f32> {
+        match &mut self.sample_tap {
+            Some(sample_tap) => std::mem::take(sample_tap),
+            None => Vec::new()
+        }
+    }
+
+    //Returns the output device the audio thread switched to since the last call, if any
+    pub fn take_device_change(&mut self) -> Option<AudioDeviceChange> {
+        let change = self.sink.take_device_change()?;
+        self.gb_sample_rate = M_CYCLE_RATE * self.speed / change.sample_rate;
+        self.output.set_sample_rate(change.sample_rate);
+        Some(change)
+    }
 
-            is_left_channel = ! is_left_channel;
-            
-            high_pass_filter(sample, true)
+    //Spreads the samples out over more cycles when running faster than real time, so the device still gets them at
+    //its own rate instead of falling further behind every frame. The sound plays back sped up
+    pub fn set_speed(&mut self, speed: f32) {
+        let sample_rate = self.output_sample_rate();
+        self.speed = speed;
+        self.gb_sample_rate = M_CYCLE_RATE * speed / sample_rate;
+    }
+
+    pub fn timestamp(&mut self, cycle: u64) {
+        if self.is_output_enabled {
+            self.sink.timestamp(cycle);
+        }
+    }
+
+    pub fn last_played(&self) -> Option<AudioTimestamp> {
+        self.sink.last_played()
+    }
+
+    pub fn queued(&self) -> Option<Duration> {
+        self.sink.queued().filter(|_| self.is_output_enabled)
+    }
+
+    //Plays a short fade to silence, for when the output is about to stop
+    pub fn fade_out(&mut self) {
+        if !self.is_output_enabled {
+            return;
+        }
+        for (left, right) in self.output.fade_out() {
+            self.sink.push(left, right);
+        }
+    }
+
+    //Smooths over the output jumping somewhere else, like after loading a savestate
+    pub fn smooth_jump(&mut self) {
+        self.output.smooth_jump();
+    }
+
+    pub fn set_volume_normalization(&mut self, is_enabled: bool) {
+        self.output.set_normalization(is_enabled, self.output_sample_rate());
+    }
+
+    pub fn set_playback_settings(&mut self, settings: PlaybackSettings) {
+        self.sink.set_playback_settings(settings);
+    }
+
+    pub fn output_sample_rate(&self) -> f32 {
+        M_CYCLE_RATE * self.speed / self.gb_sample_rate
+    }
+
+    fn log_event(&mut self, channel: u8, kind: ApuEventKind) {
+        if let Some(event_log) = &mut self.event_log {
+            event_log.push(ApuEvent { cycle: self.event_cycle, channel, kind });
+        }
+    }
+
+    fn log_note_on(&mut self, channel: u8) {
+        let (volume, duty) = match channel {
+            1 => (self.ch_1_2_volume >> 4, Some(self.ch_1_1_length >> 6)),
+            2 => (self.ch_2_2_volume >> 4, Some(self.ch_2_1_length >> 6)),
+            3 => ((self.ch_3_2_level >> 5) & 0b11, None),
+            _ => (self.ch_4_2_volume >> 4, None),
         };
+        self.log_event(channel, ApuEventKind::NoteOn { frequency: self.channel_frequency(channel), volume, duty });
+    }
 
-        let err_fn = |err| eprintln!("An error occurred on stream: {}", err);
-
-        let stream = device.build_output_stream(
-            config,
-            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-                Self::write_data(data, channels, &mut next_value)
-            },
-            err_fn,
-            None,
-        ).unwrap();
-        stream.play().unwrap();
-
-        loop {}
-    }
-
-    fn write_data<T>(output: &mut [T], channels: usize, next_sample: &mut dyn FnMut() -> f32)
-    where
-        T: Sample + FromSample<f32>,
-    {
-        for frame in output.chunks_mut(channels) {
-            
-            for sample in frame.iter_mut() {
-                let value: T = T::from_sample(next_sample());
-                *sample = value;
-            }
+    fn log_pitch(&mut self, channel: u8) {
+        let is_enabled = match channel {
+            1 => self.ch_1_enable,
+            2 => self.ch_2_enable,
+            _ => self.ch_3_enable,
+        };
+        if is_enabled {
+            self.log_event(channel, ApuEventKind::Pitch { frequency: self.channel_frequency(channel) });
+        }
+    }
+
+    fn channel_frequency(&self, channel: u8) -> f32 {
+        match channel {
+            1 => 131072.0 / (2048 - self.ch_1_3_period) as f32,
+            2 => 131072.0 / (2048 - self.ch_2_3_period) as f32,
+            3 => 65536.0 / (2048 - self.ch_3_3_period) as f32,
+            _ => M_CYCLE_RATE / self.get_ch_4_divisor() as f32,
         }
     }
 
+    fn get_ch_4_divisor(&self) -> u16 {
+        let divisor_code = self.ch_4_3_randomness & 0b111;
+        let divisor = if divisor_code == 0 {8} else {16 * divisor_code} as u16;
+        (divisor << (self.ch_4_3_randomness >> 4)) >> 2
+    }
+    
     pub fn update_apu_timer(&mut self) {
         let apu_counter_before = self.apu_counter;
-        self.apu_counter += 1;
+        self.apu_counter = self.apu_counter.wrapping_add(1);
 
         let will_update_envelope;
         {
@@ -561,7 +859,7 @@ impl APU {
         }
         if will_update_envelope {
             if self.ch_1_envelope_pace != 0 {
-                self.ch_1_envelope_counter += 1;
+                self.ch_1_envelope_counter = self.ch_1_envelope_counter.wrapping_add(1);
                 if self.ch_1_envelope_counter == self.ch_1_envelope_pace {
                     if self.ch_1_envelope_increases && self.ch_1_volume < 0xF {
                         self.ch_1_volume += 1;
@@ -575,7 +873,7 @@ impl APU {
             }
 
             if self.ch_2_envelope_pace != 0 {
-                self.ch_2_envelope_counter += 1;
+                self.ch_2_envelope_counter = self.ch_2_envelope_counter.wrapping_add(1);
                 if self.ch_2_envelope_counter == self.ch_2_envelope_pace {
                     if self.ch_2_envelope_increases && self.ch_2_volume < 0xF {
                         self.ch_2_volume += 1;
@@ -589,7 +887,7 @@ impl APU {
             }
 
             if self.ch_4_envelope_pace != 0 {
-                self.ch_4_envelope_counter += 1;
+                self.ch_4_envelope_counter = self.ch_4_envelope_counter.wrapping_add(1);
                 if self.ch_4_envelope_counter == self.ch_4_envelope_pace {
                     if self.ch_4_envelope_increases && self.ch_4_volume < 0xF {
                         self.ch_4_volume += 1;
@@ -625,7 +923,7 @@ impl APU {
             }
 
             if self.ch_3_4_length_enable && self.ch_3_enable {
-                self.ch_3_length_counter += 1;
+                self.ch_3_length_counter = self.ch_3_length_counter.wrapping_add(1);
                 if self.ch_3_length_counter == 0 {
                     self.disable_ch_3();
                 }
@@ -691,6 +989,10 @@ impl APU {
     }
 
     pub fn update_apu(&mut self) {
+        if self.event_log.is_some() {
+            self.event_cycle = self.event_cycle.wrapping_add(1);
+        }
+
         if self.ch_5_2_enable {
             if self.dac_1_enable {
                 if self.ch_1_enable {
@@ -708,7 +1010,7 @@ impl APU {
                         self.dac_1_signal = DUTY_VALUES[duty_cycle][duty_step];
 
                         //Clock the duty step counter
-                        self.ch_1_duty_counter += 1;
+                        self.ch_1_duty_counter = self.ch_1_duty_counter.wrapping_add(1);
                         //println!("f: {frequency}, d: {duty_cycle}, v: {}", self.sample_data.ch_2_amp);
                     }
                     else {
@@ -737,7 +1039,7 @@ impl APU {
                         self.dac_2_signal = DUTY_VALUES[duty_cycle][duty_step];
 
                         //Clock the duty step counter
-                        self.ch_2_duty_counter += 1;
+                        self.ch_2_duty_counter = self.ch_2_duty_counter.wrapping_add(1);
                         //println!("f: {frequency}, d: {duty_cycle}, v: {}", self.sample_data.ch_2_amp);
                     }
                     else {
@@ -770,7 +1072,7 @@ impl APU {
                             self.dac_3_signal = digital_to_analog(sample);
 
                             //Clock the sample index
-                            self.ch_3_sample_index += 1;
+                            self.ch_3_sample_index = self.ch_3_sample_index.wrapping_add(1);
                         }
                         else {
                             self.ch_3_period_counter += 1;
@@ -808,59 +1110,80 @@ impl APU {
 
         self.gb_sample_counter += 1.0;
         if self.gb_sample_counter >= self.gb_sample_rate {
-            //if the APU is disabled, only play silence 
-            if !self.ch_5_2_enable {
-                self.sender.send(0.0).unwrap();
-                self.sender.send(0.0).unwrap();
+            self.gb_sample_counter -= self.gb_sample_rate;
+            if !self.is_output_enabled {
                 return;
             }
 
-            let mut left_sample = 0.0;
-            let mut right_sample = 0.0;
+            //Turning the APU or a DAC on or off jumps the output
+            let powered_dacs = [self.ch_5_2_enable, self.dac_1_enable, self.dac_2_enable, self.dac_3_enable, self.dac_4_enable];
+            if powered_dacs != self.powered_dacs {
+                self.powered_dacs = powered_dacs;
+                self.output.smooth_jump();
+            }
 
-            const CH_3_REDUCTION: f32 = 0.25;
+            //if the APU is disabled, only play silence 
+            let (left_sample, right_sample) = if self.ch_5_2_enable {self.mix()} else {(0.0, 0.0)};
+            let (left_sample, right_sample) = self.output.process(left_sample, right_sample);
 
-            //Mixing and Panning
-            if self.ch_5_1_panning & 0b1 != 0 {
-                right_sample += self.dac_1_signal * volume_to_analog(self.ch_1_volume);
-            }
-            if self.ch_5_1_panning & 0b10 != 0 {
-                right_sample += self.dac_2_signal * volume_to_analog(self.ch_2_volume);
-            }
-            if self.ch_5_1_panning & 0b100 != 0 {
-                right_sample += self.dac_3_signal * self.ch_3_volume * CH_3_REDUCTION;
-            }
-            if self.ch_5_1_panning & 0b1000 != 0 {
-                right_sample += self.dac_4_signal * volume_to_analog(self.ch_4_volume);
-            }
-            if self.ch_5_1_panning & 0b10000 != 0 {
-                left_sample += self.dac_1_signal * volume_to_analog(self.ch_1_volume);
-            }
-            if self.ch_5_1_panning & 0b100000 != 0 {
-                left_sample += self.dac_2_signal * volume_to_analog(self.ch_2_volume);
-            }
-            if self.ch_5_1_panning & 0b1000000 != 0 {
-                left_sample += self.dac_3_signal * self.ch_3_volume * CH_3_REDUCTION;
-            }
-            if self.ch_5_1_panning & 0b10000000 != 0 {
-                left_sample += self.dac_4_signal * volume_to_analog(self.ch_4_volume);
+            self.sink.push(left_sample, right_sample);
+            if let Some(sample_tap) = &mut self.sample_tap {
+                sample_tap.push((left_sample + right_sample) / 2.0);
             }
+        }
+    }
 
-            //Brings the mixed signal back into the range of -1.0 to +1.0
-            left_sample /= 4.0;
-            right_sample /= 4.0;
+    fn mix(&self) -> (f32, f32) {
+        let mut left_sample = 0.0;
+        let mut right_sample = 0.0;
 
-            //Applies the master volume to left and right channels
-            let left_volume = ((self.ch_5_0_volume & 0x70) >> 3) + 1;
-            let right_volume = ((self.ch_5_0_volume & 0x7) << 1) + 1;
-            left_sample *= volume_to_analog(left_volume);
-            right_sample *= volume_to_analog(right_volume);
+        const CH_3_REDUCTION: f32 = 0.25;
 
-            self.sender.send(left_sample).unwrap();
-            self.sender.send(right_sample).unwrap();
+        //Muted channels are taken out of the panning so the mixer skips them
+        let mut panning = self.ch_5_1_panning;
+        for (channel, is_enabled) in self.enabled_channels.iter().enumerate() {
+            if !is_enabled {
+                panning &= !(0b10001 << channel);
+            }
+        }
 
-            self.gb_sample_counter -= self.gb_sample_rate;
+        //Mixing and Panning
+        if panning & 0b1 != 0 {
+            right_sample += self.dac_1_signal * volume_to_analog(self.ch_1_volume);
+        }
+        if panning & 0b10 != 0 {
+            right_sample += self.dac_2_signal * volume_to_analog(self.ch_2_volume);
+        }
+        if panning & 0b100 != 0 {
+            right_sample += self.dac_3_signal * self.ch_3_volume * CH_3_REDUCTION;
         }
+        if panning & 0b1000 != 0 {
+            right_sample += self.dac_4_signal * volume_to_analog(self.ch_4_volume);
+        }
+        if panning & 0b10000 != 0 {
+            left_sample += self.dac_1_signal * volume_to_analog(self.ch_1_volume);
+        }
+        if panning & 0b100000 != 0 {
+            left_sample += self.dac_2_signal * volume_to_analog(self.ch_2_volume);
+        }
+        if panning & 0b1000000 != 0 {
+            left_sample += self.dac_3_signal * self.ch_3_volume * CH_3_REDUCTION;
+        }
+        if panning & 0b10000000 != 0 {
+            left_sample += self.dac_4_signal * volume_to_analog(self.ch_4_volume);
+        }
+
+        //Brings the mixed signal back into the range of -1.0 to +1.0
+        left_sample /= 4.0;
+        right_sample /= 4.0;
+
+        //Applies the master volume to left and right channels
+        let left_volume = ((self.ch_5_0_volume & 0x70) >> 3) + 1;
+        let right_volume = ((self.ch_5_0_volume & 0x7) << 1) + 1;
+        left_sample *= volume_to_analog(left_volume);
+        right_sample *= volume_to_analog(right_volume);
+
+        (left_sample, right_sample)
     }
 }
 
@@ -0,0 +1,84 @@
+use std::{fs::File, io::{self, BufWriter, Write}};
+
+const M_CYCLE_RATE: f64 = 1048576.0;
+
+#[derive(Clone, Copy)]
+pub enum ApuEventKind {
+    NoteOn { frequency: f32, volume: u8, duty: Option<u8> },
+    Pitch { frequency: f32 }, //The period was rewritten while the channel was playing
+    NoteOff,
+}
+
+#[derive(Clone, Copy)]
+pub struct ApuEvent {
+    pub cycle: u32, //M-cycles since the events were last taken from the APU
+    pub channel: u8,
+    pub kind: ApuEventKind,
+}
+
+//Writes APU events as a tab separated note list that can be turned into MIDI or tracker data
+pub struct ApuEventLogger {
+    writer: BufWriter<File>,
+    cycle: u64,
+}
+
+impl ApuEventLogger {
+    pub fn new(log_path: &str) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(log_path)?);
+        writeln!(writer, "#cycle\tseconds\tchannel\tevent\tnote\tfrequency\tvolume\tduty")?;
+
+        Ok(Self {
+            writer,
+            cycle: 0,
+        })
+    }
+
+    pub fn write_events(&mut self, events: &[ApuEvent], elapsed_cycles: u32) -> io::Result<()> {
+        for event in events {
+            let cycle = self.cycle + event.cycle as u64;
+            let seconds = cycle as f64 / M_CYCLE_RATE;
+            write!(self.writer, "{}\t{:.6}\t{}\t", cycle, seconds, event.channel)?;
+
+            match event.kind {
+                ApuEventKind::NoteOn { frequency, volume, duty } => {
+                    let duty = duty.map(|duty| duty.to_string()).unwrap_or(String::from("-"));
+                    writeln!(self.writer, "note_on\t{}\t{:.2}\t{}\t{}", Self::note_name(event.channel, frequency), frequency, volume, duty)?;
+                }
+                ApuEventKind::Pitch { frequency } => {
+                    writeln!(self.writer, "pitch\t{}\t{:.2}\t-\t-", Self::note_name(event.channel, frequency), frequency)?;
+                }
+                ApuEventKind::NoteOff => {
+                    writeln!(self.writer, "note_off\t-\t-\t-\t-")?;
+                }
+            }
+        }
+
+        self.cycle += elapsed_cycles as u64;
+        Ok(())
+    }
+
+    //Nearest MIDI note number, or "-" for the noise channel which has no pitch
+    fn note_name(channel: u8, frequency: f32) -> String {
+        if channel == 4 || frequency <= 0.0 {
+            return String::from("-");
+        }
+
+        let note = 69.0 + 12.0 * (frequency / 440.0).log2();
+        format!("{}", note.round() as i32)
+    }
+}
+
+impl Drop for ApuEventLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+pub fn rom_to_apu_log(rom_file_path: String) -> String {
+    if let Some(apu_log_path) = rom_file_path.rsplitn(2, ".").last() {
+        apu_log_path.to_owned() + ".apulog"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
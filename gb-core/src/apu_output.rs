@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+//How long jumps in the output are smoothed over
+const RAMP_SECONDS: f32 = 0.01;
+
+//Loudness the normalizer boosts quiet games towards, as RMS of the mixed output
+const TARGET_RMS: f32 = 0.12;
+const MAX_GAIN: f32 = 4.0;
+//Below this the game is treated as silent and the gain is left alone, so it doesn't blast the next sound
+const SILENCE_RMS: f32 = 0.005;
+const NORMALIZER_SECONDS: f32 = 1.0;
+
+//Applied by the audio thread to what's played on the device. Captures and the visualizer get the mix without them
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackSettings {
+    pub volume: f32, //From 0.0 to 1.0
+    pub is_high_pass_enabled: bool, //Removes the DC offset like the capacitor on real hardware's output
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            is_high_pass_enabled: true,
+        }
+    }
+}
+
+//Sits between the mixer and the sink. When the output is stopped, started or jumps somewhere else, the difference
+//from the last sample played is faded away instead of reaching the high-pass filter as a step, which pops
+pub struct OutputStage {
+    ramp_length: usize,
+    last: (f32, f32),
+    offset: (f32, f32),
+    ramp_position: usize,
+    is_jump_pending: bool,
+    normalizer: Option<Normalizer>,
+}
+
+impl OutputStage {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            ramp_length: (sample_rate * RAMP_SECONDS) as usize,
+            last: (0.0, 0.0),
+            offset: (0.0, 0.0),
+            ramp_position: 0,
+            is_jump_pending: true, //Fade in from silence
+            normalizer: None,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.ramp_length = (sample_rate * RAMP_SECONDS) as usize;
+        if let Some(normalizer) = &mut self.normalizer {
+            *normalizer = Normalizer::new(sample_rate);
+        }
+    }
+
+    pub fn set_normalization(&mut self, is_enabled: bool, sample_rate: f32) {
+        if is_enabled != self.normalizer.is_some() {
+            self.normalizer = if is_enabled {Some(Normalizer::new(sample_rate))} else {None};
+            self.smooth_jump();
+        }
+    }
+
+    //The next sample starts wherever the last one left off
+    pub fn smooth_jump(&mut self) {
+        self.is_jump_pending = true;
+    }
+
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let (mut left, mut right) = match &mut self.normalizer {
+            Some(normalizer) => normalizer.process(left, right),
+            None => (left, right),
+        };
+
+        if self.is_jump_pending {
+            self.is_jump_pending = false;
+            self.offset = (self.last.0 - left, self.last.1 - right);
+            self.ramp_position = 0;
+        }
+        if self.ramp_position < self.ramp_length {
+            let remaining = 1.0 - self.ramp_position as f32 / self.ramp_length as f32;
+            left += self.offset.0 * remaining;
+            right += self.offset.1 * remaining;
+            self.ramp_position += 1;
+        }
+
+        self.last = (left, right);
+        (left, right)
+    }
+
+    //Samples taking the output from the last one played down to silence. The next sample fades in from there
+    pub fn fade_out(&mut self) -> Vec<(f32, f32)> {
+        let (left, right) = self.last;
+        let samples = (0..self.ramp_length)
+            .map(|i| 1.0 - (i + 1) as f32 / self.ramp_length as f32)
+            .map(|remaining| (left * remaining, right * remaining))
+            .collect();
+        self.last = (0.0, 0.0);
+        self.is_jump_pending = true;
+        samples
+    }
+}
+
+//Slowly boosts quiet games towards TARGET_RMS. Only the signal around its average is scaled so the DC offset the
+//high-pass filter removes doesn't grow with it
+struct Normalizer {
+    smoothing: f32,
+    mean: f32,
+    power: f32,
+    gain: f32,
+}
+
+impl Normalizer {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            smoothing: 1.0 / (sample_rate * NORMALIZER_SECONDS),
+            mean: 0.0,
+            power: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let mono = (left + right) / 2.0;
+        self.mean += (mono - self.mean) * self.smoothing;
+        self.power += ((mono - self.mean).powi(2) - self.power) * self.smoothing;
+
+        let rms = self.power.sqrt();
+        if rms > SILENCE_RMS {
+            let target_gain = (TARGET_RMS / rms).clamp(1.0, MAX_GAIN);
+            self.gain += (target_gain - self.gain) * self.smoothing;
+        }
+
+        let scale = |sample: f32| (self.mean + (sample - self.mean) * self.gain).clamp(-1.0, 1.0);
+        (scale(left), scale(right))
+    }
+}
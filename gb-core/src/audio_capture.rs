@@ -0,0 +1,120 @@
+use std::{fs::File, io::{self, BufWriter, Seek, SeekFrom, Write}, path::Path, sync::{Arc, Mutex}, time::Duration};
+
+use super::{apu::{AudioDeviceChange, AudioSink, AudioTimestamp}, apu_output::PlaybackSettings};
+
+//Keeps every sample the APU mixes instead of playing it, so a headless console can render its audio as fast as it
+//runs. Samples are made on a fixed count of M-cycles, so the same inputs always give the same track, lined up with
+//the frames it was made alongside. Rates that divide 1048576, like 32768 or 65536, make that count exact
+pub struct CaptureSink {
+    samples: Arc<Mutex<Vec<[f32; 2]>>>,
+    sample_rate: f32,
+}
+
+//The other end of a CaptureSink, kept by whoever runs the console
+#[derive(Clone)]
+pub struct AudioCapture {
+    samples: Arc<Mutex<Vec<[f32; 2]>>>,
+    sample_rate: f32,
+}
+
+impl CaptureSink {
+    pub fn new(sample_rate: f32) -> (Self, AudioCapture) {
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let capture = AudioCapture { samples: samples.clone(), sample_rate };
+        (Self { samples, sample_rate }, capture)
+    }
+}
+
+impl AudioSink for CaptureSink {
+    fn push(&mut self, left: f32, right: f32) {
+        self.samples.lock().unwrap().push([left, right]);
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn take_device_change(&mut self) -> Option<AudioDeviceChange> {
+        None
+    }
+
+    fn timestamp(&mut self, _cycle: u64) {}
+
+    fn last_played(&self) -> Option<AudioTimestamp> {
+        None
+    }
+
+    fn queued(&self) -> Option<Duration> {
+        None
+    }
+
+    //Captures keep the mix as it is so they can be mastered afterwards
+    fn set_playback_settings(&mut self, _settings: PlaybackSettings) {}
+}
+
+impl AudioCapture {
+    //Everything mixed since the last call, as left and right pairs
+    pub fn take(&self) -> Vec<[f32; 2]> {
+        std::mem::take(&mut *self.samples.lock().unwrap())
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+}
+
+//The RIFF size, 36 bytes more than the data, has to fit in 32 bits too. Kept to whole sample frames
+const MAX_DATA_SIZE: u32 = (u32::MAX - 36) / 4 * 4;
+
+//Writes 16 bit stereo PCM. The sizes in the header are filled in by finish, so the length doesn't need to be known
+//up front. A WAV file can't hold more than about 4 GiB, so samples past that are dropped
+pub struct WavWriter {
+    writer: BufWriter<File>,
+    data_size: u32,
+    is_full: bool,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, sample_rate: u32) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(b"RIFF\0\0\0\0WAVEfmt ")?;
+        writer.write_all(&16_u32.to_le_bytes())?;
+        writer.write_all(&1_u16.to_le_bytes())?; //PCM
+        writer.write_all(&2_u16.to_le_bytes())?; //Channels
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * 4).to_le_bytes())?; //Bytes per second
+        writer.write_all(&4_u16.to_le_bytes())?; //Bytes per sample frame
+        writer.write_all(&16_u16.to_le_bytes())?; //Bits per sample
+        writer.write_all(b"data\0\0\0\0")?;
+
+        Ok(Self {
+            writer,
+            data_size: 0,
+            is_full: false,
+        })
+    }
+
+    pub fn write(&mut self, samples: &[[f32; 2]]) -> io::Result<()> {
+        let room = ((MAX_DATA_SIZE - self.data_size) / 4) as usize;
+        if samples.len() > room && !self.is_full {
+            println!("ERROR: The WAV file reached its 4 GiB limit, the rest of the capture isn't written");
+            self.is_full = true;
+        }
+
+        let samples = &samples[..samples.len().min(room)];
+        for sample in samples.iter().flatten() {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_size += samples.len() as u32 * 4;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&(36 + self.data_size).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&self.data_size.to_le_bytes())?;
+        self.writer.flush()
+    }
+}
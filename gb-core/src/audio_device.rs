@@ -0,0 +1,321 @@
+use std::{sync::{atomic::{AtomicU64, Ordering}, mpsc::{channel, Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError}, Arc, Mutex}, thread, time::{Duration, Instant}};
+
+use cpal::{traits::{DeviceTrait, HostTrait, StreamTrait}, FromSample, Sample, SizedSample};
+
+use super::{apu::{AudioDeviceChange, AudioSink, AudioTimestamp, FALLBACK_SAMPLE_RATE}, apu_output::PlaybackSettings};
+
+const T_CYCLE_RATE: f32 = 4194304.0;
+
+//How often the audio thread checks whether the default output device changed
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+//Plays on the default output device from its own audio thread
+pub struct DeviceSink {
+    sender: Sender<f32>,
+    timestamp_sender: Sender<(u64, u64)>,
+    settings_sender: Sender<PlaybackSettings>,
+    settings: PlaybackSettings, //The last ones sent, so they're only sent when they change
+    samples_sent: u64,
+    samples_taken: Arc<AtomicU64>, //By the device, or thrown away while there isn't one
+    last_played: Arc<Mutex<Option<AudioTimestamp>>>,
+    device_change_receiver: Receiver<AudioDeviceChange>,
+    sample_rate: f32,
+    has_device: bool,
+}
+
+impl DeviceSink {
+    pub fn open() -> Self {
+        let (sender, receiver) = channel();
+        let (timestamp_sender, timestamp_receiver) = channel();
+        let (settings_sender, settings_receiver) = channel();
+        let (device_change_send, device_change_receiver) = channel();
+        let last_played = Arc::new(Mutex::new(None));
+        let samples_taken = Arc::new(AtomicU64::new(0));
+
+        let playback = Playback {
+            samples: receiver,
+            timestamps: timestamp_receiver,
+            next_timestamp: None,
+            settings_receiver,
+            settings: PlaybackSettings::default(),
+            position: samples_taken.clone(),
+            last_played: last_played.clone(),
+        };
+        thread::spawn(move || {
+            run_output(playback, device_change_send);
+        });
+
+        let first_device = device_change_receiver.recv().unwrap();
+        Self {
+            sender,
+            timestamp_sender,
+            settings_sender,
+            settings: PlaybackSettings::default(),
+            samples_sent: 0,
+            samples_taken,
+            last_played,
+            device_change_receiver,
+            sample_rate: first_device.sample_rate,
+            has_device: first_device.device_name.is_some(),
+        }
+    }
+}
+
+impl AudioSink for DeviceSink {
+    fn push(&mut self, left: f32, right: f32) {
+        self.sender.send(left).unwrap();
+        self.sender.send(right).unwrap();
+        self.samples_sent += 2;
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    fn take_device_change(&mut self) -> Option<AudioDeviceChange> {
+        let change = self.device_change_receiver.try_iter().last()?;
+        self.sample_rate = change.sample_rate;
+        self.has_device = change.device_name.is_some();
+        Some(change)
+    }
+
+    fn timestamp(&mut self, cycle: u64) {
+        let _ = self.timestamp_sender.send((self.samples_sent, cycle));
+    }
+
+    fn last_played(&self) -> Option<AudioTimestamp> {
+        *self.last_played.lock().unwrap()
+    }
+
+    //Without a device the samples are thrown away in bursts, so there's no real queue to measure
+    fn queued(&self) -> Option<Duration> {
+        if !self.has_device {
+            return None;
+        }
+        let queued_samples = self.samples_sent.saturating_sub(self.samples_taken.load(Ordering::Relaxed)) / 2;
+        Some(Duration::from_secs_f32(queued_samples as f32 / self.sample_rate))
+    }
+
+    fn set_playback_settings(&mut self, settings: PlaybackSettings) {
+        if settings != self.settings {
+            self.settings = settings;
+            let _ = self.settings_sender.send(settings);
+        }
+    }
+}
+
+//The samples on their way to the device, and the timestamps saying which of them were generated when
+struct Playback {
+    samples: Receiver<f32>,
+    timestamps: Receiver<(u64, u64)>, //Index of the sample, cycle it was generated at
+    next_timestamp: Option<(u64, u64)>,
+    settings_receiver: Receiver<PlaybackSettings>,
+    settings: PlaybackSettings,
+    position: Arc<AtomicU64>, //Samples taken so far
+    last_played: Arc<Mutex<Option<AudioTimestamp>>>,
+}
+
+impl Playback {
+    //The latest settings sent, picked up once per callback
+    fn settings(&mut self) -> PlaybackSettings {
+        if let Some(settings) = self.settings_receiver.try_iter().last() {
+            self.settings = settings;
+        }
+        self.settings
+    }
+
+    //Waits for the next sample, which the device plays at played_at
+    fn recv(&mut self, played_at: Instant) -> Result<f32, RecvError> {
+        let sample = self.samples.recv()?;
+        self.advance(Some(played_at));
+        Ok(sample)
+    }
+
+    //For throwing samples away while there's no device
+    fn try_recv(&mut self) -> Result<f32, TryRecvError> {
+        let sample = self.samples.try_recv()?;
+        self.advance(None);
+        Ok(sample)
+    }
+
+    fn advance(&mut self, played_at: Option<Instant>) {
+        loop {
+            if self.next_timestamp.is_none() {
+                self.next_timestamp = self.timestamps.try_recv().ok();
+            }
+            match self.next_timestamp {
+                Some((index, cycle)) if index <= self.position.load(Ordering::Relaxed) => {
+                    if let Some(played_at) = played_at {
+                        *self.last_played.lock().unwrap() = Some(AudioTimestamp { cycle, played_at });
+                    }
+                    self.next_timestamp = None;
+                }
+                _ => break,
+            }
+        }
+        self.position.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+enum StreamEvent {
+    Error(cpal::StreamError),
+    Closed, //The APU was dropped
+}
+
+//Owns the output stream for as long as the APU exists. The stream is rebuilt on the new default device when it
+//fails or the default device changes, e.g. when headphones are unplugged
+fn run_output(playback: Playback, device_change_send: Sender<AudioDeviceChange>) {
+    let host = cpal::default_host();
+    let playback = Arc::new(Mutex::new(playback));
+    let (event_send, event_receive) = channel();
+
+    let mut stream: Option<cpal::Stream> = None;
+    let mut device_name: Option<String> = None;
+    let mut sample_rate = FALLBACK_SAMPLE_RATE;
+    let mut is_first_device = true;
+    loop {
+        let default_device = host.default_output_device();
+        let default_name = default_device.as_ref().and_then(|device| device.name().ok());
+        if stream.is_none() || default_name != device_name {
+            drop(stream.take());
+            if let Some(device) = &default_device {
+                match build_stream(device, playback.clone(), event_send.clone()) {
+                    Ok((new_stream, new_sample_rate)) => {
+                        stream = Some(new_stream);
+                        sample_rate = new_sample_rate;
+                    }
+                    Err(e) => println!("ERROR: Could not open audio device: {}", e),
+                }
+            }
+
+            let new_name = if stream.is_some() {default_name} else {None};
+            if is_first_device || new_name != device_name {
+                device_name = new_name;
+                is_first_device = false;
+                if device_change_send.send(AudioDeviceChange { device_name: device_name.clone(), sample_rate }).is_err() {
+                    return;
+                }
+            }
+        }
+
+        //Without a stream nothing reads the samples, so they're thrown away here instead of piling up
+        if stream.is_none() {
+            loop {
+                match playback.lock().unwrap().try_recv() {
+                    Ok(_) => {}
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                }
+            }
+        }
+
+        match event_receive.recv_timeout(DEVICE_POLL_INTERVAL) {
+            Ok(StreamEvent::Error(e)) => {
+                println!("ERROR: Audio stream failed, reopening: {}", e);
+                stream = None;
+            }
+            Ok(StreamEvent::Closed) | Err(RecvTimeoutError::Disconnected) => return,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+    }
+}
+
+fn build_stream(device: &cpal::Device, playback: Arc<Mutex<Playback>>, event_send: Sender<StreamEvent>) -> Result<(cpal::Stream, f32), String> {
+    let config = device.default_output_config().map_err(|e| e.to_string())?;
+    let sample_rate = config.sample_rate().0 as f32;
+
+    let stream = match config.sample_format() {
+        cpal::SampleFormat::I8 => run::<i8>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::I16 => run::<i16>(playback, event_send, device, &config.into()),
+        //cpal::SampleFormat::I24 => run::<I24>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::I32 => run::<i32>(playback, event_send, device, &config.into()),
+        //cpal::SampleFormat::I48 => run::<I48>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::I64 => run::<i64>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::U8 => run::<u8>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::U16 => run::<u16>(playback, event_send, device, &config.into()),
+        //cpal::SampleFormat::U24 => run::<U24>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::U32 => run::<u32>(playback, event_send, device, &config.into()),
+        //cpal::SampleFormat::U48 => run::<U48>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::U64 => run::<u64>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::F32 => run::<f32>(playback, event_send, device, &config.into()),
+        cpal::SampleFormat::F64 => run::<f64>(playback, event_send, device, &config.into()),
+        sample_format => return Err(format!("Unsupported sample format '{sample_format}'")),
+    };
+    Ok((stream?, sample_rate))
+}
+
+fn run<T>(playback: Arc<Mutex<Playback>>, event_send: Sender<StreamEvent>, device: &cpal::Device, config: &cpal::StreamConfig) -> Result<cpal::Stream, String>
+where 
+    T: SizedSample + FromSample<f32>,
+{
+    let sample_rate = config.sample_rate.0 as f32;
+    let channels = config.channels as usize;
+
+    let mut left_capacitor = 0.0;
+    let mut right_capacitor = 0.0;
+    let charge_factor = 0.999958_f32.powf(T_CYCLE_RATE / sample_rate);
+    let mut is_left_channel = false;
+    //The capacitor keeps charging while the filter is off, so turning it back on doesn't start from a step
+    let mut high_pass_filter = move |input: f32, enabled: bool| -> f32 {
+        let capacitor = if is_left_channel {&mut left_capacitor} else {&mut right_capacitor};
+
+        let output = input - *capacitor;
+        *capacitor = input - output * charge_factor;
+
+        if enabled {output} else {input}
+    };
+
+    let mut next_value = move |sample: f32, settings: PlaybackSettings| {
+        is_left_channel = ! is_left_channel;
+        
+        high_pass_filter(sample, settings.is_high_pass_enabled) * settings.volume
+    };
+
+    let closed_send = event_send.clone();
+    let err_fn = move |err| {
+        let _ = event_send.send(StreamEvent::Error(err));
+    };
+
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+            //The device says how long until what's written now is heard
+            let timestamp = info.timestamp();
+            let latency = timestamp.playback.duration_since(&timestamp.callback).unwrap_or_default();
+            let start = Instant::now() + latency;
+            let mut index = 0;
+
+            let mut playback = playback.lock().unwrap();
+            let settings = playback.settings();
+            write_data(data, channels, &mut || {
+                let played_at = start + Duration::from_secs_f32((index / channels) as f32 / sample_rate);
+                index += 1;
+                match playback.recv(played_at) {
+                    Ok(sample) => next_value(sample, settings),
+                    Err(_) => {
+                        let _ = closed_send.send(StreamEvent::Closed);
+                        0.0
+                    }
+                }
+            })
+        },
+        err_fn,
+        None,
+    ).map_err(|e| e.to_string())?;
+    stream.play().map_err(|e| e.to_string())?;
+
+    Ok(stream)
+}
+
+fn write_data<T>(output: &mut [T], channels: usize, next_sample: &mut dyn FnMut() -> f32)
+where
+    T: Sample + FromSample<f32>,
+{
+    for frame in output.chunks_mut(channels) {
+        
+        for sample in frame.iter_mut() {
+            let value: T = T::from_sample(next_sample());
+            *sample = value;
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::{fs::File, io::{self, BufWriter, Write}};
+
+use super::console::StrictViolation;
+
+//A CPU access to VRAM or OAM while the PPU or DMA had it locked. The instruction takes as long as it always does, but
+//a read sees 0xFF and a write is lost, which is easy to miss in homebrew until it shows up as garbage on screen
+#[derive(Clone, Copy)]
+pub struct BlockedAccess {
+    pub program_counter: u16,
+    pub address: u16,
+    pub value: Option<u8>, //Only for writes
+    pub reason: StrictViolation,
+    pub ly: u8,
+}
+
+//Writes each frame's blocked accesses as CSV
+pub struct BlockedAccessLogger {
+    writer: BufWriter<File>,
+}
+
+impl BlockedAccessLogger {
+    pub fn new(log_path: &str) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(log_path)?);
+        writeln!(writer, "frame,pc,address,access,value,ly,reason")?;
+
+        Ok(Self {
+            writer,
+        })
+    }
+
+    pub fn write_entries(&mut self, frame: u64, entries: &[BlockedAccess]) -> io::Result<()> {
+        for entry in entries {
+            write!(self.writer, "{},{:04X},{:04X},", frame, entry.program_counter, entry.address)?;
+            match entry.value {
+                Some(value) => write!(self.writer, "write,{:02X},", value)?,
+                None => write!(self.writer, "read,,")?,
+            }
+            writeln!(self.writer, "{},{}", entry.ly, entry.reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for BlockedAccessLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+pub fn rom_to_blocked_log(rom_file_path: String) -> String {
+    if let Some(blocked_log_path) = rom_file_path.rsplitn(2, ".").last() {
+        blocked_log_path.to_owned() + ".blocked.csv"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
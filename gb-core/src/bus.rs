@@ -0,0 +1,8 @@
+//Stands in for the whole memory map: cartridge, RAM, IO registers and all. With one set on the console, every read and
+//write the CPU makes goes to it instead, so the CPU can be run on its own against flat RAM, like the single step test
+//vectors expect. The rest of the console still runs, it just never sees the CPU's accesses
+pub trait Bus: Send {
+    fn read(&mut self, address: u16) -> u8;
+
+    fn write(&mut self, address: u16, value: u8);
+}
@@ -1,3 +1,45 @@
+use std::{fmt, io};
+
+//8 MiB, the biggest rom size code a header can have
+pub const MAX_ROM_SIZE: usize = 0x8000 << 8;
+
+//Why a rom couldn't be turned into a cartridge. Returned instead of panicking, so a bad file can't take the
+//emulation thread down with it
+#[derive(Debug)]
+pub enum CartridgeError {
+    Io(io::Error),
+    InvalidHeader, //Too small to hold a header, or the header checksum doesn't match
+    UnknownRomSize(u8),
+    UnknownRamSize(u8),
+    UnknownCartridgeType(u8),
+    Truncated {expected: usize, actual: usize}, //In bytes. The header promises more banks than the file holds
+    BatterySave(io::Error), //The .sav next to the rom exists but couldn't be read
+    TooLarge, //An archive unpacks to more than MAX_ROM_SIZE
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Could not read the rom: {}", e),
+            Self::InvalidHeader => write!(f, "Not a valid rom, the cartridge header is missing or corrupt"),
+            Self::UnknownRomSize(code) => write!(f, "Unknown rom size code: {:02X}", code),
+            Self::UnknownRamSize(code) => write!(f, "Unknown ram size code: {:02X}", code),
+            Self::UnknownCartridgeType(code) => write!(f, "Unsupported cartridge type: {:02X}", code),
+            Self::Truncated {expected, actual} => write!(f, "The header says the rom is {} bytes but the file only holds {}", expected, actual),
+            Self::BatterySave(e) => write!(f, "Could not read the battery save: {}", e),
+            Self::TooLarge => write!(f, "The archive holds more than the {} MiB the biggest cartridges have", MAX_ROM_SIZE >> 20),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+impl From<io::Error> for CartridgeError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
 #[derive(Clone)]
 pub enum CGBState {
     Monochrome,
@@ -24,12 +66,24 @@ pub struct CartridgeInfo {
 }
 
 impl CartridgeInfo {
-    pub fn new(header: &[u8]) -> Self {
+    //Checks the whole rom holds as many banks as its header says
+    pub fn from_rom(rom: &[u8]) -> Result<Self, CartridgeError> {
+        if !is_valid_rom(rom) {
+            return Err(CartridgeError::InvalidHeader);
+        }
+        let info = Self::new(&rom[0x134..0x14F])?;
+        if rom.len() < info.rom_size {
+            return Err(CartridgeError::Truncated {expected: info.rom_size, actual: rom.len()});
+        }
+        Ok(info)
+    }
+
+    pub fn new(header: &[u8]) -> Result<Self, CartridgeError> {
         let mut title = String::new();
         for byte in &header[..16] {
             title.push((*byte as char).to_ascii_uppercase());
         }
-        let manufacturer_code = std::str::from_utf8(&header[11..15]).expect("Invalid String").to_ascii_uppercase();
+        let manufacturer_code = String::from_utf8_lossy(&header[11..15]).to_ascii_uppercase();
         let cgb_flag = match header[15] {
             0x80 => CGBState::Both,
             0xC0 => CGBState::Color,
@@ -157,6 +211,9 @@ impl CartridgeInfo {
 
         let is_sgb = header[18] == 0x03;
         let cartridge_type = header[19];
+        if header[20] > 8 {
+            return Err(CartridgeError::UnknownRomSize(header[20]));
+        }
         let rom_size: usize = 0x8000 * (1 << header[20]);
         let rom_banks: usize = 0b10 << header[20];
         let (ram_size, ram_banks) = match header[21] {
@@ -165,13 +222,35 @@ impl CartridgeInfo {
             3 => (0x8000, 4),
             4 => (0x20000, 16),
             5 => (0x10000, 8),
-            _ => panic!("Invalid RAM Size!")
+            code => return Err(CartridgeError::UnknownRamSize(code))
         };
         let overseas_only = header[22] & 0b1 > 0;
         let version_number = header[23];
         let header_checksum = header[24];
         let global_checksum = ((header[25] as u16) << 8) + header[26] as u16;
 
-        Self {title: title, manufacturer_code: manufacturer_code, cgb_flag: cgb_flag, licensee: licensee, is_sgb: is_sgb, cartridge_type: cartridge_type, rom_size: rom_size, rom_banks: rom_banks, ram_size: ram_size, ram_banks: ram_banks, overseas_only: overseas_only, version_number: version_number, header_checksum: header_checksum, global_checksum: global_checksum}
+        Ok(Self {title, manufacturer_code, cgb_flag, licensee, is_sgb, cartridge_type, rom_size, rom_banks, ram_size, ram_banks, overseas_only, version_number, header_checksum, global_checksum})
+    }
+
+    //How many bytes of cartridge RAM a battery keeps, for the cartridge types that have one
+    pub fn battery_ram_size(&self) -> Option<usize> {
+        match self.cartridge_type {
+            0x06 => Some(512), //MBC2's RAM is built into the mapper, whatever the header says
+            0x03 | 0xFC | 0xFE | 0xFF if self.ram_banks > 0 => Some(self.ram_banks * 0x2000),
+            _ => None,
+        }
+    }
+}
+
+//Checks that the data is big enough to hold a cartridge header and that the header checksum matches
+pub fn is_valid_rom(rom: &[u8]) -> bool {
+    if rom.len() < 0x8000 {
+        return false;
+    }
+
+    let mut checksum: u8 = 0;
+    for byte in &rom[0x134..=0x14C] {
+        checksum = checksum.wrapping_sub(*byte).wrapping_sub(1);
     }
+    checksum == rom[0x14D]
 }
@@ -0,0 +1,135 @@
+use std::{fs, io};
+
+//Cheat devices plugged in between the console and the cartridge. A Game Genie swaps bytes as the game reads its ROM, a
+//GameShark writes values into RAM once a frame
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameGenieCode {
+    pub address: u16,
+    pub value: u8,
+    pub compare: Option<u8>, //Only patches while the ROM has this byte there, so other banks mapped at the address are left alone
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameSharkCode {
+    pub bank: u8, //01 writes to whichever bank is mapped in, 8X to cartridge RAM bank X and 9X to WRAM bank X
+    pub address: u16,
+    pub value: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CheatCode {
+    GameGenie(GameGenieCode),
+    GameShark(GameSharkCode),
+}
+
+impl CheatCode {
+    //Game Genie codes are ABC-DEF or ABC-DEF-GHI and GameShark codes are 8 digits. Dashes and spaces are ignored
+    pub fn parse(code: &str) -> Result<Self, String> {
+        let digits: Vec<u8> = code.chars().filter(|c| !matches!(c, '-' | ' ')).map(|c| c.to_digit(16).map(|digit| digit as u8))
+                                  .collect::<Option<_>>().ok_or_else(|| format!("{} isn't a hex code", code))?;
+        match digits.len() {
+            6 | 9 => GameGenieCode::decode(&digits).map(Self::GameGenie),
+            8 => GameSharkCode::decode(&digits).map(Self::GameShark),
+            _ => Err(format!("{} isn't a Game Genie or GameShark code", code)),
+        }
+    }
+}
+
+impl GameGenieCode {
+    //AB is the new byte and FCDE the address with its top digit inverted. GI is the byte being replaced, recovered by
+    //rotating it right twice and XORing it with BA. H is never read
+    fn decode(digits: &[u8]) -> Result<Self, String> {
+        let value = digits[0] << 4 | digits[1];
+        let address = ((digits[5] as u16) << 12 | (digits[2] as u16) << 8 | (digits[3] as u16) << 4 | digits[4] as u16) ^ 0xF000;
+        if address >= 0x8000 {
+            return Err(format!("Game Genie codes can only patch ROM, not ${:04X}", address));
+        }
+        let compare = (digits.len() == 9).then(|| (digits[6] << 4 | digits[8]).rotate_right(2) ^ 0xBA);
+
+        Ok(Self {
+            address,
+            value,
+            compare,
+        })
+    }
+}
+
+impl GameSharkCode {
+    //TTVVLLHH: the bank, the value, then the address low byte first
+    fn decode(digits: &[u8]) -> Result<Self, String> {
+        let bank = digits[0] << 4 | digits[1];
+        let value = digits[2] << 4 | digits[3];
+        let address = u16::from_le_bytes([digits[4] << 4 | digits[5], digits[6] << 4 | digits[7]]);
+        let is_valid = match bank {
+            0x01 => matches!(address, 0xA000..=0xDFFF | 0xFF80..=0xFFFE),
+            0x80..=0x8F => (0xA000..=0xBFFF).contains(&address),
+            0x90..=0x97 => (0xD000..=0xDFFF).contains(&address),
+            _ => return Err(format!("{:02X} isn't a GameShark code type", bank)),
+        };
+        if !is_valid {
+            return Err(format!("GameShark code type {:02X} can't write to ${:04X}", bank, address));
+        }
+
+        Ok(Self {
+            bank,
+            address,
+            value,
+        })
+    }
+}
+
+//A code as the player typed it, with the name they gave it
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cheat {
+    pub name: String,
+    pub code: String,
+    pub is_enabled: bool,
+}
+
+//The codes to hand the console. Anything that doesn't parse was already refused when it was added
+pub fn enabled_codes(cheats: &[Cheat]) -> Vec<CheatCode> {
+    cheats.iter().filter(|cheat| cheat.is_enabled).filter_map(|cheat| CheatCode::parse(&cheat.code).ok()).collect()
+}
+
+//One cheat per line: + or - for enabled or disabled, the code, then its name
+pub fn load_cheats(cheats_file_path: &str) -> Vec<Cheat> {
+    let Ok(contents) = fs::read_to_string(cheats_file_path) else {
+        return Vec::new();
+    };
+
+    let mut cheats = Vec::new();
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let (is_enabled, rest) = match (line.strip_prefix('+'), line.strip_prefix('-')) {
+            (Some(rest), _) => (true, rest),
+            (_, Some(rest)) => (false, rest),
+            _ => {
+                println!("ERROR: Skipping malformed cheat in {}: {}", cheats_file_path, line);
+                continue;
+            }
+        };
+        let (code, name) = rest.split_once(' ').unwrap_or((rest, ""));
+        cheats.push(Cheat { name: name.trim().to_owned(), code: code.to_owned(), is_enabled });
+    }
+    cheats
+}
+
+pub fn save_cheats(cheats_file_path: &str, cheats: &[Cheat]) -> io::Result<()> {
+    if cheats.is_empty() {
+        return match fs::remove_file(cheats_file_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+
+    let lines: Vec<String> = cheats.iter().map(|cheat| format!("{}{} {}", if cheat.is_enabled {"+"} else {"-"}, cheat.code, cheat.name)).collect();
+    fs::write(cheats_file_path, lines.join("\n") + "\n")
+}
+
+pub fn rom_to_cheats(rom_file_path: String) -> String {
+    if let Some(cheats_file_path) = rom_file_path.rsplitn(2, ".").last() {
+        cheats_file_path.to_owned() + ".cht"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
@@ -0,0 +1,2908 @@
+use std::{collections::VecDeque, io::{self, Read, Write}, time::Duration};
+
+use crate::{cartridge_info::{CartridgeError, CartridgeInfo}, mappers::{AuxData, Camera, CartridgeStorage, GameGenie, HuC1, HuC3, Mapper, NoMBC, MBC1, MBC2, MBC7}};
+
+use super::{cheats::{CheatCode, GameSharkCode}, alu::{self, C_CARRY_FLAG, H_HALF_CARRY_FLAG, N_SUBTRACTION_FLAG, Z_ZERO_FLAG}, apu::{self, AudioSink, APU}, apu_output::PlaybackSettings, bus::Bus, frame_export::{self, FrameFormat}, model::Model, opcodes, apu_log::ApuEvent, joypad::JoypadState, ram_init::RamInit, ppu::{self, Pixel, PPU}, raster_hooks::RasterHooks, savestate::*, serial::{Disconnected, SerialDevice, SerialPacing}, trace_log::{self, InstructionTrace}};
+#[cfg(feature = "apu")]
+use super::audio_device::DeviceSink;
+#[cfg(feature = "debugger")]
+use super::{debug_session::Watchpoint, heatmap::AccessCounters, inspector::PPUSnapshot, interrupt_log::InterruptEvent, watch_log::WatchLogEntry, blocked_log::BlockedAccess};
+
+pub struct GBConsole {
+    //CPU Registers
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    h: u8,
+    l: u8,
+    flags: u8,
+    stack_pointer: u16,
+    program_counter: u16,
+
+    //Cartridge ROM and RAM
+    cartridge: GameGenie, //Empty unless cheats are on
+    game_shark_codes: Vec<GameSharkCode>,
+    aux_data_path: Option<String>, //Only for cartridges backed by a battery save
+    boot_rom: Option<Vec<u8>>, //Mapped over the cartridge until FF50 is written
+
+    //Console RAM
+    working_ram: [u8; 0x1000], //Bank 0
+    aux_working_ram: Vec<[u8; 0x1000]>, //Banks 1-7 on CGB, just bank 1 on DMG
+    aux_working_ram_index: usize, //SVBK - 1
+    hardware_mode: HardwareMode,
+    post_boot_registers: [u8; 8], //A, B, C, D, E, H, L, F for the model, checked against what a boot ROM leaves
+    high_ram: [u8; 0x80],
+    ram_init: RamInit, //How RAM was filled at power on, kept so savestates record the seed
+
+    //Interrupt registers
+    pub interrupt_master_enable_flag: IMEState,
+    interrupt_enable: u8,
+    interrupt_flag: u8,
+
+    //Serial data transfer registers
+    serial_byte: u8, //SB
+    serial_control: u8, //SC
+    serial_counter: u8, //Counts down from 8 per cycle.
+    serial_incoming: Option<u8>, //The byte being shifted in from the link port during a transfer
+    serial_device: Box<dyn SerialDevice>,
+    serial_pacing: SerialPacing,
+    serial_wait: u32, //Dots left before the next transfer can start
+
+    //Timing registers
+    system_counter: u16, //DIV
+    timer_counter: u8, //TIMA
+    timer_modulo: u8, //TMA
+    timer_control: u8, //TAC
+    timer_overflowed: bool,
+    timer_overflowed_after: bool,
+
+    //CGB speed switch
+    is_double_speed: bool,
+    is_speed_switch_armed: bool, //KEY1 bit 0. The next STOP switches speed
+    is_apu_cycle_skipped: bool, //In double speed the APU only runs every other M-cycle
+
+    //DMG Pallette registers
+    pub dmg_bg_pallette: u8,    //BGP
+    pub dmg_obj_pallette_0: u8, //OBP0
+    pub dmg_obj_pallette_1: u8, //OBP1
+
+    //DMA registers
+    dma: u8,
+    dma_source: u8, //The page being copied, which only changes to the value of dma once a restarted transfer begins
+    dma_index: u16, //The next byte of OAM to copy. 0xA0 when no transfer is running
+    dma_start_delay: Option<u8>, //M-cycles until a transfer requested through FF46 begins
+
+    //VRAM DMA registers (CGB only)
+    vram_dma_source: u16, //HDMA1-2. Where the next block is copied from
+    vram_dma_destination: u16, //HDMA3-4, as an offset into VRAM. Where the next block is copied to
+    vram_dma_length: u8, //HDMA5's low 7 bits. Blocks left minus 1, so 0x7F once a transfer is done
+    is_hblank_dma_active: bool, //HDMA5's bit 7 cleared. A block is copied each HBlank until the length runs out
+    vram_dma_bytes_left: u16, //Bytes of the blocks that are due, copied while the CPU waits
+
+    //Player input register
+    joypad: u8,
+
+    //Input keys
+    joypad_state: JoypadState, //Latched once per frame so input doesn't depend on when the UI thread runs
+
+    //Misc variables
+    cpu_delay: u8, //M-cycles left in the current instruction or interrupt dispatch, 255 between them
+    instruction_start: u8, //M-cycles of interrupt dispatch before the current instruction
+    hardware_ahead: VecDeque<CycleEvents>, //Cycles the rest of the console already ran so the CPU could access memory
+    pub is_halted: bool,
+    is_stopped: bool, //STOP's low power mode. The CPU, timer, PPU and APU all wait for a button press
+
+    //Debugging
+    pub break_on_unknown_io: bool,
+    pub is_strict: bool, //Break on accesses real hardware ignores or answers with junk, see StrictViolation
+    pub is_prohibited_area_accurate: bool, //Read 0xFEA0-0xFEFF like the hardware being emulated instead of the old guess
+    io_break: Option<IOBreak>,
+    boot_error: Option<String>, //How the state a boot ROM handed over differs from the post-boot state, until taken
+    is_untracked: bool, //The emulator's own tools are reading or writing, unknown registers aren't reported
+    instruction_trace: Option<InstructionTrace>,
+    #[cfg(feature = "debugger")]
+    watchpoints: Vec<Watchpoint>,
+    #[cfg(feature = "debugger")]
+    watch_log: Vec<WatchLogEntry>, //Hits on logged watchpoints since the log was last taken
+    #[cfg(feature = "debugger")]
+    instruction_address: u16, //Where the running instruction starts, since the program counter has moved past it by its accesses
+    #[cfg(feature = "debugger")]
+    blocked_accesses: Option<Vec<BlockedAccess>>, //Only kept while blocked accesses are being logged
+    #[cfg(feature = "debugger")]
+    write_hooks: Vec<(u16, u16)>, //Inclusive ranges a script wants to hear about, see take_hooked_writes
+    #[cfg(feature = "debugger")]
+    hooked_writes: Vec<(u16, u8)>,
+    #[cfg(feature = "debugger")]
+    access_counters: Option<Box<AccessCounters>>, //Only kept while the memory heatmap is open
+    #[cfg(feature = "debugger")]
+    interrupt_events: Option<Vec<InterruptEvent>>, //Only kept while IRQ flash is on
+    #[cfg(feature = "debugger")]
+    interrupt_handlers: Vec<(u8, u16)>, //Each running handler's interrupt and the stack pointer right after it was called
+    pub is_ppu_frozen: bool, //The CPU keeps running while the PPU and APU stay exactly where they were
+    pub is_apu_frozen: bool,
+
+    //Frame access
+    output_cycle: u64, //M-cycles of output played so far. Left out of savestates so frame and audio timestamps never go back
+
+    //External objects
+    raster_hooks: Option<Box<dyn RasterHooks>>,
+    bus: Option<Box<dyn Bus>>, //Replaces the memory map for the CPU, see Bus
+    ppu: PPU,
+    apu: APU,
+}
+
+//Where the boot ROM leaves SP and PC on every model. The other registers depend on the model, see Model
+const POST_BOOT_STACK_POINTER: u16 = 0xFFFE;
+const POST_BOOT_PROGRAM_COUNTER: u16 = 0x0100;
+
+//Whether CGB features are on. Cartridges that support CGB get them on a color model, see Model::hardware_mode
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HardwareMode {
+    Dmg,
+    Cgb,
+}
+
+//Consoles are moved onto worker threads by the test runner and anything else that runs them headless
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<GBConsole>();
+};
+
+impl GBConsole {
+    #[cfg(feature = "apu")]
+    pub fn new(info: CartridgeInfo, rom: &[u8], storage: CartridgeStorage, model: Model) -> Result<Self, CartridgeError> {
+        Self::with_audio_sink(info, rom, storage, model, Box::new(DeviceSink::open()))
+    }
+
+    //A console that never opens an audio device
+    pub fn headless(info: CartridgeInfo, rom: &[u8], storage: CartridgeStorage, model: Model) -> Result<Self, CartridgeError> {
+        Self::with_audio_sink(info, rom, storage, model, Box::new(apu::NullSink))
+    }
+
+    //A headless console whose cartridge RAM isn't backed by the battery save, so loading states into it to look
+    //at them can't change the .sav file. It never touches the file system
+    pub fn detached(info: CartridgeInfo, rom: &[u8]) -> Result<Self, CartridgeError> {
+        let model = Model::for_cartridge(&info);
+        Self::with_audio_sink(info, rom, CartridgeStorage::default(), model, Box::new(apu::NullSink))
+    }
+
+    //Builds the cartridge's mapper. It's kept apart from building the console so a running console can have its cartridge swapped.
+    //The battery save comes already opened, and there's none without one
+    fn create_mapper(info: &CartridgeInfo, rom: &[u8], storage: CartridgeStorage) -> Result<Box<dyn Mapper>, CartridgeError> {
+        let battery = storage.battery;
+        let mut mapper: Box<dyn Mapper> = match info.cartridge_type {
+            0x00 => Box::new(NoMBC::new(rom, false)?),
+            //RAM wired straight to the bus. 0x09 has a battery, but without a mapper to write through it isn't saved
+            0x08 | 0x09 => Box::new(NoMBC::new(rom, true)?),
+            0x01..=0x03 => Box::new(MBC1::new(rom, info.rom_banks, info.ram_banks, battery)?),
+            0x05 | 0x06 => Box::new(MBC2::new(rom, info.rom_banks, battery)?),
+            0x22 => Box::new(MBC7::new(rom, info.rom_banks)?),
+            0xFC => Box::new(Camera::new(rom, info.rom_banks, info.ram_banks, battery)?),
+            0xFE => Box::new(HuC3::new(rom, info.rom_banks, info.ram_banks, battery)?),
+            0xFF => Box::new(HuC1::new(rom, info.rom_banks, info.ram_banks, battery)?),
+            code => return Err(CartridgeError::UnknownCartridgeType(code))
+        };
+        if let Some(aux_data_path) = &storage.aux_data_path {
+            mapper.load_aux_data(&AuxData::load(aux_data_path));
+        }
+        Ok(mapper)
+    }
+
+    //Writes the cartridge's aux data, if it has any, keeping any other sections already in the file
+    pub fn save_aux_data(&self) {
+        let Some(aux_data_path) = &self.aux_data_path else {
+            return;
+        };
+        let mut aux_data = AuxData::load(aux_data_path);
+        self.cartridge.save_aux_data(&mut aux_data);
+        if aux_data.is_empty() {
+            return;
+        }
+        if let Err(e) = aux_data.save(aux_data_path) {
+            println!("ERROR: Could not write {}: {}", aux_data_path, e);
+        }
+    }
+
+    //Pulls the cartridge out and puts another one in without resetting anything else, like the swap tricks done on real
+    //hardware. RAM, CPU registers, the PPU and the APU carry over. The new cartridge's mapper starts in its power on state
+    pub fn swap_cartridge(&mut self, info: &CartridgeInfo, rom: &[u8], storage: CartridgeStorage) -> Result<(), CartridgeError> {
+        self.save_aux_data();
+        let aux_data_path = storage.aux_data_path.clone();
+        self.cartridge.insert(Self::create_mapper(info, rom, storage)?);
+        self.aux_data_path = aux_data_path;
+        Ok(())
+    }
+
+    //For embedders that play the audio themselves. With the default storage the cartridge has no battery save
+    pub fn with_audio_sink(info: CartridgeInfo, rom: &[u8], storage: CartridgeStorage, model: Model, audio_sink: Box<dyn AudioSink>) -> Result<Self, CartridgeError> {
+        let aux_data_path = storage.aux_data_path.clone();
+        let cartridge = Self::create_mapper(&info, rom, storage)?;
+
+        let hardware_mode = model.hardware_mode(&info);
+        let aux_working_ram = vec![[0; 0x1000]; if hardware_mode == HardwareMode::Cgb {7} else {1}];
+
+        let post_boot_registers = model.post_boot_registers(&info);
+        let [a, b, c, d, e, h, l, flags] = post_boot_registers;
+
+        Ok(Self {
+            a,
+            b,
+            c,
+            d,
+            e,
+            h,
+            l,
+            flags,
+            stack_pointer: POST_BOOT_STACK_POINTER,
+            program_counter: POST_BOOT_PROGRAM_COUNTER,
+            cartridge: GameGenie::new(cartridge),
+            game_shark_codes: Vec::new(),
+            aux_data_path,
+            boot_rom: None,
+            working_ram: [0; 0x1000],
+            aux_working_ram: aux_working_ram,
+            aux_working_ram_index: 0,
+            hardware_mode,
+            post_boot_registers,
+            high_ram: [0; 0x80],
+            ram_init: RamInit::Zeroed,
+            interrupt_master_enable_flag: IMEState::Disabled,
+            interrupt_enable: 0x00,
+            interrupt_flag: 0xE1,
+            serial_byte: 0x00,
+            serial_control: 0x7E,
+            serial_counter: 0,
+            serial_incoming: None,
+            serial_device: Box::new(Disconnected),
+            serial_pacing: SerialPacing::Off,
+            serial_wait: 0,
+            system_counter: (model.post_boot_div() as u16) << 6,
+            timer_counter: 0x00,
+            timer_modulo: 0x00,
+            timer_control: 0xF8,
+            timer_overflowed: false,
+            timer_overflowed_after: false,
+            is_double_speed: false,
+            is_speed_switch_armed: false,
+            is_apu_cycle_skipped: false,
+            dmg_bg_pallette: 0xFC,
+            dmg_obj_pallette_0: 0x00,
+            dmg_obj_pallette_1: 0x00,
+            dma: 0xFF,
+            dma_source: 0xFF,
+            dma_index: 0xA0,
+            dma_start_delay: None,
+            vram_dma_source: 0,
+            vram_dma_destination: 0,
+            vram_dma_length: 0x7F,
+            is_hblank_dma_active: false,
+            vram_dma_bytes_left: 0,
+            joypad: 0xCF,
+            joypad_state: JoypadState::default(),
+            cpu_delay: 255,
+            instruction_start: 0,
+            hardware_ahead: VecDeque::new(),
+            is_halted: false,
+            is_stopped: false,
+            break_on_unknown_io: false,
+            is_strict: false,
+            is_prohibited_area_accurate: false,
+            is_ppu_frozen: false,
+            is_apu_frozen: false,
+            output_cycle: 0,
+            io_break: None,
+            boot_error: None,
+            is_untracked: false,
+            instruction_trace: None,
+            #[cfg(feature = "debugger")]
+            watchpoints: Vec::new(),
+            #[cfg(feature = "debugger")]
+            watch_log: Vec::new(),
+            #[cfg(feature = "debugger")]
+            instruction_address: 0,
+            #[cfg(feature = "debugger")]
+            blocked_accesses: None,
+            #[cfg(feature = "debugger")]
+            write_hooks: Vec::new(),
+            #[cfg(feature = "debugger")]
+            hooked_writes: Vec::new(),
+            #[cfg(feature = "debugger")]
+            access_counters: None,
+            #[cfg(feature = "debugger")]
+            interrupt_events: None,
+            #[cfg(feature = "debugger")]
+            interrupt_handlers: Vec::new(),
+            raster_hooks: None,
+            bus: None,
+            ppu: ppu::PPU::new(hardware_mode),
+            apu: apu::APU::new(audio_sink),
+        })
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
+        if let Some(bus) = &mut self.bus {
+            return bus.read(address);
+        }
+        #[cfg(feature = "debugger")]
+        self.track_access(address, None);
+        if self.is_strict {
+            self.check_strict(address, None);
+        }
+
+        //Boot ROM
+        if let Some(byte) = self.read_boot_rom(address) {
+            byte
+        }
+        //Cartrige ROM
+        else if address < 0x8000 {
+            self.cartridge.read(address)
+        }
+        //VRAM
+        else if address < 0xA000 {
+            self.ppu.read(address)
+        }
+        //Cartrige RAM
+        else if address < 0xC000 {
+            self.cartridge.read(address)
+        }
+        //WRAM, and Echo RAM mirroring it (Use is prohibited by nintendo)
+        else if address < 0xFE00 {
+            *self.working_ram_byte(address)
+        }
+        //Object Attribute Memory. The DMA has the OAM bus to itself while it's copying
+        else if address < 0xFEA0 {
+            if self.is_dma_active() {
+                0xFF
+            }
+            else {
+                self.ppu.read(address)
+            }
+        }
+        //Not Usable (Use is prohibited by Nintendo)
+        else if address < 0xFF00 {
+            self.read_prohibited_area(address)
+        }
+        //I/O Registers
+        else if address < 0xFF80 {
+            //TODO: Implement I/O Registers
+            match address {
+                0xFF00 => self.joypad | 0b11000000, //P1/JOYP
+                0xFF01 => self.serial_byte, //SB
+                0xFF02 => self.serial_control, //SC
+                0xFF04 => (self.system_counter >> 6).to_be_bytes()[1], //DIV
+                0xFF05 => self.timer_counter, //TIMA
+                0xFF06 => self.timer_modulo, //TMA
+                0xFF07 => self.timer_control, //TAC
+                0xFF0F => self.interrupt_flag, //IF
+                0xFF10..0xFF27 => self.apu.read(address), //Audio registers
+                0xFF30..0xFF40 => self.apu.read(address), //Waveform RAM             
+                0xFF46 => self.dma, //DMA transfer source address 0xXX00 + dma_index
+                0xFF47 => self.dmg_bg_pallette, //BGP
+                0xFF48 => self.dmg_obj_pallette_0, //OBP0
+                0xFF49 => self.dmg_obj_pallette_1, //OBP1
+                0xFF40..0xFF46 | 0xFF4A | 0xFF4B => self.ppu.read(address), //PPU Registers
+                0xFF50 => 0xFF, //BANK (write only)
+                0xFF4D if self.hardware_mode == HardwareMode::Cgb => 0x7E | ((self.is_double_speed as u8) << 7) | self.is_speed_switch_armed as u8, //KEY1
+                0xFF4D => 0xFF, //KEY1
+                0xFF4F | 0xFF68..0xFF6C if self.hardware_mode == HardwareMode::Cgb => self.ppu.read(address), //VBK, BCPS/BCPD, OCPS/OCPD
+                0xFF4F => 0xFF, //VBK
+                0xFF51..0xFF55 => 0xFF, //HDMA1-4 (write only)
+                0xFF55 if self.hardware_mode == HardwareMode::Cgb => ((!self.is_hblank_dma_active as u8) << 7) | self.vram_dma_length, //HDMA5
+                0xFF55 => 0xFF, //HDMA5
+                0xFF56 => 0xFF, //RP
+                0xFF68..0xFF6D => 0xFF, //Other CGB registers
+                0xFF70 if self.hardware_mode == HardwareMode::Cgb => 0xF8 | (self.aux_working_ram_index as u8 + 1), //SVBK
+                0xFF70 => 0xFF,
+                0xFF76 | 0xFF77 => 0xFF, //CGB Audio registers
+                _ => {
+                    if !self.is_untracked {
+                        println!("ERROR: Unkown register at address ${:x}", address);
+                    }
+                    self.report_unknown_io(address, None);
+                    0xFF
+                }
+            }
+        }
+        //HRAM
+        else if address < 0xFFFF {
+            self.high_ram[(address - 0xFF80) as usize]
+        }
+        //Interrupt Enable Register
+        else {
+            self.interrupt_enable
+        }
+    }
+
+
+    //Reads made by the emulator's own tools shouldn't show up on the heatmap or hit watchpoints
+    pub fn read_bulk(&mut self, start_address: u16, end_address: u16) -> Vec<u8> {
+        self.untracked(|console| (start_address..=end_address).map(|address| console.read(address)).collect())
+    }
+
+    fn untracked<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        #[cfg(feature = "debugger")]
+        let suspended = (self.access_counters.take(), std::mem::take(&mut self.watchpoints), self.blocked_accesses.take(), std::mem::take(&mut self.write_hooks));
+        let is_strict = std::mem::replace(&mut self.is_strict, false);
+        let is_untracked = std::mem::replace(&mut self.is_untracked, true);
+        let result = f(self);
+        #[cfg(feature = "debugger")]
+        {
+            (self.access_counters, self.watchpoints, self.blocked_accesses, self.write_hooks) = suspended;
+        }
+        self.is_strict = is_strict;
+        self.is_untracked = is_untracked;
+        result
+    }
+
+    //Reads for the emulator's own tools. A bank reaches into whichever banked memory the address is in, mapped in or not.
+    //VRAM and OAM are read even while the PPU or OAM DMA has them locked
+    #[cfg(feature = "debugger")]
+    pub fn peek(&mut self, bank: Option<u16>, address: u16) -> Result<u8, String> {
+        let Some(bank) = bank else {
+            if let Some(byte) = self.ppu.unlocked_byte(address) {
+                return Ok(*byte);
+            }
+            return Ok(self.read_bulk(address, address)[0]);
+        };
+        if (0xA000..=0xBFFF).contains(&address) {
+            let offset = Self::cartridge_ram_offset(bank, address);
+            return self.cartridge.ram().and_then(|ram| ram.get(offset)).copied().ok_or_else(|| Self::missing_bank(bank, address));
+        }
+        self.banked_byte(bank, address).map(|byte| *byte)
+    }
+
+    //Writes for the emulator's own tools. Banked ROM addresses patch the loaded rom, and like peek VRAM and OAM are never
+    //locked
+    #[cfg(feature = "debugger")]
+    pub fn poke(&mut self, bank: Option<u16>, address: u16, value: u8) -> Result<(), String> {
+        let Some(bank) = bank else {
+            if let Some(byte) = self.ppu.unlocked_byte(address) {
+                *byte = value;
+                return Ok(());
+            }
+            self.untracked(|console| console.write(address, value));
+            return Ok(());
+        };
+        if (0xA000..=0xBFFF).contains(&address) {
+            //Goes through the mapper so the battery save picks it up
+            let offset = Self::cartridge_ram_offset(bank, address);
+            if offset >= self.cartridge.ram().map_or(0, |ram| ram.len()) {
+                return Err(Self::missing_bank(bank, address));
+            }
+            self.cartridge.write_ram(offset, value);
+            return Ok(());
+        }
+        *self.banked_byte(bank, address)? = value;
+        Ok(())
+    }
+
+    #[cfg(feature = "debugger")]
+    fn banked_byte(&mut self, bank: u16, address: u16) -> Result<&mut u8, String> {
+        let bank = bank as usize;
+        let byte = match address {
+            0x0000..=0x3FFF if bank == 0 => self.cartridge.rom_mut().get_mut(address as usize),
+            0x4000..=0x7FFF => self.cartridge.rom_mut().get_mut(bank * 0x4000 + (address - 0x4000) as usize),
+            0x8000..=0x9FFF => self.ppu.video_ram_bank_mut(bank).map(|video_ram| &mut video_ram[(address - 0x8000) as usize]),
+            0xC000..=0xCFFF if bank == 0 => Some(&mut self.working_ram[(address - 0xC000) as usize]),
+            0xD000..=0xDFFF if bank > 0 => self.aux_working_ram.get_mut(bank - 1).map(|working_ram| &mut working_ram[(address - 0xD000) as usize]),
+            _ => return Err(format!("${:04X} has no bank {:02X}", address, bank)),
+        };
+        byte.ok_or_else(|| Self::missing_bank(bank as u16, address))
+    }
+
+    #[cfg(feature = "debugger")]
+    fn cartridge_ram_offset(bank: u16, address: u16) -> usize {
+        bank as usize * 0x2000 + (address - 0xA000) as usize
+    }
+
+    #[cfg(feature = "debugger")]
+    fn missing_bank(bank: u16, address: u16) -> String {
+        format!("Bank {:02X} doesn't exist at ${:04X}", bank, address)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if let Some(bus) = &mut self.bus {
+            bus.write(address, value);
+            return;
+        }
+        #[cfg(feature = "debugger")]
+        self.track_access(address, Some(value));
+        if self.is_strict {
+            self.check_strict(address, Some(value));
+        }
+
+        //Cartrige ROM
+        if address < 0x8000 {
+            self.cartridge.write(address, value);
+        }
+        //VRAM
+        else if address < 0xA000 {
+            self.ppu.write(address, value);
+        }
+        //Cartrige RAM
+        else if address < 0xC000 {
+            self.cartridge.write(address, value);
+        }
+        //WRAM, and Echo RAM mirroring it (Use is prohibited by nintendo)
+        else if address < 0xFE00 {
+            *self.working_ram_byte(address) = value;
+        }
+        //Object Attribute Memory
+        else if address < 0xFEA0 {
+            if !self.is_dma_active() {
+                self.ppu.write(address, value);
+            }
+        }
+        //Not Usable (Use is prohibited by Nintendo). Writes are ignored on every model
+        else if address < 0xFF00 {
+        }
+        //I/O Registers
+        else if address < 0xFF80 {
+            //TODO: Implement I/O Registers
+            let register = match address {
+                0xFF00 => { //P1/JoyP
+                    self.joypad = (self.joypad & 0xF) | (value & 0xF0);
+                    self.set_buttons();
+                    return;
+                }, 
+                0xFF01 => { //SB
+                    if self.serial_control & 0x80 > 0 {
+                        return;
+                    }
+                    &mut self.serial_byte
+                }
+                0xFF02 => { //SC
+                    if self.serial_control & 0x80 > 0 {
+                        self.serial_control = value | 0x80;
+                        return;
+                    }
+                    else if value & 0x80 > 0 {
+                        self.serial_counter = 8;
+                    }
+                    &mut self.serial_control
+                }
+                0xFF04 => { //DIV
+                    let system_counter_before = self.system_counter;
+                    self.system_counter = 0;
+                    self.timer_tick(system_counter_before, self.timer_control);
+                    return;
+                }
+                0xFF05 => { //TIMA
+                    if self.timer_overflowed {
+                        self.timer_overflowed = false;
+                    }
+                    if self.timer_overflowed_after {
+                        return;
+                    }
+                    &mut self.timer_counter
+                },
+                0xFF06 => { //TMA
+                    if self.timer_overflowed_after {
+                        self.timer_counter = value;
+                    }
+                    &mut self.timer_modulo
+                },
+                0xFF07 => { //TAC
+                   let timer_control_before = self.timer_control;
+                   self.timer_control = value;
+                   self.timer_tick(self.system_counter, timer_control_before);
+                   return;
+                },
+                0xFF0f => &mut self.interrupt_flag, //IF
+                0xFF46 => { //DMA transfer address. Also schedules a new DMA transfer, which replaces any running one once it starts
+                    self.dma = value;
+                    self.dma_start_delay = Some(1);
+                    return;
+                }
+                0xFF10..0xFF27 => { //Sound registers
+                    self.apu.write(address, value);
+                    return;
+                },
+                0xFF30..0xFF40 => { //Waveform registers
+                    self.apu.write(address, value);
+                    return;
+                },
+                0xFF47 => &mut self.dmg_bg_pallette, //BGP
+                0xFF48 => &mut self.dmg_obj_pallette_0, //OBP0
+                0xFF49 => &mut self.dmg_obj_pallette_1, //OBP1
+                0xFF40..0xFF46 | 0xFF4A | 0xFF4B => { //PPU Registers
+                    self.ppu.write(address, value);
+                    return;
+                }
+                0xFF50 => { //BANK. Unmaps the boot ROM, which can't be undone
+                    if value != 0 && self.boot_rom.is_some() {
+                        self.boot_rom = None;
+                        self.boot_error = self.validate_post_boot_state().err();
+                    }
+                    return;
+                }
+                0xFF4D => { //KEY1
+                    if self.hardware_mode == HardwareMode::Cgb {
+                        self.is_speed_switch_armed = value & 1 > 0;
+                    }
+                    return;
+                }
+                0xFF4F | 0xFF68..0xFF6C if self.hardware_mode == HardwareMode::Cgb => { //VBK, BCPS/BCPD, OCPS/OCPD
+                    self.ppu.write(address, value);
+                    return;
+                }
+                0xFF4F => return, //VBK
+                0xFF51..0xFF56 if self.hardware_mode == HardwareMode::Cgb => { //HDMA1-5
+                    self.write_vram_dma(address, value);
+                    return;
+                }
+                0xFF51..0xFF56 => return, //HDMA1-5
+                0xFF56 => return, //RP
+                0xFF68..0xFF6D => return, //Other CGB registers
+                0xFF70 => { //SVBK
+                    //Bank 0 can't be mapped to 0xD000, selecting it selects bank 1
+                    if self.hardware_mode == HardwareMode::Cgb {
+                        self.aux_working_ram_index = (value & 0b111).max(1) as usize - 1;
+                    }
+                    return;
+                }
+                0xFF76 | 0xFF77 => return, //CGB audio registers
+                _ => {
+                    if !self.is_untracked {
+                        println!("ERROR: Unknown register at address ${:x}", address);
+                    }
+                    self.report_unknown_io(address, Some(value));
+                    return;
+                }
+            };
+
+            *register = value;
+        }
+        //HRAM
+        else if address < 0xFFFF {
+            self.high_ram[(address - 0xFF80) as usize] = value;
+        }
+        //Interrupt Enable Register
+        else {
+            self.interrupt_enable = value;
+        }
+    }
+
+
+    fn flag_toggle(&mut self, condition: bool, flag: u8) {
+        if condition {
+            self.flags |= flag;
+        }
+        else {
+            self.flags &= 0xFF ^ flag;
+        }
+    }
+
+    //Runs one M-cycle of the whole console. Frontends that don't need to stop between the CPU and the rest can use
+    //this instead of step_cpu and step_hardware
+    pub fn step(&mut self) -> CycleEvents {
+        self.step_cpu();
+        self.step_hardware()
+    }
+
+    //Runs one M-cycle of the CPU. Interrupts are only taken between instructions, so they're checked when cpu_delay
+    //has run out, and dispatching one runs together with the first instruction of the handler. The instruction runs
+    //on its first cycle, moving the rest of the console along to each of its memory accesses, see sync_to
+    pub fn step_cpu(&mut self) {
+        if self.is_stopped {
+            return;
+        }
+        //VRAM DMA takes over the bus between instructions, so the CPU waits until the blocks that are due are copied
+        if self.cpu_delay == 255 && self.vram_dma_bytes_left > 0 && !self.is_halted {
+            self.step_vram_dma();
+            return;
+        }
+        if self.cpu_delay == 255 {
+            self.instruction_start = 0;
+            self.cpu_delay = self.handle_interrupt();
+            if self.is_halted {
+                return;
+            }
+
+            //EI takes effect after the instruction following it, which gets to run first even with an interrupt
+            //waiting. A DI there cancels it, since it runs with IME already set and clears it again
+            if self.interrupt_master_enable_flag == IMEState::Pending {
+                self.interrupt_master_enable_flag = IMEState::Enabled
+            }
+            if self.instruction_trace.is_some() {
+                self.trace_instruction();
+            }
+            #[cfg(feature = "debugger")]
+            {
+                self.instruction_address = self.program_counter;
+            }
+            self.instruction_start = self.cpu_delay;
+            self.cpu_delay = self.cpu_delay.wrapping_add(self.get_instruction_delay());
+            self.execute_instruction();
+            #[cfg(feature = "debugger")]
+            self.log_interrupt_returns();
+        }
+        self.cpu_delay = self.cpu_delay.wrapping_sub(1);
+
+        if self.cpu_delay == 0 {
+            self.cpu_delay = 255;
+        }
+    }
+
+    //Runs one M-cycle of everything but the CPU: DMA, the timer, the PPU and link port for each dot, then the APU.
+    //Cycles the CPU already ran ahead for its memory accesses are handed back as they were instead of running again
+    pub fn step_hardware(&mut self) -> CycleEvents {
+        self.hardware_ahead.pop_front().unwrap_or_else(|| self.run_hardware_cycle())
+    }
+
+    fn run_hardware_cycle(&mut self) -> CycleEvents {
+        let mut events = CycleEvents::default();
+        self.update_dma();
+        self.update_timer();
+
+        for _dot in 0..self.dots_per_cycle() {
+            events.is_new_frame |= self.update_ppu();
+            if let Some(byte) = self.check_serial() {
+                events.serial_byte = Some(byte);
+            }
+        }
+
+        self.update_apu();
+        events
+    }
+
+    //Runs the rest of the console ahead to just before the given M-cycle of the current instruction, counting the
+    //opcode fetch as 1, so the memory access that follows sees DMA, the timer and the PPU as they'd be at that point
+    fn sync_to(&mut self, m_cycle: u8) {
+        let cycles_before = (self.instruction_start + m_cycle - 1) as usize;
+        while self.hardware_ahead.len() < cycles_before {
+            let events = self.run_hardware_cycle();
+            self.hardware_ahead.push_back(events);
+        }
+    }
+
+    fn read_at(&mut self, m_cycle: u8, address: u16) -> u8 {
+        self.sync_to(m_cycle);
+        self.read(address)
+    }
+
+    fn write_at(&mut self, m_cycle: u8, address: u16, value: u8) {
+        self.sync_to(m_cycle);
+        self.write(address, value);
+    }
+
+    //Little endian, the low byte on the given cycle and the high byte on the next
+    fn read_16_at(&mut self, m_cycle: u8, address: u16) -> u16 {
+        let lsb = self.read_at(m_cycle, address);
+        let msb = self.read_at(m_cycle + 1, address.wrapping_add(1));
+        u16::from_be_bytes([msb, lsb])
+    }
+
+    //The high byte goes first, on the given cycle, and the low byte on the next
+    fn push_at(&mut self, m_cycle: u8, value: u16) {
+        let [msb, lsb] = value.to_be_bytes();
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.write_at(m_cycle, self.stack_pointer, msb);
+        self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+        self.write_at(m_cycle + 1, self.stack_pointer, lsb);
+    }
+
+    //The low byte is read on the given cycle, and the high byte on the next
+    fn pop_at(&mut self, m_cycle: u8) -> u16 {
+        let lsb = self.read_at(m_cycle, self.stack_pointer);
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        let msb = self.read_at(m_cycle + 1, self.stack_pointer);
+        self.stack_pointer = self.stack_pointer.wrapping_add(1);
+        u16::from_be_bytes([msb, lsb])
+    }
+
+    fn trace_instruction(&mut self) {
+        let program_counter = self.program_counter;
+        let pc_memory = self.untracked(|console| [0, 1, 2, 3].map(|offset| console.read(program_counter.wrapping_add(offset))));
+        let line = trace_log::doctor_line(&self.cpu_registers(), pc_memory);
+        if let Some(instruction_trace) = &mut self.instruction_trace {
+            instruction_trace.log(line);
+        }
+    }
+
+    //Logs every instruction from here on, replacing the trace that was running, which is returned. None stops tracing
+    pub fn set_instruction_trace(&mut self, instruction_trace: Option<InstructionTrace>) -> Option<InstructionTrace> {
+        std::mem::replace(&mut self.instruction_trace, instruction_trace)
+    }
+
+    pub fn instruction_trace(&self) -> Option<&InstructionTrace> {
+        self.instruction_trace.as_ref()
+    }
+
+    //Whether the CPU is about to start a new instruction or interrupt, which is where breakpoints stop
+    pub fn is_between_instructions(&self) -> bool {
+        self.cpu_delay == 255
+    }
+
+    pub fn handle_interrupt(&mut self) -> u8 {
+        if self.is_halted && self.interrupt_master_enable_flag != IMEState::Enabled && self.interrupt_flag & self.interrupt_enable > 0 {
+            self.is_halted = false;
+        }
+
+        if self.interrupt_master_enable_flag == IMEState::Enabled && self.pending_interrupt() > 0 {
+            //The interrupt to take is picked again between pushing the high and low bytes of PC. If the high byte
+            //landed on IE and disabled it, the CPU jumps to 0x0000 and leaves IF alone
+            let [msb, lsb] = self.program_counter.to_be_bytes();
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+            self.write_at(3, self.stack_pointer, msb);
+            let interrupt_bit = self.pending_interrupt();
+            self.stack_pointer = self.stack_pointer.wrapping_sub(1);
+            self.write_at(4, self.stack_pointer, lsb);
+
+            self.program_counter = match interrupt_bit {
+                0b1 => 0x40,
+                0b10 => 0x48,
+                0b100 => 0x50,
+                0b1000 => 0x58,
+                0b10000 => 0x60,
+                _ => 0x0000
+            };
+            self.interrupt_master_enable_flag = IMEState::Disabled;
+            self.is_halted = false;
+            if interrupt_bit > 0 {
+                self.interrupt_flag &= 0xFF ^ interrupt_bit;
+                #[cfg(feature = "debugger")]
+                self.log_interrupt_entry(interrupt_bit.trailing_zeros() as u8);
+            }
+            return 5;
+        }
+
+        0
+    }
+
+    //The highest priority interrupt that's both requested and enabled, as its bit in IF, or 0 if there isn't one
+    fn pending_interrupt(&self) -> u8 {
+        let pending = self.interrupt_enable & self.interrupt_flag & 0x1F;
+        pending & pending.wrapping_neg()
+    }
+
+    //Copies one byte per m-cycle, taking 160 m-cycles for the whole of OAM. Writing FF46 starts a transfer
+    //after a 1 m-cycle delay, during which a transfer that was already running keeps going from its old source
+    fn update_dma(&mut self) {
+        if self.is_dma_active() {
+            let lsb = self.dma_index as u8;
+            let mut source_address = u16::from_be_bytes([self.dma_source, lsb]);
+            //DMA doesn't see OAM, IO or HRAM. Everything from 0xE000 up is on the WRAM bus,
+            //so 0xE0-0xFF pages read WRAM the same way echo RAM does, including 0xFE and 0xFF
+            if source_address >= 0xE000 {
+                source_address -= 0x2000;
+            }
+            let value = self.dma_read(source_address);
+            self.ppu.dma_transfer(value, lsb);
+            self.dma_index += 1;
+        }
+
+        self.dma_start_delay = match self.dma_start_delay {
+            Some(0) => {
+                self.dma_source = self.dma;
+                self.dma_index = 0;
+                None
+            }
+            Some(delay) => Some(delay - 1),
+            None => None
+        };
+    }
+
+    //The DMA's reads aren't the CPU's, so they skip the heatmap, watchpoints and strict checks.
+    //The source is never at 0xE000 or above by now
+    fn dma_read(&mut self, address: u16) -> u8 {
+        if let Some(bus) = &mut self.bus {
+            return bus.read(address);
+        }
+
+        if let Some(byte) = self.read_boot_rom(address) {
+            byte
+        }
+        else if address < 0x8000 || (0xA000..0xC000).contains(&address) {
+            self.cartridge.read(address)
+        }
+        else if address < 0xA000 {
+            self.ppu.read(address)
+        }
+        else {
+            *self.working_ram_byte(address)
+        }
+    }
+
+    fn is_dma_active(&self) -> bool {
+        self.dma_index < 0xA0
+    }
+
+    //Writing HDMA5 starts a general transfer, which copies every block right away, or with bit 7 set an HBlank one.
+    //Writing it with bit 7 cleared during an HBlank transfer stops it instead
+    fn write_vram_dma(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF51 => self.vram_dma_source = (self.vram_dma_source & 0x00FF) | ((value as u16) << 8),
+            0xFF52 => self.vram_dma_source = (self.vram_dma_source & 0xFF00) | (value & 0xF0) as u16,
+            0xFF53 => self.vram_dma_destination = (self.vram_dma_destination & 0x00FF) | (((value & 0x1F) as u16) << 8),
+            0xFF54 => self.vram_dma_destination = (self.vram_dma_destination & 0xFF00) | (value & 0xF0) as u16,
+            _ => {
+                if self.is_hblank_dma_active && value & 0x80 == 0 {
+                    self.is_hblank_dma_active = false;
+                    return;
+                }
+                self.vram_dma_length = value & 0x7F;
+                if value & 0x80 == 0 {
+                    self.vram_dma_bytes_left = (self.vram_dma_length as u16 + 1) * 0x10;
+                }
+                else {
+                    self.is_hblank_dma_active = true;
+                }
+            }
+        }
+    }
+
+    //Called as the PPU enters HBlank on a visible line
+    fn start_hblank_dma_block(&mut self) {
+        if self.is_hblank_dma_active && self.vram_dma_bytes_left == 0 {
+            self.vram_dma_bytes_left = 0x10;
+        }
+    }
+
+    //Copies 2 bytes per M-cycle, or 1 in double speed, so a block takes the same time at either speed
+    fn step_vram_dma(&mut self) {
+        let byte_count = if self.is_double_speed {1} else {2};
+        for _ in 0..byte_count {
+            let value = match self.vram_dma_source {
+                0x8000..0xA000 => 0xFF, //VRAM can't be copied to itself
+                source => self.dma_read(source & 0xDFFF),
+            };
+            self.ppu.vram_dma_transfer(self.vram_dma_destination, value);
+            self.vram_dma_source = self.vram_dma_source.wrapping_add(1);
+            self.vram_dma_destination = (self.vram_dma_destination + 1) & 0x1FFF;
+            self.vram_dma_bytes_left -= 1;
+
+            if self.vram_dma_bytes_left % 0x10 == 0 {
+                self.vram_dma_length = self.vram_dma_length.wrapping_sub(1) & 0x7F;
+                if self.vram_dma_length == 0x7F {
+                    self.is_hblank_dma_active = false;
+                    self.vram_dma_bytes_left = 0;
+                }
+                if self.vram_dma_bytes_left == 0 {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn update_ppu(&mut self) -> bool {
+        if self.is_ppu_frozen || self.is_stopped {
+            return false;
+        }
+
+        //get the value of STAT abefore the operation
+        let stat = self.ppu.read(0xFF41);
+        let stat_before = stat & 0b1011 == 0b1000 || //STAT mode 0 is selcted and the mode is 0
+                                stat & 0b10011 == 0b10001 || //STAT mode 1 is selected and the mode is 1
+                                stat & 0b100011 == 0b100010 || //STAT mode 2 is selected and the mode is 2
+                                stat & 0b1000100 == 0b1000100; //LYC check is selected and LY == LYC
+
+        let mode_before = stat & 0b11;
+        self.ppu.update();
+
+        //Get the value of STAT after the PPU operation
+        let stat = self.ppu.read(0xFF41);
+        if mode_before == 3 && stat & 0b11 == 0 {
+            self.run_raster_hooks(false);
+            self.start_hblank_dma_block();
+        }
+        let stat_after = stat & 0b1011 == 0b1000 || //STAT mode 0 is selcted and the mode is 0
+                               stat & 0b10011 == 0b10001 || //STAT mode 1 is selected and the mode is 1
+                               stat & 0b100011 == 0b100010 || //STAT mode 2 is selected and the mode is 2
+                               stat & 0b1000100 == 0b1000100; //LYC check is selected and LY == LYC
+
+        //If in VBLANK mode, set VBLANK flag
+        let has_entered_vblank = self.ppu.has_entered_vblank();
+        if has_entered_vblank {
+            self.apply_game_shark_codes();
+            self.run_raster_hooks(true);
+            self.apu.timestamp(self.output_cycle);
+            self.interrupt_flag |= 0b1;
+            if stat & 0b100000 != 0 {
+                self.interrupt_flag |= 0b10;
+            }
+        }
+
+        //Set STAT/LCD flag if STAT condition changes from false to true
+        if !stat_before && stat_after {
+            self.interrupt_flag |= 0b10;
+        }
+        
+        has_entered_vblank
+    }
+
+    //Replaces the cheats in use. Game Genie codes go to the cartridge, GameShark codes are written at the start of each VBlank
+    pub fn set_cheats(&mut self, codes: &[CheatCode]) {
+        let mut game_genie_codes = Vec::new();
+        self.game_shark_codes.clear();
+        for code in codes {
+            match code {
+                CheatCode::GameGenie(code) => game_genie_codes.push(*code),
+                CheatCode::GameShark(code) => self.game_shark_codes.push(*code),
+            }
+        }
+        self.cartridge.set_codes(game_genie_codes);
+    }
+
+    fn apply_game_shark_codes(&mut self) {
+        for index in 0..self.game_shark_codes.len() {
+            let GameSharkCode { bank, address, value } = self.game_shark_codes[index];
+            match (bank, address) {
+                (0x80..=0x8F, _) => {
+                    let offset = (bank & 0xF) as usize * 0x2000 + (address - 0xA000) as usize;
+                    if offset < self.cartridge.ram().map_or(0, |ram| ram.len()) {
+                        self.cartridge.write_ram(offset, value);
+                    }
+                }
+                (0x90..=0x97, _) => {
+                    let bank = ((bank & 0x7) as usize).max(1);
+                    if let Some(working_ram) = self.aux_working_ram.get_mut(bank - 1) {
+                        working_ram[(address - 0xD000) as usize] = value;
+                    }
+                }
+                (_, 0xA000..=0xBFFF) => self.cartridge.write(address, value),
+                (_, 0xC000..=0xDFFF) => *self.working_ram_byte(address) = value,
+                _ => self.high_ram[(address - 0xFF80) as usize] = value,
+            }
+        }
+    }
+
+    fn run_raster_hooks(&mut self, is_vblank: bool) {
+        let Some(hooks) = self.raster_hooks.as_mut() else {
+            return;
+        };
+
+        let mut registers = self.ppu.raster_registers(self.dmg_bg_pallette, self.dmg_obj_pallette_0, self.dmg_obj_pallette_1);
+        match is_vblank {
+            true => hooks.vblank(&mut registers),
+            false => hooks.hblank(self.ppu.read(0xFF44), &mut registers),
+        }
+        self.ppu.set_raster_registers(&registers);
+        self.dmg_bg_pallette = registers.bgp;
+        self.dmg_obj_pallette_0 = registers.obp0;
+        self.dmg_obj_pallette_1 = registers.obp1;
+    }
+
+    //Returns the hooks that were set before, if any
+    pub fn set_raster_hooks(&mut self, raster_hooks: Option<Box<dyn RasterHooks>>) -> Option<Box<dyn RasterHooks>> {
+        std::mem::replace(&mut self.raster_hooks, raster_hooks)
+    }
+
+    //Returns the bus that was set before, if any. None puts the real memory map back
+    pub fn set_bus(&mut self, bus: Option<Box<dyn Bus>>) -> Option<Box<dyn Bus>> {
+        std::mem::replace(&mut self.bus, bus)
+    }
+
+    fn update_apu(&mut self) {
+        if self.is_double_speed {
+            self.is_apu_cycle_skipped = !self.is_apu_cycle_skipped;
+            if self.is_apu_cycle_skipped {
+                return;
+            }
+        }
+        //Frames run with the output off, like run-ahead ones, aren't played so they don't move the timeline
+        if self.apu.is_output_enabled {
+            self.output_cycle += 1;
+        }
+        if !self.is_apu_frozen && !self.is_stopped {
+            self.apu.update_apu();
+        }
+    }
+
+    fn check_serial(&mut self) -> Option<u8> {
+        let mut transferred_byte = None;
+
+        if self.serial_counter == 8 && self.serial_incoming.is_none() {
+            if self.serial_wait > 0 {
+                self.serial_wait -= 1;
+                return None;
+            }
+
+            //With the internal clock the other side has to answer right away.
+            //With an external clock the transfer stalls until the other side clocks it
+            self.serial_incoming = if self.serial_control & 0b1 != 0 {
+                Some(self.serial_device.transfer(self.serial_byte))
+            }
+            else {
+                self.serial_device.external_transfer(self.serial_byte)
+            };
+
+            //Nothing to shift in yet
+            self.serial_incoming?;
+            self.serial_wait = self.serial_pacing.delay_dots(self.serial_device.latency());
+            transferred_byte = Some(self.serial_byte);
+        }
+
+        if self.serial_counter > 0 {
+            self.serial_counter -= 1;
+            let incoming_bit = (self.serial_incoming.unwrap_or(0xFF) >> self.serial_counter) & 0b1;
+            self.serial_byte = (self.serial_byte << 1) | incoming_bit;
+
+            if self.serial_counter == 0 {
+                self.serial_control &= 0x7F;
+                self.interrupt_flag |= 0b1000;
+                self.serial_incoming = None;
+            }
+        }
+
+        transferred_byte
+    }
+
+    pub fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        for register in [self.a, self.b, self.c, self.d, self.e, self.h, self.l, self.flags] {
+            write_u8(w, register)?;
+        }
+        write_u16(w, self.stack_pointer)?;
+        write_u16(w, self.program_counter)?;
+
+        w.write_all(&self.working_ram)?;
+        write_u8(w, self.aux_working_ram.len() as u8)?;
+        for bank in self.aux_working_ram.iter() {
+            w.write_all(bank)?;
+        }
+        write_u8(w, self.aux_working_ram_index as u8)?;
+        w.write_all(&self.high_ram)?;
+        let (ram_init_kind, ram_init_seed) = self.ram_init.to_bytes();
+        write_u8(w, ram_init_kind)?;
+        write_u64(w, ram_init_seed)?;
+
+        write_u8(w, match self.interrupt_master_enable_flag {
+            IMEState::Disabled => 0,
+            IMEState::Enabled => 1,
+            IMEState::Pending => 2,
+        })?;
+        for register in [
+            self.interrupt_enable,
+            self.interrupt_flag,
+            self.serial_byte,
+            self.serial_control,
+            self.serial_counter,
+            self.timer_counter,
+            self.timer_modulo,
+            self.timer_control,
+            self.dmg_bg_pallette,
+            self.dmg_obj_pallette_0,
+            self.dmg_obj_pallette_1,
+            self.dma,
+            self.joypad,
+        ] {
+            write_u8(w, register)?;
+        }
+        write_option_u8(w, self.serial_incoming)?;
+        write_u32(w, self.serial_wait)?;
+        write_u16(w, self.system_counter)?;
+        write_u8(w, self.dma_source)?;
+        write_u16(w, self.dma_index)?;
+        write_option_u8(w, self.dma_start_delay)?;
+        write_u16(w, self.vram_dma_source)?;
+        write_u16(w, self.vram_dma_destination)?;
+        write_u8(w, self.vram_dma_length)?;
+        write_bool(w, self.is_hblank_dma_active)?;
+        write_u16(w, self.vram_dma_bytes_left)?;
+        write_bool(w, self.timer_overflowed)?;
+        write_bool(w, self.timer_overflowed_after)?;
+        write_bool(w, self.is_halted)?;
+        write_bool(w, self.is_stopped)?;
+        write_bool(w, self.is_double_speed)?;
+        write_bool(w, self.is_speed_switch_armed)?;
+        write_bool(w, self.is_apu_cycle_skipped)?;
+        write_u8(w, self.joypad_state.to_byte())?;
+        write_u16(w, self.boot_rom.as_ref().map_or(0, |boot_rom| boot_rom.len() as u16))?;
+        if let Some(boot_rom) = &self.boot_rom {
+            w.write_all(boot_rom)?;
+        }
+
+        self.cartridge.save_state(w)?;
+        self.ppu.save_state(w)?;
+        self.apu.save_state(w)?;
+        //Last, where savestates kept it before the console owned it
+        write_u8(w, self.cpu_delay)?;
+        write_u8(w, self.hardware_ahead.len() as u8)?;
+        for events in &self.hardware_ahead {
+            write_bool(w, events.is_new_frame)?;
+            write_option_u8(w, events.serial_byte)?;
+        }
+        Ok(())
+    }
+
+    pub fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        for register in [&mut self.a, &mut self.b, &mut self.c, &mut self.d, &mut self.e, &mut self.h, &mut self.l, &mut self.flags] {
+            *register = read_u8(r)?;
+        }
+        self.stack_pointer = read_u16(r)?;
+        self.program_counter = read_u16(r)?;
+
+        r.read_exact(&mut self.working_ram)?;
+        let aux_working_ram_banks = read_u8(r)?;
+        self.aux_working_ram.clear();
+        for _ in 0..aux_working_ram_banks {
+            let mut bank = [0; 0x1000];
+            r.read_exact(&mut bank)?;
+            self.aux_working_ram.push(bank);
+        }
+        self.aux_working_ram_index = read_u8(r)? as usize;
+        if self.aux_working_ram_index >= self.aux_working_ram.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Selected WRAM bank doesn't exist"));
+        }
+        r.read_exact(&mut self.high_ram)?;
+        let ram_init_kind = read_u8(r)?;
+        self.ram_init = RamInit::from_bytes(ram_init_kind, read_u64(r)?);
+
+        self.interrupt_master_enable_flag = match read_u8(r)? {
+            1 => IMEState::Enabled,
+            2 => IMEState::Pending,
+            _ => IMEState::Disabled,
+        };
+        for register in [
+            &mut self.interrupt_enable,
+            &mut self.interrupt_flag,
+            &mut self.serial_byte,
+            &mut self.serial_control,
+            &mut self.serial_counter,
+            &mut self.timer_counter,
+            &mut self.timer_modulo,
+            &mut self.timer_control,
+            &mut self.dmg_bg_pallette,
+            &mut self.dmg_obj_pallette_0,
+            &mut self.dmg_obj_pallette_1,
+            &mut self.dma,
+            &mut self.joypad,
+        ] {
+            *register = read_u8(r)?;
+        }
+        self.serial_incoming = read_option_u8(r)?;
+        self.serial_wait = read_u32(r)?;
+        self.system_counter = read_u16(r)?;
+        self.dma_source = read_u8(r)?;
+        self.dma_index = read_u16(r)?;
+        self.dma_start_delay = read_option_u8(r)?;
+        self.vram_dma_source = read_u16(r)?;
+        self.vram_dma_destination = read_u16(r)?;
+        self.vram_dma_length = read_u8(r)?;
+        self.is_hblank_dma_active = read_bool(r)?;
+        self.vram_dma_bytes_left = read_u16(r)?;
+        self.timer_overflowed = read_bool(r)?;
+        self.timer_overflowed_after = read_bool(r)?;
+        self.is_halted = read_bool(r)?;
+        self.is_stopped = read_bool(r)?;
+        self.is_double_speed = read_bool(r)?;
+        self.is_speed_switch_armed = read_bool(r)?;
+        self.is_apu_cycle_skipped = read_bool(r)?;
+        self.joypad_state = JoypadState::from_byte(read_u8(r)?);
+        let boot_rom_len = read_u16(r)?;
+        self.boot_rom = if boot_rom_len > 0 {
+            let mut boot_rom = vec![0; boot_rom_len as usize];
+            r.read_exact(&mut boot_rom)?;
+            Some(boot_rom)
+        }
+        else {
+            None
+        };
+
+        self.cartridge.load_state(r)?;
+        self.ppu.load_state(r)?;
+        self.apu.load_state(r)?;
+        self.cpu_delay = read_u8(r)?;
+        self.hardware_ahead.clear();
+        for _ in 0..read_u8(r)? {
+            self.hardware_ahead.push_back(CycleEvents { is_new_frame: read_bool(r)?, serial_byte: read_option_u8(r)? });
+        }
+        Ok(())
+    }
+
+    //Returns the previously connected device
+    pub fn set_serial_device(&mut self, serial_device: Box<dyn SerialDevice>) -> Box<dyn SerialDevice> {
+        std::mem::replace(&mut self.serial_device, serial_device)
+    }
+
+    pub fn set_serial_pacing(&mut self, serial_pacing: SerialPacing) {
+        self.serial_pacing = serial_pacing;
+        self.serial_wait = 0;
+    }
+
+    pub fn hardware_mode(&self) -> HardwareMode {
+        self.hardware_mode
+    }
+
+    pub fn is_double_speed(&self) -> bool {
+        self.is_double_speed
+    }
+
+    //PPU dots in each M-cycle. The PPU keeps its speed in double speed, so it gets half as many
+    pub fn dots_per_cycle(&self) -> u8 {
+        if self.is_double_speed {2} else {4}
+    }
+
+    pub fn set_audio_output(&mut self, is_enabled: bool) {
+        self.apu.is_output_enabled = is_enabled;
+    }
+
+    pub fn is_audio_output_enabled(&self) -> bool {
+        self.apu.is_output_enabled
+    }
+
+    //The boot ROM clears VRAM, so randomized VRAM is only visible to games when starting without one
+    pub fn initialize_ram(&mut self, ram_init: RamInit) {
+        self.ram_init = ram_init;
+
+        let mut regions: Vec<&mut [u8]> = vec![&mut self.working_ram];
+        for bank in self.aux_working_ram.iter_mut() {
+            regions.push(bank);
+        }
+        regions.extend(self.ppu.video_ram_mut());
+        ram_init.fill(&mut regions);
+    }
+
+    //Maps a boot ROM and resets the CPU so it runs from 0x0000. Other hardware keeps its post-boot state
+    pub fn set_boot_rom(&mut self, boot_rom: Vec<u8>) {
+        for register in [&mut self.a, &mut self.b, &mut self.c, &mut self.d, &mut self.e, &mut self.h, &mut self.l, &mut self.flags] {
+            *register = 0;
+        }
+        self.stack_pointer = 0;
+        self.program_counter = 0;
+        self.boot_rom = Some(boot_rom);
+    }
+
+    //DMG boot ROMs are 0x100 bytes. CGB boot ROMs also cover 0x200-0x8FF, leaving the cartridge header visible
+    fn read_boot_rom(&self, address: u16) -> Option<u8> {
+        let boot_rom = self.boot_rom.as_ref()?;
+        if address < 0x100 || (0x200..0x900).contains(&address) {
+            boot_rom.get(address as usize).copied()
+        }
+        else {
+            None
+        }
+    }
+
+    //Checks the registers the boot ROM handed over against the values used when starting without one.
+    //The program counter is still on the instruction that wrote FF50 so it's checked against 0x00FE
+    fn validate_post_boot_state(&self) -> Result<(), String> {
+        let registers = [self.a, self.b, self.c, self.d, self.e, self.h, self.l, self.flags];
+        let expected = self.post_boot_registers;
+        let names = ["A", "B", "C", "D", "E", "H", "L", "F"];
+        let mut mismatches = Vec::new();
+        for i in 0..8 {
+            if registers[i] != expected[i] {
+                mismatches.push(format!("{} = ${:02X}, expected ${:02X}", names[i], registers[i], expected[i]));
+            }
+        }
+        if self.stack_pointer != POST_BOOT_STACK_POINTER {
+            mismatches.push(format!("SP = ${:04X}, expected ${:04X}", self.stack_pointer, POST_BOOT_STACK_POINTER));
+        }
+        if self.program_counter != POST_BOOT_PROGRAM_COUNTER - 2 {
+            mismatches.push(format!("unmapped at ${:04X}, expected ${:04X}", self.program_counter, POST_BOOT_PROGRAM_COUNTER - 2));
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(format!("Boot ROM left {}", mismatches.join(", ")))
+        }
+    }
+
+    //Returns how the boot ROM's hand-off differed from the post-boot state, if it was unmapped since the last call
+    pub fn take_boot_error(&mut self) -> Option<String> {
+        self.boot_error.take()
+    }
+
+    fn report_unknown_io(&mut self, address: u16, value: Option<u8>) {
+        if !self.is_untracked && (self.break_on_unknown_io || self.is_strict) && self.io_break.is_none() {
+            self.io_break = Some(IOBreak { address, value, program_counter: self.program_counter, kind: BreakKind::UnknownRegister });
+        }
+    }
+
+    //Catches the accesses the emulator otherwise lets slide quietly, so they can be looked into
+    fn check_strict(&mut self, address: u16, value: Option<u8>) {
+        let ppu_mode = self.ppu.get_mode();
+        let is_ppu_on_oam = ppu_mode == 2 || ppu_mode == 3;
+        let violation = match (self.blocked_access(address), address) {
+            (Some(violation), _) => violation,
+            (None, 0xE000..0xFE00) => StrictViolation::EchoRam,
+            (None, 0xFEA0..0xFF00) => StrictViolation::ProhibitedArea,
+            (None, 0xFF46) if value.is_some() && is_ppu_on_oam => StrictViolation::DmaDuringScan,
+            _ => return,
+        };
+        if self.io_break.is_none() {
+            let ly = self.ppu.read(0xFF44);
+            let kind = BreakKind::Strict { violation, ppu_mode, ly };
+            self.io_break = Some(IOBreak { address, value, program_counter: self.program_counter, kind });
+        }
+    }
+
+    //Why the PPU or DMA has VRAM or OAM locked at this address, if it does. Reads see 0xFF and writes are dropped,
+    //but the instruction's timing doesn't change
+    fn blocked_access(&self, address: u16) -> Option<StrictViolation> {
+        let ppu_mode = self.ppu.get_mode();
+        match address {
+            0x8000..0xA000 if ppu_mode == 3 => Some(StrictViolation::VramDuringDraw),
+            0xFE00..0xFEA0 if self.is_dma_active() => Some(StrictViolation::OamDuringDma),
+            0xFE00..0xFEA0 if ppu_mode == 2 || ppu_mode == 3 => Some(StrictViolation::OamDuringScan),
+            _ => None,
+        }
+    }
+
+    //Value is only given for writes
+    #[cfg(feature = "debugger")]
+    fn track_access(&mut self, address: u16, value: Option<u8>) {
+        if let Some(access_counters) = &mut self.access_counters {
+            match value {
+                Some(_) => access_counters.count_write(address),
+                None => access_counters.count_read(address),
+            }
+        }
+        if self.blocked_accesses.is_some() {
+            if let Some(reason) = self.blocked_access(address) {
+                let ly = self.ppu.read(0xFF44);
+                let blocked_access = BlockedAccess { program_counter: self.program_counter, address, value, reason, ly };
+                self.blocked_accesses.as_mut().unwrap().push(blocked_access);
+            }
+        }
+        if let Some(value) = value {
+            if self.write_hooks.iter().any(|(start, end)| (*start..=*end).contains(&address)) {
+                self.hooked_writes.push((address, value));
+            }
+        }
+        if self.watchpoints.is_empty() {
+            return;
+        }
+
+        let hits = self.watchpoints.iter().filter(|watchpoint| watchpoint.is_hit(address, value.is_some()));
+        let (is_logged, is_break) = hits.fold((false, false), |(is_logged, is_break), watchpoint| (is_logged || watchpoint.is_logged, is_break || !watchpoint.is_logged));
+        if is_logged {
+            let old_value = self.untracked(|console| console.read(address));
+            self.watch_log.push(WatchLogEntry { program_counter: self.instruction_address, address, old_value, new_value: value });
+        }
+        if is_break && self.io_break.is_none() {
+            self.io_break = Some(IOBreak { address, value, program_counter: self.program_counter, kind: BreakKind::Watchpoint });
+        }
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn take_watch_log(&mut self) -> Vec<WatchLogEntry> {
+        std::mem::take(&mut self.watch_log)
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn set_write_hooks(&mut self, write_hooks: Vec<(u16, u16)>) {
+        self.write_hooks = write_hooks;
+        self.hooked_writes.clear();
+    }
+
+    //CPU writes to the hooked ranges since the last call, in order
+    #[cfg(feature = "debugger")]
+    pub fn take_hooked_writes(&mut self) -> Vec<(u16, u8)> {
+        std::mem::take(&mut self.hooked_writes)
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn set_blocked_access_logging(&mut self, is_enabled: bool) {
+        self.blocked_accesses = if is_enabled {Some(Vec::new())} else {None};
+    }
+
+    //Blocked VRAM and OAM accesses since the last call
+    #[cfg(feature = "debugger")]
+    pub fn take_blocked_accesses(&mut self) -> Vec<BlockedAccess> {
+        self.blocked_accesses.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn set_watchpoints(&mut self, watchpoints: Vec<Watchpoint>) {
+        self.watchpoints = watchpoints;
+    }
+
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    //Returns the unknown IO access, watchpoint hit or strict violation that should pause emulation, if one happened since the last call
+    pub fn take_io_break(&mut self) -> Option<IOBreak> {
+        self.io_break.take()
+    }
+
+    pub fn cpu_registers(&self) -> CPURegisters {
+        CPURegisters {
+            a: self.a,
+            f: self.flags,
+            b: self.b,
+            c: self.c,
+            d: self.d,
+            e: self.e,
+            h: self.h,
+            l: self.l,
+            stack_pointer: self.stack_pointer,
+            program_counter: self.program_counter,
+            ime: self.interrupt_master_enable_flag == IMEState::Enabled,
+            is_halted: self.is_halted,
+            is_stopped: self.is_stopped,
+        }
+    }
+
+    //Puts the CPU in the given state between two instructions. An instruction that was partway through is dropped
+    pub fn set_cpu_registers(&mut self, registers: &CPURegisters) {
+        self.a = registers.a;
+        self.flags = registers.f & 0xF0;
+        self.b = registers.b;
+        self.c = registers.c;
+        self.d = registers.d;
+        self.e = registers.e;
+        self.h = registers.h;
+        self.l = registers.l;
+        self.stack_pointer = registers.stack_pointer;
+        self.program_counter = registers.program_counter;
+        self.interrupt_master_enable_flag = if registers.ime {IMEState::Enabled} else {IMEState::Disabled};
+        self.is_halted = registers.is_halted;
+        self.is_stopped = registers.is_stopped;
+        self.cpu_delay = 255;
+    }
+
+    pub fn set_joypad_state(&mut self, joypad_state: JoypadState) {
+        self.joypad_state = joypad_state;
+        self.set_buttons();
+    }
+
+    //For cartridges with an accelerometer. In g, with x positive to the right and y positive towards the player
+    pub fn set_tilt(&mut self, x: f32, y: f32) {
+        self.cartridge.set_tilt(x, y);
+    }
+
+    //For the Pocket Camera. CAMERA_WIDTH x CAMERA_HEIGHT bytes of brightness, 0 being black. Empty goes back to the
+    //test pattern. Other cartridges ignore it
+    pub fn set_camera_image(&mut self, image: &[u8]) {
+        self.cartridge.set_camera_image(image);
+    }
+
+    pub fn set_apu_event_logging(&mut self, is_enabled: bool) {
+        self.apu.set_event_logging(is_enabled);
+    }
+
+    pub fn take_apu_events(&mut self) -> (Vec<ApuEvent>, u32) {
+        self.apu.take_events()
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn set_access_counting(&mut self, is_enabled: bool) {
+        self.access_counters = if is_enabled {Some(Box::default())} else {None};
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn access_counters(&mut self) -> Option<&mut AccessCounters> {
+        self.access_counters.as_deref_mut()
+    }
+
+    //Swaps in other counters and returns the ones that were counting, so frames that shouldn't show up in the
+    //heatmap can be run with None
+    #[cfg(feature = "debugger")]
+    pub fn set_access_counters(&mut self, access_counters: Option<Box<AccessCounters>>) -> Option<Box<AccessCounters>> {
+        std::mem::replace(&mut self.access_counters, access_counters)
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn set_interrupt_logging(&mut self, is_enabled: bool) {
+        self.interrupt_events = if is_enabled {Some(Vec::new())} else {None};
+        self.interrupt_handlers.clear();
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn take_interrupt_events(&mut self) -> Vec<InterruptEvent> {
+        self.interrupt_events.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    #[cfg(feature = "debugger")]
+    fn log_interrupt_entry(&mut self, source: u8) {
+        if let Some(events) = &mut self.interrupt_events {
+            events.push(InterruptEvent { line: self.ppu.read(0xFF44), source, is_entry: true });
+            self.interrupt_handlers.push((source, self.stack_pointer));
+        }
+    }
+
+    //A handler is over once the stack pointer is back above the return address pushed for it, usually after a RETI
+    #[cfg(feature = "debugger")]
+    fn log_interrupt_returns(&mut self) {
+        let Some(events) = &mut self.interrupt_events else {
+            return;
+        };
+        while let Some(&(source, stack_pointer)) = self.interrupt_handlers.last() {
+            if self.stack_pointer <= stack_pointer {
+                break;
+            }
+            self.interrupt_handlers.pop();
+            events.push(InterruptEvent { line: self.ppu.read(0xFF44), source, is_entry: false });
+        }
+    }
+
+    pub fn fade_out_audio(&mut self) {
+        self.apu.fade_out();
+    }
+
+    pub fn smooth_audio_jump(&mut self) {
+        self.apu.smooth_jump();
+    }
+
+    pub fn set_volume_normalization(&mut self, is_enabled: bool) {
+        self.apu.set_volume_normalization(is_enabled);
+    }
+
+    pub fn set_playback_settings(&mut self, settings: PlaybackSettings) {
+        self.apu.set_playback_settings(settings);
+    }
+
+    //For fast-forwarding. Only changes how the audio is resampled, running the extra frames is up to the caller
+    pub fn set_audio_speed(&mut self, speed: f32) {
+        self.apu.set_speed(speed);
+    }
+
+    //For catching up when running behind. The CPU, timers and PPU timing all still run, the screen just isn't updated
+    pub fn set_frame_skip(&mut self, is_skipped: bool) {
+        self.ppu.set_frame_skip(is_skipped);
+    }
+
+    //Pulse 1, pulse 2, wave and noise. Disabled channels are left out of the mix
+    pub fn set_enabled_audio_channels(&mut self, enabled_channels: [bool; 4]) {
+        self.apu.enabled_channels = enabled_channels;
+    }
+
+    pub fn set_audio_sample_tap(&mut self, is_enabled: bool) {
+        self.apu.set_sample_tap(is_enabled);
+    }
+
+    pub fn take_audio_samples(&mut self) -> Vec<f32> {
+        self.apu.take_samples()
+    }
+
+    pub fn take_audio_device_change(&mut self) -> Option<apu::AudioDeviceChange> {
+        self.apu.take_device_change()
+    }
+
+    pub fn audio_sample_rate(&self) -> f32 {
+        self.apu.output_sample_rate()
+    }
+
+    //How much audio the output device still has to play. None when nothing is being played in real time
+    pub fn queued_audio(&self) -> Option<Duration> {
+        self.apu.queued()
+    }
+
+    //Where the console is on the timeline frames and audio are stamped with
+    pub fn output_cycle(&self) -> u64 {
+        self.output_cycle
+    }
+
+    pub fn last_played_audio(&self) -> Option<apu::AudioTimestamp> {
+        self.apu.last_played()
+    }
+
+    fn update_timer(&mut self) {
+        //A cartridge clock has its own crystal, so it keeps going through STOP
+        self.cartridge.tick();
+        if self.is_stopped {
+            return;
+        }
+
+        if self.timer_overflowed_after {
+            self.timer_overflowed_after = false;
+        }
+        //timer counter is reset and interrupt is requested on the m-cycle after overflow
+        if self.timer_overflowed {
+            self.timer_counter = self.timer_modulo;
+            self.interrupt_flag |= 0b100;
+            self.timer_overflowed = false;
+            self.timer_overflowed_after = true;
+        }
+
+        let system_counter_before = self.system_counter;
+        self.system_counter = self.system_counter.wrapping_add(1);
+
+        self.timer_tick(system_counter_before, self.timer_control);
+    }
+
+    fn timer_tick(&mut self, system_counter_before: u16, timer_control_before: u8) {
+        const TAC_FREQUENCIES: [u16; 4] = [0x80, 0x02, 0x08, 0x20];
+        let bit_to_check_before = TAC_FREQUENCIES[(timer_control_before & 0b11) as usize];
+        let bit_to_check_after = TAC_FREQUENCIES[(self.timer_control & 0b11) as usize];
+
+        //TIMA is clocked by DIV, so it speeds up along with the CPU in double speed
+        let state_before = (system_counter_before & bit_to_check_before != 0)
+                                 && (timer_control_before & 0b100 != 0);
+        let state_after = (self.system_counter & bit_to_check_after != 0)
+                                && (self.timer_control & 0b100 != 0);
+        let will_update = state_before && !state_after;
+
+        if will_update {
+            self.timer_counter = self.timer_counter.wrapping_add(1);
+
+            if self.timer_counter == 0 {
+                self.timer_overflowed = true;
+            }
+        }
+
+        //DIV runs twice as fast in double speed, so the APU is clocked by the next bit up to keep it at 512 Hz
+        let apu_bit = if self.is_double_speed {0x800} else {0x400};
+        let will_update_apu = (system_counter_before & apu_bit != 0) && (self.system_counter & apu_bit == 0);
+
+        if will_update_apu && !self.is_apu_frozen {
+            self.apu.update_apu_timer();
+        }
+    }
+    
+    pub fn dump_screen(&mut self) -> [[Pixel; 160]; 144] {
+        self.ppu.dump_screen()
+    }
+
+    //A frame from dump_screen in the format an embedder draws with, colored by the current DMG palettes
+    pub fn export_frame(&self, screen: &[[Pixel; 160]; 144], format: &FrameFormat) -> Vec<u8> {
+        frame_export::export(screen, [self.dmg_bg_pallette, self.dmg_obj_pallette_0, self.dmg_obj_pallette_1], format)
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn ppu_snapshot(&self) -> PPUSnapshot {
+        self.ppu.snapshot()
+    }
+
+    //Returns the contents of 0xC000-0xDFFF with the currently selected WRAM bank mapped in
+    pub fn working_ram(&self) -> Vec<u8> {
+        let mut working_ram = Vec::with_capacity(0x2000);
+        working_ram.extend_from_slice(&self.working_ram);
+        working_ram.extend_from_slice(&self.aux_working_ram[self.aux_working_ram_index]);
+        working_ram
+    }
+
+    //0xFEA0-0xFEFF reads 0xFF while OAM is locked. Otherwise a DMG reads 0x00, and a CGB (revision E, like the AGB)
+    //reads the high nibble of the address's low byte twice, so 0xFEB3 reads 0xBB. The DMG's OAM corruption on these
+    //reads isn't emulated, and neither is the masked RAM earlier CGB revisions have here
+    fn read_prohibited_area(&self, address: u16) -> u8 {
+        let ppu_mode = self.ppu.get_mode();
+        if !self.is_prohibited_area_accurate {
+            return if ppu_mode == 2 || ppu_mode == 3 {0xFF} else {0x00};
+        }
+
+        if self.is_dma_active() || ppu_mode == 2 || ppu_mode == 3 {
+            0xFF
+        }
+        else {
+            match self.hardware_mode {
+                HardwareMode::Dmg => 0x00,
+                HardwareMode::Cgb => {
+                    let nibble = (address as u8) >> 4;
+                    nibble << 4 | nibble
+                }
+            }
+        }
+    }
+
+    //0xC000-0xDFFF and its echo at 0xE000-0xFDFF. The upper 4KB is whichever bank SVBK selects
+    fn working_ram_byte(&mut self, address: u16) -> &mut u8 {
+        let offset = (address & 0x0FFF) as usize;
+        if address & 0x1000 == 0 {
+            &mut self.working_ram[offset]
+        }
+        else {
+            &mut self.aux_working_ram[self.aux_working_ram_index][offset]
+        }
+    }
+
+    //WRAM bank 0 followed by every switchable bank, whichever one is selected
+    pub fn working_ram_banks(&self) -> Vec<&[u8]> {
+        let mut banks: Vec<&[u8]> = vec![&self.working_ram];
+        banks.extend(self.aux_working_ram.iter().map(|bank| &bank[..]));
+        banks
+    }
+
+    pub fn high_ram(&self) -> &[u8] {
+        &self.high_ram
+    }
+
+    pub fn mapper_registers(&self) -> Vec<(&'static str, u8)> {
+        self.cartridge.registers()
+    }
+
+    pub fn cartridge_ram(&self) -> Option<&[u8]> {
+        self.cartridge.ram()
+    }
+
+    pub fn is_cartridge_ram_enabled(&self) -> bool {
+        self.cartridge.is_ram_enabled()
+    }
+
+    pub fn write_cartridge_ram(&mut self, offset: usize, value: u8) {
+        self.cartridge.write_ram(offset, value);
+    }
+
+    //Updates the button lines of P1 from the selected rows. It runs whenever the buttons or the selection change, so
+    //a press raises the joypad interrupt right away, even while the game is halted and not reading P1
+    fn set_buttons(&mut self) {
+        let joypad_before = self.joypad;
+
+        self.joypad |= 0xF;
+
+        let buttons = self.joypad_state;
+        if (self.joypad & 0b100000 == 0 && buttons.start) || (self.joypad & 0b10000 == 0 && buttons.down) {
+            self.joypad ^= 0b1000;
+        }
+        if (self.joypad & 0b100000 == 0 && buttons.select) || (self.joypad & 0b10000 == 0 && buttons.up) {
+            self.joypad ^= 0b100;
+        }
+        if (self.joypad & 0b100000 == 0 && buttons.b) || (self.joypad & 0b10000 == 0 && buttons.left) {
+            self.joypad ^= 0b10;
+        }
+        if (self.joypad & 0b100000 == 0 && buttons.a) || (self.joypad & 0b10000 == 0 && buttons.right) {
+            self.joypad ^= 0b1;
+        }
+
+        if (joypad_before & !self.joypad) & 0xF != 0 {
+            self.interrupt_flag |= 0b10000;
+            self.is_stopped = false;
+        }
+    }
+
+    pub fn execute_instruction(&mut self) -> u8 {
+        let mut instruction_size = 1;
+        let mut cycle_count = 4;
+
+        let opcode = self.read(self.program_counter);
+
+        match opcode {
+            //Block 0 one-offs
+            0o000 => {}, //NOP
+            0o010 => { //LD [n16], SP
+                cycle_count = 20;
+                instruction_size = 3;
+                let address = self.read_16_at(2, self.program_counter.wrapping_add(1));
+                let [msb, lsb] = self.stack_pointer.to_be_bytes();
+                self.write_at(4, address, lsb);
+                self.write_at(5, address.wrapping_add(1), msb);
+            }
+            0o020 => { //STOP
+                //On CGB, STOP with KEY1 armed switches between normal and double speed instead of stopping.
+                //Otherwise everything but the cartridge stops until a selected button is pressed. With one already
+                //held there's no press to wait for, so the CPU carries on. Either way DIV is reset
+                if self.is_speed_switch_armed {
+                    self.is_double_speed = !self.is_double_speed;
+                    self.is_speed_switch_armed = false;
+                    self.is_apu_cycle_skipped = false;
+                }
+                else if self.joypad & 0xF == 0xF {
+                    self.is_stopped = true;
+                }
+                let system_counter_before = self.system_counter;
+                self.system_counter = 0;
+                self.timer_tick(system_counter_before, self.timer_control);
+            }
+            0o007 => { //RLCA
+                self.flag_toggle(false, Z_ZERO_FLAG | N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                self.flag_toggle(self.a & 0x80 > 0, C_CARRY_FLAG);
+
+                self.a <<= 1;
+                if self.flags & C_CARRY_FLAG > 0 {
+                    self.a += 0x01;
+                }
+            }
+            0o017 => { //RRCA
+                self.flag_toggle(false, Z_ZERO_FLAG | N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                self.flag_toggle(self.a & 0x01 > 0, C_CARRY_FLAG);
+
+                self.a >>= 1;
+                if self.flags & C_CARRY_FLAG > 0 {
+                    self.a += 0x80;
+                }
+            }
+            0o027 => { //RLA
+                self.flag_toggle(false, Z_ZERO_FLAG | N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                let will_carry = self.a & 0x80 > 0;
+
+                self.a <<= 1;
+                if self.flags & C_CARRY_FLAG > 0 {
+                    self.a += 0x01;
+                }
+
+                self.flag_toggle(will_carry, C_CARRY_FLAG);
+            }
+            0o037 => { //RRA
+                self.flag_toggle(false, Z_ZERO_FLAG | N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                let will_carry = self.a & 0x01 > 0;
+
+                self.a >>= 1;
+                if self.flags & C_CARRY_FLAG > 0 {
+                    self.a += 0x80;
+                }
+
+                self.flag_toggle(will_carry, C_CARRY_FLAG);
+            }
+            0o047 => { //DAA
+                (self.a, self.flags) = alu::daa(self.a, self.flags);
+            }
+            0o057 => { //CPL
+                self.a = self.a ^ 0xFF;
+                self.flag_toggle(true, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+            }
+            0o067 => { //SCF
+                self.flag_toggle(true, C_CARRY_FLAG);
+                self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+            } 
+            0o077 => { //CCF
+                self.flag_toggle(self.flags & C_CARRY_FLAG == 0, C_CARRY_FLAG);
+                self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+            }
+
+            //Block 1 one-offs
+            0o166 => {
+                //TODO: Implement HALT instruction
+                if self.interrupt_master_enable_flag == IMEState::Enabled {
+                    self.is_halted = true;
+                }
+                else if self.interrupt_flag & self.interrupt_enable == 0 {
+                    self.is_halted = true;
+                }
+            }
+
+            //Block 3 one-offs
+            0o303 => { //JP
+                instruction_size = 0;
+                cycle_count = 16;
+                self.program_counter = self.read_16_at(2, self.program_counter.wrapping_add(1));
+            }
+            0o311 => { //RET
+                cycle_count = 16;
+                instruction_size = 0;
+                self.program_counter = self.pop_at(2);
+            }
+            0o313 => { //PREFIX
+                instruction_size = 2;
+                cycle_count = self.execute_prefixed_instruction();
+            }
+            0o315 => { //CALL
+                cycle_count = 6;
+                instruction_size = 0;
+                let address = self.read_16_at(2, self.program_counter.wrapping_add(1));
+                self.push_at(5, self.program_counter.wrapping_add(3));
+                self.program_counter = address;
+            }
+            0o331 => { //RETI
+                cycle_count = 16;
+                instruction_size = 0;
+                self.program_counter = self.pop_at(2);
+                self.interrupt_master_enable_flag = IMEState::Enabled;
+            }
+            0o340 => { //LDH [a8], A
+                cycle_count = 12;
+                instruction_size = 2;
+                let address = u16::from_be_bytes([0xFF, self.read_at(2, self.program_counter.wrapping_add(1))]);
+                
+                self.write_at(3, address, self.a);
+            }
+            0o350 => { //ADD SP, e8
+                instruction_size = 2;
+                cycle_count = 16;
+
+                let offset = self.read_at(2, self.program_counter.wrapping_add(1));
+                (self.stack_pointer, self.flags) = alu::add_sp_e8(self.stack_pointer, offset);
+            }
+            0o351 => { //JP HL
+                instruction_size = 0;
+                self.program_counter = u16::from_be_bytes([self.h, self.l]);
+            }
+            0o360 => { //LDH A, [a8]
+                instruction_size = 2;
+                cycle_count = 12;
+                let address = u16::from_be_bytes([0xFF, self.read_at(2, self.program_counter.wrapping_add(1))]);
+
+                self.a = self.read_at(3, address);
+            }
+            0o363 => { //DI
+                self.interrupt_master_enable_flag = IMEState::Disabled;
+            }
+            0o370 => { //LD HL, SP + e8
+                instruction_size = 2;
+                cycle_count = 12;
+
+                let offset = self.read_at(2, self.program_counter.wrapping_add(1));
+                let new_pointer;
+                (new_pointer, self.flags) = alu::add_sp_e8(self.stack_pointer, offset);
+                (self.h, self.l) = new_pointer.to_be_bytes().into();
+            }
+            0o371 => { //LD SP, HL
+                cycle_count = 8;
+                self.stack_pointer = u16::from_be_bytes([self.h,self.l]);
+            }
+            0o373 => { //EI
+                //A second EI doesn't push the delay back
+                if self.interrupt_master_enable_flag == IMEState::Disabled {
+                    self.interrupt_master_enable_flag = IMEState::Pending;
+                }
+            }
+
+            //Invalid opcodes
+            0o323 | 0o333 | 0o335 | 0o343 | 0o344 | 0o353 | 0o354 | 0o355 | 0o364 | 0o374 | 0o375 => panic!("ERROR: Invalid opcode!"),
+
+            //The rest of the instructions are interpreted through pattern-matching. The above are instructions which break those patterns.
+            _ => {
+                let block = opcode & 0o300;
+                match block {
+                    0o000 => { //Block 0
+                        match opcode & 0o007 {
+                            0o000 => { //JR e8, JR cc, e8
+                                let jump_condition = match opcode & 0o070 {
+                                    0o030 => true,
+                                    0o040 => self.flags & Z_ZERO_FLAG == 0,
+                                    0o050 => self.flags & Z_ZERO_FLAG > 0,
+                                    0o060 => self.flags & C_CARRY_FLAG == 0,
+                                    0o070 => self.flags & C_CARRY_FLAG > 0,
+                                    _ => panic!("ERROR: Condition opcode out of bounds!")
+                                };
+
+                                if jump_condition {
+                                    cycle_count = 12;
+                                    let jump_offset_u8 = self.read_at(2, self.program_counter.wrapping_add(1));
+                                    if jump_offset_u8 >= 0x80 {
+                                        instruction_size = u16::from_be_bytes([0xFF, jump_offset_u8]);
+                                    }
+                                    else {
+                                        instruction_size = u16::from_be_bytes([0, jump_offset_u8]);
+                                    }
+                                    instruction_size = instruction_size.wrapping_add(2);
+                                }
+                                else {
+                                    instruction_size = 2;
+                                    cycle_count = 8;
+                                }
+                            }
+                            0o001 => { //LD r16, n16 | LD SP, n16 | ADD HL, r16 | ADD HL, SP
+                                let is_add = opcode & 0o010 > 0;
+                                let mut is_sp = false;
+                            
+                                let value;
+                                if !is_add {
+                                    value = self.read_16_at(2, self.program_counter.wrapping_add(1));
+                                }
+                                else {
+                                    value = u16::from_be_bytes([self.h, self.l]);
+                                }
+                            
+                                let (register_high, register_low) = match opcode & 0o060 {
+                                    0o000 => (&mut self.b, &mut self.c),
+                                    0o020 => (&mut self.d, &mut self.e),
+                                    0o040 => (&mut self.h, &mut self.l),
+                                    0o060 => {
+                                        is_sp = true;
+                                        (&mut self.b, &mut self.c) //<== Throwaway value
+                                    }
+                                    _ => panic!("ERROR: Register octet out of bounds!")
+                                };
+                            
+                                if !is_add {
+                                    cycle_count = 12;
+                                    instruction_size = 3;
+                                    if !is_sp {
+                                        (*register_high, *register_low) = value.to_be_bytes().into();
+                                    }
+                                    else {
+                                        self.stack_pointer = value;
+                                    }
+                                }
+                                else {
+                                    cycle_count = 8;
+                                    let register_value = if !is_sp {u16::from_be_bytes([*register_high, *register_low])} else {self.stack_pointer};
+                                    let hl;
+                                    (hl, self.flags) = alu::add16(value, register_value, self.flags);
+                                    (self.h, self.l) = hl.to_be_bytes().into();
+                                }
+                            }
+                            0o002 => {
+                                //LD [R16], a | LD a, [R16] | LD [HL+], a | ld a, [HL+] | ld [HL-], a | LD a, [HL-]
+                                cycle_count = 8;
+                                let address = match opcode & 0o060 {
+                                    0o000 => u16::from_be_bytes([self.b, self.c]),
+                                    0o020 => u16::from_be_bytes([self.d, self.e]),
+                                    0o040 => {
+                                        let address_temp = u16::from_be_bytes([self.h, self.l]);
+                                        (self.h, self.l) = address_temp.wrapping_add(1).to_be_bytes().into();
+                                        address_temp
+                                    }
+                                    0o060 => {
+                                        let address_temp = u16::from_be_bytes([self.h, self.l]);
+                                        (self.h, self.l) = address_temp.wrapping_sub(1).to_be_bytes().into();
+                                        address_temp
+                                    }
+                                    _ => panic!("ERROR: address octet out of bounds!")
+                                };
+                            
+                                if opcode & 0o010 > 0 {
+                                    self.a = self.read_at(2, address);
+                                }
+                                else {
+                                    self.write_at(2, address, self.a);
+                                }
+                            }
+                            0o003 => { //INC r16, INC SP, DEC r16, DEC SP
+                                cycle_count = 8;
+                                let incrementor = if opcode & 0o010 == 0 {1} else {u16::MAX}; //Adding u16::MAX wraps around to a decrement
+                                let mut is_sp = false;
+                                let (register_high, register_low) = match opcode & 0o060 {
+                                    0o000 => (&mut self.b, &mut self.c),
+                                    0o020 => (&mut self.d, &mut self.e),
+                                    0o040 => (&mut self.h, &mut self.l),
+                                    0o060 => {
+                                        is_sp = true;
+                                        (&mut self.b, &mut self.c) //<== throwaway value
+                                    },
+                                    _ => panic!("ERROR: register octet out of bounds!")
+                                };
+                            
+                                if !is_sp {
+                                    let value = u16::from_be_bytes([*register_high, *register_low]).wrapping_add(incrementor);
+                                    (*register_high, *register_low) = value.to_be_bytes().into();
+                                }
+                                else {
+                                    self.stack_pointer = self.stack_pointer.wrapping_add(incrementor);
+                                }
+                            }
+                            0o004 | 0o005 => { //INC r8, INC [HL], DEC r8, DEC [HL]
+                                let mut is_hl = false;
+                                let is_increment = opcode & 0o007 == 0o004;
+                                let register = match opcode & 0o070 {
+                                    0o000 => &mut self.b,
+                                    0o010 => &mut self.c,
+                                    0o020 => &mut self.d,
+                                    0o030 => &mut self.e,
+                                    0o040 => &mut self.h,
+                                    0o050 => &mut self.l,
+                                    0o060 => {
+                                        is_hl = true;
+                                        cycle_count = 12;
+                                        &mut self.b //<== Throwaway value
+                                    }
+                                    0o070 => &mut self.a,
+                                    _ => panic!("ERROR: Register octet out of bounds!")
+                                };
+                            
+                                let step = if is_increment {alu::inc8} else {alu::dec8};
+                                if !is_hl {
+                                    (*register, self.flags) = step(*register, self.flags);
+                                }
+                                else {
+                                    let address = u16::from_be_bytes([self.h, self.l]);
+                                    let value;
+                                    (value, self.flags) = step(self.read_at(2, address), self.flags);
+                                    self.write_at(3, address, value);
+                                }
+                            }
+                            0o006 => {
+                                //LD r8, n8 | LD [HL], r8
+                                instruction_size = 2;
+                                cycle_count = 8;
+                            
+                                let mut is_hl = false;
+                                let value = self.read_at(2, self.program_counter.wrapping_add(1));
+                                let register = match opcode & 0o070 {
+                                    0o000 => &mut self.b,
+                                    0o010 => &mut self.c,
+                                    0o020 => &mut self.d,
+                                    0o030 => &mut self.e,
+                                    0o040 => &mut self.h,
+                                    0o050 => &mut self.l,
+                                    0o060 => {
+                                        is_hl = true;
+                                        cycle_count = 12;
+                                        &mut self.b //<== Throwaway value
+                                    }
+                                    0o070 => &mut self.a,
+                                    _ => panic!("ERROR: Register octet out of bounds!")
+                                };
+                            
+                                if !is_hl {
+                                    *register = value;
+                                }
+                                else {
+                                    let address = u16::from_be_bytes([self.h, self.l]);
+                                    self.write_at(3, address, value);
+                                }
+                            }
+                            _ => panic!("ERROR: Column octet out of bounds!")
+                        }
+                    }
+                
+                    0o100 => { //Block 1
+                        //LD r8, r8, | LD r8, [HL] | LD [HL], r8
+                        let source = match opcode & 0o007 {
+                            0o000 => self.b,
+                            0o001 => self.c,
+                            0o002 => self.d,
+                            0o003 => self.e,
+                            0o004 => self.h,
+                            0o005 => self.l,
+                            0o006 => {
+                                cycle_count = 8;
+                                self.read_at(2, u16::from_be_bytes([self.h, self.l]))
+                            }
+                            0o007 => self.a,
+                            _ => panic!("ERROR: Source octet out of bounds!")
+                        };
+                    
+                        let mut is_hl = false;
+                        let destination = match opcode & 0o070 {
+                            0o000 => &mut self.b,
+                            0o010 => &mut self.c,
+                            0o020 => &mut self.d,
+                            0o030 => &mut self.e,
+                            0o040 => &mut self.h,
+                            0o050 => &mut self.l,
+                            0o060 => {
+                                is_hl = true;
+                                cycle_count = 8;
+                                &mut self.b //<== throway value to get out of the match (Will not be used!)
+                            }
+                            0o070 => &mut self.a,
+                            _ =>panic!("Error: Destination octet out of bounds!")
+                        };
+                    
+                        if !is_hl {
+                            *destination = source;
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            self.write_at(2, address, source);
+                        }
+                    }
+                
+                    0o200 => { //Block 2
+                        let operand = match opcode & 0o007 {
+                            0o000 => self.b,
+                            0o001 => self.c,
+                            0o002 => self.d,
+                            0o003 => self.e,
+                            0o004 => self.h,
+                            0o005 => self.l,
+                            0o006 => {
+                                cycle_count = 8;
+                                self.read_at(2, u16::from_be_bytes([self.h, self.l]))
+                            }
+                            0o007 => self.a,
+                            _ => panic!("ERROR: Operand octet out of bounds!")
+                        };
+
+                        match opcode & 0o070 {
+                            0o000 => { //ADD A, r8 | ADD A, [HL]
+                                (self.a, self.flags) = alu::add8(self.a, operand);
+                            }
+                            0o010 => { //ADC A, r8 | ADC A, [HL]
+                                (self.a, self.flags) = alu::adc8(self.a, operand, self.flags & C_CARRY_FLAG > 0);
+                            }
+                            0o020 => { //SUB A, r8 | SUB A, [HL]
+                                (self.a, self.flags) = alu::sub8(self.a, operand);
+                            }
+                            0o030 => { //SBC A, r8 | SBC A, [HL]
+                                (self.a, self.flags) = alu::sbc8(self.a, operand, self.flags & C_CARRY_FLAG > 0);
+                            }
+                            0o040 => { //AND A, r8 | AND A [HL]
+                                self.a &= operand;
+
+                                self.flag_toggle(self.a == 0, Z_ZERO_FLAG);
+                                self.flag_toggle(true, H_HALF_CARRY_FLAG);
+                                self.flag_toggle(false, N_SUBTRACTION_FLAG | C_CARRY_FLAG);
+                            }
+                            0o050 => { //XOR A, r8 | XOR A [HL]
+                                self.a ^= operand;
+
+                                self.flag_toggle(self.a == 0, Z_ZERO_FLAG);
+                                self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG | C_CARRY_FLAG);
+                            }
+                            0o060 => { //OR A, r8 | OR A [HL]
+                                self.a |= operand;
+
+                                self.flag_toggle(self.a == 0, Z_ZERO_FLAG);
+                                self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG | C_CARRY_FLAG);
+                            }
+                            0o070 => { //CP A, r8 | CP A, [HL]
+                                (_, self.flags) = alu::sub8(self.a, operand);
+                            }
+                            _ => panic!("ERROR: Operator octet out of bounds!")
+                        }
+                    }
+                
+                    0o300 => { //Block 3
+                        match opcode & 0o007 {
+                            0o000 => { //RET cc
+                                let return_condition = match opcode & 0o030 {
+                                    0o000 => self.flags & Z_ZERO_FLAG == 0,
+                                    0o010 => self.flags & Z_ZERO_FLAG > 0,
+                                    0o020 => self.flags & C_CARRY_FLAG == 0,
+                                    0o030 => self.flags & C_CARRY_FLAG > 0,
+                                    _ => panic!("ERROR: condition octet out of bounds!")
+                                };
+
+                                if return_condition {
+                                    cycle_count = 20;
+                                    instruction_size = 0;
+                                    self.program_counter = self.pop_at(3);
+                                }
+                                else {
+                                    cycle_count = 8;
+                                }
+                            }
+                            0o001 => { //POP r16 | POP AF
+                                cycle_count = 12;
+
+                                let popped_value = self.pop_at(2);
+
+                                let (register_high, register_low) = match opcode & 0o060 {
+                                    0o000 => (&mut self.b, &mut self.c),
+                                    0o020 => (&mut self.d, &mut self.e),
+                                    0o040 => (&mut self.h, &mut self.l),
+                                    0o060 => (&mut self.a, &mut self.flags),
+                                    _ => panic!("ERROR: register octet out of bounds!")
+                                };
+
+                                (*register_high, *register_low) = u16::to_be_bytes(popped_value).into();
+                                self.flags &= 0xF0;
+                            }
+                            0o002 if opcode & 0o070 >= 0o040 => { //LDH [C], A | LD [a16], A | LDH A, [C] | LD A, [a16]
+                                let address;
+                                let access_cycle;
+                                if opcode & 0o010 == 0 {
+                                    cycle_count = 8;
+                                    access_cycle = 2;
+                                    address = u16::from_be_bytes([0xFF, self.c]);
+                                }
+                                else {
+                                    cycle_count = 16;
+                                    access_cycle = 4;
+                                    instruction_size = 3;
+                                    address = self.read_16_at(2, self.program_counter.wrapping_add(1));
+                                }
+
+                                if opcode & 0o020 == 0 {
+                                    self.write_at(access_cycle, address, self.a);
+                                }
+                                else {
+                                    self.a = self.read_at(access_cycle, address);
+                                }
+                            }
+                            0o002 => { //JP cc
+                                let jump_condition = match opcode & 0o030 {
+                                    0o000 => self.flags & Z_ZERO_FLAG == 0,
+                                    0o010 => self.flags & Z_ZERO_FLAG > 0,
+                                    0o020 => self.flags & C_CARRY_FLAG == 0,
+                                    0o030 => self.flags & C_CARRY_FLAG > 0,
+                                    _ => panic!("ERROR: condition octet out of bounds!")
+                                };
+                                if jump_condition {
+                                    instruction_size = 0;
+                                    cycle_count = 16;
+                                    self.program_counter = self.read_16_at(2, self.program_counter.wrapping_add(1));
+                                }
+                                else {
+                                    cycle_count = 12;
+                                    instruction_size = 3;
+                                }
+                            }
+                            0o004 => { //CALL cc
+                                let jump_condition = match opcode & 0o030 {
+                                    0o000 => self.flags & Z_ZERO_FLAG == 0,
+                                    0o010 => self.flags & Z_ZERO_FLAG > 0,
+                                    0o020 => self.flags & C_CARRY_FLAG == 0,
+                                    0o030 => self.flags & C_CARRY_FLAG > 0,
+                                    _ => panic!("ERROR: condition octet out of bounds!")
+                                };
+                                if jump_condition {
+                                    instruction_size = 0;
+                                    cycle_count = 24;
+                                    let address = self.read_16_at(2, self.program_counter.wrapping_add(1));
+                                    self.push_at(5, self.program_counter.wrapping_add(3));
+                                    self.program_counter = address;
+                                }
+                                else {
+                                    cycle_count = 12;
+                                    instruction_size = 3;
+                                }
+                            }
+                            0o005 => { //PUSH r16 | PUSH AF
+                                cycle_count = 16;
+                                let pushed_value = u16::from_be_bytes(match opcode & 0o060 {
+                                    0o000 => [self.b, self.c],
+                                    0o020 => [self.d, self.e],
+                                    0o040 => [self.h, self.l],
+                                    0o060 => [self.a, self.flags],
+                                    _ => panic!("ERROR: register octet out of bounds!")
+                                });
+
+                                self.push_at(3, pushed_value);
+                            }
+                            0o006 => {
+                                instruction_size = 2;
+                                let operand = self.read_at(2, self.program_counter.wrapping_add(1));
+                                
+                                match opcode & 0o070 {
+                                    0o000 => { //ADD A, n8
+                                        (self.a, self.flags) = alu::add8(self.a, operand);
+                                    }
+                                    0o010 => { //ADC A, n8
+                                        (self.a, self.flags) = alu::adc8(self.a, operand, self.flags & C_CARRY_FLAG > 0);
+                                    }
+                                    0o020 => { //SUB A, n8
+                                        (self.a, self.flags) = alu::sub8(self.a, operand);
+                                    }
+                                    0o030 => { //SBC A, n8
+                                        (self.a, self.flags) = alu::sbc8(self.a, operand, self.flags & C_CARRY_FLAG > 0);
+                                    }
+                                    0o040 => { //AND A, n8
+                                        self.a &= operand;
+                                    
+                                        self.flag_toggle(self.a == 0, Z_ZERO_FLAG);
+                                        self.flag_toggle(true, H_HALF_CARRY_FLAG);
+                                        self.flag_toggle(false, N_SUBTRACTION_FLAG | C_CARRY_FLAG);
+                                    }
+                                    0o050 => { //XOR A, n8
+                                        self.a ^= operand;
+                                    
+                                        self.flag_toggle(self.a == 0, Z_ZERO_FLAG);
+                                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG | C_CARRY_FLAG);
+                                    }
+                                    0o060 => { //OR A, n8
+                                        self.a |= operand;
+                                    
+                                        self.flag_toggle(self.a == 0, Z_ZERO_FLAG);
+                                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG | C_CARRY_FLAG);
+                                    }
+                                    0o070 => { //CP A, n8
+                                        (_, self.flags) = alu::sub8(self.a, operand);
+                                    }
+                                    _ => panic!("ERROR: Operator octet out of bounds!")
+                                }
+                            }
+                            0o007 => { //RST vec
+                                let jump_address_vector = match opcode & 0o070 {
+                                    0o000 => 0x00,
+                                    0o010 => 0x08,
+                                    0o020 => 0x10,
+                                    0o030 => 0x18,
+                                    0o040 => 0x20,
+                                    0o050 => 0x28,
+                                    0o060 => 0x30,
+                                    0o070 => 0x38,
+                                    _ => panic!("ERROR: Vector octet out of bounds!")
+                                };
+                                
+                                let return_address = self.program_counter.wrapping_add(1);
+                                self.push_at(3, return_address);
+
+                                instruction_size = 0;
+                                //self.program_counter = self.read_16(jump_address_vector);
+                                self.program_counter = jump_address_vector;
+                            }
+                            _ => panic!("ERROR: Column octet out of bounds!")
+                        }
+                    }
+                    _ => panic!("ERROR: Block octet out of bounds!")
+                }
+            }
+        }
+        
+
+        self.program_counter = self.program_counter.wrapping_add(instruction_size);
+        cycle_count
+    }
+
+    fn execute_prefixed_instruction(&mut self) -> u8 {
+        let opcode = self.read(self.program_counter.wrapping_add(1));
+        let mut cycle_count = 8; 
+        
+        let mut is_hl = false;
+        let operand = match opcode & 0o007 {
+            0o000 => &mut self.b,
+            0o001 => &mut self.c,
+            0o002 => &mut self.d,
+            0o003 => &mut self.e,
+            0o004 => &mut self.h,
+            0o005 => &mut self.l,
+            0o006 => {
+                is_hl = true;
+                cycle_count = 16;
+                &mut self.b //<===Throwaway value
+            }
+            0o007 => &mut self.a,
+            _ => panic!("ERROR: Operand octet out of bounds!")
+        };
+
+        match opcode & 0o300 {
+            0o000 => {
+                match opcode & 0o070 {
+                    0o000 => { //RLC r8 | RLC [HL]
+                        let zero_condition ;
+                        let carry_condition;
+
+                        if !is_hl {
+                            zero_condition = *operand == 0;
+                            carry_condition = *operand & 0x80 > 0;
+
+                            *operand <<= 1;
+                            if carry_condition {
+                                *operand += 1;
+                            }
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+
+                            zero_condition = value == 0;
+                            carry_condition = value & 0x80 > 0;
+
+                            value <<= 1;
+                            if carry_condition {
+                                value += 1;
+                            }
+
+                            self.write_at(4, address, value);
+                        }
+
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                        self.flag_toggle(carry_condition, C_CARRY_FLAG);
+                    }
+                    0o010 => { //RRC r8 | RRC [HL]
+                        let zero_condition ;
+                        let carry_condition;
+
+                        if !is_hl {
+                            zero_condition = *operand == 0;
+                            carry_condition = *operand & 0x01 > 0;
+
+                            *operand >>= 1;
+                            if carry_condition {
+                                *operand += 0x80;
+                            }
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+
+                            zero_condition = value == 0;
+                            carry_condition = value & 0x01 > 0;
+
+                            value >>= 1;
+                            if carry_condition {
+                                value += 0x80;
+                            }
+
+                            self.write_at(4, address, value);
+                        }
+
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                        self.flag_toggle(carry_condition, C_CARRY_FLAG);
+                    }
+                    0o020 => { //RL r8 | RL [HL]
+                        let zero_condition ;
+                        let carry_condition;
+
+                        if !is_hl {
+                            carry_condition = *operand & 0x80 > 0;
+
+                            *operand <<= 1;
+                            if self.flags & C_CARRY_FLAG > 0 {
+                                *operand += 1;
+                            }
+
+                            zero_condition = *operand == 0;
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+
+                            carry_condition = value & 0x80 > 0;
+
+                            value <<= 1;
+                            if self.flags & C_CARRY_FLAG > 0 {
+                                value += 1;
+                            }
+
+                            zero_condition = value == 0;
+
+                            self.write_at(4, address, value);
+                        }
+
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                        self.flag_toggle(carry_condition, C_CARRY_FLAG);
+                    }
+                    0o030 => { //RR r8 | RR [HL]
+                        let zero_condition ;
+                        let carry_condition;
+    
+                        if !is_hl {
+                            carry_condition = *operand & 0x01 > 0;
+    
+                            *operand >>= 1;
+                            if self.flags & C_CARRY_FLAG > 0 {
+                                *operand += 0x80;
+                            }
+
+                            zero_condition = *operand == 0;
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+    
+                            carry_condition = value & 0x01 > 0;
+    
+                            value >>= 1;
+                            if self.flags & C_CARRY_FLAG > 0 {
+                                value += 0x80;
+                            }
+
+                            zero_condition = value == 0;
+    
+                            self.write_at(4, address, value);
+                        }
+    
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                        self.flag_toggle(carry_condition, C_CARRY_FLAG);
+                    }
+                    0o040 => { //SLA r8 | SLA [HL]
+                        let zero_condition ;
+                        let carry_condition;
+
+                        if !is_hl {
+                            carry_condition = *operand & 0x80 > 0;
+
+                            *operand <<= 1;
+
+                            zero_condition = *operand == 0;
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+
+                            carry_condition = value & 0x80 > 0;
+
+                            value <<= 1;
+
+                            zero_condition = value == 0;
+
+                            self.write_at(4, address, value);
+                        }
+
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                        self.flag_toggle(carry_condition, C_CARRY_FLAG);
+                    }
+                    0o050 => { //SRA r8 | SRA [HL]
+                        let zero_condition ;
+                        let carry_condition;
+    
+                        if !is_hl {
+                            carry_condition = *operand & 0x01 > 0;
+    
+                            let kept_bit = *operand & 0x80;
+                            *operand >>= 1;
+                            *operand |= kept_bit;
+
+                            zero_condition = *operand == 0;
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+    
+                            carry_condition = value & 0x01 > 0;
+    
+                            let kept_bit = value & 0x80;
+                            value >>= 1;
+                            value |= kept_bit;
+
+                            zero_condition = value == 0;
+    
+                            self.write_at(4, address, value);
+                        }
+    
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                        self.flag_toggle(carry_condition, C_CARRY_FLAG);
+                    }
+                    0o060 => { //SWAP r8 | SWAP [HL]
+                        let zero_condition;
+
+                        if !is_hl {
+                            zero_condition = *operand == 0;
+
+                            let upper_nibble = (*operand) << 4;
+                            let lower_nibble = (*operand) >> 4;
+                            *operand = upper_nibble | lower_nibble;
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+
+                            zero_condition = value == 0;
+
+                            let upper_nibble = value << 4;
+                            let lower_nibble = value >> 4;
+                            value = upper_nibble | lower_nibble;
+
+                            self.write_at(4, address, value);
+                        }
+
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG | C_CARRY_FLAG);
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                    }
+                    0o070 => { //SRL r8 | SRL [HL]
+                        let zero_condition ;
+                        let carry_condition;
+    
+                        if !is_hl {
+                            carry_condition = *operand & 0x01 > 0;
+    
+                            *operand >>= 1;
+
+                            zero_condition = *operand == 0;
+                        }
+                        else {
+                            let address = u16::from_be_bytes([self.h, self.l]);
+                            let mut value = self.read_at(3, address);
+    
+                            carry_condition = value & 0x01 > 0;
+    
+                            value >>= 1;
+
+                            zero_condition = value == 0;
+    
+                            self.write_at(4, address, value);
+                        }
+    
+                        self.flag_toggle(zero_condition, Z_ZERO_FLAG);
+                        self.flag_toggle(false, N_SUBTRACTION_FLAG | H_HALF_CARRY_FLAG);
+                        self.flag_toggle(carry_condition, C_CARRY_FLAG);
+                    }
+                    _ => panic!("ERROR: instruction octet out of bounds!")
+                };
+            }
+            0o100 => { //BIT u3, r8 | BIT u3, [HL]
+                let u3 = (opcode & 0o070) >> 3;
+                let tested_bit = 1 << u3;
+
+                if !is_hl{
+                    let condition = *operand & tested_bit == 0;
+                    self.flag_toggle(condition, Z_ZERO_FLAG);
+                }
+                else {
+                    cycle_count = 12;
+                    let address = u16::from_be_bytes([self.h,self.l]);
+                    let value = self.read_at(3, address);
+                    self.flag_toggle(value & tested_bit == 0, Z_ZERO_FLAG);
+                }
+
+                self.flag_toggle(false, N_SUBTRACTION_FLAG);
+                self.flag_toggle(true, H_HALF_CARRY_FLAG);
+            }
+            0o200 => { //RES u3, r8 | RES u3, [HL]
+                let u3 = (opcode & 0o070) >> 3;
+                let reset_bit = 1 << u3;
+
+                if !is_hl{
+                    *operand &= 0xFF ^ reset_bit;
+                }
+                else {
+                    let address = u16::from_be_bytes([self.h,self.l]);
+                    let value = self.read_at(3, address) & (0xFF ^ reset_bit);
+                    self.write_at(4, address, value);
+                }
+            }
+            0o300 => { //SET u3, r8 | SET u3, [HL]
+                let u3 = (opcode & 0o070) >> 3;
+                let set_bit = 1 << u3;
+
+                if !is_hl{
+                    *operand |= set_bit;
+                }
+                else {
+                    let address = u16::from_be_bytes([self.h,self.l]);
+                    let value = self.read_at(3, address) | set_bit;
+                    self.write_at(4, address, value);
+                }
+            }
+            _ => panic!("ERROR: Block octet out of bounds!")
+        }
+
+        cycle_count
+    }
+
+    //Looks ahead at the opcode without counting it as an access, since execute_instruction is what fetches it
+    pub fn get_instruction_delay(&mut self) -> u8 {
+        let program_counter = self.program_counter;
+        let (opcode, prefixed_opcode) = self.untracked(|console| {
+            let opcode = console.read(program_counter);
+            (opcode, if opcode == 0o313 {console.read(program_counter.wrapping_add(1))} else {0})
+        });
+        opcodes::m_cycles(opcode, prefixed_opcode, self.flags).unwrap_or_else(|| panic!("ERROR: Invalid opcode!"))
+    }
+}
+
+//The cartridge is removed when the console goes away, so that's when its aux data is saved, like a battery save's last commit
+impl Drop for GBConsole {
+    fn drop(&mut self) {
+        self.save_aux_data();
+    }
+}
+
+#[derive(PartialEq)]
+pub enum IMEState {
+    Enabled,
+    Disabled,
+    Pending
+}
+
+pub struct IOBreak {
+    pub address: u16,
+    pub value: Option<u8>, //None for reads
+    pub program_counter: u16, //The instruction that made the access
+    pub kind: BreakKind,
+}
+
+#[derive(Clone, Copy)]
+pub enum BreakKind {
+    UnknownRegister,
+    Watchpoint,
+    Strict { violation: StrictViolation, ppu_mode: u8, ly: u8 }, //With the PPU state at the time of the access
+}
+
+//Accesses that real hardware ignores or answers with junk. The emulator papers over them unless strict mode is on
+#[derive(Clone, Copy)]
+pub enum StrictViolation {
+    EchoRam,
+    ProhibitedArea,
+    VramDuringDraw,
+    OamDuringScan,
+    OamDuringDma,
+    DmaDuringScan, //The PPU reads OAM while the DMA is writing it
+}
+
+impl std::fmt::Display for StrictViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StrictViolation::EchoRam => write!(f, "echo RAM is prohibited by Nintendo"),
+            StrictViolation::ProhibitedArea => write!(f, "$FEA0-$FEFF is prohibited by Nintendo"),
+            StrictViolation::VramDuringDraw => write!(f, "VRAM is locked while the PPU draws"),
+            StrictViolation::OamDuringScan => write!(f, "OAM is locked while the PPU scans or draws"),
+            StrictViolation::OamDuringDma => write!(f, "OAM is locked during OAM DMA"),
+            StrictViolation::DmaDuringScan => write!(f, "OAM DMA started while the PPU is reading OAM"),
+        }
+    }
+}
+
+impl std::fmt::Display for IOBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.value, self.kind) {
+            (Some(value), BreakKind::UnknownRegister) => write!(f, "Write of ${:02X} to unknown register ${:04X} at ${:04X}", value, self.address, self.program_counter),
+            (None, BreakKind::UnknownRegister) => write!(f, "Read from unknown register ${:04X} at ${:04X}", self.address, self.program_counter),
+            (Some(value), BreakKind::Watchpoint) => write!(f, "Watchpoint: write of ${:02X} to ${:04X} at ${:04X}", value, self.address, self.program_counter),
+            (None, BreakKind::Watchpoint) => write!(f, "Watchpoint: read from ${:04X} at ${:04X}", self.address, self.program_counter),
+            (Some(value), BreakKind::Strict { violation, ppu_mode, ly }) => write!(f, "Strict: write of ${:02X} to ${:04X} at ${:04X}, {} (PPU mode {}, LY {})", value, self.address, self.program_counter, violation, ppu_mode, ly),
+            (None, BreakKind::Strict { violation, ppu_mode, ly }) => write!(f, "Strict: read from ${:04X} at ${:04X}, {} (PPU mode {}, LY {})", self.address, self.program_counter, violation, ppu_mode, ly),
+        }
+    }
+}
+
+//What happened during a call to step
+#[derive(Default)]
+pub struct CycleEvents {
+    pub is_new_frame: bool,
+    pub serial_byte: Option<u8>,
+}
+
+#[derive(Clone, Copy)]
+pub struct CPURegisters {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub stack_pointer: u16,
+    pub program_counter: u16,
+    pub ime: bool,
+    pub is_halted: bool,
+    pub is_stopped: bool,
+}
@@ -0,0 +1,250 @@
+use std::{collections::{BTreeSet, HashMap}, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{console::GBConsole, disassembler::{self, Instruction}};
+
+pub const DEBUG_SESSIONS_KEY: &str = "debug_sessions";
+
+//Pauses when the address is accessed the selected ways, or logs the access to a CSV and keeps going
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchpointEntry {
+    pub expression: String, //An address or label, or a range of them like $C000-$C0FF
+    pub on_read: bool,
+    pub on_write: bool,
+    #[serde(default)]
+    pub is_logged: bool,
+}
+
+//The debugger setup for one game, restored whenever it's opened again. Addresses are kept as the expressions
+//they were typed as, so breakpoints on labels follow them when a homebrew project is rebuilt
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DebugSession {
+    pub breakpoints: Vec<String>,
+    pub watchpoints: Vec<WatchpointEntry>,
+    pub watches: Vec<String>, //Addresses whose values are shown in the debugger
+    pub symbol_file: Option<PathBuf>,
+}
+
+//Debug sessions keyed by game id, kept between sessions
+pub type DebugSessions = HashMap<String, DebugSession>;
+
+#[derive(Clone, Copy)]
+pub struct Watchpoint {
+    pub start: u16,
+    pub end: u16, //Inclusive
+    pub on_read: bool,
+    pub on_write: bool,
+    pub is_logged: bool,
+}
+
+impl Watchpoint {
+    pub fn is_hit(&self, address: u16, is_write: bool) -> bool {
+        (self.start..=self.end).contains(&address) && if is_write {self.on_write} else {self.on_read}
+    }
+}
+
+//Asked for by the debugger's step buttons, and worked out into a StepTarget by the emulation thread
+#[derive(Clone)]
+pub enum StepRequest {
+    Into,
+    Over, //Runs CALLs and RSTs until they return, anything else is a single step
+    RunTo(String), //An address or label
+}
+
+//Where the emulation thread pauses next because of a step, on top of the breakpoints
+#[derive(Clone, Copy)]
+pub enum StepTarget {
+    NextInstruction { has_started: bool }, //Whether the instruction being stepped over has passed the check yet
+    Return { address: u16, stack_pointer: u16 }, //Recursive calls only stop on the way out of the outer one
+    Address(u16),
+}
+
+impl StepTarget {
+    //Checked between instructions, before the one at the program counter runs
+    pub fn is_reached(&mut self, program_counter: u16, stack_pointer: u16) -> bool {
+        match self {
+            StepTarget::NextInstruction { has_started } => std::mem::replace(has_started, true),
+            StepTarget::Return { address, stack_pointer: call_stack_pointer } => program_counter == *address && stack_pointer >= *call_stack_pointer,
+            StepTarget::Address(address) => program_counter == *address,
+        }
+    }
+}
+
+//What the emulation thread made of the debug session, for the debugger tab
+#[derive(Clone, Default)]
+pub struct DebugView {
+    pub symbol_count: usize,
+    pub symbol_error: Option<String>,
+    pub unresolved: Vec<String>, //Expressions that aren't an address or a known label
+    pub watch_values: Vec<Option<(u16, u8)>>, //In the same order as the watches
+}
+
+//The code around the program counter, for the disassembly tab. Each instruction comes with its label, if it has one
+#[derive(Clone, Default)]
+pub struct DisassemblyView {
+    pub program_counter: u16,
+    pub lines: Vec<(Option<String>, Instruction)>,
+}
+
+//Labels from an RGBDS style .sym file, one "bank:address label" per line. Banks are ignored
+#[derive(Default)]
+pub struct Symbols {
+    addresses: HashMap<String, u16>,
+    labels: HashMap<u16, String>,
+}
+
+impl Symbols {
+    pub fn load(path: &PathBuf) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut symbols = Self::default();
+        for line in source.lines() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+            let Some((location, label)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let address = location.rsplit(':').next().and_then(|address| u16::from_str_radix(address, 16).ok());
+            if let Some(address) = address {
+                symbols.addresses.insert(label.trim().to_owned(), address);
+                symbols.labels.entry(address).or_insert_with(|| label.trim().to_owned());
+            }
+        }
+        Ok(symbols)
+    }
+
+    pub fn size(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn label(&self, address: u16) -> Option<&str> {
+        self.labels.get(&address).map(|label| label.as_str())
+    }
+
+    //A label, or an address in hex with or without a $ or 0x prefix
+    pub fn resolve(&self, expression: &str) -> Option<u16> {
+        let expression = expression.trim();
+        if let Some(address) = self.addresses.get(expression) {
+            return Some(*address);
+        }
+        let hex = expression.trim_start_matches('$').trim_start_matches("0x").trim_start_matches("0X");
+        u16::from_str_radix(hex, 16).ok()
+    }
+
+    //Either end of a range can be a label. A single address is a range of one
+    pub fn resolve_range(&self, expression: &str) -> Option<(u16, u16)> {
+        match expression.split_once('-') {
+            Some((start, end)) => Some((self.resolve(start)?, self.resolve(end)?)).filter(|(start, end)| start <= end),
+            None => self.resolve(expression).map(|address| (address, address)),
+        }
+    }
+}
+
+//The debug session as the emulation thread uses it, with every expression looked up
+#[derive(Default)]
+pub struct ResolvedSession {
+    pub session: DebugSession,
+    pub symbols: Symbols,
+    pub breakpoints: BTreeSet<u16>,
+    pub watchpoints: Vec<Watchpoint>,
+    pub watches: Vec<Option<u16>>,
+    pub view: DebugView,
+}
+
+impl ResolvedSession {
+    pub fn new(session: DebugSession, previous: ResolvedSession) -> Self {
+        let mut view = DebugView::default();
+        let symbols = if session.symbol_file == previous.session.symbol_file {
+            previous.symbols
+        }
+        else {
+            match &session.symbol_file {
+                Some(path) => Symbols::load(path).unwrap_or_else(|e| {
+                    println!("ERROR: Could not load symbols {}: {}", path.display(), e);
+                    view.symbol_error = Some(e);
+                    Symbols::default()
+                }),
+                None => Symbols::default(),
+            }
+        };
+        view.symbol_count = symbols.size();
+        if session.symbol_file == previous.session.symbol_file {
+            view.symbol_error = previous.view.symbol_error;
+        }
+
+        let watchpoints = session.watchpoints.iter()
+            .filter_map(|entry| {
+                let range = symbols.resolve_range(&entry.expression);
+                if range.is_none() {
+                    view.unresolved.push(entry.expression.clone());
+                }
+                let (start, end) = range?;
+                Some(Watchpoint { start, end, on_read: entry.on_read, on_write: entry.on_write, is_logged: entry.is_logged })
+            })
+            .collect();
+
+        let mut resolve = |expression: &String| {
+            let address = symbols.resolve(expression);
+            if address.is_none() {
+                view.unresolved.push(expression.clone());
+            }
+            address
+        };
+        let breakpoints = session.breakpoints.iter().filter_map(&mut resolve).collect();
+        let watches = session.watches.iter().map(&mut resolve).collect();
+
+        Self {
+            session,
+            symbols,
+            breakpoints,
+            watchpoints,
+            watches,
+            view,
+        }
+    }
+
+    //is_checked is whether the console was paused by the breakpoint check for the instruction at the program counter,
+    //which has already been passed. Partway through an instruction the program counter is already past it
+    pub fn step_target(&self, request: &StepRequest, console: &mut GBConsole, is_checked: bool) -> Option<StepTarget> {
+        let program_counter = console.program_counter();
+        let is_between_instructions = console.is_between_instructions();
+        let call_size = match console.read_bulk(program_counter, program_counter)[0] {
+            _ if !is_between_instructions => None,
+            0xC4 | 0xCC | 0xCD | 0xD4 | 0xDC => Some(3), //CALL
+            opcode if opcode & 0xC7 == 0xC7 => Some(1), //RST
+            _ => None,
+        };
+        match (request, call_size) {
+            (StepRequest::Over, Some(call_size)) => {
+                let stack_pointer = console.cpu_registers().stack_pointer;
+                Some(StepTarget::Return { address: program_counter.wrapping_add(call_size), stack_pointer })
+            }
+            (StepRequest::Into | StepRequest::Over, _) => Some(StepTarget::NextInstruction { has_started: is_checked || !is_between_instructions }),
+            (StepRequest::RunTo(expression), _) => {
+                let address = self.symbols.resolve(expression);
+                if address.is_none() {
+                    println!("ERROR: Can't run to {}, it isn't an address or a known label", expression);
+                }
+                address.map(StepTarget::Address)
+            }
+        }
+    }
+
+    pub fn disassembly(&self, console: &mut GBConsole) -> DisassemblyView {
+        const BEFORE: usize = 16;
+        const AFTER: usize = 32;
+
+        let program_counter = console.program_counter();
+        let lines = disassembler::around(console, program_counter, BEFORE, AFTER).into_iter()
+            .map(|instruction| (self.symbols.label(instruction.address).map(str::to_owned), instruction))
+            .collect();
+        DisassemblyView { program_counter, lines }
+    }
+
+    //Names the address after its label when there is one
+    pub fn describe(&self, address: u16) -> String {
+        match self.symbols.label(address) {
+            Some(label) => format!("{} (${:04X})", label, address),
+            None => format!("${:04X}", address),
+        }
+    }
+}
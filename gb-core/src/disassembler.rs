@@ -0,0 +1,79 @@
+use super::{console::GBConsole, opcodes};
+
+//One instruction as it sits in memory, decoded without running it
+#[derive(Clone)]
+pub struct Instruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+impl Instruction {
+    //The opcode and the two bytes after it, whether or not they're operands
+    pub fn decode(address: u16, [opcode, low, high]: [u8; 3]) -> Self {
+        let length = opcodes::length(opcode) as usize;
+        Self {
+            address,
+            bytes: [opcode, low, high][..length].to_vec(),
+            text: opcodes::mnemonic(opcode, Some([low, high])),
+        }
+    }
+
+    pub fn next_address(&self) -> u16 {
+        self.address.wrapping_add(self.bytes.len() as u16)
+    }
+
+    pub fn hex(&self) -> String {
+        self.bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<String>>().join(" ")
+    }
+}
+
+//Decodes count instructions one after another from start
+pub fn disassemble(console: &mut GBConsole, start: u16, count: usize) -> Vec<Instruction> {
+    let mut instructions: Vec<Instruction> = Vec::with_capacity(count);
+    let mut address = start;
+    for _ in 0..count {
+        let instruction = decode_at(console, address);
+        address = instruction.next_address();
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+//Up to before instructions leading up to the address, the one at it, and after more. Code can't be decoded backwards
+//for certain since any byte could be an operand, so this starts far enough back for the longest instructions and
+//takes the furthest start that lines up with the address
+pub fn around(console: &mut GBConsole, address: u16, before: usize, after: usize) -> Vec<Instruction> {
+    let mut leading = Vec::new();
+    for distance in (1..=before as u16 * 3).rev() {
+        let mut instructions = Vec::new();
+        let mut next_address = address.wrapping_sub(distance);
+        while address.wrapping_sub(next_address) <= distance && next_address != address {
+            let instruction = decode_at(console, next_address);
+            next_address = instruction.next_address();
+            instructions.push(instruction);
+        }
+        if next_address == address {
+            leading = instructions;
+            break;
+        }
+    }
+
+    let skipped = leading.len().saturating_sub(before);
+    leading.drain(..skipped);
+    leading.extend(disassemble(console, address, after + 1));
+    leading
+}
+
+fn decode_at(console: &mut GBConsole, address: u16) -> Instruction {
+    let bytes = [0, 1, 2].map(|offset| read(console, address.wrapping_add(offset)));
+    Instruction::decode(address, bytes)
+}
+
+//The IO registers aren't code, and reading ones the console doesn't know would report them
+fn read(console: &mut GBConsole, address: u16) -> u8 {
+    match address {
+        0xFF00..=0xFF7F => 0xFF,
+        _ => console.read_bulk(address, address)[0],
+    }
+}
@@ -0,0 +1,92 @@
+use std::{fs, path::Path};
+
+use crate::cartridge_info::CartridgeError;
+
+use super::{audio_capture::CaptureSink, console::GBConsole, frame_export::FrameFormat, headless::HeadlessConsole, joypad::JoypadState};
+
+//The whole core behind the handful of calls a frontend needs: load a rom, set the buttons, run a frame, then take the
+//picture and the sound it made. Nothing here knows about windows, audio devices or input devices, so it works the same
+//under any frontend, in tests and in benchmarks. GBConsole is still there for anything this doesn't cover
+pub struct Emulator {
+    headless: Option<HeadlessConsole>,
+    sample_rate: f32,
+    frame_format: FrameFormat,
+    framebuffer: Vec<u8>,
+}
+
+impl Emulator {
+    //Audio is mixed at the sample rate the frontend plays it at
+    pub fn new(sample_rate: f32) -> Self {
+        let frame_format = FrameFormat::default();
+        Self {
+            headless: None,
+            sample_rate,
+            frame_format,
+            framebuffer: vec![0; frame_format.pixel_format.bytes_per_frame()],
+        }
+    }
+
+    //Replaces whatever was running. The rom has no file, so its battery save is lost when another one is loaded
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), CartridgeError> {
+        self.start(rom, None)
+    }
+
+    //Like load_rom, with the battery save kept next to the rom
+    pub fn load_rom_file(&mut self, path: &Path) -> Result<(), CartridgeError> {
+        self.start(&fs::read(path)?, Some(path))
+    }
+
+    fn start(&mut self, rom: &[u8], path: Option<&Path>) -> Result<(), CartridgeError> {
+        let (sink, capture) = CaptureSink::new(self.sample_rate);
+        let mut headless = HeadlessConsole::with_audio_sink(rom, path, None, Box::new(sink))?;
+        headless.audio = Some(capture);
+        self.framebuffer = headless.frame(&self.frame_format);
+        self.headless = Some(headless);
+        Ok(())
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.headless.is_some()
+    }
+
+    //Held until changed, like the real buttons
+    pub fn set_buttons(&mut self, buttons: JoypadState) {
+        if let Some(headless) = &mut self.headless {
+            headless.console.set_joypad_state(buttons);
+        }
+    }
+
+    //Runs one frame's worth of cycles. Returns whether the picture changed, which it doesn't while the LCD is off
+    pub fn run_frame(&mut self) -> bool {
+        let Some(headless) = &mut self.headless else {
+            return false;
+        };
+        let has_new_frame = headless.run_frame();
+        if has_new_frame {
+            self.framebuffer = headless.frame(&self.frame_format);
+        }
+        has_new_frame
+    }
+
+    //The last finished frame, 160x144 in the frame format. RGBA8 unless changed
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    pub fn set_frame_format(&mut self, frame_format: FrameFormat) {
+        self.frame_format = frame_format;
+        self.framebuffer = match &self.headless {
+            Some(headless) => headless.frame(&frame_format),
+            None => vec![0; frame_format.pixel_format.bytes_per_frame()],
+        };
+    }
+
+    //Stereo samples mixed since the last call, to be queued on the frontend's audio output
+    pub fn audio_samples(&mut self) -> Vec<[f32; 2]> {
+        self.headless.as_ref().map(HeadlessConsole::take_audio).unwrap_or_default()
+    }
+
+    pub fn console(&mut self) -> Option<&mut GBConsole> {
+        self.headless.as_mut().map(|headless| &mut headless.console)
+    }
+}
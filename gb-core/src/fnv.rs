@@ -0,0 +1,13 @@
+//FNV-1a, for the hashes that only need to tell data apart, not resist tampering: frame hashes, journal checksums and
+//cache file names
+pub fn hash(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    const FNV_OFFSET: u64 = 0xCBF29CE484222325;
+    const FNV_PRIME: u64 = 0x100000001B3;
+
+    let mut hash = FNV_OFFSET;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
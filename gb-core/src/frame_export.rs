@@ -0,0 +1,105 @@
+use super::ppu::Pixel;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PixelFormat {
+    Rgba8,
+    Bgra8,
+    Rgb565, //Little-endian
+    Indexed2bpp, //The shade of each pixel, 4 pixels to a byte with the leftmost in the top bits
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorSpace {
+    Raw, //CGB colors scaled straight up from 5 bits per channel
+    CgbLcd, //CGB colors mixed and darkened the way the CGB screen shows them
+}
+
+//How a finished frame is handed to an embedder, so each one doesn't have to convert the PPU's pixels itself
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FrameFormat {
+    pub pixel_format: PixelFormat,
+    pub color_space: ColorSpace,
+    pub shades: [[u8; 3]; 4], //RGB of the four DMG shades, lightest first
+}
+
+impl Default for FrameFormat {
+    fn default() -> Self {
+        Self {
+            pixel_format: PixelFormat::Rgba8,
+            color_space: ColorSpace::Raw,
+            shades: [[0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55], [0x00, 0x00, 0x00]],
+        }
+    }
+}
+
+impl PixelFormat {
+    pub fn bytes_per_frame(&self) -> usize {
+        match self {
+            Self::Rgba8 | Self::Bgra8 => 160 * 144 * 4,
+            Self::Rgb565 => 160 * 144 * 2,
+            Self::Indexed2bpp => 160 * 144 / 4,
+        }
+    }
+}
+
+//Converts a frame from the PPU, using the DMG palette registers for pixels without a CGB color
+pub fn export(screen: &[[Pixel; 160]; 144], dmg_palettes: [u8; 3], format: &FrameFormat) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(format.pixel_format.bytes_per_frame());
+    let mut packed_shades = 0;
+    for row in screen.iter() {
+        for (x, pixel) in row.iter().enumerate() {
+            let (shade, [r, g, b]) = match pixel.rgb {
+                Some(rgb) => (cgb_shade(rgb), cgb_rgb(rgb, format.color_space)),
+                None => {
+                    let register = match pixel.palette {
+                        None => dmg_palettes[0],
+                        Some(0) => dmg_palettes[1],
+                        Some(_) => dmg_palettes[2],
+                    };
+                    let shade = (register >> (pixel.color * 2)) & 0b11;
+                    (shade, format.shades[shade as usize])
+                }
+            };
+
+            match format.pixel_format {
+                PixelFormat::Rgba8 => frame.extend_from_slice(&[r, g, b, 0xFF]),
+                PixelFormat::Bgra8 => frame.extend_from_slice(&[b, g, r, 0xFF]),
+                PixelFormat::Rgb565 => {
+                    let rgb565 = ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3);
+                    frame.extend_from_slice(&rgb565.to_le_bytes());
+                }
+                PixelFormat::Indexed2bpp => {
+                    packed_shades = (packed_shades << 2) | shade;
+                    if x & 0b11 == 0b11 {
+                        frame.push(packed_shades);
+                        packed_shades = 0;
+                    }
+                }
+            }
+        }
+    }
+    frame
+}
+
+pub fn cgb_rgb(rgb: u16, color_space: ColorSpace) -> [u8; 3] {
+    let r = (rgb & 0x1F) as u32;
+    let g = ((rgb >> 5) & 0x1F) as u32;
+    let b = ((rgb >> 10) & 0x1F) as u32;
+    match color_space {
+        ColorSpace::Raw => {
+            let scale = |channel: u32| ((channel << 3) | (channel >> 2)) as u8;
+            [scale(r), scale(g), scale(b)]
+        }
+        ColorSpace::CgbLcd => {
+            //Each channel bleeds into the others and the screen never gets fully bright
+            let mix = |channel: u32| (channel.min(960) >> 2) as u8;
+            [mix(r * 26 + g * 4 + b * 2), mix(g * 24 + b * 8), mix(r * 6 + g * 4 + b * 22)]
+        }
+    }
+}
+
+//CGB colors have no shade register, so the shade comes from how dark the color is
+pub fn cgb_shade(rgb: u16) -> u8 {
+    let brightness = (rgb & 0x1F) + ((rgb >> 5) & 0x1F) + ((rgb >> 10) & 0x1F);
+    3 - (brightness * 4 / 94) as u8
+}
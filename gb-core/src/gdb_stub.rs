@@ -0,0 +1,384 @@
+use std::{collections::BTreeSet, fmt::Write as _, io::{self, Read, Write}, net::{Shutdown, SocketAddr, TcpListener, TcpStream}, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, thread, time::Duration};
+
+use super::{console::GBConsole, CPURegisters};
+
+pub const DEFAULT_PORT: u16 = 1234;
+
+//How often the listener checks whether it was closed while nobody is connecting
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+//Memory reads are answered in hex, so this keeps a reply within the packet size given in qSupported
+const MAX_PACKET_SIZE: usize = 0x1000;
+
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+//What gdb asked the emulator to do. Everything else is answered right away
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GdbRequest {
+    Pause, //gdb attached or sent Ctrl-C, stop at the next instruction
+    Continue,
+    Step,
+}
+
+#[derive(Default)]
+struct Connection {
+    stream: Option<TcpStream>,
+    peer: Option<SocketAddr>,
+    input: Vec<u8>,
+    is_new: bool,
+}
+
+//A gdb remote serial protocol server, so gdb, lldb or an IDE with SM83 support can debug the running game. Registers
+//are numbered like gdb's Z80 target: AF, BC, DE, HL, SP and PC, each 16 bits. Only one debugger is connected at a time,
+//a new one replaces the last. The socket is read on its own threads, which call back when there's something to
+//handle, but the packets are only handled when the emulation thread polls, since that's where the console is
+pub struct GdbStub {
+    port: u16,
+    connection: Arc<Mutex<Connection>>,
+    is_closed: Arc<AtomicBool>,
+    buffer: Vec<u8>, //Received but not handled yet
+    is_waiting: bool, //Packets after a pause, continue or step wait until the emulator gets there
+    is_no_ack: bool,
+    is_running: bool, //gdb continued or stepped and is waiting to hear where the CPU stopped
+    stop_signal: u8,
+    breakpoints: BTreeSet<u16>,
+}
+
+impl GdbStub {
+    //Port 0 picks a free one, see port
+    pub fn listen(port: u16, on_input: impl Fn() + Send + Sync + 'static) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+        let connection = Arc::new(Mutex::new(Connection::default()));
+        let is_closed = Arc::new(AtomicBool::new(false));
+
+        let on_input = Arc::new(on_input);
+        let (listener_connection, listener_is_closed) = (connection.clone(), is_closed.clone());
+        thread::spawn(move || {
+            while !listener_is_closed.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        if let Err(e) = Self::accept(stream, peer, &listener_connection, on_input.clone()) {
+                            println!("ERROR: GDB connection from {} failed: {}", peer, e);
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+                    Err(e) => {
+                        println!("ERROR: GDB server stopped listening: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            port,
+            connection,
+            is_closed,
+            buffer: Vec::new(),
+            is_waiting: false,
+            is_no_ack: false,
+            is_running: false,
+            stop_signal: SIGTRAP,
+            breakpoints: BTreeSet::new(),
+        })
+    }
+
+    fn accept(stream: TcpStream, peer: SocketAddr, connection: &Arc<Mutex<Connection>>, on_input: Arc<impl Fn() + Send + Sync + 'static>) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        let mut reader = stream.try_clone()?;
+        let mut lock = connection.lock().unwrap();
+        if let Some(previous) = lock.stream.take() {
+            let _ = previous.shutdown(Shutdown::Both);
+        }
+        *lock = Connection { stream: Some(stream), peer: Some(peer), input: Vec::new(), is_new: true };
+        drop(lock);
+        on_input();
+
+        let connection = connection.clone();
+        thread::spawn(move || {
+            let mut buffer = [0; 1024];
+            loop {
+                let length = reader.read(&mut buffer).unwrap_or(0);
+                let mut lock = connection.lock().unwrap();
+                if length == 0 {
+                    //Only forget the connection if it wasn't replaced by a newer one
+                    if lock.peer == Some(peer) {
+                        *lock = Connection::default();
+                    }
+                    drop(lock);
+                    on_input();
+                    return;
+                }
+                lock.input.extend_from_slice(&buffer[..length]);
+                drop(lock);
+                on_input();
+            }
+        });
+        Ok(())
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn peer(&self) -> Option<SocketAddr> {
+        self.connection.lock().unwrap().peer
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<u16> {
+        &self.breakpoints
+    }
+
+    //Whether poll has anything to do
+    pub fn has_input(&self) -> bool {
+        let connection = self.connection.lock().unwrap();
+        connection.is_new || !connection.input.is_empty() || (self.is_waiting && !self.buffer.is_empty())
+    }
+
+    //Handles the packets received since the last poll, up to the first one the emulator has to act on
+    pub fn poll(&mut self, console: &mut GBConsole) -> Option<GdbRequest> {
+        let mut connection = self.connection.lock().unwrap();
+        let is_new = std::mem::take(&mut connection.is_new);
+        self.buffer.append(&mut connection.input);
+        drop(connection);
+        self.is_waiting = false;
+        if is_new {
+            self.breakpoints.clear();
+            self.is_no_ack = false;
+            self.is_running = false;
+            self.stop_signal = SIGTRAP;
+            return self.wait_for(GdbRequest::Pause);
+        }
+
+        while let Some(&byte) = self.buffer.first() {
+            if byte == 0x03 {
+                self.buffer.remove(0);
+                self.stop_signal = SIGINT;
+                return self.wait_for(GdbRequest::Pause);
+            }
+            if byte != b'$' {
+                //Acks, and anything else between packets
+                self.buffer.remove(0);
+                continue;
+            }
+            let Some(end) = self.buffer.iter().position(|&byte| byte == b'#').filter(|end| self.buffer.len() >= end + 3) else {
+                break;
+            };
+            let packet: Vec<u8> = self.buffer.drain(..end + 3).collect();
+            let (data, checksum) = (&packet[1..end], &packet[end + 1..]);
+            let is_valid = std::str::from_utf8(checksum).ok().and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                == Some(data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)));
+            if !self.is_no_ack {
+                self.send_raw(if is_valid {b"+"} else {b"-"});
+            }
+            if !is_valid {
+                continue;
+            }
+            if let Some(request) = self.handle_packet(&String::from_utf8_lossy(data), console) {
+                return self.wait_for(request);
+            }
+        }
+        None
+    }
+
+    fn wait_for(&mut self, request: GdbRequest) -> Option<GdbRequest> {
+        self.is_waiting = true;
+        Some(request)
+    }
+
+    //Tells gdb where the CPU stopped if it was waiting to hear
+    pub fn report_stop(&mut self) {
+        if self.is_running {
+            self.is_running = false;
+            let signal = std::mem::replace(&mut self.stop_signal, SIGTRAP);
+            self.send(&format!("S{:02x}", signal));
+        }
+    }
+
+    fn handle_packet(&mut self, packet: &str, console: &mut GBConsole) -> Option<GdbRequest> {
+        let (command, arguments) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match command {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => {
+                let registers = console.cpu_registers();
+                Self::register_values(&registers).iter().map(|value| Self::hex_16(*value)).collect()
+            }
+            "G" => {
+                let values: Option<Vec<u16>> = (0..6).map(|index| arguments.get(index * 4..index * 4 + 4).and_then(Self::parse_register)).collect();
+                match values {
+                    Some(values) => {
+                        let mut registers = console.cpu_registers();
+                        for (index, value) in values.into_iter().enumerate() {
+                            Self::set_register(&mut registers, index, value);
+                        }
+                        console.set_cpu_registers(&registers);
+                        "OK".to_owned()
+                    }
+                    None => "E01".to_owned(),
+                }
+            }
+            "p" => match usize::from_str_radix(arguments, 16) {
+                Ok(index) if index < 6 => Self::hex_16(Self::register_values(&console.cpu_registers())[index]),
+                _ => "E01".to_owned(),
+            },
+            "P" => {
+                let register = arguments.split_once('=').and_then(|(index, value)| Some((usize::from_str_radix(index, 16).ok()?, Self::parse_register(value)?)));
+                match register {
+                    Some((index, value)) if index < 6 => {
+                        let mut registers = console.cpu_registers();
+                        Self::set_register(&mut registers, index, value);
+                        console.set_cpu_registers(&registers);
+                        "OK".to_owned()
+                    }
+                    _ => "E01".to_owned(),
+                }
+            }
+            "m" => match Self::parse_range(arguments) {
+                Some((address, length)) => {
+                    (0..length as u16).fold(String::new(), |mut reply, offset| {
+                        let _ = write!(reply, "{:02x}", console.peek(None, address.wrapping_add(offset)).unwrap_or(0xFF));
+                        reply
+                    })
+                }
+                _ => "E01".to_owned(),
+            },
+            "M" => {
+                let write = arguments.split_once(':').and_then(|(range, data)| {
+                    let (address, length) = Self::parse_range(range)?;
+                    let bytes: Option<Vec<u8>> = (0..length).map(|index| u8::from_str_radix(data.get(index * 2..index * 2 + 2)?, 16).ok()).collect();
+                    Some((address, bytes?))
+                });
+                match write {
+                    Some((address, bytes)) => {
+                        for (offset, byte) in bytes.into_iter().enumerate() {
+                            let _ = console.poke(None, address.wrapping_add(offset as u16), byte);
+                        }
+                        "OK".to_owned()
+                    }
+                    None => "E01".to_owned(),
+                }
+            }
+            //Software and hardware breakpoints are the same thing here. Watchpoints aren't supported
+            "Z" | "z" => {
+                let mut fields = arguments.split(',');
+                let kind = fields.next();
+                match (kind, fields.next().and_then(|address| u16::from_str_radix(address, 16).ok())) {
+                    (Some("0" | "1"), Some(address)) => {
+                        if command == "Z" {
+                            self.breakpoints.insert(address);
+                        }
+                        else {
+                            self.breakpoints.remove(&address);
+                        }
+                        "OK".to_owned()
+                    }
+                    _ => String::new(),
+                }
+            }
+            "c" | "s" => {
+                if let Ok(address) = u16::from_str_radix(arguments, 16) {
+                    let mut registers = console.cpu_registers();
+                    registers.program_counter = address;
+                    console.set_cpu_registers(&registers);
+                }
+                self.is_running = true;
+                return Some(if command == "c" {GdbRequest::Continue} else {GdbRequest::Step});
+            }
+            //Detaching or killing leaves the game running without gdb's breakpoints
+            "D" | "k" => {
+                self.breakpoints.clear();
+                if command == "D" {
+                    self.send("OK");
+                }
+                return Some(GdbRequest::Continue);
+            }
+            "H" | "T" => "OK".to_owned(),
+            "q" | "Q" => match packet.split(':').next().unwrap_or_default() {
+                "qSupported" => format!("PacketSize={:x};QStartNoAckMode+", MAX_PACKET_SIZE),
+                "QStartNoAckMode" => {
+                    self.send("OK");
+                    self.is_no_ack = true;
+                    return None;
+                }
+                "qAttached" => "1".to_owned(),
+                "qC" => "QC1".to_owned(),
+                "qfThreadInfo" => "m1".to_owned(),
+                "qsThreadInfo" => "l".to_owned(),
+                _ => String::new(),
+            },
+            _ => String::new(),
+        };
+        self.send(&reply);
+        None
+    }
+
+    fn register_values(registers: &CPURegisters) -> [u16; 6] {
+        [
+            u16::from_be_bytes([registers.a, registers.f]),
+            u16::from_be_bytes([registers.b, registers.c]),
+            u16::from_be_bytes([registers.d, registers.e]),
+            u16::from_be_bytes([registers.h, registers.l]),
+            registers.stack_pointer,
+            registers.program_counter,
+        ]
+    }
+
+    fn set_register(registers: &mut CPURegisters, index: usize, value: u16) {
+        let [high, low] = value.to_be_bytes();
+        match index {
+            0 => (registers.a, registers.f) = (high, low),
+            1 => (registers.b, registers.c) = (high, low),
+            2 => (registers.d, registers.e) = (high, low),
+            3 => (registers.h, registers.l) = (high, low),
+            4 => registers.stack_pointer = value,
+            _ => registers.program_counter = value,
+        }
+    }
+
+    //Register values go over the wire in target byte order, which is little endian
+    fn hex_16(value: u16) -> String {
+        let [low, high] = value.to_le_bytes();
+        format!("{:02x}{:02x}", low, high)
+    }
+
+    fn parse_register(text: &str) -> Option<u16> {
+        if text.len() != 4 {
+            return None;
+        }
+        u16::from_str_radix(text, 16).ok().map(u16::swap_bytes)
+    }
+
+    //addr,length
+    //Lengths whose hex wouldn't fit in a packet are refused, for reads and writes alike
+    fn parse_range(text: &str) -> Option<(u16, usize)> {
+        let (address, length) = text.split_once(',')?;
+        let length = usize::from_str_radix(length, 16).ok().filter(|length| length.checked_mul(2).is_some_and(|size| size <= MAX_PACKET_SIZE))?;
+        Some((u16::from_str_radix(address, 16).ok()?, length))
+    }
+
+    fn send(&self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        self.send_raw(format!("${}#{:02x}", data, checksum).as_bytes());
+    }
+
+    fn send_raw(&self, data: &[u8]) {
+        if let Some(stream) = &mut self.connection.lock().unwrap().stream {
+            if let Err(e) = stream.write_all(data) {
+                println!("ERROR: Could not reply to GDB: {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for GdbStub {
+    fn drop(&mut self) {
+        self.is_closed.store(true, Ordering::Relaxed);
+        if let Some(stream) = self.connection.lock().unwrap().stream.take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
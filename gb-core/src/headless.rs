@@ -0,0 +1,157 @@
+use std::{fs, path::Path};
+
+use crate::{cartridge_info::{CartridgeError, CartridgeInfo}, mappers::CartridgeStorage};
+
+use super::{apu::{AudioSink, NullSink}, audio_capture::{AudioCapture, CaptureSink}, console::GBConsole, frame_export::FrameFormat, model::Model, ppu::Pixel};
+
+//Mooneye's test roms load these into B, C, D, E, H and L when they pass, and 0x42 into all of them when they fail
+const MOONEYE_PASS: [u8; 6] = [3, 5, 8, 13, 21, 34];
+const MOONEYE_FAIL: [u8; 6] = [0x42; 6];
+
+//What a test rom has said about itself, with how it said it, or the line it failed on
+pub enum TestReport {
+    Passed(String),
+    Failed(String),
+}
+
+//A console with no audio device or UI attached, run one frame at a time as fast as the host allows.
+//It's Send, so any number of them can run side by side on worker threads
+pub struct HeadlessConsole {
+    pub console: GBConsole,
+    screen: Box<[[Pixel; 160]; 144]>,
+    serial_output: Vec<u8>,
+    pub(super) audio: Option<AudioCapture>,
+    last_break_address: Option<u16>,
+}
+
+impl HeadlessConsole {
+    pub fn new(rom_path: &Path) -> Result<Self, CartridgeError> {
+        let mut headless = Self::with_audio_sink(&fs::read(rom_path)?, Some(rom_path), None, Box::new(NullSink))?;
+        headless.console.set_audio_output(false);
+        Ok(headless)
+    }
+
+    //Runs a rom that's already in memory. There's no file to keep a battery save next to, so saves are lost when it's dropped
+    pub fn from_rom(rom: &[u8]) -> Result<Self, CartridgeError> {
+        let mut headless = Self::with_audio_sink(rom, None, None, Box::new(NullSink))?;
+        headless.console.set_audio_output(false);
+        Ok(headless)
+    }
+
+    //Starts up as the given model instead of the one the cartridge would pick
+    pub fn with_model(rom_path: &Path, model: Model) -> Result<Self, CartridgeError> {
+        let mut headless = Self::with_audio_sink(&fs::read(rom_path)?, Some(rom_path), Some(model), Box::new(NullSink))?;
+        headless.console.set_audio_output(false);
+        Ok(headless)
+    }
+
+    //Keeps the audio the game makes, to be taken after each frame. Replaying a movie's inputs one frame at a time
+    //through this renders its soundtrack offline, lined up with the frames
+    pub fn with_audio_capture(rom_path: &Path, sample_rate: f32) -> Result<Self, CartridgeError> {
+        let (sink, capture) = CaptureSink::new(sample_rate);
+        let mut headless = Self::with_audio_sink(&fs::read(rom_path)?, Some(rom_path), None, Box::new(sink))?;
+        headless.audio = Some(capture);
+        Ok(headless)
+    }
+
+    pub(super) fn with_audio_sink(rom: &[u8], rom_path: Option<&Path>, model: Option<Model>, audio_sink: Box<dyn AudioSink>) -> Result<Self, CartridgeError> {
+        let info = CartridgeInfo::from_rom(rom)?;
+        let model = model.unwrap_or_else(|| Model::for_cartridge(&info));
+        let storage = match rom_path {
+            Some(rom_path) => CartridgeStorage::beside_rom(&info, &rom_path.display().to_string())?,
+            None => CartridgeStorage::default(),
+        };
+        let mut console = GBConsole::with_audio_sink(info, rom, storage, model, audio_sink)?;
+        let screen = Box::new(console.dump_screen());
+
+        Ok(Self {
+            console,
+            screen,
+            serial_output: Vec::new(),
+            audio: None,
+            last_break_address: None,
+        })
+    }
+
+    //Runs one frame's worth of cycles. Returns whether the PPU finished a frame, which it doesn't while the LCD is off
+    pub fn run_frame(&mut self) -> bool {
+        self.run_cycles(&[]).0
+    }
+
+    //Like run_frame, but stops before running an instruction at any of the breakpoints and returns its address.
+    //Running again from there doesn't stop on the same one right away
+    pub fn run_frame_until(&mut self, breakpoints: &[u16]) -> Option<u16> {
+        self.run_cycles(breakpoints).1
+    }
+
+    fn run_cycles(&mut self, breakpoints: &[u16]) -> (bool, Option<u16>) {
+        let mut has_new_frame = false;
+        for _cycle in 0..(154 * 456 / self.console.dots_per_cycle() as u32) {
+            if self.console.is_between_instructions() && !breakpoints.is_empty() {
+                let program_counter = self.console.program_counter();
+                if self.last_break_address != Some(program_counter) {
+                    self.last_break_address = None;
+                    if breakpoints.contains(&program_counter) {
+                        self.last_break_address = Some(program_counter);
+                        return (has_new_frame, Some(program_counter));
+                    }
+                }
+            }
+
+            let events = self.console.step();
+            if events.is_new_frame {
+                *self.screen = self.console.dump_screen();
+                has_new_frame = true;
+            }
+            if let Some(byte) = events.serial_byte {
+                self.serial_output.push(byte);
+            }
+        }
+        (has_new_frame, None)
+    }
+
+    //The last finished frame
+    pub fn screen(&self) -> &[[Pixel; 160]; 144] {
+        &self.screen
+    }
+
+    //The last finished frame, converted for drawing
+    pub fn frame(&self, format: &FrameFormat) -> Vec<u8> {
+        self.console.export_frame(&self.screen, format)
+    }
+
+    //The samples mixed since the last call, or nothing if the console wasn't made to capture audio
+    pub fn take_audio(&self) -> Vec<[f32; 2]> {
+        self.audio.as_ref().map(|audio| audio.take()).unwrap_or_default()
+    }
+
+    pub fn audio_capture(&self) -> Option<&AudioCapture> {
+        self.audio.as_ref()
+    }
+
+    //Everything the game has sent over the link port so far
+    pub fn serial_output(&self) -> &[u8] {
+        &self.serial_output
+    }
+
+    //Blargg's roms print their result over the link port, Mooneye's leave it in the registers
+    pub fn test_report(&self) -> Option<TestReport> {
+        let serial_output = String::from_utf8_lossy(&self.serial_output);
+        if serial_output.contains("Passed") {
+            return Some(TestReport::Passed(String::from("Serial")));
+        }
+        if serial_output.contains("Failed") {
+            return Some(TestReport::Failed(serial_output.trim().lines().last().unwrap_or_default().to_owned()));
+        }
+
+        let registers = self.console.cpu_registers();
+        let fibonacci = [registers.b, registers.c, registers.d, registers.e, registers.h, registers.l];
+        if fibonacci == MOONEYE_PASS {
+            return Some(TestReport::Passed(String::from("Registers")));
+        }
+        if fibonacci == MOONEYE_FAIL {
+            return Some(TestReport::Failed(String::from("Registers")));
+        }
+        None
+    }
+}
@@ -0,0 +1,119 @@
+//VRAM, cartridge RAM and WRAM sit next to each other on the bus, so one array covers all three
+const HEATMAP_START: u16 = 0x8000;
+const HEATMAP_SIZE: usize = 0x6000;
+
+//How much of the previous heat is kept each frame. At 0.9 an access fades out in about a second
+const DECAY: f32 = 0.9;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum HeatmapRegion {
+    Vram,
+    CartridgeRam,
+    Wram,
+}
+
+impl HeatmapRegion {
+    pub const ALL: [HeatmapRegion; 3] = [HeatmapRegion::Vram, HeatmapRegion::CartridgeRam, HeatmapRegion::Wram];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeatmapRegion::Vram => "VRAM",
+            HeatmapRegion::CartridgeRam => "Cartridge RAM",
+            HeatmapRegion::Wram => "WRAM",
+        }
+    }
+
+    pub fn start_address(&self) -> u16 {
+        match self {
+            HeatmapRegion::Vram => 0x8000,
+            HeatmapRegion::CartridgeRam => 0xA000,
+            HeatmapRegion::Wram => 0xC000,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        0x2000
+    }
+}
+
+//Per byte access counts, kept by the console's bus while the heatmap is open and cleared every frame
+pub struct AccessCounters {
+    reads: Vec<u16>,
+    writes: Vec<u16>,
+}
+
+impl Default for AccessCounters {
+    fn default() -> Self {
+        Self {
+            reads: vec![0; HEATMAP_SIZE],
+            writes: vec![0; HEATMAP_SIZE],
+        }
+    }
+}
+
+impl AccessCounters {
+    pub fn count_read(&mut self, address: u16) {
+        if let Some(index) = heatmap_index(address) {
+            self.reads[index] = self.reads[index].saturating_add(1);
+        }
+    }
+
+    pub fn count_write(&mut self, address: u16) {
+        if let Some(index) = heatmap_index(address) {
+            self.writes[index] = self.writes[index].saturating_add(1);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.reads.fill(0);
+        self.writes.fill(0);
+    }
+}
+
+//Echo RAM is folded into WRAM since it's the same memory
+fn heatmap_index(address: u16) -> Option<usize> {
+    match address {
+        0x8000..=0xDFFF => Some((address - HEATMAP_START) as usize),
+        0xE000..=0xFDFF => Some((address - 0x2000 - HEATMAP_START) as usize),
+        _ => None
+    }
+}
+
+//Decaying read and write frequency for each byte, read by the heatmap tab
+pub struct MemoryHeatmap {
+    reads: Vec<f32>,
+    writes: Vec<f32>,
+}
+
+impl Default for MemoryHeatmap {
+    fn default() -> Self {
+        Self {
+            reads: vec![0.0; HEATMAP_SIZE],
+            writes: vec![0.0; HEATMAP_SIZE],
+        }
+    }
+}
+
+impl MemoryHeatmap {
+    pub fn add_frame(&mut self, counters: &AccessCounters) {
+        for (heat, count) in self.reads.iter_mut().zip(counters.reads.iter()) {
+            *heat = *heat * DECAY + *count as f32;
+        }
+        for (heat, count) in self.writes.iter_mut().zip(counters.writes.iter()) {
+            *heat = *heat * DECAY + *count as f32;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.reads.fill(0.0);
+        self.writes.fill(0.0);
+    }
+
+    //Read and write heat of one byte
+    pub fn heat(&self, address: u16) -> (f32, f32) {
+        match heatmap_index(address) {
+            Some(index) => (self.reads[index], self.writes[index]),
+            None => (0.0, 0.0)
+        }
+    }
+}
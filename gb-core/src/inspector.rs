@@ -0,0 +1,92 @@
+//A copy of the PPU's memory and registers taken at the end of a frame, used to look up
+//what was drawn at a given screen position. Mid-frame register writes (e.g. scroll effects) aren't captured
+#[derive(Clone)]
+pub struct PPUSnapshot {
+    pub video_ram: Vec<[u8; 0x4000]>,
+    pub object_attribute_memory: [u8; 0xA0],
+    pub lcdc: u8,
+    pub scy: u8,
+    pub scx: u8,
+    pub wy: u8,
+    pub wx: u8,
+    pub mode_3_lengths: [u16; 144],
+}
+
+pub struct BGTileInfo {
+    pub is_window: bool,
+    pub map_x: u8,
+    pub map_y: u8,
+    pub map_address: u16,
+    pub tile_index: u8,
+    pub tile_address: u16,
+    pub attributes: Option<u8>, //Only present when there's a second VRAM bank
+}
+
+pub struct ObjInfo {
+    pub oam_index: u8,
+    pub x: u8,
+    pub y: u8,
+    pub tile_index: u8,
+    pub attributes: u8,
+}
+
+impl PPUSnapshot {
+    pub fn bg_tile_at(&self, x: u8, y: u8) -> BGTileInfo {
+        let is_window = self.lcdc & 32 > 0 && y >= self.wy && x as u16 + 7 >= self.wx as u16;
+
+        let (map_area, map_x, map_y) = if is_window {
+            let map_area = if self.lcdc & 64 > 0 {0x9C00} else {0x9800};
+            (map_area, (x + 7 - self.wx) >> 3, (y - self.wy) >> 3)
+        }
+        else {
+            let map_area = if self.lcdc & 8 > 0 {0x9C00} else {0x9800};
+            (map_area, x.wrapping_add(self.scx) >> 3, y.wrapping_add(self.scy) >> 3)
+        };
+
+        let map_address = map_area + ((map_y as u16) << 5) + map_x as u16;
+        let tile_index = self.video_ram[0][(map_address - 0x8000) as usize];
+        let tile_address = if self.lcdc & 16 > 0 {
+            0x8000 + ((tile_index as u16) << 4)
+        }
+        else {
+            (0x9000_i32 + ((tile_index as i8 as i32) << 4)) as u16
+        };
+        let attributes = self.video_ram.get(1).map(|bank| bank[(map_address - 0x8000) as usize]);
+
+        BGTileInfo {
+            is_window,
+            map_x,
+            map_y,
+            map_address,
+            tile_index,
+            tile_address,
+            attributes,
+        }
+    }
+
+    pub fn objects_at(&self, x: u8, y: u8) -> Vec<ObjInfo> {
+        let obj_height = if self.lcdc & 4 > 0 {16} else {8};
+        let mut objects = Vec::new();
+
+        for oam_index in 0..40 {
+            let obj_address = oam_index * 4;
+            let obj_y = self.object_attribute_memory[obj_address];
+            let obj_x = self.object_attribute_memory[obj_address + 1];
+
+            //OAM positions are offset by (8, 16) so objects can be partially offscreen
+            let screen_x = x as u16 + 8;
+            let screen_y = y as u16 + 16;
+            if screen_x >= obj_x as u16 && screen_x < obj_x as u16 + 8 && screen_y >= obj_y as u16 && screen_y < obj_y as u16 + obj_height {
+                objects.push(ObjInfo {
+                    oam_index: oam_index as u8,
+                    x: obj_x,
+                    y: obj_y,
+                    tile_index: self.object_attribute_memory[obj_address + 2],
+                    attributes: self.object_attribute_memory[obj_address + 3],
+                });
+            }
+        }
+
+        objects
+    }
+}
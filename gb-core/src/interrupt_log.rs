@@ -0,0 +1,7 @@
+//An interrupt handler starting or returning, and the scanline the PPU was on when it happened
+#[derive(Clone, Copy)]
+pub struct InterruptEvent {
+    pub line: u8,
+    pub source: u8, //The interrupt's bit in IE and IF
+    pub is_entry: bool,
+}
@@ -0,0 +1,34 @@
+//Which Game Boy buttons are held for a frame
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct JoypadState {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub start: bool,
+    pub select: bool,
+    pub a: bool,
+    pub b: bool,
+}
+
+impl JoypadState {
+    //Packed in the same order as the P1 register: Start, Select, B, A, Down, Up, Left, Right
+    pub fn to_byte(self) -> u8 {
+        let buttons = [self.start, self.select, self.b, self.a, self.down, self.up, self.left, self.right];
+        buttons.iter().fold(0, |byte, is_pressed| (byte << 1) | *is_pressed as u8)
+    }
+
+    pub fn from_byte(byte: u8) -> Self {
+        let is_pressed = |bit: u8| byte & (1 << bit) != 0;
+        Self {
+            start: is_pressed(7),
+            select: is_pressed(6),
+            b: is_pressed(5),
+            a: is_pressed(4),
+            down: is_pressed(3),
+            up: is_pressed(2),
+            left: is_pressed(1),
+            right: is_pressed(0),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//The emulation core: the console, its cartridges and the debugging tools around them, without any frontend.
+//It builds for every target the app does, including the web without zstd
+pub mod cartridge_info;
+pub mod mappers;
+
+pub mod alu;
+pub mod cheats;
+pub mod console;
+pub mod ppu;
+pub mod apu;
+pub mod apu_log;
+pub mod apu_output;
+pub mod audio_capture;
+pub mod frame_export;
+pub mod fnv;
+#[cfg(feature = "apu")]
+mod audio_device;
+pub mod ram_init;
+pub mod model;
+pub mod headless;
+pub mod emulator;
+pub mod joypad;
+pub mod opcodes;
+pub mod savestate;
+pub mod serial;
+pub mod raster_hooks;
+pub mod bus;
+pub mod disassembler;
+pub mod trace_log;
+
+#[cfg(feature = "debugger")]
+pub mod blocked_log;
+#[cfg(feature = "debugger")]
+pub mod debug_session;
+#[cfg(feature = "debugger")]
+pub mod gdb_stub;
+#[cfg(feature = "debugger")]
+pub mod heatmap;
+#[cfg(feature = "debugger")]
+pub mod inspector;
+#[cfg(feature = "debugger")]
+pub mod interrupt_log;
+#[cfg(feature = "debugger")]
+pub mod memory_editor;
+#[cfg(feature = "debugger")]
+pub mod monitor;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "debugger")]
+pub mod watch_log;
+
+pub use apu::{AudioSink, NullSink};
+#[cfg(feature = "apu")]
+pub use apu::AudioDeviceChange;
+pub use console::{CPURegisters, CycleEvents, GBConsole, HardwareMode};
@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, fs, io::{self, Read}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::savestate::{read_u32, read_u64, write_u32, write_u64};
+
+//Battery-backed cartridge state that isn't cartridge RAM, like clock counters, EEPROM or sensor calibration. It's all
+//kept in one .aux file next to the .sav, so each mapper doesn't need a file format of its own. The file is a version
+//and the time it was written, followed by sections named with a tag and written like write_tagged_state
+const MAGIC: &[u8; 4] = b"GBAX";
+pub const AUX_DATA_VERSION: u32 = 1;
+
+#[derive(Default)]
+pub struct AuxData {
+    saved_at: Option<SystemTime>,
+    sections: BTreeMap<[u8; 4], Vec<u8>>,
+}
+
+impl AuxData {
+    //A missing file is the same as an empty one. So is a broken one, after a warning, so the game still starts
+    pub fn load(aux_path: &str) -> Self {
+        let file = match fs::read(aux_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                println!("ERROR: Could not read {}: {}", aux_path, e);
+                return Self::default();
+            }
+        };
+        match Self::parse(&mut &file[..]) {
+            Ok(aux_data) => aux_data,
+            Err(e) => {
+                println!("ERROR: Could not load {}: {}", aux_path, e);
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(r: &mut &[u8]) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not an aux data file"));
+        }
+        let version = read_u32(r)?;
+        if version > AUX_DATA_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Aux data version {} is newer than this build supports", version)));
+        }
+        let saved_at = Some(UNIX_EPOCH + Duration::from_secs(read_u64(r)?));
+
+        let mut sections = BTreeMap::new();
+        while !r.is_empty() {
+            let mut tag = [0; 4];
+            r.read_exact(&mut tag)?;
+            //Checked against what's left before anything is allocated, so a corrupt length can't ask for gigabytes
+            let length = read_u32(r)? as usize;
+            if length > r.len() {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Aux data section runs past the end of the file"));
+            }
+            let (section, rest) = r.split_at(length);
+            sections.insert(tag, section.to_vec());
+            *r = rest;
+        }
+
+        Ok(Self {
+            saved_at,
+            sections,
+        })
+    }
+
+    //Written to a temporary file first, so a crash part way through leaves the old file in place
+    pub fn save(&mut self, aux_path: &str) -> io::Result<()> {
+        let saved_at = SystemTime::now();
+        let mut file = Vec::new();
+        file.extend_from_slice(MAGIC);
+        write_u32(&mut file, AUX_DATA_VERSION)?;
+        write_u64(&mut file, saved_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())?;
+        for (tag, section) in &self.sections {
+            super::mapper::write_tagged_state(&mut file, tag, section)?;
+        }
+
+        let temp_path = aux_path.to_owned() + ".tmp";
+        fs::write(&temp_path, file)?;
+        fs::rename(temp_path, aux_path)?;
+        self.saved_at = Some(saved_at);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    //When the file was last written, so clocks can catch up on the time the emulator was closed. This and the section
+    //accessors are for mappers with a clock or EEPROM, which load and save their own sections
+    pub fn saved_at(&self) -> Option<SystemTime> {
+        self.saved_at
+    }
+
+    pub fn section(&self, tag: &[u8; 4]) -> Option<&[u8]> {
+        self.sections.get(tag).map(|section| section.as_slice())
+    }
+
+    //Replaces the section with this tag. Sections with other tags are left alone, even ones this build doesn't know
+    pub fn set_section(&mut self, tag: &[u8; 4], section: Vec<u8>) {
+        self.sections.insert(*tag, section);
+    }
+}
+
+pub fn rom_to_aux(rom_file_path: String) -> String {
+    if let Some(aux_file_path) = rom_file_path.rsplitn(2, ".").last() {
+        aux_file_path.to_owned() + ".aux"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
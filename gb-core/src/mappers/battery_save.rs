@@ -0,0 +1,149 @@
+use std::{fs::{self, File}, io::{self, Write}, sync::mpsc::{channel, RecvTimeoutError}, thread, time::{Duration, Instant}};
+
+use crate::{cartridge_info::{CartridgeError, CartridgeInfo}, fnv};
+
+use super::{rom_to_aux, rom_to_save, BatteryRam, BatterySink};
+
+//How long writes are gathered before the save file is replaced. Games often write a few bytes every frame
+const COMMIT_DELAY: Duration = Duration::from_millis(500);
+
+//Each commit writes the whole image plus a checksum to a journal file and syncs it before touching the .sav.
+//A crash while the .sav is being rewritten leaves a complete journal to recover from on the next load,
+//and a crash while the journal is being written leaves the old .sav untouched
+fn journal_path(save_path: &str) -> String {
+    save_path.to_owned() + ".journal"
+}
+
+//Where a cartridge's battery save and aux data are kept. The frontend opens them and hands the console the result,
+//so the core only ever sees bytes. The default has neither, for consoles that must not touch the file system
+#[derive(Default)]
+pub struct CartridgeStorage {
+    pub battery: Option<BatteryRam>,
+    pub aux_data_path: Option<String>,
+}
+
+impl CartridgeStorage {
+    //The .sav and .aux next to the rom
+    pub fn beside_rom(info: &CartridgeInfo, rom_file_path: &str) -> Result<Self, CartridgeError> {
+        let battery = match info.battery_ram_size() {
+            Some(ram_size) => Some(open(&rom_to_save(rom_file_path.to_owned()), ram_size).map_err(CartridgeError::BatterySave)?),
+            None => None,
+        };
+        Ok(Self {
+            battery,
+            aux_data_path: Some(rom_to_aux(rom_file_path.to_owned())),
+        })
+    }
+}
+
+//Loads the save and starts the thread that writes it back
+pub fn open(save_path: &str, ram_size: usize) -> io::Result<BatteryRam> {
+    let image = load(save_path, ram_size)?;
+    Ok(BatteryRam {
+        sink: spawn_writer(save_path.to_owned(), image.clone()),
+        image,
+    })
+}
+
+//Returns the last consistent image of the cartridge RAM, sized to ram_size. A journal with a valid checksum belongs to a
+//commit that may not have reached the .sav, so it's the newest image. A journal without one was torn and is thrown away.
+//A missing .sav is a new game, but one that can't be read is an error so it isn't overwritten with a blank save
+fn load(save_path: &str, ram_size: usize) -> io::Result<Vec<u8>> {
+    let journal_path = journal_path(save_path);
+    if let Ok(journal) = fs::read(&journal_path) {
+        match verify_journal(&journal) {
+            Some(image) => {
+                println!("Recovering battery save from an interrupted write: {}", journal_path);
+                if let Err(e) = fs::write(save_path, image).and_then(|_| fs::remove_file(&journal_path)) {
+                    println!("ERROR: Could not recover battery save {}: {}", save_path, e);
+                }
+            }
+            None => {
+                println!("Discarding incomplete battery save write: {}", journal_path);
+                let _ = fs::remove_file(&journal_path);
+            }
+        }
+    }
+
+    let mut image = match fs::read(save_path) {
+        Ok(image) => image,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    image.resize(ram_size, 0);
+    Ok(image)
+}
+
+//Keeps a copy of the RAM on the writer thread and applies each (value, address) sent to it. The copy is committed
+//COMMIT_DELAY after the first write that hasn't been saved yet, and when the sink is dropped
+fn spawn_writer(save_path: String, mut image: Vec<u8>) -> BatterySink {
+    let (sender, receiver) = channel::<(u8, u64)>();
+
+    let writer = thread::spawn(move || {
+        let mut first_unsaved_write: Option<Instant> = None;
+        loop {
+            let timeout = match first_unsaved_write {
+                Some(time) => COMMIT_DELAY.saturating_sub(time.elapsed()),
+                None => Duration::from_secs(3600),
+            };
+
+            match receiver.recv_timeout(timeout) {
+                Ok((value, address)) => {
+                    image[address as usize] = value;
+                    first_unsaved_write.get_or_insert_with(Instant::now);
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    if first_unsaved_write.is_some() {
+                        commit(&save_path, &image);
+                    }
+                    return;
+                }
+            }
+
+            if first_unsaved_write.take().is_some() {
+                commit(&save_path, &image);
+            }
+        }
+    });
+
+    BatterySink::with_writer(sender, writer)
+}
+
+fn commit(save_path: &str, image: &[u8]) {
+    if let Err(e) = try_commit(save_path, image) {
+        println!("ERROR: Could not write battery save {}: {}", save_path, e);
+    }
+}
+
+fn try_commit(save_path: &str, image: &[u8]) -> io::Result<()> {
+    let journal_path = journal_path(save_path);
+
+    let mut journal = File::create(&journal_path)?;
+    journal.write_all(image)?;
+    journal.write_all(&fnv::hash(image.iter().copied()).to_le_bytes())?;
+    journal.sync_all()?;
+    drop(journal);
+
+    let mut save = File::create(save_path)?;
+    save.write_all(image)?;
+    save.sync_all()?;
+    drop(save);
+
+    fs::remove_file(journal_path)
+}
+
+fn verify_journal(journal: &[u8]) -> Option<&[u8]> {
+    if journal.len() < 8 {
+        return None;
+    }
+
+    let (image, stored_checksum) = journal.split_at(journal.len() - 8);
+    if fnv::hash(image.iter().copied()).to_le_bytes() == stored_checksum {
+        Some(image)
+    }
+    else {
+        None
+    }
+}
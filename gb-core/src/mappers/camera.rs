@@ -0,0 +1,217 @@
+use std::{io::{self, Read, Write}};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_bool, read_u32, read_u8, write_bool, write_u32, write_u8}};
+
+use super::{BatteryRam, BatterySink, Mapper};
+
+//The sensor's picture, one byte of brightness per pixel from 0 (black) to 255 (white), row by row
+pub const CAMERA_WIDTH: usize = 128;
+pub const CAMERA_HEIGHT: usize = 112;
+
+const REGISTER_COUNT: usize = 0x36;
+const IMAGE_OFFSET: usize = 0x100; //Where captures land in RAM bank 0, as 16x14 tiles
+const DITHER_MATRIX: usize = 0x06; //4x4 pixels, 3 thresholds each
+const EXPOSURE_NEUTRAL: u32 = 0x0800; //Exposure that passes the sensor's brightness through unchanged
+
+//The Pocket Camera's MAC-GBD mapper. Bit 4 of the RAM bank register swaps cartridge RAM for the camera's registers
+//(CRAM), mirrored every 0x80 bytes. Writing 1 to bit 0 of A000 takes a picture, which clears once it's in RAM. The
+//sensor's analog processing is reduced to exposure scaling, then the 4x4 dither matrix turns it into 2bpp tiles
+pub struct Camera {
+    rom_banks: Vec<[u8; 0x4000]>,
+    aux_rom_bank_index: usize,
+    ram_banks: Vec<u8>,
+    ram_bank_offset: usize,
+    save_sender: Option<BatterySink>,
+    ram_enabled: bool,
+    is_cram_mapped: bool,
+    registers: [u8; REGISTER_COUNT],
+    capture_cycles_left: u32, //0 when no picture is being taken
+    image: Vec<u8>, //What the sensor sees. Input, like the joypad, so it isn't part of savestates
+}
+
+impl Camera {
+    pub fn new(rom: &[u8], rom_bank_count: usize, ram_bank_count: usize, battery: Option<BatteryRam>) -> Result<Self, CartridgeError> {
+        let rom_banks = super::mapper::prepare_rom(rom, rom_bank_count)?;
+        let (ram_banks, save_sender) = super::mapper::battery_ram(battery, ram_bank_count * 0x2000);
+
+        Ok(Self {
+            rom_banks,
+            aux_rom_bank_index: 1,
+            ram_banks,
+            ram_bank_offset: 0,
+            save_sender,
+            ram_enabled: false,
+            is_cram_mapped: false,
+            registers: [0; REGISTER_COUNT],
+            capture_cycles_left: 0,
+            image: Self::test_pattern(),
+        })
+    }
+
+    //Shown until a picture is given, so the viewfinder isn't a flat color. Light at the top, dark at the bottom
+    fn test_pattern() -> Vec<u8> {
+        (0..CAMERA_WIDTH * CAMERA_HEIGHT).map(|index| {
+            let (x, y) = (index % CAMERA_WIDTH, index / CAMERA_WIDTH);
+            let checker = if (x / 16 + y / 16) % 2 == 0 {0} else {32};
+            (224 - y * 192 / CAMERA_HEIGHT + checker) as u8
+        }).collect()
+    }
+
+    //N (bit 7 of A001) skips a step of the readout. The rest of the time is the exposure, in 16 m-cycle units
+    fn capture_cycles(&self) -> u32 {
+        let exposure = u16::from_be_bytes([self.registers[2], self.registers[3]]) as u32;
+        32448 + if self.registers[1] & 0x80 != 0 {0} else {512} + exposure * 16
+    }
+
+    fn capture(&mut self) {
+        let exposure = u16::from_be_bytes([self.registers[2], self.registers[3]]) as u32;
+        for tile_y in 0..CAMERA_HEIGHT / 8 {
+            for tile_x in 0..CAMERA_WIDTH / 8 {
+                for row in 0..8 {
+                    let y = tile_y * 8 + row;
+                    let (mut low, mut high) = (0, 0);
+                    for x in tile_x * 8..tile_x * 8 + 8 {
+                        let brightness = (self.image[y * CAMERA_WIDTH + x] as u32 * exposure / EXPOSURE_NEUTRAL).min(0xFF) as u8;
+                        let thresholds = &self.registers[DITHER_MATRIX + ((y & 3) * 4 + (x & 3)) * 3..][..3];
+                        let shade = thresholds.iter().filter(|threshold| brightness < **threshold).count() as u8;
+                        low = low << 1 | (shade & 1);
+                        high = high << 1 | (shade >> 1);
+                    }
+                    let offset = IMAGE_OFFSET + (tile_y * CAMERA_WIDTH / 8 + tile_x) * 16 + row * 2;
+                    if offset + 1 < self.ram_banks.len() {
+                        self.write_ram(offset, low);
+                        self.write_ram(offset + 1, high);
+                    }
+                }
+            }
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        let offset = self.ram_bank_offset + (address - 0xA000) as usize;
+        (offset < self.ram_banks.len()).then_some(offset)
+    }
+}
+
+impl Mapper for Camera {
+    fn read(&self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_banks[0][address as usize],
+            0x4000..=0x7FFF => self.rom_banks[self.aux_rom_bank_index][(address - 0x4000) as usize],
+            //Only A000 reads back, with bit 0 set while a picture is being taken
+            0xA000..=0xBFFF if self.is_cram_mapped => if address & 0x7F == 0 {self.registers[0] & 0x07} else {0x00},
+            //The sensor's busy writing RAM
+            0xA000..=0xBFFF if self.capture_cycles_left > 0 => 0x00,
+            0xA000..=0xBFFF => self.ram_offset(address).map_or(0xFF, |offset| self.ram_banks[offset]),
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0xF == 0xA,
+            0x2000..=0x3FFF => self.aux_rom_bank_index = (value & 0x3F) as usize % self.rom_banks.len(),
+            0x4000..=0x5FFF => {
+                self.is_cram_mapped = value & 0x10 != 0;
+                let bank_count = (self.ram_banks.len() / 0x2000).max(1);
+                self.ram_bank_offset = (value & 0xF) as usize % bank_count * 0x2000;
+            }
+            0x6000..=0x7FFF => {}
+            0xA000..=0xBFFF if self.is_cram_mapped => {
+                let register = (address & 0x7F) as usize;
+                if register == 0 {
+                    self.registers[0] = value & 0x07;
+                    if value & 1 != 0 && self.capture_cycles_left == 0 {
+                        self.capture_cycles_left = self.capture_cycles();
+                    }
+                }
+                else if register < REGISTER_COUNT {
+                    self.registers[register] = value;
+                }
+            }
+            0xA000..=0xBFFF if self.ram_enabled && self.capture_cycles_left == 0 => {
+                if let Some(offset) = self.ram_offset(address) {
+                    self.write_ram(offset, value);
+                }
+            }
+            0xA000..=0xBFFF => {}
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        self.rom_banks.as_flattened_mut()
+    }
+
+    fn ram(&self) -> Option<&[u8]> {
+        (!self.ram_banks.is_empty()).then_some(&self.ram_banks[..])
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        self.ram_banks[offset] = value;
+        if let Some(sender) = &self.save_sender {
+            sender.send((value, offset as u64)).unwrap();
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.capture_cycles_left > 0 {
+            self.capture_cycles_left -= 1;
+            if self.capture_cycles_left == 0 {
+                self.capture();
+                self.registers[0] &= !1;
+            }
+        }
+    }
+
+    fn set_camera_image(&mut self, image: &[u8]) {
+        if image.is_empty() {
+            self.image = Self::test_pattern();
+        }
+        else if image.len() == CAMERA_WIDTH * CAMERA_HEIGHT {
+            self.image = image.to_vec();
+        }
+    }
+
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        vec![("ROM bank", self.aux_rom_bank_index as u8), ("RAM bank", (self.ram_bank_offset / 0x2000) as u8), ("RAM enable", self.ram_enabled as u8),
+             ("CRAM mapped", self.is_cram_mapped as u8), ("A000", self.registers[0]), ("A001", self.registers[1]),
+             ("Exposure high", self.registers[2]), ("Exposure low", self.registers[3])]
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut state = Vec::new();
+        write_u8(&mut state, self.aux_rom_bank_index as u8)?;
+        write_u8(&mut state, (self.ram_bank_offset / 0x2000) as u8)?;
+        write_bool(&mut state, self.ram_enabled)?;
+        write_bool(&mut state, self.is_cram_mapped)?;
+        state.extend_from_slice(&self.registers);
+        write_u32(&mut state, self.capture_cycles_left)?;
+        state.extend_from_slice(&self.ram_banks);
+
+        super::mapper::write_tagged_state(w, b"CAMR", &state)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let state = super::mapper::read_tagged_state(r, b"CAMR")?;
+        let mut r = &state[..];
+
+        self.aux_rom_bank_index = read_u8(&mut r)? as usize % self.rom_banks.len();
+        let bank_count = (self.ram_banks.len() / 0x2000).max(1);
+        self.ram_bank_offset = read_u8(&mut r)? as usize % bank_count * 0x2000;
+        self.ram_enabled = read_bool(&mut r)?;
+        self.is_cram_mapped = read_bool(&mut r)?;
+        r.read_exact(&mut self.registers)?;
+        self.capture_cycles_left = read_u32(&mut r)?;
+        let mut new_ram = vec![0; self.ram_banks.len()];
+        r.read_exact(&mut new_ram)?;
+        super::mapper::sync_save(&self.save_sender, &self.ram_banks, &new_ram);
+        self.ram_banks = new_ram;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::{self, Read, Write};
+
+use crate::cheats::GameGenieCode;
+
+use super::{AuxData, Mapper};
+
+//Sits between the console and the cartridge like the real device, swapping bytes as they're read from ROM. With no
+//codes it passes everything straight through. Codes aren't part of the cartridge's state, so savestates don't keep them
+pub struct GameGenie {
+    cartridge: Box<dyn Mapper>,
+    codes: Vec<GameGenieCode>,
+}
+
+impl GameGenie {
+    pub fn new(cartridge: Box<dyn Mapper>) -> Self {
+        Self {
+            cartridge,
+            codes: Vec::new(),
+        }
+    }
+
+    pub fn set_codes(&mut self, codes: Vec<GameGenieCode>) {
+        self.codes = codes;
+    }
+
+    //Swaps the cartridge underneath, keeping the codes entered
+    pub fn insert(&mut self, cartridge: Box<dyn Mapper>) {
+        self.cartridge = cartridge;
+    }
+}
+
+impl Mapper for GameGenie {
+    fn read(&self, address: u16) -> u8 {
+        let value = self.cartridge.read(address);
+        if address < 0x8000 {
+            let patch = self.codes.iter().find(|code| code.address == address && code.compare.map_or(true, |compare| compare == value));
+            if let Some(code) = patch {
+                return code.value;
+            }
+        }
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.cartridge.write(address, value);
+    }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        self.cartridge.rom_mut()
+    }
+
+    fn ram(&self) -> Option<&[u8]> {
+        self.cartridge.ram()
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.cartridge.is_ram_enabled()
+    }
+
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        self.cartridge.write_ram(offset, value);
+    }
+
+    fn tick(&mut self) {
+        self.cartridge.tick();
+    }
+
+    fn set_tilt(&mut self, x: f32, y: f32) {
+        self.cartridge.set_tilt(x, y);
+    }
+
+    fn set_camera_image(&mut self, image: &[u8]) {
+        self.cartridge.set_camera_image(image);
+    }
+
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        self.cartridge.registers()
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        self.cartridge.save_state(w)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        self.cartridge.load_state(r)
+    }
+
+    fn load_aux_data(&mut self, aux_data: &AuxData) {
+        self.cartridge.load_aux_data(aux_data);
+    }
+
+    fn save_aux_data(&self, aux_data: &mut AuxData) {
+        self.cartridge.save_aux_data(aux_data);
+    }
+}
@@ -0,0 +1,120 @@
+use std::{io::{self, Read, Write}};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_bool, read_u8, write_bool, write_u8}};
+
+use super::{BatteryRam, BatterySink};
+
+//Hudson's mapper with an infrared port, used by Pokémon Card GB among others. Banking works like a simpler MBC1.
+//Instead of a RAM enable, 0x0000-0x1FFF switches 0xA000-0xBFFF between cartridge RAM and the infrared port. Nothing
+//is ever on the other end of the port, so the receiver never sees light
+pub struct HuC1 {
+    rom_banks: Vec<[u8; 0x4000]>,
+    aux_rom_bank_index: usize,
+    ram_banks: Vec<u8>,
+    ram_bank_offset: usize,
+    save_sender: Option<BatterySink>,
+    is_infrared_mode: bool,
+    is_led_on: bool,
+}
+
+impl HuC1 {
+    pub fn new(rom: &[u8], rom_bank_count: usize, ram_bank_count: usize, battery: Option<BatteryRam>) -> Result<Self, CartridgeError> {
+        let rom_banks = super::mapper::prepare_rom(rom, rom_bank_count)?;
+        let (ram_banks, save_sender) = super::mapper::battery_ram(battery, ram_bank_count * 0x2000);
+
+        Ok(Self {
+            rom_banks,
+            aux_rom_bank_index: 1,
+            ram_banks,
+            ram_bank_offset: 0,
+            save_sender,
+            is_infrared_mode: false,
+            is_led_on: false,
+        })
+    }
+}
+
+impl super::Mapper for HuC1 {
+    fn read(&self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_banks[0][address as usize],
+            0x4000..=0x7FFF => self.rom_banks[self.aux_rom_bank_index][(address - 0x4000) as usize],
+            //Bit 0 is set while the receiver sees light
+            0xA000..=0xBFFF if self.is_infrared_mode => 0xC0,
+            0xA000..=0xBFFF => self.ram_banks.get(self.ram_bank_offset + (address - 0xA000) as usize).copied().unwrap_or(0xFF),
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.is_infrared_mode = value & 0xF == 0xE,
+            0x2000..=0x3FFF => self.aux_rom_bank_index = ((value & 0x3F) as usize).max(1) % self.rom_banks.len(),
+            0x4000..=0x5FFF => {
+                let bank_count = (self.ram_banks.len() / 0x2000).max(1);
+                self.ram_bank_offset = (value & 0b11) as usize % bank_count * 0x2000;
+            }
+            0x6000..=0x7FFF => {}
+            0xA000..=0xBFFF if self.is_infrared_mode => self.is_led_on = value & 1 != 0,
+            0xA000..=0xBFFF => {
+                let offset = self.ram_bank_offset + (address - 0xA000) as usize;
+                if offset < self.ram_banks.len() {
+                    self.write_ram(offset, value);
+                }
+            }
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        self.rom_banks.as_flattened_mut()
+    }
+
+    fn ram(&self) -> Option<&[u8]> {
+        (!self.ram_banks.is_empty()).then_some(&self.ram_banks[..])
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        !self.is_infrared_mode
+    }
+
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        self.ram_banks[offset] = value;
+        if let Some(sender) = &self.save_sender {
+            sender.send((value, offset as u64)).unwrap();
+        }
+    }
+
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        vec![("ROM bank", self.aux_rom_bank_index as u8), ("RAM bank", (self.ram_bank_offset / 0x2000) as u8),
+             ("IR mode", self.is_infrared_mode as u8), ("IR LED", self.is_led_on as u8)]
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut state = Vec::new();
+        write_u8(&mut state, self.aux_rom_bank_index as u8)?;
+        write_u8(&mut state, (self.ram_bank_offset / 0x2000) as u8)?;
+        write_bool(&mut state, self.is_infrared_mode)?;
+        write_bool(&mut state, self.is_led_on)?;
+        state.extend_from_slice(&self.ram_banks);
+
+        super::mapper::write_tagged_state(w, b"HUC1", &state)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let state = super::mapper::read_tagged_state(r, b"HUC1")?;
+        let mut r = &state[..];
+
+        self.aux_rom_bank_index = read_u8(&mut r)? as usize % self.rom_banks.len();
+        let bank_count = (self.ram_banks.len() / 0x2000).max(1);
+        self.ram_bank_offset = read_u8(&mut r)? as usize % bank_count * 0x2000;
+        self.is_infrared_mode = read_bool(&mut r)?;
+        self.is_led_on = read_bool(&mut r)?;
+        let mut new_ram = vec![0; self.ram_banks.len()];
+        r.read_exact(&mut new_ram)?;
+        super::mapper::sync_save(&self.save_sender, &self.ram_banks, &new_ram);
+        self.ram_banks = new_ram;
+
+        Ok(())
+    }
+}
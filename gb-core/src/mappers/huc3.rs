@@ -0,0 +1,270 @@
+use std::{io::{self, Read, Write}, time::SystemTime};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_bool, read_u16, read_u32, read_u8, write_bool, write_u16, write_u32, write_u8}};
+
+use super::{AuxData, BatteryRam, BatterySink};
+
+const M_CYCLES_PER_MINUTE: u32 = 1_048_576 * 60;
+const MINUTES_PER_DAY: u64 = 1440;
+const CLOCK_TAG: &[u8; 4] = b"HUC3";
+
+//Hudson's mapper with a real time clock and an infrared port, used by Robopon among others. The low nibble written to
+//0x0000-0x1FFF picks what 0xA000-0xBFFF is: cartridge RAM (read only in mode 0, writable in mode A), clock commands
+//(B), clock responses (C), clock status (D) or the infrared port (E). The clock counts minutes of the day and days in
+//12 bits each, and is read and written a nibble at a time through an address register
+pub struct HuC3 {
+    rom_banks: Vec<[u8; 0x4000]>,
+    aux_rom_bank_index: usize,
+    ram_banks: Vec<u8>,
+    ram_bank_offset: usize,
+    save_sender: Option<BatterySink>,
+    mode: u8,
+    clock: Clock,
+    clock_address: u8,
+    clock_response: u8, //The nibble the last read command fetched
+    clock_flags: u8, //Set by command 6
+    is_led_on: bool,
+}
+
+//Kept in the aux file. The alarm is stored for the game but never rings, since nothing on the cartridge is wired to it
+#[derive(Clone, Copy, Default)]
+struct Clock {
+    minutes: u16,
+    days: u16,
+    cycles: u32, //M-cycles into the current minute
+    alarm_minutes: u16,
+    alarm_days: u16,
+    is_alarm_enabled: bool,
+}
+
+impl Clock {
+    fn advance(&mut self, cycles: u64) {
+        let total_cycles = self.cycles as u64 + cycles;
+        self.cycles = (total_cycles % M_CYCLES_PER_MINUTE as u64) as u32;
+        let minutes = self.minutes as u64 + total_cycles / M_CYCLES_PER_MINUTE as u64;
+        self.minutes = (minutes % MINUTES_PER_DAY) as u16;
+        self.days = ((self.days as u64 + minutes / MINUTES_PER_DAY) & 0xFFF) as u16;
+    }
+
+    //Nibbles 0-2 are the minutes and 3-5 the days. The alarm sits at 0x58-0x5E
+    fn register_mut(&mut self, address: u8) -> Option<(&mut u16, u8)> {
+        match address {
+            0x00..=0x02 => Some((&mut self.minutes, address)),
+            0x03..=0x05 => Some((&mut self.days, address - 0x03)),
+            0x58..=0x5A => Some((&mut self.alarm_minutes, address - 0x58)),
+            0x5B..=0x5D => Some((&mut self.alarm_days, address - 0x5B)),
+            _ => None,
+        }
+    }
+
+    fn read_nibble(&mut self, address: u8) -> u8 {
+        match self.register_mut(address) {
+            Some((register, nibble)) => (*register >> (nibble * 4)) as u8 & 0xF,
+            None if address == 0x5E => self.is_alarm_enabled as u8,
+            None => 0x0,
+        }
+    }
+
+    fn write_nibble(&mut self, address: u8, value: u8) {
+        match self.register_mut(address) {
+            Some((register, nibble)) => *register = (*register & !(0xF << (nibble * 4))) | ((value as u16 & 0xF) << (nibble * 4)),
+            None if address == 0x5E => self.is_alarm_enabled = value & 1 != 0,
+            None => {}
+        }
+    }
+
+    fn write(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u16(w, self.minutes)?;
+        write_u16(w, self.days)?;
+        write_u32(w, self.cycles)?;
+        write_u16(w, self.alarm_minutes)?;
+        write_u16(w, self.alarm_days)?;
+        write_bool(w, self.is_alarm_enabled)
+    }
+
+    fn read(r: &mut dyn Read) -> io::Result<Self> {
+        Ok(Self {
+            minutes: read_u16(r)?,
+            days: read_u16(r)?,
+            cycles: read_u32(r)? % M_CYCLES_PER_MINUTE,
+            alarm_minutes: read_u16(r)?,
+            alarm_days: read_u16(r)?,
+            is_alarm_enabled: read_bool(r)?,
+        })
+    }
+}
+
+impl HuC3 {
+    pub fn new(rom: &[u8], rom_bank_count: usize, ram_bank_count: usize, battery: Option<BatteryRam>) -> Result<Self, CartridgeError> {
+        let rom_banks = super::mapper::prepare_rom(rom, rom_bank_count)?;
+        let (ram_banks, save_sender) = super::mapper::battery_ram(battery, ram_bank_count * 0x2000);
+
+        Ok(Self {
+            rom_banks,
+            aux_rom_bank_index: 1,
+            ram_banks,
+            ram_bank_offset: 0,
+            save_sender,
+            mode: 0x0,
+            clock: Clock::default(),
+            clock_address: 0,
+            clock_response: 0,
+            clock_flags: 0,
+            is_led_on: false,
+        })
+    }
+
+    //The high nibble is the command and the low one its argument
+    fn run_clock_command(&mut self, value: u8) {
+        let argument = value & 0xF;
+        match value >> 4 & 0x7 {
+            0x1 => {
+                self.clock_response = self.clock.read_nibble(self.clock_address);
+                self.clock_address = self.clock_address.wrapping_add(1);
+            }
+            0x2 => self.clock.write_nibble(self.clock_address, argument),
+            0x3 => {
+                self.clock.write_nibble(self.clock_address, argument);
+                self.clock_address = self.clock_address.wrapping_add(1);
+            }
+            0x4 => self.clock_address = (self.clock_address & 0xF0) | argument,
+            0x5 => self.clock_address = (self.clock_address & 0x0F) | argument << 4,
+            0x6 => self.clock_flags = argument,
+            _ => {}
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        let offset = self.ram_bank_offset + (address - 0xA000) as usize;
+        (offset < self.ram_banks.len()).then_some(offset)
+    }
+}
+
+impl super::Mapper for HuC3 {
+    fn read(&self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_banks[0][address as usize],
+            0x4000..=0x7FFF => self.rom_banks[self.aux_rom_bank_index][(address - 0x4000) as usize],
+            0xA000..=0xBFFF => match self.mode {
+                0x0 | 0xA => self.ram_offset(address).map_or(0xFF, |offset| self.ram_banks[offset]),
+                0xC if self.clock_flags == 0x2 => 0x1,
+                0xC => self.clock_response,
+                0xE => 0xC0, //No light on the receiver
+                _ => 0x1, //Ready for the next command
+            },
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.mode = value & 0xF,
+            0x2000..=0x3FFF => self.aux_rom_bank_index = ((value & 0x7F) as usize).max(1) % self.rom_banks.len(),
+            0x4000..=0x5FFF => {
+                let bank_count = (self.ram_banks.len() / 0x2000).max(1);
+                self.ram_bank_offset = (value & 0b11) as usize % bank_count * 0x2000;
+            }
+            0x6000..=0x7FFF => {}
+            0xA000..=0xBFFF => match self.mode {
+                0xA => {
+                    if let Some(offset) = self.ram_offset(address) {
+                        self.write_ram(offset, value);
+                    }
+                }
+                0xB => self.run_clock_command(value),
+                0xE => self.is_led_on = value & 1 != 0,
+                _ => {}
+            },
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        self.rom_banks.as_flattened_mut()
+    }
+
+    fn ram(&self) -> Option<&[u8]> {
+        (!self.ram_banks.is_empty()).then_some(&self.ram_banks[..])
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.mode == 0xA
+    }
+
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        self.ram_banks[offset] = value;
+        if let Some(sender) = &self.save_sender {
+            sender.send((value, offset as u64)).unwrap();
+        }
+    }
+
+    fn tick(&mut self) {
+        self.clock.cycles += 1;
+        if self.clock.cycles == M_CYCLES_PER_MINUTE {
+            self.clock.advance(0);
+        }
+    }
+
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        vec![("ROM bank", self.aux_rom_bank_index as u8), ("RAM bank", (self.ram_bank_offset / 0x2000) as u8), ("Mode", self.mode),
+             ("Clock address", self.clock_address), ("IR LED", self.is_led_on as u8)]
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut state = Vec::new();
+        write_u8(&mut state, self.aux_rom_bank_index as u8)?;
+        write_u8(&mut state, (self.ram_bank_offset / 0x2000) as u8)?;
+        write_u8(&mut state, self.mode)?;
+        self.clock.write(&mut state)?;
+        write_u8(&mut state, self.clock_address)?;
+        write_u8(&mut state, self.clock_response)?;
+        write_u8(&mut state, self.clock_flags)?;
+        write_bool(&mut state, self.is_led_on)?;
+        state.extend_from_slice(&self.ram_banks);
+
+        super::mapper::write_tagged_state(w, b"HUC3", &state)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let state = super::mapper::read_tagged_state(r, b"HUC3")?;
+        let mut r = &state[..];
+
+        self.aux_rom_bank_index = read_u8(&mut r)? as usize % self.rom_banks.len();
+        let bank_count = (self.ram_banks.len() / 0x2000).max(1);
+        self.ram_bank_offset = read_u8(&mut r)? as usize % bank_count * 0x2000;
+        self.mode = read_u8(&mut r)?;
+        self.clock = Clock::read(&mut r)?;
+        self.clock_address = read_u8(&mut r)?;
+        self.clock_response = read_u8(&mut r)?;
+        self.clock_flags = read_u8(&mut r)?;
+        self.is_led_on = read_bool(&mut r)?;
+        let mut new_ram = vec![0; self.ram_banks.len()];
+        r.read_exact(&mut new_ram)?;
+        super::mapper::sync_save(&self.save_sender, &self.ram_banks, &new_ram);
+        self.ram_banks = new_ram;
+
+        Ok(())
+    }
+
+    //The clock catches up on however long the emulator was closed
+    fn load_aux_data(&mut self, aux_data: &AuxData) {
+        let Some(mut section) = aux_data.section(CLOCK_TAG) else {
+            return;
+        };
+        match Clock::read(&mut section) {
+            Ok(clock) => self.clock = clock,
+            Err(e) => {
+                println!("ERROR: Could not load the HuC3 clock: {}", e);
+                return;
+            }
+        }
+        let elapsed = aux_data.saved_at().and_then(|saved_at| SystemTime::now().duration_since(saved_at).ok()).unwrap_or_default();
+        self.clock.advance(elapsed.as_secs() * (M_CYCLES_PER_MINUTE / 60) as u64);
+    }
+
+    fn save_aux_data(&self, aux_data: &mut AuxData) {
+        let mut section = Vec::new();
+        self.clock.write(&mut section).unwrap();
+        aux_data.set_section(CLOCK_TAG, section);
+    }
+}
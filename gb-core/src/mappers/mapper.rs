@@ -0,0 +1,143 @@
+use std::{io::{self, Read, Write}, sync::mpsc::{SendError, Sender}, thread::JoinHandle};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_u32, write_u32}};
+
+use super::AuxData;
+
+pub trait Mapper: Send {
+    fn read(&self, address: u16) -> u8;
+    fn write(&mut self, address: u16, value: u8);
+    //The whole rom, ignoring banking, for tools that patch it while the game runs
+    fn rom_mut(&mut self) -> &mut [u8];
+    //Returns the entirety of the cartridge's external RAM, ignoring banking and RAM enable
+    fn ram(&self) -> Option<&[u8]>;
+    //Whether the game has cartridge RAM enabled right now. Games usually only enable it while saving
+    fn is_ram_enabled(&self) -> bool;
+    //Writes straight to external RAM, ignoring banking and RAM enable. Offsets index into ram()
+    fn write_ram(&mut self, offset: usize, value: u8);
+    //Advances anything on the cartridge that keeps time on its own, like a real time clock. Called every m-cycle.
+    //Clocks must count emulated cycles instead of reading the host clock so savestates, movies and netplay stay
+    //deterministic. The host clock is only used when the save is loaded, to catch up on the time the emulator was closed
+    fn tick(&mut self) {}
+    //How far the player is tilting the console, for cartridges with an accelerometer. In g, with x positive to the right
+    //and y positive towards the player
+    fn set_tilt(&mut self, _x: f32, _y: f32) {}
+    //What the camera sees, for cartridges with one. CAMERA_WIDTH x CAMERA_HEIGHT bytes of brightness, 0 being black.
+    //Empty goes back to the cartridge's own picture
+    fn set_camera_image(&mut self, _image: &[u8]) {}
+    //The banking registers by name, for tools that show the cartridge's state
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        Vec::new()
+    }
+    //Banking registers, RAM enable, cartridge RAM and anything else that changes while the game runs.
+    //Written with write_tagged_state so each mapper's state is labeled with its type and length
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()>;
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()>;
+    //Battery-backed state besides cartridge RAM, like a real time clock or an EEPROM, kept in the .aux file.
+    //Each peripheral gets its own section, named by a tag. Loaded when the cartridge is inserted and saved when it's removed
+    fn load_aux_data(&mut self, _aux_data: &AuxData) {}
+    fn save_aux_data(&self, _aux_data: &mut AuxData) {}
+}
+
+//Wraps a mapper's state in a tag naming the mapper and the length of what follows. Loading the state into the
+//wrong kind of cartridge fails instead of scrambling it, and newer versions can append fields that older ones skip
+pub fn write_tagged_state(w: &mut dyn Write, tag: &[u8; 4], state: &[u8]) -> io::Result<()> {
+    w.write_all(tag)?;
+    write_u32(w, state.len() as u32)?;
+    w.write_all(state)
+}
+
+pub fn read_tagged_state(r: &mut dyn Read, tag: &[u8; 4]) -> io::Result<Vec<u8>> {
+    let mut state_tag = [0; 4];
+    r.read_exact(&mut state_tag)?;
+    if &state_tag != tag {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Savestate is for a {} cartridge, not {}",
+            String::from_utf8_lossy(&state_tag), String::from_utf8_lossy(tag))));
+    }
+
+    let mut state = vec![0; read_u32(r)? as usize];
+    r.read_exact(&mut state)?;
+    Ok(state)
+}
+
+//Cartridge RAM kept by a battery. The mapper starts from the image and sends each write to the sink as (value, offset).
+//Whoever opened the save decides where the writes end up, so mappers never touch the file system
+pub struct BatteryRam {
+    pub image: Vec<u8>,
+    pub sink: BatterySink,
+}
+
+//Where a mapper's battery RAM writes go. If a thread writes them to disk, dropping the sink waits for it to commit the
+//last of them, so removing the cartridge or quitting right after the game saves doesn't lose the save
+pub struct BatterySink {
+    sender: Option<Sender<(u8, u64)>>, //Only None while dropping
+    writer: Option<JoinHandle<()>>,
+}
+
+impl BatterySink {
+    pub fn new(sender: Sender<(u8, u64)>) -> Self {
+        Self { sender: Some(sender), writer: None }
+    }
+
+    //The writer must return once the sender is dropped
+    pub(super) fn with_writer(sender: Sender<(u8, u64)>, writer: JoinHandle<()>) -> Self {
+        Self { sender: Some(sender), writer: Some(writer) }
+    }
+
+    pub fn send(&self, write: (u8, u64)) -> Result<(), SendError<(u8, u64)>> {
+        match &self.sender {
+            Some(sender) => sender.send(write),
+            None => Err(SendError(write)),
+        }
+    }
+}
+
+impl Drop for BatterySink {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                println!("ERROR: The battery save writer crashed");
+            }
+        }
+    }
+}
+
+//Splits a mapper's battery into its starting RAM, sized to ram_size, and the sink. Without one the RAM starts cleared
+pub fn battery_ram(battery: Option<BatteryRam>, ram_size: usize) -> (Vec<u8>, Option<BatterySink>) {
+    match battery {
+        Some(BatteryRam { mut image, sink }) => {
+            image.resize(ram_size, 0);
+            (image, Some(sink))
+        }
+        None => (vec![0; ram_size], None),
+    }
+}
+
+//Sends the bytes that a loaded state changed to the battery save, so the .sav file keeps matching the RAM
+pub fn sync_save(save_sender: &Option<BatterySink>, old_ram: &[u8], new_ram: &[u8]) {
+    if let Some(sender) = save_sender {
+        for (address, (old, new)) in old_ram.iter().zip(new_ram.iter()).enumerate() {
+            if old != new {
+                sender.send((*new, address as u64)).unwrap();
+            }
+        }
+    }
+}
+
+//Splits the rom into 16KB banks
+pub fn prepare_rom(rom: &[u8], rom_bank_count: usize) -> Result<Vec<[u8; 0x4000]>, CartridgeError> {
+    if rom.len() < rom_bank_count * 0x4000 {
+        return Err(CartridgeError::Truncated {expected: rom_bank_count * 0x4000, actual: rom.len()});
+    }
+    Ok(rom.chunks_exact(0x4000).take(rom_bank_count).map(|bank| bank.try_into().unwrap()).collect())
+}
+
+pub fn rom_to_save(rom_file_path: String) -> String {
+    if let Some(ram_file_path) = rom_file_path.rsplitn(2, ".").last() {
+        ram_file_path.to_owned() + ".sav"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,204 @@
+use std::{io::{self, Read, Write}};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_bool, read_u8, write_bool, write_u8}};
+
+use super::{BatteryRam, BatterySink};
+
+//Multicarts (MBC1M) wire BANK2 to rom bank bits 4-5 instead of 5-6, so each 256KB game sees its own 16 banks
+const MULTICART_ROM_BANK_COUNT: usize = 64;
+const MULTICART_GAME_BANKS: usize = 0x10;
+
+pub struct MBC1 {
+    rom_banks: Vec<[u8; 0x4000]>,
+    rom_bank_0_index: usize, //The bank at 0x0000-0x3FFF, only moved by BANK2 in mode 1
+    aux_rom_bank_index: usize,
+    rom_bank_register: u8, //BANK1, the lower 5 bits of the rom bank
+    bank_2_register: u8, //BANK2, the upper 2 bits of the rom bank, or the ram bank
+    is_advanced_banking: bool, //Mode 1, where BANK2 also switches 0x0000-0x3FFF and the ram bank
+    is_multicart: bool,
+    ram_banks: Option<Vec<u8>>,
+    ram_bank_offset: usize,
+    save_sender: Option<BatterySink>,
+    ram_enabled: bool,
+}
+
+impl MBC1 {
+    pub fn new(rom: &[u8], rom_bank_count: usize, ram_bank_count: usize, battery: Option<BatteryRam>) -> Result<Self, CartridgeError> {
+        let rom_banks = super::mapper::prepare_rom(rom, rom_bank_count)?;
+        let is_multicart = Self::is_multicart(&rom_banks);
+
+        let mut save_sender_temp = None;
+        let ram_banks;
+        if ram_bank_count == 0 {
+            ram_banks = None;
+        }
+        else {
+            let ram_bank_vec;
+            (ram_bank_vec, save_sender_temp) = super::mapper::battery_ram(battery, ram_bank_count * 0x2000);
+            ram_banks = Some(ram_bank_vec);
+        }
+
+        Ok(Self {
+            rom_banks: rom_banks,
+            rom_bank_0_index: 0,
+            aux_rom_bank_index: 1,
+            rom_bank_register: 1,
+            bank_2_register: 0,
+            is_advanced_banking: false,
+            is_multicart,
+            ram_banks: ram_banks,
+            ram_bank_offset: 0,
+            save_sender: save_sender_temp,
+            ram_enabled: true
+        })
+    }
+
+    //Multicarts are 1MB and have a second copy of the Nintendo logo at the start of the second game, which is
+    //what their menus check for. Single games that size only have the one
+    fn is_multicart(rom_banks: &[[u8; 0x4000]]) -> bool {
+        rom_banks.len() == MULTICART_ROM_BANK_COUNT &&
+            rom_banks[0][0x104..0x134] == rom_banks[MULTICART_GAME_BANKS][0x104..0x134]
+    }
+
+    //Unconnected bank lines are ignored, so bank numbers wrap around the actual rom and ram sizes
+    fn update_banks(&mut self) {
+        //On multicarts BANK1's top bit isn't connected, but it still counts for the 0 to 1 translation
+        let (bank_1, bank_2) = if self.is_multicart {
+            (self.rom_bank_register & 0b1111, (self.bank_2_register << 4) as usize)
+        }
+        else {
+            (self.rom_bank_register, (self.bank_2_register << 5) as usize)
+        };
+        self.aux_rom_bank_index = (bank_2 | bank_1 as usize) % self.rom_banks.len();
+        self.rom_bank_0_index = if self.is_advanced_banking {bank_2 % self.rom_banks.len()} else {0};
+
+        let ram_bank_count = self.ram_banks.as_ref().map_or(0, |ram_banks| ram_banks.len() / 0x2000);
+        self.ram_bank_offset = if ram_bank_count > 0 && self.is_advanced_banking {
+            (self.bank_2_register as usize % ram_bank_count) * 0x2000
+        }
+        else {
+            0
+        };
+    }
+}
+
+impl super::Mapper for MBC1 {
+    fn read(&self, address: u16) -> u8 {
+        if address <= 0x3FFF {
+            self.rom_banks[self.rom_bank_0_index][address as usize]
+        }
+        else if address <= 0x7FFF {
+            self.rom_banks[self.aux_rom_bank_index][(address - 0x4000) as usize]
+        }
+        else if address >= 0xA000 && address <= 0xBFFF {
+            if self.ram_enabled {
+                match &self.ram_banks {
+                    Some(ram_banks) => ram_banks[self.ram_bank_offset + (address - 0xA000) as usize],
+                    None => 0xFF //I'm not sure what happens when you try to read ram without having it, so I'm having it act like disabled ram
+                }
+            }
+            else {
+                0xFF
+            }
+        }
+        else {
+            panic!("Error: index out of bounds!");
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address <= 0x1FFF {
+            self.ram_enabled = value & 0xF == 0xA;
+        }
+        else if address <= 0x3FFF {
+            //A 0 in the 5 bit register is turned into 1 before BANK2 is added,
+            //so banks 0x20, 0x40 and 0x60 can't be selected and map to the bank after them instead
+            self.rom_bank_register = value & 0b11111;
+            if self.rom_bank_register == 0 {
+                self.rom_bank_register = 1;
+            }
+            self.update_banks();
+        }
+        else if address <= 0x5FFF {
+            self.bank_2_register = value & 0b11;
+            self.update_banks();
+        }
+        else if address <= 0x7FFF {
+            self.is_advanced_banking = value & 0b1 != 0;
+            self.update_banks();
+        }
+        else if address >= 0xA000 && address <= 0xBFFF {
+            if self.ram_enabled {
+                if self.ram_banks != None {
+                    let address = self.ram_bank_offset + (address - 0xA000) as usize;
+                    self.ram_banks.as_mut().unwrap()[address] = value;
+                    
+                    if let Some(sender) = &self.save_sender {
+                        sender.send((value, address as u64)).unwrap();
+                    }
+                }
+            }
+        }
+        else {
+            panic!("Error:: Index out of bounds")
+        }
+    }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        self.rom_banks.as_flattened_mut()
+    }
+
+    fn ram(&self) -> Option<&[u8]> {
+        self.ram_banks.as_deref()
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        if let Some(ram_banks) = &mut self.ram_banks {
+            ram_banks[offset] = value;
+            if let Some(sender) = &self.save_sender {
+                sender.send((value, offset as u64)).unwrap();
+            }
+        }
+    }
+
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        vec![("BANK1", self.rom_bank_register), ("BANK2", self.bank_2_register), ("Mode", self.is_advanced_banking as u8), ("RAM enable", self.ram_enabled as u8)]
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut state = Vec::new();
+        write_u8(&mut state, self.rom_bank_register)?;
+        write_u8(&mut state, self.bank_2_register)?;
+        write_bool(&mut state, self.ram_enabled)?;
+        if let Some(ram_banks) = &self.ram_banks {
+            state.extend_from_slice(ram_banks);
+        }
+        write_bool(&mut state, self.is_advanced_banking)?;
+
+        super::mapper::write_tagged_state(w, b"MBC1", &state)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let state = super::mapper::read_tagged_state(r, b"MBC1")?;
+        let mut r = &state[..];
+
+        self.rom_bank_register = read_u8(&mut r)?;
+        self.bank_2_register = read_u8(&mut r)?;
+        self.ram_enabled = read_bool(&mut r)?;
+        if let Some(ram_banks) = &mut self.ram_banks {
+            let mut new_ram = vec![0; ram_banks.len()];
+            r.read_exact(&mut new_ram)?;
+            super::mapper::sync_save(&self.save_sender, ram_banks, &new_ram);
+            *ram_banks = new_ram;
+        }
+        //States from before the mode register was emulated end here
+        self.is_advanced_banking = !r.is_empty() && read_bool(&mut r)?;
+        self.update_banks();
+
+        Ok(())
+    }
+}
\ No newline at end of file
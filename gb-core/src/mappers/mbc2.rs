@@ -1,74 +1,32 @@
-use std::{fs::{File, OpenOptions}, io::{BufWriter, Bytes, Read}, sync::mpsc::{channel, Sender}};
+use std::{io::{self, Read, Write}};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_bool, read_u8, write_bool, write_u8}};
+
+use super::{BatteryRam, BatterySink};
 
 pub struct MBC2 {
     rom_banks: Vec<[u8; 0x4000]>,
     aux_rom_bank_index: usize,
     ram: [u8; 512],
-    save_sender: Option<Sender<(u8, u64)>>,
+    save_sender: Option<BatterySink>,
     ram_enabled: bool,
 }
 
 impl MBC2 {
-    pub fn new(rom_bank_count: usize, has_battery: bool, rom_file_path: String) -> Self {
-        let mut save_sender_temp = None;
-        let mut ram = [0; 512];
-        
-        if has_battery {
-            let ram_file_path = super::mapper::rom_to_save(rom_file_path.clone());
+    pub fn new(rom: &[u8], rom_bank_count: usize, battery: Option<BatteryRam>) -> Result<Self, CartridgeError> {
+        let rom_banks = super::mapper::prepare_rom(rom, rom_bank_count)?;
 
-            match File::open(ram_file_path.clone()) {
-                Ok(mut file) => {
-                    file.read(&mut ram).unwrap();
-                }
-                Err(e) => {
-                    match e.kind() {
-                        _ => panic!("{}", e),
-                    }
-                }
-            }
-
-            let save_file = BufWriter::new(OpenOptions::new()
-                                                            .write(true)
-                                                            .create(true)
-                                                            .open(ram_file_path)
-                                                            .unwrap());
-            let (save_sender, save_receiver) = channel();
-            super::mapper::write_thread(save_file, save_receiver);
-
-            save_sender_temp = Some(save_sender);
-        }
-
-        let rom_file = File::open(rom_file_path).unwrap().bytes();
-        let rom_banks = Self::prepare_rom(rom_file, rom_bank_count);
+        let mut ram = [0; 512];
+        let (ram_image, save_sender_temp) = super::mapper::battery_ram(battery, ram.len());
+        ram.copy_from_slice(&ram_image);
 
-        Self {
+        Ok(Self {
             rom_banks: rom_banks,
             aux_rom_bank_index: 1,
             ram: ram,
             save_sender: save_sender_temp,
             ram_enabled: false
-        }
-    }
-
-    pub fn prepare_rom(mut file: Bytes<File>, rom_bank_count: usize) -> Vec<[u8; 0x4000]> {
-        let mut rom_data: Vec<[u8; 0x4000]> = Vec::new();
-        
-        for _ in 0..rom_bank_count {
-            let mut rom_bank = [0; 0x4000];
-            let mut iter = 0..0x4000;
-            while let Some(i) = iter.next() {
-                rom_bank[i] = match file.next() {
-                    Some(val) => val.expect("Invalid byte?"),
-                    None => {
-                        panic!("Invalid rom size!")
-                    },
-                };
-            }
-    
-            rom_data.push(rom_bank);
-        }
-    
-        rom_data
+        })
     }
 }
 
@@ -106,7 +64,7 @@ impl super::Mapper for MBC2 {
                     return;
                 }
 
-                if temp_index > self.rom_banks.len() {
+                if temp_index >= self.rom_banks.len() {
                     temp_index %= self.rom_banks.len();
                 }
 
@@ -132,4 +90,52 @@ impl super::Mapper for MBC2 {
             panic!("Error:: Index out of bounds")
         }
     }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        self.rom_banks.as_flattened_mut()
+    }
+
+    fn ram(&self) -> Option<&[u8]> {
+        Some(&self.ram)
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    //Only the low nibble of each byte exists
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        let value = value & 0xF;
+        self.ram[offset] = value;
+        if let Some(sender) = &self.save_sender {
+            sender.send((value, offset as u64)).unwrap();
+        }
+    }
+
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        vec![("ROM bank", self.aux_rom_bank_index as u8), ("RAM enable", self.ram_enabled as u8)]
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut state = Vec::new();
+        write_u8(&mut state, self.aux_rom_bank_index as u8)?;
+        write_bool(&mut state, self.ram_enabled)?;
+        state.extend_from_slice(&self.ram);
+
+        super::mapper::write_tagged_state(w, b"MBC2", &state)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let state = super::mapper::read_tagged_state(r, b"MBC2")?;
+        let mut r = &state[..];
+
+        self.aux_rom_bank_index = read_u8(&mut r)? as usize % self.rom_banks.len();
+        self.ram_enabled = read_bool(&mut r)?;
+        let mut new_ram = [0; 512];
+        r.read_exact(&mut new_ram)?;
+        super::mapper::sync_save(&self.save_sender, &self.ram, &new_ram);
+        self.ram = new_ram;
+
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,336 @@
+use std::io::{self, Read, Write};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_bool, read_u16, read_u8, write_bool, write_u16, write_u8}};
+
+use super::AuxData;
+
+//The accelerometer reads about 0x81D0 when held flat and moves about 0x70 for each g of tilt
+const ACCELEROMETER_CENTER: f32 = 0x81D0 as f32;
+const ACCELEROMETER_G: f32 = 0x70 as f32;
+const EEPROM_TAG: &[u8; 4] = b"EEPR";
+const EEPROM_SIZE: usize = 256;
+
+//Ax8x
+const EEPROM_CS: u8 = 0x80;
+const EEPROM_CLK: u8 = 0x40;
+const EEPROM_DI: u8 = 0x02;
+const EEPROM_DO: u8 = 0x01;
+
+//Where the EEPROM is in the command it's being clocked through, one bit per rising edge of CLK
+#[derive(Clone, Copy, PartialEq)]
+enum EepromState {
+    Idle, //Waiting for a start bit
+    Command { bits: u8, value: u16 }, //2 opcode bits and 8 address bits
+    Reading { word: u16, bits_left: u8 },
+    Writing { address: Option<u8>, bits: u8, value: u16 }, //No address writes every word
+    Done, //Until CS goes low
+}
+
+//Kirby Tilt 'n' Tumble and Command Master. Instead of RAM there's a 2-axis accelerometer and a 93LC56 EEPROM of 128
+//16-bit words, both reached through registers at 0xA000-0xAFFF once both RAM enables are set. The EEPROM is battery
+//free, so it's kept in the aux file instead of a .sav
+pub struct MBC7 {
+    rom_banks: Vec<[u8; 0x4000]>,
+    aux_rom_bank_index: usize,
+    ram_enabled_1: bool, //0x0A written to 0x0000-0x1FFF
+    ram_enabled_2: bool, //0x40 written to 0x4000-0x5FFF
+    tilt: (f32, f32), //What the player is doing now, in g. Only reaches the game when it latches
+    latched_x: u16,
+    latched_y: u16,
+    is_latch_erased: bool, //Latching needs 0x55 then 0xAA, like erasing then programming a flash chip
+    eeprom: [u8; EEPROM_SIZE], //Words stored high byte first
+    eeprom_pins: u8, //CS, CLK and DI as last written, with DO
+    eeprom_state: EepromState,
+    is_eeprom_write_enabled: bool,
+}
+
+impl MBC7 {
+    pub fn new(rom: &[u8], rom_bank_count: usize) -> Result<Self, CartridgeError> {
+        Ok(Self {
+            rom_banks: super::mapper::prepare_rom(rom, rom_bank_count)?,
+            aux_rom_bank_index: 1,
+            ram_enabled_1: false,
+            ram_enabled_2: false,
+            tilt: (0.0, 0.0),
+            latched_x: 0x8000,
+            latched_y: 0x8000,
+            is_latch_erased: false,
+            eeprom: [0xFF; EEPROM_SIZE],
+            eeprom_pins: EEPROM_DO,
+            eeprom_state: EepromState::Idle,
+            is_eeprom_write_enabled: false,
+        })
+    }
+
+    fn are_registers_enabled(&self) -> bool {
+        self.ram_enabled_1 && self.ram_enabled_2
+    }
+
+    fn accelerometer_value(tilt: f32) -> u16 {
+        (ACCELEROMETER_CENTER + tilt.clamp(-2.0, 2.0) * ACCELEROMETER_G) as u16
+    }
+
+    fn word(&self, address: u8) -> u16 {
+        let offset = (address & 0x7F) as usize * 2;
+        u16::from_be_bytes([self.eeprom[offset], self.eeprom[offset + 1]])
+    }
+
+    fn set_word(&mut self, address: u8, word: u16) {
+        let offset = (address & 0x7F) as usize * 2;
+        self.eeprom[offset..offset + 2].copy_from_slice(&word.to_be_bytes());
+    }
+
+    fn write_eeprom_pins(&mut self, value: u8) {
+        let old_pins = self.eeprom_pins;
+        self.eeprom_pins = (value & (EEPROM_CS | EEPROM_CLK | EEPROM_DI)) | (old_pins & EEPROM_DO);
+
+        if value & EEPROM_CS == 0 {
+            //Deselecting abandons whatever was being clocked in. DO floats high, which also reads as ready
+            self.eeprom_state = EepromState::Idle;
+            self.eeprom_pins |= EEPROM_DO;
+        }
+        else if old_pins & EEPROM_CLK == 0 && value & EEPROM_CLK != 0 {
+            self.clock_eeprom(value & EEPROM_DI != 0);
+        }
+    }
+
+    fn clock_eeprom(&mut self, di: bool) {
+        self.eeprom_state = match self.eeprom_state {
+            EepromState::Idle if di => EepromState::Command { bits: 0, value: 0 },
+            EepromState::Idle => EepromState::Idle,
+            EepromState::Command { bits, value } => {
+                let value = value << 1 | di as u16;
+                if bits + 1 < 10 {
+                    EepromState::Command { bits: bits + 1, value }
+                }
+                else {
+                    self.run_eeprom_command(value)
+                }
+            }
+            EepromState::Reading { word, bits_left } => {
+                self.set_eeprom_do(word & 0x8000 != 0);
+                if bits_left > 1 {
+                    EepromState::Reading { word: word << 1, bits_left: bits_left - 1 }
+                }
+                else {
+                    EepromState::Done
+                }
+            }
+            EepromState::Writing { address, bits, value } => {
+                let value = value << 1 | di as u16;
+                if bits + 1 < 16 {
+                    EepromState::Writing { address, bits: bits + 1, value }
+                }
+                else {
+                    if self.is_eeprom_write_enabled {
+                        match address {
+                            Some(address) => self.set_word(address, value),
+                            None => (0..0x80).for_each(|address| self.set_word(address, value)),
+                        }
+                    }
+                    self.set_eeprom_do(true);
+                    EepromState::Done
+                }
+            }
+            EepromState::Done => EepromState::Done,
+        };
+    }
+
+    //Writes and erases finish instantly, so DO always reports ready afterwards
+    fn run_eeprom_command(&mut self, command: u16) -> EepromState {
+        let address = command as u8;
+        match command >> 8 {
+            0b10 => {
+                //A dummy 0 comes out before the word
+                self.set_eeprom_do(false);
+                EepromState::Reading { word: self.word(address), bits_left: 16 }
+            }
+            0b01 => EepromState::Writing { address: Some(address), bits: 0, value: 0 },
+            0b11 => {
+                if self.is_eeprom_write_enabled {
+                    self.set_word(address, 0xFFFF);
+                }
+                self.set_eeprom_do(true);
+                EepromState::Done
+            }
+            _ => match address >> 6 {
+                0b00 => {
+                    self.is_eeprom_write_enabled = false;
+                    EepromState::Done
+                }
+                0b01 => {
+                    if self.is_eeprom_write_enabled {
+                        self.eeprom = [0xFF; EEPROM_SIZE];
+                    }
+                    self.set_eeprom_do(true);
+                    EepromState::Done
+                }
+                0b10 => EepromState::Writing { address: None, bits: 0, value: 0 },
+                _ => {
+                    self.is_eeprom_write_enabled = true;
+                    EepromState::Done
+                }
+            }
+        }
+    }
+
+    fn set_eeprom_do(&mut self, is_high: bool) {
+        self.eeprom_pins = (self.eeprom_pins & !EEPROM_DO) | is_high as u8;
+    }
+
+    fn read_register(&self, address: u16) -> u8 {
+        match (address >> 4) & 0xF {
+            0x2 => self.latched_x as u8,
+            0x3 => (self.latched_x >> 8) as u8,
+            0x4 => self.latched_y as u8,
+            0x5 => (self.latched_y >> 8) as u8,
+            0x6 => 0x00, //There's no Z axis
+            0x8 => self.eeprom_pins,
+            _ => 0xFF,
+        }
+    }
+
+    fn write_register(&mut self, address: u16, value: u8) {
+        match (address >> 4) & 0xF {
+            0x0 if value == 0x55 => {
+                self.latched_x = 0x8000;
+                self.latched_y = 0x8000;
+                self.is_latch_erased = true;
+            }
+            0x1 if value == 0xAA && self.is_latch_erased => {
+                self.latched_x = Self::accelerometer_value(self.tilt.0);
+                self.latched_y = Self::accelerometer_value(self.tilt.1);
+                self.is_latch_erased = false;
+            }
+            0x8 => self.write_eeprom_pins(value),
+            _ => {}
+        }
+    }
+
+    fn encode_eeprom_state(&self, w: &mut Vec<u8>) -> io::Result<()> {
+        let (kind, bits, value, address) = match self.eeprom_state {
+            EepromState::Idle => (0, 0, 0, None),
+            EepromState::Command { bits, value } => (1, bits, value, None),
+            EepromState::Reading { word, bits_left } => (2, bits_left, word, None),
+            EepromState::Writing { address, bits, value } => (3, bits, value, address),
+            EepromState::Done => (4, 0, 0, None),
+        };
+        write_u8(w, kind)?;
+        write_u8(w, bits)?;
+        write_u16(w, value)?;
+        write_u8(w, address.unwrap_or(0xFF))
+    }
+
+    fn decode_eeprom_state(r: &mut &[u8]) -> io::Result<EepromState> {
+        let kind = read_u8(r)?;
+        let bits = read_u8(r)?;
+        let value = read_u16(r)?;
+        let address = read_u8(r)?;
+        Ok(match kind {
+            0 => EepromState::Idle,
+            1 => EepromState::Command { bits, value },
+            2 => EepromState::Reading { word: value, bits_left: bits },
+            3 => EepromState::Writing { address: (address != 0xFF).then_some(address), bits, value },
+            4 => EepromState::Done,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown EEPROM state {}", kind))),
+        })
+    }
+}
+
+impl super::Mapper for MBC7 {
+    fn read(&self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x3FFF => self.rom_banks[0][address as usize],
+            0x4000..=0x7FFF => self.rom_banks[self.aux_rom_bank_index][(address - 0x4000) as usize],
+            0xA000..=0xAFFF if self.are_registers_enabled() => self.read_register(address),
+            0xA000..=0xBFFF => 0xFF,
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => {
+                self.ram_enabled_1 = value == 0x0A;
+                if !self.ram_enabled_1 {
+                    self.ram_enabled_2 = false;
+                }
+            }
+            0x2000..=0x3FFF => self.aux_rom_bank_index = value as usize % self.rom_banks.len(),
+            0x4000..=0x5FFF => self.ram_enabled_2 = self.ram_enabled_1 && value == 0x40,
+            0x6000..=0x7FFF => {}
+            0xA000..=0xAFFF if self.are_registers_enabled() => self.write_register(address, value),
+            0xA000..=0xBFFF => {}
+            _ => panic!("Error: Address ${:x} out of bounds", address),
+        }
+    }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        self.rom_banks.as_flattened_mut()
+    }
+
+    //The EEPROM, so tools that show cartridge RAM have something to show
+    fn ram(&self) -> Option<&[u8]> {
+        Some(&self.eeprom)
+    }
+
+    fn is_ram_enabled(&self) -> bool {
+        self.are_registers_enabled()
+    }
+
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        self.eeprom[offset] = value;
+    }
+
+    fn set_tilt(&mut self, x: f32, y: f32) {
+        self.tilt = (x, y);
+    }
+
+    fn registers(&self) -> Vec<(&'static str, u8)> {
+        vec![("ROM bank", self.aux_rom_bank_index as u8), ("RAM enable", self.are_registers_enabled() as u8), ("EEPROM pins", self.eeprom_pins)]
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut state = Vec::new();
+        write_u8(&mut state, self.aux_rom_bank_index as u8)?;
+        write_bool(&mut state, self.ram_enabled_1)?;
+        write_bool(&mut state, self.ram_enabled_2)?;
+        write_u16(&mut state, self.latched_x)?;
+        write_u16(&mut state, self.latched_y)?;
+        write_bool(&mut state, self.is_latch_erased)?;
+        write_u8(&mut state, self.eeprom_pins)?;
+        self.encode_eeprom_state(&mut state)?;
+        write_bool(&mut state, self.is_eeprom_write_enabled)?;
+        state.extend_from_slice(&self.eeprom);
+
+        super::mapper::write_tagged_state(w, b"MBC7", &state)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let state = super::mapper::read_tagged_state(r, b"MBC7")?;
+        let mut r = &state[..];
+
+        self.aux_rom_bank_index = read_u8(&mut r)? as usize % self.rom_banks.len();
+        self.ram_enabled_1 = read_bool(&mut r)?;
+        self.ram_enabled_2 = read_bool(&mut r)?;
+        self.latched_x = read_u16(&mut r)?;
+        self.latched_y = read_u16(&mut r)?;
+        self.is_latch_erased = read_bool(&mut r)?;
+        self.eeprom_pins = read_u8(&mut r)?;
+        self.eeprom_state = Self::decode_eeprom_state(&mut r)?;
+        self.is_eeprom_write_enabled = read_bool(&mut r)?;
+        r.read_exact(&mut self.eeprom)?;
+
+        Ok(())
+    }
+
+    fn load_aux_data(&mut self, aux_data: &AuxData) {
+        if let Some(eeprom) = aux_data.section(EEPROM_TAG).filter(|eeprom| eeprom.len() == EEPROM_SIZE) {
+            self.eeprom.copy_from_slice(eeprom);
+        }
+    }
+
+    fn save_aux_data(&self, aux_data: &mut AuxData) {
+        aux_data.set_section(EEPROM_TAG, self.eeprom.to_vec());
+    }
+}
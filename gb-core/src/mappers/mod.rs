@@ -0,0 +1,26 @@
+mod mapper;
+mod battery_save;
+mod aux_data;
+
+mod no_mbc;
+mod mbc1;
+mod mbc2;
+mod mbc7;
+mod huc1;
+mod huc3;
+mod camera;
+mod game_genie;
+
+pub use self::{
+    mapper::{rom_to_save, BatteryRam, BatterySink, Mapper},
+    battery_save::CartridgeStorage,
+    aux_data::{rom_to_aux, AuxData},
+    no_mbc::NoMBC,
+    mbc1::MBC1,
+    mbc2::MBC2,
+    mbc7::MBC7,
+    huc1::HuC1,
+    huc3::HuC3,
+    camera::{Camera, CAMERA_HEIGHT, CAMERA_WIDTH},
+    game_genie::GameGenie,
+};
\ No newline at end of file
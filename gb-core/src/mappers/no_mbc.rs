@@ -0,0 +1,84 @@
+use std::io::{self, Read, Write};
+
+use crate::{cartridge_info::CartridgeError, savestate::{read_bool, write_bool}};
+
+pub struct NoMBC {
+    rom_bank: [u8; 0x8000],
+    ram_bank: Option<[u8; 0x2000]>
+}
+
+impl NoMBC {
+    pub fn new(rom: &[u8], has_ram: bool) -> Result<Self, CartridgeError> {
+        let rom_bank = Self::prepare_rom(rom)?;
+        Ok(Self {
+            rom_bank: rom_bank,
+            ram_bank: if has_ram {Some([0; 0x2000])} else {None}
+        })
+    }
+
+    pub fn prepare_rom(rom: &[u8]) -> Result<[u8; 0x8000], CartridgeError> {
+        match rom.get(..0x8000) {
+            Some(rom_data) => Ok(rom_data.try_into().unwrap()),
+            None => Err(CartridgeError::Truncated {expected: 0x8000, actual: rom.len()}),
+        }
+    }
+}
+
+impl super::Mapper for NoMBC {
+    fn read(&self, address: u16) -> u8 {
+        match (address, &self.ram_bank) {
+            (0x0000..=0x7FFF, _) => self.rom_bank[address as usize],
+            (0xA000..=0xBFFF, Some(ram_bank)) => ram_bank[(address - 0xA000) as usize],
+            //Nothing drives the bus without RAM
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if let (0xA000..=0xBFFF, Some(ram_bank)) = (address, &mut self.ram_bank) {
+            ram_bank[(address - 0xA000) as usize] = value;
+        }
+    }
+
+    fn rom_mut(&mut self) -> &mut [u8] {
+        &mut self.rom_bank
+    }
+
+    fn ram(&self) -> Option<&[u8]> {
+        self.ram_bank.as_ref().map(|ram_bank| &ram_bank[..])
+    }
+
+    //There's no enable register, so RAM is always on when there is any
+    fn is_ram_enabled(&self) -> bool {
+        self.ram_bank.is_some()
+    }
+
+    fn write_ram(&mut self, offset: usize, value: u8) {
+        if let Some(ram_bank) = &mut self.ram_bank {
+            ram_bank[offset] = value;
+        }
+    }
+
+    fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut state = Vec::new();
+        write_bool(&mut state, self.ram_bank.is_some())?;
+        if let Some(ram_bank) = &self.ram_bank {
+            state.extend_from_slice(ram_bank);
+        }
+
+        super::mapper::write_tagged_state(w, b"ROM ", &state)
+    }
+
+    fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let state = super::mapper::read_tagged_state(r, b"ROM ")?;
+        let mut r = &state[..];
+
+        if read_bool(&mut r)? {
+            let mut ram_bank = [0; 0x2000];
+            r.read_exact(&mut ram_bank)?;
+            self.ram_bank = Some(ram_bank);
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,101 @@
+use super::console::GBConsole;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum MemoryRegion {
+    #[default]
+    Rom0,
+    RomX,
+    Vram,
+    CartridgeRam,
+    Wram0,
+    WramX,
+    Oam,
+    Io,
+    Hram,
+}
+
+impl MemoryRegion {
+    pub const ALL: [MemoryRegion; 9] = [MemoryRegion::Rom0, MemoryRegion::RomX, MemoryRegion::Vram, MemoryRegion::CartridgeRam, MemoryRegion::Wram0, MemoryRegion::WramX, MemoryRegion::Oam, MemoryRegion::Io, MemoryRegion::Hram];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemoryRegion::Rom0 => "ROM0",
+            MemoryRegion::RomX => "ROMX",
+            MemoryRegion::Vram => "VRAM",
+            MemoryRegion::CartridgeRam => "Cartridge RAM",
+            MemoryRegion::Wram0 => "WRAM0",
+            MemoryRegion::WramX => "WRAMX",
+            MemoryRegion::Oam => "OAM",
+            MemoryRegion::Io => "I/O",
+            MemoryRegion::Hram => "HRAM",
+        }
+    }
+
+    pub fn start_address(&self) -> u16 {
+        match self {
+            MemoryRegion::Rom0 => 0x0000,
+            MemoryRegion::RomX => 0x4000,
+            MemoryRegion::Vram => 0x8000,
+            MemoryRegion::CartridgeRam => 0xA000,
+            MemoryRegion::Wram0 => 0xC000,
+            MemoryRegion::WramX => 0xD000,
+            MemoryRegion::Oam => 0xFE00,
+            MemoryRegion::Io => 0xFF00,
+            MemoryRegion::Hram => 0xFF80,
+        }
+    }
+
+    //HRAM takes IE along with it
+    pub fn size(&self) -> usize {
+        match self {
+            MemoryRegion::Rom0 | MemoryRegion::RomX => 0x4000,
+            MemoryRegion::Vram | MemoryRegion::CartridgeRam => 0x2000,
+            MemoryRegion::Wram0 | MemoryRegion::WramX => 0x1000,
+            MemoryRegion::Oam => 0xA0,
+            MemoryRegion::Io | MemoryRegion::Hram => 0x80,
+        }
+    }
+
+    //The lowest bank the user can pick, or None if the region isn't banked
+    pub fn first_bank(&self) -> Option<u16> {
+        match self {
+            MemoryRegion::RomX | MemoryRegion::WramX => Some(1),
+            MemoryRegion::Vram | MemoryRegion::CartridgeRam => Some(0),
+            _ => None,
+        }
+    }
+
+    //What peek and poke take. ROM0 and WRAM0 are bank 0 so edits patch the rom instead of writing the mapper's registers
+    pub fn bank(&self, bank: u16) -> Option<u16> {
+        match self {
+            MemoryRegion::Rom0 | MemoryRegion::Wram0 => Some(0),
+            _ => self.first_bank().map(|first_bank| bank.max(first_bank)),
+        }
+    }
+}
+
+//Shared between the memory editor tab and the emulation thread. The tab picks what to show and queues edits, the
+//emulation thread makes the edits and copies the region out every frame and whenever it's woken up while paused
+#[derive(Default)]
+pub struct MemoryEditor {
+    pub region: MemoryRegion,
+    pub bank: u16,
+    pub edits: Vec<(Option<u16>, u16, u8)>, //Bank, address and value, in the order they were typed
+    pub is_stale: bool, //The region, bank or edits changed since the last sync
+    pub bytes: Vec<Option<u8>>, //None where the bank doesn't exist
+}
+
+impl MemoryEditor {
+    pub fn sync(&mut self, console: &mut GBConsole) {
+        for (bank, address, value) in std::mem::take(&mut self.edits) {
+            if let Err(e) = console.poke(bank, address, value) {
+                println!("ERROR: {}", e);
+            }
+        }
+
+        let bank = self.region.bank(self.bank);
+        let start_address = self.region.start_address();
+        self.bytes = (0..self.region.size() as u16).map(|offset| console.peek(bank, start_address + offset).ok()).collect();
+        self.is_stale = false;
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cartridge_info::{CartridgeInfo, CGBState};
+
+use super::console::HardwareMode;
+
+//Which Game Boy the console starts up as. Games tell them apart by what the boot ROM leaves in the registers, mostly
+//A and B, to pick colors, turn on GBA extras or refuse to run. Only that start up state differs between models, the
+//SGB's border and packets aren't emulated
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Model {
+    Dmg0, //Early Japanese DMG, with an older boot ROM
+    Dmg,
+    Mgb, //Game Boy Pocket
+    Sgb,
+    Cgb,
+    Agb, //Game Boy Advance
+}
+
+impl Model {
+    pub const ALL: [Model; 6] = [Model::Dmg0, Model::Dmg, Model::Mgb, Model::Sgb, Model::Cgb, Model::Agb];
+
+    //What a cartridge gets when no model is picked: a CGB if it supports one, otherwise a DMG
+    pub fn for_cartridge(info: &CartridgeInfo) -> Self {
+        match info.cgb_flag {
+            CGBState::Monochrome => Self::Dmg,
+            CGBState::Both | CGBState::Color => Self::Cgb,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dmg0 => "DMG0",
+            Self::Dmg => "DMG",
+            Self::Mgb => "MGB",
+            Self::Sgb => "SGB",
+            Self::Cgb => "CGB",
+            Self::Agb => "AGB",
+        }
+    }
+
+    fn is_color(self) -> bool {
+        matches!(self, Self::Cgb | Self::Agb)
+    }
+
+    //CGB features are only on for cartridges that support them on a color model. A CGB only cartridge on a
+    //monochrome model runs as a DMG game, which is usually just its warning screen
+    pub fn hardware_mode(self, info: &CartridgeInfo) -> HardwareMode {
+        if self.is_color() && !matches!(info.cgb_flag, CGBState::Monochrome) {
+            HardwareMode::Cgb
+        }
+        else {
+            HardwareMode::Dmg
+        }
+    }
+
+    //A, B, C, D, E, H, L, F as the boot ROM leaves them. The DMG boot ROM's header check leaves H and C set unless the
+    //checksum is 0. A color model running a monochrome cartridge is in its DMG mode, where the boot ROM picks a
+    //palette from the title. H and L depend on that palette and are given for the default one
+    pub fn post_boot_registers(self, info: &CartridgeInfo) -> [u8; 8] {
+        let is_dmg_mode = matches!(info.cgb_flag, CGBState::Monochrome);
+        let header_flags = if info.header_checksum == 0 {0x80} else {0xB0};
+        match self {
+            Self::Dmg0 => [0x01, 0xFF, 0x13, 0x00, 0xC1, 0x84, 0x03, 0x00],
+            Self::Dmg => [0x01, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D, header_flags],
+            Self::Mgb => [0xFF, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D, header_flags],
+            Self::Sgb => [0x01, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60, 0x00],
+            Self::Cgb if is_dmg_mode => [0x11, 0x00, 0x00, 0x00, 0x08, 0x00, 0x7C, 0x80],
+            Self::Cgb => [0x11, 0x00, 0x00, 0xFF, 0x56, 0x00, 0x0D, 0x80],
+            //The AGB boot ROM ends with an extra INC B, which also clears Z
+            Self::Agb if is_dmg_mode => [0x11, 0x01, 0x00, 0x00, 0x08, 0x00, 0x7C, 0x00],
+            Self::Agb => [0x11, 0x01, 0x00, 0xFF, 0x56, 0x00, 0x0D, 0x00],
+        }
+    }
+
+    //DIV when the game starts, which depends on how long the boot ROM took
+    pub fn post_boot_div(self) -> u8 {
+        match self {
+            Self::Dmg0 => 0x18,
+            _ => 0xAB,
+        }
+    }
+}
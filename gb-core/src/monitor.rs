@@ -0,0 +1,158 @@
+use super::{console::GBConsole, debug_session::{DebugSession, Symbols, WatchpointEntry}};
+
+//Bytes read when r isn't given a count
+const DEFAULT_READ_COUNT: u16 = 16;
+const MAX_LOG_LINES: usize = 1000;
+
+pub const HELP: &str = "\
+r ADDRESS [COUNT]       Read COUNT bytes (16 by default)
+w ADDRESS BYTE...       Write one or more bytes
+bp ADDRESS              Break before the instruction at ADDRESS runs
+bp ADDRESS r|w|rw       Break when ADDRESS is read and/or written
+bp ADDRESS r|w|rw log   Log reads and/or writes of ADDRESS to a CSV next to the ROM instead of breaking
+help                    Show this list
+
+Addresses and bytes are hex, counts are decimal. Prefixing an address with a bank, like 03:4F20,
+reaches that bank of ROM, cartridge RAM, WRAM or VRAM at the address, mapped in or not. Labels from the
+debugger's symbol file work anywhere an address does. Watchpoints can cover a range, like C000-C0FF";
+
+//An address as typed, looked up when the command runs so labels come from the symbol file loaded at the time
+#[derive(Clone, PartialEq, Debug)]
+pub struct Target {
+    pub bank: Option<u16>,
+    pub address: String,
+}
+
+impl Target {
+    fn parse(text: &str) -> Self {
+        match text.split_once(':') {
+            Some((bank, address)) if u16::from_str_radix(bank, 16).is_ok() => Self {
+                bank: u16::from_str_radix(bank, 16).ok(),
+                address: address.to_owned(),
+            },
+            _ => Self { bank: None, address: text.to_owned() },
+        }
+    }
+
+    fn resolve(&self, symbols: &Symbols) -> Result<u16, String> {
+        symbols.resolve(&self.address).ok_or_else(|| format!("{} isn't an address or a known label", self.address))
+    }
+
+    fn describe(&self, address: u16) -> String {
+        match self.bank {
+            Some(bank) => format!("{:02X}:{:04X}", bank, address),
+            None => format!("{:04X}", address),
+        }
+    }
+}
+
+//A monitor command. Parsing is kept apart from running them, so the monitor window and scripts share it
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    Read { target: Target, count: u16 },
+    Write { target: Target, bytes: Vec<u8> },
+    Breakpoint { expression: String },
+    Watchpoint { expression: String, on_read: bool, on_write: bool, is_logged: bool },
+    Help,
+}
+
+pub fn parse(line: &str) -> Result<Command, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((name, args)) = words.split_first() else {
+        return Err(String::from("Type a command, or help for a list"));
+    };
+
+    match (name.to_ascii_lowercase().as_str(), args) {
+        ("r", [target]) => Ok(Command::Read { target: Target::parse(target), count: DEFAULT_READ_COUNT }),
+        ("r", [target, count]) => {
+            let count = count.parse().ok().filter(|count| *count > 0).ok_or_else(|| format!("{} isn't a count", count))?;
+            Ok(Command::Read { target: Target::parse(target), count })
+        }
+        ("w", [target, bytes @ ..]) if !bytes.is_empty() => {
+            let bytes = bytes.iter()
+                .map(|byte| u8::from_str_radix(byte.trim_start_matches('$'), 16).map_err(|_| format!("{} isn't a byte", byte)))
+                .collect::<Result<Vec<u8>, String>>()?;
+            Ok(Command::Write { target: Target::parse(target), bytes })
+        }
+        ("bp", [expression, access @ ..]) if access.len() <= 2 => {
+            if expression.contains(':') {
+                return Err(String::from("Breakpoints can't be limited to a bank"));
+            }
+            let expression = expression.to_string();
+            let is_logged = match access.get(1) {
+                Some(log) if log.eq_ignore_ascii_case("log") => true,
+                Some(log) => return Err(format!("{} isn't log", log)),
+                None => false,
+            };
+            match access.first().map(|access| access.to_ascii_lowercase()).as_deref() {
+                None => Ok(Command::Breakpoint { expression }),
+                Some("r") => Ok(Command::Watchpoint { expression, on_read: true, on_write: false, is_logged }),
+                Some("w") => Ok(Command::Watchpoint { expression, on_read: false, on_write: true, is_logged }),
+                Some("rw") | Some("wr") => Ok(Command::Watchpoint { expression, on_read: true, on_write: true, is_logged }),
+                Some(access) => Err(format!("{} isn't r, w or rw", access)),
+            }
+        }
+        ("help", []) | ("?", []) => Ok(Command::Help),
+        ("r", _) | ("w", _) | ("bp", _) => Err(format!("Wrong arguments for {}. Type help for a list", name)),
+        _ => Err(format!("Unknown command {}. Type help for a list", name)),
+    }
+}
+
+//Breakpoints and watchpoints go into the game's debug session, so they show up in the debugger and are saved with it
+pub fn add_to_session(command: &Command, session: &mut DebugSession) -> Option<String> {
+    match command {
+        Command::Breakpoint { expression } => {
+            session.breakpoints.push(expression.clone());
+            Some(format!("Breakpoint at {}", expression))
+        }
+        Command::Watchpoint { expression, on_read, on_write, is_logged } => {
+            session.watchpoints.push(WatchpointEntry { expression: expression.clone(), on_read: *on_read, on_write: *on_write, is_logged: *is_logged });
+            Some(format!("{} at {}", if *is_logged {"Logged watchpoint"} else {"Watchpoint"}, expression))
+        }
+        _ => None,
+    }
+}
+
+//Runs a command that touches memory on the emulation thread and returns what it printed
+pub fn run(command: &Command, console: &mut GBConsole, symbols: &Symbols) -> Result<Vec<String>, String> {
+    match command {
+        Command::Read { target, count } => {
+            let start = target.resolve(symbols)?;
+            let mut lines = Vec::new();
+            //Counted in u32 so the last row of a read close to 65535 bytes doesn't wrap around
+            for row_start in (0..*count as u32).step_by(16) {
+                let address = start.wrapping_add(row_start as u16);
+                let bytes = (row_start..(row_start + 16).min(*count as u32))
+                    .map(|offset| console.peek(target.bank, start.wrapping_add(offset as u16)).map(|byte| format!("{:02X}", byte)))
+                    .collect::<Result<Vec<String>, String>>()?;
+                lines.push(format!("{}: {}", target.describe(address), bytes.join(" ")));
+            }
+            Ok(lines)
+        }
+        Command::Write { target, bytes } => {
+            let start = target.resolve(symbols)?;
+            for (offset, byte) in bytes.iter().enumerate() {
+                console.poke(target.bank, start.wrapping_add(offset as u16), *byte)?;
+            }
+            Ok(vec![format!("Wrote {} bytes at {}", bytes.len(), target.describe(start))])
+        }
+        Command::Help => Ok(HELP.lines().map(str::to_owned).collect()),
+        Command::Breakpoint { .. } | Command::Watchpoint { .. } => Ok(Vec::new()),
+    }
+}
+
+//Commands typed into the monitor window and everything it has printed, for one session
+#[derive(Default)]
+pub struct Monitor {
+    pub pending: Vec<Command>,
+    pub log: Vec<String>,
+}
+
+impl Monitor {
+    //Only the newest lines are kept
+    pub fn print(&mut self, lines: impl IntoIterator<Item = String>) {
+        self.log.extend(lines);
+        let excess = self.log.len().saturating_sub(MAX_LOG_LINES);
+        self.log.drain(..excess);
+    }
+}
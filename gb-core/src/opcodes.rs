@@ -0,0 +1,608 @@
+use std::sync::OnceLock;
+
+use super::alu::{C_CARRY_FLAG, Z_ZERO_FLAG};
+
+//Operand bytes following the opcode, or None to show placeholders like n16 instead
+pub fn mnemonic(opcode: u8, operands: Option<[u8; 2]>) -> String {
+    let n8 = || operands.map_or(String::from("n8"), |[value, _]| format!("${:02X}", value));
+    let e8 = || operands.map_or(String::from("e8"), |[value, _]| format!("{}", value as i8));
+    let n16 = || operands.map_or(String::from("n16"), |[low, high]| format!("${:04X}", u16::from_le_bytes([low, high])));
+
+    match opcode {
+        0o000 => String::from("NOP"),
+        0o010 => format!("LD [{}], SP", n16()),
+        0o020 => format!("STOP {}", n8()),
+
+        0o007 => String::from("RLCA"),
+        0o017 => String::from("RRCA"),
+        0o027 => String::from("RLA"),
+        0o037 => String::from("RRA"),
+
+        0o047 => String::from("DAA"),
+        0o057 => String::from("CPL"),
+        0o067 => String::from("SCF"),
+        0o077 => String::from("CCF"),
+
+        0o166 => String::from("HALT"),
+
+        0o340 => format!("LDH [{}], A", n8()),
+        0o350 => format!("ADD SP, {}", e8()),
+        0o360 => format!("LDH A, [{}]", n8()),
+        0o370 => format!("LD HL, SP + {}", e8()),
+
+        0o311 => String::from("RET"),
+        0o331 => String::from("RETI"),
+        0o351 => String::from("JP HL"),
+        0o371 => String::from("LD SP, HL"),
+
+        0o303 => format!("JP {}", n16()),
+        0o313 => operands.map_or(String::from("PREFIX"), |[prefixed_opcode, _]| prefixed_mnemonic(prefixed_opcode)),
+        0o363 => String::from("DI"),
+        0o373 => String::from("EI"),
+        
+        0o315 => format!("CALL {}", n16()),
+
+        0o323 | 0o333 | 0o335 | 0o343 | 0o344 | 0o353 | 0o354 | 0o355 | 0o364 | 0o374 | 0o375 => format!("ILLEGAL OPCODE ${:x}", opcode),
+
+        _ => match opcode & 0o300 {
+            0o000 => {
+                match opcode & 0o007 {
+                    0o000 => {
+                        let condition = match opcode & 0o070 {
+                            0o030 => "",
+                            0o040 => "NZ",
+                            0o050 => "Z",
+                            0o060 => "NC",
+                            0o070 => "C",
+                            _ => panic!()
+                        };
+                        if condition.is_empty() {
+                            format!("JR {}", e8())
+                        }
+                        else {
+                            format!("JR {}, {}", condition, e8())
+                        }
+                    }
+                    0o001 => {
+                        let register = match opcode & 0o060 {
+                            0o000 => "BC",
+                            0o020 => "DE",
+                            0o040 => "HL",
+                            0o060 => "SP",
+                            _ => panic!()
+                        };
+
+                        if opcode & 0o010 == 0 {
+                            format!("LD {}, {}", register, n16())
+                        }
+                        else {
+                            format!("ADD HL, {}", register)
+                        }
+                    }
+                    0o002 => {
+                        let register = match opcode & 0o060 {
+                            0o000 => "BC",
+                            0o020 => "DE",
+                            0o040 => "HL+",
+                            0o060 => "HL-",
+                            _ => panic!()
+                        };
+
+                        if opcode & 0o010 == 0 {
+                            format!("LD [{}], A", register)
+                        }
+                        else {
+                            format!("LD A, [{}]", register)
+                        }
+                    }
+                    0o003 => {
+                        let register = match opcode & 0o060 {
+                            0o000 => "BC",
+                            0o020 => "DE",
+                            0o040 => "HL",
+                            0o060 => "SP",
+                            _ => panic!()
+                        };
+
+                        if opcode & 0o010 == 0 {
+                            format!("INC {}", register)
+                        }
+                        else {
+                            format!("DEC {}", register)
+                        }
+                    }
+                    0o004 | 0o005 => {
+                        let register = match opcode & 0o070 {
+                            0o000 => "B",
+                            0o010 => "C",
+                            0o020 => "D",
+                            0o030 => "E",
+                            0o040 => "H",
+                            0o050 => "L",
+                            0o060 => "[HL]",
+                            0o070 => "A",
+                            _ => panic!()
+                        };
+
+                        if opcode & 0o007 == 0o004 {
+                            format!("INC {}", register)
+                        }
+                        else {
+                            format!("DEC {}", register)
+                        }
+                    }
+                    0o006 => {
+                        let register = match opcode & 0o070 {
+                            0o000 => "B",
+                            0o010 => "C",
+                            0o020 => "D",
+                            0o030 => "E",
+                            0o040 => "H",
+                            0o050 => "L",
+                            0o060 => "[HL]",
+                            0o070 => "A",
+                            _ => panic!()
+                        };
+                        format!("LD {}, {}", register, n8())
+                    }
+                    _ => panic!()
+                }
+            }
+            0o100 => {
+                let src = match opcode & 0o007 {
+                    0o000 => "B",
+                    0o001 => "C",
+                    0o002 => "D",
+                    0o003 => "E",
+                    0o004 => "H",
+                    0o005 => "L",
+                    0o006 => "[HL]",
+                    0o007 => "A",
+                    _ => panic!()
+                };
+                let dest = match opcode & 0o070 {
+                    0o000 => "B",
+                    0o010 => "C",
+                    0o020 => "D",
+                    0o030 => "E",
+                    0o040 => "H",
+                    0o050 => "L",
+                    0o060 => "[HL]",
+                    0o070 => "A",
+                    _ => panic!()
+                };
+                format!("LD {}, {}", dest, src)
+            }
+            0o200 => {
+                let src = match opcode & 0o007 {
+                    0o000 => "B",
+                    0o001 => "C",
+                    0o002 => "D",
+                    0o003 => "E",
+                    0o004 => "H",
+                    0o005 => "L",
+                    0o006 => "[HL]",
+                    0o007 => "A",
+                    _ => panic!()
+                };
+                let op = match opcode & 0o070 {
+                    0o000 => "ADD",
+                    0o010 => "ADC",
+                    0o020 => "SUB",
+                    0o030 => "SBC",
+                    0o040 => "AND",
+                    0o050 => "XOR",
+                    0o060 => "OR",
+                    0o070 => "CP",
+                    _ => panic!()
+                };
+                format!("{} A, {}", op, src)
+            }
+            0o300 => {
+                match opcode & 0o007 {
+                    0o000 => {
+                        let condition = match opcode & 0o070 {
+                            0o000 => "NZ",
+                            0o010 => "Z",
+                            0o020 => "NC",
+                            0o030 => "C",
+                            _ => panic!()
+                        };
+                        format!("RET {}", condition)
+                    }
+                    0o001 => {
+                        let register = match opcode & 0o070 {
+                            0o000 => "BC",
+                            0o020 => "DE",
+                            0o040 => "HL",
+                            0o060 => "AF",
+                            _ => panic!()
+                        };
+                        format!("POP {}", register)
+                    }
+                    0o002 if opcode & 0o040 == 0 => {
+                        let condition = match opcode & 0o070 {
+                            0o000 => "NZ",
+                            0o010 => "Z",
+                            0o020 => "NC",
+                            0o030 => "C",
+                            _ => panic!()
+                        };
+                        format!("JP {}, {}", condition, n16())
+                    }
+                    0o002 => {
+                        let (op, register) = if opcode & 0o010 == 0 {("LDH", String::from("C"))} else {("LD", n16())};
+                        if opcode & 0o020 == 0 {
+                            format!("{} [{}], A", op, register)
+                        }
+                        else {
+                            format!("{} A, [{}]", op, register)
+                        }
+                    }
+                    0o004 => {
+                        let condition = match opcode & 0o070 {
+                            0o000 => "NZ",
+                            0o010 => "Z",
+                            0o020 => "NC",
+                            0o030 => "C",
+                            _ => panic!()
+                        };
+                        format!("CALL {}, {}", condition, n16())
+                    }
+                    0o005 => {
+                        let register = match opcode & 0o070 {
+                            0o000 => "BC",
+                            0o020 => "DE",
+                            0o040 => "HL",
+                            0o060 => "AF",
+                            _ => panic!()
+                        };
+                        format!("PUSH {}", register)
+                    }
+                    0o006 => {
+                        let op = match opcode & 0o070 {
+                            0o000 => "ADD",
+                            0o010 => "ADC",
+                            0o020 => "SUB",
+                            0o030 => "SBC",
+                            0o040 => "AND",
+                            0o050 => "XOR",
+                            0o060 => "OR",
+                            0o070 => "CP",
+                            _ => panic!()
+                        };
+                        format!("{} A, {}", op, n8())
+                    }
+                    0o007 => {
+                        let vector = match opcode & 0o070 {
+                            0o000 => "00",
+                            0o010 => "08",
+                            0o020 => "10",
+                            0o030 => "18",
+                            0o040 => "20",
+                            0o050 => "28",
+                            0o060 => "30",
+                            0o070 => "38",
+                            _ => panic!()
+                        };
+                        format!("RST ${}", vector)
+                    }
+                    _ => panic!("Unknown opcode {:o}", opcode)
+                }
+            }
+            _ => String::from("ERROR: Invalid opcode!")
+        }
+    }
+}
+
+fn prefixed_mnemonic(opcode: u8) -> String {
+    let register = match opcode & 0o007 {
+        0o000 => "B",
+        0o001 => "C",
+        0o002 => "D",
+        0o003 => "E",
+        0o004 => "H",
+        0o005 => "L",
+        0o006 => "[HL]",
+        0o007 => "A",
+        _ => panic!()
+    };
+    
+    if opcode & 0o300 == 0 {
+        let op = match opcode & 0o070 {
+            0o000 => "RLC",
+            0o010 => "RRC",
+            0o020 => "RL",
+            0o030 => "RR",
+            0o040 => "SLA",
+            0o050 => "SRA",
+            0o060 => "SWAP",
+            0o070 => "SRL",
+            _ => panic!()
+        };
+        format!("{} {}", op, register)
+    }
+    else {
+        let op = match opcode & 0o300 {
+            0o100 => "BIT",
+            0o200 => "RES",
+            0o300 => "SET",
+            _ => panic!()
+        };
+        let bit = (opcode & 0o070) >> 3;
+        format!("{} {}, {}", op, bit, register)
+    }
+}
+
+//How many M-cycles the instruction takes with the given flags, or None for the opcodes that lock up the CPU.
+//prefixed_opcode is only looked at for 0xCB
+pub fn m_cycles(opcode: u8, prefixed_opcode: u8, flags: u8) -> Option<u8> {
+    let m_cycles = match opcode {
+        //One-offs
+        //Block 0
+        0o000 | 0o020 => 1,
+        0o010 => 5,
+        
+        //Block 1
+        0o166 => 1,
+
+        //Block 3
+        0o340 | 0o360 | 0o370 => 3,
+        0o350 => 4,
+        0o311 | 0o331 => 4,
+        0o361 => 3,
+        0o351 => 1,
+        0o371 => 2,
+        0o303 => 4,
+        0o313 => {
+            if prefixed_opcode & 0o007 == 0o006 {
+                if prefixed_opcode & 0o300 == 0o100 {
+                    3
+                }
+                else {
+                    4
+                }
+            }
+            else {
+                2
+            }
+        }
+        0o363 | 0o373 => 1,
+        0o315 => 6,
+
+        //Invalid opcodes
+        0o323 | 0o333 | 0o335 | 0o343 | 0o344 | 0o353 | 0o354 | 0o355 | 0o364 | 0o374 | 0o375 => return None,
+
+        _ => {
+            match opcode & 0o300 {
+                0o000 => {
+                    match opcode & 0o007 {
+                        0o000 => {
+                            let jump_condition = match opcode & 0o070 {
+                                0o030 => true,
+                                0o040 => flags & Z_ZERO_FLAG == 0,
+                                0o050 => flags & Z_ZERO_FLAG > 0,
+                                0o060 => flags & C_CARRY_FLAG == 0,
+                                0o070 => flags & C_CARRY_FLAG > 0,
+                                _ => panic!("ERROR: Invalid condition octet")
+                            };
+
+                            if jump_condition {
+                                3
+                            }
+                            else {
+                                2
+                            }
+                        }
+                        0o001 => {
+                            if opcode & 0o010 == 0 {
+                                3
+                            }
+                            else {
+                                2
+                            }
+                        }
+                        0o002 | 0o003 => 2,
+                        0o004 | 0o005 => {
+                            if opcode & 0o070 == 0o060 {
+                                3
+                            }
+                            else {
+                                1
+                            }
+                        }
+                        0o006 => {
+                            if opcode & 0o070 == 0o060 {
+                                3
+                            }
+                            else {
+                                2
+                            }
+                        }
+                        0o007 => 1,
+                        _ => panic!("ERROR: invalid Column octet")
+                    }
+                }
+                0o100 => {
+                    if opcode & 0o070 == 0o060 || opcode & 0o007 == 0o006 {
+                        2
+                    }
+                    else {
+                        1
+                    }
+                }
+                0o200 => {
+                    if opcode & 0o007 == 0o006 {
+                        2
+                    }
+                    else {
+                        1
+                    }
+                }
+                0o300 => {
+                    match opcode & 0o007 {
+                        0o000 => {
+                            let jump_condition = match opcode & 0o070 {
+                                0o000 => flags & Z_ZERO_FLAG == 0,
+                                0o010 => flags & Z_ZERO_FLAG > 0,
+                                0o020 => flags & C_CARRY_FLAG == 0,
+                                0o030 => flags & C_CARRY_FLAG > 0,
+                                _ => panic!("Error: Invalid condition opcode")
+                            };
+
+                            if jump_condition {
+                                5
+                            }
+                            else {
+                                2
+                            }
+                        }
+                        0o001 => 3,
+                        0o002 => {
+                            if opcode & 0o040 == 0 {
+                                let jump_condition = match opcode & 0o070 {
+                                    0o000 => flags & Z_ZERO_FLAG == 0,
+                                    0o010 => flags & Z_ZERO_FLAG > 0,
+                                    0o020 => flags & C_CARRY_FLAG == 0,
+                                    0o030 => flags & C_CARRY_FLAG > 0,
+                                    _ => panic!("Error: Invalid condition opcode")
+                                };
+
+                                if jump_condition {
+                                    4
+                                }
+                                else {
+                                    3
+                                }
+                            }
+                            else if opcode & 0o010 == 0 {
+                                2
+                            }
+                            else {
+                                4
+                            }
+                        }
+                        0o004 => {
+                            let jump_condition = match opcode & 0o070 {
+                                0o000 => flags & Z_ZERO_FLAG == 0,
+                                0o010 => flags & Z_ZERO_FLAG > 0,
+                                0o020 => flags & C_CARRY_FLAG == 0,
+                                0o030 => flags & C_CARRY_FLAG > 0,
+                                _ => panic!("Error: Invalid condition opcode")
+                            };
+
+                            if jump_condition {
+                                6
+                            }
+                            else {
+                                3
+                            }
+                        }
+                        0o005 => 4,
+                        0o006 => 2,
+                        0o007 => 4,
+                        _ => panic!("Error: Invalid column octet")
+                    }
+                }
+                _ => panic!("ERROR: Unrecognized opcode block")
+            }
+        }
+    };
+    Some(m_cycles)
+}
+
+//How Z, N, H and C are affected: the letter when it's set from the result, 0 or 1 when it's forced, - when it's left alone
+pub fn flag_effects(opcode: u8, prefixed_opcode: u8) -> &'static str {
+    match opcode {
+        0o007 | 0o017 | 0o027 | 0o037 => "000C",
+        0o047 => "Z-0C",
+        0o057 => "-11-",
+        0o067 => "-001",
+        0o077 => "-00C",
+        0o350 | 0o370 => "00HC",
+        0o361 => "ZNHC", //POP AF
+        0o313 => match prefixed_opcode & 0o300 {
+            0o000 if prefixed_opcode & 0o070 == 0o060 => "Z000", //SWAP
+            0o000 => "Z00C",
+            0o100 => "Z01-",
+            _ => "----",
+        },
+        _ if opcode & 0o300 == 0o200 || opcode & 0o307 == 0o306 => match opcode & 0o070 {
+            0o000 | 0o010 => "Z0HC",
+            0o020 | 0o030 | 0o070 => "Z1HC",
+            0o040 => "Z010",
+            _ => "Z000",
+        },
+        _ => match opcode & 0o317 {
+            0o011 => "-0HC", //ADD HL, r16
+            _ if opcode & 0o307 == 0o004 => "Z0H-",
+            _ if opcode & 0o307 == 0o005 => "Z1H-",
+            _ => "----",
+        },
+    }
+}
+
+//Instruction length in bytes, worked out from which operands the mnemonic takes
+pub fn length(opcode: u8) -> u8 {
+    if opcode == 0o313 {
+        return 2;
+    }
+
+    let template = mnemonic(opcode, None);
+    if template.contains("n16") {
+        3
+    }
+    else if template.contains("n8") || template.contains("e8") {
+        2
+    }
+    else {
+        1
+    }
+}
+
+pub struct OpcodeInfo {
+    pub bytes: Vec<u8>, //The opcode, after 0xCB for prefixed ones
+    pub mnemonic: String,
+    pub length: u8,
+    pub m_cycles: u8,
+    pub m_cycles_not_taken: Option<u8>, //For conditional jumps, calls and returns
+    pub flags: &'static str,
+}
+
+impl OpcodeInfo {
+    fn new(opcode: u8, prefixed_opcode: u8) -> Option<Self> {
+        let taken = m_cycles(opcode, prefixed_opcode, 0)?;
+        let other = m_cycles(opcode, prefixed_opcode, Z_ZERO_FLAG | C_CARRY_FLAG)?;
+        let is_prefixed = opcode == 0o313;
+
+        Some(Self {
+            bytes: if is_prefixed {vec![opcode, prefixed_opcode]} else {vec![opcode]},
+            mnemonic: if is_prefixed {prefixed_mnemonic(prefixed_opcode)} else {mnemonic(opcode, None)},
+            length: length(opcode),
+            m_cycles: taken.max(other),
+            m_cycles_not_taken: (taken != other).then(|| taken.min(other)),
+            flags: flag_effects(opcode, prefixed_opcode),
+        })
+    }
+
+    pub fn hex(&self) -> String {
+        self.bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<String>>().join(" ")
+    }
+
+    //Matches a mnemonic fragment like "ld a" or an opcode in hex like "CB 37", "$3E" or "0x3e"
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_ascii_uppercase();
+        let hex_query = query.trim_start_matches("0X").trim_start_matches('$').replace(' ', "");
+        query.is_empty() || self.mnemonic.to_ascii_uppercase().contains(&query) || (!hex_query.is_empty() && self.hex().replace(' ', "").starts_with(&hex_query))
+    }
+}
+
+//Every valid opcode, built from the same functions the CPU uses for timing and the debugger uses for mnemonics
+pub fn reference() -> &'static [OpcodeInfo] {
+    static REFERENCE: OnceLock<Vec<OpcodeInfo>> = OnceLock::new();
+    REFERENCE.get_or_init(|| {
+        let unprefixed = (0..=0xFF).filter(|opcode| *opcode != 0o313).filter_map(|opcode| OpcodeInfo::new(opcode, 0));
+        let prefixed = (0..=0xFF).filter_map(|prefixed_opcode| OpcodeInfo::new(0o313, prefixed_opcode));
+        unprefixed.chain(prefixed).collect()
+    })
+}
@@ -0,0 +1,895 @@
+use std::{collections::VecDeque, io::{self, Read, Write}};
+
+use super::{console::HardwareMode, raster_hooks::RasterRegisters, savestate::*};
+#[cfg(feature = "debugger")]
+use super::inspector::PPUSnapshot;
+
+pub struct PPU {
+    //Memory
+    video_ram: Vec<[u8; 0x4000]>,
+    video_ram_index: usize,
+    object_attribute_memory: [u8; 0xA0],
+
+    //LCDC register values
+        //All u16 lcdc values are subtracted by 0x8000 so they can be plugged into video_ram directly
+    lcdc_7_lcd_enabled: bool, 
+    lcdc_6_window_tile_map_area: u16, //0x9800, 0x9C00
+    lcdc_5_window_enabled: bool,
+    lcdc_4_tile_data_area: bool, //0: 0x8800, 1: 0x8000
+    lcdc_3_bg_tile_map_area: u16, //0x9800, 0x9C00
+    lcdc_2_obj_is_tall: bool, //0: 8x8, 1: 8x16
+    lcdc_1_obj_enable: bool,
+    lcdc_0_bg_window_enable: bool,
+
+    //STAT register values
+    ppu_mode:u8,
+    stat: u8,
+
+    //CGB palette registers. Each palette is 4 little-endian RGB555 colors
+    bg_palette_index: u8, //BCPS, bit 7 increments it after each BCPD write
+    obj_palette_index: u8, //OCPS
+    bg_palette_ram: [u8; 0x40],
+    obj_palette_ram: [u8; 0x40],
+    hardware_mode: HardwareMode,
+
+    //Standalone registers
+    ly: u8,
+    ly_compare: u8,
+    scy: u8,
+    scx: u8,
+    wy: u8,
+    wx: u8,
+
+    //Buffers for the rendering process
+    obj_buffer: Vec<u16>,
+    bg_fifo: VecDeque<Pixel>,
+    obj_fifo: VecDeque<Pixel>,
+    screen: Box<[[Pixel; 160]; 144]>,
+    is_frame_skipped: bool, //The frame is timed and fetched as usual, but its pixels are never written to the screen
+
+    //Misc. variables
+    dot_counter: u16, //The current dot on the current scanline;
+    mode_3_penalty: u8,
+    obj_penalty_tile: Option<(bool, u8)>, //The last background or window tile an object waited on this scanline
+    #[cfg(feature = "debugger")]
+    mode_3_lengths: Box<[u16; 144]>, //Dots each visible scanline spent in mode 3, for the debugger
+    bg_fetch_state: u8,
+    obj_fetch_state: u8,
+    fetched_obj_address: u16,
+    lx: u8, //The current pixel being pushed in mode 3
+    w_ly: u8, //The current line of the window
+    w_lx: u8, //The currrent x coordinate of the window
+    ly_eq_wy: bool, //Whether or not ly = wy is true at any point in the frame
+    is_window_fetching_mode: bool,
+}
+
+//PPU mode values 
+const PPU_MODE_0_HBLANK: u8      = 0;
+const PPU_MODE_1_VBLANK: u8      = 1;
+const PPU_MODE_2_OAM_SCAN:  u8   = 2;
+const PPU_MODE_3_DRAW_PIXELS: u8 = 3;
+
+impl PPU {
+    pub fn new(hardware_mode: HardwareMode) -> Self {
+        //CGB has a second bank of VRAM, which holds more tiles and the background attribute map
+        let video_ram = vec![[0; 0x4000]; if hardware_mode == HardwareMode::Cgb {2} else {1}];
+
+        Self {
+            video_ram,
+            video_ram_index: 0,
+            object_attribute_memory: [0; 0xA0],
+            lcdc_7_lcd_enabled: true,
+            lcdc_6_window_tile_map_area: 0x9800 - 0x8000,
+            lcdc_5_window_enabled: false,
+            lcdc_4_tile_data_area: false,
+            lcdc_3_bg_tile_map_area: 0x9800 - 0x8000,
+            lcdc_2_obj_is_tall: false,
+            lcdc_1_obj_enable: false,
+            lcdc_0_bg_window_enable: true,
+            ppu_mode: PPU_MODE_1_VBLANK,
+            stat: 0x85,
+            bg_palette_index: 0,
+            obj_palette_index: 0,
+            bg_palette_ram: [0xFF; 0x40],
+            obj_palette_ram: [0xFF; 0x40],
+            hardware_mode,
+            ly: 0,
+            ly_compare: 0,
+            scy: 0x00,
+            scx: 0x00,
+            wy: 0x00,
+            wx: 0x00,
+            obj_buffer: Vec::with_capacity(10),
+            bg_fifo: VecDeque::with_capacity(8),
+            obj_fifo: VecDeque::with_capacity(8),
+            screen: Box::new([[Pixel {color: 0, palette: None, bg_priority: None, tile: None, rgb: None}; 160]; 144]),
+            is_frame_skipped: false,
+            dot_counter: 0,
+            mode_3_penalty: 0,
+            obj_penalty_tile: None,
+            #[cfg(feature = "debugger")]
+            mode_3_lengths: Box::new([0; 144]),
+            bg_fetch_state: 250,
+            obj_fetch_state: 7,
+            fetched_obj_address: 0,
+            lx: 0,
+            w_ly: 0,
+            w_lx: 0,
+            ly_eq_wy: false,
+            is_window_fetching_mode: false,
+        }
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        if address >= 0x8000 && address <= 0x9fff {
+            if self.ppu_mode != PPU_MODE_3_DRAW_PIXELS {
+                self.video_ram[self.video_ram_index][(address - 0x8000) as usize]
+            }
+            else {
+                0xFF
+            }
+        }
+        else if address >= 0xFE00 && address <= 0xFE9F {
+            if self.ppu_mode != PPU_MODE_2_OAM_SCAN && self.ppu_mode != PPU_MODE_3_DRAW_PIXELS {
+                self.object_attribute_memory[(address - 0xFE00) as usize]
+            }
+            else {
+                0xFF
+            }
+        }
+        else if address >= 0xFF00 && address <= 0xFF7F {
+            //TODO: Implement PPU registers
+            match address {
+                0xFF40 => self.read_lcdc(), //LCDC
+                0xFF41 => self.stat,
+                0xFF42 => self.scy,
+                0xFF43 => self.scx,
+                0xFF44 => self.ly,
+                0xFF45 => self.ly_compare, //LYC
+                0xFF4A => self.wy,
+                0xFF4B => self.wx,
+                0xFF4F => 0xFE | self.video_ram_index as u8, //VBK
+                0xFF68 => self.bg_palette_index | 0x40, //BCPS
+                0xFF69 => self.read_palette_ram(false), //BCPD
+                0xFF6A => self.obj_palette_index | 0x40, //OCPS
+                0xFF6B => self.read_palette_ram(true), //OCPD
+                _ => panic!("ERROR: Unknown register at address ${:x}", address)
+            }
+        }
+        else {
+            panic!("ERROR: Address out of bounds!")
+        }
+    }
+
+    //Palette RAM can't be reached while the PPU is drawing, like VRAM
+    fn read_palette_ram(&self, is_obj: bool) -> u8 {
+        if self.ppu_mode == PPU_MODE_3_DRAW_PIXELS {
+            return 0xFF;
+        }
+        match is_obj {
+            true => self.obj_palette_ram[(self.obj_palette_index & 0x3F) as usize],
+            false => self.bg_palette_ram[(self.bg_palette_index & 0x3F) as usize],
+        }
+    }
+
+    fn write_palette_ram(&mut self, is_obj: bool, value: u8) {
+        let (index, palette_ram) = match is_obj {
+            true => (&mut self.obj_palette_index, &mut self.obj_palette_ram),
+            false => (&mut self.bg_palette_index, &mut self.bg_palette_ram),
+        };
+        if self.ppu_mode != PPU_MODE_3_DRAW_PIXELS {
+            palette_ram[(*index & 0x3F) as usize] = value;
+        }
+        //The index still increments when the write is blocked
+        if *index & 0x80 > 0 {
+            *index = 0x80 | ((*index + 1) & 0x3F);
+        }
+    }
+
+    fn read_lcdc(&self) -> u8 {
+        let mut lcdc = 0;
+        if self.lcdc_7_lcd_enabled {
+            lcdc |= 128;
+        }
+        if self.lcdc_6_window_tile_map_area == 0x9C00 - 0x8000 {
+            lcdc |= 64;
+        }
+        if self.lcdc_5_window_enabled {
+            lcdc |= 32;
+        }
+        if self.lcdc_4_tile_data_area {
+            lcdc |= 16;
+        }
+        if self.lcdc_3_bg_tile_map_area == 0x9c00 - 0x8000 {
+            lcdc |= 8;
+        }
+        if self.lcdc_2_obj_is_tall {
+            lcdc |= 4;
+        }
+        if self.lcdc_1_obj_enable {
+            lcdc |= 2;
+        }
+        if self.lcdc_0_bg_window_enable {
+            lcdc |= 1;
+        }
+
+        lcdc
+    }
+
+    fn write_lcdc(&mut self, value: u8) {
+        self.lcdc_7_lcd_enabled = value & 128 > 0;
+        self.lcdc_6_window_tile_map_area = if value & 64 > 0 {0x9C00 - 0x8000} else {0x9800 - 0x8000};
+        self.lcdc_5_window_enabled = value & 32 > 0;
+        self.lcdc_4_tile_data_area = value & 16 > 0;
+        self.lcdc_3_bg_tile_map_area = if value & 8 > 0 {0x9C00 - 0x8000} else {0x9800 - 0x8000};
+        self.lcdc_2_obj_is_tall = value & 4 > 0;
+        self.lcdc_1_obj_enable = value & 2 > 0;
+        self.lcdc_0_bg_window_enable = value & 1 > 0;
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        if address >= 0x8000 && address <= 0x9fff {
+            if self.ppu_mode != PPU_MODE_3_DRAW_PIXELS {
+                self.video_ram[self.video_ram_index][(address - 0x8000) as usize] = value;
+            }
+        }
+        else if address >= 0xFE00 && address <= 0xFE9F {
+            if self.ppu_mode != PPU_MODE_2_OAM_SCAN && self.ppu_mode != PPU_MODE_3_DRAW_PIXELS {
+                self.object_attribute_memory[(address - 0xFE00) as usize] = value;
+            }
+        }
+        else if address >= 0xFF00 && address <= 0xFF7F {
+            let mut value = value;
+
+            //TODO: Implement PPU registers
+            let register = match address {
+                0xFF40 => { //LCDC
+                    self.write_lcdc(value);
+
+                    if !self.lcdc_7_lcd_enabled {
+                        self.ly = 0;
+                        self.dot_counter = 0;
+                        self.ppu_mode = PPU_MODE_0_HBLANK;
+                        self.stat &= 0xFC;
+                    }
+                    return;
+                }
+                0xFF41 => { //STAT
+                    value |= 0x80;
+                    &mut self.stat
+                }
+                0xFF42 => &mut self.scy,
+                0xFF43 => &mut self.scx,
+                0xFF44 => return, //LY is read only!
+                0xFF45 => &mut self.ly_compare, //LYC
+                0xFF4A => &mut self.wy,
+                0xFF4B => &mut self.wx,
+                0xFF4F => { //VBK
+                    self.video_ram_index = (value & 1) as usize;
+                    return;
+                }
+                0xFF68 => { //BCPS
+                    value &= 0xBF;
+                    &mut self.bg_palette_index
+                }
+                0xFF69 => { //BCPD
+                    self.write_palette_ram(false, value);
+                    return;
+                }
+                0xFF6A => { //OCPS
+                    value &= 0xBF;
+                    &mut self.obj_palette_index
+                }
+                0xFF6B => { //OCPD
+                    self.write_palette_ram(true, value);
+                    return;
+                }
+                _ => panic!("ERROR: Unkown register at address ${:x}", address)
+            };
+
+            *register = value;
+        }
+        else {
+            panic!("ERROR: Address out of bounds!")
+        }
+    }
+
+    pub fn update(&mut self) {
+        match self.ppu_mode {
+            PPU_MODE_0_HBLANK => {
+                
+            }
+            PPU_MODE_1_VBLANK => {
+
+            }
+            PPU_MODE_2_OAM_SCAN => {
+                //Hidden objects aren't fetched, so they don't stretch mode 3 either
+                if self.dot_counter & 1 == 0 && self.obj_buffer.len() < 10 && self.lcdc_1_obj_enable {
+                    let obj_address = self.dot_counter << 1;
+                    let obj_y = self.object_attribute_memory[obj_address as usize].wrapping_sub(16);
+
+                    let obj_height = match self.lcdc_2_obj_is_tall {
+                        true => 16,
+                        false => 8
+                    };
+                    if self.ly >= obj_y && self.ly < obj_y.wrapping_add(obj_height) {
+                        self.obj_buffer.push(obj_address);
+                    }
+                }
+            }
+            PPU_MODE_3_DRAW_PIXELS => {
+                let bg_tile_map_index = self.lcdc_3_bg_tile_map_area as usize;
+                let w_tile_map_index = self.lcdc_6_window_tile_map_area as usize;
+
+                if self.ly == self.wy {
+                    self.ly_eq_wy = true;
+                }
+                if !self.is_window_fetching_mode {
+                    self.is_window_fetching_mode = self.lcdc_5_window_enabled && self.ly_eq_wy && (self.lx + 7) >= self.wx;
+                    if self.is_window_fetching_mode {
+                        //Mid-line, restarting the fetcher stalls the pixel pipe for the window's 6 dot penalty. At the start of
+                        //the line the initial fetches are still running, so they're left alone and the penalty is added below
+                        self.bg_fifo.clear();
+                        if self.bg_fetch_state <= 6 {
+                            self.bg_fetch_state = 0;
+                        }
+                    }
+                }
+
+                //Background/Window Fetching
+                if self.bg_fetch_state == 6 {
+                    //every 8 pixels (after the initial pixels are pushed), fetch a new tile
+                    if self.bg_fifo.is_empty() {
+                        if self.is_window_fetching_mode {
+                            let tile_map_offset_x = (self.w_lx >> 3) as usize;
+                            let tile_map_offset_y = (((self.w_ly as u16) & 0xF8) << 2) as usize;
+                            let tile_map_address = w_tile_map_index + tile_map_offset_x + tile_map_offset_y;
+                            self.bg_fifo = self.tile_fetch_w(self.video_ram[0][tile_map_address], self.bg_attributes(tile_map_address));
+
+                            //A window starting left of the screen (WX < 7) has its hidden pixels discarded, like SCX does
+                            if self.lx == 0 && self.w_lx == 0 {
+                                let offset = 7_u8.saturating_sub(self.wx);
+                                for _ in 0..offset {
+                                    self.bg_fifo.pop_front();
+                                }
+
+                                self.w_lx += offset;
+                                self.mode_3_penalty += 6 + offset;
+                            }
+                        }
+                        else {
+                            let tile_map_offset_x = (self.lx.wrapping_add(self.scx) >> 3) as usize;
+                            let tile_map_offset_y = (((self.ly as u16 + self.scy as u16) & 0xF8) << 2) as usize;
+                            let tile_map_address = bg_tile_map_index + tile_map_offset_x + tile_map_offset_y;
+                            self.bg_fifo = self.tile_fetch_bg(self.video_ram[0][tile_map_address], self.bg_attributes(tile_map_address));
+
+                            if self.lx == 0 {
+                                let offset = self.scx & 0b111;
+
+                                for _ in 0..offset {
+                                    self.bg_fifo.pop_front();
+                                }
+
+                                self.mode_3_penalty += offset;
+                            }
+                        }
+
+                        self.bg_fetch_state = 0;
+                    }
+                }
+                else {
+                    self.bg_fetch_state = self.bg_fetch_state.wrapping_add(1);
+                }
+
+                //Object fetching
+                if self.obj_fetch_state == 7 { //Get Tile address
+                    let mut has_been_fetched = false;
+
+                    if self.lx == 0 {
+                        //Fetch objects with x coordinates that are slightly off screen to the left
+                        for offset in 0..8 {
+                            for index in 0..self.obj_buffer.len() {
+                                let object = self.obj_buffer[index];
+                                if self.object_attribute_memory[object as usize + 1] == offset {
+                                    self.fetched_obj_address = object;
+                                    self.obj_fetch_state = 0;
+                                    self.mode_3_penalty += self.obj_penalty(object);
+                                    self.obj_buffer.remove(index);
+                                    has_been_fetched = true;
+                                    break;
+                                }
+                            }
+                            if has_been_fetched {
+                                break;
+                            }
+                        }
+                    } 
+
+                    if !has_been_fetched {
+                        //Fetch objects with the same x coordinate as the current pixel
+                        for index in 0..self.obj_buffer.len() {
+                            let object = self.obj_buffer[index];
+                            if self.object_attribute_memory[object as usize + 1].wrapping_sub(8) == self.lx {
+                                self.fetched_obj_address = object;
+                                self.obj_fetch_state = 0;
+                                self.mode_3_penalty += self.obj_penalty(object);
+                                self.obj_buffer.remove(index);
+                                break;
+                            }
+                        }
+                    }
+                }
+                else if self.obj_fetch_state == 4 { //Fetch tile data
+                    let mut pixel_row = self.tile_fetch_obj(self.fetched_obj_address);
+
+                    if self.lx == 0 {
+                        let offset = 8 - self.object_attribute_memory[self.fetched_obj_address as usize + 1];
+
+                        for _ in 0..offset {
+                            pixel_row.pop_front();
+                        }
+                    }
+
+
+                    for pixel_index in 0..self.obj_fifo.len() {
+                        if self.obj_fifo[pixel_index].color == 0 {
+                            self.obj_fifo[pixel_index] = pixel_row.pop_front().unwrap();
+                        }
+                        else {
+                            pixel_row.pop_front();
+                        }
+                    }
+                    self.obj_fifo.extend(pixel_row);
+                    self.obj_fetch_state = 7;
+                }
+                else {
+                    self.obj_fetch_state += 1;
+                }
+
+                //End of cycle
+                if self.mode_3_penalty == 0 {
+                    //Pixel Mixing
+                    if !self.bg_fifo.is_empty() {
+                        let mut bg_pixel = self.bg_fifo.pop_front().unwrap();
+                        let obj_pixel = self.obj_fifo.pop_front();
+                        //On CGB, LCDC.0 doesn't hide the background. It takes away its priority over objects instead
+                        if !self.lcdc_0_bg_window_enable && self.hardware_mode == HardwareMode::Dmg {
+                            bg_pixel = Pixel { color: 0, palette: None, bg_priority: None, tile: None, rgb: None }
+                        }
+                        let pixel = match obj_pixel {
+                            Some(obj_pixel) => {
+                                if !self.lcdc_1_obj_enable {
+                                    bg_pixel
+                                }
+                                else if obj_pixel.color == 0 {
+                                    bg_pixel
+                                }
+                                else if self.lcdc_0_bg_window_enable && bg_pixel.color != 0 && (obj_pixel.bg_priority.unwrap() || bg_pixel.bg_priority == Some(true)) {
+                                    bg_pixel
+                                }
+                                else {
+                                    obj_pixel
+                                }
+                            }
+                            None => bg_pixel,
+                        };
+                        if !self.is_frame_skipped {
+                            self.screen[self.ly as usize][self.lx as usize] = pixel;
+                        }
+                        self.lx += 1;
+                        if self.is_window_fetching_mode {
+                            self.w_lx += 1;
+                        } 
+                    }
+                }
+                else if !self.bg_fifo.is_empty() {
+                    self.mode_3_penalty -= 1;
+                }
+            }
+            _ => panic!("ERROR: Invalid PPU Mode \"{}\"", self.ppu_mode)
+        }
+
+        if self.lcdc_7_lcd_enabled {
+            self.dot_counter += 1;
+            if self.ppu_mode == PPU_MODE_2_OAM_SCAN && self.dot_counter == 80 {
+                self.ppu_mode = PPU_MODE_3_DRAW_PIXELS;
+            }
+            else if self.ppu_mode == PPU_MODE_3_DRAW_PIXELS && self.lx == 160 {
+                #[cfg(feature = "debugger")]
+                if let Some(length) = self.mode_3_lengths.get_mut(self.ly as usize) {
+                    *length = self.dot_counter - 80;
+                }
+                self.ppu_mode = PPU_MODE_0_HBLANK;
+                self.obj_penalty_tile = None;
+                self.lx = 0;
+                self.w_lx = 0;
+                if self.is_window_fetching_mode {
+                    self.w_ly += 1;
+                }
+                self.is_window_fetching_mode = false;
+                self.obj_buffer.clear();
+                self.bg_fifo.clear();
+                self.obj_fifo.clear();
+                self.bg_fetch_state = 250; //-6 because the ppu does 2 tile fetches at the beginning of mode 3 and always discards the first
+                self.obj_fetch_state = 7;
+            }
+            else if self.dot_counter == 456 {
+                self.dot_counter = 0;
+                if self.ly >= 153 {
+                    self.ly = 0;
+                    self.w_ly = 0;
+                    self.ly_eq_wy = false;
+                    self.ppu_mode = PPU_MODE_2_OAM_SCAN;
+                }
+                else {
+                    self.ly += 1;
+
+                    if self.ppu_mode == PPU_MODE_0_HBLANK && self.ly < 144 {
+                        self.ppu_mode = PPU_MODE_2_OAM_SCAN;
+                    }
+                    else {
+                        self.ppu_mode = PPU_MODE_1_VBLANK;
+                    }
+                }
+            }
+
+            let mut stat = self.stat & 0b11111000;
+            if self.ly == self.ly_compare {
+                stat |= 0b100;
+            }
+            stat |= self.ppu_mode;
+
+            self.stat = stat;
+        }
+    }
+
+    //6 dots for fetching the object, plus up to 5 more waiting for the fetcher to finish the background or window tile
+    //under the object's leftmost pixel. Only the first object over a tile waits for it
+    fn obj_penalty(&mut self, object: u16) -> u8 {
+        let obj_x = self.object_attribute_memory[object as usize + 1];
+        if obj_x == 0 {
+            return 11;
+        }
+
+        //Where the object's leftmost pixel lands in the tile map. It may be off the left edge of the screen
+        let map_x = if self.is_window_fetching_mode {
+            obj_x.wrapping_sub(self.wx).wrapping_sub(1)
+        }
+        else {
+            obj_x.wrapping_add(self.scx).wrapping_sub(8)
+        };
+        let tile = (self.is_window_fetching_mode, map_x >> 3);
+        if self.obj_penalty_tile == Some(tile) {
+            return 6;
+        }
+        self.obj_penalty_tile = Some(tile);
+        6 + 5_u8.saturating_sub(map_x & 0b111)
+    }
+
+    fn tile_row_fetch(&self, tile_index: u8, tile_height: u16, y_flip: bool, x_flip: bool, bank: usize, is_obj: bool) -> VecDeque<u8> {
+        let mut tile_row = VecDeque::with_capacity(8);
+        let tile_index = match is_obj && self.lcdc_2_obj_is_tall {
+            true => tile_index & 0xFE,
+            false => tile_index
+        };
+
+        let tile_address = match self.lcdc_4_tile_data_area || is_obj {
+            true => (tile_index as u16) << 4,
+            false => {
+                let area_start = if tile_index & 0x80 > 0 {0x8000 - 0x8000} else {0x9000 - 0x8000};
+                area_start + ((tile_index as u16) << 4)
+            }
+        };
+
+        let flip_edge = match is_obj && self.lcdc_2_obj_is_tall {
+            true => 30,
+            false => 14
+        };
+        let row_offset = match y_flip {
+            true => flip_edge - (tile_height << 1),
+            false => tile_height << 1
+        };
+        let lsb = self.video_ram[bank][(tile_address + row_offset) as usize];
+        let msb = self.video_ram[bank][(tile_address + row_offset) as usize + 1];
+        for bit in 0..8 {
+            let mut pixel = 0;
+            if lsb & (1 << bit) > 0 {
+                pixel |= 0b1;
+            }
+            if msb & (1 << bit) > 0 {
+                pixel |= 0b10;
+            }
+
+            match x_flip {
+                true => tile_row.push_back(pixel),
+                false => tile_row.push_front(pixel),
+            }
+        }
+
+        tile_row
+    }
+
+    //On CGB, each tile in a map has an attribute byte at the same address in VRAM bank 1
+    fn bg_attributes(&self, tile_map_address: usize) -> Option<u8> {
+        self.video_ram.get(1).map(|bank| bank[tile_map_address])
+    }
+
+    fn tile_fetch_bg(&self, tile_index: u8, attributes: Option<u8>) -> VecDeque<Pixel> {
+        let tile_height = (self.ly as u16 + self.scy as u16) & 0b111;
+        self.tile_fetch_bg_w(tile_index, tile_height, attributes)
+    }
+
+    fn tile_fetch_w(&self, tile_index: u8, attributes: Option<u8>) -> VecDeque<Pixel> {
+        let tile_height = (self.w_ly as u16) & 0b111;
+        self.tile_fetch_bg_w(tile_index, tile_height, attributes)
+    }
+
+    fn tile_fetch_bg_w(&self, tile_index: u8, tile_height: u16, attributes: Option<u8>) -> VecDeque<Pixel> {
+        let mut pixel_row = VecDeque::with_capacity(8);
+        match attributes {
+            Some(attributes) => {
+                let y_flip = attributes & 0b1000000 > 0;
+                let x_flip = attributes & 0b100000 > 0;
+                let bank = ((attributes & 0b1000) >> 3) as usize;
+                let bg_priority = attributes & 0b10000000 > 0;
+                let palette = attributes & 0b111;
+                for pixel in self.tile_row_fetch(tile_index, tile_height, y_flip, x_flip, bank, false) {
+                    let rgb = Self::palette_color(&self.bg_palette_ram, palette, pixel);
+                    pixel_row.push_back(Pixel{color: pixel, palette: None, bg_priority: Some(bg_priority), tile: None, rgb: Some(rgb)});
+                }
+            }
+            None => {
+                for pixel in self.tile_row_fetch(tile_index, tile_height, false, false, 0, false) {
+                    pixel_row.push_back(Pixel{color: pixel, palette: None, bg_priority: None, tile: None, rgb: None});
+                }
+            }
+        }
+
+        pixel_row
+    }
+
+    //Palette RAM can't change while the PPU is drawing, so colors can be looked up as soon as tiles are fetched
+    fn palette_color(palette_ram: &[u8; 0x40], palette: u8, color: u8) -> u16 {
+        let index = ((palette << 3) | (color << 1)) as usize;
+        u16::from_le_bytes([palette_ram[index], palette_ram[index + 1]]) & 0x7FFF
+    }
+
+    fn tile_fetch_obj(&self, oam_index: u16) -> VecDeque<Pixel> {
+        //Masked to the object's height in case an OAM DMA moved it after the scan picked it
+        let obj_height_mask = if self.lcdc_2_obj_is_tall {0xF} else {0x7};
+        let tile_height = (self.ly as u16).wrapping_sub(self.object_attribute_memory[oam_index as usize].wrapping_sub(16) as u16) & obj_height_mask;
+        let tile_index = self.object_attribute_memory[oam_index as usize + 2];
+        let obj_attributes = self.object_attribute_memory[oam_index as usize + 3];
+        let y_flip = obj_attributes & 0b1000000 > 0;
+        let x_flip = obj_attributes & 0b100000 > 0;
+        let bank = match self.hardware_mode {
+            HardwareMode::Cgb => ((obj_attributes & 0b1000) >> 3) as usize,
+            HardwareMode::Dmg => 0,
+        };
+        let color_row = self.tile_row_fetch(tile_index, tile_height, y_flip, x_flip, bank, true);
+
+        let mut pixel_row = VecDeque::with_capacity(8);
+        let bg_priority = obj_attributes & 0b10000000 > 0;
+        let palette = (obj_attributes & 0b10000) >> 4;
+        let tile = self.object_attribute_memory[oam_index as usize + 1].wrapping_sub(8).wrapping_add(self.scx) & 0b11111000;
+
+
+        for pixel in color_row {
+            let rgb = match self.hardware_mode {
+                HardwareMode::Cgb => Some(Self::palette_color(&self.obj_palette_ram, obj_attributes & 0b111, pixel)),
+                HardwareMode::Dmg => None,
+            };
+            pixel_row.push_back(Pixel{color: pixel, palette: Some(palette), bg_priority: Some(bg_priority), tile: Some(tile), rgb});
+        }
+
+        pixel_row
+    }
+
+    pub fn get_mode(&self) -> u8 {
+        self.ppu_mode
+    }
+
+    pub fn has_entered_vblank(&self) -> bool {
+        self.ly == 144 && self.dot_counter == 0
+    }
+
+    pub fn raster_registers(&self, bgp: u8, obp0: u8, obp1: u8) -> RasterRegisters {
+        RasterRegisters {
+            scx: self.scx,
+            scy: self.scy,
+            wx: self.wx,
+            wy: self.wy,
+            bgp,
+            obp0,
+            obp1,
+            bg_palette_ram: self.bg_palette_ram,
+            obj_palette_ram: self.obj_palette_ram,
+        }
+    }
+
+    pub fn set_frame_skip(&mut self, is_skipped: bool) {
+        self.is_frame_skipped = is_skipped;
+    }
+
+    pub fn set_raster_registers(&mut self, registers: &RasterRegisters) {
+        self.scx = registers.scx;
+        self.scy = registers.scy;
+        self.wx = registers.wx;
+        self.wy = registers.wy;
+        if self.hardware_mode == HardwareMode::Cgb {
+            self.bg_palette_ram = registers.bg_palette_ram;
+            self.obj_palette_ram = registers.obj_palette_ram;
+        }
+    }
+
+    pub fn dma_transfer(&mut self, value: u8, address: u8) {
+        self.object_attribute_memory[address as usize] = value;
+    }
+
+    //VRAM DMA writes to the selected bank whatever mode the PPU is in
+    pub fn vram_dma_transfer(&mut self, address: u16, value: u8) {
+        self.video_ram[self.video_ram_index][(address & 0x1FFF) as usize] = value;
+    }
+
+    pub fn save_state(&self, w: &mut dyn Write) -> io::Result<()> {
+        write_u8(w, self.video_ram.len() as u8)?;
+        for bank in self.video_ram.iter() {
+            w.write_all(bank)?;
+        }
+        write_u8(w, self.video_ram_index as u8)?;
+        w.write_all(&self.object_attribute_memory)?;
+
+        let lcdc = self.read_lcdc();
+        write_u8(w, lcdc)?;
+        write_u8(w, self.ppu_mode)?;
+        write_u8(w, self.stat)?;
+        for register in [self.ly, self.ly_compare, self.scy, self.scx, self.wy, self.wx] {
+            write_u8(w, register)?;
+        }
+
+        write_u8(w, self.obj_buffer.len() as u8)?;
+        for object in self.obj_buffer.iter() {
+            write_u16(w, *object)?;
+        }
+        for fifo in [&self.bg_fifo, &self.obj_fifo] {
+            write_u8(w, fifo.len() as u8)?;
+            for pixel in fifo.iter() {
+                write_pixel(w, pixel)?;
+            }
+        }
+        for row in self.screen.iter() {
+            for pixel in row.iter() {
+                write_pixel(w, pixel)?;
+            }
+        }
+
+        write_u16(w, self.dot_counter)?;
+        write_u8(w, self.mode_3_penalty)?;
+        write_bool(w, self.obj_penalty_tile.is_some_and(|(is_window, _)| is_window))?;
+        write_option_u8(w, self.obj_penalty_tile.map(|(_, tile)| tile))?;
+        write_u8(w, self.bg_fetch_state)?;
+        write_u8(w, self.obj_fetch_state)?;
+        write_u16(w, self.fetched_obj_address)?;
+        write_u8(w, self.lx)?;
+        write_u8(w, self.w_ly)?;
+        write_u8(w, self.w_lx)?;
+        write_bool(w, self.ly_eq_wy)?;
+        write_bool(w, self.is_window_fetching_mode)?;
+
+        write_u8(w, self.bg_palette_index)?;
+        write_u8(w, self.obj_palette_index)?;
+        w.write_all(&self.bg_palette_ram)?;
+        w.write_all(&self.obj_palette_ram)
+    }
+
+    pub fn load_state(&mut self, r: &mut dyn Read) -> io::Result<()> {
+        let video_ram_banks = read_u8(r)?;
+        self.video_ram.clear();
+        for _ in 0..video_ram_banks {
+            let mut bank = [0; 0x4000];
+            r.read_exact(&mut bank)?;
+            self.video_ram.push(bank);
+        }
+        self.video_ram_index = read_u8(r)? as usize;
+        r.read_exact(&mut self.object_attribute_memory)?;
+
+        //Restore LCDC without the side effects of turning the LCD off
+        let lcdc = read_u8(r)?;
+        self.write_lcdc(lcdc);
+        self.ppu_mode = read_u8(r)?;
+        self.stat = read_u8(r)?;
+        self.ly = read_u8(r)?;
+        self.ly_compare = read_u8(r)?;
+        self.scy = read_u8(r)?;
+        self.scx = read_u8(r)?;
+        self.wy = read_u8(r)?;
+        self.wx = read_u8(r)?;
+
+        let obj_buffer_len = read_u8(r)?;
+        self.obj_buffer.clear();
+        for _ in 0..obj_buffer_len {
+            self.obj_buffer.push(read_u16(r)?);
+        }
+        for fifo in [&mut self.bg_fifo, &mut self.obj_fifo] {
+            let fifo_len = read_u8(r)?;
+            fifo.clear();
+            for _ in 0..fifo_len {
+                fifo.push_back(read_pixel(r)?);
+            }
+        }
+        for row in self.screen.iter_mut() {
+            for pixel in row.iter_mut() {
+                *pixel = read_pixel(r)?;
+            }
+        }
+
+        self.dot_counter = read_u16(r)?;
+        self.mode_3_penalty = read_u8(r)?;
+        let is_window_tile = read_bool(r)?;
+        self.obj_penalty_tile = read_option_u8(r)?.map(|tile| (is_window_tile, tile));
+        self.bg_fetch_state = read_u8(r)?;
+        self.obj_fetch_state = read_u8(r)?;
+        self.fetched_obj_address = read_u16(r)?;
+        self.lx = read_u8(r)?;
+        self.w_ly = read_u8(r)?;
+        self.w_lx = read_u8(r)?;
+        self.ly_eq_wy = read_bool(r)?;
+        self.is_window_fetching_mode = read_bool(r)?;
+
+        self.bg_palette_index = read_u8(r)?;
+        self.obj_palette_index = read_u8(r)?;
+        r.read_exact(&mut self.bg_palette_ram)?;
+        r.read_exact(&mut self.obj_palette_ram)?;
+
+        Ok(())
+    }
+
+    //VRAM in the selected bank or OAM, whatever mode the PPU is in
+    #[cfg(feature = "debugger")]
+    pub fn unlocked_byte(&mut self, address: u16) -> Option<&mut u8> {
+        match address {
+            0x8000..=0x9FFF => Some(&mut self.video_ram[self.video_ram_index][(address - 0x8000) as usize]),
+            0xFE00..=0xFE9F => Some(&mut self.object_attribute_memory[(address - 0xFE00) as usize]),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn video_ram_bank_mut(&mut self, bank: usize) -> Option<&mut [u8]> {
+        self.video_ram.get_mut(bank).map(|video_ram| &mut video_ram[..0x2000])
+    }
+
+    pub fn video_ram_mut(&mut self) -> Vec<&mut [u8]> {
+        self.video_ram.iter_mut().map(|video_ram| &mut video_ram[..0x2000]).collect()
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn snapshot(&self) -> PPUSnapshot {
+        PPUSnapshot {
+            video_ram: self.video_ram.clone(),
+            object_attribute_memory: self.object_attribute_memory,
+            lcdc: self.read_lcdc(),
+            scy: self.scy,
+            scx: self.scx,
+            wy: self.wy,
+            wx: self.wx,
+            mode_3_lengths: *self.mode_3_lengths,
+        }
+    }
+
+    pub fn dump_screen(&mut self) -> [[Pixel; 160]; 144] {
+        let mut temp = Box::new([[Pixel {color: 0, palette: None, bg_priority: None, tile: None, rgb: None}; 160]; 144]);
+        std::mem::swap(&mut self.screen, &mut temp);
+
+        *temp
+    }
+}
+
+#[derive(Clone,Copy)]
+pub struct Pixel {
+    pub color: u8,
+    pub palette: Option<u8>,
+    pub bg_priority: Option<bool>,
+    pub tile: Option<u8>,
+    pub rgb: Option<u16>, //The CGB palette color, which replaces the DMG palettes when present
+}
\ No newline at end of file
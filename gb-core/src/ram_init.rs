@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+//How WRAM and VRAM are filled at power on. Real DMGs start with semi-random contents
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum RamInit {
+    #[default]
+    Zeroed,
+    //Alternating runs of 0x00 and 0xFF, 8 bytes each, similar to what many DMGs show at power on
+    Pattern,
+    //Noise from a seeded generator so the same seed always gives the same memory
+    Random { seed: u64 },
+}
+
+impl RamInit {
+    //Fills each region in order, continuing the same random stream across them
+    pub fn fill(&self, regions: &mut [&mut [u8]]) {
+        match self {
+            RamInit::Zeroed => {
+                for region in regions.iter_mut() {
+                    region.fill(0);
+                }
+            }
+            RamInit::Pattern => {
+                for region in regions.iter_mut() {
+                    for (i, byte) in region.iter_mut().enumerate() {
+                        *byte = if (i >> 3) & 1 == 0 {0x00} else {0xFF};
+                    }
+                }
+            }
+            RamInit::Random { seed } => {
+                //xorshift64*, which can't be seeded with 0
+                let mut state = if *seed == 0 {0x9E3779B97F4A7C15} else {*seed};
+                for region in regions.iter_mut() {
+                    for byte in region.iter_mut() {
+                        state ^= state >> 12;
+                        state ^= state << 25;
+                        state ^= state >> 27;
+                        *byte = (state.wrapping_mul(0x2545F4914F6CDD1D) >> 56) as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn to_bytes(self) -> (u8, u64) {
+        match self {
+            RamInit::Zeroed => (0, 0),
+            RamInit::Pattern => (1, 0),
+            RamInit::Random { seed } => (2, seed),
+        }
+    }
+
+    pub fn from_bytes(kind: u8, seed: u64) -> Self {
+        match kind {
+            1 => RamInit::Pattern,
+            2 => RamInit::Random { seed },
+            _ => RamInit::Zeroed,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//Hooks into the PPU for raster effects the game doesn't make itself, like palette cycling or scrolling tricks for
+//widescreen hacks. They run on the emulation thread as the PPU enters H-blank and V-blank, so whatever they write to
+//the registers is what the next line is drawn with
+pub trait RasterHooks: Send {
+    //Called once line ly has finished drawing
+    fn hblank(&mut self, _ly: u8, _registers: &mut RasterRegisters) {}
+
+    //Called once the last visible line has been drawn, before the V-blank interrupt is handled
+    fn vblank(&mut self, _registers: &mut RasterRegisters) {}
+}
+
+//The registers a hook can change between lines. The game sees the changed values when it reads them back
+#[derive(Clone, Copy)]
+pub struct RasterRegisters {
+    pub scx: u8,
+    pub scy: u8,
+    pub wx: u8,
+    pub wy: u8,
+    pub bgp: u8,
+    pub obp0: u8,
+    pub obp1: u8,
+    pub bg_palette_ram: [u8; 0x40], //CGB only. 8 palettes of 4 little-endian RGB555 colors
+    pub obj_palette_ram: [u8; 0x40],
+}
+
+impl RasterRegisters {
+    pub fn bg_color(&self, palette: u8, color: u8) -> u16 {
+        Self::color(&self.bg_palette_ram, palette, color)
+    }
+
+    pub fn set_bg_color(&mut self, palette: u8, color: u8, rgb: u16) {
+        Self::set_color(&mut self.bg_palette_ram, palette, color, rgb);
+    }
+
+    pub fn obj_color(&self, palette: u8, color: u8) -> u16 {
+        Self::color(&self.obj_palette_ram, palette, color)
+    }
+
+    pub fn set_obj_color(&mut self, palette: u8, color: u8, rgb: u16) {
+        Self::set_color(&mut self.obj_palette_ram, palette, color, rgb);
+    }
+
+    fn color(palette_ram: &[u8; 0x40], palette: u8, color: u8) -> u16 {
+        let index = Self::color_index(palette, color);
+        u16::from_le_bytes([palette_ram[index], palette_ram[index + 1]])
+    }
+
+    fn set_color(palette_ram: &mut [u8; 0x40], palette: u8, color: u8, rgb: u16) {
+        let index = Self::color_index(palette, color);
+        palette_ram[index..index + 2].copy_from_slice(&rgb.to_le_bytes());
+    }
+
+    fn color_index(palette: u8, color: u8) -> usize {
+        (((palette & 0b111) << 3) | ((color & 0b11) << 1)) as usize
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::VecDeque, io::{self, Read, Write}};
+
+use super::{console::GBConsole, ppu::Pixel};
+
+//Savestates are a flat little-endian byte stream written field by field by each component.
+//They're always taken between frames of the processor loop.
+pub const SAVESTATE_MAGIC: &[u8; 4] = b"GBRS";
+pub const SAVESTATE_VERSION: u8 = 14;
+
+#[cfg(feature = "zstd")]
+const COMPRESSION_LEVEL: i32 = 3;
+#[cfg(not(feature = "zstd"))]
+const ZSTD_MAGIC: &[u8; 4] = &[0x28, 0xB5, 0x2F, 0xFD];
+
+pub fn write_u8(w: &mut dyn Write, value: u8) -> io::Result<()> {
+    w.write_all(&[value])
+}
+
+pub fn write_u16(w: &mut dyn Write, value: u16) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub fn write_u32(w: &mut dyn Write, value: u32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub fn write_u64(w: &mut dyn Write, value: u64) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub fn write_f32(w: &mut dyn Write, value: f32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+pub fn write_bool(w: &mut dyn Write, value: bool) -> io::Result<()> {
+    write_u8(w, value as u8)
+}
+
+pub fn write_option_u8(w: &mut dyn Write, value: Option<u8>) -> io::Result<()> {
+    write_bool(w, value.is_some())?;
+    write_u8(w, value.unwrap_or(0))
+}
+
+pub fn write_option_u16(w: &mut dyn Write, value: Option<u16>) -> io::Result<()> {
+    write_bool(w, value.is_some())?;
+    write_u16(w, value.unwrap_or(0))
+}
+
+pub fn write_pixel(w: &mut dyn Write, pixel: &Pixel) -> io::Result<()> {
+    write_u8(w, pixel.color)?;
+    write_option_u8(w, pixel.palette)?;
+    write_option_u8(w, pixel.bg_priority.map(|bg_priority| bg_priority as u8))?;
+    write_option_u8(w, pixel.tile)?;
+    write_option_u16(w, pixel.rgb)
+}
+
+pub fn read_u8(r: &mut dyn Read) -> io::Result<u8> {
+    let mut buffer = [0; 1];
+    r.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+pub fn read_u16(r: &mut dyn Read) -> io::Result<u16> {
+    let mut buffer = [0; 2];
+    r.read_exact(&mut buffer)?;
+    Ok(u16::from_le_bytes(buffer))
+}
+
+pub fn read_u32(r: &mut dyn Read) -> io::Result<u32> {
+    let mut buffer = [0; 4];
+    r.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+pub fn read_u64(r: &mut dyn Read) -> io::Result<u64> {
+    let mut buffer = [0; 8];
+    r.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+pub fn read_f32(r: &mut dyn Read) -> io::Result<f32> {
+    let mut buffer = [0; 4];
+    r.read_exact(&mut buffer)?;
+    Ok(f32::from_le_bytes(buffer))
+}
+
+pub fn read_bool(r: &mut dyn Read) -> io::Result<bool> {
+    Ok(read_u8(r)? != 0)
+}
+
+pub fn read_option_u8(r: &mut dyn Read) -> io::Result<Option<u8>> {
+    let is_some = read_bool(r)?;
+    let value = read_u8(r)?;
+    Ok(if is_some {Some(value)} else {None})
+}
+
+pub fn read_option_u16(r: &mut dyn Read) -> io::Result<Option<u16>> {
+    let is_some = read_bool(r)?;
+    let value = read_u16(r)?;
+    Ok(if is_some {Some(value)} else {None})
+}
+
+pub fn read_pixel(r: &mut dyn Read) -> io::Result<Pixel> {
+    Ok(Pixel {
+        color: read_u8(r)?,
+        palette: read_option_u8(r)?,
+        bg_priority: read_option_u8(r)?.map(|bg_priority| bg_priority != 0),
+        tile: read_option_u8(r)?,
+        rgb: read_option_u16(r)?,
+    })
+}
+
+#[cfg(feature = "zstd")]
+pub fn compress(state: &[u8]) -> Vec<u8> {
+    zstd::encode_all(state, COMPRESSION_LEVEL).unwrap()
+}
+
+#[cfg(feature = "zstd")]
+pub fn decompress(compressed_state: &[u8]) -> io::Result<Vec<u8>> {
+    zstd::decode_all(compressed_state)
+}
+
+//Without zstd, like on the web, states are kept as they are. Files from a build with it can't be read back
+#[cfg(not(feature = "zstd"))]
+pub fn compress(state: &[u8]) -> Vec<u8> {
+    state.to_vec()
+}
+
+#[cfg(not(feature = "zstd"))]
+pub fn decompress(compressed_state: &[u8]) -> io::Result<Vec<u8>> {
+    if compressed_state.starts_with(ZSTD_MAGIC) {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "This savestate is compressed with zstd, which this build can't read"));
+    }
+    Ok(compressed_state.to_vec())
+}
+
+#[derive(Clone, Copy)]
+pub enum SavestateRequest {
+    Save(u8),
+    Load(u8),
+    UndoLoad, //Goes back to the state from just before the last load or reset
+    Reset, //Starts the game over with the cartridge RAM it has now. Can be undone like a load
+}
+
+//The console saves where it is in the current instruction too, so a load resumes mid-instruction exactly where it left off
+pub fn create(console: &GBConsole) -> Vec<u8> {
+    let mut state = Vec::new();
+    state.extend_from_slice(SAVESTATE_MAGIC);
+    write_u8(&mut state, SAVESTATE_VERSION).unwrap();
+    console.save_state(&mut state).unwrap();
+    state
+}
+
+pub fn restore(console: &mut GBConsole, state: &[u8]) -> io::Result<()> {
+    let mut r = state;
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != SAVESTATE_MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a savestate"));
+    }
+    let version = read_u8(&mut r)?;
+    if version != SAVESTATE_VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unsupported savestate version {}", version)));
+    }
+
+    console.load_state(&mut r)
+}
+
+pub fn rom_to_savestate(rom_file_path: String, slot: u8) -> String {
+    if let Some(savestate_path) = rom_file_path.rsplitn(2, ".").last() {
+        format!("{}.ss{}", savestate_path, slot)
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct StateHistorySettings {
+    pub enabled: bool,
+    pub capture_interval: u32, //Frames between captures
+    pub max_states: usize,
+    pub max_memory: usize, //Bytes
+}
+
+impl Default for StateHistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capture_interval: 2,
+            max_states: 60 * 60,
+            max_memory: 64 * 1024 * 1024,
+        }
+    }
+}
+
+//A bounded history of savestates for rewinding. States are grouped behind a keyframe,
+//with the rest of the group stored as compressed XORs against it, which are mostly zeros
+struct StateGroup {
+    keyframe: Vec<u8>,
+    deltas: Vec<Vec<u8>>,
+}
+
+impl StateGroup {
+    fn size(&self) -> usize {
+        self.keyframe.len() + self.deltas.iter().map(|delta| delta.len()).sum::<usize>()
+    }
+}
+
+pub struct StateHistory {
+    settings: StateHistorySettings,
+    groups: VecDeque<StateGroup>,
+    newest_keyframe: Vec<u8>, //Uncompressed copy of the newest group's keyframe
+    state_count: usize,
+    memory_usage: usize,
+}
+
+const STATES_PER_KEYFRAME: usize = 30;
+
+impl StateHistory {
+    pub fn new(settings: StateHistorySettings) -> Self {
+        Self {
+            settings,
+            groups: VecDeque::new(),
+            newest_keyframe: Vec::new(),
+            state_count: 0,
+            memory_usage: 0,
+        }
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        let start_new_group = match self.groups.back() {
+            Some(group) => group.deltas.len() + 1 >= STATES_PER_KEYFRAME || state.len() != self.newest_keyframe.len(),
+            None => true
+        };
+
+        if start_new_group {
+            let group = StateGroup {
+                keyframe: compress(&state),
+                deltas: Vec::new()
+            };
+            self.memory_usage += group.size();
+            self.groups.push_back(group);
+            self.newest_keyframe = state;
+        }
+        else {
+            let delta: Vec<u8> = state.iter().zip(self.newest_keyframe.iter()).map(|(a, b)| a ^ b).collect();
+            let delta = compress(&delta);
+            self.memory_usage += delta.len();
+            self.groups.back_mut().unwrap().deltas.push(delta);
+        }
+        self.state_count += 1;
+
+        //Evict whole groups from the front, since their deltas can't be decoded without the keyframe
+        while self.groups.len() > 1 && (self.state_count > self.settings.max_states || self.memory_usage > self.settings.max_memory) {
+            let group = self.groups.pop_front().unwrap();
+            self.state_count -= group.deltas.len() + 1;
+            self.memory_usage -= group.size();
+        }
+    }
+
+    //Takes the newest state out of the history, for stepping back through it
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let group = self.groups.back_mut()?;
+        //A delta that can't be decompressed is left where it is, so the memory it uses is still counted
+        let state = match group.deltas.last() {
+            Some(delta) => {
+                let delta = decompress(delta).ok()?;
+                self.memory_usage -= group.deltas.pop().unwrap().len();
+                delta.iter().zip(self.newest_keyframe.iter()).map(|(a, b)| a ^ b).collect()
+            }
+            None => {
+                let group = self.groups.pop_back().unwrap();
+                self.memory_usage -= group.size();
+                let keyframe = self.groups.back().and_then(|group| decompress(&group.keyframe).ok()).unwrap_or_default();
+                std::mem::replace(&mut self.newest_keyframe, keyframe)
+            }
+        };
+        self.state_count -= 1;
+        Some(state)
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.state_count
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+}
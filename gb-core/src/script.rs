@@ -0,0 +1,332 @@
+use std::{cell::{Cell, RefCell}, ptr::NonNull, rc::Rc, sync::{Arc, Mutex}};
+
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, ImmutableString, Scope, AST};
+
+use super::{console::GBConsole, debug_session::Symbols, joypad::JoypadState, monitor::{self, Command}, raster_hooks::{RasterHooks, RasterRegisters}};
+
+//Rhai scripts for automating the emulator. The top level runs once when the script is loaded, and its variables are
+//kept, so the callbacks it registers can share them.
+//
+//  on_frame(|| ...)                        Runs after every frame
+//  on_memory_write(address, |address, value| ...)
+//                                          Runs after the CPU writes there. Also takes an inclusive end address
+//                                          before the callback. Callbacks can also be Fn("name") or just "name"
+//  on_scanline(ly, |ly| ...)               Runs in the H-blank after line ly is drawn, so writes to SCX, BGP and the
+//                                          like change the lines below it
+//  read(address), read16(address)          Memory as the CPU sees it, except VRAM and OAM are never locked
+//  write(address, value)
+//  press(button), release(button)          Holds or blocks a button for the next frame, whatever the player does
+//  text(x, y, message)                     Draws on top of the screen until the next frame
+//  monitor(command)                        Runs a read, write or help command like the monitor window's, returning
+//                                          what it printed. Breakpoints and watchpoints are for the debugger
+//  hex(value), frame()
+//
+//Buttons are "a", "b", "start", "select", "up", "down", "left" and "right"
+const MAX_OPERATIONS: u64 = 1_000_000; //Per hook, so a script stuck in a loop doesn't hang the emulator
+const MAX_CALL_LEVELS: usize = 64;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct OverlayText {
+    pub x: f32,
+    pub y: f32,
+    pub text: String,
+}
+
+struct WriteHook {
+    start: u16,
+    end: u16,
+    callback: FnPtr,
+}
+
+struct ScanlineHook {
+    ly: u8,
+    callback: FnPtr,
+}
+
+//The console's raster hooks for a script. The script can't leave the frontend's thread, so this only notes which of
+//its lines have been drawn, and the frontend runs their callbacks after the step, while the PPU is still in H-blank
+#[derive(Clone, Default)]
+pub struct ScanlineHooks(Arc<Mutex<ScanlineQueue>>);
+
+#[derive(Default)]
+struct ScanlineQueue {
+    lines: Vec<u8>,
+    drawn: Vec<u8>,
+}
+
+impl RasterHooks for ScanlineHooks {
+    fn hblank(&mut self, ly: u8, _registers: &mut RasterRegisters) {
+        let mut queue = self.0.lock().unwrap();
+        if queue.lines.contains(&ly) {
+            queue.drawn.push(ly);
+        }
+    }
+}
+
+//What the functions scripts call change, shared with the engine's closures
+#[derive(Default)]
+struct ScriptState {
+    frame_hooks: Vec<FnPtr>,
+    write_hooks: Vec<WriteHook>,
+    is_write_hooks_changed: bool,
+    scanline_hooks: Vec<ScanlineHook>,
+    held: u8, //Buttons packed like JoypadState::to_byte
+    released: u8,
+    overlay: Vec<OverlayText>,
+    frame: u64,
+}
+
+//Lets the functions scripts call reach the console, which is only borrowed for as long as a hook runs
+#[derive(Clone, Default)]
+struct ConsoleSlot(Rc<Cell<Option<NonNull<GBConsole>>>>);
+
+impl ConsoleSlot {
+    fn with<T>(&self, f: impl FnOnce(&mut GBConsole) -> Result<T, String>) -> ScriptResult<T> {
+        let mut console = self.0.get().ok_or("The console can only be used while the script runs")?;
+        //SAFETY: Script::enter only sets the pointer from a &mut GBConsole that it holds on to until the hook returns,
+        //and f can't call back into the script, so this is the only reference to it
+        f(unsafe { console.as_mut() }).map_err(Into::into)
+    }
+}
+
+//Clears the console pointer when the hook returns, even if it panics
+struct ConsoleGuard<'a>(&'a ConsoleSlot);
+
+impl Drop for ConsoleGuard<'_> {
+    fn drop(&mut self) {
+        self.0 .0.set(None);
+    }
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    state: Rc<RefCell<ScriptState>>,
+    console: ConsoleSlot,
+    scanlines: ScanlineHooks,
+}
+
+impl Script {
+    //Compiles the script and runs its top level
+    pub fn load(source: &str, console: &mut GBConsole) -> Result<Self, String> {
+        let state = Rc::new(RefCell::new(ScriptState { is_write_hooks_changed: true, ..Default::default() }));
+        let console_slot = ConsoleSlot::default();
+        let scanlines = ScanlineHooks::default();
+        let engine = Self::engine(&state, &console_slot, &scanlines);
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        let mut script = Self {
+            engine,
+            ast,
+            scope: Scope::new(),
+            state,
+            console: console_slot,
+            scanlines,
+        };
+        script.enter(console, |script| script.engine.run_ast_with_scope(&mut script.scope, &script.ast)).map_err(|e| e.to_string())?;
+        Ok(script)
+    }
+
+    //Input and overlay text only last a frame, so on_frame starts them over
+    pub fn on_frame(&mut self, console: &mut GBConsole) -> Result<(), String> {
+        let callbacks = {
+            let mut state = self.state.borrow_mut();
+            state.frame += 1;
+            state.held = 0;
+            state.released = 0;
+            state.overlay.clear();
+            state.frame_hooks.clone()
+        };
+        for callback in callbacks {
+            self.call(&callback, (), console).map_err(|e| format!("In on_frame: {}", e))?;
+        }
+        Ok(())
+    }
+
+    pub fn on_memory_write(&mut self, address: u16, value: u8, console: &mut GBConsole) -> Result<(), String> {
+        let callbacks: Vec<FnPtr> = self.state.borrow().write_hooks.iter()
+            .filter(|hook| (hook.start..=hook.end).contains(&address))
+            .map(|hook| hook.callback.clone())
+            .collect();
+        for callback in callbacks {
+            self.call(&callback, (address as i64, value as i64), console).map_err(|e| format!("In on_memory_write: {}", e))?;
+        }
+        Ok(())
+    }
+
+    //Runs the callbacks for the lines drawn since the last call
+    pub fn on_scanlines(&mut self, console: &mut GBConsole) -> Result<(), String> {
+        let drawn = std::mem::take(&mut self.scanlines.0.lock().unwrap().drawn);
+        for ly in drawn {
+            let callbacks: Vec<FnPtr> = self.state.borrow().scanline_hooks.iter()
+                .filter(|hook| hook.ly == ly)
+                .map(|hook| hook.callback.clone())
+                .collect();
+            for callback in callbacks {
+                self.call(&callback, (ly as i64,), console).map_err(|e| format!("In on_scanline: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_scanline_drawn(&self) -> bool {
+        !self.scanlines.0.lock().unwrap().drawn.is_empty()
+    }
+
+    //For GBConsole::set_raster_hooks, which on_scanline depends on
+    pub fn raster_hooks(&self) -> Box<dyn RasterHooks> {
+        Box::new(self.scanlines.clone())
+    }
+
+    pub fn write_ranges(&self) -> Vec<(u16, u16)> {
+        self.state.borrow().write_hooks.iter().map(|hook| (hook.start, hook.end)).collect()
+    }
+
+    //Whether a hook was added since the last call, for whoever gives the console its ranges
+    pub fn take_write_hooks_changed(&mut self) -> bool {
+        std::mem::take(&mut self.state.borrow_mut().is_write_hooks_changed)
+    }
+
+    pub fn apply_input(&self, joypad: JoypadState) -> JoypadState {
+        let state = self.state.borrow();
+        JoypadState::from_byte((joypad.to_byte() | state.held) & !state.released)
+    }
+
+    pub fn overlay(&self) -> Vec<OverlayText> {
+        self.state.borrow().overlay.clone()
+    }
+
+    fn call(&mut self, callback: &FnPtr, arguments: impl rhai::FuncArgs, console: &mut GBConsole) -> ScriptResult<()> {
+        self.enter(console, |script| callback.call::<Dynamic>(&script.engine, &script.ast, arguments).map(|_| ()))
+    }
+
+    fn enter<T>(&mut self, console: &mut GBConsole, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.console.0.set(Some(NonNull::from(console)));
+        let console_slot = self.console.clone();
+        let _guard = ConsoleGuard(&console_slot);
+        f(self)
+    }
+
+    fn engine(state: &Rc<RefCell<ScriptState>>, console: &ConsoleSlot, scanlines: &ScanlineHooks) -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+
+        let slot = console.clone();
+        engine.register_fn("read", move |address: i64| -> ScriptResult<i64> {
+            let address = to_address(address)?;
+            slot.with(|console| console.peek(None, address).map(i64::from))
+        });
+        let slot = console.clone();
+        engine.register_fn("read16", move |address: i64| -> ScriptResult<i64> {
+            let address = to_address(address)?;
+            slot.with(|console| Ok(u16::from_le_bytes([console.peek(None, address)?, console.peek(None, address.wrapping_add(1))?]) as i64))
+        });
+        let slot = console.clone();
+        engine.register_fn("write", move |address: i64, value: i64| -> ScriptResult<()> {
+            let address = to_address(address)?;
+            slot.with(|console| console.poke(None, address, value as u8))
+        });
+
+        let slot = console.clone();
+        engine.register_fn("monitor", move |line: &str| -> ScriptResult<String> {
+            let command = monitor::parse(line)?;
+            if matches!(command, Command::Breakpoint { .. } | Command::Watchpoint { .. }) {
+                return Err("Breakpoints and watchpoints can only be set from the debugger".into());
+            }
+            slot.with(|console| monitor::run(&command, console, &Symbols::default()).map(|lines| lines.join("\n")))
+        });
+
+        let shared = state.clone();
+        engine.register_fn("press", move |button: &str| -> ScriptResult<()> {
+            shared.borrow_mut().held |= button_mask(button)?;
+            Ok(())
+        });
+        let shared = state.clone();
+        engine.register_fn("release", move |button: &str| -> ScriptResult<()> {
+            shared.borrow_mut().released |= button_mask(button)?;
+            Ok(())
+        });
+        let shared = state.clone();
+        engine.register_fn("text", move |x: i64, y: i64, message: Dynamic| {
+            shared.borrow_mut().overlay.push(OverlayText { x: x as f32, y: y as f32, text: message.to_string() });
+        });
+        engine.register_fn("hex", |value: i64| {
+            if (0..=0xFF).contains(&value) {format!("{:02X}", value)} else {format!("{:04X}", value)}
+        });
+        let shared = state.clone();
+        engine.register_fn("frame", move || shared.borrow().frame as i64);
+
+        let shared = state.clone();
+        engine.register_fn("on_frame", move |callback: FnPtr| {
+            shared.borrow_mut().frame_hooks.push(callback);
+        });
+        let shared = state.clone();
+        engine.register_fn("on_frame", move |name: ImmutableString| -> ScriptResult<()> {
+            shared.borrow_mut().frame_hooks.push(FnPtr::new(name)?);
+            Ok(())
+        });
+        let shared = state.clone();
+        engine.register_fn("on_memory_write", move |address: i64, callback: FnPtr| -> ScriptResult<()> {
+            add_write_hook(&shared, address, address, callback)
+        });
+        let shared = state.clone();
+        engine.register_fn("on_memory_write", move |address: i64, name: ImmutableString| -> ScriptResult<()> {
+            add_write_hook(&shared, address, address, FnPtr::new(name)?)
+        });
+        let shared = state.clone();
+        engine.register_fn("on_memory_write", move |start: i64, end: i64, callback: FnPtr| -> ScriptResult<()> {
+            add_write_hook(&shared, start, end, callback)
+        });
+        let shared = state.clone();
+        engine.register_fn("on_memory_write", move |start: i64, end: i64, name: ImmutableString| -> ScriptResult<()> {
+            add_write_hook(&shared, start, end, FnPtr::new(name)?)
+        });
+        let (shared, lines) = (state.clone(), scanlines.clone());
+        engine.register_fn("on_scanline", move |ly: i64, callback: FnPtr| -> ScriptResult<()> {
+            add_scanline_hook(&shared, &lines, ly, callback)
+        });
+        let (shared, lines) = (state.clone(), scanlines.clone());
+        engine.register_fn("on_scanline", move |ly: i64, name: ImmutableString| -> ScriptResult<()> {
+            add_scanline_hook(&shared, &lines, ly, FnPtr::new(name)?)
+        });
+
+        engine
+    }
+}
+
+fn add_write_hook(state: &RefCell<ScriptState>, start: i64, end: i64, callback: FnPtr) -> ScriptResult<()> {
+    let hook = WriteHook { start: to_address(start)?, end: to_address(end)?, callback };
+    let mut state = state.borrow_mut();
+    state.write_hooks.push(hook);
+    state.is_write_hooks_changed = true;
+    Ok(())
+}
+
+fn add_scanline_hook(state: &RefCell<ScriptState>, scanlines: &ScanlineHooks, ly: i64, callback: FnPtr) -> ScriptResult<()> {
+    let ly = u8::try_from(ly).ok().filter(|ly| *ly < 144).ok_or_else(|| format!("{} isn't a visible line", ly))?;
+    state.borrow_mut().scanline_hooks.push(ScanlineHook { ly, callback });
+    scanlines.0.lock().unwrap().lines.push(ly);
+    Ok(())
+}
+
+fn to_address(value: i64) -> ScriptResult<u16> {
+    u16::try_from(value).map_err(|_| format!("${:X} isn't an address", value).into())
+}
+
+fn button_mask(button: &str) -> ScriptResult<u8> {
+    let joypad = match button {
+        "a" => JoypadState { a: true, ..Default::default() },
+        "b" => JoypadState { b: true, ..Default::default() },
+        "start" => JoypadState { start: true, ..Default::default() },
+        "select" => JoypadState { select: true, ..Default::default() },
+        "up" => JoypadState { up: true, ..Default::default() },
+        "down" => JoypadState { down: true, ..Default::default() },
+        "left" => JoypadState { left: true, ..Default::default() },
+        "right" => JoypadState { right: true, ..Default::default() },
+        _ => return Err(format!("There's no {} button", button).into()),
+    };
+    Ok(joypad.to_byte())
+}
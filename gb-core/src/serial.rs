@@ -0,0 +1,273 @@
+use std::{collections::VecDeque, io::{self, Write}, sync::{Arc, Mutex}, time::Duration};
+#[cfg(feature = "netplay")]
+use std::{io::Read, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+const DOTS_PER_MICROSECOND: f64 = 4.194304;
+
+//Something plugged into the console's link port
+pub trait SerialDevice: Send {
+    //Called when the console drives the clock (SC bit 0 set) and starts shifting out a byte.
+    //Returns the byte the other side shifts in during the same transfer
+    fn transfer(&mut self, outgoing: u8) -> u8;
+
+    //Called while the console waits on an external clock (SC bit 0 clear).
+    //Returns the incoming byte once the other side has clocked a transfer, or None if it hasn't yet
+    fn external_transfer(&mut self, outgoing: u8) -> Option<u8>;
+
+    //How long the last transfer took on the real side, for pacing. The console only counts emulated cycles,
+    //so devices that wait on real hardware measure this themselves
+    fn latency(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+//Extra time between bytes on the link port. Real peripherals and networked links often can't keep up with games
+//that send bytes back to back, which an emulator that answers instantly never has to worry about
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SerialPacing {
+    #[default]
+    Off,
+    Fixed(u32), //Microseconds of emulated time between bytes
+    Auto, //Waits as long as the device took to answer the last byte, so slow devices get the time they need
+}
+
+impl SerialPacing {
+    //How many dots the next transfer is held back, given how long the device took with the last one
+    pub fn delay_dots(&self, device_time: Duration) -> u32 {
+        let microseconds = match self {
+            SerialPacing::Off => return 0,
+            SerialPacing::Fixed(microseconds) => *microseconds as f64,
+            SerialPacing::Auto => device_time.as_micros() as f64,
+        };
+        (microseconds * DOTS_PER_MICROSECOND).min(u32::MAX as f64) as u32
+    }
+}
+
+//Bytes typed or scanned for the peripheral on the link port, shared with the UI
+pub type PeripheralInput = Arc<Mutex<VecDeque<u8>>>;
+
+//Nothing is plugged in. The data line is pulled high, so every transfer reads 0xFF
+//and externally clocked transfers never complete
+pub struct Disconnected;
+
+impl SerialDevice for Disconnected {
+    fn transfer(&mut self, _outgoing: u8) -> u8 {
+        0xFF
+    }
+
+    fn external_transfer(&mut self, _outgoing: u8) -> Option<u8> {
+        None
+    }
+}
+
+//A cable with its data out wired back to data in, like the plugs link test ROMs ask for. Every byte sent comes
+//straight back. Nothing else drives the clock, so externally clocked transfers never complete
+pub struct Loopback;
+
+impl SerialDevice for Loopback {
+    fn transfer(&mut self, outgoing: u8) -> u8 {
+        outgoing
+    }
+
+    fn external_transfer(&mut self, _outgoing: u8) -> Option<u8> {
+        None
+    }
+}
+
+//Nothing is plugged in, but every byte the game sends is written to stdout as is. Test ROMs print their results this
+//way, so it can be piped or redirected to a file
+pub struct Stdout;
+
+impl SerialDevice for Stdout {
+    fn transfer(&mut self, outgoing: u8) -> u8 {
+        let mut stdout = io::stdout().lock();
+        if let Err(e) = stdout.write_all(&[outgoing]).and_then(|_| stdout.flush()) {
+            println!("ERROR: Could not write serial output: {}", e);
+        }
+        0xFF
+    }
+
+    fn external_transfer(&mut self, _outgoing: u8) -> Option<u8> {
+        None
+    }
+}
+
+//A USB to link cable bridge (Arduino/stm32 based adapters) connected to a real Game Boy.
+//The protocol is one byte at a time: every byte written to the adapter is shifted out to the real console
+//and answered with the byte that was shifted back in. When the real console is the clock master, the adapter
+//sends the byte it received unprompted, answering with the last byte we wrote to it.
+#[cfg(feature = "netplay")]
+pub struct LinkAdapter {
+    port: Box<dyn serialport::SerialPort>,
+    is_preloaded: bool,
+    latency: Duration,
+}
+
+#[cfg(feature = "netplay")]
+const LINK_ADAPTER_BAUD_RATE: u32 = 115200;
+#[cfg(feature = "netplay")]
+const LINK_ADAPTER_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[cfg(feature = "netplay")]
+impl LinkAdapter {
+    pub fn new(port_name: &str) -> Result<Self, serialport::Error> {
+        let port = serialport::new(port_name, LINK_ADAPTER_BAUD_RATE)
+                                                     .timeout(LINK_ADAPTER_TIMEOUT)
+                                                     .open()?;
+
+        Ok(Self {
+            port,
+            is_preloaded: false,
+            latency: Duration::ZERO,
+        })
+    }
+
+    pub fn available_ports() -> Vec<String> {
+        match serialport::available_ports() {
+            Ok(ports) => ports.into_iter().map(|port| port.port_name).collect(),
+            Err(_) => Vec::new()
+        }
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        let mut incoming = [0xFF];
+        match self.port.read_exact(&mut incoming) {
+            Ok(_) => Some(incoming[0]),
+            Err(e) => {
+                println!("ERROR: Link adapter read failed: {}", e);
+                None
+            }
+        }
+    }
+
+    fn exchange(&mut self, outgoing: u8) -> u8 {
+        self.is_preloaded = false;
+
+        if let Err(e) = self.port.write_all(&[outgoing]) {
+            println!("ERROR: Link adapter write failed: {}", e);
+            return 0xFF;
+        }
+
+        self.read_byte().unwrap_or(0xFF)
+    }
+
+    fn poll(&mut self, outgoing: u8) -> Option<u8> {
+        //The adapter needs our byte before the real console starts clocking
+        if !self.is_preloaded {
+            if let Err(e) = self.port.write_all(&[outgoing]) {
+                println!("ERROR: Link adapter write failed: {}", e);
+                return None;
+            }
+            self.is_preloaded = true;
+        }
+
+        if self.port.bytes_to_read().unwrap_or(0) == 0 {
+            return None;
+        }
+
+        self.is_preloaded = false;
+        self.read_byte()
+    }
+}
+
+#[cfg(feature = "netplay")]
+impl SerialDevice for LinkAdapter {
+    fn transfer(&mut self, outgoing: u8) -> u8 {
+        let start = Instant::now();
+        let incoming = self.exchange(outgoing);
+        self.latency = start.elapsed();
+        incoming
+    }
+
+    fn external_transfer(&mut self, outgoing: u8) -> Option<u8> {
+        let start = Instant::now();
+        let incoming = self.poll(outgoing);
+        self.latency = start.elapsed();
+        incoming
+    }
+
+    fn latency(&self) -> Duration {
+        self.latency
+    }
+}
+
+//Namco's barcode reader. The game clocks a 4 byte handshake, then waits on the external clock while the
+//Barcode Boy sends each scanned code as 0x02, the 13 ASCII digits of the EAN-13 code, 0x03, twice in a row.
+//Codes come from the UI instead of a scanner, 13 digits at a time
+pub struct BarcodeBoy {
+    input: PeripheralInput,
+    handshake_index: usize,
+    outgoing: VecDeque<u8>,
+}
+
+const BARCODE_BOY_HANDSHAKE: [u8; 4] = [0x10, 0x07, 0x10, 0x07];
+const BARCODE_BOY_HANDSHAKE_REPLY: [u8; 4] = [0xFF, 0xFF, 0x10, 0x07];
+pub const BARCODE_LENGTH: usize = 13;
+
+impl BarcodeBoy {
+    pub fn new(input: PeripheralInput) -> Self {
+        Self {
+            input,
+            handshake_index: 0,
+            outgoing: VecDeque::new(),
+        }
+    }
+}
+
+impl SerialDevice for BarcodeBoy {
+    fn transfer(&mut self, outgoing: u8) -> u8 {
+        if outgoing != BARCODE_BOY_HANDSHAKE[self.handshake_index] {
+            self.handshake_index = 0;
+            return 0xFF;
+        }
+
+        let reply = BARCODE_BOY_HANDSHAKE_REPLY[self.handshake_index];
+        self.handshake_index = (self.handshake_index + 1) % BARCODE_BOY_HANDSHAKE.len();
+        reply
+    }
+
+    fn external_transfer(&mut self, _outgoing: u8) -> Option<u8> {
+        if self.outgoing.is_empty() {
+            let mut input = self.input.lock().unwrap();
+            if input.len() < BARCODE_LENGTH {
+                return None;
+            }
+
+            let digits: Vec<u8> = input.drain(..BARCODE_LENGTH).collect();
+            for _ in 0..2 {
+                self.outgoing.push_back(0x02);
+                self.outgoing.extend(&digits);
+                self.outgoing.push_back(0x03);
+            }
+        }
+
+        self.outgoing.pop_front()
+    }
+}
+
+//Stub for the Workboy organizer keyboard. Its protocol is only partly documented, so this just answers every
+//byte the game clocks out with the next key typed on the host keyboard as ASCII, or 0x00 when no key is waiting
+pub struct Workboy {
+    input: PeripheralInput,
+}
+
+impl Workboy {
+    pub fn new(input: PeripheralInput) -> Self {
+        Self {
+            input,
+        }
+    }
+}
+
+impl SerialDevice for Workboy {
+    fn transfer(&mut self, _outgoing: u8) -> u8 {
+        self.input.lock().unwrap().pop_front().unwrap_or(0x00)
+    }
+
+    //The Workboy never drives the clock
+    fn external_transfer(&mut self, _outgoing: u8) -> Option<u8> {
+        None
+    }
+}
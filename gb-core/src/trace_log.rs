@@ -0,0 +1,100 @@
+use std::{collections::VecDeque, fs::File, io::{self, BufWriter, Write}, path::Path};
+
+use super::CPURegisters;
+
+//The CPU state before an instruction in Game Boy Doctor's format, which other emulators can also log, so a trace can be
+//diffed against a known good one to find the first instruction that went wrong. PCMEM is the 4 bytes at PC
+pub fn doctor_line(registers: &CPURegisters, pc_memory: [u8; 4]) -> String {
+    format!("A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+        registers.a, registers.f, registers.b, registers.c, registers.d, registers.e, registers.h, registers.l,
+        registers.stack_pointer, registers.program_counter, pc_memory[0], pc_memory[1], pc_memory[2], pc_memory[3])
+}
+
+//Enough to see a few scanlines' worth of code before a breakpoint
+pub const RING_BUFFER_LINES: usize = 4096;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum TraceMode {
+    #[default]
+    Off,
+    File,
+    RingBuffer,
+}
+
+impl TraceMode {
+    pub const ALL: [TraceMode; 3] = [TraceMode::Off, TraceMode::File, TraceMode::RingBuffer];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TraceMode::Off => "Off",
+            TraceMode::File => "To file",
+            TraceMode::RingBuffer => "Last instructions",
+        }
+    }
+}
+
+enum TraceOutput {
+    File(BufWriter<File>),
+    Ring(VecDeque<String>, usize),
+}
+
+//Where the console puts a line for every instruction it runs while tracing. A file keeps the whole run, a ring buffer
+//only the last lines, which is enough to see how the CPU got to a breakpoint without filling the disk
+pub struct InstructionTrace {
+    output: TraceOutput,
+}
+
+impl InstructionTrace {
+    pub fn to_file(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            output: TraceOutput::File(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    pub fn ring_buffer(capacity: usize) -> Self {
+        Self {
+            output: TraceOutput::Ring(VecDeque::with_capacity(capacity), capacity),
+        }
+    }
+
+    pub fn log(&mut self, line: String) {
+        match &mut self.output {
+            TraceOutput::File(writer) => {
+                if let Err(e) = writeln!(writer, "{}", line) {
+                    println!("ERROR: Could not write the instruction trace: {}", e);
+                }
+            }
+            TraceOutput::Ring(lines, capacity) => {
+                if lines.len() == *capacity {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+            }
+        }
+    }
+
+    //The ring buffer's lines, oldest first. Empty when tracing to a file
+    pub fn lines(&self) -> Vec<String> {
+        match &self.output {
+            TraceOutput::File(_) => Vec::new(),
+            TraceOutput::Ring(lines, _) => lines.iter().cloned().collect(),
+        }
+    }
+}
+
+impl Drop for InstructionTrace {
+    fn drop(&mut self) {
+        if let TraceOutput::File(writer) = &mut self.output {
+            let _ = writer.flush();
+        }
+    }
+}
+
+pub fn rom_to_trace_log(rom_file_path: String) -> String {
+    if let Some(trace_log_path) = rom_file_path.rsplitn(2, ".").last() {
+        trace_log_path.to_owned() + ".trace.log"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
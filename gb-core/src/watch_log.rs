@@ -0,0 +1,52 @@
+use std::{fs::File, io::{self, BufWriter, Write}};
+
+//A hit on a watchpoint that logs instead of pausing
+#[derive(Clone, Copy)]
+pub struct WatchLogEntry {
+    pub program_counter: u16, //Of the instruction that made the access
+    pub address: u16,
+    pub old_value: u8, //What was there before the access
+    pub new_value: Option<u8>, //Only for writes
+}
+
+//Writes logged watchpoint hits as CSV, for tracking down values that only get corrupted once in a long while
+pub struct WatchLogger {
+    writer: BufWriter<File>,
+}
+
+impl WatchLogger {
+    pub fn new(log_path: &str) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(log_path)?);
+        writeln!(writer, "frame,pc,address,access,old,new")?;
+
+        Ok(Self {
+            writer,
+        })
+    }
+
+    pub fn write_entries(&mut self, frame: u64, entries: &[WatchLogEntry]) -> io::Result<()> {
+        for entry in entries {
+            write!(self.writer, "{},{:04X},{:04X},", frame, entry.program_counter, entry.address)?;
+            match entry.new_value {
+                Some(new_value) => writeln!(self.writer, "write,{:02X},{:02X}", entry.old_value, new_value)?,
+                None => writeln!(self.writer, "read,{:02X},", entry.old_value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for WatchLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+pub fn rom_to_watch_log(rom_file_path: String) -> String {
+    if let Some(watch_log_path) = rom_file_path.rsplitn(2, ".").last() {
+        watch_log_path.to_owned() + ".watchlog.csv"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
@@ -1,167 +1,789 @@
+#[cfg(feature = "gui")]
 use std::sync::atomic::Ordering;
 
+#[cfg(feature = "gui")]
+use gbemu::{link_port::LinkPortDevice, serial::SerialPacing};
+#[cfg(feature = "gui")]
+use i18n::{tr, tr_args};
+
 pub mod gbemu;
-pub mod cartridge_info;
+pub use gb_core::cartridge_info;
+#[cfg(feature = "gui")]
+pub mod border;
+#[cfg(feature = "gui")]
+pub mod compatibility;
+#[cfg(feature = "gui")]
+pub mod demos;
+#[cfg(feature = "gui")]
+pub mod file_association;
+#[cfg(feature = "gui")]
+pub mod i18n;
+#[cfg(feature = "gui")]
+pub mod kiosk;
+#[cfg(feature = "gui")]
+pub mod rom_archive;
+#[cfg(feature = "gui")]
+pub mod rom_download;
+#[cfg(feature = "gui")]
+pub mod updater;
+#[cfg(feature = "gui")]
+pub mod view_settings;
+#[cfg(feature = "gui")]
+pub mod workspace;
+#[cfg(feature = "gui")]
 pub use cartridge_info::CGBState;
-use egui::Shape;
 
 
+#[cfg(feature = "gui")]
 impl eframe::App for gbemu::GBEmu {
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
 
-        let size = ctx.input(|i: &egui::InputState| i.screen_rect().max);
-        let mut game_width = size.x;
-        let mut game_height = size.y;
-        let mut x_offset = 0.0;
-        let mut y_offset = 0.0;
+        self.is_minimized.store(ctx.input(|input| input.viewport().minimized.unwrap_or(false)), Ordering::Relaxed);
+
+        if self.is_kiosk {
+            self.kiosk_view(ctx);
+            return;
+        }
 
-        let game_ratio = 160.0 / 144.0;
-        let screen_ratio = game_width / game_height;
-        if screen_ratio < game_ratio {
-            game_height = game_width / game_ratio;
-            y_offset = (size.y - game_height) / 2.0;
+        //Typed text goes to the Workboy keyboard as well as the joypad bindings
+        if *self.link_port_device.lock().unwrap() == LinkPortDevice::Workboy && !ctx.wants_keyboard_input() {
+            let typed: Vec<u8> = ctx.input(|input| input.events.iter().filter_map(|event| match event {
+                egui::Event::Text(text) => Some(text.clone()),
+                egui::Event::Key { key: egui::Key::Enter, pressed: true, .. } => Some("\r".to_owned()),
+                egui::Event::Key { key: egui::Key::Backspace, pressed: true, .. } => Some("\x08".to_owned()),
+                _ => None,
+            }).flat_map(|text| text.into_bytes()).filter(|byte| byte.is_ascii()).collect());
+            self.peripheral_input.lock().unwrap().extend(typed);
         }
-        else if screen_ratio > game_ratio {
-            game_width = game_height * game_ratio;
-            x_offset = (size.x - game_width) / 2.0;
+
+        //Macro hotkeys play in the focused tab
+        if !ctx.wants_keyboard_input() {
+            let game_id = self.rom_info.lock().unwrap().as_ref().map(|info| gbemu::input::game_id(&info.title, info.global_checksum));
+            let input_macros = self.input_macros.lock().unwrap();
+            if let Some(game_macros) = game_id.and_then(|game_id| input_macros.get(&game_id)) {
+                let pressed = game_macros.iter().position(|input_macro| input_macro.hotkey.is_some_and(|key| ctx.input(|input| input.key_pressed(key))));
+                if pressed.is_some() {
+                    *self.macro_play_request.lock().unwrap() = pressed;
+                }
+            }
+            drop(input_macros);
+        }
+
+        let view_settings = *self.view_settings.lock().unwrap();
+        view_settings.apply_opacity(ctx);
+        if view_settings.compact {
+            self.compact_view(ctx);
+            return;
         }
 
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
 
             egui::menu::bar(ui, |ui| {
-                ui.menu_button("File", |ui| {
-                    if ui.button("Open Rom").clicked() {
-                        if let Some(path) = rfd::FileDialog::new().add_filter("GameBoy Roms", &["gb", "gbc"]).pick_file() {
-                            let mut lock = self.rom_file_path.lock().unwrap();
-                            *lock = Some(path.display().to_string());
-                            drop(lock);
-                            self.file_changed.store(true, Ordering::Relaxed);
+                ui.menu_button(tr("menu.file"), |ui| {
+                    if ui.button(tr("menu.open_rom")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new().add_filter(tr("file_dialog.roms"), &rom_archive::ROM_EXTENSIONS).pick_file() {
+                            self.open_rom(path.display().to_string(), ctx);
+                        }
+                    }
+                    if ui.add_enabled(self.rom_file_path.lock().unwrap().is_some(), egui::Button::new(tr("menu.close_rom"))).clicked() {
+                        let focused_session = self.sessions.lock().unwrap().iter().position(|session| session.is_same(self));
+                        if let Some(index) = focused_session {
+                            self.close_session(index, ctx);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.rom_file_path.lock().unwrap().is_some(), egui::Button::new(tr("menu.swap_cartridge"))).on_hover_text(tr("hint.swap_cartridge")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new().add_filter(tr("file_dialog.roms"), &rom_archive::ROM_EXTENSIONS).pick_file() {
+                            *self.cartridge_swap_request.lock().unwrap() = Some(path.display().to_string());
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.rom_file_path.lock().unwrap().is_some(), egui::Button::new(tr("menu.reset"))).on_hover_text(tr("hint.reset")).clicked() {
+                        *self.savestate_request.lock().unwrap() = Some(gbemu::savestate::SavestateRequest::Reset);
+                        ui.close_menu();
+                    }
+                    ui.menu_button(tr("menu.boot_rom"), |ui| {
+                        let mut lock = self.boot_rom_path.lock().unwrap();
+                        ui.label(match &*lock {
+                            Some(boot_rom_path) => tr_args("boot_rom.using", &[("path", boot_rom_path)]),
+                            None => tr("boot_rom.none").to_owned()
+                        });
+                        if ui.button(tr("boot_rom.select")).clicked() {
+                            if let Some(path) = rfd::FileDialog::new().add_filter(tr("file_dialog.boot_roms"), &["bin", "gb", "rom"]).pick_file() {
+                                *lock = Some(path.display().to_string());
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("boot_rom.dont_use")).clicked() {
+                            *lock = None;
+                            ui.close_menu();
+                        }
+                        drop(lock);
+                    }).response.on_hover_text(tr("hint.next_rom"));
+                    ui.menu_button(tr("menu.startup_ram"), |ui| {
+                        let mut lock = self.ram_init.lock().unwrap();
+                        let is_random = matches!(*lock, gbemu::ram_init::RamInit::Random { .. });
+                        ui.radio_value(&mut *lock, gbemu::ram_init::RamInit::Zeroed, tr("startup_ram.zeroed"));
+                        ui.radio_value(&mut *lock, gbemu::ram_init::RamInit::Pattern, tr("startup_ram.pattern"));
+                        if ui.radio(is_random, tr("startup_ram.random")).clicked() && !is_random {
+                            *lock = gbemu::ram_init::RamInit::Random { seed: 1 };
+                        }
+                        if let gbemu::ram_init::RamInit::Random { seed } = &mut *lock {
+                            ui.horizontal(|ui| {
+                                ui.label(tr("startup_ram.seed"));
+                                ui.add(egui::DragValue::new(seed));
+                            });
+                        }
+                        drop(lock);
+                    }).response.on_hover_text(tr("hint.next_rom"));
+                    ui.menu_button(tr("menu.model"), |ui| {
+                        let mut lock = self.model.lock().unwrap();
+                        ui.radio_value(&mut *lock, None, tr("model.automatic")).on_hover_text(tr("model.automatic_hint"));
+                        for model in gbemu::model::Model::ALL {
+                            ui.radio_value(&mut *lock, Some(model), model.name());
+                        }
+                        drop(lock);
+                    }).response.on_hover_text(tr("hint.next_rom"));
+                    let mut accurate_prohibited_area = self.accurate_prohibited_area.load(Ordering::Relaxed);
+                    if ui.checkbox(&mut accurate_prohibited_area, tr("accuracy.prohibited_area")).on_hover_text(tr("accuracy.prohibited_area_hint")).changed() {
+                        self.accurate_prohibited_area.store(accurate_prohibited_area, Ordering::Relaxed);
+                    }
+                    ui.menu_button(tr("menu.save_state"), |ui| {
+                        for slot in 1..=4 {
+                            if ui.button(tr_args("savestate.slot", &[("slot", &slot)])).clicked() {
+                                *self.savestate_request.lock().unwrap() = Some(gbemu::savestate::SavestateRequest::Save(slot));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.menu_button(tr("menu.load_state"), |ui| {
+                        for slot in 1..=4 {
+                            if ui.button(tr_args("savestate.slot", &[("slot", &slot)])).clicked() {
+                                *self.savestate_request.lock().unwrap() = Some(gbemu::savestate::SavestateRequest::Load(slot));
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        let can_undo_load = self.can_undo_load.load(Ordering::Relaxed);
+                        if ui.add_enabled(can_undo_load, egui::Button::new(tr("savestate.undo_load"))).on_hover_text(tr("savestate.undo_load_hint")).clicked() {
+                            *self.savestate_request.lock().unwrap() = Some(gbemu::savestate::SavestateRequest::UndoLoad);
+                            ui.close_menu();
+                        }
+                    });
+                    if ui.button(tr("menu.open_rom_from_url")).clicked() {
+                        let mut lock = self.rom_download.lock().unwrap();
+                        if lock.is_none() {
+                            *lock = Some(rom_download::RomDownload::default());
                         }
+                        drop(lock);
+                        ui.close_menu();
                     }
+                    ui.menu_button(tr("menu.demos"), |ui| {
+                        for demo in demos::DEMOS {
+                            let credit = tr_args("demos.credit", &[("author", &demo.author), ("license", &demo.license)]);
+                            if ui.button(demo.name).on_hover_text(credit).clicked() {
+                                match demos::prepare(demo) {
+                                    Ok(path) => self.open_rom(path, ctx),
+                                    Err(e) => println!("ERROR: Could not open demo {}: {}", demo.name, e),
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                    });
                     // NOTE: no File->Quit on web pages!
                     let is_web = cfg!(target_arch = "wasm32");
                     if !is_web {
-                        if ui.button("Quit").clicked() {
+                        if ui.button(tr("menu.register_file_types")).on_hover_text(tr("hint.register_file_types")).clicked() {
+                            if let Err(e) = file_association::register() {
+                                println!("ERROR: Could not register file types: {}", e);
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("menu.quit")).clicked() {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
                     }
                 });
+                ui.menu_button(tr("menu.tools"), |ui| {
+                    let mut memory_view_enabled = self.memory_view_enabled.load(Ordering::Relaxed);
+                    if ui.checkbox(&mut memory_view_enabled, tr("tools.expose_ram")).on_hover_text(tr("tools.expose_ram_hint")).changed() {
+                        self.memory_view_enabled.store(memory_view_enabled, Ordering::Relaxed);
+                    }
+                    let mut debug_mode = self.debug_mode.load(Ordering::Relaxed);
+                    if ui.checkbox(&mut debug_mode, tr("tools.debug_mode")).on_hover_text(tr("tools.debug_mode_hint")).changed() {
+                        self.debug_mode.store(debug_mode, Ordering::Relaxed);
+                        if !debug_mode {
+                            *self.ppu_snapshot.lock().unwrap() = None;
+                        }
+                    }
+                    let mut volume_normalization = self.volume_normalization.load(Ordering::Relaxed);
+                    if ui.checkbox(&mut volume_normalization, tr("tools.normalize_volume")).on_hover_text(tr("tools.normalize_volume_hint")).changed() {
+                        self.volume_normalization.store(volume_normalization, Ordering::Relaxed);
+                    }
+                    let mut playback_settings = self.playback_settings.lock().unwrap();
+                    ui.add(egui::Slider::new(&mut playback_settings.volume, 0.0..=1.0).text(tr("tools.volume")).custom_formatter(|volume, _| format!("{:.0}%", volume * 100.0)));
+                    ui.checkbox(&mut playback_settings.is_high_pass_enabled, tr("tools.high_pass_filter")).on_hover_text(tr("tools.high_pass_filter_hint"));
+                    drop(playback_settings);
+                    if ui.add_enabled(self.screen_shades.lock().unwrap().is_some(), egui::Button::new(tr("tools.copy_screen_text"))).clicked() {
+                        workspace::WorkspaceViewer { emu: self }.copy_screen_text(ctx);
+                        ui.close_menu();
+                    }
+                    ui.menu_button(tr("tools.dump_frames"), |ui| {
+                        let mut settings = self.frame_dump_settings.lock().unwrap();
+                        ui.add(egui::Slider::new(&mut settings.every_nth_frame, 1..=60).text(tr("frame_dump.every_nth_frame")));
+                        ui.add(egui::Slider::new(&mut settings.seconds, 0.5..=60.0).text(tr("frame_dump.seconds")));
+
+                        let frames_left = *self.frame_dump_progress.lock().unwrap();
+                        match frames_left {
+                            Some(frames_left) => {
+                                ui.label(tr_args("frame_dump.progress", &[("frames", &frames_left)]));
+                            }
+                            None => {
+                                if ui.add_enabled(self.rom_file_path.lock().unwrap().is_some(), egui::Button::new(tr("frame_dump.start"))).clicked() {
+                                    if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                                        settings.folder = folder;
+                                        *self.frame_dump_request.lock().unwrap() = Some(settings.clone());
+                                    }
+                                    ui.close_menu();
+                                }
+                            }
+                        }
+                        drop(settings);
+                    });
+                    ui.menu_button(tr("tools.rumble"), |ui| {
+                        let game_id = self.rom_info.lock().unwrap().as_ref().map(|info| gbemu::input::game_id(&info.title, info.global_checksum));
+                        let mut settings = self.rumble_settings.lock().unwrap();
+                        ui.checkbox(&mut settings.enabled, tr("rumble.enabled"));
+                        ui.add(egui::Slider::new(&mut settings.strength, 0.0..=1.0).text(tr("rumble.strength")));
+                        ui.checkbox(&mut settings.on_state_load, tr("rumble.on_state_load"));
+                        if let Some(game_id) = game_id {
+                            ui.separator();
+                            let mut has_watch = settings.watches.contains_key(&game_id);
+                            if ui.checkbox(&mut has_watch, tr("rumble.watch")).on_hover_text(tr("rumble.watch_hint")).changed() {
+                                if has_watch {
+                                    settings.watches.insert(game_id.clone(), gbemu::rumble::MemoryWatch::default());
+                                }
+                                else {
+                                    settings.watches.remove(&game_id);
+                                }
+                            }
+                            if let Some(watch) = settings.watches.get_mut(&game_id) {
+                                ui.horizontal(|ui| {
+                                    ui.label(tr("rumble.address"));
+                                    ui.add(egui::DragValue::new(&mut watch.address).hexadecimal(4, false, true));
+                                    ui.label(tr("rumble.threshold"));
+                                    ui.add(egui::DragValue::new(&mut watch.threshold));
+                                });
+                            }
+                        }
+                        drop(settings);
+                    });
+                    ui.menu_button(tr("tools.input_macros"), |ui| {
+                        let game_id = self.rom_info.lock().unwrap().as_ref().map(|info| gbemu::input::game_id(&info.title, info.global_checksum));
+                        let Some(game_id) = game_id else {
+                            ui.label(tr("macros.no_rom"));
+                            return;
+                        };
+
+                        let is_recording = self.is_recording_macro.load(Ordering::Relaxed);
+                        let record_text = if is_recording {tr("macros.stop_recording")} else {tr("macros.record")};
+                        if ui.button(record_text).on_hover_text(tr("macros.record_hint")).clicked() {
+                            self.is_recording_macro.store(!is_recording, Ordering::Relaxed);
+                        }
+                        if self.is_playing_macro.load(Ordering::Relaxed) {
+                            ui.label(tr("macros.playing"));
+                        }
+                        ui.separator();
+
+                        let mut input_macros = self.input_macros.lock().unwrap();
+                        let game_macros = input_macros.entry(game_id).or_default();
+                        if game_macros.is_empty() {
+                            ui.label(tr("macros.none"));
+                        }
+                        let mut removed = None;
+                        for (index, input_macro) in game_macros.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut input_macro.name).desired_width(100.0));
+                                ui.label(tr_args("macros.frames", &[("frames", &input_macro.frame_count())]));
+                                if ui.button(tr("macros.play")).clicked() {
+                                    *self.macro_play_request.lock().unwrap() = Some(index);
+                                }
+                                let hotkey_name = input_macro.hotkey.map(|key| key.name()).unwrap_or(tr("macros.no_hotkey"));
+                                egui::ComboBox::from_id_salt(("macro_hotkey", index)).selected_text(hotkey_name).show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut input_macro.hotkey, None, tr("macros.no_hotkey"));
+                                    for key in gbemu::macros::HOTKEYS {
+                                        ui.selectable_value(&mut input_macro.hotkey, Some(key), key.name());
+                                    }
+                                });
+                                ui.checkbox(&mut input_macro.run_on_load, tr("macros.run_on_load"));
+                                if ui.button(tr("macros.delete")).clicked() {
+                                    removed = Some(index);
+                                }
+                            });
+                        }
+                        if let Some(index) = removed {
+                            game_macros.remove(index);
+                        }
+                        input_macros.retain(|_, game_macros| !game_macros.is_empty());
+                        drop(input_macros);
+                    });
+                    if ui.button(tr("tools.monitor")).clicked() {
+                        self.monitor_open.store(true, Ordering::Relaxed);
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("tools.state_diff")).clicked() {
+                        self.state_diff.lock().unwrap().is_open = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("tools.cheats")).clicked() {
+                        self.cheats.lock().unwrap().is_open = true;
+                        ui.close_menu();
+                    }
+                    ui.menu_button(tr("tools.camera"), |ui| {
+                        let mut camera_image_path = self.camera_image_path.lock().unwrap();
+                        if ui.radio(camera_image_path.is_none(), tr("camera.test_pattern")).clicked() {
+                            *camera_image_path = None;
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("camera.choose")).on_hover_text(tr("camera.choose_hint")).clicked() {
+                            if let Some(path) = rfd::FileDialog::new().add_filter("PNG", &["png"]).pick_file() {
+                                *camera_image_path = Some(path);
+                            }
+                            ui.close_menu();
+                        }
+                    });
+                    if ui.button(tr("tools.test_suite")).clicked() {
+                        self.test_runner.is_open.store(true, Ordering::Relaxed);
+                        ui.close_menu();
+                    }
+                    ui.menu_button(tr("tools.link_cable"), |ui| {
+                        let mut lock = self.link_port_device.lock().unwrap();
+                        ui.radio_value(&mut *lock, LinkPortDevice::Disconnected, tr("link_cable.disconnected"));
+                        ui.radio_value(&mut *lock, LinkPortDevice::Loopback, tr("link_cable.loopback")).on_hover_text(tr("link_cable.loopback_hint"));
+                        ui.radio_value(&mut *lock, LinkPortDevice::Stdout, tr("link_cable.stdout")).on_hover_text(tr("link_cable.stdout_hint"));
+                        for port_name in gbemu::serial::LinkAdapter::available_ports() {
+                            let label = tr_args("link_cable.adapter", &[("port", &port_name)]);
+                            ui.radio_value(&mut *lock, LinkPortDevice::LinkAdapter(port_name), label);
+                        }
+                        ui.separator();
+                        ui.radio_value(&mut *lock, LinkPortDevice::BarcodeBoy, tr("link_cable.barcode_boy"));
+                        ui.radio_value(&mut *lock, LinkPortDevice::Workboy, tr("link_cable.workboy")).on_hover_text(tr("link_cable.workboy_hint"));
+
+                        if *lock == LinkPortDevice::BarcodeBoy {
+                            ui.separator();
+                            let barcode_id = ui.make_persistent_id("barcode_entry");
+                            let mut barcode: String = ui.data(|data| data.get_temp(barcode_id)).unwrap_or_default();
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(&mut barcode).char_limit(gbemu::serial::BARCODE_LENGTH).hint_text(tr("link_cable.barcode_hint")));
+                                let is_valid = barcode.len() == gbemu::serial::BARCODE_LENGTH && barcode.bytes().all(|digit| digit.is_ascii_digit());
+                                if ui.add_enabled(is_valid, egui::Button::new(tr("link_cable.scan"))).clicked() {
+                                    self.peripheral_input.lock().unwrap().extend(barcode.bytes());
+                                }
+                            });
+                            ui.data_mut(|data| data.insert_temp(barcode_id, barcode));
+                        }
+
+                        let mut pacing_settings = self.serial_pacing.lock().unwrap();
+                        if let Some(pacing) = pacing_settings.for_device_mut(&lock) {
+                            ui.separator();
+                            ui.label(tr("link_cable.pacing")).on_hover_text(tr("link_cable.pacing_hint"));
+                            ui.horizontal(|ui| {
+                                ui.radio_value(pacing, SerialPacing::Off, tr("link_cable.pacing_off"));
+                                if ui.radio(matches!(pacing, SerialPacing::Fixed(_)), tr("link_cable.pacing_fixed")).clicked() && !matches!(pacing, SerialPacing::Fixed(_)) {
+                                    *pacing = SerialPacing::Fixed(gbemu::link_port::DEFAULT_SERIAL_DELAY);
+                                }
+                                ui.radio_value(pacing, SerialPacing::Auto, tr("link_cable.pacing_auto")).on_hover_text(tr("link_cable.pacing_auto_hint"));
+                            });
+                            if let SerialPacing::Fixed(microseconds) = pacing {
+                                ui.add(egui::Slider::new(microseconds, 0..=20000).logarithmic(true).suffix(" µs").text(tr("link_cable.pacing_delay")));
+                            }
+                        }
+                        drop(pacing_settings);
+                        drop(lock);
+                    });
+                    ui.menu_button(tr("tools.run_ahead"), |ui| {
+                        let mut lock = self.run_ahead_frames.lock().unwrap();
+                        ui.radio_value(&mut *lock, 0, tr("run_ahead.off"));
+                        ui.radio_value(&mut *lock, 1, tr("run_ahead.one_frame"));
+                        ui.radio_value(&mut *lock, 2, tr("run_ahead.two_frames"));
+                        drop(lock);
+                    });
+                    ui.menu_button(tr("tools.frame_hashes"), |ui| {
+                        let mut lock = self.frame_hash_mode.lock().unwrap();
+                        ui.radio_value(&mut *lock, gbemu::desync::FrameHashMode::Off, tr("frame_hashes.off"));
+                        ui.radio_value(&mut *lock, gbemu::desync::FrameHashMode::Record, tr("frame_hashes.record"));
+                        ui.radio_value(&mut *lock, gbemu::desync::FrameHashMode::Verify, tr("frame_hashes.verify"));
+                        drop(lock);
+                    });
+                    ui.menu_button(tr("tools.fast_forward"), |ui| {
+                        let mut settings = self.fast_forward.lock().unwrap();
+                        ui.add_enabled(!settings.is_uncapped, egui::Slider::new(&mut settings.speed, 1..=8).suffix("x").text(tr("fast_forward.speed")));
+                        ui.checkbox(&mut settings.is_uncapped, tr("fast_forward.uncapped"));
+                        ui.label(tr("fast_forward.hint"));
+                    });
+                    ui.menu_button(tr("tools.state_history"), |ui| {
+                        let mut settings = *self.state_history_settings.lock().unwrap();
+                        let mut max_memory_mb = settings.max_memory / (1024 * 1024);
+                        ui.checkbox(&mut settings.enabled, tr("state_history.enabled")).on_hover_text(tr("state_history.rewind_hint"));
+                        ui.add(egui::Slider::new(&mut settings.capture_interval, 1..=60).text(tr("state_history.interval")));
+                        ui.add(egui::Slider::new(&mut settings.max_states, 60..=36000).text(tr("state_history.max_states")));
+                        ui.add(egui::Slider::new(&mut max_memory_mb, 8..=1024).text(tr("state_history.max_memory")));
+                        settings.max_memory = max_memory_mb * 1024 * 1024;
+                        *self.state_history_settings.lock().unwrap() = settings;
+
+                        let (state_count, memory_usage) = *self.state_history_usage.lock().unwrap();
+                        ui.label(tr_args("state_history.usage", &[("states", &state_count), ("megabytes", &format!("{:.1}", memory_usage as f32 / (1024.0 * 1024.0)))]));
+                    });
+                });
+                ui.menu_button(tr("menu.window"), |ui| {
+                    let mut lock = self.workspace.lock().unwrap();
+                    for tab in workspace::WorkspaceTab::ALL {
+                        let mut is_open = lock.find_tab(&tab).is_some();
+                        if ui.checkbox(&mut is_open, tab.title()).changed() {
+                            if is_open {
+                                lock.push_to_focused_leaf(tab);
+                            }
+                            else if let Some(location) = lock.find_tab(&tab) {
+                                lock.remove_tab(location);
+                            }
+                        }
+                    }
+                    ui.separator();
+                    if ui.button(tr("window.reset_layout")).clicked() {
+                        *lock = workspace::default_layout();
+                        ui.close_menu();
+                    }
+                    drop(lock);
+                    ui.separator();
+
+                    ui.menu_button(tr("window.palette"), |ui| {
+                        let game_id = self.rom_info.lock().unwrap().as_ref().map(|info| gbemu::input::game_id(&info.title, info.global_checksum));
+                        let mut lock = self.palette_profiles.lock().unwrap();
+                        let profiles = &mut *lock;
+                        if let Some(game_id) = &game_id {
+                            let mut has_override = profiles.per_game.contains_key(game_id);
+                            if ui.checkbox(&mut has_override, tr_args("palette.custom", &[("game", game_id)])).changed() {
+                                if has_override {
+                                    profiles.per_game.insert(game_id.clone(), profiles.default);
+                                }
+                                else {
+                                    profiles.per_game.remove(game_id);
+                                }
+                            }
+                            ui.separator();
+                        }
+                        let settings = match game_id.as_ref().and_then(|game_id| profiles.per_game.get_mut(game_id)) {
+                            Some(settings) => settings,
+                            None => &mut profiles.default
+                        };
+                        for preset in gbemu::palette::PalettePreset::ALL {
+                            ui.radio_value(&mut settings.preset, preset, preset.name());
+                        }
+                        ui.separator();
+                        ui.add(egui::Slider::new(&mut settings.background_contrast, 0.5..=2.0).text(tr("palette.background_contrast")));
+                        ui.add(egui::Slider::new(&mut settings.sprite_contrast, 0.5..=2.0).text(tr("palette.sprite_contrast")));
+                        drop(lock);
+                    });
+                    let mut flicker_blending = self.flicker_blending.load(Ordering::Relaxed);
+                    if ui.checkbox(&mut flicker_blending, tr("window.blend_flicker")).on_hover_text(tr("window.blend_flicker_hint")).changed() {
+                        self.flicker_blending.store(flicker_blending, Ordering::Relaxed);
+                    }
+                    ui.separator();
+
+                    let mut view_settings = *self.view_settings.lock().unwrap();
+                    ui.checkbox(&mut view_settings.always_on_top, tr("window.always_on_top"));
+                    if ui.checkbox(&mut view_settings.compact, tr("window.compact")).on_hover_text(tr("window.compact_hint")).changed() {
+                        ui.close_menu();
+                    }
+                    ui.add(egui::Slider::new(&mut view_settings.opacity, 0.2..=1.0).text(tr("window.opacity")));
+                    ui.menu_button(tr("window.border"), |ui| {
+                        let mut settings = self.border_settings.lock().unwrap();
+                        if ui.radio(settings.image_path.is_none(), tr("border.none")).clicked() {
+                            settings.image_path = None;
+                        }
+                        if let Some(path) = &settings.image_path {
+                            let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                            let _ = ui.radio(true, file_name);
+                        }
+                        if ui.button(tr("border.choose")).on_hover_text(tr("border.choose_hint")).clicked() {
+                            if let Some(path) = rfd::FileDialog::new().add_filter("PNG", &["png"]).pick_file() {
+                                settings.image_path = Some(path);
+                            }
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        let mut overscan_percent = settings.overscan * 100.0;
+                        if ui.add(egui::Slider::new(&mut overscan_percent, 0.0..=10.0).text(tr("border.overscan")).suffix("%")).changed() {
+                            settings.overscan = overscan_percent / 100.0;
+                        }
+                        drop(settings);
+                    });
+                    ui.menu_button(tr("window.language"), |ui| {
+                        for language in i18n::Language::ALL {
+                            if ui.radio(i18n::language() == language, language.name()).clicked() {
+                                i18n::set_language(language);
+                            }
+                        }
+                    });
+                    if view_settings != *self.view_settings.lock().unwrap() {
+                        view_settings.apply(ctx);
+                        *self.view_settings.lock().unwrap() = view_settings;
+                    }
+                });
+                ui.menu_button(tr("menu.help"), |ui| {
+                    if ui.button(tr("help.opcode_reference")).clicked() {
+                        self.opcode_reference_open.store(true, Ordering::Relaxed);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    let mut update_check = self.update_check.load(Ordering::Relaxed);
+                    if ui.checkbox(&mut update_check, tr("help.check_updates")).on_hover_text(tr("help.check_updates_hint")).changed() {
+                        self.update_check.store(update_check, Ordering::Relaxed);
+                    }
+                    let is_checking = matches!(*self.update_state.lock().unwrap(), updater::UpdateState::Checking);
+                    if ui.add_enabled(update_check && !is_checking, egui::Button::new(tr("help.check_updates_now"))).clicked() {
+                        updater::check(self.update_state.clone(), ctx.clone(), true);
+                        ui.close_menu();
+                    }
+                });
                 ui.add_space(16.0);
 
                 egui::widgets::global_theme_preference_buttons(ui);
             });
         });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            // The central panel the region left after adding TopPanel's and SidePanel's
-            /*let top = ctx.available_rect().top();
-            let left = ctx.available_rect().left();
-            let painter = ui.painter();
-
-            painter.rect(egui::Rect { min: egui::pos2(0.0 + left, 10.0 + top), max: egui::pos2(100.0 + left, 100.0 + top) }, egui::Rounding::ZERO, egui::Color32::RED, egui::Stroke::NONE);
-*/
-            let lock = self.rom_file_path.lock().unwrap();
-            if let Some(picked_path) = lock.clone() {
+        let sessions = self.sessions.lock().unwrap().clone();
+        if sessions.len() > 1 {
+            let mut focus_request = None;
+            let mut close_request = None;
+            egui::TopBottomPanel::top("session_tabs").show(ctx, |ui| {
                 ui.horizontal(|ui| {
-                    ui.label("Loaded Rom: ");
-                    ui.monospace(picked_path);
+                    for (i, session) in sessions.iter().enumerate() {
+                        if ui.selectable_label(session.is_same(self), session.title()).clicked() {
+                            focus_request = Some(i);
+                        }
+                        if ui.small_button("✖").on_hover_text(tr("tabs.close")).clicked() {
+                            close_request = Some(i);
+                        }
+                        ui.separator();
+                    }
                 });
+            });
+            if let Some(index) = close_request {
+                self.close_session(index, ctx);
             }
-            else {
-                ui.horizontal(|ui| {
-                    ui.label("No rom detected!");
-                });
+            else if let Some(index) = focus_request {
+                self.focus_session(index);
             }
-            drop(lock);
-
-            let lock = self.screen_pixels.lock().unwrap();
-            if let Some(color_array) = lock.clone() {
-                let painter = ui.painter();
-                let pixel_stack: Vec<Shape>;
+        }
 
-                pixel_stack = color_array.iter()
-                           .map(|pixel| Shape::Rect(pixel.to_rect(game_height, game_width, y_offset, x_offset)))
-                           .collect();
+        let rom_download = self.rom_download.clone();
+        let mut lock = rom_download.lock().unwrap();
+        if let Some(rom_download) = &mut *lock {
+            let mut is_open = true;
+            egui::Window::new(tr("menu.open_rom_from_url")).open(&mut is_open).collapsible(false).show(ctx, |ui| {
+                rom_download.show(ui);
+            });
 
-                painter.extend(pixel_stack);
+            let finished_path = match &*rom_download.state.lock().unwrap() {
+                rom_download::DownloadState::Finished(path) => Some(path.clone()),
+                _ => None
+            };
+            if let Some(path) = finished_path {
+                self.open_rom(path, ctx);
+                is_open = false;
             }
-            drop(lock);
+            if !is_open {
+                *lock = None;
+            }
+        }
+        drop(lock);
 
-            let lock = self.rom_info.lock().unwrap();
-            if let Some(info) = lock.clone() {
-                ui.horizontal(|ui| {
-                    ui.label("Title: ");
-                    ui.monospace(info.title);
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Manufacturer Code: ");
-                    ui.monospace(info.manufacturer_code);
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Gameboy Color Compatibility: ");
-                    ui.monospace(match info.cgb_flag {
-                        CGBState::Monochrome => "GameBoy only",
-                        CGBState::Color => "GameBoy Color only",
-                        CGBState::Both => "Gameboy Color enhancement supported"
-                    });
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Super GameBoy support: ");
-                    ui.monospace(format!("{}", info.is_sgb));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Licensee: ");
-                    ui.monospace(info.licensee);
-                });
-                ui.horizontal(|ui|{
-                    ui.label("Mapper Code:");
-                    ui.label(format!("{}", info.cartridge_type));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Rom Size: ");
-                    ui.label(format!("{} bytes ({} banks)", info.rom_size, info.rom_banks));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Ram Size: ");
-                    ui.label(format!("{} bytes ({} banks)", info.ram_size, info.ram_banks));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Can be sold in Japan: ");
-                    ui.monospace(format!("{}", info.overseas_only));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Version: ");
-                    ui.monospace(format!("{}", info.version_number));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Header Checksum: ");
-                    ui.monospace(format!("{}", info.header_checksum));
-                });
-                ui.horizontal(|ui| {
-                    ui.label("Global Chacksum: ");
-                    ui.monospace(format!("{}", info.global_checksum));
-                });
+        let mut is_monitor_open = self.monitor_open.load(Ordering::Relaxed);
+        if is_monitor_open {
+            egui::Window::new(tr("tools.monitor")).open(&mut is_monitor_open).show(ctx, |ui| {
+                workspace::WorkspaceViewer { emu: self }.monitor(ui);
+            });
+            self.monitor_open.store(is_monitor_open, Ordering::Relaxed);
+        }
+
+        let mut state_diff = self.state_diff.lock().unwrap();
+        if state_diff.is_open {
+            let mut is_state_diff_open = true;
+            let rom_path = self.rom_file_path.lock().unwrap().clone();
+            egui::Window::new(tr("tools.state_diff")).open(&mut is_state_diff_open).show(ctx, |ui| {
+                state_diff.show(ui, rom_path);
+            });
+            state_diff.is_open = is_state_diff_open;
+        }
+        drop(state_diff);
+
+        let mut cheats = self.cheats.lock().unwrap();
+        if cheats.is_open {
+            let mut is_cheats_open = true;
+            egui::Window::new(tr("tools.cheats")).open(&mut is_cheats_open).show(ctx, |ui| {
+                cheats.show(ui);
+            });
+            cheats.is_open = is_cheats_open;
+        }
+        drop(cheats);
+
+        let mut is_test_suite_open = self.test_runner.is_open.load(Ordering::Relaxed);
+        if is_test_suite_open {
+            egui::Window::new(tr("tools.test_suite")).open(&mut is_test_suite_open).show(ctx, |ui| {
+                self.test_runner.show(ui);
+            });
+            self.test_runner.is_open.store(is_test_suite_open, Ordering::Relaxed);
+        }
+
+        let mut is_opcode_reference_open = self.opcode_reference_open.load(Ordering::Relaxed);
+        if is_opcode_reference_open {
+            egui::Window::new(tr("help.opcode_reference")).open(&mut is_opcode_reference_open).show(ctx, |ui| {
+                gbemu::opcode_reference::show_reference(ui);
+            });
+            self.opcode_reference_open.store(is_opcode_reference_open, Ordering::Relaxed);
+        }
+
+        let desync_frame = *self.desync_frame.lock().unwrap();
+        if let Some(desync_frame) = desync_frame {
+            egui::Window::new(tr("desync.title")).collapsible(false).resizable(false).show(ctx, |ui| {
+                ui.label(tr_args("desync.message", &[("frame", &desync_frame)]));
+                if ui.button(tr("dialog.dismiss")).clicked() {
+                    *self.desync_frame.lock().unwrap() = None;
+                }
+            });
+        }
+
+        let load_error = self.load_error.lock().unwrap().clone();
+        if let Some((rom_file_path, error)) = load_error {
+            egui::Window::new(tr("load_error.title")).collapsible(false).resizable(false).show(ctx, |ui| {
+                ui.label(tr_args("load_error.message", &[("path", &rom_file_path), ("error", &error)]));
+                if ui.button(tr("dialog.dismiss")).clicked() {
+                    *self.load_error.lock().unwrap() = None;
+                }
+            });
+        }
+
+        let compatibility_warnings = self.compatibility_warnings.lock().unwrap().clone();
+        if !compatibility_warnings.is_empty() {
+            egui::Window::new(tr("compatibility.title")).collapsible(false).resizable(false).show(ctx, |ui| {
+                for warning in compatibility_warnings {
+                    ui.label(warning);
+                }
+                if ui.button(tr("dialog.dismiss")).clicked() {
+                    self.compatibility_warnings.lock().unwrap().clear();
+                }
+            });
+        }
+
+        updater::show(&self.update_state, ctx);
+
+        let audio_device_notice = self.audio_device_notice.lock().unwrap().clone();
+        if let Some(device_change) = audio_device_notice {
+            egui::Window::new(tr("audio_device.title")).collapsible(false).resizable(false).show(ctx, |ui| {
+                match &device_change.device_name {
+                    Some(device_name) => ui.label(tr_args("audio_device.switched", &[("device", device_name)])),
+                    None => ui.label(tr("audio_device.none")),
+                };
+                if ui.button(tr("dialog.dismiss")).clicked() {
+                    *self.audio_device_notice.lock().unwrap() = None;
+                }
+            });
+        }
+
+        let workspace = self.workspace.clone();
+        let mut lock = workspace.lock().unwrap();
+        if self.debugger_requested.swap(false, Ordering::Relaxed) {
+            match lock.find_tab(&workspace::WorkspaceTab::Debugger) {
+                Some(location) => lock.set_active_tab(location),
+                None => lock.push_to_focused_leaf(workspace::WorkspaceTab::Debugger),
             }
-        });
+        }
+        egui_dock::DockArea::new(&mut lock)
+            .style(egui_dock::Style::from_egui(ctx.style().as_ref()))
+            .show(ctx, &mut workspace::WorkspaceViewer { emu: self });
+        drop(lock);
+    }
+
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        //Panels are drawn with the chosen opacity, so nothing else should show behind them
+        [0.0; 4]
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.is_kiosk {
+            return;
+        }
+        eframe::set_value(storage, workspace::WORKSPACE_KEY, &*self.workspace.lock().unwrap());
+        eframe::set_value(storage, gbemu::input::INPUT_PROFILES_KEY, &*self.input_profiles.lock().unwrap());
+        eframe::set_value(storage, gbemu::BOOT_ROM_PATH_KEY, &*self.boot_rom_path.lock().unwrap());
+        eframe::set_value(storage, gbemu::RAM_INIT_KEY, &*self.ram_init.lock().unwrap());
+        eframe::set_value(storage, gbemu::MODEL_KEY, &*self.model.lock().unwrap());
+        eframe::set_value(storage, gbemu::ACCURATE_PROHIBITED_AREA_KEY, &self.accurate_prohibited_area.load(Ordering::Relaxed));
+        eframe::set_value(storage, gbemu::palette::PALETTE_PROFILES_KEY, &*self.palette_profiles.lock().unwrap());
+        eframe::set_value(storage, gbemu::rumble::RUMBLE_SETTINGS_KEY, &*self.rumble_settings.lock().unwrap());
+        eframe::set_value(storage, gbemu::link_port::SERIAL_PACING_KEY, &*self.serial_pacing.lock().unwrap());
+        let mut debug_sessions = self.debug_sessions.lock().unwrap();
+        debug_sessions.retain(|_, session| *session != gbemu::debug_session::DebugSession::default());
+        eframe::set_value(storage, gbemu::debug_session::DEBUG_SESSIONS_KEY, &*debug_sessions);
+        drop(debug_sessions);
+        eframe::set_value(storage, gbemu::macros::INPUT_MACROS_KEY, &*self.input_macros.lock().unwrap());
+        eframe::set_value(storage, border::BORDER_SETTINGS_KEY, &*self.border_settings.lock().unwrap());
+        eframe::set_value(storage, gbemu::test_runner::TEST_MATRIX_KEY, &*self.test_runner.matrix.lock().unwrap());
+        eframe::set_value(storage, i18n::LANGUAGE_KEY, &i18n::language());
+        eframe::set_value(storage, view_settings::VIEW_SETTINGS_KEY, &*self.view_settings.lock().unwrap());
+        eframe::set_value(storage, gbemu::flicker::FLICKER_BLENDING_KEY, &self.flicker_blending.load(Ordering::Relaxed));
+        eframe::set_value(storage, gbemu::irq_flash::IRQ_FLASH_KEY, &*self.irq_flash.lock().unwrap());
+        eframe::set_value(storage, gbemu::VOLUME_NORMALIZATION_KEY, &self.volume_normalization.load(Ordering::Relaxed));
+        eframe::set_value(storage, gbemu::PLAYBACK_SETTINGS_KEY, &*self.playback_settings.lock().unwrap());
+        eframe::set_value(storage, gbemu::av_sync::AV_SYNC_CORRECTION_KEY, &self.av_sync_correction.load(Ordering::Relaxed));
+        eframe::set_value(storage, gbemu::pacing::SYNC_MODE_KEY, &*self.sync_mode.lock().unwrap());
+        eframe::set_value(storage, gbemu::pacing::FAST_FORWARD_KEY, &*self.fast_forward.lock().unwrap());
+        eframe::set_value(storage, gbemu::pacing::FRAME_SKIP_KEY, &*self.frame_skip.lock().unwrap());
+        eframe::set_value(storage, updater::UPDATE_CHECK_KEY, &self.update_check.load(Ordering::Relaxed));
     }
 }
 
-/*fn parse_tile(tile: Tile) {
-    for row in tile.pixels {
-        for pixel in row {
-            match pixel {
-                0 => print!("."),
-                1 => print!("░"),
-                2 => print!("▒"),
-                3 => print!("▓"),
-                _ => print!("?")
+#[cfg(feature = "gui")]
+impl gbemu::GBEmu {
+    //Just the game screen. The window has no decorations here, so it's dragged by the screen itself
+    fn compact_view(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().frame(egui::Frame::none().fill(ctx.style().visuals.panel_fill)).show(ctx, |ui| {
+            let response = ui.interact(ui.max_rect(), ui.id().with("compact_view"), egui::Sense::click_and_drag());
+            if response.drag_started_by(egui::PointerButton::Primary) {
+                ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
             }
+            response.context_menu(|ui| {
+                if ui.button(tr("window.leave_compact")).clicked() {
+                    let mut lock = self.view_settings.lock().unwrap();
+                    lock.compact = false;
+                    lock.apply(ctx);
+                    drop(lock);
+                    ui.close_menu();
+                }
+            });
+
+            workspace::WorkspaceViewer { emu: self }.game(ui);
+        });
+    }
+
+    //Just the game screen, fullscreen, with nothing to click on
+    fn kiosk_view(&mut self, ctx: &egui::Context) {
+        if !ctx.input(|input| input.viewport().fullscreen.unwrap_or(false)) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
         }
-        println!();
+        ctx.set_cursor_icon(egui::CursorIcon::None);
+
+        egui::CentralPanel::default().frame(egui::Frame::none().fill(egui::Color32::BLACK)).show(ctx, |ui| {
+            if self.rom_file_path.lock().unwrap().is_none() {
+                ui.centered_and_justified(|ui| ui.label(tr("kiosk.no_rom")));
+                return;
+            }
+            workspace::WorkspaceViewer { emu: self }.game(ui);
+        });
     }
-    println!("\n");
-}*/
+}
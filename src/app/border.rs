@@ -0,0 +1,157 @@
+use std::{collections::VecDeque, fs::File, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use super::i18n::tr;
+
+pub const BORDER_SETTINGS_KEY: &str = "border_settings";
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct BorderSettings {
+    pub image_path: Option<PathBuf>, //A PNG drawn over the screen, like a handheld shell
+    pub overscan: f32, //Fraction of each edge of the screen that's cropped off
+}
+
+impl Default for BorderSettings {
+    fn default() -> Self {
+        Self {
+            image_path: None,
+            overscan: 0.0,
+        }
+    }
+}
+
+//A border image and the window in it where the screen goes. The window is the largest transparent area that doesn't
+//touch the edge of the image, so it can sit anywhere in the frame, like the screen in the top half of a DMG shell
+pub struct Border {
+    texture: egui::TextureHandle,
+    size: egui::Vec2,
+    screen: egui::Rect, //In image pixels
+}
+
+impl Border {
+    pub fn load(ctx: &egui::Context, path: &Path) -> Result<Self, String> {
+        let image = decode_png(path)?;
+        let screen = find_screen_window(&image).ok_or(tr("border.no_screen_area"))?;
+        let size = egui::vec2(image.size[0] as f32, image.size[1] as f32);
+        let texture = ctx.load_texture(path.display().to_string(), image, egui::TextureOptions::NEAREST);
+
+        Ok(Self {
+            texture,
+            size,
+            screen,
+        })
+    }
+
+    //Fits the whole image in rect and returns where the image and the screen end up
+    pub fn layout(&self, rect: egui::Rect) -> (egui::Rect, egui::Rect) {
+        let image_rect = fit_rect(rect, self.size.x / self.size.y);
+        let scale = image_rect.width() / self.size.x;
+        let screen_rect = egui::Rect::from_min_size(image_rect.min + self.screen.min.to_vec2() * scale, self.screen.size() * scale);
+        (image_rect, screen_rect)
+    }
+
+    pub fn paint(&self, painter: &egui::Painter, image_rect: egui::Rect) {
+        let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(self.texture.id(), image_rect, uv, egui::Color32::WHITE);
+    }
+}
+
+//Keeps the decoded border around so the image is only loaded again when the path changes
+#[derive(Default)]
+pub struct BorderCache {
+    path: Option<PathBuf>,
+    border: Option<Border>,
+}
+
+impl BorderCache {
+    pub fn get(&mut self, ctx: &egui::Context, path: &Option<PathBuf>) -> Option<&Border> {
+        if *path != self.path {
+            self.path = path.clone();
+            self.border = path.as_ref().and_then(|path| match Border::load(ctx, path) {
+                Ok(border) => Some(border),
+                Err(e) => {
+                    println!("ERROR: Could not load border {}: {}", path.display(), e);
+                    None
+                }
+            });
+        }
+        self.border.as_ref()
+    }
+}
+
+//The largest rect with the given width / height ratio that fits in rect, centered
+pub fn fit_rect(rect: egui::Rect, ratio: f32) -> egui::Rect {
+    let mut size = rect.size();
+    if size.x / size.y < ratio {
+        size.y = size.x / ratio;
+    }
+    else {
+        size.x = size.y * ratio;
+    }
+    egui::Rect::from_center_size(rect.center(), size)
+}
+
+pub(crate) fn decode_png(path: &Path) -> Result<egui::ColorImage, String> {
+    let mut decoder = png::Decoder::new(File::open(path).map_err(|e| e.to_string())?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|e| e.to_string())?;
+    let bytes = &buffer[..info.buffer_size()];
+    let size = [info.width as usize, info.height as usize];
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => bytes.chunks(4).map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3])).collect(),
+        png::ColorType::Rgb => bytes.chunks(3).map(|p| egui::Color32::from_rgb(p[0], p[1], p[2])).collect(),
+        png::ColorType::GrayscaleAlpha => bytes.chunks(2).map(|p| egui::Color32::from_rgba_unmultiplied(p[0], p[0], p[0], p[1])).collect(),
+        png::ColorType::Grayscale => bytes.iter().map(|p| egui::Color32::from_gray(*p)).collect(),
+        png::ColorType::Indexed => return Err(tr("border.unsupported_color_type").to_owned()),
+    };
+    Ok(egui::ColorImage { size, pixels })
+}
+
+//Flood fills each transparent area and returns the bounding box of the largest one that doesn't touch the edge
+fn find_screen_window(image: &egui::ColorImage) -> Option<egui::Rect> {
+    let [width, height] = image.size;
+    let mut visited = vec![false; width * height];
+    let mut best: Option<(usize, egui::Rect)> = None;
+
+    for start in 0..width * height {
+        if visited[start] || image.pixels[start].a() != 0 {
+            continue;
+        }
+
+        let mut queue = VecDeque::from([start]);
+        visited[start] = true;
+        let mut area = 0;
+        let mut touches_edge = false;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (width, height, 0, 0);
+        while let Some(index) = queue.pop_front() {
+            let (x, y) = (index % width, index / width);
+            area += 1;
+            touches_edge |= x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            (min_x, min_y, max_x, max_y) = (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y));
+
+            let neighbors = [
+                (x > 0).then(|| index - 1),
+                (x < width - 1).then(|| index + 1),
+                (y > 0).then(|| index - width),
+                (y < height - 1).then(|| index + width),
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !visited[neighbor] && image.pixels[neighbor].a() == 0 {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        if !touches_edge && best.as_ref().map_or(true, |(best_area, _)| area > *best_area) {
+            let rect = egui::Rect::from_min_max(egui::pos2(min_x as f32, min_y as f32), egui::pos2((max_x + 1) as f32, (max_y + 1) as f32));
+            best = Some((area, rect));
+        }
+    }
+
+    best.map(|(_, rect)| rect)
+}
@@ -0,0 +1,60 @@
+use std::sync::OnceLock;
+
+use super::cartridge_info::CartridgeInfo;
+
+enum Rule {
+    Mapper(Vec<u8>),
+    Crc32(u32),
+}
+
+//Known emulator limitations from assets/compatibility.txt, matched by cartridge type or by a hash of the rom
+fn database() -> &'static [(Rule, &'static str)] {
+    static DATABASE: OnceLock<Vec<(Rule, &'static str)>> = OnceLock::new();
+    DATABASE.get_or_init(|| parse_database(include_str!("../../assets/compatibility.txt")))
+}
+
+fn parse_database(source: &'static str) -> Vec<(Rule, &'static str)> {
+    let mut database = Vec::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let rule = line.split_once('=').and_then(|(key, warning)| {
+            let mut words = key.split_whitespace();
+            let rule = match words.next()? {
+                "mapper" => Rule::Mapper(words.map(|byte| u8::from_str_radix(byte, 16)).collect::<Result<_, _>>().ok()?),
+                "crc32" => Rule::Crc32(u32::from_str_radix(words.next()?, 16).ok()?),
+                _ => return None,
+            };
+            Some((rule, warning.trim()))
+        });
+        match rule {
+            Some(rule) => database.push(rule),
+            None => println!("ERROR: Invalid line in compatibility database: {}", line)
+        }
+    }
+    database
+}
+
+//Every warning that applies to the rom
+pub fn warnings(info: &CartridgeInfo, rom: &[u8]) -> Vec<&'static str> {
+    let mut crc: Option<u32> = None;
+    database().iter().filter(|(rule, _)| match rule {
+        Rule::Mapper(cartridge_types) => cartridge_types.contains(&info.cartridge_type),
+        Rule::Crc32(rom_crc) => *crc.get_or_insert_with(|| crc32(rom)) == *rom_crc,
+    }).map(|(_, warning)| *warning).collect()
+}
+
+//The usual CRC-32 (as in zip and No-Intro), so entries can be copied from rom databases
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFF_u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xEDB88320} else {crc >> 1};
+        }
+    }
+    !crc
+}
@@ -0,0 +1,40 @@
+use std::{fs, path::PathBuf};
+
+//Homebrew that ships with the emulator so there's something to play without hunting for roms. Only roms whose
+//authors allow them to be redistributed belong here, with their license
+pub struct Demo {
+    pub name: &'static str,
+    pub author: &'static str,
+    pub license: &'static str,
+    pub rom: &'static [u8],
+}
+
+pub const DEMOS: &[Demo] = &[
+    Demo {
+        name: "Scroller",
+        author: "gb-rs contributors",
+        license: "MIT OR Apache-2.0",
+        rom: include_bytes!("../../assets/demos/scroller.gb"),
+    },
+];
+
+//Demos are played straight from the bytes built into the emulator. They still get a path in the data folder, which is
+//where their battery saves and savestates are kept, so it's the same between runs
+fn rom_path(demo: &Demo) -> Option<PathBuf> {
+    let file_name: String = demo.name.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    Some(eframe::storage_dir("gb_rs")?.join("demos").join(file_name + ".gb"))
+}
+
+//The path to open the demo with. Its folder is made for the saves, but the rom is never written there
+pub fn prepare(demo: &Demo) -> Result<String, String> {
+    let path = rom_path(demo).ok_or("Could not find a data directory")?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+    }
+    Ok(path.display().to_string())
+}
+
+//The demo a path from prepare belongs to
+pub fn find(path: &str) -> Option<&'static Demo> {
+    DEMOS.iter().find(|demo| rom_path(demo).is_some_and(|rom_path| rom_path.display().to_string() == path))
+}
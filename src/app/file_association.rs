@@ -0,0 +1,79 @@
+use std::{env, path::{Path, PathBuf}};
+
+//Registers the rom file types and exits without opening a window, for installers
+pub const REGISTER_FLAG: &str = "--register-file-types";
+
+//The first argument that isn't a flag, which is how file managers hand a double clicked file to the emulator
+pub fn launch_rom_path() -> Option<String> {
+    let path = PathBuf::from(env::args().skip(1).find(|arg| !arg.starts_with("--"))?);
+    if !path.is_file() {
+        println!("ERROR: Could not open {}: Not a file", path.display());
+        return None;
+    }
+    Some(path.canonicalize().unwrap_or(path).display().to_string())
+}
+
+//Makes .gb and .gbc files open with this executable for the current user
+pub fn register() -> Result<(), String> {
+    let executable = env::current_exe().map_err(|e| e.to_string())?;
+    register_for(&executable)
+}
+
+#[cfg(target_os = "windows")]
+fn register_for(executable: &Path) -> Result<(), String> {
+    //Per user keys, so no administrator rights are needed
+    let open_command = format!("\"{}\" \"%1\"", executable.display());
+    let keys = [
+        (r"HKCU\Software\Classes\gb_rs.rom", "Game Boy ROM"),
+        (r"HKCU\Software\Classes\gb_rs.rom\shell\open\command", open_command.as_str()),
+        (r"HKCU\Software\Classes\.gb", "gb_rs.rom"),
+        (r"HKCU\Software\Classes\.gbc", "gb_rs.rom"),
+    ];
+    for (key, value) in keys {
+        run("reg", &["add", key, "/ve", "/d", value, "/f"])?;
+    }
+    for extension in [r"HKCU\Software\Classes\.gb\OpenWithProgids", r"HKCU\Software\Classes\.gbc\OpenWithProgids"] {
+        run("reg", &["add", extension, "/v", "gb_rs.rom", "/t", "REG_NONE", "/f"])?;
+    }
+    Ok(())
+}
+
+//shared-mime-info already knows both rom types, so this only needs a desktop entry that opens them
+#[cfg(all(unix, not(target_os = "macos")))]
+fn register_for(executable: &Path) -> Result<(), String> {
+    const MIME_TYPES: [&str; 2] = ["application/x-gameboy-rom", "application/x-gameboy-color-rom"];
+
+    let data_dir = env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or("Could not find the data directory")?;
+    let applications_dir = data_dir.join("applications");
+    std::fs::create_dir_all(&applications_dir).map_err(|e| e.to_string())?;
+
+    let desktop_entry = format!(
+        "[Desktop Entry]\nType=Application\nName=gb_rs\nComment=Game Boy emulator\nExec=\"{}\" %f\nTerminal=false\nCategories=Game;Emulator;\nMimeType={};\n",
+        executable.display(),
+        MIME_TYPES.join(";"),
+    );
+    std::fs::write(applications_dir.join("gb_rs.desktop"), desktop_entry).map_err(|e| e.to_string())?;
+
+    //Only refreshes the menu cache, so it's fine for it to be missing
+    if let Err(e) = run("update-desktop-database", &[&applications_dir.display().to_string()]) {
+        println!("ERROR: {}", e);
+    }
+    run("xdg-mime", &[&["default", "gb_rs.desktop"][..], &MIME_TYPES[..]].concat())
+}
+
+//Finder only associates files through the app bundle's Info.plist
+#[cfg(any(target_os = "macos", target_arch = "wasm32"))]
+fn register_for(_executable: &Path) -> Result<(), String> {
+    Err(String::from("File types can't be registered on this platform"))
+}
+
+#[cfg(any(target_os = "windows", all(unix, not(target_os = "macos"))))]
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = std::process::Command::new(program).args(args).status().map_err(|e| format!("Could not run {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("{} failed with {}", program, status));
+    }
+    Ok(())
+}
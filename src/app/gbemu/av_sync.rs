@@ -0,0 +1,119 @@
+use std::time::Instant;
+
+use super::apu::AudioTimestamp;
+
+pub const AV_SYNC_CORRECTION_KEY: &str = "av_sync_correction";
+
+const M_CYCLE_RATE: f64 = 1048576.0;
+const FRAME_SECONDS: f64 = 17556.0 / M_CYCLE_RATE;
+
+//Frames measured before the skew counts as settled. Whatever it settles at is the latency of the output device,
+//which can't be corrected, so only drift away from it is
+const SETTLE_FRAMES: u32 = 120;
+//How much of each new measurement goes into the shown skew
+const SMOOTHING: f64 = 0.05;
+//How far the skew may drift before a frame is dropped or duplicated. Over one frame so a correction can't overshoot
+const CORRECTION_THRESHOLD: f64 = 1.5 * FRAME_SECONDS;
+//Frames measured after a correction before another one can happen, giving the skew time to show its effect
+const CORRECTION_COOLDOWN: u32 = 30;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Correction {
+    DropFrame, //The sound is running ahead of the picture
+    DuplicateFrame, //The picture is running ahead of the sound
+}
+
+//Measures how far the picture on screen is from the sound coming out of the speakers. Both are stamped with the
+//console's output timeline, so the skew is how far apart the two timestamps are at the moment the frame is shown
+#[derive(Default)]
+pub struct AvSync {
+    frame_cycle: Option<u64>, //The frame waiting to be shown
+    audio: Option<AudioTimestamp>,
+    measurements: u32,
+    skew: Option<f64>, //Seconds, positive when the picture is ahead of the sound
+    settled_skew: Option<f64>,
+    pending_correction: Option<Correction>,
+    cooldown: u32,
+    pub dropped_frames: u32,
+    pub duplicated_frames: u32,
+}
+
+impl AvSync {
+    //Called by the emulation thread with each frame it sends to the UI
+    pub fn new_frame(&mut self, cycle: u64, audio: Option<AudioTimestamp>) {
+        self.frame_cycle = Some(cycle);
+        self.audio = audio;
+    }
+
+    //Called by the UI right after painting. Only the first paint of a frame is measured
+    pub fn presented(&mut self, now: Instant) {
+        let (Some(frame_cycle), Some(audio)) = (self.frame_cycle.take(), self.audio) else {
+            return;
+        };
+
+        let heard_cycle = if now >= audio.played_at {
+            audio.cycle as f64 + now.duration_since(audio.played_at).as_secs_f64() * M_CYCLE_RATE
+        }
+        else {
+            audio.cycle as f64 - audio.played_at.duration_since(now).as_secs_f64() * M_CYCLE_RATE
+        };
+        let measured = (frame_cycle as f64 - heard_cycle) / M_CYCLE_RATE;
+        let skew = match self.skew {
+            Some(skew) => skew + (measured - skew) * SMOOTHING,
+            None => measured,
+        };
+        self.skew = Some(skew);
+
+        self.measurements += 1;
+        if self.measurements == SETTLE_FRAMES {
+            self.settled_skew = Some(skew);
+        }
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return;
+        }
+        if let (Some(drift), None) = (self.drift(), self.pending_correction) {
+            if drift > CORRECTION_THRESHOLD {
+                self.pending_correction = Some(Correction::DuplicateFrame);
+            }
+            else if drift < -CORRECTION_THRESHOLD {
+                self.pending_correction = Some(Correction::DropFrame);
+            }
+        }
+    }
+
+    //Called by the emulation thread between frames
+    pub fn take_correction(&mut self, is_enabled: bool) -> Option<Correction> {
+        let correction = self.pending_correction.take().filter(|_| is_enabled)?;
+        match correction {
+            Correction::DropFrame => self.dropped_frames += 1,
+            Correction::DuplicateFrame => self.duplicated_frames += 1,
+        }
+        //The smoothed skew would take a while to catch up, so it starts over from the next measurement
+        self.skew = None;
+        self.cooldown = CORRECTION_COOLDOWN;
+        Some(correction)
+    }
+
+    //Called when the output device changes, since the new one has its own latency
+    pub fn reset(&mut self) {
+        *self = Self {
+            dropped_frames: self.dropped_frames,
+            duplicated_frames: self.duplicated_frames,
+            ..Self::default()
+        };
+    }
+
+    pub fn skew(&self) -> Option<f64> {
+        self.skew
+    }
+
+    pub fn settled_skew(&self) -> Option<f64> {
+        self.settled_skew
+    }
+
+    //How far the skew has moved since it settled
+    pub fn drift(&self) -> Option<f64> {
+        Some(self.skew? - self.settled_skew?)
+    }
+}
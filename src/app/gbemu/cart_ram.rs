@@ -0,0 +1,109 @@
+use std::{collections::BTreeMap, ops::Range, sync::{mpsc::{channel, Receiver, Sender}, Arc, Mutex}};
+
+use super::console::GBConsole;
+
+//Sent to subscribers whenever cartridge RAM differs from the last frame
+#[derive(Clone)]
+pub struct CartRamChange {
+    pub version: u64,
+    pub changed: Vec<Range<usize>>, //Offsets into cartridge RAM, ignoring banking
+}
+
+#[derive(Default)]
+struct CartRamState {
+    ram: Vec<u8>,
+    version: u64,
+    pending_writes: BTreeMap<usize, u8>,
+    subscribers: Vec<Sender<CartRamChange>>,
+}
+
+//Lets tools like party viewers and save editors work on cartridge RAM while the game runs. Reads come from a copy
+//taken once per frame. Writes are batched and only applied once the game has RAM disabled, so they can't land in
+//the middle of the game writing its save. Applied writes go through the mapper, so battery saves pick them up
+#[derive(Clone, Default)]
+pub struct CartRamAccess {
+    state: Arc<Mutex<CartRamState>>,
+}
+
+impl CartRamAccess {
+    //Empty until the first frame, or if the cartridge has no RAM
+    pub fn read(&self) -> Vec<u8> {
+        self.state.lock().unwrap().ram.clone()
+    }
+
+    pub fn read_range(&self, range: Range<usize>) -> Option<Vec<u8>> {
+        self.state.lock().unwrap().ram.get(range).map(|bytes| bytes.to_vec())
+    }
+
+    //Goes up by one every time the RAM changes
+    pub fn version(&self) -> u64 {
+        self.state.lock().unwrap().version
+    }
+
+    //Queues the bytes to be written at offset. A later write to the same byte replaces an earlier one still waiting
+    pub fn write(&self, offset: usize, bytes: &[u8]) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        if offset + bytes.len() > state.ram.len() {
+            return Err(format!("Write of {} bytes at {:04X} is outside the cartridge's {} bytes of RAM", bytes.len(), offset, state.ram.len()));
+        }
+
+        for (i, byte) in bytes.iter().enumerate() {
+            state.pending_writes.insert(offset + i, *byte);
+        }
+        Ok(())
+    }
+
+    pub fn has_pending_writes(&self) -> bool {
+        !self.state.lock().unwrap().pending_writes.is_empty()
+    }
+
+    //Subscribers are dropped once their receiver is
+    pub fn subscribe(&self) -> Receiver<CartRamChange> {
+        let (sender, receiver) = channel();
+        self.state.lock().unwrap().subscribers.push(sender);
+        receiver
+    }
+
+    //Called by the emulation thread once per frame
+    pub fn sync(&self, console: &mut GBConsole) {
+        let mut state = self.state.lock().unwrap();
+        if !state.pending_writes.is_empty() && !console.is_cartridge_ram_enabled() {
+            for (offset, value) in std::mem::take(&mut state.pending_writes) {
+                console.write_cartridge_ram(offset, value);
+            }
+        }
+
+        let ram = match console.cartridge_ram() {
+            Some(ram) => ram,
+            None => return,
+        };
+        if ram == state.ram.as_slice() {
+            return;
+        }
+
+        let changed = changed_ranges(&state.ram, ram);
+        state.ram = ram.to_vec();
+        state.version += 1;
+
+        let change = CartRamChange { version: state.version, changed };
+        state.subscribers.retain(|subscriber| subscriber.send(change.clone()).is_ok());
+    }
+}
+
+pub(super) fn changed_ranges(old_ram: &[u8], new_ram: &[u8]) -> Vec<Range<usize>> {
+    if old_ram.len() != new_ram.len() {
+        return vec![Range { start: 0, end: new_ram.len() }];
+    }
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (offset, (old, new)) in old_ram.iter().zip(new_ram.iter()).enumerate() {
+        if old == new {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if range.end == offset => range.end += 1,
+            _ => ranges.push(offset..offset + 1),
+        }
+    }
+    ranges
+}
@@ -0,0 +1,79 @@
+use crate::app::i18n::tr;
+
+use super::cheats::{load_cheats, rom_to_cheats, save_cheats, Cheat, CheatCode};
+
+//The "Cheats" window. Each game's list is kept next to its save file and saved as soon as it changes
+#[derive(Default)]
+pub struct CheatsTool {
+    pub is_open: bool,
+    pub cheats: Vec<Cheat>,
+    pub revision: u64, //Bumped on every change so the emulation thread knows to hand the console the codes again
+    cheats_file_path: Option<String>,
+    new_name: String,
+    new_code: String,
+    error: Option<String>,
+}
+
+impl CheatsTool {
+    pub fn open_rom(&mut self, rom_file_path: String) {
+        let cheats_file_path = rom_to_cheats(rom_file_path);
+        self.cheats = load_cheats(&cheats_file_path);
+        self.cheats_file_path = Some(cheats_file_path);
+        self.revision += 1;
+        self.error = None;
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let Some(cheats_file_path) = self.cheats_file_path.clone() else {
+            ui.label(tr("cheats.no_rom"));
+            return;
+        };
+
+        let old_cheats = self.cheats.clone();
+        ui.horizontal(|ui| {
+            ui.label(tr("cheats.code"));
+            ui.add(egui::TextEdit::singleline(&mut self.new_code).desired_width(100.0).hint_text("ABC-DEF-GHI"));
+            ui.label(tr("cheats.name"));
+            ui.add(egui::TextEdit::singleline(&mut self.new_name).desired_width(150.0));
+            if ui.button(tr("cheats.add")).clicked() {
+                let code = self.new_code.trim().to_uppercase();
+                match CheatCode::parse(&code) {
+                    Ok(_) => {
+                        self.cheats.push(Cheat { name: self.new_name.trim().to_owned(), code, is_enabled: true });
+                        self.new_code.clear();
+                        self.new_name.clear();
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+        });
+        if let Some(e) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, e);
+        }
+        ui.separator();
+
+        let mut removed = None;
+        egui::Grid::new("cheats").striped(true).show(ui, |ui| {
+            for (index, cheat) in self.cheats.iter_mut().enumerate() {
+                ui.checkbox(&mut cheat.is_enabled, "");
+                ui.monospace(&cheat.code);
+                ui.add(egui::TextEdit::singleline(&mut cheat.name).desired_width(150.0));
+                if ui.button(tr("cheats.delete")).clicked() {
+                    removed = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = removed {
+            self.cheats.remove(index);
+        }
+
+        if self.cheats != old_cheats {
+            self.revision += 1;
+            if let Err(e) = save_cheats(&cheats_file_path, &self.cheats) {
+                println!("ERROR: Could not write {}: {}", cheats_file_path, e);
+            }
+        }
+    }
+}
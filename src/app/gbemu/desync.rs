@@ -0,0 +1,110 @@
+use std::{collections::HashMap, fs::File, io::{BufRead, BufReader, BufWriter, Write}};
+
+use super::{fnv, ppu::Pixel};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FrameHashMode {
+    Off,
+    Record, //Writes the hash of every frame to the hash log
+    Verify, //Compares the hash of every frame against the hash log
+}
+
+//Compares frame hashes against a reference log so a movie or netplay session that diverges
+//is reported at the first frame it happens instead of silently drifting
+pub struct DesyncDetector {
+    mode: FrameHashMode,
+    log_writer: Option<BufWriter<File>>,
+    reference: HashMap<u64, u64>,
+    desync_frame: Option<u64>,
+}
+
+impl DesyncDetector {
+    pub fn new(mode: FrameHashMode, hash_log_path: String) -> Self {
+        let mut log_writer = None;
+        let mut reference = HashMap::new();
+
+        match mode {
+            FrameHashMode::Off => {}
+            FrameHashMode::Record => {
+                match File::create(hash_log_path.clone()) {
+                    Ok(file) => log_writer = Some(BufWriter::new(file)),
+                    Err(e) => println!("ERROR: Could not create hash log {}: {}", hash_log_path, e)
+                }
+            }
+            FrameHashMode::Verify => {
+                match File::open(hash_log_path.clone()) {
+                    Ok(file) => {
+                        //Each line is "<frame> <hash>", both in hex. Lines that can't be read are skipped
+                        for line in BufReader::new(file).split(b'\n').map_while(Result::ok) {
+                            let line = String::from_utf8_lossy(&line);
+                            let mut parts = line.split_whitespace();
+                            if let (Some(frame), Some(hash)) = (parts.next(), parts.next()) {
+                                if let (Ok(frame), Ok(hash)) = (u64::from_str_radix(frame, 16), u64::from_str_radix(hash, 16)) {
+                                    reference.insert(frame, hash);
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => println!("ERROR: Could not open hash log {}: {}", hash_log_path, e)
+                }
+            }
+        }
+
+        Self {
+            mode,
+            log_writer,
+            reference,
+            desync_frame: None,
+        }
+    }
+
+    pub fn is_hashing(&self) -> bool {
+        self.mode != FrameHashMode::Off
+    }
+
+    //Returns the frame number of the first desync, if one was just detected
+    pub fn check_frame(&mut self, frame: u64, frame_hash: u64) -> Option<u64> {
+        match self.mode {
+            FrameHashMode::Off => None,
+            FrameHashMode::Record => {
+                if let Some(writer) = &mut self.log_writer {
+                    if let Err(e) = writeln!(writer, "{:x} {:x}", frame, frame_hash) {
+                        println!("ERROR: Could not write hash log: {}", e);
+                        self.log_writer = None;
+                    }
+                }
+                None
+            }
+            FrameHashMode::Verify => {
+                if self.desync_frame.is_some() {
+                    return None;
+                }
+
+                match self.reference.get(&frame) {
+                    Some(expected_hash) if *expected_hash != frame_hash => {
+                        self.desync_frame = Some(frame);
+                        self.desync_frame
+                    }
+                    _ => None
+                }
+            }
+        }
+    }
+}
+
+//FNV-1a over the shade and palette of every pixel, plus the color on CGB
+pub fn hash_frame(screen: &[[Pixel; 160]; 144]) -> u64 {
+    fnv::hash(screen.iter().flatten().flat_map(|pixel| {
+        let rgb = pixel.rgb.map(u16::to_le_bytes);
+        [pixel.color, pixel.palette.unwrap_or(0xFF)].into_iter().chain(rgb.into_iter().flatten())
+    }))
+}
+
+pub fn rom_to_hash_log(rom_file_path: String) -> String {
+    if let Some(hash_log_path) = rom_file_path.rsplitn(2, ".").last() {
+        hash_log_path.to_owned() + ".hashes"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
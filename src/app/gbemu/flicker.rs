@@ -0,0 +1,53 @@
+use egui::Color32;
+
+use super::{frame_dump::{RawFrame, ScreenColors}, ppu::Pixel};
+
+pub const FLICKER_BLENDING_KEY: &str = "flicker_blending";
+
+//Frames in a row a pixel has to change sprites before it counts as flickering
+const MIN_STREAK: u8 = 3;
+
+//Games fake transparency, or fit more than 10 sprites on a line, by drawing some sprites only every other frame.
+//This spots pixels that swap between a sprite and something else on every frame and shows the last two frames mixed,
+//so the sprite looks see-through instead of flickering. Real hardware flickers, so this isn't accurate
+pub struct FlickerBlender {
+    previous: Option<RawFrame>, //Before blending
+    streaks: Box<[[u8; 160]; 144]>,
+}
+
+impl Default for FlickerBlender {
+    fn default() -> Self {
+        Self {
+            previous: None,
+            streaks: Box::new([[0; 160]; 144]),
+        }
+    }
+}
+
+impl FlickerBlender {
+    pub fn apply(&mut self, pixels: &[[Pixel; 160]; 144], colors: &mut ScreenColors) {
+        let unblended = Box::new(*colors);
+        if let Some(previous) = &self.previous {
+            for y in 0..144 {
+                for x in 0..160 {
+                    let streak = &mut self.streaks[y][x];
+                    *streak = if changed_sprite(&pixels[y][x], &previous.pixels[y][x]) {streak.saturating_add(1)} else {0};
+                    if *streak >= MIN_STREAK {
+                        colors[y][x] = mix(colors[y][x], previous.colors[y][x]);
+                    }
+                }
+            }
+        }
+        self.previous = Some(RawFrame { pixels: Box::new(*pixels), colors: unblended });
+    }
+}
+
+//Only sprites are compared, a scrolling background isn't flicker
+fn changed_sprite(pixel: &Pixel, previous: &Pixel) -> bool {
+    (pixel.palette.is_some() || previous.palette.is_some()) && (pixel.palette, pixel.tile) != (previous.palette, previous.tile)
+}
+
+fn mix(a: Color32, b: Color32) -> Color32 {
+    let average = |a: u8, b: u8| ((a as u16 + b as u16) / 2) as u8;
+    Color32::from_rgb(average(a.r(), b.r()), average(a.g(), b.g()), average(a.b(), b.b()))
+}
@@ -0,0 +1,131 @@
+use std::{fs::{self, File}, io::BufWriter, path::PathBuf, sync::{atomic::{AtomicU32, AtomicUsize, Ordering}, mpsc::{sync_channel, SyncSender}, Arc, Mutex}, thread};
+
+use egui::Color32;
+
+use super::ppu::Pixel;
+
+pub type ScreenColors = [[Color32; 160]; 144];
+
+//How many frames can wait to be encoded. Past that, frames are dropped instead of holding up the emulation thread
+pub const QUEUE_CAPACITY: usize = 60;
+const MAX_ENCODERS: usize = 4;
+
+//The last frame as the PPU produced it, with each pixel's color index and palette next to the color it was shown in
+#[derive(Clone)]
+pub struct RawFrame {
+    pub pixels: Box<[[Pixel; 160]; 144]>,
+    pub colors: Box<ScreenColors>,
+}
+
+#[derive(Clone)]
+pub struct FrameDumpRequest {
+    pub folder: PathBuf,
+    pub every_nth_frame: u32,
+    pub seconds: f32,
+}
+
+impl Default for FrameDumpRequest {
+    fn default() -> Self {
+        Self {
+            folder: PathBuf::new(),
+            every_nth_frame: 1,
+            seconds: 5.0,
+        }
+    }
+}
+
+//How far behind the encoders are, for the stats panel. Shared between dumps
+#[derive(Default)]
+pub struct EncodeQueue {
+    queued: AtomicUsize, //Frames waiting for or being encoded
+    dropped: AtomicU32, //Frames thrown away because the queue was full, since the last dump started
+}
+
+impl EncodeQueue {
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+//Saves emulated frames as numbered PNGs. Encoding happens on a pool of worker threads fed through a bounded queue, so
+//dumping every frame never makes the game stutter. When the encoders can't keep up, frames are dropped
+pub struct FrameDumper {
+    every_nth_frame: u32,
+    frames_left: u32,
+    frames_since_dump: u32,
+    sender: SyncSender<(PathBuf, Vec<u8>)>,
+    queue: Arc<EncodeQueue>,
+    folder: PathBuf,
+}
+
+impl FrameDumper {
+    pub fn new(request: &FrameDumpRequest, queue: Arc<EncodeQueue>) -> Self {
+        if let Err(e) = fs::create_dir_all(&request.folder) {
+            println!("ERROR: Could not create frame dump folder {}: {}", request.folder.display(), e);
+        }
+
+        //Frames are named by number, so it doesn't matter which worker finishes first. The workers stop once the
+        //dumper is dropped and the queue has run dry
+        let (sender, receiver) = sync_channel::<(PathBuf, Vec<u8>)>(QUEUE_CAPACITY);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let encoder_count = thread::available_parallelism().map_or(1, |count| count.get() / 2).clamp(1, MAX_ENCODERS);
+        for _ in 0..encoder_count {
+            let receiver = receiver.clone();
+            let queue = queue.clone();
+            thread::spawn(move || {
+                loop {
+                    let next = receiver.lock().unwrap().recv();
+                    let Ok((path, rgb)) = next else {
+                        return;
+                    };
+                    if let Err(e) = write_png(&path, &rgb) {
+                        println!("ERROR: Could not write frame {}: {}", path.display(), e);
+                    }
+                    queue.queued.fetch_sub(1, Ordering::Relaxed);
+                }
+            });
+        }
+        queue.dropped.store(0, Ordering::Relaxed);
+
+        Self {
+            every_nth_frame: request.every_nth_frame.max(1),
+            frames_left: (request.seconds * 59.7275).round() as u32,
+            frames_since_dump: 0,
+            sender,
+            queue,
+            folder: request.folder.clone(),
+        }
+    }
+
+    //Called once per emulated frame with its number. Returns false once the requested time has been dumped
+    pub fn on_frame(&mut self, frame_number: u64, colors: &ScreenColors) -> bool {
+        if self.frames_since_dump == 0 {
+            let rgb = colors.iter().flatten().flat_map(|color| [color.r(), color.g(), color.b()]).collect();
+            let path = self.folder.join(format!("frame_{:06}.png", frame_number));
+            self.queue.queued.fetch_add(1, Ordering::Relaxed);
+            if self.sender.try_send((path, rgb)).is_err() {
+                self.queue.queued.fetch_sub(1, Ordering::Relaxed);
+                self.queue.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.frames_since_dump = (self.frames_since_dump + 1) % self.every_nth_frame;
+
+        self.frames_left = self.frames_left.saturating_sub(1);
+        self.frames_left > 0
+    }
+
+    pub fn frames_left(&self) -> u32 {
+        self.frames_left
+    }
+}
+
+fn write_png(path: &PathBuf, rgb: &[u8]) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), 160, 144);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgb)
+}
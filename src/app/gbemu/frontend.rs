@@ -0,0 +1,1418 @@
+use std::{collections::VecDeque, fs, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, thread, time::Instant};
+use egui::Color32;
+use egui_dock::DockState;
+
+use super::{apu_output::PlaybackSettings, av_sync::{self, AvSync, Correction}, AudioDeviceChange, CPURegisters, console::GBConsole, input::{self, GamepadState, InputConfig, InputProfiles}, joypad::JoypadState, macros::{self, InputMacros, MacroPlayer, MacroRecorder}, memory_editor::MemoryEditor, monitor::{self, Monitor}, memory_view::{self, MemoryView}, palette::{self, PaletteProfiles, PaletteSettings}, ram_init::RamInit, model::Model, apu_log::{self, ApuEventLogger}, desync::{self, DesyncDetector, FrameHashMode}, rumble::{self, Rumble, RumbleEvent, RumbleSettings}, cart_ram::CartRamAccess, gdb_stub::{GdbRequest, GdbStub}, script::{OverlayText, Script}, debug_session::{self, DebugSessions, DebugView, DisassemblyView, ResolvedSession, StepRequest, StepTarget}, heatmap::MemoryHeatmap, irq_flash::{self, IrqFlash, IrqFlashSettings}, pacing::{self, FastForwardSettings, FramePacer, SyncMode}, watch_log::{self, WatchLogger}, blocked_log::{self, BlockedAccessLogger}, flicker::{self, FlickerBlender}, frame_export::{self, ColorSpace}, frame_dump::{EncodeQueue, FrameDumpRequest, FrameDumper, RawFrame, ScreenColors}, ppu::Pixel, inspector::PPUSnapshot, savestate::{self, SavestateRequest, StateHistory, StateHistorySettings}, serial::{BarcodeBoy, Disconnected, LinkAdapter, Loopback, PeripheralInput, SerialDevice, SerialPacing, Stdout, Workboy}, session::Session, state_diff::StateDiffTool, cheats, cheats_tool::CheatsTool, link_port::{self, LinkPortDevice, SerialPacingSettings}, test_runner::{self, TestRunner}, text_art::ScreenShades, trace_log::{self, InstructionTrace, TraceMode}, visualizer::AudioVisualizer, wake::WakeSignal};
+
+use crate::{app::{border::{self, BorderCache, BorderSettings}, cartridge_info::{CartridgeError, CartridgeInfo}, compatibility, demos, i18n::{self, tr_args}, kiosk::{self, ExitCombo}, rom_archive, rom_download::RomDownload, updater::{self, UpdateState}, view_settings::{self, ViewSettings}, workspace::{self, WorkspaceTab}}, CartridgeStorage, CAMERA_HEIGHT, CAMERA_WIDTH};
+
+pub const BOOT_ROM_PATH_KEY: &str = "boot_rom_path";
+pub const RAM_INIT_KEY: &str = "ram_init";
+pub const ACCURATE_PROHIBITED_AREA_KEY: &str = "accurate_prohibited_area";
+pub const MODEL_KEY: &str = "model";
+pub const VOLUME_NORMALIZATION_KEY: &str = "volume_normalization";
+pub const PLAYBACK_SETTINGS_KEY: &str = "playback_settings";
+
+#[derive(Clone)]
+pub struct GBEmu {
+    pub rom_file_path: Arc<Mutex<Option<String>>>,
+    pub is_kiosk: bool, //Started with --kiosk. Only the game is shown and only the gamepad controls it
+    pub rom_info: Arc<Mutex<Option<CartridgeInfo>>>,
+    pub compatibility_warnings: Arc<Mutex<Vec<&'static str>>>, //Known problems with the loaded rom, until dismissed
+    pub load_error: Arc<Mutex<Option<(String, String)>>>, //Path and reason of the last rom that couldn't be loaded, until dismissed
+    pub file_changed: Arc<AtomicBool>,
+    pub screen_pixels: Arc<Mutex<Option<Vec<ScreenPixel>>>>,
+    pub screen_shades: Arc<Mutex<Option<Box<ScreenShades>>>>, //The DMG shade of each pixel of the last frame
+    pub memory_view_enabled: Arc<AtomicBool>,
+    pub link_port_device: Arc<Mutex<LinkPortDevice>>,
+    pub peripheral_input: PeripheralInput,
+    pub serial_pacing: Arc<Mutex<SerialPacingSettings>>,
+    pub frame_hash_mode: Arc<Mutex<FrameHashMode>>,
+    pub desync_frame: Arc<Mutex<Option<u64>>>,
+    pub savestate_request: Arc<Mutex<Option<SavestateRequest>>>,
+    pub can_undo_load: Arc<AtomicBool>,
+    pub cartridge_swap_request: Arc<Mutex<Option<String>>>, //Path of the rom to swap in
+    pub monitor: Arc<Mutex<Monitor>>,
+    pub monitor_open: Arc<AtomicBool>,
+    pub input_macros: Arc<Mutex<InputMacros>>,
+    pub macro_play_request: Arc<Mutex<Option<usize>>>, //Index into the game's macros
+    pub is_recording_macro: Arc<AtomicBool>,
+    pub is_playing_macro: Arc<AtomicBool>,
+    pub frame_dump_settings: Arc<Mutex<FrameDumpRequest>>, //Settings for the next dump, the folder is picked on start
+    pub frame_dump_request: Arc<Mutex<Option<FrameDumpRequest>>>,
+    pub frame_dump_progress: Arc<Mutex<Option<u32>>>, //Frames left while dumping
+    pub frame_dump_queue: Arc<EncodeQueue>,
+    pub state_history_settings: Arc<Mutex<StateHistorySettings>>,
+    pub state_history_usage: Arc<Mutex<(usize, usize)>>, //(States, bytes)
+    pub console_output: Arc<Mutex<String>>,
+    pub audio_device_notice: Arc<Mutex<Option<AudioDeviceChange>>>, //Shown once after the output device changes
+    pub workspace: Arc<Mutex<DockState<WorkspaceTab>>>,
+    pub debug_mode: Arc<AtomicBool>,
+    pub ppu_snapshot: Arc<Mutex<Option<PPUSnapshot>>>,
+    pub run_ahead_frames: Arc<Mutex<u8>>,
+    pub apu_logging_enabled: Arc<AtomicBool>,
+    pub blocked_access_logging: Arc<AtomicBool>,
+    pub instruction_trace_mode: Arc<Mutex<TraceMode>>,
+    pub instruction_trace_lines: Arc<Mutex<Vec<String>>>, //The ring buffer's lines, published whenever the debugger pauses
+    pub gdb_server_port: Arc<Mutex<Option<u16>>>, //None while the GDB server is off
+    pub gdb_server_status: Arc<Mutex<String>>,
+    pub script_path: Arc<Mutex<Option<PathBuf>>>, //None while no script is running
+    pub script_reload: Arc<AtomicBool>,
+    pub script_status: Arc<Mutex<String>>,
+    pub script_overlay: Arc<Mutex<Vec<OverlayText>>>,
+    pub input_profiles: Arc<Mutex<InputProfiles>>,
+    pub boot_rom_path: Arc<Mutex<Option<String>>>,
+    pub rom_download: Arc<Mutex<Option<RomDownload>>>,
+    pub update_check: Arc<AtomicBool>, //Off unless the user opts in, nothing is fetched without it
+    pub update_state: Arc<Mutex<UpdateState>>,
+    pub test_runner: TestRunner,
+    pub state_diff: Arc<Mutex<StateDiffTool>>,
+    pub cheats: Arc<Mutex<CheatsTool>>,
+    pub camera_image_path: Arc<Mutex<Option<PathBuf>>>, //What the Pocket Camera sees. None shows a test pattern
+    pub opcode_reference_open: Arc<AtomicBool>,
+    pub ram_init: Arc<Mutex<RamInit>>,
+    pub accurate_prohibited_area: Arc<AtomicBool>,
+    pub model: Arc<Mutex<Option<Model>>>, //None picks one for each cartridge
+    pub paused: Arc<AtomicBool>,
+    pub break_on_unknown_io: Arc<AtomicBool>,
+    pub strict_mode: Arc<AtomicBool>,
+    pub freeze_ppu: Arc<AtomicBool>,
+    pub freeze_apu: Arc<AtomicBool>,
+    pub irq_flash: Arc<Mutex<IrqFlashSettings>>,
+    pub break_reason: Arc<Mutex<Option<String>>>,
+    pub log_error: Arc<Mutex<Option<String>>>, //Why a debug log couldn't be written, until dismissed
+    pub step_request: Arc<Mutex<Option<StepRequest>>>, //Taken by the emulation thread when it resumes, or at the next frame
+    pub debug_sessions: Arc<Mutex<DebugSessions>>,
+    pub debug_view: Arc<Mutex<DebugView>>,
+    pub cpu_registers: Arc<Mutex<Option<CPURegisters>>>, //Published while paused
+    pub debugger_requested: Arc<AtomicBool>,
+    pub is_focused: Arc<AtomicBool>,
+    pub is_closed: Arc<AtomicBool>,
+    pub wake: WakeSignal, //Wakes the emulation thread when it's waiting for a rom or paused
+    pub is_minimized: Arc<AtomicBool>, //No repaints are requested while the window is minimized
+    pub sessions: Arc<Mutex<Vec<Session>>>, //Every open rom tab, including the focused one
+    pub view_settings: Arc<Mutex<ViewSettings>>,
+    pub palette_profiles: Arc<Mutex<PaletteProfiles>>,
+    pub rumble_settings: Arc<Mutex<RumbleSettings>>,
+    pub border_settings: Arc<Mutex<BorderSettings>>,
+    pub border_cache: Arc<Mutex<BorderCache>>,
+    pub audio_visualizer_enabled: Arc<AtomicBool>,
+    pub audio_channels: Arc<Mutex<[bool; 4]>>, //Pulse 1, pulse 2, wave and noise, false when muted
+    pub audio_visualizer: Arc<Mutex<AudioVisualizer>>,
+    pub magnifier_enabled: Arc<AtomicBool>,
+    pub raw_frame: Arc<Mutex<Option<RawFrame>>>, //Only captured while the magnifier is enabled
+    pub flicker_blending: Arc<AtomicBool>,
+    pub volume_normalization: Arc<AtomicBool>,
+    pub playback_settings: Arc<Mutex<PlaybackSettings>>,
+    pub memory_heatmap_enabled: Arc<AtomicBool>,
+    pub memory_heatmap: Arc<Mutex<MemoryHeatmap>>,
+    pub disassembly_enabled: Arc<AtomicBool>,
+    pub disassembly: Arc<Mutex<DisassemblyView>>, //Updated every frame and whenever the debugger pauses, while enabled
+    pub memory_editor_enabled: Arc<AtomicBool>,
+    pub memory_editor: Arc<Mutex<MemoryEditor>>,
+    pub av_sync: Arc<Mutex<AvSync>>,
+    pub av_sync_correction: Arc<AtomicBool>, //Drops or duplicates frames when the audio device's clock drifts from the video pacing
+    pub sync_mode: Arc<Mutex<SyncMode>>,
+    pub fast_forward: Arc<Mutex<FastForwardSettings>>,
+    pub frame_skip: Arc<Mutex<u32>>, //Most frames skipped in a row when running behind. 0 never skips
+    pub cart_ram: CartRamAccess,
+}
+
+impl Default for GBEmu {
+    fn default() -> Self {
+        Self {
+            rom_file_path: Arc::new(Mutex::new(None)),
+            rom_info: Arc::new(Mutex::new(None)),
+            compatibility_warnings: Arc::new(Mutex::new(Vec::new())),
+            load_error: Arc::new(Mutex::new(None)),
+            file_changed: Arc::new(AtomicBool::from(false)),
+            screen_pixels: Arc::new(Mutex::new(None)),
+            screen_shades: Arc::new(Mutex::new(None)),
+            memory_view_enabled: Arc::new(AtomicBool::from(false)),
+            link_port_device: Arc::new(Mutex::new(LinkPortDevice::Disconnected)),
+            peripheral_input: Arc::new(Mutex::new(VecDeque::new())),
+            serial_pacing: Arc::new(Mutex::new(SerialPacingSettings::default())),
+            frame_hash_mode: Arc::new(Mutex::new(FrameHashMode::Off)),
+            desync_frame: Arc::new(Mutex::new(None)),
+            savestate_request: Arc::new(Mutex::new(None)),
+            can_undo_load: Arc::new(AtomicBool::from(false)),
+            cartridge_swap_request: Arc::new(Mutex::new(None)),
+            monitor: Arc::new(Mutex::new(Monitor::default())),
+            monitor_open: Arc::new(AtomicBool::from(false)),
+            input_macros: Arc::new(Mutex::new(InputMacros::new())),
+            macro_play_request: Arc::new(Mutex::new(None)),
+            is_recording_macro: Arc::new(AtomicBool::from(false)),
+            is_playing_macro: Arc::new(AtomicBool::from(false)),
+            frame_dump_settings: Arc::new(Mutex::new(FrameDumpRequest::default())),
+            frame_dump_request: Arc::new(Mutex::new(None)),
+            frame_dump_progress: Arc::new(Mutex::new(None)),
+            frame_dump_queue: Arc::new(EncodeQueue::default()),
+            state_history_settings: Arc::new(Mutex::new(StateHistorySettings::default())),
+            state_history_usage: Arc::new(Mutex::new((0, 0))),
+            console_output: Arc::new(Mutex::new(String::new())),
+            audio_device_notice: Arc::new(Mutex::new(None)),
+            workspace: Arc::new(Mutex::new(workspace::default_layout())),
+            debug_mode: Arc::new(AtomicBool::from(false)),
+            ppu_snapshot: Arc::new(Mutex::new(None)),
+            run_ahead_frames: Arc::new(Mutex::new(0)),
+            apu_logging_enabled: Arc::new(AtomicBool::from(false)),
+            blocked_access_logging: Arc::new(AtomicBool::from(false)),
+            instruction_trace_mode: Arc::new(Mutex::new(TraceMode::Off)),
+            instruction_trace_lines: Arc::new(Mutex::new(Vec::new())),
+            gdb_server_port: Arc::new(Mutex::new(None)),
+            gdb_server_status: Arc::new(Mutex::new(String::new())),
+            script_path: Arc::new(Mutex::new(None)),
+            script_reload: Arc::new(AtomicBool::from(false)),
+            script_status: Arc::new(Mutex::new(String::new())),
+            script_overlay: Arc::new(Mutex::new(Vec::new())),
+            input_profiles: Arc::new(Mutex::new(InputProfiles::default())),
+            boot_rom_path: Arc::new(Mutex::new(None)),
+            rom_download: Arc::new(Mutex::new(None)),
+            update_check: Arc::new(AtomicBool::from(false)),
+            update_state: Arc::new(Mutex::new(UpdateState::Idle)),
+            test_runner: TestRunner::default(),
+            state_diff: Arc::new(Mutex::new(StateDiffTool::default())),
+            cheats: Arc::new(Mutex::new(CheatsTool::default())),
+            camera_image_path: Arc::new(Mutex::new(None)),
+            opcode_reference_open: Arc::new(AtomicBool::from(false)),
+            ram_init: Arc::new(Mutex::new(RamInit::default())),
+            accurate_prohibited_area: Arc::new(AtomicBool::from(false)),
+            model: Arc::new(Mutex::new(None)),
+            paused: Arc::new(AtomicBool::from(false)),
+            break_on_unknown_io: Arc::new(AtomicBool::from(false)),
+            strict_mode: Arc::new(AtomicBool::from(false)),
+            freeze_ppu: Arc::new(AtomicBool::from(false)),
+            freeze_apu: Arc::new(AtomicBool::from(false)),
+            irq_flash: Arc::new(Mutex::new(IrqFlashSettings::default())),
+            break_reason: Arc::new(Mutex::new(None)),
+            log_error: Arc::new(Mutex::new(None)),
+            step_request: Arc::new(Mutex::new(None)),
+            debug_sessions: Arc::new(Mutex::new(DebugSessions::new())),
+            debug_view: Arc::new(Mutex::new(DebugView::default())),
+            cpu_registers: Arc::new(Mutex::new(None)),
+            debugger_requested: Arc::new(AtomicBool::from(false)),
+            is_focused: Arc::new(AtomicBool::from(true)),
+            is_closed: Arc::new(AtomicBool::from(false)),
+            wake: WakeSignal::default(),
+            is_minimized: Arc::new(AtomicBool::from(false)),
+            sessions: Arc::new(Mutex::new(Vec::new())),
+            view_settings: Arc::new(Mutex::new(ViewSettings::default())),
+            palette_profiles: Arc::new(Mutex::new(PaletteProfiles::default())),
+            rumble_settings: Arc::new(Mutex::new(RumbleSettings::default())),
+            border_settings: Arc::new(Mutex::new(BorderSettings::default())),
+            border_cache: Arc::new(Mutex::new(BorderCache::default())),
+            audio_visualizer_enabled: Arc::new(AtomicBool::from(false)),
+            audio_channels: Arc::new(Mutex::new([true; 4])),
+            audio_visualizer: Arc::new(Mutex::new(AudioVisualizer::default())),
+            magnifier_enabled: Arc::new(AtomicBool::from(false)),
+            flicker_blending: Arc::new(AtomicBool::from(false)),
+            volume_normalization: Arc::new(AtomicBool::from(false)),
+            playback_settings: Arc::new(Mutex::new(PlaybackSettings::default())),
+            raw_frame: Arc::new(Mutex::new(None)),
+            memory_heatmap_enabled: Arc::new(AtomicBool::from(false)),
+            memory_heatmap: Arc::new(Mutex::new(MemoryHeatmap::default())),
+            disassembly_enabled: Arc::new(AtomicBool::from(false)),
+            disassembly: Arc::new(Mutex::new(DisassemblyView::default())),
+            memory_editor_enabled: Arc::new(AtomicBool::from(false)),
+            memory_editor: Arc::new(Mutex::new(MemoryEditor::default())),
+            av_sync: Arc::new(Mutex::new(AvSync::default())),
+            av_sync_correction: Arc::new(AtomicBool::from(false)),
+            sync_mode: Arc::new(Mutex::new(SyncMode::default())),
+            fast_forward: Arc::new(Mutex::new(FastForwardSettings::default())),
+            frame_skip: Arc::new(Mutex::new(0)),
+            cart_ram: CartRamAccess::default(),
+            is_kiosk: false,
+        }
+    }
+}
+
+impl GBEmu {
+    /// Called once before the first frame.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        // This is also where you can customize the look and feel of egui using
+        // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
+
+        let mut r = GBEmu { is_kiosk: kiosk::is_requested(), ..Default::default() };
+        if let Some(storage) = cc.storage {
+            if let Some(saved_workspace) = eframe::get_value(storage, workspace::WORKSPACE_KEY) {
+                *r.workspace.lock().unwrap() = saved_workspace;
+            }
+            if let Some(saved_input_profiles) = eframe::get_value(storage, input::INPUT_PROFILES_KEY) {
+                *r.input_profiles.lock().unwrap() = saved_input_profiles;
+            }
+            if let Some(saved_boot_rom_path) = eframe::get_value(storage, BOOT_ROM_PATH_KEY) {
+                *r.boot_rom_path.lock().unwrap() = saved_boot_rom_path;
+            }
+            if let Some(saved_ram_init) = eframe::get_value(storage, RAM_INIT_KEY) {
+                *r.ram_init.lock().unwrap() = saved_ram_init;
+            }
+            if let Some(saved_accurate_prohibited_area) = eframe::get_value(storage, ACCURATE_PROHIBITED_AREA_KEY) {
+                r.accurate_prohibited_area.store(saved_accurate_prohibited_area, Ordering::Relaxed);
+            }
+            if let Some(saved_model) = eframe::get_value(storage, MODEL_KEY) {
+                *r.model.lock().unwrap() = saved_model;
+            }
+            if let Some(saved_palette_profiles) = eframe::get_value(storage, palette::PALETTE_PROFILES_KEY) {
+                *r.palette_profiles.lock().unwrap() = saved_palette_profiles;
+            }
+            if let Some(saved_rumble_settings) = eframe::get_value(storage, rumble::RUMBLE_SETTINGS_KEY) {
+                *r.rumble_settings.lock().unwrap() = saved_rumble_settings;
+            }
+            if let Some(saved_serial_pacing) = eframe::get_value(storage, link_port::SERIAL_PACING_KEY) {
+                *r.serial_pacing.lock().unwrap() = saved_serial_pacing;
+            }
+            if let Some(saved_debug_sessions) = eframe::get_value(storage, debug_session::DEBUG_SESSIONS_KEY) {
+                *r.debug_sessions.lock().unwrap() = saved_debug_sessions;
+            }
+            if let Some(saved_input_macros) = eframe::get_value(storage, macros::INPUT_MACROS_KEY) {
+                *r.input_macros.lock().unwrap() = saved_input_macros;
+            }
+            if let Some(saved_border_settings) = eframe::get_value(storage, crate::app::border::BORDER_SETTINGS_KEY) {
+                *r.border_settings.lock().unwrap() = saved_border_settings;
+            }
+            if let Some(saved_test_matrix) = eframe::get_value(storage, test_runner::TEST_MATRIX_KEY) {
+                *r.test_runner.matrix.lock().unwrap() = saved_test_matrix;
+            }
+            if let Some(saved_language) = eframe::get_value(storage, i18n::LANGUAGE_KEY) {
+                i18n::set_language(saved_language);
+            }
+            if let Some(saved_view_settings) = eframe::get_value(storage, view_settings::VIEW_SETTINGS_KEY) {
+                *r.view_settings.lock().unwrap() = saved_view_settings;
+            }
+            if let Some(saved_flicker_blending) = eframe::get_value(storage, flicker::FLICKER_BLENDING_KEY) {
+                r.flicker_blending.store(saved_flicker_blending, Ordering::Relaxed);
+            }
+            if let Some(saved_irq_flash) = eframe::get_value(storage, irq_flash::IRQ_FLASH_KEY) {
+                *r.irq_flash.lock().unwrap() = saved_irq_flash;
+            }
+            if let Some(saved_volume_normalization) = eframe::get_value(storage, VOLUME_NORMALIZATION_KEY) {
+                r.volume_normalization.store(saved_volume_normalization, Ordering::Relaxed);
+            }
+            if let Some(saved_playback_settings) = eframe::get_value(storage, PLAYBACK_SETTINGS_KEY) {
+                *r.playback_settings.lock().unwrap() = saved_playback_settings;
+            }
+            if let Some(saved_av_sync_correction) = eframe::get_value(storage, av_sync::AV_SYNC_CORRECTION_KEY) {
+                r.av_sync_correction.store(saved_av_sync_correction, Ordering::Relaxed);
+            }
+            if let Some(saved_sync_mode) = eframe::get_value(storage, pacing::SYNC_MODE_KEY) {
+                *r.sync_mode.lock().unwrap() = saved_sync_mode;
+            }
+            if let Some(saved_fast_forward) = eframe::get_value(storage, pacing::FAST_FORWARD_KEY) {
+                *r.fast_forward.lock().unwrap() = saved_fast_forward;
+            }
+            if let Some(saved_frame_skip) = eframe::get_value(storage, pacing::FRAME_SKIP_KEY) {
+                *r.frame_skip.lock().unwrap() = saved_frame_skip;
+            }
+            if let Some(saved_update_check) = eframe::get_value(storage, updater::UPDATE_CHECK_KEY) {
+                r.update_check.store(saved_update_check, Ordering::Relaxed);
+            }
+        }
+        if !r.is_kiosk {
+            r.view_settings.lock().unwrap().apply(&cc.egui_ctx);
+        }
+        if r.update_check.load(Ordering::Relaxed) && !r.is_kiosk {
+            updater::check(r.update_state.clone(), cc.egui_ctx.clone(), false);
+        }
+
+        r.sessions.lock().unwrap().push(Session::of(&r));
+        r.start_session(cc.egui_ctx.clone());
+        if let Some(path) = crate::app::file_association::launch_rom_path() {
+            r.open_rom(path, &cc.egui_ctx);
+        }
+
+        r
+    }
+
+    //Waits for a rom to be opened in the focused session, then emulates it until the session is closed
+    //A rom that fails to load leaves the session empty and waiting for another one
+    fn start_session(&self, ctx: egui::Context) {
+        let lock = self.clone();
+        thread::spawn(move || loop {
+            lock.wake.wait_while(|| !lock.file_changed.load(Ordering::Relaxed) && !lock.is_closed.load(Ordering::Relaxed));
+            if lock.is_closed.load(Ordering::Relaxed) {
+                return;
+            }
+            lock.file_changed.store(false, Ordering::Relaxed);
+
+            let Err(e) = lock.processor(ctx.clone()) else {
+                return;
+            };
+            let rom_file_path = lock.rom_file_path.lock().unwrap().take().unwrap_or_default();
+            println!("ERROR: Could not load {}: {}", rom_file_path, e);
+            *lock.rom_info.lock().unwrap() = None;
+            lock.compatibility_warnings.lock().unwrap().clear();
+            *lock.load_error.lock().unwrap() = Some((rom_file_path, e.to_string()));
+            ctx.request_repaint();
+        });
+    }
+
+    //Opens the rom in the focused tab, or in a new tab if that one is already running something
+    pub fn open_rom(&mut self, path: String, ctx: &egui::Context) {
+        if self.rom_file_path.lock().unwrap().is_some() {
+            let session = Session::default();
+            self.sessions.lock().unwrap().push(session.clone());
+            session.focus(self);
+            self.start_session(ctx.clone());
+        }
+
+        *self.rom_file_path.lock().unwrap() = Some(path);
+        self.file_changed.store(true, Ordering::Relaxed);
+        self.wake.wake();
+    }
+
+    pub fn focus_session(&mut self, index: usize) {
+        let session = self.sessions.lock().unwrap()[index].clone();
+        session.focus(self);
+    }
+
+    //Stops the tab's emulation thread. The last tab is replaced by an empty one rather than closed
+    pub fn close_session(&mut self, index: usize, ctx: &egui::Context) {
+        let mut lock = self.sessions.lock().unwrap();
+        let session = lock.remove(index);
+        session.is_closed.store(true, Ordering::Relaxed);
+        session.paused.store(false, Ordering::Relaxed);
+        session.wake.wake();
+
+        if lock.is_empty() {
+            let new_session = Session::default();
+            lock.push(new_session.clone());
+            drop(lock);
+            new_session.focus(self);
+            self.start_session(ctx.clone());
+        }
+        else if session.is_same(self) {
+            let next_session = lock[index.min(lock.len() - 1)].clone();
+            drop(lock);
+            next_session.focus(self);
+        }
+    }
+
+    //Demos are built into the emulator, so they're read from memory. Everything else comes from disk
+    fn read_rom(path: &str) -> Result<(Vec<u8>, String), CartridgeError> {
+        match demos::find(path) {
+            Some(demo) => Ok((demo.rom.to_vec(), path.to_owned())),
+            None => rom_archive::read_rom(path),
+        }
+    }
+
+    //The rom is checked before the old cartridge is pulled out, since a mapper can't be built from a bad image. Also
+    //returns the path the new cartridge's files are kept under, see rom_archive::read_rom
+    fn swap_cartridge(console: &mut GBConsole, path: &str) -> Result<(CartridgeInfo, String), CartridgeError> {
+        let (rom, rom_file_path) = Self::read_rom(path)?;
+        let info = CartridgeInfo::from_rom(&rom)?;
+        let storage = CartridgeStorage::beside_rom(&info, &rom_file_path)?;
+        console.swap_cartridge(&info, &rom, storage)?;
+        Ok((info, rom_file_path))
+    }
+
+    //Grayscale, stretched to the sensor's size
+    fn load_camera_image(path: &Path) -> Result<Vec<u8>, String> {
+        let image = border::decode_png(path)?;
+        let [width, height] = image.size;
+        Ok((0..CAMERA_WIDTH * CAMERA_HEIGHT).map(|index| {
+            let x = index % CAMERA_WIDTH * width / CAMERA_WIDTH;
+            let y = index / CAMERA_WIDTH * height / CAMERA_HEIGHT;
+            let pixel = image.pixels[y * width + x];
+            ((pixel.r() as u32 * 299 + pixel.g() as u32 * 587 + pixel.b() as u32 * 114) / 1000) as u8
+        }).collect())
+    }
+
+    //Runs until the session is closed. Roms that can't be loaded return right away, before anything is set up
+    fn processor(&self, frame: egui::Context) -> Result<(), CartridgeError> {
+        //Gets a local copyof the rom file path so we don't need to request access to it every time we read
+        let mut current_file_path: String;
+        {
+            let lock = self.rom_file_path.lock().unwrap();
+            current_file_path = lock.clone().unwrap();
+            drop(lock);
+        }
+
+        //Saves and the rest are kept under the rom's own name when it's in an archive
+        let rom;
+        (rom, current_file_path) = Self::read_rom(&current_file_path)?;
+
+        //Grabs metadata from the rom's cartrige header
+        let info = CartridgeInfo::from_rom(&rom)?;
+        {
+            let mut lock = self.rom_info.lock().unwrap();
+            *lock = Some(info.clone());
+            drop(lock);
+        }
+
+        //Warn before the console is built, since unsupported mappers don't get past that
+        *self.compatibility_warnings.lock().unwrap() = compatibility::warnings(&info, &rom);
+        frame.request_repaint();
+
+        let mut game_id = input::game_id(&info.title, info.global_checksum);
+        let mut input_config = self.input_profiles.lock().unwrap().for_game(&game_id).clone();
+        let mut gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                println!("ERROR: Could not initialize gamepad support: {}", e);
+                None
+            }
+        };
+        let mut gamepad_state = GamepadState::default();
+        let mut kiosk_exit = ExitCombo::default();
+        let mut rumble = Rumble::new();
+        let mut rumble_event: Option<RumbleEvent> = None;
+
+        //Taken right before each savestate load or reset, so loading the wrong slot doesn't throw away progress. Undoing a
+        //load keeps the state it replaced, so the undo can be undone too
+        let mut undo_state: Option<Vec<u8>> = None;
+        self.can_undo_load.store(false, Ordering::Relaxed);
+
+        let model = self.model.lock().unwrap().unwrap_or_else(|| Model::for_cartridge(&info));
+        let storage = CartridgeStorage::beside_rom(&info, &current_file_path)?;
+        let mut console = GBConsole::new(info, &rom, storage, model)?;
+        drop(rom);
+        console.initialize_ram(*self.ram_init.lock().unwrap());
+        console.is_prohibited_area_accurate = self.accurate_prohibited_area.load(Ordering::Relaxed);
+        if let Some(boot_rom_path) = self.boot_rom_path.lock().unwrap().clone() {
+            match fs::read(boot_rom_path.clone()) {
+                Ok(boot_rom) => console.set_boot_rom(boot_rom),
+                Err(e) => println!("ERROR: Could not read boot ROM {}: {}", boot_rom_path, e)
+            }
+        }
+        //What a reset goes back to
+        let power_on_state = savestate::create(&console);
+        self.cheats.lock().unwrap().open_rom(current_file_path.clone());
+        let mut current_cheat_revision = None;
+        let mut current_camera_image_path = None;
+        let mut memory_view: Option<MemoryView> = None;
+
+        let mut console_output = String::new();
+
+        //Enforce framerate
+        let mut pacer = FramePacer::default();
+        let mut was_fast_forwarding = false;
+        let mut current_speed = 1;
+
+        let mut frame_time = Instant::now();
+
+        let mut current_link_port_device = LinkPortDevice::Disconnected;
+        let mut current_serial_pacing = SerialPacing::Off;
+        let mut current_frame_hash_mode = FrameHashMode::Off;
+        let mut desync_detector = DesyncDetector::new(FrameHashMode::Off, String::new());
+        let mut frame_count: u64 = 0;
+        let mut frame_dumper: Option<FrameDumper> = None;
+        let mut current_state_history_settings = StateHistorySettings::default();
+        let mut state_history = StateHistory::new(current_state_history_settings);
+        let mut frames_since_capture = 0;
+        let mut apu_logger: Option<ApuEventLogger> = None;
+        let mut watch_logger: Option<WatchLogger> = None; //Created with the first logged watchpoint hit
+        let mut is_watch_log_failed = false; //Not tried again for this rom, so the error isn't repeated every frame
+        let mut blocked_logger: Option<BlockedAccessLogger> = None;
+        let mut current_trace_mode = TraceMode::Off;
+        let mut is_sample_tap_enabled = false;
+        let mut effects = FrameEffects::default();
+        let mut is_access_counting_enabled = false;
+        let mut debug_session = ResolvedSession::default();
+        let mut last_break_address: Option<u16> = None;
+        let mut step_target: Option<StepTarget> = None;
+        let mut gdb_server = GdbServer::default();
+        let mut script: Option<Script> = None;
+        let mut current_script_path: Option<PathBuf> = None;
+        let mut macro_recorder: Option<MacroRecorder> = None;
+        let mut macro_player = self.input_macros.lock().unwrap().get(&game_id)
+            .and_then(|game_macros| game_macros.iter().find(|input_macro| input_macro.run_on_load))
+            .map(MacroPlayer::new);
+
+        let mut is_dropping_frame = false;
+        let mut is_behind = false;
+        let mut skipped_frames = 0;
+
+        '_Frame: loop {
+            if self.is_closed.load(Ordering::Relaxed) {
+                console.fade_out_audio();
+                return Ok(());
+            }
+            let is_focused = self.is_focused.load(Ordering::Relaxed);
+            if !is_focused {
+                console.fade_out_audio();
+            }
+            else if !console.is_audio_output_enabled() {
+                //The skew measured before the tab was put in the background no longer holds
+                self.av_sync.lock().unwrap().reset();
+            }
+
+            let keyboard = if self.is_kiosk {None} else {Some(&frame)};
+            let is_fast_forwarding = is_focused && input_config.buttons.fast_forward.get_state(keyboard, &gamepad_state);
+            if is_fast_forwarding != was_fast_forwarding {
+                was_fast_forwarding = is_fast_forwarding;
+                //The skew measured at the other speed no longer holds
+                self.av_sync.lock().unwrap().reset();
+            }
+            let fast_forward = *self.fast_forward.lock().unwrap();
+            let (sync_mode, speed) = match *self.sync_mode.lock().unwrap() {
+                _ if is_fast_forwarding && fast_forward.is_uncapped => (SyncMode::Uncapped, 1),
+                sync_mode if is_fast_forwarding => (sync_mode, fast_forward.speed.clamp(1, 8)),
+                sync_mode => (sync_mode, 1),
+            };
+            if speed != current_speed {
+                current_speed = speed;
+                console.set_audio_speed(speed as f32);
+            }
+            console.set_audio_output(is_focused && sync_mode != SyncMode::Uncapped);
+            console.set_volume_normalization(self.volume_normalization.load(Ordering::Relaxed));
+            console.set_playback_settings(*self.playback_settings.lock().unwrap());
+
+            {
+                let lock = self.link_port_device.lock().unwrap();
+                if *lock != current_link_port_device {
+                    current_link_port_device = lock.clone();
+                    self.peripheral_input.lock().unwrap().clear();
+                    let serial_device: Box<dyn SerialDevice> = match &current_link_port_device {
+                        LinkPortDevice::LinkAdapter(port_name) => match LinkAdapter::new(port_name) {
+                            Ok(adapter) => Box::new(adapter),
+                            Err(e) => {
+                                println!("ERROR: Could not open link adapter {}: {}", port_name, e);
+                                Box::new(Disconnected)
+                            }
+                        }
+                        LinkPortDevice::BarcodeBoy => Box::new(BarcodeBoy::new(self.peripheral_input.clone())),
+                        LinkPortDevice::Workboy => Box::new(Workboy::new(self.peripheral_input.clone())),
+                        LinkPortDevice::Loopback => Box::new(Loopback),
+                        LinkPortDevice::Stdout => Box::new(Stdout),
+                        LinkPortDevice::Disconnected => Box::new(Disconnected)
+                    };
+                    console.set_serial_device(serial_device);
+                }
+                drop(lock);
+
+                let serial_pacing = self.serial_pacing.lock().unwrap().for_device(&current_link_port_device);
+                if serial_pacing != current_serial_pacing {
+                    current_serial_pacing = serial_pacing;
+                    console.set_serial_pacing(serial_pacing);
+                }
+            }
+            {
+                let lock = self.cheats.lock().unwrap();
+                if current_cheat_revision != Some(lock.revision) {
+                    current_cheat_revision = Some(lock.revision);
+                    console.set_cheats(&cheats::enabled_codes(&lock.cheats));
+                }
+                drop(lock);
+            }
+            {
+                let lock = self.camera_image_path.lock().unwrap();
+                if *lock != current_camera_image_path {
+                    current_camera_image_path = lock.clone();
+                    match &current_camera_image_path {
+                        Some(path) => match Self::load_camera_image(path) {
+                            Ok(image) => console.set_camera_image(&image),
+                            Err(e) => println!("ERROR: Could not load camera image {}: {}", path.display(), e),
+                        }
+                        None => console.set_camera_image(&[]),
+                    }
+                }
+                drop(lock);
+            }
+            {
+                let lock = self.frame_hash_mode.lock().unwrap();
+                if *lock != current_frame_hash_mode {
+                    current_frame_hash_mode = *lock;
+                    desync_detector = DesyncDetector::new(current_frame_hash_mode, desync::rom_to_hash_log(current_file_path.clone()));
+                    *self.desync_frame.lock().unwrap() = None;
+                }
+                drop(lock);
+            }
+            {
+                let request = self.savestate_request.lock().unwrap().take();
+                match request {
+                    Some(SavestateRequest::Save(slot)) => {
+                        let state = savestate::compress(&savestate::create(&console));
+                        let savestate_path = savestate::rom_to_savestate(current_file_path.clone(), slot);
+                        if let Err(e) = fs::write(savestate_path.clone(), state) {
+                            println!("ERROR: Could not write savestate {}: {}", savestate_path, e);
+                        }
+                    }
+                    Some(SavestateRequest::Load(slot)) => {
+                        let savestate_path = savestate::rom_to_savestate(current_file_path.clone(), slot);
+                        let state_before = savestate::create(&console);
+                        let result = fs::read(savestate_path.clone())
+                            .and_then(|compressed_state| savestate::decompress(&compressed_state))
+                            .and_then(|state| savestate::restore(&mut console, &state));
+                        match result {
+                            Ok(_) => {
+                                console.smooth_audio_jump();
+                                rumble_event = Some(RumbleEvent::StateLoaded);
+                                undo_state = Some(state_before);
+                                self.can_undo_load.store(true, Ordering::Relaxed);
+                            }
+                            Err(e) => println!("ERROR: Could not load savestate {}: {}", savestate_path, e)
+                        }
+                    }
+                    Some(SavestateRequest::UndoLoad) => {
+                        if let Some(state) = undo_state.take() {
+                            let state_before = savestate::create(&console);
+                            match savestate::restore(&mut console, &state) {
+                                Ok(_) => {
+                                    console.smooth_audio_jump();
+                                    undo_state = Some(state_before);
+                                }
+                                Err(e) => println!("ERROR: Could not undo savestate load: {}", e)
+                            }
+                        }
+                        self.can_undo_load.store(undo_state.is_some(), Ordering::Relaxed);
+                    }
+                    Some(SavestateRequest::Reset) => {
+                        let state_before = savestate::create(&console);
+                        let cartridge_ram = console.cartridge_ram().map(<[u8]>::to_vec);
+                        match savestate::restore(&mut console, &power_on_state) {
+                            Ok(_) => {
+                                //Restoring rolled the RAM back to when the rom was opened, which would lose the battery save
+                                for (offset, value) in cartridge_ram.into_iter().flatten().enumerate() {
+                                    console.write_cartridge_ram(offset, value);
+                                }
+                                console.smooth_audio_jump();
+                                undo_state = Some(state_before);
+                                self.can_undo_load.store(true, Ordering::Relaxed);
+                            }
+                            Err(e) => println!("ERROR: Could not reset: {}", e)
+                        }
+                    }
+                    None => {}
+                }
+            }
+            if let Some(swap_path) = self.cartridge_swap_request.lock().unwrap().take() {
+                match Self::swap_cartridge(&mut console, &swap_path) {
+                    Ok((swap_info, swap_file_path)) => {
+                        game_id = input::game_id(&swap_info.title, swap_info.global_checksum);
+                        *self.rom_info.lock().unwrap() = Some(swap_info);
+                        *self.rom_file_path.lock().unwrap() = Some(swap_path);
+                        self.cheats.lock().unwrap().open_rom(swap_file_path.clone());
+                        current_camera_image_path = None; //The new cartridge starts with the test pattern
+                        current_file_path = swap_file_path;
+                        frame.request_repaint();
+                    }
+                    Err(e) => {
+                        println!("ERROR: Could not swap in {}: {}", swap_path, e);
+                        *self.load_error.lock().unwrap() = Some((swap_path, e.to_string()));
+                        frame.request_repaint();
+                    }
+                }
+            }
+            if let Some(request) = self.frame_dump_request.lock().unwrap().take() {
+                frame_dumper = Some(FrameDumper::new(&request, self.frame_dump_queue.clone()));
+            }
+            {
+                let lock = self.state_history_settings.lock().unwrap();
+                if *lock != current_state_history_settings {
+                    current_state_history_settings = *lock;
+                    state_history = StateHistory::new(current_state_history_settings);
+                    frames_since_capture = 0;
+                }
+                drop(lock);
+
+                //Each frame held steps back one state, then plays the frame after it so there's something to show
+                let keyboard = if self.is_kiosk {None} else {Some(&frame)};
+                let is_rewinding = current_state_history_settings.enabled && self.is_focused.load(Ordering::Relaxed)
+                    && input_config.buttons.rewind.get_state(keyboard, &gamepad_state);
+                if is_rewinding {
+                    if let Some(state) = state_history.pop() {
+                        match savestate::restore(&mut console, &state) {
+                            Ok(_) => console.smooth_audio_jump(),
+                            Err(e) => println!("ERROR: Could not rewind: {}", e)
+                        }
+                    }
+                    frames_since_capture = 0;
+                }
+                else if current_state_history_settings.enabled {
+                    frames_since_capture += 1;
+                    if frames_since_capture >= current_state_history_settings.capture_interval {
+                        frames_since_capture = 0;
+                        state_history.push(savestate::create(&console));
+                    }
+                }
+                *self.state_history_usage.lock().unwrap() = (state_history.state_count(), state_history.memory_usage());
+            }
+
+            {
+                let lock = self.input_profiles.lock().unwrap();
+                let new_input_config: &InputConfig = lock.for_game(&game_id);
+                if *new_input_config != input_config {
+                    input_config = new_input_config.clone();
+                }
+                drop(lock);
+
+                //Input is sampled once per frame rather than whenever the game reads P1, so the same
+                //inputs always give the same result no matter how the host schedules the UI thread
+                if let Some(gilrs) = &mut gilrs {
+                    gamepad_state = GamepadState::poll(gilrs, &input_config.stick);
+                }
+
+                if let Some(index) = self.macro_play_request.lock().unwrap().take() {
+                    macro_player = self.input_macros.lock().unwrap().get(&game_id)
+                        .and_then(|game_macros| game_macros.get(index))
+                        .map(MacroPlayer::new);
+                }
+                if self.is_recording_macro.load(Ordering::Relaxed) != macro_recorder.is_some() {
+                    match macro_recorder.take() {
+                        Some(recorder) => {
+                            let mut input_macros = self.input_macros.lock().unwrap();
+                            let game_macros = input_macros.entry(game_id.clone()).or_default();
+                            let name = tr_args("macros.default_name", &[("number", &(game_macros.len() + 1))]);
+                            game_macros.extend(recorder.finish(name));
+                        }
+                        None => macro_recorder = Some(MacroRecorder::default()),
+                    }
+                }
+
+                //Background tabs keep running but don't react to the keyboard or gamepad. A playing macro replaces the input either way
+                let keyboard = if self.is_kiosk {None} else {Some(&frame)};
+                let sampled_joypad = if is_focused {input_config.buttons.sample(keyboard, &gamepad_state)} else {JoypadState::default()};
+                if self.is_kiosk && kiosk_exit.update(&sampled_joypad) {
+                    frame.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+                let macro_joypad = macro_player.as_mut().and_then(|player| player.next());
+                if macro_joypad.is_none() {
+                    macro_player = None;
+                }
+                self.is_playing_macro.store(macro_player.is_some(), Ordering::Relaxed);
+                let joypad = macro_joypad.unwrap_or(sampled_joypad);
+                if let Some(recorder) = &mut macro_recorder {
+                    recorder.push(joypad);
+                }
+                let joypad = script.as_ref().map_or(joypad, |script| script.apply_input(joypad));
+                console.set_joypad_state(joypad);
+                let (tilt_x, tilt_y) = if is_focused {input_config.buttons.sample_tilt(keyboard, &gamepad_state)} else {(0.0, 0.0)};
+                console.set_tilt(tilt_x, tilt_y);
+
+                if is_focused {
+                    let rumble_settings = self.rumble_settings.lock().unwrap();
+                    if let Some(watch) = rumble_settings.watches.get(&game_id) {
+                        if rumble.check_watch(watch, console.read_bulk(watch.address, watch.address)[0]) {
+                            rumble_event = Some(RumbleEvent::WatchTriggered);
+                        }
+                    }
+                    if let (Some(gilrs), Some(event)) = (&mut gilrs, rumble_event.take()) {
+                        if !matches!(event, RumbleEvent::StateLoaded) || rumble_settings.on_state_load {
+                            rumble.pulse(gilrs, &rumble_settings, event);
+                        }
+                    }
+                    drop(rumble_settings);
+                }
+                else {
+                    rumble_event = None;
+                }
+            }
+            let palette = self.palette_profiles.lock().unwrap().for_game(&game_id);
+            console.break_on_unknown_io = self.break_on_unknown_io.load(Ordering::Relaxed);
+            console.is_strict = self.strict_mode.load(Ordering::Relaxed);
+            console.is_ppu_frozen = self.freeze_ppu.load(Ordering::Relaxed);
+            console.is_apu_frozen = self.freeze_apu.load(Ordering::Relaxed);
+            if self.apu_logging_enabled.load(Ordering::Relaxed) != apu_logger.is_some() {
+                apu_logger = if apu_logger.is_none() {
+                    let apu_log_path = apu_log::rom_to_apu_log(current_file_path.clone());
+                    ApuEventLogger::new(&apu_log_path).inspect_err(|e| {
+                        *self.log_error.lock().unwrap() = Some(tr_args("error.create", &[("path", &apu_log_path), ("error", &e)]));
+                        self.apu_logging_enabled.store(false, Ordering::Relaxed);
+                    }).ok()
+                }
+                else {
+                    None
+                };
+                console.set_apu_event_logging(apu_logger.is_some());
+            }
+            if self.blocked_access_logging.load(Ordering::Relaxed) != blocked_logger.is_some() {
+                blocked_logger = if blocked_logger.is_none() {
+                    let blocked_log_path = blocked_log::rom_to_blocked_log(current_file_path.clone());
+                    BlockedAccessLogger::new(&blocked_log_path).inspect_err(|e| {
+                        *self.log_error.lock().unwrap() = Some(tr_args("error.create", &[("path", &blocked_log_path), ("error", &e)]));
+                        self.blocked_access_logging.store(false, Ordering::Relaxed);
+                    }).ok()
+                }
+                else {
+                    None
+                };
+                console.set_blocked_access_logging(blocked_logger.is_some());
+            }
+            let script_path = self.script_path.lock().unwrap().clone();
+            if script_path != current_script_path || self.script_reload.swap(false, Ordering::Relaxed) {
+                current_script_path = script_path;
+                script = None;
+                console.set_write_hooks(Vec::new());
+                console.set_raster_hooks(None);
+                self.script_overlay.lock().unwrap().clear();
+                if let Some(path) = &current_script_path {
+                    let loaded = fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|source| Script::load(&source, &mut console));
+                    *self.script_status.lock().unwrap() = match &loaded {
+                        Ok(_) => tr_args("debugger.script_running", &[("script", &path.file_name().unwrap_or_default().to_string_lossy())]),
+                        Err(e) => e.clone(),
+                    };
+                    script = loaded.ok();
+                    console.set_raster_hooks(script.as_ref().map(Script::raster_hooks));
+                    self.run_script(&mut script, &mut console, |_, _| Ok(()));
+                }
+            }
+            let trace_mode = *self.instruction_trace_mode.lock().unwrap();
+            if trace_mode != current_trace_mode {
+                let instruction_trace = match trace_mode {
+                    TraceMode::Off => None,
+                    TraceMode::File => {
+                        let trace_log_path = trace_log::rom_to_trace_log(current_file_path.clone());
+                        InstructionTrace::to_file(Path::new(&trace_log_path))
+                            .inspect_err(|e| println!("ERROR: Could not create {}: {}", trace_log_path, e)).ok()
+                    }
+                    TraceMode::RingBuffer => Some(InstructionTrace::ring_buffer(trace_log::RING_BUFFER_LINES)),
+                };
+                console.set_instruction_trace(instruction_trace);
+                current_trace_mode = trace_mode;
+            }
+
+            if self.flicker_blending.load(Ordering::Relaxed) != effects.flicker_blender.is_some() {
+                effects.flicker_blender = if effects.flicker_blender.is_none() {Some(FlickerBlender::default())} else {None};
+            }
+            let irq_flash_settings = *self.irq_flash.lock().unwrap();
+            if irq_flash_settings.enabled != effects.irq_flash.is_some() {
+                effects.irq_flash = if irq_flash_settings.enabled {Some(IrqFlash::new(irq_flash_settings))} else {None};
+                console.set_interrupt_logging(irq_flash_settings.enabled);
+            }
+            if let Some(irq_flash) = &mut effects.irq_flash {
+                irq_flash.settings = irq_flash_settings;
+            }
+
+            console.set_enabled_audio_channels(*self.audio_channels.lock().unwrap());
+            if self.audio_visualizer_enabled.load(Ordering::Relaxed) != is_sample_tap_enabled {
+                is_sample_tap_enabled = !is_sample_tap_enabled;
+                console.set_audio_sample_tap(is_sample_tap_enabled);
+                self.audio_visualizer.lock().unwrap().clear();
+            }
+            if self.memory_heatmap_enabled.load(Ordering::Relaxed) != is_access_counting_enabled {
+                is_access_counting_enabled = !is_access_counting_enabled;
+                console.set_access_counting(is_access_counting_enabled);
+                self.memory_heatmap.lock().unwrap().clear();
+            }
+
+            let saved_debug_session = self.debug_sessions.lock().unwrap().get(&game_id).cloned().unwrap_or_default();
+            if saved_debug_session != debug_session.session {
+                debug_session = ResolvedSession::new(saved_debug_session, std::mem::take(&mut debug_session));
+                console.set_watchpoints(debug_session.watchpoints.clone());
+            }
+            self.run_monitor_commands(&mut console, &debug_session);
+            self.update_gdb_server(&mut gdb_server);
+            //gdb expects the CPU to stop between instructions, so its pauses go in as a step
+            if let Some(gdb_stub) = &mut gdb_server.stub {
+                if gdb_stub.poll(&mut console) == Some(GdbRequest::Pause) {
+                    *self.step_request.lock().unwrap() = Some(StepRequest::Into);
+                }
+            }
+            if let Some(request) = self.step_request.lock().unwrap().take() {
+                step_target = debug_session.step_target(&request, &mut console, false);
+            }
+
+            let run_ahead_frames = *self.run_ahead_frames.lock().unwrap();
+            //Skipped frames are still run in full, only the screen isn't updated. Never while something needs every
+            //frame's pixels
+            let max_frame_skip = (*self.frame_skip.lock().unwrap()).min(pacing::MAX_FRAME_SKIP);
+            let is_skipping_frame = is_behind && skipped_frames < max_frame_skip && run_ahead_frames == 0
+                                    && frame_dumper.is_none() && !desync_detector.is_hashing();
+            skipped_frames = if is_skipping_frame {skipped_frames + 1} else {0};
+            console.set_frame_skip(is_skipping_frame);
+
+            if run_ahead_frames > 0 {
+                let state = savestate::create(&console);
+                //Scripts don't run on speculative frames, so neither do their scanline hooks. The trace and the
+                //heatmap would count the same instructions twice
+                let raster_hooks = console.set_raster_hooks(None);
+                let instruction_trace = console.set_instruction_trace(None);
+                let access_counters = console.set_access_counters(None);
+                self.run_ahead(&frame, &mut console, &palette, &mut effects, run_ahead_frames + 1);
+                console.set_access_counters(access_counters);
+                console.set_instruction_trace(instruction_trace);
+                console.set_raster_hooks(raster_hooks);
+                //The console is left wherever the speculative frames took it, so run-ahead is turned off rather than
+                //risk the same thing next frame
+                if let Err(e) = savestate::restore(&mut console, &state) {
+                    println!("ERROR: Could not roll back after running ahead, turning run-ahead off: {}", e);
+                    *self.run_ahead_frames.lock().unwrap() = 0;
+                }
+                //Speculative frames will be played again for real
+                console.take_apu_events();
+                console.take_io_break();
+                console.take_boot_error();
+                console.take_watch_log();
+                console.take_blocked_accesses();
+                console.take_hooked_writes();
+            }
+
+            for _scanline in 0..154 {
+                //A scanline is 456 dots, however many M-cycles the CPU gets through in that time
+                for _cycle in 0..(456 / console.dots_per_cycle() as u32) {
+                    //Breakpoints and steps stop before the instruction runs. Resuming from a breakpoint doesn't stop on
+                    //it again right away
+                    let gdb_breakpoints = gdb_server.stub.as_ref().map(GdbStub::breakpoints);
+                    if console.is_between_instructions() && (step_target.is_some() || !debug_session.breakpoints.is_empty() || gdb_breakpoints.is_some_and(|breakpoints| !breakpoints.is_empty())) {
+                        let program_counter = console.program_counter();
+                        let mut break_reason = None;
+                        if last_break_address != Some(program_counter) {
+                            last_break_address = None;
+                            if debug_session.breakpoints.contains(&program_counter) {
+                                last_break_address = Some(program_counter);
+                                break_reason = Some(tr_args("debugger.break_at", &[("address", &debug_session.describe(program_counter))]));
+                            }
+                            else if gdb_breakpoints.is_some_and(|breakpoints| breakpoints.contains(&program_counter)) {
+                                last_break_address = Some(program_counter);
+                                break_reason = Some(tr_args("debugger.gdb_break_at", &[("address", &debug_session.describe(program_counter))]));
+                            }
+                        }
+                        let stack_pointer = console.cpu_registers().stack_pointer;
+                        if step_target.as_mut().is_some_and(|target| target.is_reached(program_counter, stack_pointer)) && break_reason.is_none() {
+                            break_reason = Some(tr_args("debugger.stepped_to", &[("address", &debug_session.describe(program_counter))]));
+                        }
+
+                        if let Some(break_reason) = break_reason {
+                            *self.break_reason.lock().unwrap() = Some(break_reason);
+                            self.paused.store(true, Ordering::Relaxed);
+                            self.debugger_requested.store(true, Ordering::Relaxed);
+                            self.publish_debug_view(&mut console, &debug_session);
+                            let request = self.wait_while_paused(&frame, &mut console, &debug_session, &mut gdb_server);
+                            step_target = request.and_then(|request| debug_session.step_target(&request, &mut console, true));
+                            pacer.restart();
+                        }
+                    }
+
+                    console.step_cpu();
+                    if script.is_some() {
+                        let writes = console.take_hooked_writes();
+                        if !writes.is_empty() {
+                            self.run_script(&mut script, &mut console, |script, console| {
+                                writes.into_iter().try_for_each(|(address, value)| script.on_memory_write(address, value, console))
+                            });
+                        }
+                    }
+
+                    if let Some(io_break) = console.take_io_break() {
+                        *self.break_reason.lock().unwrap() = Some(io_break.to_string());
+                        self.paused.store(true, Ordering::Relaxed);
+                        self.debugger_requested.store(true, Ordering::Relaxed);
+                    }
+                    //A boot ROM that hands over the wrong state is stopped on, like an unknown register
+                    if let Some(boot_error) = console.take_boot_error() {
+                        *self.break_reason.lock().unwrap() = Some(boot_error);
+                        self.paused.store(true, Ordering::Relaxed);
+                        self.debugger_requested.store(true, Ordering::Relaxed);
+                    }
+                    if self.paused.load(Ordering::Relaxed) {
+                        self.publish_debug_view(&mut console, &debug_session);
+                        let request = self.wait_while_paused(&frame, &mut console, &debug_session, &mut gdb_server);
+                        step_target = request.and_then(|request| debug_session.step_target(&request, &mut console, false));
+                        pacer.restart();
+                    }
+
+                    let events = console.step_hardware();
+                    if script.as_ref().is_some_and(Script::is_scanline_drawn) {
+                        self.run_script(&mut script, &mut console, Script::on_scanlines);
+                    }
+                    if events.is_new_frame {
+                        if script.is_some() {
+                            self.run_script(&mut script, &mut console, Script::on_frame);
+                            *self.script_overlay.lock().unwrap() = script.as_ref().map(Script::overlay).unwrap_or_default();
+                        }
+                        let internal_screen = console.dump_screen();
+                        if let Some(irq_flash) = &mut effects.irq_flash {
+                            irq_flash.add_frame(&console.take_interrupt_events());
+                        }
+                        //With run-ahead the speculative frame has already been drawn
+                        if run_ahead_frames == 0 && !is_dropping_frame && !is_skipping_frame {
+                            self.draw_new_frame(&frame, &console, &internal_screen, &palette, &mut effects);
+                        }
+
+                        if let Some(dumper) = &mut frame_dumper {
+                            let is_dumping = dumper.on_frame(frame_count, &Self::screen_colors(&console, &internal_screen, &palette));
+                            *self.frame_dump_progress.lock().unwrap() = if is_dumping {Some(dumper.frames_left())} else {None};
+                            if !is_dumping {
+                                frame_dumper = None;
+                            }
+                        }
+
+                        if let Some(desync_frame) = desync_detector.check_frame(frame_count, desync::hash_frame(&internal_screen)) {
+                            *self.desync_frame.lock().unwrap() = Some(desync_frame);
+                        }
+
+                        let watch_log = console.take_watch_log();
+                        if !watch_log.is_empty() && !is_watch_log_failed {
+                            let watch_log_path = watch_log::rom_to_watch_log(current_file_path.clone());
+                            let result = match watch_logger.as_mut() {
+                                Some(watch_logger) => watch_logger.write_entries(frame_count, &watch_log),
+                                None => WatchLogger::new(&watch_log_path).and_then(|new_logger| watch_logger.insert(new_logger).write_entries(frame_count, &watch_log)),
+                            };
+                            if let Err(e) = result {
+                                *self.log_error.lock().unwrap() = Some(tr_args("error.write", &[("path", &watch_log_path), ("error", &e)]));
+                                watch_logger = None;
+                                is_watch_log_failed = true;
+                            }
+                        }
+                        if let Some(logger) = &mut blocked_logger {
+                            if let Err(e) = logger.write_entries(frame_count, &console.take_blocked_accesses()) {
+                                *self.log_error.lock().unwrap() = Some(tr_args("error.write", &[("path", &blocked_log::rom_to_blocked_log(current_file_path.clone())), ("error", &e)]));
+                                self.blocked_access_logging.store(false, Ordering::Relaxed);
+                                blocked_logger = None;
+                                console.set_blocked_access_logging(false);
+                            }
+                        }
+                        frame_count += 1;
+
+                        if self.debug_mode.load(Ordering::Relaxed) {
+                            *self.ppu_snapshot.lock().unwrap() = Some(console.ppu_snapshot());
+                        }
+                        if self.disassembly_enabled.load(Ordering::Relaxed) {
+                            *self.disassembly.lock().unwrap() = debug_session.disassembly(&mut console);
+                        }
+                        if self.memory_editor_enabled.load(Ordering::Relaxed) {
+                            self.memory_editor.lock().unwrap().sync(&mut console);
+                        }
+
+                        if self.memory_view_enabled.load(Ordering::Relaxed) {
+                            let memory_view_path = memory_view::rom_to_memory_view(current_file_path.clone());
+                            let result = match memory_view.as_mut() {
+                                Some(memory_view) => memory_view.update(&mut console),
+                                None => MemoryView::new(&memory_view_path).and_then(|new_view| memory_view.insert(new_view).update(&mut console)),
+                            };
+                            if let Err(e) = result {
+                                *self.log_error.lock().unwrap() = Some(tr_args("error.write", &[("path", &memory_view_path), ("error", &e)]));
+                                self.memory_view_enabled.store(false, Ordering::Relaxed);
+                                memory_view = None;
+                            }
+                        }
+                        else {
+                            memory_view = None;
+                        }
+                        
+                        if false {
+                            println!("{:?}", Instant::now() - frame_time);
+                        }
+                        frame_time = Instant::now();
+                    }
+
+                    if let Some(serial_output) = events.serial_byte {
+                        console_output.push((serial_output as char).to_ascii_uppercase());
+                    }
+                }
+            }
+            is_behind = pacer.wait(sync_mode, console.queued_audio(), speed);
+
+            if let Some(logger) = &mut apu_logger {
+                let (events, elapsed_cycles) = console.take_apu_events();
+                if let Err(e) = logger.write_events(&events, elapsed_cycles) {
+                    *self.log_error.lock().unwrap() = Some(tr_args("error.write", &[("path", &apu_log::rom_to_apu_log(current_file_path.clone())), ("error", &e)]));
+                    self.apu_logging_enabled.store(false, Ordering::Relaxed);
+                    apu_logger = None;
+                    console.set_apu_event_logging(false);
+                }
+            }
+
+            if is_sample_tap_enabled {
+                let samples = console.take_audio_samples();
+                let mut lock = self.audio_visualizer.lock().unwrap();
+                lock.sample_rate = console.audio_sample_rate();
+                lock.push(&samples);
+                drop(lock);
+            }
+
+            self.cart_ram.sync(&mut console);
+            self.publish_debug_view(&mut console, &debug_session);
+
+            if let Some(access_counters) = console.access_counters() {
+                self.memory_heatmap.lock().unwrap().add_frame(access_counters);
+                access_counters.clear();
+            }
+
+            //A frame is dropped by running the next one without waiting or drawing it, and duplicated by waiting a
+            //frame longer. Either way the audio queued for the device shrinks or grows by a frame
+            is_dropping_frame = false;
+            let is_correcting = self.av_sync_correction.load(Ordering::Relaxed) && !is_fast_forwarding;
+            match self.av_sync.lock().unwrap().take_correction(is_correcting) {
+                Some(Correction::DropFrame) => {
+                    pacer.drop_frame();
+                    is_dropping_frame = true;
+                }
+                Some(Correction::DuplicateFrame) => pacer.duplicate_frame(),
+                None => {}
+            }
+
+            if let Some(device_change) = console.take_audio_device_change() {
+                self.av_sync.lock().unwrap().reset();
+                *self.audio_device_notice.lock().unwrap() = Some(device_change);
+                frame.request_repaint();
+            }
+
+            print!("{}", console_output);
+            self.console_output.lock().unwrap().push_str(&console_output);
+            console_output.clear();
+        }
+    }
+
+    //Runs whatever was typed into the monitor since the last frame
+    fn run_monitor_commands(&self, console: &mut GBConsole, debug_session: &ResolvedSession) {
+        let mut monitor = self.monitor.lock().unwrap();
+        for command in std::mem::take(&mut monitor.pending) {
+            match monitor::run(&command, console, &debug_session.symbols) {
+                Ok(lines) => monitor.print(lines),
+                Err(e) => monitor.print([tr_args("monitor.error", &[("error", &e)])]),
+            }
+        }
+    }
+
+    fn publish_debug_view(&self, console: &mut GBConsole, debug_session: &ResolvedSession) {
+        let mut view = debug_session.view.clone();
+        view.watch_values = debug_session.watches.iter()
+            .map(|address| address.map(|address| (address, console.read_bulk(address, address)[0])))
+            .collect();
+        *self.debug_view.lock().unwrap() = view;
+        if self.disassembly_enabled.load(Ordering::Relaxed) {
+            *self.disassembly.lock().unwrap() = debug_session.disassembly(console);
+        }
+        if self.memory_editor_enabled.load(Ordering::Relaxed) {
+            self.memory_editor.lock().unwrap().sync(console);
+        }
+        *self.instruction_trace_lines.lock().unwrap() = console.instruction_trace().map(InstructionTrace::lines).unwrap_or_default();
+    }
+
+    //Blocks the emulation thread until the UI resumes it, publishing the CPU state for the debugger. Returns the step
+    //it was resumed with, if any
+    fn wait_while_paused(&self, frame: &egui::Context, console: &mut GBConsole, debug_session: &ResolvedSession, gdb_server: &mut GdbServer) -> Option<StepRequest> {
+        *self.cpu_registers.lock().unwrap() = Some(console.cpu_registers());
+        console.fade_out_audio();
+        frame.request_repaint();
+        if let Some(gdb_stub) = &mut gdb_server.stub {
+            gdb_stub.report_stop();
+        }
+
+        //Monitor commands, memory edits and gdb still run while paused, which is when they're most useful
+        loop {
+            self.wake.wait_while(|| self.paused.load(Ordering::Relaxed) && !self.is_closed.load(Ordering::Relaxed) && self.monitor.lock().unwrap().pending.is_empty() && !self.memory_editor.lock().unwrap().is_stale
+                                    && *self.gdb_server_port.lock().unwrap() == gdb_server.port && !gdb_server.stub.as_ref().is_some_and(GdbStub::has_input));
+            if !self.paused.load(Ordering::Relaxed) || self.is_closed.load(Ordering::Relaxed) {
+                break;
+            }
+            self.update_gdb_server(gdb_server);
+            if let Some(gdb_stub) = &mut gdb_server.stub {
+                match gdb_stub.poll(console) {
+                    Some(GdbRequest::Continue) => self.paused.store(false, Ordering::Relaxed),
+                    Some(GdbRequest::Step) => {
+                        *self.step_request.lock().unwrap() = Some(StepRequest::Into);
+                        self.paused.store(false, Ordering::Relaxed);
+                    }
+                    _ => (),
+                }
+            }
+            self.run_monitor_commands(console, debug_session);
+            let mut memory_editor = self.memory_editor.lock().unwrap();
+            if memory_editor.is_stale {
+                memory_editor.sync(console);
+            }
+            drop(memory_editor);
+            self.publish_debug_view(console, debug_session);
+            *self.cpu_registers.lock().unwrap() = Some(console.cpu_registers());
+            frame.request_repaint();
+        }
+
+        *self.cpu_registers.lock().unwrap() = None;
+        *self.break_reason.lock().unwrap() = None;
+        self.step_request.lock().unwrap().take()
+    }
+
+    //Runs a hook and keeps the console's write hooks up to date. The first error stops the script, and is left where
+    //the UI shows it
+    fn run_script(&self, script: &mut Option<Script>, console: &mut GBConsole, hook: impl FnOnce(&mut Script, &mut GBConsole) -> Result<(), String>) {
+        let Some(running) = script else {
+            return;
+        };
+        match hook(running, console) {
+            Ok(()) => {
+                if running.take_write_hooks_changed() {
+                    console.set_write_hooks(running.write_ranges());
+                }
+            }
+            Err(e) => {
+                *self.script_status.lock().unwrap() = e;
+                *script = None;
+                console.set_write_hooks(Vec::new());
+                console.set_raster_hooks(None);
+                self.script_overlay.lock().unwrap().clear();
+            }
+        }
+    }
+
+    //Starts, moves or stops the GDB server to match the setting, and publishes how it's doing
+    fn update_gdb_server(&self, gdb_server: &mut GdbServer) {
+        let port = *self.gdb_server_port.lock().unwrap();
+        if port != gdb_server.port {
+            gdb_server.port = port;
+            gdb_server.stub = None;
+            if let Some(port) = port {
+                let wake = self.wake.clone();
+                match GdbStub::listen(port, move || wake.wake()) {
+                    Ok(gdb_stub) => gdb_server.stub = Some(gdb_stub),
+                    Err(e) => *self.gdb_server_status.lock().unwrap() = tr_args("debugger.gdb_listen_failed", &[("port", &port), ("error", &e)]),
+                }
+            }
+        }
+        if let Some(gdb_stub) = &gdb_server.stub {
+            *self.gdb_server_status.lock().unwrap() = match gdb_stub.peer() {
+                Some(peer) => tr_args("debugger.gdb_connected", &[("peer", &peer)]),
+                None => tr_args("debugger.gdb_listening", &[("port", &gdb_stub.port())]),
+            };
+        }
+    }
+
+    //Runs frames unpaced and without audio, drawing the last one so the screen shows where the game
+    //will be a few frames from now. The caller rolls the console back afterwards
+    fn run_ahead(&self, frame: &egui::Context, console: &mut GBConsole, palette: &PaletteSettings, effects: &mut FrameEffects, frames: u8) {
+        //Keep the link cable out of it since a real device can't be rolled back
+        let serial_device = console.set_serial_device(Box::new(Disconnected));
+        let was_audio_enabled = console.is_audio_output_enabled();
+        console.set_audio_output(false);
+
+        for i in 0..frames {
+            for _cycle in 0..(154 * 456 / console.dots_per_cycle() as u32) {
+                if console.step().is_new_frame {
+                    if let Some(irq_flash) = &mut effects.irq_flash {
+                        irq_flash.add_frame(&console.take_interrupt_events());
+                    }
+                    if i == frames - 1 {
+                        let internal_screen = console.dump_screen();
+                        self.draw_new_frame(frame, console, &internal_screen, palette, effects);
+                    }
+                }
+            }
+        }
+
+        console.set_audio_output(was_audio_enabled);
+        console.set_serial_device(serial_device);
+    }
+
+    //Sends the finished frame to the UI
+    fn draw_new_frame(&self, frame: &egui::Context, console: &GBConsole, internal_screen: &[[Pixel; 160]; 144], palette: &PaletteSettings, effects: &mut FrameEffects) {
+        let mut colors = Self::screen_colors(console, internal_screen, palette);
+        if self.magnifier_enabled.load(Ordering::Relaxed) {
+            *self.raw_frame.lock().unwrap() = Some(RawFrame { pixels: Box::new(*internal_screen), colors: colors.clone() });
+        }
+        if let Some(blender) = &mut effects.flicker_blender {
+            blender.apply(internal_screen, &mut colors);
+        }
+        if let Some(irq_flash) = &effects.irq_flash {
+            irq_flash.apply(&mut colors);
+        }
+        let pixel_colors = ScreenPixel::from_colors(&colors);
+        self.av_sync.lock().unwrap().new_frame(console.output_cycle(), console.last_played_audio());
+
+        let mut shades = Box::new([[0; 160]; 144]);
+        for i in 0..144 {
+            for j in 0..160 {
+                let shade_register = match internal_screen[i][j].palette {
+                    None => console.dmg_bg_pallette,
+                    Some(0) => console.dmg_obj_pallette_0,
+                    Some(_) => console.dmg_obj_pallette_1,
+                };
+                shades[i][j] = match internal_screen[i][j].rgb {
+                    Some(rgb) => frame_export::cgb_shade(rgb),
+                    None => (shade_register >> (internal_screen[i][j].color * 2)) & 0b11,
+                };
+            }
+        }
+    
+        {
+            let mut lock = self.screen_pixels.lock().unwrap();
+            *lock = Some(pixel_colors);
+            drop(lock);
+            *self.screen_shades.lock().unwrap() = Some(shades);
+        }
+        if !self.is_minimized.load(Ordering::Relaxed) {
+            frame.request_repaint();
+        }
+    }
+
+    fn screen_colors(console: &GBConsole, internal_screen: &[[Pixel; 160]; 144], palette: &PaletteSettings) -> Box<ScreenColors> {
+        let bg_pallette = palette.background_colors(console.dmg_bg_pallette);
+        let obj0_pallette = palette.sprite_colors(console.dmg_obj_pallette_0);
+        let obj1_pallette = palette.sprite_colors(console.dmg_obj_pallette_1);
+
+        let mut colors = Box::new([[Color32::PLACEHOLDER; 160]; 144]);
+        for (row, pixels) in colors.iter_mut().zip(internal_screen.iter()) {
+            for (color, pixel) in row.iter_mut().zip(pixels.iter()) {
+                *color = match (pixel.rgb, pixel.palette) {
+                    (Some(rgb), _) => {
+                        let [r, g, b] = frame_export::cgb_rgb(rgb, ColorSpace::Raw);
+                        Color32::from_rgb(r, g, b)
+                    }
+                    (None, None) => bg_pallette[pixel.color as usize],
+                    (None, Some(0)) => obj0_pallette[pixel.color as usize],
+                    (None, Some(_)) => obj1_pallette[pixel.color as usize],
+                };
+            }
+        }
+        colors
+    }
+}
+
+//The port the GDB server was asked to listen on, and the stub if that worked
+#[derive(Default)]
+struct GdbServer {
+    port: Option<u16>,
+    stub: Option<GdbStub>,
+}
+
+//Debugging and enhancement passes over finished frames, applied only to what's shown
+#[derive(Default)]
+struct FrameEffects {
+    flicker_blender: Option<FlickerBlender>,
+    irq_flash: Option<IrqFlash>,
+}
+
+#[derive(Clone)]
+pub struct ScreenPixel {
+    color: Color32,
+    x: f32,
+    y: f32,
+    width: f32,
+}
+
+impl ScreenPixel {
+    //Turns a frame into one rect per run of same colored pixels in each row, which is far fewer shapes to paint
+    pub fn from_colors(colors: &ScreenColors) -> Vec<ScreenPixel> {
+        let mut pixel_colors = Vec::new();
+        for (i, row) in colors.iter().enumerate() {
+            let mut pixel_chunk = ScreenPixel { color: Color32::PLACEHOLDER, x: -1.0, y: -1.0, width: 0.0};
+            for (j, pixel_color) in row.iter().enumerate() {
+                if *pixel_color != pixel_chunk.color {
+                    if pixel_chunk.width > 0.0 {
+                        pixel_colors.push(pixel_chunk.clone());
+                    }
+                    pixel_chunk.color = *pixel_color;
+                    pixel_chunk.width = 0.0;
+                    pixel_chunk.x = j as f32;
+                    pixel_chunk.y = i as f32;
+                }
+                pixel_chunk.width += 1.0;
+            }
+            if pixel_chunk.width > 0.0 {
+                pixel_colors.push(pixel_chunk.clone());
+            }
+        }
+        pixel_colors
+    }
+
+    pub fn to_rect(&self, game_height: f32, game_width: f32, y_offset: f32, x_offset: f32) -> egui::epaint::RectShape {
+        let pixel_width = game_width / 160.0;
+        let pixel_height = game_height / 144.0;
+    
+        let min_x = x_offset + (pixel_width * self.x);
+        let min_y = y_offset + (pixel_height * self.y);
+    
+        let max_x = min_x + (pixel_width * self.width) + 1.0;
+        let max_y = min_y + pixel_height + 1.0;
+        
+        egui::epaint::RectShape::new(
+            egui::Rect {
+                min: egui::Pos2::new(min_x, min_y),
+                max: egui::Pos2::new(max_x, max_y)
+            },
+            egui::Rounding::ZERO,
+            self.color,
+            egui::Stroke::NONE
+        )
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use gilrs::{Axis, Gilrs};
+use serde::{Deserialize, Serialize};
+
+use super::joypad::JoypadState;
+
+pub const INPUT_PROFILES_KEY: &str = "input_profiles";
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ButtonList {
+    pub up: Binding,
+    pub down: Binding,
+    pub left: Binding,
+    pub right: Binding,
+    pub start: Binding,
+    pub select: Binding,
+    pub a: Binding,
+    pub b: Binding,
+    #[serde(default = "ButtonList::default_rewind")]
+    pub rewind: Binding, //Steps back through the state history while held
+    #[serde(default = "ButtonList::default_fast_forward")]
+    pub fast_forward: Binding,
+    //Tilting the console, for cartridges with an accelerometer
+    #[serde(default = "ButtonList::default_tilt_up")]
+    pub tilt_up: Binding,
+    #[serde(default = "ButtonList::default_tilt_down")]
+    pub tilt_down: Binding,
+    #[serde(default = "ButtonList::default_tilt_left")]
+    pub tilt_left: Binding,
+    #[serde(default = "ButtonList::default_tilt_right")]
+    pub tilt_right: Binding,
+}
+
+impl Default for ButtonList {
+    fn default() -> Self {
+        Self { 
+            up: Binding::new(KeyType::Key(egui::Key::ArrowUp), gilrs::Button::DPadUp), 
+            down: Binding::new(KeyType::Key(egui::Key::ArrowDown), gilrs::Button::DPadDown), 
+            left: Binding::new(KeyType::Key(egui::Key::ArrowLeft), gilrs::Button::DPadLeft), 
+            right: Binding::new(KeyType::Key(egui::Key::ArrowRight), gilrs::Button::DPadRight), 
+            start: Binding::new(KeyType::Key(egui::Key::Enter), gilrs::Button::Start), 
+            select: Binding::new(KeyType::Modifier(egui::Modifiers::SHIFT), gilrs::Button::Select), 
+            a: Binding::new(KeyType::Key(egui::Key::Z), gilrs::Button::East), 
+            b: Binding::new(KeyType::Key(egui::Key::X), gilrs::Button::South),
+            rewind: Self::default_rewind(),
+            fast_forward: Self::default_fast_forward(),
+            tilt_up: Self::default_tilt_up(),
+            tilt_down: Self::default_tilt_down(),
+            tilt_left: Self::default_tilt_left(),
+            tilt_right: Self::default_tilt_right(),
+        }
+    }
+}
+
+impl ButtonList {
+    fn default_rewind() -> Binding {
+        Binding::new(KeyType::Key(egui::Key::Backspace), gilrs::Button::LeftTrigger2)
+    }
+
+    fn default_fast_forward() -> Binding {
+        Binding::new(KeyType::Key(egui::Key::Tab), gilrs::Button::RightTrigger2)
+    }
+
+    //The right stick tilts too, so these are keyboard only
+    fn default_tilt_up() -> Binding {
+        Binding { key: KeyType::Key(egui::Key::I), gamepad_button: None }
+    }
+
+    fn default_tilt_down() -> Binding {
+        Binding { key: KeyType::Key(egui::Key::K), gamepad_button: None }
+    }
+
+    fn default_tilt_left() -> Binding {
+        Binding { key: KeyType::Key(egui::Key::J), gamepad_button: None }
+    }
+
+    fn default_tilt_right() -> Binding {
+        Binding { key: KeyType::Key(egui::Key::L), gamepad_button: None }
+    }
+
+    //Each button with the catalog key for its name
+    pub fn bindings_mut(&mut self) -> [(&'static str, &mut Binding); 14] {
+        [
+            ("controls.up", &mut self.up),
+            ("controls.down", &mut self.down),
+            ("controls.left", &mut self.left),
+            ("controls.right", &mut self.right),
+            ("controls.start", &mut self.start),
+            ("controls.select", &mut self.select),
+            ("controls.a", &mut self.a),
+            ("controls.b", &mut self.b),
+            ("controls.rewind", &mut self.rewind),
+            ("controls.fast_forward", &mut self.fast_forward),
+            ("controls.tilt_up", &mut self.tilt_up),
+            ("controls.tilt_down", &mut self.tilt_down),
+            ("controls.tilt_left", &mut self.tilt_left),
+            ("controls.tilt_right", &mut self.tilt_right),
+        ]
+    }
+}
+
+impl ButtonList {
+    //Without a context only the gamepad is read
+    pub fn sample(&self, keyboard: Option<&egui::Context>, gamepad: &GamepadState) -> JoypadState {
+        JoypadState {
+            up: self.up.get_state(keyboard, gamepad) || gamepad.stick_dpad[0],
+            down: self.down.get_state(keyboard, gamepad) || gamepad.stick_dpad[1],
+            left: self.left.get_state(keyboard, gamepad) || gamepad.stick_dpad[2],
+            right: self.right.get_state(keyboard, gamepad) || gamepad.stick_dpad[3],
+            start: self.start.get_state(keyboard, gamepad),
+            select: self.select.get_state(keyboard, gamepad),
+            a: self.a.get_state(keyboard, gamepad),
+            b: self.b.get_state(keyboard, gamepad),
+        }
+    }
+
+    //How far the console is tilted, in g. A held key tilts all the way, and the right stick tilts as far as it's pushed
+    pub fn sample_tilt(&self, keyboard: Option<&egui::Context>, gamepad: &GamepadState) -> (f32, f32) {
+        let axis = |negative: &Binding, positive: &Binding| {
+            positive.get_state(keyboard, gamepad) as i8 as f32 - negative.get_state(keyboard, gamepad) as i8 as f32
+        };
+        let x = axis(&self.tilt_left, &self.tilt_right) + gamepad.right_stick.0;
+        let y = axis(&self.tilt_up, &self.tilt_down) + gamepad.right_stick.1;
+        (x.clamp(-1.0, 1.0), y.clamp(-1.0, 1.0))
+    }
+}
+
+//A Game Boy button can be held from the keyboard or a gamepad
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub key: KeyType,
+    pub gamepad_button: Option<gilrs::Button>,
+}
+
+impl Binding {
+    fn new(key: KeyType, gamepad_button: gilrs::Button) -> Self {
+        Self {
+            key,
+            gamepad_button: Some(gamepad_button),
+        }
+    }
+
+    pub fn get_state(&self, keyboard: Option<&egui::Context>, gamepad: &GamepadState) -> bool {
+        keyboard.is_some_and(|ctx| self.key.get_state(ctx)) || self.gamepad_button.is_some_and(|button| gamepad.pressed.contains(&button))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum KeyType {
+    Key(egui::Key),
+    Modifier(egui::Modifiers),
+}
+
+impl KeyType {
+    pub fn get_state(&self, ctx: &egui::Context) -> bool {
+        match self {
+            Self::Key(key) => ctx.input(|x| x.key_down(*key)),
+            Self::Modifier(modifier) => ctx.input(|x| x.modifiers.matches_logically(*modifier))
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Key(key) => key.name().to_owned(),
+            Self::Modifier(modifier) => format!("{:?}", modifier)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StickSettings {
+    pub enabled: bool,
+    pub dead_zone: f32, //Stick deflection below this is ignored, from 0 to 1
+    //From -1 to 1. At 0 all 8 directions get an equal share of the stick, lower values make
+    //diagonals harder to hit until -1 only allows the 4 cardinal directions
+    pub diagonal_bias: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dead_zone: 0.3,
+            diagonal_bias: 0.0,
+        }
+    }
+}
+
+impl StickSettings {
+    //Returns which of up, down, left and right the stick is held towards
+    pub fn dpad_directions(&self, x: f32, y: f32) -> [bool; 4] {
+        if !self.enabled || (x * x + y * y).sqrt() < self.dead_zone {
+            return [false; 4];
+        }
+
+        //The minor axis only counts once the stick is far enough from the major axis
+        let (major, minor) = if x.abs() >= y.abs() {(x.abs(), y.abs())} else {(y.abs(), x.abs())};
+        let diagonal_threshold = 22.5_f32 * (1.0 - self.diagonal_bias.clamp(-1.0, 1.0));
+        let is_diagonal = minor.atan2(major).to_degrees() >= diagonal_threshold;
+
+        let is_horizontal = x.abs() >= y.abs() || is_diagonal;
+        let is_vertical = y.abs() > x.abs() || is_diagonal;
+        [
+            is_vertical && y > 0.0,
+            is_vertical && y < 0.0,
+            is_horizontal && x < 0.0,
+            is_horizontal && x > 0.0,
+        ]
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct InputConfig {
+    pub buttons: ButtonList,
+    pub stick: StickSettings,
+}
+
+//The default controls, plus overrides for individual games keyed by game_id()
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct InputProfiles {
+    pub default: InputConfig,
+    pub per_game: HashMap<String, InputConfig>,
+}
+
+impl InputProfiles {
+    pub fn for_game(&self, game_id: &str) -> &InputConfig {
+        self.per_game.get(game_id).unwrap_or(&self.default)
+    }
+}
+
+//Titles alone aren't unique, so the global checksum is included
+pub fn game_id(title: &str, global_checksum: u16) -> String {
+    format!("{} {:04X}", title.trim_end_matches('\0'), global_checksum)
+}
+
+#[derive(Clone, Default)]
+pub struct GamepadState {
+    pub pressed: Vec<gilrs::Button>,
+    pub stick_dpad: [bool; 4], //Up, down, left, right
+    pub right_stick: (f32, f32), //With y positive towards the player, like tilting
+}
+
+impl GamepadState {
+    //Reads the first connected gamepad
+    pub fn poll(gilrs: &mut Gilrs, stick: &StickSettings) -> Self {
+        while gilrs.next_event().is_some() {}
+
+        let mut state = Self::default();
+        if let Some((_, gamepad)) = gilrs.gamepads().next() {
+            for button in ALL_GAMEPAD_BUTTONS {
+                if gamepad.is_pressed(button) {
+                    state.pressed.push(button);
+                }
+            }
+            state.stick_dpad = stick.dpad_directions(gamepad.value(Axis::LeftStickX), gamepad.value(Axis::LeftStickY));
+            state.right_stick = (gamepad.value(Axis::RightStickX), -gamepad.value(Axis::RightStickY));
+        }
+
+        state
+    }
+}
+
+pub const ALL_GAMEPAD_BUTTONS: [gilrs::Button; 19] = [
+    gilrs::Button::South,
+    gilrs::Button::East,
+    gilrs::Button::North,
+    gilrs::Button::West,
+    gilrs::Button::C,
+    gilrs::Button::Z,
+    gilrs::Button::LeftTrigger,
+    gilrs::Button::LeftTrigger2,
+    gilrs::Button::RightTrigger,
+    gilrs::Button::RightTrigger2,
+    gilrs::Button::Select,
+    gilrs::Button::Start,
+    gilrs::Button::Mode,
+    gilrs::Button::LeftThumb,
+    gilrs::Button::RightThumb,
+    gilrs::Button::DPadUp,
+    gilrs::Button::DPadDown,
+    gilrs::Button::DPadLeft,
+    gilrs::Button::DPadRight,
+];
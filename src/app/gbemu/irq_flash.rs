@@ -0,0 +1,104 @@
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use super::{frame_dump::ScreenColors, interrupt_log::InterruptEvent};
+
+pub const IRQ_FLASH_KEY: &str = "irq_flash";
+
+pub const SOURCE_NAMES: [&str; 5] = ["irq_flash.vblank", "irq_flash.stat", "irq_flash.timer", "irq_flash.serial", "irq_flash.joypad"]; //Catalog keys
+pub const SOURCE_COLORS: [Color32; 5] = [
+    Color32::from_rgb(0x30, 0x60, 0xFF),
+    Color32::from_rgb(0xFF, 0x30, 0x30),
+    Color32::from_rgb(0x30, 0xD0, 0x30),
+    Color32::from_rgb(0xFF, 0xD0, 0x20),
+    Color32::from_rgb(0xD0, 0x30, 0xD0),
+];
+
+//The lines the PPU goes through in V-blank. Events are taken as V-blank starts, so these come first in each frame
+const VBLANK_LINES: usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IrqFlashSettings {
+    pub enabled: bool,
+    pub sources: [bool; 5],
+    pub strength: f32, //How much of the interrupt's color goes into the line
+}
+
+impl Default for IrqFlashSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sources: [true; 5],
+            strength: 0.5,
+        }
+    }
+}
+
+//Tints the lines an interrupt handler was running on in the color of its interrupt, so it's easy to see where STAT
+//handlers land on the picture. Worked out from the handler entries and returns the console logs each frame
+pub struct IrqFlash {
+    pub settings: IrqFlashSettings,
+    running: Vec<u8>, //Handlers that hadn't returned when the last frame ended, innermost last
+    lines: Box<[u8; 144]>, //A bit for each interrupt whose handler ran during the line
+}
+
+impl IrqFlash {
+    pub fn new(settings: IrqFlashSettings) -> Self {
+        Self {
+            settings,
+            running: Vec::new(),
+            lines: Box::new([0; 144]),
+        }
+    }
+
+    //Called as V-blank starts with everything logged since the last one
+    pub fn add_frame(&mut self, events: &[InterruptEvent]) {
+        let mut lines = [0_u8; 154];
+        let mut position = 0;
+        for event in events {
+            //Frame order, with the V-blank lines of the frame before first
+            let event_position = (event.line as usize + VBLANK_LINES) % 154;
+            let event_position = event_position.max(position);
+            let running = self.running_sources();
+            for line in &mut lines[position..=event_position] {
+                *line |= running;
+            }
+            position = event_position;
+
+            if event.is_entry {
+                self.running.push(event.source);
+                lines[position] |= 1 << event.source;
+            }
+            else if let Some(index) = self.running.iter().rposition(|source| *source == event.source) {
+                self.running.remove(index);
+            }
+        }
+        let running = self.running_sources();
+        for line in &mut lines[position..] {
+            *line |= running;
+        }
+
+        self.lines.copy_from_slice(&lines[VBLANK_LINES..]);
+    }
+
+    fn running_sources(&self) -> u8 {
+        self.running.iter().fold(0, |sources, source| sources | (1 << source))
+    }
+
+    pub fn apply(&self, colors: &mut ScreenColors) {
+        for (row, sources) in colors.iter_mut().zip(self.lines.iter()) {
+            //Higher priority interrupts win when handlers for several ran on the same line
+            let Some(source) = (0..5).find(|source| sources & (1 << source) != 0 && self.settings.sources[*source]) else {
+                continue;
+            };
+            for color in row.iter_mut() {
+                *color = tint(*color, SOURCE_COLORS[source], self.settings.strength);
+            }
+        }
+    }
+}
+
+fn tint(color: Color32, tint: Color32, strength: f32) -> Color32 {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * strength) as u8;
+    Color32::from_rgb(mix(color.r(), tint.r()), mix(color.g(), tint.g()), mix(color.b(), tint.b()))
+}
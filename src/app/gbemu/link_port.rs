@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use super::serial::SerialPacing;
+
+pub const SERIAL_PACING_KEY: &str = "serial_pacing";
+pub const DEFAULT_SERIAL_DELAY: u32 = 1000; //Microseconds
+
+//What the link port menu has selected. The processor builds the matching SerialDevice when it changes
+#[derive(Clone, PartialEq, Default)]
+pub enum LinkPortDevice {
+    #[default]
+    Disconnected,
+    Loopback,
+    Stdout,
+    LinkAdapter(String), //Serial port name
+    BarcodeBoy,
+    Workboy,
+}
+
+//Pacing is set per kind of peripheral, since a printer and a link adapter need different amounts of slack
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct SerialPacingSettings {
+    pub link_adapter: SerialPacing,
+    pub barcode_boy: SerialPacing,
+    pub workboy: SerialPacing,
+}
+
+impl SerialPacingSettings {
+    pub fn for_device(&self, device: &LinkPortDevice) -> SerialPacing {
+        match device {
+            LinkPortDevice::Disconnected | LinkPortDevice::Loopback | LinkPortDevice::Stdout => SerialPacing::Off,
+            LinkPortDevice::LinkAdapter(_) => self.link_adapter,
+            LinkPortDevice::BarcodeBoy => self.barcode_boy,
+            LinkPortDevice::Workboy => self.workboy,
+        }
+    }
+
+    pub fn for_device_mut(&mut self, device: &LinkPortDevice) -> Option<&mut SerialPacing> {
+        match device {
+            LinkPortDevice::Disconnected | LinkPortDevice::Loopback | LinkPortDevice::Stdout => None,
+            LinkPortDevice::LinkAdapter(_) => Some(&mut self.link_adapter),
+            LinkPortDevice::BarcodeBoy => Some(&mut self.barcode_boy),
+            LinkPortDevice::Workboy => Some(&mut self.workboy),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::joypad::JoypadState;
+
+pub const INPUT_MACROS_KEY: &str = "input_macros";
+
+//Keys that can play a macro. Function keys stay clear of the joypad bindings
+pub const HOTKEYS: [egui::Key; 12] = [
+    egui::Key::F1, egui::Key::F2, egui::Key::F3, egui::Key::F4, egui::Key::F5, egui::Key::F6,
+    egui::Key::F7, egui::Key::F8, egui::Key::F9, egui::Key::F10, egui::Key::F11, egui::Key::F12,
+];
+
+//Buttons held for a number of frames, packed like JoypadState::to_byte
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub buttons: u8,
+    pub frames: u32,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputMacro {
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+    pub hotkey: Option<egui::Key>,
+    pub run_on_load: bool, //Played as soon as the game is opened, e.g. to skip its intro
+}
+
+impl InputMacro {
+    pub fn frame_count(&self) -> u32 {
+        self.steps.iter().map(|step| step.frames).sum()
+    }
+}
+
+//Macros for each game keyed by game_id(), kept between sessions
+pub type InputMacros = HashMap<String, Vec<InputMacro>>;
+
+//Collects the joypad state of every frame while recording
+#[derive(Default)]
+pub struct MacroRecorder {
+    steps: Vec<MacroStep>,
+}
+
+impl MacroRecorder {
+    pub fn push(&mut self, joypad: JoypadState) {
+        let buttons = joypad.to_byte();
+        match self.steps.last_mut() {
+            Some(step) if step.buttons == buttons => step.frames += 1,
+            _ => self.steps.push(MacroStep { buttons, frames: 1 }),
+        }
+    }
+
+    //Nothing is kept if no frames were recorded
+    pub fn finish(self, name: String) -> Option<InputMacro> {
+        if self.steps.is_empty() {
+            return None;
+        }
+        Some(InputMacro { name, steps: self.steps, hotkey: None, run_on_load: false })
+    }
+}
+
+//Gives a macro's joypad state one frame at a time, in place of the player's input
+pub struct MacroPlayer {
+    steps: Vec<MacroStep>,
+    step: usize,
+    frame: u32,
+}
+
+impl MacroPlayer {
+    pub fn new(input_macro: &InputMacro) -> Self {
+        Self {
+            steps: input_macro.steps.clone(),
+            step: 0,
+            frame: 0,
+        }
+    }
+}
+
+impl Iterator for MacroPlayer {
+    type Item = JoypadState;
+
+    fn next(&mut self) -> Option<JoypadState> {
+        while let Some(step) = self.steps.get(self.step) {
+            if self.frame < step.frames {
+                self.frame += 1;
+                return Some(JoypadState::from_byte(step.buttons));
+            }
+            self.step += 1;
+            self.frame = 0;
+        }
+        None
+    }
+}
@@ -0,0 +1,54 @@
+use std::{fs::{File, OpenOptions}, io::{self, Seek, SeekFrom, Write}};
+
+use super::console::GBConsole;
+
+//Mirrors the console's RAM into a file once per frame so external tools (map trackers, party viewers, etc.)
+//can mmap or poll it while the game runs. The file layout is fixed:
+//  0x0000-0x1FFF: WRAM (0xC000-0xDFFF) with the current WRAM bank mapped in
+//  0x2000-0x207F: HRAM (0xFF80-0xFFFF, the last byte being IE)
+//  0x2080-......: Cartridge RAM, all banks in order (absent if the cartridge has no RAM)
+pub struct MemoryView {
+    file: File,
+}
+
+pub const WRAM_OFFSET: u64 = 0x0000;
+pub const HRAM_OFFSET: u64 = 0x2000;
+pub const CARTRIDGE_RAM_OFFSET: u64 = 0x2080;
+
+impl MemoryView {
+    pub fn new(file_path: &str) -> io::Result<Self> {
+        let file = OpenOptions::new()
+                                .read(true)
+                                .write(true)
+                                .create(true)
+                                .truncate(true)
+                                .open(file_path)?;
+
+        Ok(Self {
+            file
+        })
+    }
+
+    pub fn update(&mut self, console: &mut GBConsole) -> io::Result<()> {
+        let high_ram = console.read_bulk(0xFF80, 0xFFFF);
+
+        self.file.seek(SeekFrom::Start(WRAM_OFFSET))?;
+        self.file.write_all(&console.working_ram())?;
+        self.file.seek(SeekFrom::Start(HRAM_OFFSET))?;
+        self.file.write_all(&high_ram)?;
+        if let Some(cartridge_ram) = console.cartridge_ram() {
+            self.file.seek(SeekFrom::Start(CARTRIDGE_RAM_OFFSET))?;
+            self.file.write_all(cartridge_ram)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn rom_to_memory_view(rom_file_path: String) -> String {
+    if let Some(view_file_path) = rom_file_path.rsplitn(2, ".").last() {
+        view_file_path.to_owned() + ".mem"
+    }
+    else {
+        panic!("Error! Invalid file path");
+    }
+}
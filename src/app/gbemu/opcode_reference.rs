@@ -0,0 +1,38 @@
+use crate::app::i18n::{tr, tr_args};
+
+use super::opcodes::{reference, OpcodeInfo};
+
+//The "Opcode Reference" window
+pub fn show_reference(ui: &mut egui::Ui) {
+    let query_id = ui.make_persistent_id("opcode_query");
+    let mut query: String = ui.data(|data| data.get_temp(query_id)).unwrap_or_default();
+    ui.add(egui::TextEdit::singleline(&mut query).hint_text(tr("opcodes.search_hint")));
+
+    let matching: Vec<&OpcodeInfo> = reference().iter().filter(|info| info.matches(&query)).collect();
+    ui.label(tr_args("opcodes.count", &[("count", &matching.len())]));
+    ui.separator();
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        egui::Grid::new("opcode_reference").striped(true).show(ui, |ui| {
+            ui.strong(tr("opcodes.opcode"));
+            ui.strong(tr("opcodes.mnemonic"));
+            ui.strong(tr("opcodes.bytes"));
+            ui.strong(tr("opcodes.m_cycles"));
+            ui.strong("Z N H C");
+            ui.end_row();
+
+            for info in matching {
+                ui.monospace(info.hex());
+                ui.monospace(&info.mnemonic);
+                ui.monospace(info.length.to_string());
+                ui.monospace(match info.m_cycles_not_taken {
+                    Some(not_taken) => format!("{}/{}", info.m_cycles, not_taken),
+                    None => info.m_cycles.to_string(),
+                }).on_hover_text(tr("opcodes.m_cycles_hint"));
+                ui.monospace(info.flags.chars().map(String::from).collect::<Vec<String>>().join(" "));
+                ui.end_row();
+            }
+        });
+    });
+    ui.data_mut(|data| data.insert_temp(query_id, query));
+}
@@ -0,0 +1,102 @@
+use std::{thread, time::{Duration, Instant}};
+
+use serde::{Deserialize, Serialize};
+
+pub const SYNC_MODE_KEY: &str = "sync_mode";
+pub const FAST_FORWARD_KEY: &str = "fast_forward";
+pub const FRAME_SKIP_KEY: &str = "frame_skip";
+
+//Most frames that can be skipped in a row when running behind, so the screen still updates at least this often
+pub const MAX_FRAME_SKIP: u32 = 8;
+
+//17556 M-cycles at 1048576 Hz
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+//How much audio audio sync keeps queued for the device. Enough to ride out a slow frame, little enough not to be
+//heard as lag
+const AUDIO_TARGET: Duration = Duration::from_millis(50);
+//How far behind its deadline a frame can fall before the deadlines start over, e.g. after the window was dragged.
+//Otherwise the frames missed would all run at once
+const MAX_LAG: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SyncMode {
+    #[default]
+    Video, //One frame every 1/59.73 seconds by the host clock
+    Audio, //Whenever the output device is running low. Falls back to video without a device
+    Uncapped, //As fast as the host allows, with the sound off
+}
+
+//How fast the game runs while the fast forward control is held
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FastForwardSettings {
+    pub speed: u32, //Times real time, from 1 to 8
+    pub is_uncapped: bool, //As fast as the host allows instead, with the sound off
+}
+
+impl Default for FastForwardSettings {
+    fn default() -> Self {
+        Self {
+            speed: 4,
+            is_uncapped: false,
+        }
+    }
+}
+
+//Runs the emulation thread a frame at a time, waiting between frames instead of between cycles
+pub struct FramePacer {
+    deadline: Instant, //When the next frame should start
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self {
+            deadline: Instant::now() + FRAME_DURATION,
+        }
+    }
+}
+
+impl FramePacer {
+    //Called once each frame's cycles have run. With audio sync the speed takes care of itself, since the audio is
+    //resampled to match it. Returns true if the frame finished too late, meaning emulation is running behind
+    pub fn wait(&mut self, mode: SyncMode, queued_audio: Option<Duration>, speed: u32) -> bool {
+        let is_behind = match (mode, queued_audio) {
+            (SyncMode::Uncapped, _) => false,
+            (SyncMode::Audio, Some(queued_audio)) => {
+                if queued_audio > AUDIO_TARGET {
+                    thread::sleep(queued_audio - AUDIO_TARGET);
+                }
+                queued_audio < FRAME_DURATION
+            }
+            _ => {
+                let now = Instant::now();
+                let is_behind = self.deadline <= now;
+                if !is_behind {
+                    thread::sleep(self.deadline - now);
+                }
+                else if now - self.deadline > MAX_LAG {
+                    self.deadline = now;
+                }
+                self.deadline += FRAME_DURATION / speed.max(1);
+                return is_behind;
+            }
+        };
+        //Kept up to date so switching to video sync doesn't try to catch up
+        self.restart();
+        is_behind
+    }
+
+    //Called after emulation was stopped for a while, like when paused
+    pub fn restart(&mut self) {
+        self.deadline = Instant::now() + FRAME_DURATION;
+    }
+
+    //The next frame starts right away, so the audio queued for the device shrinks by a frame
+    pub fn drop_frame(&mut self) {
+        self.deadline -= FRAME_DURATION;
+    }
+
+    //The next frame starts a frame late, so the audio queued for the device grows by a frame
+    pub fn duplicate_frame(&mut self) {
+        self.deadline += FRAME_DURATION;
+    }
+}
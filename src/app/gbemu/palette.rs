@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::app::i18n::tr;
+
+pub const PALETTE_PROFILES_KEY: &str = "palette_profiles";
+
+//The colors used for the four DMG shades, from lightest to darkest
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+pub enum PalettePreset {
+    #[default]
+    Grayscale,
+    //Blue to yellow, which stays distinct without red-green discrimination (deuteranopia and protanopia)
+    BlueYellow,
+    //Red to teal, which stays distinct without blue-yellow discrimination (tritanopia)
+    RedTeal,
+    //Shades spread evenly in perceived lightness from pure white to pure black
+    HighContrast,
+}
+
+impl PalettePreset {
+    pub const ALL: [PalettePreset; 4] = [PalettePreset::Grayscale, PalettePreset::BlueYellow, PalettePreset::RedTeal, PalettePreset::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PalettePreset::Grayscale => tr("palette.grayscale"),
+            PalettePreset::BlueYellow => tr("palette.blue_yellow"),
+            PalettePreset::RedTeal => tr("palette.red_teal"),
+            PalettePreset::HighContrast => tr("palette.high_contrast"),
+        }
+    }
+
+    fn shades(&self) -> [Color32; 4] {
+        match self {
+            PalettePreset::Grayscale => [Color32::WHITE, Color32::LIGHT_GRAY, Color32::DARK_GRAY, Color32::BLACK],
+            PalettePreset::BlueYellow => [
+                Color32::from_rgb(0xFF, 0xEA, 0x46),
+                Color32::from_rgb(0xA5, 0x9C, 0x74),
+                Color32::from_rgb(0x57, 0x5C, 0x6D),
+                Color32::from_rgb(0x00, 0x20, 0x4D),
+            ],
+            PalettePreset::RedTeal => [
+                Color32::from_rgb(0xFF, 0xE5, 0xE5),
+                Color32::from_rgb(0xF0, 0x78, 0x78),
+                Color32::from_rgb(0x2E, 0x8B, 0x8B),
+                Color32::from_rgb(0x0D, 0x1B, 0x1E),
+            ],
+            PalettePreset::HighContrast => [
+                Color32::WHITE,
+                Color32::from_gray(0xA2),
+                Color32::from_gray(0x4F),
+                Color32::BLACK,
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PaletteSettings {
+    pub preset: PalettePreset,
+    //Contrast multipliers applied separately so sprites can be made to stand out from the background
+    pub background_contrast: f32,
+    pub sprite_contrast: f32,
+}
+
+impl Default for PaletteSettings {
+    fn default() -> Self {
+        Self {
+            preset: PalettePreset::default(),
+            background_contrast: 1.0,
+            sprite_contrast: 1.0,
+        }
+    }
+}
+
+impl PaletteSettings {
+    //Maps a BGP/OBP register to the colors of its four color indexes
+    pub fn background_colors(&self, console_pallette: u8) -> [Color32; 4] {
+        Self::colors(self.preset.shades(), console_pallette, self.background_contrast)
+    }
+
+    pub fn sprite_colors(&self, console_pallette: u8) -> [Color32; 4] {
+        Self::colors(self.preset.shades(), console_pallette, self.sprite_contrast)
+    }
+
+    fn colors(shades: [Color32; 4], console_pallette: u8, contrast: f32) -> [Color32; 4] {
+        let mut pallette = [Color32::WHITE; 4];
+
+        for (i, color) in pallette.iter_mut().enumerate() {
+            let shade = shades[((console_pallette >> (i * 2)) & 0b11) as usize];
+            let stretch = |channel: u8| (127.5 + (channel as f32 - 127.5) * contrast).round().clamp(0.0, 255.0) as u8;
+            *color = Color32::from_rgb(stretch(shade.r()), stretch(shade.g()), stretch(shade.b()));
+        }
+
+        pallette
+    }
+}
+
+//The default palette, plus overrides for individual games keyed by input::game_id()
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct PaletteProfiles {
+    pub default: PaletteSettings,
+    pub per_game: HashMap<String, PaletteSettings>,
+}
+
+impl PaletteProfiles {
+    pub fn for_game(&self, game_id: &str) -> PaletteSettings {
+        *self.per_game.get(game_id).unwrap_or(&self.default)
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use gilrs::{ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks}, Gilrs};
+use serde::{Deserialize, Serialize};
+
+pub const RUMBLE_SETTINGS_KEY: &str = "rumble_settings";
+
+//Pulses when the byte at address drops below threshold, e.g. a game's health counter
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MemoryWatch {
+    pub address: u16,
+    pub threshold: u8,
+}
+
+impl Default for MemoryWatch {
+    fn default() -> Self {
+        Self {
+            address: 0xC000,
+            threshold: 1,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct RumbleSettings {
+    pub enabled: bool,
+    pub strength: f32,
+    pub on_state_load: bool,
+    pub watches: HashMap<String, MemoryWatch>, //Keyed by input::game_id()
+}
+
+impl Default for RumbleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strength: 0.5,
+            on_state_load: true,
+            watches: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum RumbleEvent {
+    StateLoaded,
+    WatchTriggered,
+}
+
+impl RumbleEvent {
+    fn duration_ms(&self) -> u32 {
+        match self {
+            RumbleEvent::StateLoaded => 120,
+            RumbleEvent::WatchTriggered => 300,
+        }
+    }
+}
+
+pub struct Rumble {
+    effect: Option<Effect>, //Dropping an effect stops it, so the last one is kept until the next pulse
+    last_watched_value: Option<u8>,
+}
+
+impl Rumble {
+    pub fn new() -> Self {
+        Self {
+            effect: None,
+            last_watched_value: None,
+        }
+    }
+
+    //Called once per frame with the watched byte. Only the frame where it crosses the threshold counts,
+    //so a value that stays low doesn't keep the controller shaking
+    pub fn check_watch(&mut self, watch: &MemoryWatch, value: u8) -> bool {
+        let was_above = self.last_watched_value.is_some_and(|last_value| last_value >= watch.threshold);
+        self.last_watched_value = Some(value);
+        was_above && value < watch.threshold
+    }
+
+    //Plays on the first connected gamepad that supports force feedback
+    pub fn pulse(&mut self, gilrs: &mut Gilrs, settings: &RumbleSettings, event: RumbleEvent) {
+        if !settings.enabled {
+            return;
+        }
+
+        let gamepad_id = match gilrs.gamepads().find(|(_, gamepad)| gamepad.is_ff_supported()) {
+            Some((id, _)) => id,
+            None => return,
+        };
+
+        let duration = Ticks::from_ms(event.duration_ms());
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong { magnitude: (settings.strength.clamp(0.0, 1.0) * u16::MAX as f32) as u16 },
+                scheduling: Replay { after: Ticks::from_ms(0), play_for: duration, with_delay: Ticks::from_ms(0) },
+                envelope: Default::default(),
+            })
+            .repeat(Repeat::For(duration))
+            .gamepads(&[gamepad_id])
+            .finish(gilrs);
+
+        match effect.and_then(|effect| effect.play().map(|_| effect)) {
+            Ok(effect) => self.effect = Some(effect),
+            Err(e) => println!("ERROR: Could not play rumble effect: {}", e),
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::{collections::VecDeque, path::PathBuf, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}};
+
+use crate::app::{cartridge_info::CartridgeInfo, i18n::tr};
+
+use super::{av_sync::AvSync, cart_ram::CartRamAccess, frame_dump::{FrameDumpRequest, RawFrame}, heatmap::MemoryHeatmap, memory_editor::MemoryEditor, inspector::PPUSnapshot, text_art::ScreenShades, visualizer::AudioVisualizer, savestate::SavestateRequest, debug_session::{DebugView, DisassemblyView, StepRequest}, cheats_tool::CheatsTool, monitor::Monitor, script::OverlayText, link_port::LinkPortDevice, serial::PeripheralInput, wake::WakeSignal, CPURegisters, GBEmu, ScreenPixel};
+
+//The per-rom half of GBEmu. Every open rom tab gets its own emulation thread, and the UI swaps
+//these handles into GBEmu when a tab is focused so the rest of the UI doesn't need to know about tabs
+#[derive(Clone)]
+pub struct Session {
+    pub rom_file_path: Arc<Mutex<Option<String>>>,
+    pub rom_info: Arc<Mutex<Option<CartridgeInfo>>>,
+    pub compatibility_warnings: Arc<Mutex<Vec<&'static str>>>,
+    pub file_changed: Arc<AtomicBool>,
+    pub screen_pixels: Arc<Mutex<Option<Vec<ScreenPixel>>>>,
+    pub screen_shades: Arc<Mutex<Option<Box<ScreenShades>>>>,
+    pub link_port_device: Arc<Mutex<LinkPortDevice>>,
+    pub peripheral_input: PeripheralInput,
+    pub desync_frame: Arc<Mutex<Option<u64>>>,
+    pub savestate_request: Arc<Mutex<Option<SavestateRequest>>>,
+    pub can_undo_load: Arc<AtomicBool>,
+    pub cartridge_swap_request: Arc<Mutex<Option<String>>>,
+    pub monitor: Arc<Mutex<Monitor>>,
+    pub macro_play_request: Arc<Mutex<Option<usize>>>,
+    pub is_recording_macro: Arc<AtomicBool>,
+    pub is_playing_macro: Arc<AtomicBool>,
+    pub frame_dump_request: Arc<Mutex<Option<FrameDumpRequest>>>,
+    pub frame_dump_progress: Arc<Mutex<Option<u32>>>,
+    pub state_history_usage: Arc<Mutex<(usize, usize)>>,
+    pub console_output: Arc<Mutex<String>>,
+    pub ppu_snapshot: Arc<Mutex<Option<PPUSnapshot>>>,
+    pub paused: Arc<AtomicBool>,
+    pub break_reason: Arc<Mutex<Option<String>>>,
+    pub log_error: Arc<Mutex<Option<String>>>,
+    pub debug_view: Arc<Mutex<DebugView>>,
+    pub cpu_registers: Arc<Mutex<Option<CPURegisters>>>,
+    pub debugger_requested: Arc<AtomicBool>,
+    pub audio_visualizer: Arc<Mutex<AudioVisualizer>>,
+    pub audio_channels: Arc<Mutex<[bool; 4]>>,
+    pub memory_heatmap: Arc<Mutex<MemoryHeatmap>>,
+    pub av_sync: Arc<Mutex<AvSync>>,
+    pub raw_frame: Arc<Mutex<Option<RawFrame>>>,
+    pub cart_ram: CartRamAccess,
+    pub script_path: Arc<Mutex<Option<PathBuf>>>, //None while no script is running
+    pub script_reload: Arc<AtomicBool>,
+    pub script_status: Arc<Mutex<String>>,
+    pub script_overlay: Arc<Mutex<Vec<OverlayText>>>,
+    pub gdb_server_port: Arc<Mutex<Option<u16>>>, //None while the GDB server is off
+    pub gdb_server_status: Arc<Mutex<String>>,
+    pub step_request: Arc<Mutex<Option<StepRequest>>>, //Taken by the emulation thread when it resumes, or at the next frame
+    pub memory_editor_enabled: Arc<AtomicBool>,
+    pub memory_editor: Arc<Mutex<MemoryEditor>>,
+    pub disassembly_enabled: Arc<AtomicBool>,
+    pub disassembly: Arc<Mutex<DisassemblyView>>, //Updated every frame and whenever the debugger pauses, while enabled
+    pub cheats: Arc<Mutex<CheatsTool>>,
+    pub is_focused: Arc<AtomicBool>, //Only the focused session plays audio and reads input
+    pub is_closed: Arc<AtomicBool>, //Tells the emulation thread to stop
+    pub wake: WakeSignal,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            rom_file_path: Arc::new(Mutex::new(None)),
+            rom_info: Arc::new(Mutex::new(None)),
+            compatibility_warnings: Arc::new(Mutex::new(Vec::new())),
+            file_changed: Arc::new(AtomicBool::from(false)),
+            screen_pixels: Arc::new(Mutex::new(None)),
+            screen_shades: Arc::new(Mutex::new(None)),
+            link_port_device: Arc::new(Mutex::new(LinkPortDevice::Disconnected)),
+            peripheral_input: Arc::new(Mutex::new(VecDeque::new())),
+            desync_frame: Arc::new(Mutex::new(None)),
+            savestate_request: Arc::new(Mutex::new(None)),
+            can_undo_load: Arc::new(AtomicBool::from(false)),
+            cartridge_swap_request: Arc::new(Mutex::new(None)),
+            monitor: Arc::new(Mutex::new(Monitor::default())),
+            macro_play_request: Arc::new(Mutex::new(None)),
+            is_recording_macro: Arc::new(AtomicBool::from(false)),
+            is_playing_macro: Arc::new(AtomicBool::from(false)),
+            frame_dump_request: Arc::new(Mutex::new(None)),
+            frame_dump_progress: Arc::new(Mutex::new(None)),
+            state_history_usage: Arc::new(Mutex::new((0, 0))),
+            console_output: Arc::new(Mutex::new(String::new())),
+            ppu_snapshot: Arc::new(Mutex::new(None)),
+            paused: Arc::new(AtomicBool::from(false)),
+            break_reason: Arc::new(Mutex::new(None)),
+            log_error: Arc::new(Mutex::new(None)),
+            debug_view: Arc::new(Mutex::new(DebugView::default())),
+            cpu_registers: Arc::new(Mutex::new(None)),
+            debugger_requested: Arc::new(AtomicBool::from(false)),
+            audio_visualizer: Arc::new(Mutex::new(AudioVisualizer::default())),
+            audio_channels: Arc::new(Mutex::new([true; 4])),
+            memory_heatmap: Arc::new(Mutex::new(MemoryHeatmap::default())),
+            av_sync: Arc::new(Mutex::new(AvSync::default())),
+            raw_frame: Arc::new(Mutex::new(None)),
+            cart_ram: CartRamAccess::default(),
+            script_path: Arc::new(Mutex::new(None)),
+            script_reload: Arc::new(AtomicBool::from(false)),
+            script_status: Arc::new(Mutex::new(String::new())),
+            script_overlay: Arc::new(Mutex::new(Vec::new())),
+            gdb_server_port: Arc::new(Mutex::new(None)),
+            gdb_server_status: Arc::new(Mutex::new(String::new())),
+            step_request: Arc::new(Mutex::new(None)),
+            memory_editor_enabled: Arc::new(AtomicBool::from(false)),
+            memory_editor: Arc::new(Mutex::new(MemoryEditor::default())),
+            disassembly_enabled: Arc::new(AtomicBool::from(false)),
+            disassembly: Arc::new(Mutex::new(DisassemblyView::default())),
+            cheats: Arc::new(Mutex::new(CheatsTool::default())),
+            is_focused: Arc::new(AtomicBool::from(true)),
+            is_closed: Arc::new(AtomicBool::from(false)),
+            wake: WakeSignal::default(),
+        }
+    }
+}
+
+impl Session {
+    pub fn of(emu: &GBEmu) -> Self {
+        Self {
+            rom_file_path: emu.rom_file_path.clone(),
+            rom_info: emu.rom_info.clone(),
+            compatibility_warnings: emu.compatibility_warnings.clone(),
+            file_changed: emu.file_changed.clone(),
+            screen_pixels: emu.screen_pixels.clone(),
+            screen_shades: emu.screen_shades.clone(),
+            link_port_device: emu.link_port_device.clone(),
+            peripheral_input: emu.peripheral_input.clone(),
+            desync_frame: emu.desync_frame.clone(),
+            savestate_request: emu.savestate_request.clone(),
+            can_undo_load: emu.can_undo_load.clone(),
+            cartridge_swap_request: emu.cartridge_swap_request.clone(),
+            monitor: emu.monitor.clone(),
+            macro_play_request: emu.macro_play_request.clone(),
+            is_recording_macro: emu.is_recording_macro.clone(),
+            is_playing_macro: emu.is_playing_macro.clone(),
+            frame_dump_request: emu.frame_dump_request.clone(),
+            frame_dump_progress: emu.frame_dump_progress.clone(),
+            state_history_usage: emu.state_history_usage.clone(),
+            console_output: emu.console_output.clone(),
+            ppu_snapshot: emu.ppu_snapshot.clone(),
+            paused: emu.paused.clone(),
+            break_reason: emu.break_reason.clone(),
+            log_error: emu.log_error.clone(),
+            debug_view: emu.debug_view.clone(),
+            cpu_registers: emu.cpu_registers.clone(),
+            debugger_requested: emu.debugger_requested.clone(),
+            audio_visualizer: emu.audio_visualizer.clone(),
+            audio_channels: emu.audio_channels.clone(),
+            memory_heatmap: emu.memory_heatmap.clone(),
+            av_sync: emu.av_sync.clone(),
+            raw_frame: emu.raw_frame.clone(),
+            cart_ram: emu.cart_ram.clone(),
+            script_path: emu.script_path.clone(),
+            script_reload: emu.script_reload.clone(),
+            script_status: emu.script_status.clone(),
+            script_overlay: emu.script_overlay.clone(),
+            gdb_server_port: emu.gdb_server_port.clone(),
+            gdb_server_status: emu.gdb_server_status.clone(),
+            step_request: emu.step_request.clone(),
+            memory_editor_enabled: emu.memory_editor_enabled.clone(),
+            memory_editor: emu.memory_editor.clone(),
+            disassembly_enabled: emu.disassembly_enabled.clone(),
+            disassembly: emu.disassembly.clone(),
+            cheats: emu.cheats.clone(),
+            is_focused: emu.is_focused.clone(),
+            is_closed: emu.is_closed.clone(),
+            wake: emu.wake.clone(),
+        }
+    }
+
+    pub fn focus(&self, emu: &mut GBEmu) {
+        emu.is_focused.store(false, Ordering::Relaxed);
+
+        emu.rom_file_path = self.rom_file_path.clone();
+        emu.rom_info = self.rom_info.clone();
+        emu.compatibility_warnings = self.compatibility_warnings.clone();
+        emu.file_changed = self.file_changed.clone();
+        emu.screen_pixels = self.screen_pixels.clone();
+        emu.screen_shades = self.screen_shades.clone();
+        emu.link_port_device = self.link_port_device.clone();
+        emu.peripheral_input = self.peripheral_input.clone();
+        emu.desync_frame = self.desync_frame.clone();
+        emu.savestate_request = self.savestate_request.clone();
+        emu.can_undo_load = self.can_undo_load.clone();
+        emu.cartridge_swap_request = self.cartridge_swap_request.clone();
+        emu.monitor = self.monitor.clone();
+        emu.macro_play_request = self.macro_play_request.clone();
+        emu.is_recording_macro = self.is_recording_macro.clone();
+        emu.is_playing_macro = self.is_playing_macro.clone();
+        emu.frame_dump_request = self.frame_dump_request.clone();
+        emu.frame_dump_progress = self.frame_dump_progress.clone();
+        emu.state_history_usage = self.state_history_usage.clone();
+        emu.console_output = self.console_output.clone();
+        emu.ppu_snapshot = self.ppu_snapshot.clone();
+        emu.paused = self.paused.clone();
+        emu.break_reason = self.break_reason.clone();
+        emu.log_error = self.log_error.clone();
+        emu.debug_view = self.debug_view.clone();
+        emu.cpu_registers = self.cpu_registers.clone();
+        emu.debugger_requested = self.debugger_requested.clone();
+        emu.audio_visualizer = self.audio_visualizer.clone();
+        emu.audio_channels = self.audio_channels.clone();
+        emu.memory_heatmap = self.memory_heatmap.clone();
+        emu.av_sync = self.av_sync.clone();
+        emu.raw_frame = self.raw_frame.clone();
+        emu.cart_ram = self.cart_ram.clone();
+        emu.script_path = self.script_path.clone();
+        emu.script_reload = self.script_reload.clone();
+        emu.script_status = self.script_status.clone();
+        emu.script_overlay = self.script_overlay.clone();
+        emu.gdb_server_port = self.gdb_server_port.clone();
+        emu.gdb_server_status = self.gdb_server_status.clone();
+        emu.step_request = self.step_request.clone();
+        emu.memory_editor_enabled = self.memory_editor_enabled.clone();
+        emu.memory_editor = self.memory_editor.clone();
+        emu.disassembly_enabled = self.disassembly_enabled.clone();
+        emu.disassembly = self.disassembly.clone();
+        emu.cheats = self.cheats.clone();
+        emu.is_focused = self.is_focused.clone();
+        emu.is_closed = self.is_closed.clone();
+        emu.wake = self.wake.clone();
+
+        emu.is_focused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_same(&self, emu: &GBEmu) -> bool {
+        Arc::ptr_eq(&self.is_closed, &emu.is_closed)
+    }
+
+    pub fn title(&self) -> String {
+        match &*self.rom_info.lock().unwrap() {
+            Some(info) => {
+                let title = info.title.trim_end_matches('\0').trim();
+                if title.is_empty() {
+                    tr("tabs.untitled").to_owned()
+                }
+                else {
+                    title.to_owned()
+                }
+            }
+            None => tr("tabs.no_rom").to_owned(),
+        }
+    }
+
+    pub fn has_rom(&self) -> bool {
+        self.rom_file_path.lock().unwrap().is_some()
+    }
+}
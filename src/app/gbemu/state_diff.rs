@@ -0,0 +1,223 @@
+use std::{fs, ops::Range, path::{Path, PathBuf}};
+
+use crate::app::{cartridge_info::CartridgeInfo, i18n::{tr, tr_args}, rom_archive};
+
+use super::{cart_ram::changed_ranges, console::GBConsole, savestate};
+
+//Bytes of each differing range shown before the rest is cut off
+const MAX_SHOWN_BYTES: usize = 256;
+
+//A named block of memory and the address its first byte is mapped at
+struct Region {
+    name: String,
+    base_address: Option<u16>, //None for memory that isn't mapped all at once, like cartridge RAM
+    bytes: Vec<u8>,
+}
+
+//Everything in a savestate worth comparing, pulled out of a console it was loaded into
+struct StateContents {
+    registers: Vec<(&'static str, u16)>,
+    mapper_registers: Vec<(&'static str, u8)>,
+    regions: Vec<Region>,
+}
+
+impl StateContents {
+    fn load(rom_path: &Path, state_path: &Path) -> Result<Self, String> {
+        let (rom, _) = rom_archive::read_rom(&rom_path.display().to_string()).map_err(|e| tr_args("error.read", &[("path", &rom_path.display()), ("error", &e)]))?;
+        let info = CartridgeInfo::from_rom(&rom).map_err(|e| tr_args("error.load", &[("path", &rom_path.display()), ("error", &e)]))?;
+        let state = fs::read(state_path).and_then(|compressed_state| savestate::decompress(&compressed_state))
+            .map_err(|e| tr_args("error.read", &[("path", &state_path.display()), ("error", &e)]))?;
+
+        let mut console = GBConsole::detached(info, &rom).map_err(|e| e.to_string())?;
+        savestate::restore(&mut console, &state).map_err(|e| tr_args("error.load", &[("path", &state_path.display()), ("error", &e)]))?;
+
+        let cpu = console.cpu_registers();
+        let registers = vec![
+            ("A", cpu.a as u16), ("F", cpu.f as u16), ("B", cpu.b as u16), ("C", cpu.c as u16),
+            ("D", cpu.d as u16), ("E", cpu.e as u16), ("H", cpu.h as u16), ("L", cpu.l as u16),
+            ("SP", cpu.stack_pointer), ("PC", cpu.program_counter), ("IME", cpu.ime as u16), ("HALT", cpu.is_halted as u16), ("STOP", cpu.is_stopped as u16),
+        ];
+
+        let mut regions = Vec::new();
+        let ppu = console.ppu_snapshot();
+        for (bank, video_ram) in ppu.video_ram.iter().enumerate() {
+            regions.push(Region { name: tr_args("state_diff.vram_bank", &[("bank", &bank)]), base_address: Some(0x8000), bytes: video_ram[..0x2000].to_vec() });
+        }
+        for (bank, working_ram) in console.working_ram_banks().into_iter().enumerate() {
+            let base_address = if bank == 0 {0xC000} else {0xD000};
+            regions.push(Region { name: tr_args("state_diff.wram_bank", &[("bank", &bank)]), base_address: Some(base_address), bytes: working_ram.to_vec() });
+        }
+        regions.push(Region { name: String::from("OAM"), base_address: Some(0xFE00), bytes: ppu.object_attribute_memory.to_vec() });
+        regions.push(Region { name: tr("state_diff.io_registers").to_owned(), base_address: Some(0xFF00), bytes: console.read_bulk(0xFF00, 0xFF7F) });
+        regions.push(Region { name: String::from("HRAM"), base_address: Some(0xFF80), bytes: console.high_ram().to_vec() });
+        if let Some(cartridge_ram) = console.cartridge_ram() {
+            regions.push(Region { name: tr("memory.cartridge_ram").to_owned(), base_address: None, bytes: cartridge_ram.to_vec() });
+        }
+
+        Ok(Self {
+            registers,
+            mapper_registers: console.mapper_registers(),
+            regions,
+        })
+    }
+}
+
+pub struct RegionDiff {
+    pub name: String,
+    pub base_address: Option<u16>,
+    pub ranges: Vec<Range<usize>>,
+    old: Vec<u8>,
+    new: Vec<u8>,
+}
+
+impl RegionDiff {
+    fn changed_byte_count(&self) -> usize {
+        self.ranges.iter().map(|range| range.len()).sum()
+    }
+
+    //Where a range starts, as a CPU address when the region is mapped in, or an offset into it when not
+    fn describe(&self, range: &Range<usize>) -> String {
+        match self.base_address {
+            Some(base_address) => format!("${:04X}-${:04X}", base_address as usize + range.start, base_address as usize + range.end - 1),
+            None => format!("+{:05X}-{:05X}", range.start, range.end - 1),
+        }
+    }
+}
+
+//What differs between two savestates of the same game
+pub struct StateDiff {
+    pub registers: Vec<(&'static str, u16, u16)>,
+    pub mapper_registers: Vec<(&'static str, u8, u8)>,
+    pub regions: Vec<RegionDiff>, //Only the regions with differences
+}
+
+impl StateDiff {
+    pub fn new(rom_path: &Path, old_state_path: &Path, new_state_path: &Path) -> Result<Self, String> {
+        let old = StateContents::load(rom_path, old_state_path)?;
+        let new = StateContents::load(rom_path, new_state_path)?;
+
+        let registers = old.registers.iter().zip(new.registers.iter())
+            .filter(|(old, new)| old.1 != new.1)
+            .map(|(old, new)| (old.0, old.1, new.1))
+            .collect();
+        let mapper_registers = old.mapper_registers.iter().zip(new.mapper_registers.iter())
+            .filter(|(old, new)| old.1 != new.1)
+            .map(|(old, new)| (old.0, old.1, new.1))
+            .collect();
+        //Both states come from the same rom, so they have the same regions in the same order
+        let regions = old.regions.into_iter().zip(new.regions)
+            .map(|(old, new)| RegionDiff { ranges: changed_ranges(&old.bytes, &new.bytes), name: old.name, base_address: old.base_address, old: old.bytes, new: new.bytes })
+            .filter(|region| !region.ranges.is_empty())
+            .collect();
+
+        Ok(Self {
+            registers,
+            mapper_registers,
+            regions,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.mapper_registers.is_empty() && self.regions.is_empty()
+    }
+}
+
+//The "Savestate Diff" window
+#[derive(Default)]
+pub struct StateDiffTool {
+    pub is_open: bool,
+    state_paths: [Option<PathBuf>; 2],
+    result: Option<Result<StateDiff, String>>,
+}
+
+impl StateDiffTool {
+    pub fn show(&mut self, ui: &mut egui::Ui, rom_path: Option<String>) {
+        let Some(rom_path) = rom_path else {
+            ui.label(tr("state_diff.no_rom"));
+            return;
+        };
+
+        for (index, label) in [tr("state_diff.before"), tr("state_diff.after")].into_iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                let path_name = self.state_paths[index].as_ref().map(|path| path.display().to_string()).unwrap_or(tr("state_diff.no_state").to_owned());
+                ui.label(path_name);
+                if ui.button(tr("state_diff.choose")).clicked() {
+                    let directory = Path::new(&rom_path).parent().map(Path::to_path_buf).unwrap_or_default();
+                    if let Some(path) = rfd::FileDialog::new().set_directory(directory).pick_file() {
+                        self.state_paths[index] = Some(path);
+                        self.result = None;
+                    }
+                }
+            });
+        }
+
+        if let [Some(old_path), Some(new_path)] = &self.state_paths {
+            if ui.button(tr("state_diff.compare")).clicked() {
+                self.result = Some(StateDiff::new(Path::new(&rom_path), old_path, new_path));
+            }
+        }
+        ui.separator();
+
+        match &self.result {
+            Some(Ok(diff)) if diff.is_empty() => {
+                ui.label(tr("state_diff.identical"));
+            }
+            Some(Ok(diff)) => {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    show_diff(ui, diff);
+                });
+            }
+            Some(Err(e)) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+            None => {}
+        }
+    }
+}
+
+fn show_diff(ui: &mut egui::Ui, diff: &StateDiff) {
+    if !diff.registers.is_empty() {
+        egui::CollapsingHeader::new(tr_args("state_diff.cpu_registers", &[("count", &diff.registers.len())])).default_open(true).show(ui, |ui| {
+            egui::Grid::new("state_diff_registers").striped(true).show(ui, |ui| {
+                for (name, old, new) in &diff.registers {
+                    ui.monospace(*name);
+                    ui.monospace(format!("${:04X} -> ${:04X}", old, new));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+    if !diff.mapper_registers.is_empty() {
+        egui::CollapsingHeader::new(tr_args("state_diff.mapper", &[("count", &diff.mapper_registers.len())])).default_open(true).show(ui, |ui| {
+            egui::Grid::new("state_diff_mapper").striped(true).show(ui, |ui| {
+                for (name, old, new) in &diff.mapper_registers {
+                    ui.monospace(*name);
+                    ui.monospace(format!("${:02X} -> ${:02X}", old, new));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    for region in &diff.regions {
+        let title = tr_args("state_diff.region", &[("region", &region.name), ("bytes", &region.changed_byte_count()), ("ranges", &region.ranges.len())]);
+        egui::CollapsingHeader::new(title).id_salt(&region.name).show(ui, |ui| {
+            for range in &region.ranges {
+                egui::CollapsingHeader::new(tr_args("state_diff.range", &[("range", &region.describe(range)), ("bytes", &range.len())])).id_salt((&region.name, range.start)).show(ui, |ui| {
+                    let shown = range.start..range.end.min(range.start + MAX_SHOWN_BYTES);
+                    for (label, bytes) in [(tr("state_diff.before"), &region.old), (tr("state_diff.after"), &region.new)] {
+                        let hex: Vec<String> = bytes[shown.clone()].iter().map(|byte| format!("{:02X}", byte)).collect();
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(label);
+                            ui.monospace(hex.join(" "));
+                        });
+                    }
+                    if shown.len() < range.len() {
+                        ui.label(tr_args("state_diff.more_bytes", &[("bytes", &(range.len() - shown.len()))]));
+                    }
+                });
+            }
+        });
+    }
+}
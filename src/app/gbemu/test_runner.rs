@@ -0,0 +1,281 @@
+use std::{collections::BTreeMap, fs, panic::{self, AssertUnwindSafe}, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, Arc, Mutex}, thread};
+
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+use crate::app::i18n::{tr, tr_args};
+
+use super::{desync, headless::{HeadlessConsole, TestReport}};
+
+pub const TEST_MATRIX_KEY: &str = "test_matrix";
+
+const FRAMES_PER_SECOND: u64 = 60;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    TimedOut,
+    Crashed,
+}
+
+impl TestOutcome {
+    //In English, for the exported report that gets pasted into issues
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestOutcome::Passed => "Passed",
+            TestOutcome::Failed => "Failed",
+            TestOutcome::TimedOut => "Timed out",
+            TestOutcome::Crashed => "Crashed",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TestOutcome::Passed => tr("test_suite.passed"),
+            TestOutcome::Failed => tr("test_suite.failed"),
+            TestOutcome::TimedOut => tr("test_suite.timed_out"),
+            TestOutcome::Crashed => tr("test_suite.crashed"),
+        }
+    }
+
+    fn color(&self) -> Color32 {
+        match self {
+            TestOutcome::Passed => Color32::from_rgb(0x40, 0xC0, 0x40),
+            TestOutcome::Failed => Color32::from_rgb(0xE0, 0x40, 0x40),
+            TestOutcome::TimedOut => Color32::from_rgb(0xE0, 0xA0, 0x20),
+            TestOutcome::Crashed => Color32::from_rgb(0xC0, 0x40, 0xC0),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    pub outcome: TestOutcome,
+    pub frames: u64,
+    pub detail: String,
+    pub screen_hash: u64, //Hash of the last frame, so a timed out test's screen can be accepted as the expected one
+}
+
+//The results of the last run of each rom, keyed by its path relative to the folder. Kept between sessions
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TestMatrix {
+    pub folder: Option<PathBuf>,
+    pub timeout_seconds: u32,
+    pub results: BTreeMap<String, TestResult>,
+}
+
+impl Default for TestMatrix {
+    fn default() -> Self {
+        Self {
+            folder: None,
+            timeout_seconds: 30,
+            results: BTreeMap::new(),
+        }
+    }
+}
+
+impl TestMatrix {
+    pub fn to_markdown(&self) -> String {
+        let passed = self.results.values().filter(|result| result.outcome == TestOutcome::Passed).count();
+        let mut markdown = format!("{} of {} tests passed\n\n| Rom | Result | Frames | Detail |\n| --- | --- | --- | --- |\n", passed, self.results.len());
+        for (rom, result) in &self.results {
+            markdown += &format!("| {} | {} | {} | {} |\n", rom, result.outcome.name(), result.frames, result.detail.replace('|', "\\|").replace('\n', " "));
+        }
+        markdown
+    }
+}
+
+//The "Test Suite" window. Runs every rom in a folder headlessly on worker threads
+#[derive(Clone)]
+pub struct TestRunner {
+    pub is_open: Arc<AtomicBool>,
+    pub matrix: Arc<Mutex<TestMatrix>>,
+    remaining: Arc<AtomicUsize>,
+}
+
+impl Default for TestRunner {
+    fn default() -> Self {
+        Self {
+            is_open: Arc::new(AtomicBool::from(false)),
+            matrix: Arc::new(Mutex::new(TestMatrix::default())),
+            remaining: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl TestRunner {
+    pub fn is_running(&self) -> bool {
+        self.remaining.load(Ordering::Relaxed) > 0
+    }
+
+    pub fn start(&self, ctx: egui::Context) {
+        let mut matrix = self.matrix.lock().unwrap();
+        let folder = match &matrix.folder {
+            Some(folder) => folder.clone(),
+            None => return,
+        };
+        let timeout_frames = matrix.timeout_seconds as u64 * FRAMES_PER_SECOND;
+
+        let mut roms = Vec::new();
+        find_roms(&folder, &mut roms);
+        matrix.results.retain(|rom, _| roms.iter().any(|path| relative_name(&folder, path) == *rom));
+        drop(matrix);
+
+        self.remaining.store(roms.len(), Ordering::Relaxed);
+        let queue = Arc::new(Mutex::new(roms));
+        let worker_count = thread::available_parallelism().map(|count| count.get()).unwrap_or(1);
+        for _ in 0..worker_count {
+            let queue = queue.clone();
+            let matrix = self.matrix.clone();
+            let remaining = self.remaining.clone();
+            let folder = folder.clone();
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                loop {
+                    let rom_path = match queue.lock().unwrap().pop() {
+                        Some(rom_path) => rom_path,
+                        None => return,
+                    };
+
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| run_test(&rom_path, timeout_frames)))
+                        .unwrap_or_else(|_| TestResult { outcome: TestOutcome::Crashed, frames: 0, detail: String::from("The emulator panicked"), screen_hash: 0 });
+                    matrix.lock().unwrap().results.insert(relative_name(&folder, &rom_path), result);
+                    remaining.fetch_sub(1, Ordering::Relaxed);
+                    ctx.request_repaint();
+                }
+            });
+        }
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        let is_running = self.is_running();
+        let mut is_start_requested = false;
+        let mut matrix = self.matrix.lock().unwrap();
+
+        ui.horizontal(|ui| {
+            let folder_name = matrix.folder.as_ref().map(|folder| folder.display().to_string()).unwrap_or(tr("test_suite.no_folder").to_owned());
+            ui.label(folder_name);
+            if ui.add_enabled(!is_running, egui::Button::new(tr("test_suite.choose_folder"))).clicked() {
+                if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                    matrix.folder = Some(folder);
+                    matrix.results.clear();
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add_enabled(!is_running, egui::Slider::new(&mut matrix.timeout_seconds, 1..=300).text(tr("test_suite.timeout")));
+            if ui.add_enabled(!is_running && matrix.folder.is_some(), egui::Button::new(tr("test_suite.run"))).clicked() {
+                is_start_requested = true;
+            }
+            if ui.add_enabled(!matrix.results.is_empty(), egui::Button::new(tr("test_suite.export"))).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter(tr("file_dialog.markdown"), &["md"]).set_file_name("test_results.md").save_file() {
+                    if let Err(e) = fs::write(&path, matrix.to_markdown()) {
+                        println!("ERROR: Could not write test results {}: {}", path.display(), e);
+                    }
+                }
+            }
+        });
+
+        if is_running {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(tr_args("test_suite.remaining", &[("roms", &self.remaining.load(Ordering::Relaxed))]));
+            });
+        }
+        let passed = matrix.results.values().filter(|result| result.outcome == TestOutcome::Passed).count();
+        ui.label(tr_args("test_suite.summary", &[("passed", &passed), ("total", &matrix.results.len())]));
+        ui.separator();
+
+        let folder = matrix.folder.clone();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("test_matrix").striped(true).show(ui, |ui| {
+                for (rom, result) in &matrix.results {
+                    ui.label(rom);
+                    ui.colored_label(result.outcome.color(), result.outcome.label());
+                    ui.label(tr_args("test_suite.frames", &[("frames", &result.frames)]));
+                    ui.label(&result.detail);
+                    if result.outcome == TestOutcome::TimedOut {
+                        if let Some(folder) = &folder {
+                            if ui.small_button(tr("test_suite.accept_screen")).on_hover_text(tr("test_suite.accept_screen_hint")).clicked() {
+                                let hash_path = expected_hash_path(&folder.join(rom));
+                                if let Err(e) = fs::write(&hash_path, format!("{:016X}", result.screen_hash)) {
+                                    println!("ERROR: Could not write {}: {}", hash_path.display(), e);
+                                }
+                            }
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        drop(matrix);
+
+        if is_start_requested {
+            self.start(ui.ctx().clone());
+        }
+    }
+}
+
+fn find_roms(folder: &Path, roms: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("ERROR: Could not read test folder {}: {}", folder.display(), e);
+            return;
+        }
+    };
+
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.is_dir() {
+            find_roms(&path, roms);
+        }
+        else if matches!(path.extension().and_then(|extension| extension.to_str()), Some("gb") | Some("gbc")) {
+            roms.push(path);
+        }
+    }
+}
+
+fn relative_name(folder: &Path, rom_path: &Path) -> String {
+    rom_path.strip_prefix(folder).unwrap_or(rom_path).display().to_string()
+}
+
+//Roms that don't report over serial or through registers pass when their screen matches the hash in this file
+fn expected_hash_path(rom_path: &Path) -> PathBuf {
+    rom_path.with_extension("screen_hash")
+}
+
+//Runs a rom headlessly until it reports a result or times out
+fn run_test(rom_path: &Path, timeout_frames: u64) -> TestResult {
+    let mut headless = match HeadlessConsole::new(rom_path) {
+        Ok(headless) => headless,
+        Err(e) => return TestResult { outcome: TestOutcome::Crashed, frames: 0, detail: e.to_string(), screen_hash: 0 },
+    };
+    let expected_hash = fs::read_to_string(expected_hash_path(rom_path)).ok()
+        .and_then(|hash| u64::from_str_radix(hash.trim(), 16).ok());
+
+    let mut screen_hash = desync::hash_frame(headless.screen());
+    for frame in 1..=timeout_frames {
+        if headless.run_frame() {
+            screen_hash = desync::hash_frame(headless.screen());
+        }
+
+        let result = |outcome: TestOutcome, detail: String| TestResult { outcome, frames: frame, detail, screen_hash };
+
+        match headless.test_report() {
+            Some(TestReport::Passed(detail)) => return result(TestOutcome::Passed, detail),
+            Some(TestReport::Failed(detail)) => return result(TestOutcome::Failed, detail),
+            None => (),
+        }
+
+        if expected_hash == Some(screen_hash) {
+            return result(TestOutcome::Passed, String::from("Screen hash"));
+        }
+    }
+
+    let detail = match expected_hash {
+        Some(_) => String::from("Screen never matched"),
+        None => String::from_utf8_lossy(headless.serial_output()).trim().lines().last().unwrap_or_default().to_owned(),
+    };
+    TestResult { outcome: TestOutcome::TimedOut, frames: timeout_frames, detail, screen_hash }
+}
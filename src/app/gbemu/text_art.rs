@@ -0,0 +1,33 @@
+use super::inspector::PPUSnapshot;
+
+//One character per DMG shade, lightest first. White is a dot rather than a space so lines don't get trimmed when pasted
+const SHADE_CHARACTERS: [char; 4] = ['.', '░', '▒', '▓'];
+
+pub type ScreenShades = [[u8; 160]; 144];
+
+pub fn screen_to_text(shades: &ScreenShades) -> String {
+    let mut text = String::with_capacity(145 * 160 * 3);
+    for row in shades {
+        for shade in row {
+            text.push(SHADE_CHARACTERS[(*shade & 0b11) as usize]);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+//Tiles are drawn with their raw color indexes since the palette depends on what's using them
+pub fn tile_to_text(snapshot: &PPUSnapshot, tile_address: u16) -> String {
+    let tile_start = (tile_address - 0x8000) as usize;
+    let tile_data = &snapshot.video_ram[0][tile_start..tile_start + 16];
+
+    let mut text = String::new();
+    for row in tile_data.chunks(2) {
+        for bit in (0..8).rev() {
+            let color = ((row[0] >> bit) & 1) | (((row[1] >> bit) & 1) << 1);
+            text.push(SHADE_CHARACTERS[color as usize]);
+        }
+        text.push('\n');
+    }
+    text
+}
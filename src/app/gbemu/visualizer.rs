@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+//Number of samples analyzed for the spectrum. Must be a power of two for the FFT
+pub const SPECTRUM_SIZE: usize = 1024;
+
+//A ring buffer of the most recent mixed output, read by the visualizer tab
+pub struct AudioVisualizer {
+    samples: VecDeque<f32>,
+    capacity: usize,
+    pub sample_rate: f32,
+}
+
+impl Default for AudioVisualizer {
+    fn default() -> Self {
+        Self::new(4096)
+    }
+}
+
+impl AudioVisualizer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            sample_rate: 48000.0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for sample in samples {
+            if self.samples.len() == self.capacity {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(*sample);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    //The newest `count` samples, oldest first
+    pub fn waveform(&self, count: usize) -> Vec<f32> {
+        let start = self.samples.len().saturating_sub(count);
+        self.samples.range(start..).copied().collect()
+    }
+
+    //Magnitudes of the first half of an FFT over the newest samples, from 0 Hz up to half the sample rate
+    pub fn spectrum(&self) -> Vec<f32> {
+        let mut real = self.waveform(SPECTRUM_SIZE);
+        if real.len() < SPECTRUM_SIZE {
+            return Vec::new();
+        }
+
+        //Hann window to keep the edges of the buffer from smearing the spectrum
+        for (i, sample) in real.iter_mut().enumerate() {
+            *sample *= 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (SPECTRUM_SIZE - 1) as f32).cos();
+        }
+        let mut imaginary = vec![0.0; SPECTRUM_SIZE];
+        fft(&mut real, &mut imaginary);
+
+        real.iter().zip(imaginary.iter())
+            .take(SPECTRUM_SIZE / 2)
+            .map(|(re, im)| (re * re + im * im).sqrt() / (SPECTRUM_SIZE / 4) as f32)
+            .collect()
+    }
+}
+
+//In-place iterative radix-2 FFT
+fn fft(real: &mut [f32], imaginary: &mut [f32]) {
+    let n = real.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imaginary.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let angle = -2.0 * std::f32::consts::PI / length as f32;
+        for start in (0..n).step_by(length) {
+            for k in 0..length / 2 {
+                let (w_im, w_re) = (angle * k as f32).sin_cos();
+                let even = start + k;
+                let odd = even + length / 2;
+                let odd_re = real[odd] * w_re - imaginary[odd] * w_im;
+                let odd_im = real[odd] * w_im + imaginary[odd] * w_re;
+                real[odd] = real[even] - odd_re;
+                imaginary[odd] = imaginary[even] - odd_im;
+                real[even] += odd_re;
+                imaginary[even] += odd_im;
+            }
+        }
+        length <<= 1;
+    }
+}
@@ -0,0 +1,25 @@
+use std::{sync::{Arc, Condvar, Mutex}, time::Duration};
+
+//Lets an emulation thread that has nothing to do block until the UI gives it something, rather than polling.
+//Whoever changes what the thread is waiting on has to call wake afterwards
+#[derive(Clone, Default)]
+pub struct WakeSignal {
+    state: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl WakeSignal {
+    pub fn wake(&self) {
+        let (wake_count, condvar) = &*self.state;
+        *wake_count.lock().unwrap() += 1;
+        condvar.notify_all();
+    }
+
+    //Blocks while condition holds. It's checked again every few seconds anyway, in case a change went without a wake
+    pub fn wait_while(&self, condition: impl Fn() -> bool) {
+        let (wake_count, condvar) = &*self.state;
+        let mut lock = wake_count.lock().unwrap();
+        while condition() {
+            lock = condvar.wait_timeout(lock, Duration::from_secs(5)).unwrap().0;
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicU8, Ordering}, OnceLock}};
+
+use serde::{Deserialize, Serialize};
+
+pub const LANGUAGE_KEY: &str = "language";
+
+//Each language is a catalog of `key = text` lines in assets/i18n. Adding a language means adding a
+//catalog there and a variant here. Keys missing from a catalog fall back to English
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    //Always shown in the language itself so it can be found no matter what the UI is set to
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    fn catalog_source(&self) -> &'static str {
+        match self {
+            Language::English => include_str!("../../assets/i18n/en.lang"),
+            Language::Spanish => include_str!("../../assets/i18n/es.lang"),
+        }
+    }
+}
+
+static CURRENT_LANGUAGE: AtomicU8 = AtomicU8::new(0);
+static CATALOGS: OnceLock<HashMap<Language, HashMap<&'static str, &'static str>>> = OnceLock::new();
+
+pub fn set_language(language: Language) {
+    CURRENT_LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    Language::ALL[CURRENT_LANGUAGE.load(Ordering::Relaxed) as usize]
+}
+
+//Looks up a UI string in the current language. Unknown keys are returned as is so they stand out
+pub fn tr(key: &'static str) -> &'static str {
+    let catalogs = CATALOGS.get_or_init(|| {
+        Language::ALL.iter().map(|language| (*language, parse_catalog(language.catalog_source()))).collect()
+    });
+
+    catalogs[&language()].get(key)
+        .or_else(|| catalogs[&Language::English].get(key))
+        .copied()
+        .unwrap_or(key)
+}
+
+//Like tr, but replaces `{name}` placeholders with the given values
+pub fn tr_args(key: &'static str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let mut text = tr(key).to_owned();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+//Blank lines and lines starting with # are ignored
+fn parse_catalog(source: &'static str) -> HashMap<&'static str, &'static str> {
+    let mut catalog = HashMap::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) => {
+                catalog.insert(key.trim(), value.trim());
+            }
+            None => println!("ERROR: Invalid line in language catalog: {}", line)
+        }
+    }
+    catalog
+}
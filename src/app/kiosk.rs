@@ -0,0 +1,32 @@
+use std::env;
+
+use super::gbemu::joypad::JoypadState;
+
+//Runs fullscreen with only the game on screen and only the gamepad for input, for cabinets and handhelds built around
+//gb_rs. Settings are used as they were last saved and nothing is saved while in kiosk mode
+pub const KIOSK_FLAG: &str = "--kiosk";
+
+//How long Start and Select have to be held together to quit, since there's no keyboard or menu to do it with
+const EXIT_HOLD_FRAMES: u32 = 180;
+
+pub fn is_requested() -> bool {
+    env::args().skip(1).any(|arg| arg == KIOSK_FLAG)
+}
+
+#[derive(Default)]
+pub struct ExitCombo {
+    held_frames: u32,
+}
+
+impl ExitCombo {
+    //Called once a frame. Returns true once the combo has been held long enough
+    pub fn update(&mut self, joypad: &JoypadState) -> bool {
+        if joypad.start && joypad.select {
+            self.held_frames += 1;
+        }
+        else {
+            self.held_frames = 0;
+        }
+        self.held_frames == EXIT_HOLD_FRAMES
+    }
+}
@@ -0,0 +1,61 @@
+use std::{fs::{self, File}, io::{self, Read}, path::Path};
+
+use flate2::read::GzDecoder;
+
+use super::cartridge_info::{CartridgeError, MAX_ROM_SIZE};
+
+pub const ROM_EXTENSIONS: [&str; 4] = ["gb", "gbc", "zip", "gz"];
+
+fn is_rom_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".gb") || name.ends_with(".gbc")
+}
+
+//Reads a rom, taking it out of a .zip or .gz archive if it's in one. Also returns the path the rom's saves, states and
+//other files are kept under, which for an archive is the rom's own name next to the archive. That way saves don't
+//change when a rom gets zipped up, and every rom in an archive keeps its own
+pub fn read_rom(path: &str) -> Result<(Vec<u8>, String), CartridgeError> {
+    let lowercase_path = path.to_ascii_lowercase();
+    if lowercase_path.ends_with(".zip") {
+        read_zip(path)
+    }
+    else if lowercase_path.ends_with(".gz") {
+        let rom = read_capped(GzDecoder::new(File::open(path)?))?;
+        let inner_path = &path[..path.len() - ".gz".len()];
+        let inner_path = if is_rom_name(inner_path) {inner_path.to_owned()} else {inner_path.to_owned() + ".gb"};
+        Ok((rom, inner_path))
+    }
+    else {
+        Ok((fs::read(path)?, path.to_owned()))
+    }
+}
+
+//Takes the first .gb or .gbc file in the archive, in the order the archive lists them
+fn read_zip(path: &str) -> Result<(Vec<u8>, String), CartridgeError> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if !entry.is_file() || !is_rom_name(entry.name()) {
+            continue;
+        }
+        //Folders inside the archive are dropped, the saves sit right next to it
+        let Some(file_name) = entry.enclosed_name().and_then(|name| name.file_name().map(|file_name| file_name.to_owned())) else {
+            continue;
+        };
+
+        let rom = read_capped(&mut entry)?;
+        let inner_path = Path::new(path).with_file_name(file_name);
+        return Ok((rom, inner_path.display().to_string()));
+    }
+    Err(io::Error::new(io::ErrorKind::NotFound, "No .gb or .gbc file in the archive").into())
+}
+
+//Archives say how big they unpack to, but that can't be trusted, so a small file can't make us fill memory
+fn read_capped(reader: impl Read) -> Result<Vec<u8>, CartridgeError> {
+    let mut rom = Vec::new();
+    reader.take(MAX_ROM_SIZE as u64 + 1).read_to_end(&mut rom)?;
+    if rom.len() > MAX_ROM_SIZE {
+        return Err(CartridgeError::TooLarge);
+    }
+    Ok(rom)
+}
@@ -0,0 +1,121 @@
+use std::{fs, io::Read, path::PathBuf, sync::{Arc, Mutex}, thread};
+
+use super::{cartridge_info, gbemu::fnv, i18n::{tr, tr_args}};
+
+#[derive(Clone)]
+pub enum DownloadState {
+    Idle,
+    Downloading { received: u64, total: Option<u64> },
+    Failed(String),
+    Finished(String), //Path to the downloaded rom
+}
+
+//The "Open Rom from URL" window. Downloads are cached by URL so a rom can be reopened offline
+pub struct RomDownload {
+    pub url: String,
+    pub state: Arc<Mutex<DownloadState>>,
+}
+
+impl Default for RomDownload {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            state: Arc::new(Mutex::new(DownloadState::Idle)),
+        }
+    }
+}
+
+impl RomDownload {
+    pub fn start(&self, ctx: egui::Context) {
+        let url = self.url.trim().to_owned();
+        let state = self.state.clone();
+        *state.lock().unwrap() = DownloadState::Downloading { received: 0, total: None };
+
+        thread::spawn(move || {
+            let result = Self::download(&url, &state, &ctx);
+            *state.lock().unwrap() = match result {
+                Ok(path) => DownloadState::Finished(path.display().to_string()),
+                Err(e) => DownloadState::Failed(e),
+            };
+            ctx.request_repaint();
+        });
+    }
+
+    fn download(url: &str, state: &Arc<Mutex<DownloadState>>, ctx: &egui::Context) -> Result<PathBuf, String> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(tr("download.unsupported_url").to_owned());
+        }
+
+        let cache_path = cache_path(url).ok_or(tr("download.no_cache"))?;
+        if cache_path.exists() {
+            return Ok(cache_path);
+        }
+
+        let response = ureq::get(url).call().map_err(|e| e.to_string())?;
+        let total = response.header("Content-Length").and_then(|length| length.parse().ok());
+        let mut reader = response.into_reader();
+
+        let mut rom = Vec::new();
+        let mut buffer = [0; 0x4000];
+        loop {
+            let len = reader.read(&mut buffer).map_err(|e| e.to_string())?;
+            if len == 0 {
+                break;
+            }
+            rom.extend_from_slice(&buffer[..len]);
+
+            *state.lock().unwrap() = DownloadState::Downloading { received: rom.len() as u64, total };
+            ctx.request_repaint();
+        }
+
+        if !cartridge_info::is_valid_rom(&rom) {
+            return Err(tr("download.not_a_rom").to_owned());
+        }
+
+        fs::create_dir_all(cache_path.parent().unwrap()).map_err(|e| e.to_string())?;
+        fs::write(&cache_path, rom).map_err(|e| e.to_string())?;
+        Ok(cache_path)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        let state = self.state.lock().unwrap().clone();
+        let is_downloading = matches!(state, DownloadState::Downloading { .. });
+
+        ui.horizontal(|ui| {
+            ui.label(tr("download.url"));
+            ui.add_enabled(!is_downloading, egui::TextEdit::singleline(&mut self.url).hint_text("https://"));
+            if ui.add_enabled(!is_downloading && !self.url.trim().is_empty(), egui::Button::new(tr("download.download"))).clicked() {
+                self.start(ui.ctx().clone());
+            }
+        });
+
+        match state {
+            DownloadState::Idle | DownloadState::Finished(_) => {}
+            DownloadState::Downloading { received, total: Some(total) } => {
+                ui.add(egui::ProgressBar::new(received as f32 / total.max(1) as f32).show_percentage());
+            }
+            DownloadState::Downloading { received, total: None } => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(tr_args("download.received", &[("bytes", &received)]));
+                });
+            }
+            DownloadState::Failed(e) => {
+                ui.colored_label(ui.visuals().error_fg_color, e);
+            }
+        }
+    }
+}
+
+//Roms are cached under the app's storage directory, named by a hash of the URL plus the file name
+fn cache_path(url: &str) -> Option<PathBuf> {
+    let hash = fnv::hash(url.bytes());
+
+    let file_name: String = url.split(['?', '#']).next().unwrap_or(url)
+                               .rsplit('/').next().unwrap_or("rom")
+                               .chars().filter(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-' || *c == '_')
+                               .collect();
+    let file_name = if file_name.ends_with(".gb") || file_name.ends_with(".gbc") {file_name} else {file_name + ".gb"};
+
+    Some(eframe::storage_dir("gb_rs")?.join("rom_cache").join(format!("{:016x}-{}", hash, file_name)))
+}
@@ -0,0 +1,142 @@
+use std::{sync::{Arc, Mutex}, thread};
+
+use super::i18n::{tr, tr_args};
+
+pub const UPDATE_CHECK_KEY: &str = "update_check";
+
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Clone)]
+pub struct Release {
+    pub version: String,
+    pub notes: String,
+    pub url: String, //The release page, which has the builds
+}
+
+#[derive(Clone)]
+pub enum UpdateState {
+    Idle,
+    Checking,
+    UpToDate,
+    Available(Vec<Release>), //Newest first
+    Failed(String),
+}
+
+//Looks for newer versions on the GitHub releases feed. Nothing is requested unless the user turned the check on or
+//asked for it, and the request is a plain download of the public feed. Checks made on startup stay quiet unless
+//they find something
+pub fn check(state: Arc<Mutex<UpdateState>>, ctx: egui::Context, is_manual: bool) {
+    *state.lock().unwrap() = UpdateState::Checking;
+
+    thread::spawn(move || {
+        let result = fetch_releases();
+        *state.lock().unwrap() = match result {
+            Ok(releases) if !releases.is_empty() => UpdateState::Available(releases),
+            Ok(_) if is_manual => UpdateState::UpToDate,
+            Err(e) if is_manual => UpdateState::Failed(e),
+            Ok(_) => UpdateState::Idle,
+            Err(e) => {
+                println!("ERROR: Could not check for updates: {}", e);
+                UpdateState::Idle
+            }
+        };
+        ctx.request_repaint();
+    });
+}
+
+fn fetch_releases() -> Result<Vec<Release>, String> {
+    let feed_url = format!("{}/releases.atom", env!("CARGO_PKG_REPOSITORY"));
+    let feed = ureq::get(&feed_url).call().and_then(|response| Ok(response.into_string()?)).map_err(|e| e.to_string())?;
+
+    let current = parse_version(CURRENT_VERSION).ok_or("Invalid version")?;
+    let releases = feed.split("<entry>").skip(1)
+        .filter_map(parse_entry)
+        .filter(|release| parse_version(&release.version).is_some_and(|version| version > current))
+        .collect();
+    Ok(releases)
+}
+
+//Atom entries link to the release page, whose last path segment is the tag
+fn parse_entry(entry: &str) -> Option<Release> {
+    let url = between(entry, "<link rel=\"alternate\" type=\"text/html\" href=\"", "\"")?;
+    let tag = url.rsplit('/').next()?;
+    let notes = between(entry, "<content type=\"html\">", "</content>").map(html_to_text).unwrap_or_default();
+
+    Some(Release {
+        version: tag.trim_start_matches('v').to_owned(),
+        notes,
+        url: url.to_owned(),
+    })
+}
+
+fn between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let text = &text[text.find(start)? + start.len()..];
+    Some(&text[..text.find(end)?])
+}
+
+//Pre-releases like 1.2.0-beta are skipped so only finished versions are offered
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+//The changelog comes as escaped HTML. Block ends become line breaks and the rest of the markup is dropped
+fn html_to_text(escaped: &str) -> String {
+    let html = escaped.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&#39;", "'").replace("&amp;", "&");
+    let html = html.replace("<li>", "<li>- ").replace("</p>", "\n").replace("</li>", "\n").replace("<br>", "\n");
+    let html = html.replace("</h1>", "\n").replace("</h2>", "\n").replace("</h3>", "\n");
+
+    let mut text = String::new();
+    let mut is_in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => is_in_tag = true,
+            '>' => is_in_tag = false,
+            _ if !is_in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join("\n")
+}
+
+//The window with the result of the last check, until it's dismissed
+pub fn show(state: &Arc<Mutex<UpdateState>>, ctx: &egui::Context) {
+    let current_state = state.lock().unwrap().clone();
+    let mut is_dismissed = false;
+    match &current_state {
+        UpdateState::Available(releases) => {
+            egui::Window::new(tr("update.title")).collapsible(false).show(ctx, |ui| {
+                ui.label(tr_args("update.available", &[("version", &releases[0].version), ("current", &CURRENT_VERSION)]));
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for release in releases {
+                        ui.heading(&release.version);
+                        ui.label(&release.notes);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button(tr("update.open_page")).clicked() {
+                        ctx.open_url(egui::OpenUrl::new_tab(&releases[0].url));
+                        is_dismissed = true;
+                    }
+                    if ui.button(tr("dialog.dismiss")).clicked() {
+                        is_dismissed = true;
+                    }
+                });
+            });
+        }
+        UpdateState::UpToDate | UpdateState::Failed(_) => {
+            egui::Window::new(tr("update.title")).collapsible(false).resizable(false).show(ctx, |ui| {
+                match &current_state {
+                    UpdateState::Failed(e) => ui.label(tr_args("update.failed", &[("error", e)])),
+                    _ => ui.label(tr_args("update.up_to_date", &[("current", &CURRENT_VERSION)])),
+                };
+                if ui.button(tr("dialog.dismiss")).clicked() {
+                    is_dismissed = true;
+                }
+            });
+        }
+        _ => {}
+    }
+    if is_dismissed {
+        *state.lock().unwrap() = UpdateState::Idle;
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+pub const VIEW_SETTINGS_KEY: &str = "view_settings";
+
+//Window options for keeping the game visible on top of other windows like guides or maps
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ViewSettings {
+    pub always_on_top: bool,
+    pub compact: bool, //Only the game screen, without the menu bar, tabs or window decorations
+    pub opacity: f32,
+}
+
+impl Default for ViewSettings {
+    fn default() -> Self {
+        Self {
+            always_on_top: false,
+            compact: false,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl ViewSettings {
+    pub fn apply(&self, ctx: &egui::Context) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(if self.always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        }
+        else {
+            egui::WindowLevel::Normal
+        }));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(!self.compact));
+    }
+
+    //The window itself is created transparent, so opacity comes from how opaque the panels are drawn
+    pub fn apply_opacity(&self, ctx: &egui::Context) {
+        let base_visuals = if ctx.style().visuals.dark_mode {
+            egui::Visuals::dark()
+        }
+        else {
+            egui::Visuals::light()
+        };
+        ctx.style_mut(|style| {
+            style.visuals.panel_fill = base_visuals.panel_fill.gamma_multiply(self.opacity);
+            style.visuals.window_fill = base_visuals.window_fill.gamma_multiply(self.opacity);
+            style.visuals.extreme_bg_color = base_visuals.extreme_bg_color.gamma_multiply(self.opacity);
+        });
+    }
+}
@@ -0,0 +1,1135 @@
+use std::{sync::atomic::Ordering, time::Instant};
+
+use egui::Shape;
+use egui_dock::{DockState, NodeIndex, TabViewer};
+use serde::{Deserialize, Serialize};
+
+use super::{border, i18n::{tr, tr_args}, gbemu::{text_art, frame_dump, debug_session::{StepRequest, WatchpointEntry}, monitor::{self, Command}, heatmap::HeatmapRegion, memory_editor::MemoryRegion, irq_flash::{self, IrqFlashSettings}, input::{self, KeyType}, pacing::{self, SyncMode}, trace_log::TraceMode, gdb_stub, inspector::PPUSnapshot, GBEmu}, CGBState};
+
+pub const WORKSPACE_KEY: &str = "workspace";
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WorkspaceTab {
+    Game,
+    RomInfo,
+    ConsoleOutput,
+    Apu,
+    Controls,
+    Debugger,
+    Disassembly,
+    MemoryEditor,
+    AudioVisualizer,
+    MemoryHeatmap,
+    Magnifier,
+    Stats,
+}
+
+impl WorkspaceTab {
+    pub const ALL: [WorkspaceTab; 12] = [WorkspaceTab::Game, WorkspaceTab::RomInfo, WorkspaceTab::ConsoleOutput, WorkspaceTab::Apu, WorkspaceTab::Controls, WorkspaceTab::Debugger, WorkspaceTab::Disassembly, WorkspaceTab::MemoryEditor, WorkspaceTab::AudioVisualizer, WorkspaceTab::MemoryHeatmap, WorkspaceTab::Magnifier, WorkspaceTab::Stats];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            WorkspaceTab::Game => tr("tab.game"),
+            WorkspaceTab::RomInfo => tr("tab.rom_info"),
+            WorkspaceTab::ConsoleOutput => tr("tab.console_output"),
+            WorkspaceTab::Apu => tr("tab.apu"),
+            WorkspaceTab::Controls => tr("tab.controls"),
+            WorkspaceTab::Debugger => tr("tab.debugger"),
+            WorkspaceTab::Disassembly => tr("tab.disassembly"),
+            WorkspaceTab::MemoryEditor => tr("tab.memory_editor"),
+            WorkspaceTab::AudioVisualizer => tr("tab.audio_visualizer"),
+            WorkspaceTab::MemoryHeatmap => tr("tab.memory_heatmap"),
+            WorkspaceTab::Magnifier => tr("tab.magnifier"),
+            WorkspaceTab::Stats => tr("tab.stats"),
+        }
+    }
+}
+
+//The game on the left with the tools stacked on the right
+pub fn default_layout() -> DockState<WorkspaceTab> {
+    let mut workspace = DockState::new(vec![WorkspaceTab::Game]);
+    let [_, tools] = workspace.main_surface_mut().split_right(NodeIndex::root(), 0.65, vec![WorkspaceTab::RomInfo, WorkspaceTab::Controls]);
+    workspace.main_surface_mut().split_below(tools, 0.5, vec![WorkspaceTab::ConsoleOutput, WorkspaceTab::Apu, WorkspaceTab::Debugger]);
+    workspace
+}
+
+//The core names these in English. Hardware names like VRAM or OAM are the same in every language
+fn trace_mode_name(mode: TraceMode) -> &'static str {
+    match mode {
+        TraceMode::Off => tr("trace.off"),
+        TraceMode::File => tr("trace.file"),
+        TraceMode::RingBuffer => tr("trace.ring_buffer"),
+    }
+}
+
+fn memory_region_name(region: MemoryRegion) -> &'static str {
+    match region {
+        MemoryRegion::CartridgeRam => tr("memory.cartridge_ram"),
+        _ => region.name(),
+    }
+}
+
+fn heatmap_region_name(region: HeatmapRegion) -> &'static str {
+    match region {
+        HeatmapRegion::CartridgeRam => tr("memory.cartridge_ram"),
+        _ => region.name(),
+    }
+}
+
+pub struct WorkspaceViewer<'a> {
+    pub emu: &'a GBEmu,
+}
+
+impl TabViewer for WorkspaceViewer<'_> {
+    type Tab = WorkspaceTab;
+
+    fn title(&mut self, tab: &mut Self::Tab) -> egui::WidgetText {
+        tab.title().into()
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Self::Tab) {
+        match tab {
+            WorkspaceTab::Game => self.game(ui),
+            WorkspaceTab::RomInfo => self.rom_info(ui),
+            WorkspaceTab::ConsoleOutput => self.console_output(ui),
+            WorkspaceTab::Apu => self.apu(ui),
+            WorkspaceTab::Controls => self.controls(ui),
+            WorkspaceTab::Debugger => self.debugger(ui),
+            WorkspaceTab::Disassembly => self.disassembly(ui),
+            WorkspaceTab::MemoryEditor => self.memory_editor(ui),
+            WorkspaceTab::AudioVisualizer => self.audio_visualizer(ui),
+            WorkspaceTab::MemoryHeatmap => self.memory_heatmap(ui),
+            WorkspaceTab::Magnifier => self.magnifier(ui),
+            WorkspaceTab::Stats => self.stats(ui),
+        }
+    }
+
+    fn scroll_bars(&self, tab: &Self::Tab) -> [bool; 2] {
+        match tab {
+            WorkspaceTab::Game | WorkspaceTab::Magnifier | WorkspaceTab::Disassembly | WorkspaceTab::MemoryEditor => [false, false],
+            _ => [true, true]
+        }
+    }
+}
+
+impl WorkspaceViewer<'_> {
+    pub fn game(&self, ui: &mut egui::Ui) {
+        ui.multiply_opacity(self.emu.view_settings.lock().unwrap().opacity);
+
+        //Fit the screen, or the border and the screen inside it, to the tab while keeping the aspect ratio
+        let rect = ui.max_rect();
+        let border_settings = self.emu.border_settings.lock().unwrap().clone();
+        let mut border_cache = self.emu.border_cache.lock().unwrap();
+        let border = border_cache.get(ui.ctx(), &border_settings.image_path);
+        let (image_rect, screen_rect) = match border {
+            Some(border) => {
+                let (image_rect, screen_rect) = border.layout(rect);
+                (Some(image_rect), screen_rect)
+            }
+            None => (None, border::fit_rect(rect, 160.0 / 144.0))
+        };
+
+        //Overscan crops the edges by drawing the screen bigger than the space it's clipped to
+        let overscan = border_settings.overscan.clamp(0.0, 0.2);
+        let game_width = screen_rect.width() / (1.0 - 2.0 * overscan);
+        let game_height = screen_rect.height() / (1.0 - 2.0 * overscan);
+        let x_offset = screen_rect.left() - overscan * game_width;
+        let y_offset = screen_rect.top() - overscan * game_height;
+
+        let lock = self.emu.screen_pixels.lock().unwrap();
+        if let Some(color_array) = lock.clone() {
+            let painter = ui.painter().with_clip_rect(screen_rect);
+            let pixel_stack: Vec<Shape> = color_array.iter()
+                       .map(|pixel| Shape::Rect(pixel.to_rect(game_height, game_width, y_offset, x_offset)))
+                       .collect();
+
+            painter.extend(pixel_stack);
+            self.emu.av_sync.lock().unwrap().presented(Instant::now());
+        }
+        drop(lock);
+
+        if let (Some(border), Some(image_rect)) = (border, image_rect) {
+            border.paint(ui.painter(), image_rect);
+        }
+        drop(border_cache);
+
+        //Script text is sized like the game's 8x8 tiles, with a shadow so it reads over anything
+        let overlay = self.emu.script_overlay.lock().unwrap();
+        if !overlay.is_empty() {
+            let painter = ui.painter().with_clip_rect(screen_rect);
+            let pixel_size = game_height / 144.0;
+            let font = egui::FontId::monospace(8.0 * pixel_size);
+            for text in overlay.iter() {
+                let position = egui::pos2(x_offset + text.x * pixel_size, y_offset + text.y * pixel_size);
+                painter.text(position + egui::vec2(pixel_size, pixel_size) * 0.5, egui::Align2::LEFT_TOP, &text.text, font.clone(), egui::Color32::BLACK);
+                painter.text(position, egui::Align2::LEFT_TOP, &text.text, font.clone(), egui::Color32::WHITE);
+            }
+        }
+        drop(overlay);
+
+        if self.emu.debug_mode.load(Ordering::Relaxed) {
+            let response = ui.interact(screen_rect, ui.id().with("game_screen"), egui::Sense::click());
+            let to_screen_position = |pointer: egui::Pos2| (
+                (((pointer.x - x_offset) / game_width * 160.0) as u8).min(159),
+                (((pointer.y - y_offset) / game_height * 144.0) as u8).min(143)
+            );
+
+            //The background tile under the cursor when the context menu was opened
+            let selected_tile_id = ui.id().with("selected_tile");
+            if response.secondary_clicked() {
+                let snapshot = self.emu.ppu_snapshot.lock().unwrap().clone();
+                let selected_tile = response.interact_pointer_pos().zip(snapshot).map(|(pointer, snapshot)| {
+                    let (x, y) = to_screen_position(pointer);
+                    let bg_tile = snapshot.bg_tile_at(x, y);
+                    (bg_tile.tile_index, bg_tile.tile_address)
+                });
+                ui.data_mut(|data| data.insert_temp(selected_tile_id, selected_tile));
+            }
+            let selected_tile: Option<(u8, u16)> = ui.data(|data| data.get_temp(selected_tile_id)).flatten();
+
+            let response = response.context_menu(|ui| {
+                if ui.button(tr("tools.copy_screen_text")).clicked() {
+                    self.copy_screen_text(ui.ctx());
+                    ui.close_menu();
+                }
+                if let Some((tile_index, tile_address)) = selected_tile {
+                    if ui.button(tr_args("debug.copy_tile_text", &[("tile", &format!("{:02X}", tile_index))])).clicked() {
+                        if let Some(snapshot) = &*self.emu.ppu_snapshot.lock().unwrap() {
+                            ui.ctx().copy_text(text_art::tile_to_text(snapshot, tile_address));
+                        }
+                        ui.close_menu();
+                    }
+                }
+            }).map_or(response, |inner| inner.response);
+
+            if let Some(pointer) = response.hover_pos() {
+                let (x, y) = to_screen_position(pointer);
+                let snapshot = self.emu.ppu_snapshot.lock().unwrap().clone();
+                response.on_hover_ui_at_pointer(|ui| Self::hover_inspection(ui, x, y, snapshot));
+            }
+        }
+    }
+
+    pub fn copy_screen_text(&self, ctx: &egui::Context) {
+        if let Some(shades) = &*self.emu.screen_shades.lock().unwrap() {
+            ctx.copy_text(text_art::screen_to_text(shades));
+        }
+    }
+
+    fn hover_inspection(ui: &mut egui::Ui, x: u8, y: u8, snapshot: Option<PPUSnapshot>) {
+        ui.monospace(format!("X: {:3}  Y: {:3}", x, y));
+
+        let Some(snapshot) = snapshot else {
+            return;
+        };
+
+        let bg_tile = snapshot.bg_tile_at(x, y);
+        ui.separator();
+        ui.monospace(tr_args(if bg_tile.is_window {"inspect.window_map"} else {"inspect.bg_map"}, &[("x", &format!("{:2}", bg_tile.map_x)), ("y", &format!("{:2}", bg_tile.map_y)), ("address", &format!("{:04X}", bg_tile.map_address))]));
+        ui.monospace(tr_args("inspect.tile", &[("tile", &format!("{:02X}", bg_tile.tile_index)), ("address", &format!("{:04X}", bg_tile.tile_address))]));
+        if let Some(attributes) = bg_tile.attributes {
+            ui.monospace(tr_args("inspect.attributes", &[("attributes", &format!("{:08b}", attributes))]));
+        }
+
+        for object in snapshot.objects_at(x, y) {
+            ui.separator();
+            ui.monospace(tr_args("inspect.object", &[("index", &format!("{:02}", object.oam_index)), ("x", &format!("{:3}", object.x)), ("y", &format!("{:3}", object.y))]));
+            ui.monospace(tr_args("inspect.object_tile", &[("tile", &format!("{:02X}", object.tile_index)), ("attributes", &format!("{:08b}", object.attributes))]));
+        }
+    }
+
+    fn rom_info(&self, ui: &mut egui::Ui) {
+        let lock = self.emu.rom_file_path.lock().unwrap();
+        if let Some(picked_path) = lock.clone() {
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.loaded"));
+                ui.monospace(picked_path);
+            });
+        }
+        else {
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.none"));
+            });
+        }
+        drop(lock);
+
+        let lock = self.emu.rom_info.lock().unwrap();
+        if let Some(info) = lock.clone() {
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.title"));
+                ui.monospace(info.title);
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.manufacturer_code"));
+                ui.monospace(info.manufacturer_code);
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.cgb"));
+                ui.monospace(match info.cgb_flag {
+                    CGBState::Monochrome => tr("rom_info.cgb_monochrome"),
+                    CGBState::Color => tr("rom_info.cgb_color"),
+                    CGBState::Both => tr("rom_info.cgb_both")
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.sgb"));
+                ui.monospace(format!("{}", info.is_sgb));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.licensee"));
+                ui.monospace(info.licensee);
+            });
+            ui.horizontal(|ui|{
+                ui.label(tr("rom_info.mapper"));
+                ui.label(format!("{}", info.cartridge_type));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.rom_size"));
+                ui.label(tr_args("rom_info.size", &[("bytes", &info.rom_size), ("banks", &info.rom_banks)]));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.ram_size"));
+                ui.label(tr_args("rom_info.size", &[("bytes", &info.ram_size), ("banks", &info.ram_banks)]));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.japan"));
+                ui.monospace(format!("{}", info.overseas_only));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.version"));
+                ui.monospace(format!("{}", info.version_number));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.header_checksum"));
+                ui.monospace(format!("{}", info.header_checksum));
+            });
+            ui.horizontal(|ui| {
+                ui.label(tr("rom_info.global_checksum"));
+                ui.monospace(format!("{}", info.global_checksum));
+            });
+        }
+    }
+
+    fn console_output(&self, ui: &mut egui::Ui) {
+        let lock = self.emu.console_output.lock().unwrap();
+        ui.monospace(lock.as_str());
+        drop(lock);
+    }
+
+    fn apu(&self, ui: &mut egui::Ui) {
+        let is_logging = self.emu.apu_logging_enabled.load(Ordering::Relaxed);
+        ui.horizontal(|ui| {
+            if is_logging {
+                if ui.button(tr("apu.stop_logging")).clicked() {
+                    self.emu.apu_logging_enabled.store(false, Ordering::Relaxed);
+                }
+                ui.label(tr("apu.logging"));
+            }
+            else if ui.button(tr("apu.start_logging")).clicked() {
+                self.emu.apu_logging_enabled.store(true, Ordering::Relaxed);
+            }
+        });
+    }
+
+    fn stats(&self, ui: &mut egui::Ui) {
+        let mut sync_mode = self.emu.sync_mode.lock().unwrap();
+        ui.horizontal(|ui| {
+            ui.label(tr("stats.sync_to"));
+            ui.radio_value(&mut *sync_mode, SyncMode::Video, tr("stats.sync_video")).on_hover_text(tr("stats.sync_video_hint"));
+            ui.radio_value(&mut *sync_mode, SyncMode::Audio, tr("stats.sync_audio")).on_hover_text(tr("stats.sync_audio_hint"));
+            ui.radio_value(&mut *sync_mode, SyncMode::Uncapped, tr("stats.sync_uncapped")).on_hover_text(tr("stats.sync_uncapped_hint"));
+        });
+        drop(sync_mode);
+
+        let mut frame_skip = self.emu.frame_skip.lock().unwrap();
+        ui.horizontal(|ui| {
+            ui.label(tr("stats.frame_skip"));
+            ui.add(egui::Slider::new(&mut *frame_skip, 0..=pacing::MAX_FRAME_SKIP)).on_hover_text(tr("stats.frame_skip_hint"));
+        });
+        drop(frame_skip);
+
+        let lock = self.emu.av_sync.lock().unwrap();
+        ui.horizontal(|ui| {
+            ui.label(tr("stats.av_skew"));
+            match lock.skew() {
+                Some(skew) => ui.monospace(tr_args(if skew >= 0.0 {"stats.picture_ahead"} else {"stats.sound_ahead"}, &[("ms", &format!("{:+.1}", skew * 1000.0))])),
+                None => ui.monospace(tr("stats.waiting_for_audio")),
+            };
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("stats.drift"));
+            match lock.drift() {
+                Some(drift) => ui.monospace(tr_args("stats.drift_from", &[("drift", &format!("{:+.1}", drift * 1000.0)), ("skew", &format!("{:+.1}", lock.settled_skew().unwrap_or_default() * 1000.0))])),
+                None => ui.monospace(tr("stats.settling")),
+            };
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("stats.corrections"));
+            ui.monospace(tr_args("stats.corrections_count", &[("dropped", &lock.dropped_frames), ("duplicated", &lock.duplicated_frames)]));
+        });
+        drop(lock);
+
+        let mut is_correcting = self.emu.av_sync_correction.load(Ordering::Relaxed);
+        if ui.checkbox(&mut is_correcting, tr("stats.correct_drift")).changed() {
+            self.emu.av_sync_correction.store(is_correcting, Ordering::Relaxed);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(tr("stats.frame_dump_queue"));
+            let queue = &self.emu.frame_dump_queue;
+            ui.monospace(tr_args("stats.frame_dump_queue_count", &[("queued", &queue.queued()), ("capacity", &frame_dump::QUEUE_CAPACITY), ("dropped", &queue.dropped())]));
+        });
+    }
+
+    fn audio_visualizer(&self, ui: &mut egui::Ui) {
+        const CHANNEL_NAMES: [&str; 4] = ["audio_channel.pulse_1", "audio_channel.pulse_2", "audio_channel.wave", "audio_channel.noise"];
+
+        ui.horizontal(|ui| {
+            let mut audio_channels = self.emu.audio_channels.lock().unwrap();
+            for (channel, name) in CHANNEL_NAMES.iter().enumerate() {
+                ui.checkbox(&mut audio_channels[channel], tr(name));
+                let is_solo = audio_channels.iter().enumerate().all(|(other, is_enabled)| *is_enabled == (other == channel));
+                if ui.selectable_label(is_solo, tr("audio_channel.solo")).on_hover_text(tr("audio_channel.solo_hint")).clicked() {
+                    *audio_channels = if is_solo {[true; 4]} else {std::array::from_fn(|other| other == channel)};
+                }
+                ui.separator();
+            }
+        });
+
+        let mut is_enabled = self.emu.audio_visualizer_enabled.load(Ordering::Relaxed);
+        if ui.checkbox(&mut is_enabled, tr("visualizer.capture")).changed() {
+            self.emu.audio_visualizer_enabled.store(is_enabled, Ordering::Relaxed);
+        }
+        if !is_enabled {
+            return;
+        }
+
+        let lock = self.emu.audio_visualizer.lock().unwrap();
+        let waveform = lock.waveform(1024);
+        let spectrum = lock.spectrum();
+        let sample_rate = lock.sample_rate;
+        drop(lock);
+
+        let stroke = egui::Stroke::new(1.0, ui.visuals().strong_text_color());
+
+        ui.label(tr("visualizer.waveform"));
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 100.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        if waveform.len() > 1 {
+            let points = waveform.iter().enumerate()
+                .map(|(i, sample)| egui::pos2(
+                    rect.left() + i as f32 / (waveform.len() - 1) as f32 * rect.width(),
+                    rect.center().y - sample.clamp(-1.0, 1.0) * rect.height() / 2.0
+                ))
+                .collect();
+            painter.add(Shape::line(points, stroke));
+        }
+
+        ui.label(tr_args("visualizer.spectrum", &[("hz", &format!("{:.0}", sample_rate / 2.0))]));
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 100.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        if !spectrum.is_empty() {
+            let bar_width = rect.width() / spectrum.len() as f32;
+            for (i, magnitude) in spectrum.iter().enumerate() {
+                let height = magnitude.min(1.0).sqrt() * rect.height();
+                let x = rect.left() + i as f32 * bar_width;
+                painter.rect_filled(egui::Rect::from_min_max(egui::pos2(x, rect.bottom() - height), egui::pos2(x + bar_width.max(1.0), rect.bottom())), 0.0, stroke.color);
+            }
+
+            if let Some(pointer) = response.hover_pos() {
+                let frequency = (pointer.x - rect.left()) / rect.width() * sample_rate / 2.0;
+                response.on_hover_text_at_pointer(format!("{:.0} Hz", frequency));
+            }
+        }
+    }
+
+    fn memory_heatmap(&self, ui: &mut egui::Ui) {
+        const BYTES_PER_ROW: usize = 64;
+
+        let mut is_enabled = self.emu.memory_heatmap_enabled.load(Ordering::Relaxed);
+        if ui.checkbox(&mut is_enabled, tr("heatmap.enabled")).changed() {
+            self.emu.memory_heatmap_enabled.store(is_enabled, Ordering::Relaxed);
+        }
+        if !is_enabled {
+            return;
+        }
+
+        let region_id = ui.make_persistent_id("heatmap_region");
+        let mut region = ui.data(|data| data.get_temp(region_id)).unwrap_or(HeatmapRegion::Wram);
+        ui.horizontal(|ui| {
+            for option in HeatmapRegion::ALL {
+                ui.radio_value(&mut region, option, heatmap_region_name(option));
+            }
+            ui.label(tr("heatmap.legend"));
+        });
+        ui.data_mut(|data| data.insert_temp(region_id, region));
+
+        let lock = self.emu.memory_heatmap.lock().unwrap();
+        let heat: Vec<(f32, f32)> = (0..region.size()).map(|offset| lock.heat(region.start_address() + offset as u16)).collect();
+        drop(lock);
+
+        //Log scale so a loop hammering one address doesn't wash out everything else
+        let max_heat = heat.iter().fold(1.0_f32, |max, (reads, writes)| max.max(*reads).max(*writes));
+        let scale = |heat: f32| ((1.0 + heat).ln() / (1.0 + max_heat).ln() * 255.0) as u8;
+
+        let cell_size = (ui.available_width() / BYTES_PER_ROW as f32).clamp(2.0, 8.0);
+        let rows = region.size() / BYTES_PER_ROW;
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(cell_size * BYTES_PER_ROW as f32, cell_size * rows as f32), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+        for (offset, (reads, writes)) in heat.iter().enumerate() {
+            if *reads == 0.0 && *writes == 0.0 {
+                continue;
+            }
+            let position = rect.min + egui::vec2((offset % BYTES_PER_ROW) as f32, (offset / BYTES_PER_ROW) as f32) * cell_size;
+            let color = egui::Color32::from_rgb(scale(*writes), scale(*reads) / 2, scale(*reads));
+            painter.rect_filled(egui::Rect::from_min_size(position, egui::vec2(cell_size, cell_size)), 0.0, color);
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            let column = ((pointer.x - rect.left()) / cell_size) as usize;
+            let row = ((pointer.y - rect.top()) / cell_size) as usize;
+            let offset = (row * BYTES_PER_ROW + column).min(region.size() - 1);
+            let (reads, writes) = heat[offset];
+            let reads = tr_args("heatmap.reads", &[("reads", &format!("{:.1}", reads))]);
+            let writes = tr_args("heatmap.writes", &[("writes", &format!("{:.1}", writes))]);
+            response.on_hover_text_at_pointer(format!("{:04X}\n{}\n{}", region.start_address() as usize + offset, reads, writes));
+        }
+    }
+
+    //Scrolls to the current instruction whenever the program counter moves
+    fn disassembly(&self, ui: &mut egui::Ui) {
+        let mut is_enabled = self.emu.disassembly_enabled.load(Ordering::Relaxed);
+        if ui.checkbox(&mut is_enabled, tr("disassembly.enabled")).on_hover_text(tr("debugger.updated_hint")).changed() {
+            self.emu.disassembly_enabled.store(is_enabled, Ordering::Relaxed);
+        }
+        if !is_enabled {
+            return;
+        }
+
+        let view = self.emu.disassembly.lock().unwrap().clone();
+        let shown_id = ui.make_persistent_id("disassembly_program_counter");
+        let shown_program_counter: Option<u16> = ui.data(|data| data.get_temp(shown_id));
+        let is_moved = shown_program_counter != Some(view.program_counter);
+        ui.data_mut(|data| data.insert_temp(shown_id, view.program_counter));
+
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+            egui::Grid::new("disassembly").striped(true).show(ui, |ui| {
+                for (label, instruction) in &view.lines {
+                    if let Some(label) = label {
+                        ui.label("");
+                        ui.label("");
+                        ui.strong(format!("{}:", label));
+                        ui.end_row();
+                    }
+
+                    let is_current = instruction.address == view.program_counter;
+                    let current_color = ui.visuals().warn_fg_color;
+                    let highlight = |text: String| {
+                        let text = egui::RichText::new(text).monospace();
+                        if is_current {text.color(current_color).strong()} else {text}
+                    };
+                    let response = ui.label(highlight(format!("{} ${:04X}", if is_current {">"} else {" "}, instruction.address)));
+                    ui.label(highlight(instruction.hex()));
+                    ui.label(highlight(instruction.text.clone()));
+                    ui.end_row();
+                    if is_current && is_moved {
+                        response.scroll_to_me(Some(egui::Align::Center));
+                    }
+                }
+            });
+        });
+    }
+
+    //Click a byte to edit it. Enter writes it and moves on to the next one, Escape or clicking away cancels
+    fn memory_editor(&self, ui: &mut egui::Ui) {
+        const BYTES_PER_ROW: usize = 16;
+
+        let mut is_enabled = self.emu.memory_editor_enabled.load(Ordering::Relaxed);
+        if ui.checkbox(&mut is_enabled, tr("memory_editor.enabled")).on_hover_text(tr("debugger.updated_hint")).changed() {
+            self.emu.memory_editor_enabled.store(is_enabled, Ordering::Relaxed);
+            self.emu.memory_editor.lock().unwrap().is_stale = true;
+            self.emu.wake.wake();
+        }
+        if !is_enabled {
+            return;
+        }
+
+        let mut memory_editor = self.emu.memory_editor.lock().unwrap();
+        let (mut region, mut bank) = (memory_editor.region, memory_editor.bank);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("memory_editor_region").selected_text(memory_region_name(region)).show_ui(ui, |ui| {
+                for option in MemoryRegion::ALL {
+                    ui.selectable_value(&mut region, option, memory_region_name(option));
+                }
+            });
+            if let Some(first_bank) = region.first_bank() {
+                ui.label(tr("memory_editor.bank"));
+                ui.add(egui::DragValue::new(&mut bank).range(first_bank..=0x1FF).hexadecimal(2, false, true));
+            }
+        });
+        if region != memory_editor.region || bank != memory_editor.bank {
+            (memory_editor.region, memory_editor.bank) = (region, bank);
+            memory_editor.bytes.clear();
+            memory_editor.is_stale = true;
+            self.emu.wake.wake();
+        }
+        let bytes = memory_editor.bytes.clone();
+        drop(memory_editor);
+
+        let start_address = region.start_address();
+        let edit_id = ui.make_persistent_id("memory_editor_edit");
+        let mut edit: Option<(u16, String)> = ui.data(|data| data.get_temp(edit_id));
+        let mut typed = None;
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace) + ui.spacing().item_spacing.y;
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show_rows(ui, row_height, bytes.len().div_ceil(BYTES_PER_ROW), |ui, rows| {
+            for row in rows {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(format!("${:04X}", start_address as usize + row * BYTES_PER_ROW)).monospace().strong());
+                    for (offset, byte) in bytes.iter().enumerate().skip(row * BYTES_PER_ROW).take(BYTES_PER_ROW) {
+                        let address = start_address + offset as u16;
+                        match &mut edit {
+                            Some((edit_address, text)) if *edit_address == address => {
+                                let response = ui.add(egui::TextEdit::singleline(text).font(egui::TextStyle::Monospace).char_limit(2).desired_width(ui.text_style_height(&egui::TextStyle::Monospace)));
+                                if !response.has_focus() && !response.lost_focus() {
+                                    response.request_focus();
+                                }
+                                else if response.lost_focus() {
+                                    typed = Some((address, ui.input(|input| input.key_pressed(egui::Key::Enter))));
+                                }
+                            },
+                            _ => {
+                                let text = byte.map_or("--".to_owned(), |byte| format!("{:02X}", byte));
+                                if ui.add(egui::Label::new(egui::RichText::new(text).monospace()).sense(egui::Sense::click())).clicked() {
+                                    edit = byte.map(|byte| (address, format!("{:02X}", byte)));
+                                }
+                            },
+                        }
+                    }
+                });
+            }
+        });
+
+        //Clicking another byte moved the edit there already
+        if let Some((address, is_entered)) = typed.filter(|(address, _)| edit.as_ref().is_some_and(|(edit_address, _)| edit_address == address)) {
+            let value = edit.take().and_then(|(_, text)| u8::from_str_radix(text.trim(), 16).ok());
+            if let (true, Some(value)) = (is_entered, value) {
+                let mut memory_editor = self.emu.memory_editor.lock().unwrap();
+                memory_editor.edits.push((region.bank(bank), address, value));
+                memory_editor.is_stale = true;
+                if let Some(next_address) = address.checked_add(1).filter(|next_address| ((*next_address - start_address) as usize) < bytes.len()) {
+                    let next_byte = memory_editor.bytes.get((next_address - start_address) as usize).copied().flatten();
+                    edit = next_byte.map(|byte| (next_address, format!("{:02X}", byte)));
+                }
+                drop(memory_editor);
+                self.emu.wake.wake();
+            }
+        }
+        ui.data_mut(|data| match edit {
+            Some(edit) => data.insert_temp(edit_id, edit),
+            None => data.remove::<(u16, String)>(edit_id),
+        });
+    }
+
+    fn magnifier(&self, ui: &mut egui::Ui) {
+        let mut is_enabled = self.emu.magnifier_enabled.load(Ordering::Relaxed);
+        if ui.checkbox(&mut is_enabled, tr("magnifier.enabled")).changed() {
+            self.emu.magnifier_enabled.store(is_enabled, Ordering::Relaxed);
+        }
+        if !is_enabled {
+            return;
+        }
+
+        //Zoom in screen pixels per Game Boy pixel, and the Game Boy position at the middle of the view
+        let view_id = ui.make_persistent_id("magnifier_view");
+        let (mut zoom, mut center, mut show_grid) = ui.data(|data| data.get_temp(view_id)).unwrap_or((8.0_f32, egui::vec2(80.0, 72.0), true));
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut zoom, 1.0..=64.0).logarithmic(true).text(tr("magnifier.zoom")));
+            ui.checkbox(&mut show_grid, tr("magnifier.grid"));
+            if ui.button(tr("magnifier.reset")).clicked() {
+                (zoom, center) = (8.0, egui::vec2(80.0, 72.0));
+            }
+        });
+
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::drag());
+        if response.dragged() {
+            center -= response.drag_delta() / zoom;
+        }
+        //Scrolling zooms around the pointer so whatever is under it stays put
+        if let Some(pointer) = response.hover_pos() {
+            let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                let pointed_at = center + (pointer - rect.center()) / zoom;
+                zoom = (zoom * (scroll / 200.0).exp()).clamp(1.0, 64.0);
+                center = pointed_at - (pointer - rect.center()) / zoom;
+            }
+        }
+        center = center.clamp(egui::vec2(0.0, 0.0), egui::vec2(160.0, 144.0));
+        ui.data_mut(|data| data.insert_temp(view_id, (zoom, center, show_grid)));
+
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::from_gray(24));
+        let lock = self.emu.raw_frame.lock().unwrap();
+        let Some(frame) = &*lock else {
+            return;
+        };
+
+        //Only the pixels in view are painted
+        let origin = rect.center() - center * zoom;
+        let first_x = ((rect.left() - origin.x) / zoom).floor().max(0.0) as usize;
+        let last_x = ((rect.right() - origin.x) / zoom).ceil().clamp(0.0, 160.0) as usize;
+        let first_y = ((rect.top() - origin.y) / zoom).floor().max(0.0) as usize;
+        let last_y = ((rect.bottom() - origin.y) / zoom).ceil().clamp(0.0, 144.0) as usize;
+        for y in first_y..last_y {
+            for x in first_x..last_x {
+                let pixel_rect = egui::Rect::from_min_size(origin + egui::vec2(x as f32, y as f32) * zoom, egui::vec2(zoom, zoom));
+                painter.rect_filled(pixel_rect, 0.0, frame.colors[y][x]);
+            }
+        }
+
+        //Tile boundaries are drawn stronger than pixel boundaries
+        if show_grid && zoom >= 4.0 {
+            let line_color = |i: usize| if i % 8 == 0 {egui::Color32::from_rgba_unmultiplied(255, 64, 64, 160)} else {egui::Color32::from_rgba_unmultiplied(128, 128, 128, 96)};
+            for x in first_x..=last_x {
+                let line_x = origin.x + x as f32 * zoom;
+                painter.vline(line_x, (origin.y + first_y as f32 * zoom)..=(origin.y + last_y as f32 * zoom), egui::Stroke::new(1.0, line_color(x)));
+            }
+            for y in first_y..=last_y {
+                let line_y = origin.y + y as f32 * zoom;
+                painter.hline((origin.x + first_x as f32 * zoom)..=(origin.x + last_x as f32 * zoom), line_y, egui::Stroke::new(1.0, line_color(y)));
+            }
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            let position = (pointer - origin) / zoom;
+            if (0.0..160.0).contains(&position.x) && (0.0..144.0).contains(&position.y) {
+                let (x, y) = (position.x as usize, position.y as usize);
+                let pixel = frame.pixels[y][x];
+                let palette = match pixel.palette {
+                    None => "BGP",
+                    Some(0) => "OBP0",
+                    Some(_) => "OBP1",
+                };
+                let mut text = format!("X {} Y {}\n{}", x, y, tr_args("magnifier.color_index", &[("index", &pixel.color), ("palette", &palette)]));
+                if let Some(rgb) = pixel.rgb {
+                    text += &format!("\n{}", tr_args("magnifier.cgb_color", &[("color", &format!("{:04X}", rgb))]));
+                }
+                if let Some(tile) = pixel.tile {
+                    text += &format!("\n{}", tr_args("magnifier.tile", &[("tile", &format!("{:02X}", tile))]));
+                }
+                painter.text(rect.left_bottom() + egui::vec2(4.0, -4.0), egui::Align2::LEFT_BOTTOM, &text, egui::FontId::monospace(12.0), ui.visuals().strong_text_color());
+            }
+        }
+        drop(lock);
+    }
+
+    fn controls(&self, ui: &mut egui::Ui) {
+        let game_id = self.emu.rom_info.lock().unwrap().as_ref().map(|info| input::game_id(&info.title, info.global_checksum));
+
+        let mut lock = self.emu.input_profiles.lock().unwrap();
+        let profiles = &mut *lock;
+        if let Some(game_id) = &game_id {
+            let mut has_override = profiles.per_game.contains_key(game_id);
+            if ui.checkbox(&mut has_override, tr_args("controls.custom", &[("game", game_id)])).changed() {
+                if has_override {
+                    profiles.per_game.insert(game_id.clone(), profiles.default.clone());
+                }
+                else {
+                    profiles.per_game.remove(game_id);
+                }
+            }
+            ui.separator();
+        }
+        let config = match game_id.as_ref().and_then(|game_id| profiles.per_game.get_mut(game_id)) {
+            Some(config) => config,
+            None => &mut profiles.default
+        };
+
+        //The index of the button waiting for a key press, if any
+        let rebinding_id = ui.id().with("rebinding");
+        let mut rebinding: Option<usize> = ui.data(|data| data.get_temp(rebinding_id)).flatten();
+
+        egui::Grid::new("controls_grid").striped(true).show(ui, |ui| {
+            for (i, (name, binding)) in config.buttons.bindings_mut().into_iter().enumerate() {
+                ui.label(tr(name));
+
+                if rebinding == Some(i) {
+                    ui.label(tr("controls.press_key"));
+                    let pressed = ui.input(|input| {
+                        input.events.iter().find_map(|event| match event {
+                            egui::Event::Key { key, pressed: true, .. } => Some(KeyType::Key(*key)),
+                            _ => None
+                        }).or(if input.modifiers.any() {Some(KeyType::Modifier(input.modifiers))} else {None})
+                    });
+                    if let Some(key) = pressed {
+                        binding.key = key;
+                        rebinding = None;
+                    }
+                }
+                else if ui.button(binding.key.name()).clicked() {
+                    rebinding = Some(i);
+                }
+
+                egui::ComboBox::from_id_salt(("gamepad_binding", i))
+                    .selected_text(binding.gamepad_button.map(|button| format!("{:?}", button)).unwrap_or(tr("controls.none").to_owned()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut binding.gamepad_button, None, tr("controls.none"));
+                        for button in input::ALL_GAMEPAD_BUTTONS {
+                            ui.selectable_value(&mut binding.gamepad_button, Some(button), format!("{:?}", button));
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+        ui.data_mut(|data| data.insert_temp(rebinding_id, rebinding));
+
+        ui.separator();
+        ui.checkbox(&mut config.stick.enabled, tr("controls.stick"));
+        ui.add(egui::Slider::new(&mut config.stick.dead_zone, 0.0..=0.9).text(tr("controls.dead_zone")));
+        ui.add(egui::Slider::new(&mut config.stick.diagonal_bias, -1.0..=1.0).text(tr("controls.diagonal_bias")))
+            .on_hover_text(tr("controls.diagonal_bias_hint"));
+        drop(lock);
+    }
+
+    fn irq_flash(ui: &mut egui::Ui, settings: &mut IrqFlashSettings) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.enabled, tr("irq_flash.enabled")).on_hover_text(tr("irq_flash.hint"));
+            if settings.enabled {
+                ui.add(egui::Slider::new(&mut settings.strength, 0.1..=1.0).text(tr("irq_flash.strength")));
+            }
+        });
+        if settings.enabled {
+            ui.horizontal(|ui| {
+                for (source, name) in irq_flash::SOURCE_NAMES.into_iter().enumerate() {
+                    ui.checkbox(&mut settings.sources[source], egui::RichText::new(tr(name)).color(irq_flash::SOURCE_COLORS[source]));
+                }
+            });
+        }
+    }
+
+    //One bar per scanline, as long as the line spent in mode 3. Anything past the shortest possible 172 dots is penalties
+    fn mode_3_timeline(ui: &mut egui::Ui, lengths: &[u16; 144]) {
+        const SHORTEST: u16 = 172;
+        const LONGEST: f32 = 376.0; //Everything after OAM scan
+        const LINE_HEIGHT: f32 = 2.0;
+
+        egui::CollapsingHeader::new(tr("debugger.mode_3_timeline")).show(ui, |ui| {
+            let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width().min(376.0), 144.0 * LINE_HEIGHT), egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+            let dots_to_x = |dots: u16| rect.left() + dots as f32 / LONGEST * rect.width();
+
+            for (line, length) in lengths.iter().enumerate() {
+                let top = rect.top() + line as f32 * LINE_HEIGHT;
+                let base = egui::Rect::from_min_max(egui::pos2(rect.left(), top), egui::pos2(dots_to_x((*length).min(SHORTEST)), top + LINE_HEIGHT));
+                painter.rect_filled(base, 0.0, egui::Color32::GRAY);
+                if *length > SHORTEST {
+                    let penalty = egui::Rect::from_min_max(egui::pos2(base.right(), top), egui::pos2(dots_to_x(*length), top + LINE_HEIGHT));
+                    painter.rect_filled(penalty, 0.0, egui::Color32::from_rgb(0xE0, 0xA0, 0x20));
+                }
+            }
+            painter.vline(dots_to_x(SHORTEST), rect.y_range(), egui::Stroke::new(1.0, ui.visuals().weak_text_color()));
+
+            if let Some(pointer) = response.hover_pos() {
+                let line = (((pointer.y - rect.top()) / LINE_HEIGHT) as usize).min(143);
+                let length = lengths[line];
+                response.on_hover_text_at_pointer(tr_args("debugger.mode_3_line", &[("line", &line), ("dots", &length), ("penalty", &length.saturating_sub(SHORTEST))]));
+            }
+        });
+    }
+
+    //The monitor window. Breakpoints and help are handled here, anything touching memory runs on the emulation thread
+    pub fn monitor(&self, ui: &mut egui::Ui) {
+        let game_id = self.emu.rom_info.lock().unwrap().as_ref().map(|info| input::game_id(&info.title, info.global_checksum));
+        let entry_id = ui.make_persistent_id("monitor_entry");
+        let mut entry: String = ui.data(|data| data.get_temp(entry_id)).unwrap_or_default();
+        let mut monitor = self.emu.monitor.lock().unwrap();
+
+        egui::ScrollArea::vertical().max_height(300.0).stick_to_bottom(true).auto_shrink([false, true]).show(ui, |ui| {
+            for line in &monitor.log {
+                ui.monospace(line);
+            }
+        });
+        let response = ui.add(egui::TextEdit::singleline(&mut entry).font(egui::TextStyle::Monospace).hint_text(tr("monitor.hint")).desired_width(f32::INFINITY));
+        if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+            let line = std::mem::take(&mut entry);
+            monitor.print([format!("> {}", line)]);
+            match monitor::parse(&line) {
+                Ok(Command::Help) => monitor.print(monitor::HELP.lines().map(str::to_owned)),
+                Ok(_) if game_id.is_none() => monitor.print([tr("monitor.no_rom").to_owned()]),
+                Ok(command @ (Command::Breakpoint { .. } | Command::Watchpoint { .. })) => {
+                    let mut sessions = self.emu.debug_sessions.lock().unwrap();
+                    let session = sessions.entry(game_id.clone().unwrap_or_default()).or_default();
+                    monitor.print(monitor::add_to_session(&command, session));
+                }
+                Ok(command) => {
+                    monitor.pending.push(command);
+                    self.emu.wake.wake();
+                }
+                Err(e) => monitor.print([tr_args("monitor.error", &[("error", &e)])]),
+            }
+            response.request_focus();
+        }
+        drop(monitor);
+        ui.data_mut(|data| data.insert_temp(entry_id, entry));
+    }
+
+    //Steps while running are picked up at the start of the next frame
+    fn resume(&self, step_request: Option<StepRequest>) {
+        *self.emu.step_request.lock().unwrap() = step_request;
+        self.emu.paused.store(false, Ordering::Relaxed);
+        self.emu.wake.wake();
+    }
+
+    fn debugger(&self, ui: &mut egui::Ui) {
+        let is_paused = self.emu.paused.load(Ordering::Relaxed);
+        ui.horizontal(|ui| {
+            if is_paused {
+                if ui.button(tr("debugger.continue")).clicked() {
+                    self.resume(None);
+                }
+                if ui.button(tr("debugger.step")).on_hover_text(tr("debugger.step_hint")).clicked() {
+                    self.resume(Some(StepRequest::Into));
+                }
+                if ui.button(tr("debugger.step_over")).on_hover_text(tr("debugger.step_over_hint")).clicked() {
+                    self.resume(Some(StepRequest::Over));
+                }
+            }
+            else if ui.button(tr("debugger.pause")).clicked() {
+                self.emu.paused.store(true, Ordering::Relaxed);
+            }
+
+            let mut break_on_unknown_io = self.emu.break_on_unknown_io.load(Ordering::Relaxed);
+            if ui.checkbox(&mut break_on_unknown_io, tr("debugger.break_on_unknown_io")).changed() {
+                self.emu.break_on_unknown_io.store(break_on_unknown_io, Ordering::Relaxed);
+            }
+            let mut strict_mode = self.emu.strict_mode.load(Ordering::Relaxed);
+            if ui.checkbox(&mut strict_mode, tr("debugger.strict_mode")).on_hover_text(tr("debugger.strict_mode_hint")).changed() {
+                self.emu.strict_mode.store(strict_mode, Ordering::Relaxed);
+            }
+            let mut is_logging_blocked = self.emu.blocked_access_logging.load(Ordering::Relaxed);
+            if ui.checkbox(&mut is_logging_blocked, tr("debugger.log_blocked")).on_hover_text(tr("debugger.log_blocked_hint")).changed() {
+                self.emu.blocked_access_logging.store(is_logging_blocked, Ordering::Relaxed);
+            }
+            let mut trace_mode = self.emu.instruction_trace_mode.lock().unwrap();
+            ui.label(tr("debugger.trace")).on_hover_text(tr("debugger.trace_hint"));
+            egui::ComboBox::from_id_salt("instruction_trace_mode").selected_text(trace_mode_name(*trace_mode)).show_ui(ui, |ui| {
+                for option in TraceMode::ALL {
+                    ui.selectable_value(&mut *trace_mode, option, trace_mode_name(option));
+                }
+            });
+        });
+        ui.horizontal(|ui| {
+            let mut freeze_ppu = self.emu.freeze_ppu.load(Ordering::Relaxed);
+            if ui.checkbox(&mut freeze_ppu, tr("debugger.freeze_ppu")).on_hover_text(tr("debugger.freeze_ppu_hint")).changed() {
+                self.emu.freeze_ppu.store(freeze_ppu, Ordering::Relaxed);
+            }
+            let mut freeze_apu = self.emu.freeze_apu.load(Ordering::Relaxed);
+            if ui.checkbox(&mut freeze_apu, tr("debugger.freeze_apu")).on_hover_text(tr("debugger.freeze_apu_hint")).changed() {
+                self.emu.freeze_apu.store(freeze_apu, Ordering::Relaxed);
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut gdb_server_port = self.emu.gdb_server_port.lock().unwrap();
+            let port_id = ui.make_persistent_id("gdb_server_port");
+            let mut port = gdb_server_port.unwrap_or_else(|| ui.data(|data| data.get_temp(port_id)).unwrap_or(gdb_stub::DEFAULT_PORT));
+            let mut is_enabled = gdb_server_port.is_some();
+            let checkbox = ui.checkbox(&mut is_enabled, tr("debugger.gdb_server")).on_hover_text(tr("debugger.gdb_server_hint"));
+            ui.add_enabled(!is_enabled, egui::DragValue::new(&mut port).range(1..=u16::MAX));
+            ui.data_mut(|data| data.insert_temp(port_id, port));
+            if checkbox.changed() {
+                *gdb_server_port = is_enabled.then_some(port);
+                self.emu.gdb_server_status.lock().unwrap().clear();
+                self.emu.wake.wake();
+            }
+            if is_enabled {
+                ui.label(self.emu.gdb_server_status.lock().unwrap().as_str());
+            }
+        });
+        ui.horizontal(|ui| {
+            let mut script_path = self.emu.script_path.lock().unwrap();
+            if ui.button(tr("debugger.run_script")).on_hover_text(tr("debugger.run_script_hint")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter(tr("file_dialog.scripts"), &["rhai"]).pick_file() {
+                    *script_path = Some(path);
+                    self.emu.script_reload.store(true, Ordering::Relaxed);
+                }
+            }
+            if script_path.is_some() {
+                if ui.button(tr("debugger.reload")).clicked() {
+                    self.emu.script_reload.store(true, Ordering::Relaxed);
+                }
+                if ui.button(tr("debugger.stop")).clicked() {
+                    *script_path = None;
+                    self.emu.script_status.lock().unwrap().clear();
+                }
+            }
+            ui.label(self.emu.script_status.lock().unwrap().as_str());
+        });
+        Self::irq_flash(ui, &mut self.emu.irq_flash.lock().unwrap());
+
+        if let Some(break_reason) = self.emu.break_reason.lock().unwrap().clone() {
+            ui.colored_label(ui.visuals().warn_fg_color, break_reason);
+        }
+        let mut log_error = self.emu.log_error.lock().unwrap();
+        if let Some(error) = log_error.clone() {
+            ui.horizontal(|ui| {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+                if ui.button(tr("dialog.dismiss")).clicked() {
+                    *log_error = None;
+                }
+            });
+        }
+        drop(log_error);
+
+        let registers = *self.emu.cpu_registers.lock().unwrap();
+        if let Some(registers) = registers {
+            ui.separator();
+            egui::Grid::new("cpu_registers").show(ui, |ui| {
+                ui.monospace(format!("AF ${:02X}{:02X}", registers.a, registers.f));
+                ui.monospace(format!("BC ${:02X}{:02X}", registers.b, registers.c));
+                ui.end_row();
+                ui.monospace(format!("DE ${:02X}{:02X}", registers.d, registers.e));
+                ui.monospace(format!("HL ${:02X}{:02X}", registers.h, registers.l));
+                ui.end_row();
+                ui.monospace(format!("SP ${:04X}", registers.stack_pointer));
+                ui.monospace(format!("PC ${:04X}", registers.program_counter));
+                ui.end_row();
+                ui.monospace(format!("IME {}", registers.ime));
+                ui.monospace(format!("HALT {}", registers.is_halted));
+                ui.end_row();
+                ui.monospace(format!("STOP {}", registers.is_stopped));
+                ui.end_row();
+            });
+        }
+        else if is_paused {
+            ui.label(tr("debugger.pausing"));
+        }
+
+        let trace_lines = self.emu.instruction_trace_lines.lock().unwrap();
+        if is_paused && !trace_lines.is_empty() {
+            egui::CollapsingHeader::new(tr_args("debugger.instruction_trace", &[("lines", &trace_lines.len())])).show(ui, |ui| {
+                if ui.button(tr("debugger.copy")).clicked() {
+                    ui.ctx().copy_text(trace_lines.join("\n"));
+                }
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::vertical().max_height(200.0).stick_to_bottom(true).show_rows(ui, row_height, trace_lines.len(), |ui, rows| {
+                    for line in &trace_lines[rows] {
+                        ui.monospace(line);
+                    }
+                });
+            });
+        }
+        drop(trace_lines);
+
+        if let Some(snapshot) = &*self.emu.ppu_snapshot.lock().unwrap() {
+            ui.separator();
+            Self::mode_3_timeline(ui, &snapshot.mode_3_lengths);
+        }
+
+        let game_id = self.emu.rom_info.lock().unwrap().as_ref().map(|info| input::game_id(&info.title, info.global_checksum));
+        let Some(game_id) = game_id else {
+            return;
+        };
+        ui.separator();
+
+        //Everything below is saved for this game and restored the next time it's opened
+        let view = self.emu.debug_view.lock().unwrap().clone();
+        let mut sessions = self.emu.debug_sessions.lock().unwrap();
+        let session = sessions.entry(game_id).or_default();
+
+        ui.horizontal(|ui| {
+            let symbol_file_name = session.symbol_file.as_ref().map(|path| path.file_name().unwrap_or_default().to_string_lossy().to_string());
+            match (symbol_file_name, &view.symbol_error) {
+                (Some(name), Some(e)) => ui.colored_label(ui.visuals().warn_fg_color, format!("{}: {}", name, e)),
+                (Some(name), None) => ui.label(tr_args("debugger.symbol_count", &[("file", &name), ("count", &view.symbol_count)])),
+                (None, _) => ui.label(tr("debugger.no_symbols")),
+            };
+            if ui.button(tr("debugger.choose_symbols")).clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter(tr("file_dialog.symbols"), &["sym"]).pick_file() {
+                    session.symbol_file = Some(path);
+                }
+            }
+            if session.symbol_file.is_some() && ui.button(tr("debugger.clear")).clicked() {
+                session.symbol_file = None;
+            }
+        });
+
+        let entry_id = ui.make_persistent_id("debugger_entry");
+        let mut entry: String = ui.data(|data| data.get_temp(entry_id)).unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut entry).hint_text(tr("debugger.entry_hint")).desired_width(120.0));
+            let expression = entry.trim().to_owned();
+            let has_entry = !expression.is_empty();
+            if ui.add_enabled(has_entry, egui::Button::new(tr("debugger.break"))).on_hover_text(tr("debugger.break_hint")).clicked() {
+                session.breakpoints.push(expression.clone());
+                entry.clear();
+            }
+            if ui.add_enabled(has_entry, egui::Button::new(tr("debugger.run_to"))).on_hover_text(tr("debugger.run_to_hint")).clicked() {
+                self.resume(Some(StepRequest::RunTo(expression.clone())));
+                entry.clear();
+            }
+            if ui.add_enabled(has_entry, egui::Button::new(tr("debugger.watchpoint"))).on_hover_text(tr("debugger.watchpoint_hint")).clicked() {
+                session.watchpoints.push(WatchpointEntry { expression: expression.clone(), on_read: false, on_write: true, is_logged: false });
+                entry.clear();
+            }
+            if ui.add_enabled(has_entry, egui::Button::new(tr("debugger.watch"))).on_hover_text(tr("debugger.watch_hint")).clicked() {
+                session.watches.push(expression);
+                entry.clear();
+            }
+        });
+        ui.data_mut(|data| data.insert_temp(entry_id, entry));
+
+        let describe = |expression: &str| if view.unresolved.iter().any(|unresolved| unresolved == expression) {tr_args("debugger.unknown", &[("expression", &expression)])} else {expression.to_owned()};
+        let mut removed_breakpoint = None;
+        let mut removed_watchpoint = None;
+        let mut removed_watch = None;
+        egui::Grid::new("debugger_points").striped(true).show(ui, |ui| {
+            for (i, breakpoint) in session.breakpoints.iter().enumerate() {
+                ui.label(tr("debugger.break"));
+                ui.monospace(describe(breakpoint));
+                ui.label("");
+                if ui.small_button(tr("debugger.remove")).clicked() {
+                    removed_breakpoint = Some(i);
+                }
+                ui.end_row();
+            }
+            for (i, watchpoint) in session.watchpoints.iter_mut().enumerate() {
+                ui.label(tr("debugger.watchpoint"));
+                ui.monospace(describe(&watchpoint.expression));
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut watchpoint.on_read, tr("debugger.read"));
+                    ui.checkbox(&mut watchpoint.on_write, tr("debugger.write"));
+                    ui.checkbox(&mut watchpoint.is_logged, tr("debugger.log")).on_hover_text(tr("debugger.log_hint"));
+                });
+                if ui.small_button(tr("debugger.remove")).clicked() {
+                    removed_watchpoint = Some(i);
+                }
+                ui.end_row();
+            }
+            for (i, watch) in session.watches.iter().enumerate() {
+                ui.label(tr("debugger.watch"));
+                ui.monospace(describe(watch));
+                match view.watch_values.get(i).copied().flatten() {
+                    Some((address, value)) => ui.monospace(format!("${:04X} = ${:02X} ({})", address, value, value)),
+                    None => ui.monospace("?"),
+                };
+                if ui.small_button(tr("debugger.remove")).clicked() {
+                    removed_watch = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(i) = removed_breakpoint {
+            session.breakpoints.remove(i);
+        }
+        if let Some(i) = removed_watchpoint {
+            session.watchpoints.remove(i);
+        }
+        if let Some(i) = removed_watch {
+            session.watches.remove(i);
+        }
+        drop(sessions);
+    }
+}
@@ -0,0 +1,116 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+//Runs a rom with no window or audio device and reports how it went, for running test roms from scripts and CI.
+//Whatever the rom sends over the link port is printed as it arrives
+use std::{io::Write, path::Path, process::ExitCode};
+
+use gb_rs::{trace_log::InstructionTrace, HeadlessConsole, TestReport};
+
+const USAGE: &str = "Usage: gb_rs_headless <rom> [--frames <count>] [--break <hex address>]... [--trace <file>]";
+
+//About a minute of emulated time
+const DEFAULT_FRAMES: u64 = 3600;
+
+const EXIT_PASSED: u8 = 0;
+const EXIT_FAILED: u8 = 1;
+const EXIT_TIMED_OUT: u8 = 2;
+const EXIT_ERROR: u8 = 3;
+const EXIT_BREAKPOINT: u8 = 4;
+
+struct Options {
+    rom_path: String,
+    frames: u64,
+    breakpoints: Vec<u16>,
+    trace_path: Option<String>, //Game Boy Doctor's format, to diff against another emulator
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut rom_path = None;
+    let mut frames = DEFAULT_FRAMES;
+    let mut breakpoints = Vec::new();
+    let mut trace_path = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => {
+                let value = args.next().ok_or("--frames needs a count")?;
+                frames = value.parse().map_err(|_| format!("Invalid frame count: {}", value))?;
+            }
+            "--break" => {
+                let value = args.next().ok_or("--break needs an address")?;
+                let address = value.trim_start_matches('$').trim_start_matches("0x");
+                breakpoints.push(u16::from_str_radix(address, 16).map_err(|_| format!("Invalid address: {}", value))?);
+            }
+            "--trace" => trace_path = Some(args.next().ok_or("--trace needs a file")?),
+            _ if rom_path.is_none() && !arg.starts_with("--") => rom_path = Some(arg),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+
+    Ok(Options {
+        rom_path: rom_path.ok_or("No rom given")?,
+        frames,
+        breakpoints,
+        trace_path,
+    })
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("ERROR: {}\n{}", e, USAGE);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let mut headless = match HeadlessConsole::new(Path::new(&options.rom_path)) {
+        Ok(headless) => headless,
+        Err(e) => {
+            eprintln!("ERROR: Could not load {}: {}", options.rom_path, e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    if let Some(trace_path) = &options.trace_path {
+        match InstructionTrace::to_file(Path::new(trace_path)) {
+            Ok(instruction_trace) => headless.console.set_instruction_trace(Some(instruction_trace)),
+            Err(e) => {
+                eprintln!("ERROR: Could not create {}: {}", trace_path, e);
+                return ExitCode::from(EXIT_ERROR);
+            }
+        };
+    }
+
+    let mut stdout = std::io::stdout();
+    let mut printed_bytes = 0;
+    for frame in 1..=options.frames {
+        let break_address = headless.run_frame_until(&options.breakpoints);
+
+        let serial_output = headless.serial_output();
+        let _ = stdout.write_all(&serial_output[printed_bytes..]);
+        let _ = stdout.flush();
+        printed_bytes = serial_output.len();
+
+        match headless.test_report() {
+            Some(TestReport::Passed(detail)) => {
+                eprintln!("Passed after {} frames ({})", frame, detail);
+                return ExitCode::from(EXIT_PASSED);
+            }
+            Some(TestReport::Failed(detail)) => {
+                eprintln!("Failed after {} frames: {}", frame, detail);
+                return ExitCode::from(EXIT_FAILED);
+            }
+            None => (),
+        }
+
+        //A breakpoint ends the run with no verdict, so it gets its own code and the caller checks what was printed
+        if let Some(address) = break_address {
+            let registers = headless.console.cpu_registers();
+            eprintln!("Breakpoint at ${:04X} after {} frames. AF={:02X}{:02X} BC={:02X}{:02X} DE={:02X}{:02X} HL={:02X}{:02X} SP={:04X}",
+                address, frame, registers.a, registers.f, registers.b, registers.c, registers.d, registers.e, registers.h, registers.l, registers.stack_pointer);
+            return ExitCode::from(EXIT_BREAKPOINT);
+        }
+    }
+
+    eprintln!("Timed out after {} frames", options.frames);
+    ExitCode::from(EXIT_TIMED_OUT)
+}
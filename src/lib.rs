@@ -1,4 +1,30 @@
+#[cfg(feature = "gui")]
 mod app;
+#[cfg(feature = "gui")]
 pub use app::gbemu::GBEmu;
+#[cfg(feature = "gui")]
+pub use app::file_association;
+#[cfg(feature = "gui")]
+pub use app::kiosk;
+#[cfg(feature = "gui")]
+pub use app::rom_archive;
+#[cfg(feature = "gui")]
+pub use app::gbemu::{frame_dump::ScreenColors, palette::{PalettePreset, PaletteSettings}, ScreenPixel};
 
-mod mappers;
\ No newline at end of file
+//The emulation core, for embedding without the app. It lives in gb-core, which builds on its own for the web too
+pub use gb_core::cartridge_info::{CartridgeError, CartridgeInfo, CGBState, MAX_ROM_SIZE};
+pub use gb_core::{alu, cheats, disassembler, emulator::Emulator, headless::{HeadlessConsole, TestReport}, joypad::JoypadState, model::Model, opcodes, savestate, trace_log, AudioSink, CPURegisters, CycleEvents, GBConsole, HardwareMode, NullSink};
+pub use gb_core::frame_export::{ColorSpace, FrameFormat, PixelFormat};
+pub use gb_core::audio_capture::{AudioCapture, WavWriter};
+pub use gb_core::raster_hooks::{RasterHooks, RasterRegisters};
+pub use gb_core::bus::Bus;
+pub use gb_core::mappers::{BatteryRam, BatterySink, CartridgeStorage, CAMERA_HEIGHT, CAMERA_WIDTH};
+pub use gb_core::serial::{BarcodeBoy, Disconnected, Loopback, PeripheralInput, SerialDevice, SerialPacing, Stdout, Workboy};
+#[cfg(feature = "netplay")]
+pub use gb_core::serial::LinkAdapter;
+#[cfg(feature = "apu")]
+pub use gb_core::AudioDeviceChange;
+#[cfg(feature = "debugger")]
+pub use gb_core::{debug_session, gdb_stub, heatmap, inspector, interrupt_log, memory_editor, monitor};
+#[cfg(feature = "scripting")]
+pub use gb_core::script;
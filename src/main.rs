@@ -6,10 +6,21 @@
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    //Lets installers set up file associations without opening a window
+    if std::env::args().nth(1).as_deref() == Some(gb_rs::file_association::REGISTER_FLAG) {
+        if let Err(e) = gb_rs::file_association::register() {
+            eprintln!("ERROR: Could not register file types: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
-            .with_min_inner_size([300.0, 220.0]),
+            .with_min_inner_size([300.0, 220.0])
+            .with_transparent(true)
+            .with_fullscreen(gb_rs::kiosk::is_requested()),
         ..Default::default()
     };
     eframe::run_native(
@@ -0,0 +1,75 @@
+//Blargg's and Mooneye's test roms, checked against the ones that passed before. Skipped when the roms aren't there,
+//unless GB_RS_REQUIRE_TEST_ROMS is set. Run with GB_RS_ACCURACY_BLESS=1 to record what passes now as the new baseline
+mod common;
+
+use std::{collections::BTreeSet, env, fmt::Write, fs, path::Path};
+
+use common::accuracy::{self, Outcome, Suite};
+
+const SUITES: &[Suite] = &[
+    Suite { folder: "cpu_instrs/individual", timeout_seconds: 60 },
+    Suite { folder: "instr_timing", timeout_seconds: 10 },
+    Suite { folder: "mem_timing/individual", timeout_seconds: 10 },
+    Suite { folder: "mooneye/acceptance", timeout_seconds: 20 },
+];
+
+//Roms the emulator is known to pass, checked whatever the baseline says so blessing can't quietly drop them
+const EXPECTED_PASSING: &[&str] = &[
+    "instr_timing/instr_timing.gb",
+    "mem_timing/individual/01-read_timing.gb",
+    "mem_timing/individual/02-write_timing.gb",
+    "mem_timing/individual/03-modify_timing.gb",
+    "mooneye/acceptance/oam_dma/basic.gb",
+    "mooneye/acceptance/oam_dma/reg_read.gb",
+    "mooneye/acceptance/oam_dma/sources-GS.gb",
+    "mooneye/acceptance/oam_dma_restart.gb",
+    "mooneye/acceptance/oam_dma_start.gb",
+    "mooneye/acceptance/oam_dma_timing.gb",
+];
+
+const BASELINE_HEADER: &str = "# Test roms that passed last time, relative to the rom folder. Written by tests/accuracy.rs\n";
+
+fn baseline_path() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/accuracy_baseline.txt"))
+}
+
+#[test]
+fn test_roms_do_not_regress() {
+    let Some(rom_folder) = accuracy::rom_folder() else {
+        assert!(env::var_os("GB_RS_REQUIRE_TEST_ROMS").is_none(), "GB_RS_REQUIRE_TEST_ROMS is set but there are no test roms in tests/roms or GB_RS_TEST_ROMS");
+        eprintln!("SKIPPED: no test roms in tests/roms or GB_RS_TEST_ROMS, nothing was checked");
+        return;
+    };
+    let results = accuracy::run_suites(&rom_folder, SUITES);
+    let passed: BTreeSet<&String> = results.iter().filter(|(_, outcome)| **outcome == Outcome::Passed).map(|(name, _)| name).collect();
+
+    if env::var_os("GB_RS_ACCURACY_BLESS").is_some() {
+        let baseline = passed.iter().fold(String::from(BASELINE_HEADER), |mut baseline, name| {
+            writeln!(baseline, "{}", name).unwrap();
+            baseline
+        });
+        fs::write(baseline_path(), baseline).unwrap();
+        eprintln!("Recorded {} of {} test roms as passing", passed.len(), results.len());
+        return;
+    }
+
+    let baseline = fs::read_to_string(baseline_path()).unwrap_or_default();
+    let baseline: BTreeSet<&str> = baseline.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .chain(EXPECTED_PASSING.iter().copied())
+        .collect();
+
+    //A listed rom that isn't in the folder counts against the run, so an incomplete set of roms can't pass unnoticed
+    let regressions: Vec<String> = baseline.iter()
+        .filter_map(|name| match results.get(*name) {
+            Some(Outcome::Passed) => None,
+            Some(outcome) => Some(format!("{}: {}", name, outcome)),
+            None => Some(format!("{}: missing from {}", name, rom_folder.display())),
+        })
+        .collect();
+    for name in passed.iter().filter(|name| !baseline.contains(name.as_str())) {
+        eprintln!("Newly passing: {}", name);
+    }
+    eprintln!("{} of {} test roms passed", passed.len(), results.len());
+
+    assert!(regressions.is_empty(), "{} test roms regressed or are missing:\n{}", regressions.len(), regressions.join("\n"));
+}
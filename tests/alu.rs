@@ -0,0 +1,94 @@
+//Each flag of each operation, checked at the edges where it flips
+use gb_rs::alu::{self, C_CARRY_FLAG, H_HALF_CARRY_FLAG, N_SUBTRACTION_FLAG, Z_ZERO_FLAG};
+
+const Z: u8 = Z_ZERO_FLAG;
+const N: u8 = N_SUBTRACTION_FLAG;
+const H: u8 = H_HALF_CARRY_FLAG;
+const C: u8 = C_CARRY_FLAG;
+
+#[test]
+fn add8_flags() {
+    assert_eq!(alu::add8(0x12, 0x34), (0x46, 0));
+    assert_eq!(alu::add8(0x00, 0x00), (0x00, Z));
+    assert_eq!(alu::add8(0x0F, 0x01), (0x10, H));
+    assert_eq!(alu::add8(0xF0, 0x20), (0x10, C));
+    assert_eq!(alu::add8(0xFF, 0x01), (0x00, Z | H | C));
+}
+
+#[test]
+fn adc8_adds_the_carry_into_both_carries() {
+    assert_eq!(alu::adc8(0x12, 0x34, true), (0x47, 0));
+    assert_eq!(alu::adc8(0x0E, 0x01, true), (0x10, H));
+    assert_eq!(alu::adc8(0x00, 0x0F, true), (0x10, H));
+    assert_eq!(alu::adc8(0xFE, 0x01, true), (0x00, Z | H | C));
+    assert_eq!(alu::adc8(0x00, 0xFF, true), (0x00, Z | H | C));
+    assert_eq!(alu::adc8(0xFF, 0x00, false), (0xFF, 0));
+}
+
+#[test]
+fn sub8_flags() {
+    assert_eq!(alu::sub8(0x46, 0x34), (0x12, N));
+    assert_eq!(alu::sub8(0x42, 0x42), (0x00, Z | N));
+    assert_eq!(alu::sub8(0x10, 0x01), (0x0F, N | H));
+    assert_eq!(alu::sub8(0x10, 0x20), (0xF0, N | C));
+    assert_eq!(alu::sub8(0x00, 0x01), (0xFF, N | H | C));
+}
+
+#[test]
+fn sbc8_subtracts_the_carry_into_both_borrows() {
+    assert_eq!(alu::sbc8(0x47, 0x34, true), (0x12, N));
+    assert_eq!(alu::sbc8(0x01, 0x00, true), (0x00, Z | N));
+    assert_eq!(alu::sbc8(0x10, 0x0F, true), (0x00, Z | N | H));
+    assert_eq!(alu::sbc8(0x00, 0x00, true), (0xFF, N | H | C));
+    assert_eq!(alu::sbc8(0x00, 0xFF, true), (0x00, Z | N | H | C));
+}
+
+#[test]
+fn inc8_and_dec8_keep_the_carry() {
+    assert_eq!(alu::inc8(0x41, 0), (0x42, 0));
+    assert_eq!(alu::inc8(0x41, C), (0x42, C));
+    assert_eq!(alu::inc8(0x0F, 0), (0x10, H));
+    assert_eq!(alu::inc8(0xFF, 0), (0x00, Z | H));
+    assert_eq!(alu::inc8(0x00, Z | N | H), (0x01, 0));
+
+    assert_eq!(alu::dec8(0x42, C), (0x41, N | C));
+    assert_eq!(alu::dec8(0x01, 0), (0x00, Z | N));
+    assert_eq!(alu::dec8(0x10, 0), (0x0F, N | H));
+    assert_eq!(alu::dec8(0x00, 0), (0xFF, N | H));
+}
+
+#[test]
+fn add16_keeps_zero_and_carries_from_bits_11_and_15() {
+    assert_eq!(alu::add16(0x1234, 0x0101, 0), (0x1335, 0));
+    assert_eq!(alu::add16(0x1234, 0x0101, Z | N), (0x1335, Z));
+    assert_eq!(alu::add16(0x0FFF, 0x0001, 0), (0x1000, H));
+    assert_eq!(alu::add16(0xF000, 0x1000, 0), (0x0000, C));
+    assert_eq!(alu::add16(0xFFFF, 0x0001, 0), (0x0000, H | C));
+}
+
+#[test]
+fn add_sp_e8_carries_from_the_low_byte() {
+    assert_eq!(alu::add_sp_e8(0xFFF0, 0x01), (0xFFF1, 0));
+    assert_eq!(alu::add_sp_e8(0x000F, 0x01), (0x0010, H));
+    assert_eq!(alu::add_sp_e8(0x00F0, 0x10), (0x0100, C));
+    //-1 sets both, since 0xFF is added to the low byte unsigned
+    assert_eq!(alu::add_sp_e8(0x0001, 0xFF), (0x0000, H | C));
+    assert_eq!(alu::add_sp_e8(0x0000, 0xFF), (0xFFFF, 0));
+    assert_eq!(alu::add_sp_e8(0xFFFF, 0x01), (0x0000, H | C));
+}
+
+#[test]
+fn daa_corrects_bcd_results() {
+    //0x15 + 0x27 = 0x3C, which is 42 in BCD
+    let (sum, flags) = alu::add8(0x15, 0x27);
+    assert_eq!(alu::daa(sum, flags), (0x42, 0));
+    //0x99 + 0x01 = 0x9A, which is 100
+    let (sum, flags) = alu::add8(0x99, 0x01);
+    assert_eq!(alu::daa(sum, flags), (0x00, Z | C));
+    //0x42 - 0x15 = 0x2D, which is 27
+    let (difference, flags) = alu::sub8(0x42, 0x15);
+    assert_eq!(alu::daa(difference, flags), (0x27, N));
+    //0x10 - 0x20 borrows, which is 90 with the carry set
+    let (difference, flags) = alu::sub8(0x10, 0x20);
+    assert_eq!(alu::daa(difference, flags), (0x90, N | C));
+}
@@ -0,0 +1,89 @@
+//Replays the same inputs twice with audio captured offline and checks the tracks match and stay lined up with the frames
+mod common;
+
+use std::path::Path;
+
+use gb_rs::{HeadlessConsole, JoypadState, WavWriter};
+
+const FRAMES: u64 = 120;
+const CYCLES_PER_FRAME: u64 = 154 * 456 / 4;
+
+fn render(sample_rate: f32) -> (Vec<[f32; 2]>, Vec<usize>) {
+    let mut console = HeadlessConsole::with_audio_capture(Path::new("assets/demos/scroller.gb"), sample_rate).unwrap();
+    let mut samples = Vec::new();
+    let mut samples_per_frame = Vec::new();
+    for frame in 0..FRAMES {
+        console.console.set_joypad_state(JoypadState::from_byte(if frame % 20 < 10 {0x01} else {0x00}));
+        console.run_frame();
+        let frame_samples = console.take_audio();
+        samples_per_frame.push(frame_samples.len());
+        samples.extend(frame_samples);
+    }
+    (samples, samples_per_frame)
+}
+
+#[test]
+fn same_inputs_same_track() {
+    let (first, first_counts) = render(48000.0);
+    let (second, second_counts) = render(48000.0);
+    assert_eq!(first_counts, second_counts);
+    assert!(first == second);
+}
+
+#[test]
+fn exact_rate_lines_up_with_frames() {
+    //32 M-cycles per sample, so every frame's worth of cycles gives a known number of samples
+    let (samples, counts) = render(32768.0);
+    let mut total = 0;
+    for (frame, count) in counts.iter().enumerate() {
+        total += *count as u64;
+        assert_eq!(total, (frame as u64 + 1) * CYCLES_PER_FRAME / 32);
+    }
+    assert_eq!(samples.len() as u64, FRAMES * CYCLES_PER_FRAME / 32);
+}
+
+#[test]
+fn wav_header_matches_samples() {
+    let (samples, _) = render(32768.0);
+    let path = std::env::temp_dir().join("gb_rs_audio_capture_test.wav");
+    let mut writer = WavWriter::create(&path, 32768).unwrap();
+    writer.write(&samples).unwrap();
+    writer.finish().unwrap();
+
+    let wav = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize, samples.len() * 4);
+    assert_eq!(wav.len(), 44 + samples.len() * 4);
+}
+
+#[test]
+fn muted_channels_are_silent() {
+    //Turns the APU on and keeps a note playing on pulse 2
+    const PROGRAM: &[u8] = &[
+        0x3E, 0x80, 0xE0, 0x26, //LD A,80; LDH (NR52),A
+        0x3E, 0xFF, 0xE0, 0x25, //LD A,FF; LDH (NR51),A
+        0x3E, 0x77, 0xE0, 0x24, //LD A,77; LDH (NR50),A
+        0x3E, 0x80, 0xE0, 0x16, //LD A,80; LDH (NR21),A
+        0x3E, 0xF0, 0xE0, 0x17, //LD A,F0; LDH (NR22),A
+        0x3E, 0x87, 0xE0, 0x19, //LD A,87; LDH (NR24),A
+        0x18, 0xFE, //JR -2
+    ];
+    let rom_path = common::write_rom("muted_channels", &[(0x150, PROGRAM)]);
+    let render_channels = |enabled_channels: [bool; 4]| {
+        let mut console = HeadlessConsole::with_audio_capture(&rom_path, 48000.0).unwrap();
+        console.console.set_enabled_audio_channels(enabled_channels);
+        let mut samples = Vec::new();
+        for _ in 0..10 {
+            console.run_frame();
+            samples.extend(console.take_audio());
+        }
+        samples
+    };
+    let solo = render_channels([false, true, false, false]);
+    let muted = render_channels([false; 4]);
+    std::fs::remove_file(&rom_path).unwrap();
+
+    assert!(solo.iter().any(|sample| *sample != [0.0, 0.0]));
+    assert!(muted.iter().all(|sample| *sample == [0.0, 0.0]));
+}
@@ -0,0 +1,122 @@
+//Battery-backed cartridge RAM starts from whatever the frontend loaded and sends the game's writes back to it
+mod common;
+
+use std::{fs, path::Path, sync::mpsc::channel};
+
+use gb_rs::{BatteryRam, BatterySink, CartridgeInfo, CartridgeStorage, GBConsole, Model, NullSink};
+
+const CARTRIDGE_TYPE: u16 = 0x147;
+const RAM_SIZE: u16 = 0x149;
+
+//LD A, 0x0A, LD [0x0000], A to enable RAM, LD A, [0xA000], INC A, LD [0xA001], A, JR -2
+const PROGRAM: &[u8] = &[0x3E, 0x0A, 0xEA, 0x00, 0x00, 0xFA, 0x00, 0xA0, 0x3C, 0xEA, 0x01, 0xA0, 0x18, 0xFE];
+
+fn mbc1_battery_rom(name: &str) -> Vec<u8> {
+    let rom_path = common::write_rom(name, &[(CARTRIDGE_TYPE, &[0x03]), (RAM_SIZE, &[0x02]), (0x150, PROGRAM)]);
+    let rom = fs::read(&rom_path).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+    rom
+}
+
+//Leaves the rom on disk for the .sav and .sav.journal beside it, and returns their paths
+fn mbc1_battery_rom_file(name: &str) -> (String, String, String) {
+    let rom_path = common::write_rom(name, &[(CARTRIDGE_TYPE, &[0x03]), (RAM_SIZE, &[0x02]), (0x150, PROGRAM)]);
+    let rom_path = rom_path.to_str().unwrap().to_owned();
+    let save_path = rom_path.trim_end_matches(".gb").to_owned() + ".sav";
+    let journal_path = save_path.clone() + ".journal";
+    (rom_path, save_path, journal_path)
+}
+
+fn open_storage(rom_path: &str) -> CartridgeStorage {
+    let info = CartridgeInfo::from_rom(&fs::read(rom_path).unwrap()).unwrap();
+    CartridgeStorage::beside_rom(&info, rom_path).unwrap()
+}
+
+//What the writer appends to the image in the journal: its FNV-1a hash
+fn journal(image: &[u8]) -> Vec<u8> {
+    let mut hash: u64 = 0xCBF29CE484222325;
+    for byte in image {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001B3);
+    }
+    [image, &hash.to_le_bytes()].concat()
+}
+
+fn remove_files(paths: &[&str]) {
+    for path in paths {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[test]
+fn cartridge_ram_comes_from_bytes_and_goes_to_sink() {
+    let rom = mbc1_battery_rom("battery-bytes");
+    let info = CartridgeInfo::from_rom(&rom).unwrap();
+    let (sink, writes) = channel();
+    let storage = CartridgeStorage { battery: Some(BatteryRam { image: vec![0x41], sink: BatterySink::new(sink) }), aux_data_path: None };
+    let mut console = GBConsole::with_audio_sink(info, &rom, storage, Model::Dmg, Box::new(NullSink)).unwrap();
+    for _cycle in 0..100 {
+        console.step();
+    }
+
+    let ram = console.cartridge_ram().unwrap();
+    assert_eq!(ram.len(), 0x2000);
+    assert_eq!(&ram[..2], &[0x41, 0x42]);
+    assert_eq!(writes.try_iter().collect::<Vec<_>>(), vec![(0x42, 1)]);
+}
+
+#[test]
+fn torn_journal_is_discarded() {
+    let (rom_path, save_path, journal_path) = mbc1_battery_rom_file("battery-torn-journal");
+    fs::write(&save_path, [0x11; 0x2000]).unwrap();
+    let mut torn = journal(&[0x22; 0x2000]);
+    torn.truncate(0x1000);
+    fs::write(&journal_path, torn).unwrap();
+
+    let storage = open_storage(&rom_path);
+    let image = storage.battery.as_ref().unwrap().image.clone();
+    assert_eq!(image, vec![0x11; 0x2000]);
+    assert!(!Path::new(&journal_path).exists());
+    drop(storage);
+    assert_eq!(fs::read(&save_path).unwrap(), vec![0x11; 0x2000]);
+    remove_files(&[&rom_path, &save_path]);
+}
+
+#[test]
+fn complete_journal_replaces_torn_save() {
+    let (rom_path, save_path, journal_path) = mbc1_battery_rom_file("battery-torn-save");
+    fs::write(&save_path, [0x11; 0x800]).unwrap();
+    fs::write(&journal_path, journal(&[0x22; 0x2000])).unwrap();
+
+    let storage = open_storage(&rom_path);
+    assert_eq!(storage.battery.as_ref().unwrap().image, vec![0x22; 0x2000]);
+    assert_eq!(fs::read(&save_path).unwrap(), vec![0x22; 0x2000]);
+    assert!(!Path::new(&journal_path).exists());
+    remove_files(&[&rom_path, &save_path]);
+}
+
+#[test]
+fn writes_are_committed_when_the_cartridge_is_removed() {
+    let (rom_path, save_path, journal_path) = mbc1_battery_rom_file("battery-commit");
+    remove_files(&[&save_path, &journal_path]);
+    let rom = fs::read(&rom_path).unwrap();
+    let info = CartridgeInfo::from_rom(&rom).unwrap();
+    let storage = open_storage(&rom_path);
+    let mut console = GBConsole::with_audio_sink(info, &rom, storage, Model::Dmg, Box::new(NullSink)).unwrap();
+    for _cycle in 0..100 {
+        console.step();
+    }
+
+    //Well before the commit delay, so only the writer finishing on drop saves it
+    drop(console);
+    let save = fs::read(&save_path).unwrap();
+    assert_eq!(save.len(), 0x2000);
+    assert_eq!(&save[..2], &[0x00, 0x01]);
+    assert!(!Path::new(&journal_path).exists());
+
+    //And the next load starts from it
+    let storage = open_storage(&rom_path);
+    assert_eq!(&storage.battery.as_ref().unwrap().image[..2], &[0x00, 0x01]);
+    drop(storage);
+    remove_files(&[&rom_path, &save_path]);
+}
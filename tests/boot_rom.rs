@@ -0,0 +1,47 @@
+//A boot ROM runs from 0x0000 and hands over to the cartridge at 0x0100 by writing FF50
+mod common;
+
+use gb_rs::CPURegisters;
+
+//Loads the registers, pads with NOPs and unmaps itself from 0x00FE like the real one does
+fn synthetic_boot_rom(registers: &CPURegisters) -> Vec<u8> {
+    let mut boot_rom = vec![
+        0x31, 0xFE, 0xFF, //LD SP, 0xFFFE
+        0x21, registers.f, registers.a, //LD HL, AF
+        0xE5, //PUSH HL
+        0xF1, //POP AF
+        0x01, registers.c, registers.b, //LD BC
+        0x11, registers.e, registers.d, //LD DE
+        0x21, registers.l, registers.h, //LD HL
+    ];
+    boot_rom.resize(0xFE, 0x00);
+    boot_rom.extend([0xE0, 0x50]); //LDH (0x50), A
+    boot_rom
+}
+
+#[test]
+fn boot_rom_hands_over_post_boot_state() {
+    let mut headless = common::boot_rom("boot-rom-handover", &[(0x150, &[0x18, 0xFE])]);
+    let expected = headless.console.cpu_registers();
+    headless.console.set_boot_rom(synthetic_boot_rom(&expected));
+    assert_eq!(headless.console.cpu_registers().program_counter, 0x0000);
+
+    assert_eq!(headless.run_frame_until(&[0x100]), Some(0x100));
+    let cpu = headless.console.cpu_registers();
+    assert_eq!([cpu.a, cpu.f, cpu.b, cpu.c, cpu.d, cpu.e, cpu.h, cpu.l], [expected.a, expected.f, expected.b, expected.c, expected.d, expected.e, expected.h, expected.l]);
+    assert_eq!(cpu.stack_pointer, 0xFFFE);
+    assert_eq!(cpu.program_counter, 0x0100);
+    assert_eq!(headless.console.take_boot_error(), None);
+}
+
+#[test]
+fn boot_rom_with_wrong_state_is_reported() {
+    let mut headless = common::boot_rom("boot-rom-mismatch", &[(0x150, &[0x18, 0xFE])]);
+    let expected = headless.console.cpu_registers();
+    headless.console.set_boot_rom(synthetic_boot_rom(&CPURegisters { b: expected.b ^ 0xFF, ..expected }));
+
+    assert_eq!(headless.run_frame_until(&[0x100]), Some(0x100));
+    let boot_error = headless.console.take_boot_error().unwrap();
+    assert!(boot_error.contains(&format!("B = ${:02X}, expected ${:02X}", expected.b ^ 0xFF, expected.b)), "{}", boot_error);
+    assert_eq!(headless.console.take_boot_error(), None);
+}
@@ -0,0 +1,71 @@
+//The Pocket Camera's mapper, taking pictures the way the camera software does
+mod common;
+
+use std::fs;
+
+use gb_rs::{HeadlessConsole, CAMERA_HEIGHT, CAMERA_WIDTH};
+
+const CARTRIDGE_TYPE: u16 = 0x147;
+const RAM_SIZE: u16 = 0x149;
+const CAMERA: u8 = 0xFC;
+const RAM_128K: u8 = 0x04;
+
+//Runs a program ending in LD B, A for a few frames, long enough for a picture, and returns B
+fn run(name: &str, image: Option<&[u8]>, program: &[u8]) -> u8 {
+    let mut code = program.to_vec();
+    code.extend_from_slice(&[0x47, 0x18, 0xFE]);
+    let rom_path = common::write_rom(&format!("camera-{}", name), &[(0x150, &code), (CARTRIDGE_TYPE, &[CAMERA]), (RAM_SIZE, &[RAM_128K])]);
+    let mut headless = HeadlessConsole::new(&rom_path).unwrap();
+    if let Some(image) = image {
+        headless.console.set_camera_image(image);
+    }
+    for _ in 0..4 {
+        headless.run_frame();
+    }
+    let b = headless.console.cpu_registers().b;
+
+    drop(headless);
+    fs::remove_file(&rom_path).unwrap();
+    let _ = fs::remove_file(rom_path.with_extension("sav"));
+    let _ = fs::remove_file(rom_path.with_extension("aux"));
+    b
+}
+
+//LD A, value, LD [address], A
+fn store(address: u16, value: u8) -> [u8; 5] {
+    let [low, high] = address.to_le_bytes();
+    [0x3E, value, 0xEA, low, high]
+}
+
+//Maps the registers in, fills the dither matrix with the same thresholds everywhere and starts a neutral exposure
+fn start_capture() -> Vec<u8> {
+    let mut program = store(0x4000, 0x10).to_vec();
+    for entry in 0..16 {
+        for (threshold, value) in [0x40, 0x80, 0xC0].into_iter().enumerate() {
+            program.extend_from_slice(&store(0xA006 + entry * 3 + threshold as u16, value));
+        }
+    }
+    program.extend_from_slice(&store(0xA002, 0x08));
+    program.extend_from_slice(&store(0xA003, 0x00));
+    program.extend_from_slice(&store(0xA000, 0x01));
+    program
+}
+
+#[test]
+fn capture_is_busy_until_done() {
+    let mut program = start_capture();
+    program.extend_from_slice(&[0xFA, 0x00, 0xA0]); //LD A, [0xA000]
+    assert_eq!(run("busy", None, &program), 0x01);
+}
+
+//A black picture comes out as the darkest shade, both bitplanes set
+#[test]
+fn capture_dithers_image_into_ram() {
+    let image = vec![0; CAMERA_WIDTH * CAMERA_HEIGHT];
+    let mut program = start_capture();
+    program.extend_from_slice(&[0xFA, 0x00, 0xA0, 0xE6, 0x01, 0x20, 0xF9]); //Wait for bit 0 of A000 to clear
+    program.extend_from_slice(&store(0x4000, 0x00));
+    program.extend_from_slice(&store(0x0000, 0x0A));
+    program.extend_from_slice(&[0xFA, 0x01, 0xA1]); //LD A, [0xA101]
+    assert_eq!(run("dither", Some(&image), &program), 0xFF);
+}
@@ -0,0 +1,61 @@
+//Roms that can't be turned into a cartridge are reported instead of panicking
+mod common;
+
+use std::fs;
+
+use gb_rs::{CartridgeError, HeadlessConsole};
+
+const CARTRIDGE_TYPE: u16 = 0x147;
+const ROM_SIZE: u16 = 0x148;
+const RAM_SIZE: u16 = 0x149;
+
+fn load(name: &str, sections: &[(u16, &[u8])]) -> CartridgeError {
+    let rom_path = common::write_rom(&format!("error-{}", name), sections);
+    let error = HeadlessConsole::new(&rom_path).err().unwrap();
+    fs::remove_file(&rom_path).unwrap();
+    error
+}
+
+#[test]
+fn unknown_cartridge_type() {
+    assert!(matches!(load("mapper", &[(CARTRIDGE_TYPE, &[0xFD])]), CartridgeError::UnknownCartridgeType(0xFD)));
+}
+
+//The header asks for 64KB but write_rom only makes 32KB
+#[test]
+fn truncated_rom() {
+    assert!(matches!(load("truncated", &[(ROM_SIZE, &[0x01])]), CartridgeError::Truncated {expected: 0x10000, actual: 0x8000}));
+}
+
+#[test]
+fn unknown_ram_size() {
+    assert!(matches!(load("ram", &[(RAM_SIZE, &[0x07])]), CartridgeError::UnknownRamSize(0x07)));
+}
+
+#[test]
+fn missing_file() {
+    let error = HeadlessConsole::new(&std::env::temp_dir().join("gb-rs-error-missing.gb")).err().unwrap();
+    assert!(matches!(error, CartridgeError::Io(_)));
+}
+
+//Roms in memory go through the same checks as ones on disk
+#[test]
+fn truncated_rom_from_memory() {
+    let rom_path = common::write_rom("memory", &[(ROM_SIZE, &[0x01])]);
+    let rom = fs::read(&rom_path).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+    assert!(matches!(HeadlessConsole::from_rom(&rom).err().unwrap(), CartridgeError::Truncated {expected: 0x10000, actual: 0x8000}));
+    assert!(HeadlessConsole::from_rom(&rom[..0x8000 - 1]).is_err());
+}
+
+//A .sav that exists but can't be read mustn't be replaced with a blank one
+#[test]
+fn unreadable_battery_save() {
+    let rom_path = common::write_rom("battery", &[(CARTRIDGE_TYPE, &[0x03]), (RAM_SIZE, &[0x02])]);
+    let save_path = rom_path.with_extension("sav");
+    fs::create_dir(&save_path).unwrap();
+    let error = HeadlessConsole::new(&rom_path).err().unwrap();
+    fs::remove_dir(&save_path).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+    assert!(matches!(error, CartridgeError::BatterySave(_)));
+}
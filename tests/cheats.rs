@@ -0,0 +1,62 @@
+//Cheat codes decoded from their text and applied to a running game
+mod common;
+
+use std::{env, fs};
+
+use gb_rs::cheats::{self, Cheat, CheatCode, GameGenieCode, GameSharkCode};
+
+//LD A, [0x0200], LD B, A, looping so B always holds what the game last read from ROM
+const PROGRAM: &[u8] = &[0xFA, 0x00, 0x02, 0x47, 0x18, 0xFA];
+
+fn run(name: &str, code: &str) -> gb_rs::HeadlessConsole {
+    let mut headless = common::boot_rom(&format!("cheats-{}", name), &[(0x150, PROGRAM), (0x200, &[0x12])]);
+    headless.console.set_cheats(&[CheatCode::parse(code).unwrap()]);
+    headless.run_frame();
+    headless.run_frame();
+    headless
+}
+
+#[test]
+fn decodes_game_genie() {
+    assert_eq!(CheatCode::parse("342-00F-A02"), Ok(CheatCode::GameGenie(GameGenieCode { address: 0x0200, value: 0x34, compare: Some(0x12) })));
+    assert_eq!(CheatCode::parse("342 00F"), Ok(CheatCode::GameGenie(GameGenieCode { address: 0x0200, value: 0x34, compare: None })));
+    assert!(CheatCode::parse("342-007").is_err()); //0x8200 is VRAM
+}
+
+#[test]
+fn decodes_game_shark() {
+    assert_eq!(CheatCode::parse("014223C1"), Ok(CheatCode::GameShark(GameSharkCode { bank: 0x01, address: 0xC123, value: 0x42 })));
+    assert!(CheatCode::parse("0142FF7F").is_err()); //ROM
+    assert!(CheatCode::parse("ZZ4223C1").is_err());
+}
+
+#[test]
+fn game_genie_patches_rom_reads() {
+    assert_eq!(run("genie", "342-00F").console.cpu_registers().b, 0x34);
+}
+
+#[test]
+fn game_genie_compare_must_match() {
+    assert_eq!(run("genie-compare", "342-00F-A02").console.cpu_registers().b, 0x34);
+    assert_eq!(run("genie-mismatch", "342-00F-B02").console.cpu_registers().b, 0x12);
+}
+
+#[test]
+fn game_shark_writes_ram_each_frame() {
+    let mut headless = run("shark", "014223C1");
+    assert_eq!(headless.console.read_bulk(0xC123, 0xC123), [0x42]);
+}
+
+//Hand edited files can start a line with anything, including characters longer than a byte
+#[test]
+fn skips_malformed_cheats() {
+    let cheats_path = env::temp_dir().join(format!("gb-rs-cheats-malformed-{}.cht", std::process::id()));
+    fs::write(&cheats_path, "+00A-17B-C49 Lives\né00A-17B-C49\n—\n-010A0DC1 Timer\n").unwrap();
+    let cheats = cheats::load_cheats(cheats_path.to_str().unwrap());
+    fs::remove_file(&cheats_path).unwrap();
+
+    assert_eq!(cheats, [
+        Cheat { name: String::from("Lives"), code: String::from("00A-17B-C49"), is_enabled: true },
+        Cheat { name: String::from("Timer"), code: String::from("010A0DC1"), is_enabled: false },
+    ]);
+}
@@ -0,0 +1,101 @@
+//Runs folders of Blargg and Mooneye test roms headlessly, the same way the headless binary judges them
+use std::{collections::BTreeMap, env, fs, panic::{self, AssertUnwindSafe}, path::{Path, PathBuf}, sync::Mutex, thread};
+
+use gb_rs::{HeadlessConsole, TestReport};
+
+const FRAMES_PER_SECOND: u64 = 60;
+
+pub struct Suite {
+    pub folder: &'static str, //Relative to the rom folder. Every rom under it is run
+    pub timeout_seconds: u64,
+}
+
+#[derive(PartialEq)]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+    TimedOut,
+    Crashed(String),
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Passed => write!(f, "passed"),
+            Outcome::Failed(detail) => write!(f, "failed: {}", detail),
+            Outcome::TimedOut => write!(f, "timed out"),
+            Outcome::Crashed(detail) => write!(f, "crashed: {}", detail),
+        }
+    }
+}
+
+//The roms aren't in the repository. They're looked for where GB_RS_TEST_ROMS points, or in tests/roms
+pub fn rom_folder() -> Option<PathBuf> {
+    let folder = env::var_os("GB_RS_TEST_ROMS").map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("roms"));
+    folder.is_dir().then_some(folder)
+}
+
+//Keyed by path relative to the rom folder with / between folders, so the names are the same on every platform
+pub fn run_suites(rom_folder: &Path, suites: &[Suite]) -> BTreeMap<String, Outcome> {
+    let mut queue = Vec::new();
+    for suite in suites {
+        let mut roms = Vec::new();
+        find_roms(&rom_folder.join(suite.folder), &mut roms);
+        queue.extend(roms.into_iter().map(|rom_path| (rom_path, suite.timeout_seconds * FRAMES_PER_SECOND)));
+    }
+
+    let queue = Mutex::new(queue);
+    let results = Mutex::new(BTreeMap::new());
+    let worker_count = thread::available_parallelism().map(|count| count.get()).unwrap_or(1);
+    thread::scope(|scope| {
+        for _ in 0..worker_count {
+            scope.spawn(|| {
+                loop {
+                    let Some((rom_path, timeout_frames)) = queue.lock().unwrap().pop() else {
+                        return;
+                    };
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(|| run_rom(&rom_path, timeout_frames)))
+                        .unwrap_or_else(|_| Outcome::Crashed(String::from("The emulator panicked")));
+                    results.lock().unwrap().insert(test_name(rom_folder, &rom_path), outcome);
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
+
+fn run_rom(rom_path: &Path, timeout_frames: u64) -> Outcome {
+    let mut headless = match HeadlessConsole::new(rom_path) {
+        Ok(headless) => headless,
+        Err(e) => return Outcome::Crashed(e.to_string()),
+    };
+    for _frame in 0..timeout_frames {
+        headless.run_frame();
+        match headless.test_report() {
+            Some(TestReport::Passed(_)) => return Outcome::Passed,
+            Some(TestReport::Failed(detail)) => return Outcome::Failed(detail),
+            None => (),
+        }
+    }
+    Outcome::TimedOut
+}
+
+fn find_roms(folder: &Path, roms: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.is_dir() {
+            find_roms(&path, roms);
+        }
+        else if matches!(path.extension().and_then(|extension| extension.to_str()), Some("gb") | Some("gbc")) {
+            roms.push(path);
+        }
+    }
+}
+
+fn test_name(rom_folder: &Path, rom_path: &Path) -> String {
+    let relative_path = rom_path.strip_prefix(rom_folder).unwrap_or(rom_path);
+    relative_path.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
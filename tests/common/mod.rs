@@ -0,0 +1,36 @@
+//Builds tiny ROMs for tests that run real code. Programs start at 0x150, after the header
+#![allow(dead_code)] //Each test file uses a different part of this
+use std::{env, fs, path::PathBuf};
+
+use gb_rs::HeadlessConsole;
+
+pub mod accuracy;
+pub mod single_step;
+
+const ENTRY: &[u8] = &[0x00, 0xC3, 0x50, 0x01]; //NOP, JP 0x150
+
+//Writes the sections into an otherwise empty ROM with a valid header. The name keeps tests running side by side from
+//sharing a file
+pub fn write_rom(name: &str, sections: &[(u16, &[u8])]) -> PathBuf {
+    let mut rom = vec![0; 0x8000];
+    rom[0x100..0x104].copy_from_slice(ENTRY);
+    for (address, code) in sections {
+        rom[*address as usize..*address as usize + code.len()].copy_from_slice(code);
+    }
+    let mut checksum: u8 = 0;
+    for byte in &rom[0x134..=0x14C] {
+        checksum = checksum.wrapping_sub(*byte).wrapping_sub(1);
+    }
+    rom[0x14D] = checksum;
+
+    let rom_path = env::temp_dir().join(format!("gb-rs-{}-{}.gb", name, std::process::id()));
+    fs::write(&rom_path, &rom).unwrap();
+    rom_path
+}
+
+pub fn boot_rom(name: &str, sections: &[(u16, &[u8])]) -> HeadlessConsole {
+    let rom_path = write_rom(name, sections);
+    let headless = HeadlessConsole::new(&rom_path).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+    headless
+}
@@ -0,0 +1,172 @@
+//Runs single step CPU test vectors in the format of the community SM83 ones. Each vector is one instruction: the
+//registers and RAM before and after it, and what was on the bus during each of its M-cycles
+use std::{env, fs, panic::{self, AssertUnwindSafe}, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use gb_rs::{Bus, CPURegisters, GBConsole};
+
+use serde::Deserialize;
+
+//Steps before an instruction counts as hung. The longest takes 6
+const MAX_CYCLES: usize = 32;
+
+#[derive(Deserialize)]
+pub struct Vector {
+    pub name: String,
+    initial: CpuState,
+    #[serde(rename = "final")]
+    expected: CpuState,
+    cycles: Vec<(Option<u16>, Option<u8>, String)>, //Address, data and pins. The bus is left floating on internal cycles
+}
+
+#[derive(Deserialize)]
+struct CpuState {
+    a: u8,
+    f: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    h: u8,
+    l: u8,
+    sp: u16,
+    pc: u16,
+    #[serde(default)]
+    ime: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+impl CpuState {
+    fn registers(&self) -> CPURegisters {
+        CPURegisters {
+            a: self.a,
+            f: self.f,
+            b: self.b,
+            c: self.c,
+            d: self.d,
+            e: self.e,
+            h: self.h,
+            l: self.l,
+            stack_pointer: self.sp,
+            program_counter: self.pc,
+            ime: self.ime != 0,
+            is_halted: false,
+            is_stopped: false,
+        }
+    }
+}
+
+struct FlatRamState {
+    memory: Vec<u8>,
+    writes: Vec<(u16, u8)>,
+}
+
+//64KB of plain RAM, shared with the runner so it can check what the instruction left behind
+struct FlatRam(Arc<Mutex<FlatRamState>>);
+
+impl Bus for FlatRam {
+    fn read(&mut self, address: u16) -> u8 {
+        self.0.lock().unwrap().memory[address as usize]
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let mut state = self.0.lock().unwrap();
+        state.memory[address as usize] = value;
+        state.writes.push((address, value));
+    }
+}
+
+//The vectors aren't in the repository. They're looked for where GB_RS_SM83_TESTS points, or in tests/sm83
+pub fn vector_folder() -> Option<PathBuf> {
+    let folder = env::var_os("GB_RS_SM83_TESTS").map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("sm83"));
+    folder.is_dir().then_some(folder)
+}
+
+//A few hand-worked vectors in the same format that are always there, so the runner is checked without the full suite
+pub fn sample_folder() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("sm83_sample")
+}
+
+pub fn read_vectors(path: &Path) -> Vec<Vector> {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+pub fn vector_files(folder: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(folder).map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json")).collect()).unwrap_or_default();
+    files.sort();
+    files
+}
+
+//Returns how many vectors there were and a line for each one that didn't match
+pub fn run_vectors(console: &mut GBConsole, vectors: &[Vector]) -> (usize, Vec<String>) {
+    let failures = vectors.iter().filter_map(|vector| {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| run_vector(console, vector)))
+            .unwrap_or_else(|_| Err(String::from("the emulator panicked")));
+        result.err().map(|e| format!("{}: {}", vector.name, e))
+    }).collect();
+    (vectors.len(), failures)
+}
+
+fn run_vector(console: &mut GBConsole, vector: &Vector) -> Result<(), String> {
+    let state = Arc::new(Mutex::new(FlatRamState { memory: vec![0; 0x10000], writes: Vec::new() }));
+    for (address, value) in &vector.initial.ram {
+        state.lock().unwrap().memory[*address as usize] = *value;
+    }
+    console.set_bus(Some(Box::new(FlatRam(state.clone()))));
+    console.set_cpu_registers(&vector.initial.registers());
+
+    let mut cycle_count = 0;
+    while cycle_count < MAX_CYCLES {
+        console.step();
+        cycle_count += 1;
+        if console.is_between_instructions() {
+            break;
+        }
+    }
+    console.set_bus(None);
+
+    let mut mismatches = Vec::new();
+    let actual = console.cpu_registers();
+    let expected = vector.expected.registers();
+    let compared = [
+        ("a", actual.a as u16, expected.a as u16),
+        ("f", actual.f as u16, expected.f as u16),
+        ("b", actual.b as u16, expected.b as u16),
+        ("c", actual.c as u16, expected.c as u16),
+        ("d", actual.d as u16, expected.d as u16),
+        ("e", actual.e as u16, expected.e as u16),
+        ("h", actual.h as u16, expected.h as u16),
+        ("l", actual.l as u16, expected.l as u16),
+        ("sp", actual.stack_pointer, expected.stack_pointer),
+        ("pc", actual.program_counter, expected.program_counter),
+        ("ime", actual.ime as u16, expected.ime as u16),
+        ("cycles", cycle_count as u16, vector.cycles.len() as u16),
+    ];
+    for (name, actual, expected) in compared {
+        if actual != expected {
+            mismatches.push(format!("{} was {:X}, expected {:X}", name, actual, expected));
+        }
+    }
+
+    let state = state.lock().unwrap();
+    for (address, value) in &vector.expected.ram {
+        if state.memory[*address as usize] != *value {
+            mismatches.push(format!("${:04X} was {:02X}, expected {:02X}", address, state.memory[*address as usize], value));
+        }
+    }
+    //Only writes are checked against the cycles. The CPU looks at the opcode more than once, so it reads more than the bus would
+    let expected_writes: Vec<(u16, u8)> = vector.cycles.iter()
+        .filter(|(_, _, pins)| pins.contains('w'))
+        .filter_map(|(address, value, _)| Some(((*address)?, (*value)?)))
+        .collect();
+    if state.writes != expected_writes {
+        mismatches.push(format!("wrote {:X?}, expected {:X?}", state.writes, expected_writes));
+    }
+
+    match mismatches.is_empty() {
+        true => Ok(()),
+        false => Err(mismatches.join(", ")),
+    }
+}
@@ -0,0 +1,35 @@
+//Checks that instructions touch memory on the M-cycle they would on hardware, not all at once at the end. The timer
+//runs at its fastest, ticking TIMA every 4 M-cycles, and the setup lines a tick up to land between two accesses of
+//the instruction under test
+mod common;
+
+//LD A, 5, LDH [TAC], A, LDH [DIV], A, XOR A, LDH [TIMA], A, LD HL, TIMA, LD SP, TIMA, NOP x3
+const SETUP: &[u8] = &[0x3E, 0x05, 0xE0, 0x07, 0xE0, 0x04, 0xAF, 0xE0, 0x05, 0x21, 0x05, 0xFF, 0x31, 0x05, 0xFF, 0x00, 0x00, 0x00];
+
+fn run(name: &str, code: &[u8]) -> (u8, u8) {
+    let mut program = SETUP.to_vec();
+    program.extend_from_slice(code);
+    program.extend_from_slice(&[0x18, 0xFE]);
+    let mut headless = common::boot_rom(&format!("timing-{}", name), &[(0x150, &program)]);
+
+    for _cycle in 0..60 {
+        headless.console.step();
+    }
+    let cpu = headless.console.cpu_registers();
+    (cpu.b, cpu.c)
+}
+
+//INC [HL] reads on its second cycle and writes on its third, so the tick in between is overwritten
+#[test]
+fn read_modify_write_loses_tick_between_accesses() {
+    let (tima, _) = run("inc", &[0x34, 0xF0, 0x05, 0x47]); //INC [HL], LDH A, [TIMA], LD B, A
+    assert_eq!(tima, 3);
+}
+
+//POP reads the low byte on its second cycle, before the tick, and the high byte from TMA on its third
+#[test]
+fn pop_reads_bytes_on_separate_cycles() {
+    let (tma, tima) = run("pop", &[0xC1]); //POP BC
+    assert_eq!(tima, 2);
+    assert_eq!(tma, 0);
+}
@@ -0,0 +1,20 @@
+//Decoding code from memory without running it
+mod common;
+
+use gb_rs::disassembler;
+
+//LD A, 0x20, LDH [0x00], A, JP 0x0150
+const PROGRAM: &[u8] = &[0x3E, 0x20, 0xE0, 0x00, 0xC3, 0x50, 0x01];
+
+#[test]
+fn lines_up_before_address() {
+    let mut headless = common::boot_rom("disassembler", &[(0x150, PROGRAM)]);
+    let instructions = disassembler::disassemble(&mut headless.console, 0x150, 3);
+    let text: Vec<&str> = instructions.iter().map(|instruction| instruction.text.as_str()).collect();
+    assert_eq!(text, ["LD A, $20", "LDH [$00], A", "JP $0150"]);
+    assert_eq!(instructions[2].hex(), "C3 50 01");
+
+    //The instructions before are found by lining up with the address, never starting on an operand like the $20
+    let addresses: Vec<u16> = disassembler::around(&mut headless.console, 0x154, 2, 1).iter().map(|instruction| instruction.address).collect();
+    assert_eq!(addresses, [0x150, 0x152, 0x154, 0x157]);
+}
@@ -0,0 +1,27 @@
+//The frontend facing API, used the way a frontend would
+mod common;
+
+use std::fs;
+
+use gb_rs::{Emulator, JoypadState};
+
+//Keeps reading the d-pad into B: LD A, 0x20, LDH [0x00], A, LDH A, [0x00], LD B, A, JR back to the read
+const READ_DPAD: &[u8] = &[0x3E, 0x20, 0xE0, 0x00, 0xF0, 0x00, 0x47, 0x18, 0xFB];
+
+#[test]
+fn runs_rom_from_memory() {
+    let rom_path = common::write_rom("emulator", &[(0x150, READ_DPAD)]);
+    let rom = fs::read(&rom_path).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+
+    let mut emulator = Emulator::new(48000.0);
+    assert!(!emulator.run_frame());
+    emulator.load_rom(&rom).unwrap();
+    emulator.set_buttons(JoypadState { right: true, ..Default::default() });
+    assert!(emulator.run_frame());
+    assert!(emulator.run_frame());
+
+    assert_eq!(emulator.framebuffer().len(), 160 * 144 * 4);
+    assert!(!emulator.audio_samples().is_empty());
+    assert_eq!(emulator.console().unwrap().cpu_registers().b & 0x0F, 0x0E);
+}
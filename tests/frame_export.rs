@@ -0,0 +1,55 @@
+//Runs the bundled scroller demo headless and checks every export format describes the same frame
+use std::path::Path;
+
+use gb_rs::{ColorSpace, FrameFormat, HeadlessConsole, PixelFormat};
+
+fn scroller_frame() -> HeadlessConsole {
+    let mut console = HeadlessConsole::new(Path::new("assets/demos/scroller.gb")).unwrap();
+    for _ in 0..30 {
+        console.run_frame();
+    }
+    console
+}
+
+fn export(console: &HeadlessConsole, pixel_format: PixelFormat) -> Vec<u8> {
+    let frame = console.frame(&FrameFormat { pixel_format, ..Default::default() });
+    assert_eq!(frame.len(), pixel_format.bytes_per_frame());
+    frame
+}
+
+#[test]
+fn formats_agree() {
+    let console = scroller_frame();
+    let rgba = export(&console, PixelFormat::Rgba8);
+    let bgra = export(&console, PixelFormat::Bgra8);
+    let rgb565 = export(&console, PixelFormat::Rgb565);
+    let indexed = export(&console, PixelFormat::Indexed2bpp);
+    let shades = FrameFormat::default().shades;
+
+    for i in 0..160 * 144 {
+        let [r, g, b, a] = [rgba[i * 4], rgba[i * 4 + 1], rgba[i * 4 + 2], rgba[i * 4 + 3]];
+        assert_eq!(a, 0xFF);
+        assert_eq!(&bgra[i * 4..i * 4 + 4], &[b, g, r, a]);
+
+        let rgb565 = u16::from_le_bytes([rgb565[i * 2], rgb565[i * 2 + 1]]);
+        assert_eq!(rgb565, ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3));
+
+        let shade = (indexed[i / 4] >> (6 - (i % 4) * 2)) & 0b11;
+        assert_eq!(shades[shade as usize], [r, g, b]);
+    }
+}
+
+#[test]
+fn shows_the_demo_text() {
+    let console = scroller_frame();
+    let indexed = export(&console, PixelFormat::Indexed2bpp);
+    assert!(indexed.iter().any(|byte| *byte != 0), "the frame is blank");
+}
+
+#[test]
+fn color_space_leaves_dmg_frames_alone() {
+    let console = scroller_frame();
+    let raw = console.frame(&FrameFormat { color_space: ColorSpace::Raw, ..Default::default() });
+    let lcd = console.frame(&FrameFormat { color_space: ColorSpace::CgbLcd, ..Default::default() });
+    assert_eq!(raw, lcd);
+}
@@ -0,0 +1,99 @@
+//gdb's remote serial protocol over a real socket, with the test standing in for both gdb and the emulation thread
+mod common;
+
+use std::{io::{Read, Write}, net::TcpStream, thread, time::{Duration, Instant}};
+
+use gb_rs::{gdb_stub::{GdbRequest, GdbStub}, HeadlessConsole};
+
+//LD A, 0x42, then JR to itself
+const PROGRAM: &[u8] = &[0x3E, 0x42, 0x18, 0xFE];
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+//Polls like the emulation thread until the stub acts on something
+fn poll(gdb_stub: &mut GdbStub, headless: &mut HeadlessConsole) -> Option<GdbRequest> {
+    let start = Instant::now();
+    while !gdb_stub.has_input() {
+        assert!(start.elapsed() < TIMEOUT, "Nothing arrived");
+        thread::sleep(Duration::from_millis(1));
+    }
+    gdb_stub.poll(&mut headless.console)
+}
+
+fn send(client: &mut TcpStream, data: &str) {
+    let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+    client.write_all(format!("${}#{:02x}", data, checksum).as_bytes()).unwrap();
+}
+
+//Skips the ack and returns the packet's data
+fn receive(client: &mut TcpStream) -> String {
+    let mut received = Vec::new();
+    let mut byte = [0];
+    while received.len() < 3 || received[received.len() - 3] != b'#' {
+        client.read_exact(&mut byte).unwrap();
+        if !(received.is_empty() && byte[0] == b'+') {
+            received.push(byte[0]);
+        }
+    }
+    String::from_utf8(received[1..received.len() - 3].to_vec()).unwrap()
+}
+
+fn receive_ack(client: &mut TcpStream) -> u8 {
+    let mut byte = [0];
+    client.read_exact(&mut byte).unwrap();
+    byte[0]
+}
+
+fn exchange(client: &mut TcpStream, gdb_stub: &mut GdbStub, headless: &mut HeadlessConsole, data: &str) -> String {
+    send(client, data);
+    assert_eq!(poll(gdb_stub, headless), None);
+    receive(client)
+}
+
+#[test]
+fn debugs_over_tcp() {
+    let mut headless = common::boot_rom("gdb", &[(0x150, PROGRAM)]);
+    let mut gdb_stub = GdbStub::listen(0, || ()).unwrap();
+    let mut client = TcpStream::connect(("127.0.0.1", gdb_stub.port())).unwrap();
+    client.set_read_timeout(Some(TIMEOUT)).unwrap();
+    assert_eq!(poll(&mut gdb_stub, &mut headless), Some(GdbRequest::Pause));
+
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "?"), "S05");
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "m150,4"), "3e4218fe");
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "p5"), "0001");
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "P3=3412"), "OK");
+    assert_eq!(headless.console.cpu_registers().h, 0x12);
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "Mc000,2:beef"), "OK");
+    assert_eq!(headless.console.peek(None, 0xC001), Ok(0xEF));
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "m0,8000000000000000"), "E01");
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "Mc000,8000000000000000:beef"), "E01");
+
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "Z0,152,1"), "OK");
+    assert!(gdb_stub.breakpoints().contains(&0x152));
+
+    //Run to the breakpoint the way the frontend does, then tell gdb
+    send(&mut client, "c");
+    assert_eq!(poll(&mut gdb_stub, &mut headless), Some(GdbRequest::Continue));
+    assert_eq!(receive_ack(&mut client), b'+');
+    headless.run_frame_until(&[0x152]);
+    gdb_stub.report_stop();
+    assert_eq!(receive(&mut client), "S05");
+    let registers = exchange(&mut client, &mut gdb_stub, &mut headless, "g");
+    //AF first and PC last, both little endian
+    assert_eq!(&registers[2..4], "42");
+    assert!(registers.ends_with("5201"), "{}", registers);
+}
+
+//The ROM only fixture has no cartridge RAM, so there's nothing at 0xA000-0xBFFF to read or write
+#[test]
+fn reads_missing_cartridge_ram() {
+    let mut headless = common::boot_rom("gdb-cartridge-ram", &[(0x150, PROGRAM)]);
+    let mut gdb_stub = GdbStub::listen(0, || ()).unwrap();
+    let mut client = TcpStream::connect(("127.0.0.1", gdb_stub.port())).unwrap();
+    client.set_read_timeout(Some(TIMEOUT)).unwrap();
+    assert_eq!(poll(&mut gdb_stub, &mut headless), Some(GdbRequest::Pause));
+
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "ma000,4"), "ffffffff");
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "Ma000,1:12"), "OK");
+    assert_eq!(exchange(&mut client, &mut gdb_stub, &mut headless, "mbffe,4"), "ffff0000");
+}
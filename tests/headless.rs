@@ -0,0 +1,19 @@
+//Running test roms with no frontend, the way the headless binary does
+mod common;
+
+use gb_rs::TestReport;
+
+//Loads Mooneye's passing values into B, C, D, E, H and L, then JR to itself at 0x15C
+const MOONEYE_PASS: &[u8] = &[0x06, 0x03, 0x0E, 0x05, 0x16, 0x08, 0x1E, 0x0D, 0x26, 0x15, 0x2E, 0x22, 0x18, 0xFE];
+
+#[test]
+fn stops_at_breakpoint_with_report() {
+    let mut headless = common::boot_rom("headless", &[(0x150, MOONEYE_PASS)]);
+    assert!(headless.test_report().is_none());
+    assert_eq!(headless.run_frame_until(&[0x15C]), Some(0x15C));
+    assert_eq!(headless.console.program_counter(), 0x15C);
+    assert!(matches!(headless.test_report(), Some(TestReport::Passed(_))));
+
+    //Resuming from a breakpoint doesn't stop on it again right away
+    assert_eq!(headless.run_frame_until(&[0x15C]), None);
+}
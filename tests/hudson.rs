@@ -0,0 +1,50 @@
+//Hudson's HuC1 and HuC3 mappers, driven the way their games do it
+mod common;
+
+use std::fs;
+
+use gb_rs::HeadlessConsole;
+
+const CARTRIDGE_TYPE: u16 = 0x147;
+const HUC3: u8 = 0xFE;
+const HUC1: u8 = 0xFF;
+
+//Runs a program ending in LD B, A and returns B
+fn run(name: &str, cartridge_type: u8, program: &[u8]) -> u8 {
+    let mut code = program.to_vec();
+    code.extend_from_slice(&[0x47, 0x18, 0xFE]);
+    let rom_path = common::write_rom(&format!("hudson-{}", name), &[(0x150, &code), (CARTRIDGE_TYPE, &[cartridge_type])]);
+    let mut headless = HeadlessConsole::new(&rom_path).unwrap();
+    headless.run_frame();
+    let b = headless.console.cpu_registers().b;
+
+    drop(headless);
+    fs::remove_file(&rom_path).unwrap();
+    let _ = fs::remove_file(rom_path.with_extension("sav"));
+    let _ = fs::remove_file(rom_path.with_extension("aux"));
+    b
+}
+
+//LD A, value, LD [address], A
+fn store(address: u16, value: u8) -> [u8; 5] {
+    let [low, high] = address.to_le_bytes();
+    [0x3E, value, 0xEA, low, high]
+}
+
+#[test]
+fn huc1_infrared_sees_no_light() {
+    let mut program = store(0x0000, 0x0E).to_vec();
+    program.extend_from_slice(&[0xFA, 0x00, 0xA0]); //LD A, [0xA000]
+    assert_eq!(run("huc1-ir", HUC1, &program), 0xC0);
+}
+
+//Writes 5 into the lowest nibble of the minutes through the address register, then reads it back
+#[test]
+fn huc3_clock_reads_back_written_nibble() {
+    let mut program = Vec::new();
+    for (address, value) in [(0x0000, 0x0B), (0xA000, 0x40), (0xA000, 0x50), (0xA000, 0x35), (0xA000, 0x40), (0xA000, 0x10), (0x0000, 0x0C)] {
+        program.extend_from_slice(&store(address, value));
+    }
+    program.extend_from_slice(&[0xFA, 0x00, 0xA0]); //LD A, [0xA000]
+    assert_eq!(run("huc3-clock", HUC3, &program), 0x05);
+}
@@ -0,0 +1,92 @@
+//Interrupt timing around EI, DI, RETI and HALT, run on tiny ROMs built for each test. The programs end in a JR to
+//themselves, and each handler leaves a marker in a register before doing the same
+mod common;
+
+use gb_rs::CPURegisters;
+
+const VBLANK: u16 = 0x40;
+const TIMER: u16 = 0x50;
+
+fn run(name: &str, sections: &[(u16, &[u8])], cycles: u32) -> CPURegisters {
+    let mut headless = common::boot_rom(&format!("ime-{}", name), sections);
+
+    for _cycle in 0..cycles {
+        headless.console.step();
+    }
+    headless.console.cpu_registers()
+}
+
+//LD B, 0, then IE and IF both set to A
+fn request_interrupts(bits: u8) -> Vec<u8> {
+    vec![0x06, 0x00, 0x3E, bits, 0xE0, 0xFF, 0xE0, 0x0F]
+}
+
+#[test]
+fn ei_waits_one_instruction() {
+    let mut program = request_interrupts(0b1);
+    program.extend_from_slice(&[0xFB, 0x04, 0x04, 0x04, 0x18, 0xFE]); //EI, INC B x3
+    let cpu = run("ei", &[(0x150, &program), (VBLANK, &[0x48, 0x18, 0xFE])], 200); //LD C, B
+
+    assert_eq!(cpu.c, 1);
+    assert_eq!(cpu.program_counter, VBLANK + 1);
+    assert!(!cpu.ime);
+}
+
+#[test]
+fn second_ei_does_not_add_delay() {
+    let mut program = request_interrupts(0b1);
+    program.extend_from_slice(&[0xFB, 0xFB, 0x04, 0x18, 0xFE]); //EI, EI, INC B
+    let cpu = run("ei-ei", &[(0x150, &program), (VBLANK, &[0x48, 0x18, 0xFE])], 200);
+
+    assert_eq!(cpu.c, 0);
+    assert_eq!(cpu.b, 0);
+}
+
+#[test]
+fn di_cancels_pending_ei() {
+    let mut program = request_interrupts(0b1);
+    program.extend_from_slice(&[0xFB, 0xF3, 0x04, 0x18, 0xFE]); //EI, DI, INC B
+    let cpu = run("di", &[(0x150, &program), (VBLANK, &[0x0E, 0xAA, 0x18, 0xFE])], 40000); //LD C, 0xAA
+
+    assert_ne!(cpu.c, 0xAA);
+    assert_eq!(cpu.b, 1);
+    assert!(!cpu.ime);
+}
+
+//A second interrupt waiting when RETI runs is taken straight away, before the interrupted code gets another instruction
+#[test]
+fn reti_enables_interrupts_immediately() {
+    let mut program = request_interrupts(0b101);
+    program.extend_from_slice(&[0x16, 0x00, 0xFB, 0x04, 0x04, 0x18, 0xFE]); //LD D, 0, EI, INC B x2
+    let vblank_handler: &[u8] = &[0x14, 0xD9]; //INC D, RETI
+    let timer_handler: &[u8] = &[0x48, 0x5A, 0x18, 0xFE]; //LD C, B, LD E, D
+    let cpu = run("reti", &[(0x150, &program), (VBLANK, vblank_handler), (TIMER, timer_handler)], 200);
+
+    assert_eq!(cpu.c, 1);
+    assert_eq!(cpu.e, 1);
+}
+
+//With IME off, HALT still ends when an enabled interrupt is requested, just without calling the handler
+#[test]
+fn halt_wakes_without_ime() {
+    let program: &[u8] = &[0x06, 0x00, 0xF3, 0xAF, 0xE0, 0x0F, 0x3C, 0xE0, 0xFF, 0x76, 0x04, 0x18, 0xFE]; //LD B, 0, DI, XOR A, LDH [IF], A, INC A, LDH [IE], A, HALT, INC B
+    let cpu = run("halt", &[(0x150, program), (VBLANK, &[0x0E, 0xAA, 0x18, 0xFE])], 17556 * 2);
+
+    assert_eq!(cpu.b, 1);
+    assert_ne!(cpu.c, 0xAA);
+    assert!(!cpu.is_halted);
+}
+
+//With SP at 0x0000 the high byte of PC is pushed onto IE. Here that turns off the timer interrupt being taken, so the
+//CPU ends up at 0x0000 instead of its handler
+#[test]
+fn pushing_onto_ie_cancels_dispatch() {
+    let mut program = vec![0x31, 0x00, 0x00]; //LD SP, 0x0000
+    program.extend(request_interrupts(0b100));
+    program.extend_from_slice(&[0xFB, 0x00, 0x18, 0xFE]); //EI, NOP
+    let timer_handler: &[u8] = &[0x0E, 0xAA, 0x18, 0xFE]; //LD C, 0xAA
+    let cpu = run("ie-push", &[(0x150, &program), (0x0000, &[0x0E, 0x42, 0x18, 0xFE]), (TIMER, timer_handler)], 200); //LD C, 0x42
+
+    assert_eq!(cpu.c, 0x42);
+    assert_eq!(cpu.program_counter, 0x0002);
+}
@@ -0,0 +1,63 @@
+//The MBC7's accelerometer, read the way Kirby Tilt 'n' Tumble does: enable the registers, erase the latch with 0x55,
+//latch with 0xAA, then read each axis a byte at a time
+mod common;
+
+use std::fs;
+
+use gb_rs::HeadlessConsole;
+
+const CARTRIDGE_TYPE: u16 = 0x147;
+
+//Both RAM enables, 0x55 to 0xA000, then optionally 0xAA to 0xA010, then X into BC and Y into DE
+fn program(is_latched: bool) -> Vec<u8> {
+    let mut program = vec![0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x3E, 0x40, 0xEA, 0x00, 0x40, 0x3E, 0x55, 0xEA, 0x00, 0xA0];
+    if is_latched {
+        program.extend_from_slice(&[0x3E, 0xAA, 0xEA, 0x10, 0xA0]);
+    }
+    program.extend_from_slice(&[0xFA, 0x20, 0xA0, 0x4F, 0xFA, 0x30, 0xA0, 0x47, 0xFA, 0x40, 0xA0, 0x5F, 0xFA, 0x50, 0xA0, 0x57, 0x18, 0xFE]);
+    program
+}
+
+fn read_axes(name: &str, is_latched: bool, tilt: (f32, f32)) -> (u16, u16) {
+    let rom_path = common::write_rom(&format!("mbc7-{}", name), &[(0x150, &program(is_latched)), (CARTRIDGE_TYPE, &[0x22])]);
+    let mut headless = HeadlessConsole::new(&rom_path).unwrap();
+    headless.console.set_tilt(tilt.0, tilt.1);
+    headless.run_frame();
+    let cpu = headless.console.cpu_registers();
+
+    //The EEPROM is written to the aux file when the console goes away
+    drop(headless);
+    fs::remove_file(&rom_path).unwrap();
+    let _ = fs::remove_file(rom_path.with_extension("aux"));
+    (u16::from_be_bytes([cpu.b, cpu.c]), u16::from_be_bytes([cpu.d, cpu.e]))
+}
+
+#[test]
+fn latches_tilt() {
+    assert_eq!(read_axes("flat", true, (0.0, 0.0)), (0x81D0, 0x81D0));
+    assert_eq!(read_axes("tilted", true, (0.5, -1.0)), (0x8208, 0x8160));
+}
+
+#[test]
+fn erased_latch_reads_8000() {
+    assert_eq!(read_axes("erased", false, (0.5, -1.0)), (0x8000, 0x8000));
+}
+
+//A section claiming to be longer than the file is refused without allocating it, and the game still starts
+#[test]
+fn oversized_aux_section_is_ignored() {
+    let rom_path = common::write_rom("mbc7-oversized-aux", &[(0x150, &[0x18, 0xFE]), (CARTRIDGE_TYPE, &[0x22])]);
+    let aux_path = rom_path.with_extension("aux");
+    let mut aux = b"GBAX".to_vec();
+    aux.extend_from_slice(&1u32.to_le_bytes());
+    aux.extend_from_slice(&0u64.to_le_bytes());
+    aux.extend_from_slice(b"EEPR");
+    aux.extend_from_slice(&u32::MAX.to_le_bytes());
+    fs::write(&aux_path, &aux).unwrap();
+
+    let headless = HeadlessConsole::new(&rom_path);
+    assert!(headless.is_ok());
+    drop(headless);
+    fs::remove_file(&rom_path).unwrap();
+    let _ = fs::remove_file(&aux_path);
+}
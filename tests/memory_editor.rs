@@ -0,0 +1,27 @@
+//The memory editor reaching memory the CPU is locked out of
+mod common;
+
+use gb_rs::memory_editor::{MemoryEditor, MemoryRegion};
+
+//JR to itself
+const LOOP: &[u8] = &[0x18, 0xFE];
+
+#[test]
+fn edits_oam_while_locked() {
+    let mut headless = common::boot_rom("memory-editor", &[(0x150, LOOP)]);
+    let console = &mut headless.console;
+    //Wait for the PPU to be drawing, when both VRAM and OAM are locked
+    while console.peek(None, 0xFF41).unwrap() & 0x03 != 3 {
+        console.step();
+    }
+
+    let mut memory_editor = MemoryEditor { region: MemoryRegion::Oam, ..Default::default() };
+    memory_editor.edits.push((None, 0xFE01, 0x42));
+    memory_editor.sync(console);
+    assert_eq!(memory_editor.bytes.len(), 0xA0);
+    assert_eq!(memory_editor.bytes[1], Some(0x42));
+
+    console.poke(None, 0x9800, 0x17).unwrap();
+    assert_eq!(console.peek(None, 0x9800), Ok(0x17));
+    assert_eq!(console.peek(Some(0), 0x9800), Ok(0x17));
+}
@@ -0,0 +1,94 @@
+//How many dots mode 3 takes for a line, which stretches with SCX's fine scroll, objects and the window, as in Pan Docs
+mod common;
+
+use gb_rs::HeadlessConsole;
+
+const LCDC: u16 = 0xFF40;
+const SCX: u16 = 0xFF43;
+const WY: u16 = 0xFF4A;
+const WX: u16 = 0xFF4B;
+const LINE: usize = 10;
+
+//Puts an object on LINE at x, which is the screen position plus 8
+fn object(headless: &mut HeadlessConsole, index: u16, x: u8) {
+    headless.console.poke(None, 0xFE00 + index * 4, LINE as u8 + 16).unwrap();
+    headless.console.poke(None, 0xFE01 + index * 4, x).unwrap();
+}
+
+//The length of LINE's mode 3 in a frame drawn after the registers are written
+fn mode_3_length(name: &str, setup: impl FnOnce(&mut HeadlessConsole)) -> u16 {
+    let mut headless = common::boot_rom(name, &[(0x150, &[0x18, 0xFE])]);
+    headless.run_frame(); //The boot ROM hands over in V-blank
+    setup(&mut headless);
+    headless.run_frame();
+    headless.run_frame();
+    headless.console.ppu_snapshot().mode_3_lengths[LINE]
+}
+
+fn lcdc(headless: &mut HeadlessConsole, value: u8) {
+    headless.console.poke(None, LCDC, value).unwrap();
+}
+
+#[test]
+fn plain_line_takes_172_dots() {
+    assert_eq!(mode_3_length("mode-3-plain", |_| {}), 172);
+}
+
+#[test]
+fn scx_fine_scroll_adds_its_low_bits() {
+    for scx in [3, 7, 8, 13] {
+        let length = mode_3_length(&format!("mode-3-scx-{}", scx), |headless| headless.console.poke(None, SCX, scx).unwrap());
+        assert_eq!(length, 172 + (scx & 0b111) as u16, "SCX = {}", scx);
+    }
+}
+
+#[test]
+fn object_waits_for_the_tile_under_it() {
+    //6 dots for the object, plus 5 less however far into its tile the object starts
+    for (x, penalty) in [(0, 11), (8, 11), (11, 8), (13, 6), (40, 11), (45, 6)] {
+        let length = mode_3_length(&format!("mode-3-object-{}", x), |headless| {
+            lcdc(headless, 0x93);
+            object(headless, 0, x);
+        });
+        assert_eq!(length, 172 + penalty, "object at X = {}", x);
+    }
+}
+
+#[test]
+fn second_object_over_a_tile_only_pays_for_its_fetch() {
+    let length = mode_3_length("mode-3-object-pair", |headless| {
+        lcdc(headless, 0x93);
+        object(headless, 0, 40);
+        object(headless, 1, 42);
+    });
+    assert_eq!(length, 172 + 11 + 6);
+}
+
+#[test]
+fn objects_are_free_while_hidden() {
+    let length = mode_3_length("mode-3-object-hidden", |headless| {
+        lcdc(headless, 0x91);
+        object(headless, 0, 40);
+    });
+    assert_eq!(length, 172);
+}
+
+#[test]
+fn window_restarts_the_fetcher() {
+    for wx in [7, 87] {
+        let length = mode_3_length(&format!("mode-3-window-{}", wx), |headless| {
+            lcdc(headless, 0xB1);
+            headless.console.poke(None, WY, 0).unwrap();
+            headless.console.poke(None, WX, wx).unwrap();
+        });
+        assert_eq!(length, 172 + 6, "WX = {}", wx);
+    }
+
+    //Below WY the window isn't drawn yet
+    let length = mode_3_length("mode-3-window-below", |headless| {
+        lcdc(headless, 0xB1);
+        headless.console.poke(None, WY, LINE as u8 + 1).unwrap();
+        headless.console.poke(None, WX, 87).unwrap();
+    });
+    assert_eq!(length, 172);
+}
@@ -0,0 +1,49 @@
+//Each model should start the game with what its boot ROM leaves behind, since that's how games tell them apart
+mod common;
+
+use gb_rs::{CPURegisters, HardwareMode, HeadlessConsole, Model};
+
+const CGB_FLAG: u16 = 0x143;
+
+fn boot(name: &str, model: Model, is_cgb_cartridge: bool) -> HeadlessConsole {
+    let cgb_flag: &[u8] = if is_cgb_cartridge {&[0x80]} else {&[0x00]};
+    let rom_path = common::write_rom(&format!("model-{}", name), &[(0x150, &[0x18, 0xFE]), (CGB_FLAG, cgb_flag)]);
+    let headless = HeadlessConsole::with_model(&rom_path, model).unwrap();
+    std::fs::remove_file(&rom_path).unwrap();
+    headless
+}
+
+fn registers(name: &str, model: Model, is_cgb_cartridge: bool) -> CPURegisters {
+    boot(name, model, is_cgb_cartridge).console.cpu_registers()
+}
+
+#[test]
+fn a_and_b_tell_models_apart() {
+    let expected = [(Model::Dmg0, 0x01, 0xFF), (Model::Dmg, 0x01, 0x00), (Model::Mgb, 0xFF, 0x00), (Model::Sgb, 0x01, 0x00), (Model::Cgb, 0x11, 0x00), (Model::Agb, 0x11, 0x01)];
+    for (model, a, b) in expected {
+        let cpu = registers(&format!("ab-{}", model.name()), model, true);
+        assert_eq!((cpu.a, cpu.b), (a, b), "{}", model.name());
+    }
+}
+
+#[test]
+fn cgb_runs_monochrome_cartridge_in_dmg_mode() {
+    let headless = boot("cgb-dmg-mode", Model::Cgb, false);
+    assert_eq!(headless.console.hardware_mode(), HardwareMode::Dmg);
+    let cpu = headless.console.cpu_registers();
+    assert_eq!((cpu.a, cpu.d, cpu.e), (0x11, 0x00, 0x08));
+}
+
+#[test]
+fn monochrome_model_ignores_cgb_support() {
+    let headless = boot("dmg-cgb-cart", Model::Dmg, true);
+    assert_eq!(headless.console.hardware_mode(), HardwareMode::Dmg);
+    assert_eq!(headless.console.cpu_registers().a, 0x01);
+}
+
+#[test]
+fn automatic_model_follows_cartridge() {
+    let headless = common::boot_rom("model-automatic", &[(0x150, &[0x18, 0xFE]), (CGB_FLAG, &[0x80])]);
+    assert_eq!(headless.console.hardware_mode(), HardwareMode::Cgb);
+    assert_eq!(headless.console.cpu_registers().a, 0x11);
+}
@@ -0,0 +1,58 @@
+//The monitor's command parser and the commands that touch memory
+mod common;
+
+use gb_rs::{debug_session::Symbols, monitor::{self, Command, Target}};
+
+fn target(bank: Option<u16>, address: &str) -> Target {
+    Target { bank, address: address.to_owned() }
+}
+
+#[test]
+fn parses_reads_and_writes() {
+    assert_eq!(monitor::parse("r C000"), Ok(Command::Read { target: target(None, "C000"), count: 16 }));
+    assert_eq!(monitor::parse("R 03:4F20 40"), Ok(Command::Read { target: target(Some(3), "4F20"), count: 40 }));
+    assert_eq!(monitor::parse("r player_x 2"), Ok(Command::Read { target: target(None, "player_x"), count: 2 }));
+    assert_eq!(monitor::parse("w C000 12 $34"), Ok(Command::Write { target: target(None, "C000"), bytes: vec![0x12, 0x34] }));
+}
+
+#[test]
+fn parses_breakpoints_and_watchpoints() {
+    assert_eq!(monitor::parse("bp 0150"), Ok(Command::Breakpoint { expression: String::from("0150") }));
+    assert_eq!(monitor::parse("bp C000-C0FF rw"), Ok(Command::Watchpoint { expression: String::from("C000-C0FF"), on_read: true, on_write: true, is_logged: false }));
+    assert_eq!(monitor::parse("bp C000 w LOG"), Ok(Command::Watchpoint { expression: String::from("C000"), on_read: false, on_write: true, is_logged: true }));
+    assert_eq!(monitor::parse("help"), Ok(Command::Help));
+    assert_eq!(monitor::parse("?"), Ok(Command::Help));
+}
+
+#[test]
+fn refuses_bad_commands() {
+    assert!(monitor::parse("").is_err());
+    assert!(monitor::parse("r C000 0").is_err());
+    assert!(monitor::parse("r C000 65536").is_err());
+    assert!(monitor::parse("w C000").is_err());
+    assert!(monitor::parse("w C000 100").is_err());
+    assert!(monitor::parse("bp 01:0150").is_err());
+    assert!(monitor::parse("bp C000 x").is_err());
+    assert!(monitor::parse("bp C000 r loud").is_err());
+    assert!(monitor::parse("jump 0150").is_err());
+}
+
+#[test]
+fn reads_up_to_the_largest_count() {
+    //An MBC1, so the read can pass through 0xA000-0xBFFF with RAM off
+    let mut headless = common::boot_rom("monitor-read", &[(0x147, &[0x01])]);
+    let command = monitor::parse("r 0000 65535").unwrap();
+    let lines = monitor::run(&command, &mut headless.console, &Symbols::default()).unwrap();
+    assert_eq!(lines.len(), 4096);
+    assert_eq!(lines[4095].split_whitespace().count(), 1 + 15);
+    assert!(lines[4095].starts_with("FFF0:"));
+}
+
+#[test]
+fn reads_missing_cartridge_ram() {
+    //The default cartridge is ROM only, with nothing at 0xA000-0xBFFF
+    let mut headless = common::boot_rom("monitor-cartridge-ram", &[]);
+    let command = monitor::parse("r A000 4").unwrap();
+    let lines = monitor::run(&command, &mut headless.console, &Symbols::default()).unwrap();
+    assert_eq!(lines, ["A000: FF FF FF FF"]);
+}
@@ -0,0 +1,36 @@
+//OAM DMA copies 160 bytes from XX00 into OAM, like Mooneye's oam_dma tests check
+mod common;
+
+use gb_rs::{debug_session::Watchpoint, HeadlessConsole};
+
+//XOR A, LDH [LCDC], A, LD HL, 0xC000, LD B, 0xA0, then fills 0xC000-0xC09F with the inverse of each address's low byte
+//before LD A, 0xC0, LDH [DMA], A, JR -2
+const PROGRAM: &[u8] = &[0xAF, 0xE0, 0x40, 0x21, 0x00, 0xC0, 0x06, 0xA0, 0x7D, 0x2F, 0x22, 0x05, 0x20, 0xFA, 0x3E, 0xC0, 0xE0, 0x46, 0x18, 0xFE];
+
+//Long enough to fill WRAM and finish the transfer
+fn run_dma(headless: &mut HeadlessConsole) {
+    for _cycle in 0..2000 {
+        headless.console.step();
+    }
+}
+
+fn oam(headless: &mut HeadlessConsole) -> Vec<u8> {
+    (0xFE00..0xFEA0).map(|address| headless.console.peek(None, address).unwrap()).collect()
+}
+
+#[test]
+fn copies_source_page_into_oam() {
+    let mut headless = common::boot_rom("oam-dma-basic", &[(0x150, PROGRAM)]);
+    run_dma(&mut headless);
+    let expected: Vec<u8> = (0..0xA0).map(|index: u8| !index).collect();
+    assert_eq!(oam(&mut headless), expected);
+}
+
+#[test]
+fn dma_reads_do_not_hit_watchpoints() {
+    let mut headless = common::boot_rom("oam-dma-watchpoint", &[(0x150, PROGRAM)]);
+    headless.console.set_watchpoints(vec![Watchpoint { start: 0xC000, end: 0xC09F, on_read: true, on_write: false, is_logged: true }]);
+    run_dma(&mut headless);
+    assert!(headless.console.take_io_break().is_none());
+    assert!(headless.console.take_watch_log().is_empty());
+}
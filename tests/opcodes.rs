@@ -0,0 +1,22 @@
+//The opcode reference is built from the timing table the CPU runs on, so known timings are checked through it
+use gb_rs::opcodes::{self, OpcodeInfo};
+
+fn info(bytes: &[u8]) -> &'static OpcodeInfo {
+    opcodes::reference().iter().find(|info| info.bytes == bytes).unwrap_or_else(|| panic!("{:02X?} isn't in the reference", bytes))
+}
+
+#[test]
+fn known_timings() {
+    for (bytes, m_cycles) in [(&[0xF1][..], 3), (&[0xE9], 1), (&[0xCD], 6), (&[0xC9], 4), (&[0xF8], 3), (&[0xE8], 4), (&[0xF9], 2), (&[0xCB, 0x46], 3), (&[0xCB, 0x06], 4)] {
+        let info = info(bytes);
+        assert_eq!((info.m_cycles, info.m_cycles_not_taken), (m_cycles, None), "{}", info.mnemonic);
+    }
+}
+
+#[test]
+fn conditional_timings() {
+    for (opcode, taken, not_taken) in [(0x20, 3, 2), (0xC0, 5, 2), (0xC2, 4, 3), (0xC4, 6, 3)] {
+        let info = info(&[opcode]);
+        assert_eq!((info.m_cycles, info.m_cycles_not_taken), (taken, Some(not_taken)), "{}", info.mnemonic);
+    }
+}
@@ -0,0 +1,31 @@
+//Reads from 0xFEA0-0xFEFF with the accurate option on, with the LCD off so the PPU doesn't lock OAM
+mod common;
+
+//XOR A, LDH [LCDC], A, LD A, [0xFEB3], LD B, A
+const PROGRAM: &[u8] = &[0xAF, 0xE0, 0x40, 0xFA, 0xB3, 0xFE, 0x47, 0x18, 0xFE];
+//The same, but starting an OAM DMA from 0xC000 first
+const DMA_PROGRAM: &[u8] = &[0xAF, 0xE0, 0x40, 0x3E, 0xC0, 0xE0, 0x46, 0xFA, 0xB3, 0xFE, 0x47, 0x18, 0xFE];
+
+fn read_prohibited_area(name: &str, cgb_flag: u8, program: &[u8]) -> u8 {
+    let mut headless = common::boot_rom(&format!("prohibited-{}", name), &[(0x143, &[cgb_flag]), (0x150, program)]);
+    headless.console.is_prohibited_area_accurate = true;
+    for _cycle in 0..40 {
+        headless.console.step();
+    }
+    headless.console.cpu_registers().b
+}
+
+#[test]
+fn dmg_reads_zero() {
+    assert_eq!(read_prohibited_area("dmg", 0x00, PROGRAM), 0x00);
+}
+
+#[test]
+fn cgb_repeats_address_nibble() {
+    assert_eq!(read_prohibited_area("cgb", 0x80, PROGRAM), 0xBB);
+}
+
+#[test]
+fn dma_locks_area() {
+    assert_eq!(read_prohibited_area("dma", 0x00, DMA_PROGRAM), 0xFF);
+}
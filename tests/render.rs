@@ -0,0 +1,127 @@
+//Renders synthetic frames through the same ScreenPixel path the game tab uses and checks every pixel of the result
+use egui::Color32;
+use gb_rs::{PalettePreset, PaletteSettings, ScreenColors, ScreenPixel};
+
+//Paints the rects in order into a software canvas, sampling at pixel centers like a rasterizer would
+fn rasterize(pixels: &[ScreenPixel], canvas_size: (usize, usize), game_rect: (f32, f32, f32, f32)) -> Vec<Color32> {
+    let (canvas_width, canvas_height) = canvas_size;
+    let (x_offset, y_offset, game_width, game_height) = game_rect;
+    let mut canvas = vec![Color32::TRANSPARENT; canvas_width * canvas_height];
+
+    for pixel in pixels {
+        let shape = pixel.to_rect(game_height, game_width, y_offset, x_offset);
+        let rows = (shape.rect.min.y.max(0.0) as usize)..(shape.rect.max.y.ceil() as usize).min(canvas_height);
+        let columns = (shape.rect.min.x.max(0.0) as usize)..(shape.rect.max.x.ceil() as usize).min(canvas_width);
+        for y in rows {
+            for x in columns.clone() {
+                if shape.rect.contains(egui::pos2(x as f32 + 0.5, y as f32 + 0.5)) {
+                    canvas[y * canvas_width + x] = shape.fill;
+                }
+            }
+        }
+    }
+    canvas
+}
+
+//Checks the canvas against nearest neighbour scaling of the frame. Outside the game area only the top and left
+//are checked, since each rect overlaps the next by a pixel to hide seams
+fn assert_matches_frame(colors: &ScreenColors, canvas: &[Color32], canvas_size: (usize, usize), game_rect: (f32, f32, f32, f32)) {
+    let (canvas_width, canvas_height) = canvas_size;
+    let (x_offset, y_offset, game_width, game_height) = game_rect;
+    let scale_x = game_width / 160.0;
+    let scale_y = game_height / 144.0;
+
+    for y in 0..canvas_height {
+        for x in 0..canvas_width {
+            let center = (x as f32 + 0.5 - x_offset, y as f32 + 0.5 - y_offset);
+            let color = canvas[y * canvas_width + x];
+            if center.0 < 0.0 || center.1 < 0.0 {
+                assert_eq!(color, Color32::TRANSPARENT, "painted outside the game area at {}, {}", x, y);
+            }
+            else if center.0 < game_width && center.1 < game_height {
+                let expected = colors[(center.1 / scale_y) as usize][(center.0 / scale_x) as usize];
+                assert_eq!(color, expected, "wrong color at {}, {}", x, y);
+            }
+        }
+    }
+}
+
+fn checkerboard() -> Box<ScreenColors> {
+    let mut colors = Box::new([[Color32::WHITE; 160]; 144]);
+    for (i, row) in colors.iter_mut().enumerate() {
+        for (j, color) in row.iter_mut().enumerate() {
+            if (i / 8 + j / 8) % 2 == 1 {
+                *color = Color32::BLACK;
+            }
+        }
+    }
+    colors
+}
+
+//Four vertical bands of color indexes 0-3 mapped through BGP, the way the background is colored
+fn palette_gradient(palette: &PaletteSettings, bgp: u8) -> Box<ScreenColors> {
+    let shades = palette.background_colors(bgp);
+    let mut colors = Box::new([[Color32::PLACEHOLDER; 160]; 144]);
+    for row in colors.iter_mut() {
+        for (j, color) in row.iter_mut().enumerate() {
+            *color = shades[j * 4 / 160];
+        }
+    }
+    colors
+}
+
+#[test]
+fn rows_are_run_length_encoded() {
+    let uniform = Box::new([[Color32::WHITE; 160]; 144]);
+    assert_eq!(ScreenPixel::from_colors(&uniform).len(), 144);
+    assert_eq!(ScreenPixel::from_colors(&checkerboard()).len(), 144 * 20);
+}
+
+#[test]
+fn checkerboard_at_integer_scale_with_offset() {
+    let colors = checkerboard();
+    let canvas_size = (500, 450);
+    let game_rect = (10.0, 5.0, 480.0, 432.0);
+
+    let canvas = rasterize(&ScreenPixel::from_colors(&colors), canvas_size, game_rect);
+    assert_matches_frame(&colors, &canvas, canvas_size, game_rect);
+}
+
+#[test]
+fn checkerboard_at_fractional_scale() {
+    let colors = checkerboard();
+    let canvas_size = (260, 230);
+    let game_rect = (3.5, 7.25, 240.0, 216.0);
+
+    let canvas = rasterize(&ScreenPixel::from_colors(&colors), canvas_size, game_rect);
+    assert_matches_frame(&colors, &canvas, canvas_size, game_rect);
+}
+
+#[test]
+fn palette_gradient_for_every_preset() {
+    for preset in PalettePreset::ALL {
+        let palette = PaletteSettings { preset, ..Default::default() };
+        let shades = palette.background_colors(0b11_10_01_00);
+        let reversed = palette.background_colors(0b00_01_10_11);
+        assert_eq!(shades.iter().rev().copied().collect::<Vec<Color32>>(), reversed.to_vec(), "BGP didn't reorder {}", preset.name());
+        for (i, shade) in shades.iter().enumerate() {
+            assert!(shades[i + 1..].iter().all(|other| other != shade), "{} has two equal shades", preset.name());
+        }
+
+        let colors = palette_gradient(&palette, 0b11_10_01_00);
+        let canvas_size = (330, 300);
+        let game_rect = (0.0, 0.0, 320.0, 288.0);
+        let canvas = rasterize(&ScreenPixel::from_colors(&colors), canvas_size, game_rect);
+        assert_matches_frame(&colors, &canvas, canvas_size, game_rect);
+    }
+}
+
+#[test]
+fn contrast_spreads_background_shades() {
+    let flat = PaletteSettings::default().background_colors(0b11_10_01_00);
+    let stretched = PaletteSettings { background_contrast: 2.0, ..Default::default() }.background_colors(0b11_10_01_00);
+    assert_eq!(stretched[0], Color32::WHITE);
+    assert_eq!(stretched[3], Color32::BLACK);
+    assert!(stretched[1].r() > flat[1].r());
+    assert!(stretched[2].r() < flat[2].r());
+}
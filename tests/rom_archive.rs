@@ -0,0 +1,60 @@
+//Roms opened from .zip and .gz archives
+mod common;
+
+use std::{fs, io::Write};
+
+use flate2::{write::GzEncoder, Compression};
+use gb_rs::{rom_archive, CartridgeError, MAX_ROM_SIZE};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+#[test]
+fn zip_takes_first_rom_and_keys_saves_on_its_name() {
+    let rom_path = common::write_rom("zipped", &[]);
+    let rom = fs::read(&rom_path).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+
+    let archive_path = rom_path.with_extension("zip");
+    let mut archive = ZipWriter::new(fs::File::create(&archive_path).unwrap());
+    archive.start_file("readme.txt", SimpleFileOptions::default()).unwrap();
+    archive.write_all(b"Not a rom").unwrap();
+    archive.start_file("roms/Game.gbc", SimpleFileOptions::default()).unwrap();
+    archive.write_all(&rom).unwrap();
+    archive.finish().unwrap();
+
+    let (unzipped_rom, save_path) = rom_archive::read_rom(&archive_path.display().to_string()).unwrap();
+    fs::remove_file(&archive_path).unwrap();
+    assert!(unzipped_rom == rom);
+    assert_eq!(save_path, archive_path.with_file_name("Game.gbc").display().to_string());
+}
+
+#[test]
+fn gzip_keys_saves_on_inner_name() {
+    let rom_path = common::write_rom("gzipped", &[]);
+    let rom = fs::read(&rom_path).unwrap();
+    fs::remove_file(&rom_path).unwrap();
+
+    let archive_path = format!("{}.gz", rom_path.display());
+    let mut encoder = GzEncoder::new(fs::File::create(&archive_path).unwrap(), Compression::default());
+    encoder.write_all(&rom).unwrap();
+    encoder.finish().unwrap();
+
+    let (unzipped_rom, save_path) = rom_archive::read_rom(&archive_path).unwrap();
+    fs::remove_file(&archive_path).unwrap();
+    assert!(unzipped_rom == rom);
+    assert_eq!(save_path, rom_path.display().to_string());
+}
+
+#[test]
+fn oversized_archive_is_rejected() {
+    let rom_path = common::write_rom("gzip-bomb", &[]);
+    fs::remove_file(&rom_path).unwrap();
+
+    let archive_path = format!("{}.gz", rom_path.display());
+    let mut encoder = GzEncoder::new(fs::File::create(&archive_path).unwrap(), Compression::default());
+    encoder.write_all(&vec![0; MAX_ROM_SIZE + 1]).unwrap();
+    encoder.finish().unwrap();
+
+    let result = rom_archive::read_rom(&archive_path);
+    fs::remove_file(&archive_path).unwrap();
+    assert!(matches!(result, Err(CartridgeError::TooLarge)));
+}
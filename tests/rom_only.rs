@@ -0,0 +1,33 @@
+//Cartridges without a mapper, with and without RAM wired to 0xA000-0xBFFF
+mod common;
+
+use std::fs;
+
+use gb_rs::HeadlessConsole;
+
+const CARTRIDGE_TYPE: u16 = 0x147;
+
+//Stores 0x5A at 0xA123 and reads it back into B
+fn run(name: &str, cartridge_type: u8) -> u8 {
+    //LD A, 0x5A, LD [0xA123], A, XOR A, LD A, [0xA123], LD B, A, JR -2
+    let program = [0x3E, 0x5A, 0xEA, 0x23, 0xA1, 0xAF, 0xFA, 0x23, 0xA1, 0x47, 0x18, 0xFE];
+    let rom_path = common::write_rom(&format!("rom-only-{}", name), &[(0x150, &program), (CARTRIDGE_TYPE, &[cartridge_type])]);
+    let mut headless = HeadlessConsole::new(&rom_path).unwrap();
+    headless.run_frame();
+    let b = headless.console.cpu_registers().b;
+
+    drop(headless);
+    fs::remove_file(&rom_path).unwrap();
+    b
+}
+
+#[test]
+fn rom_only_reads_open_bus() {
+    assert_eq!(run("none", 0x00), 0xFF);
+}
+
+#[test]
+fn rom_ram_keeps_writes() {
+    assert_eq!(run("ram", 0x08), 0x5A);
+    assert_eq!(run("ram-battery", 0x09), 0x5A);
+}
@@ -0,0 +1,112 @@
+//Scripts run against a headless console the way the frontend runs them
+mod common;
+
+use gb_rs::{script::{OverlayText, Script}, JoypadState};
+
+//LD A, [0xC000], INC A, LD [0xC000], A, JR back to the load
+const COUNTER: &[u8] = &[0xFA, 0x00, 0xC0, 0x3C, 0xEA, 0x00, 0xC0, 0x18, 0xF7];
+
+const SCRIPT: &str = r#"
+let writes = 0;
+let last = 0;
+
+on_memory_write(0xC000, |address, value| {
+    writes += 1;
+    last = value;
+});
+
+on_frame(|| {
+    if frame() % 2 == 0 {
+        press("a");
+    }
+    release("b");
+    text(8, 16, "C000: " + hex(read(0xC000)));
+    text(0, 0, "last " + hex(last));
+});
+
+write(0xC100, 0x12);
+"#;
+
+#[test]
+fn hooks_memory_and_frames() {
+    let mut headless = common::boot_rom("script", &[(0x150, COUNTER)]);
+    let mut script = Script::load(SCRIPT, &mut headless.console).unwrap();
+    assert_eq!(headless.console.peek(None, 0xC100), Ok(0x12));
+    assert_eq!(script.write_ranges(), [(0xC000, 0xC000)]);
+
+    headless.console.set_write_hooks(script.write_ranges());
+    for _ in 0..100 {
+        headless.console.step();
+        for (address, value) in headless.console.take_hooked_writes() {
+            script.on_memory_write(address, value, &mut headless.console).unwrap();
+        }
+    }
+    let counter = headless.console.peek(None, 0xC000).unwrap();
+    assert!(counter > 0);
+
+    script.on_frame(&mut headless.console).unwrap();
+    assert_eq!(script.overlay(), [
+        OverlayText { x: 8.0, y: 16.0, text: format!("C000: {:02X}", counter) },
+        OverlayText { x: 0.0, y: 0.0, text: format!("last {:02X}", counter) },
+    ]);
+    let player = JoypadState { b: true, ..Default::default() };
+    assert!(script.apply_input(player) == JoypadState::default());
+    script.on_frame(&mut headless.console).unwrap();
+    assert!(script.apply_input(player) == JoypadState { a: true, ..Default::default() });
+}
+
+#[test]
+fn reports_errors() {
+    let mut headless = common::boot_rom("script-errors", &[]);
+    assert!(Script::load("let x = ;", &mut headless.console).err().unwrap().contains("line 1"));
+    assert!(Script::load("\nlet x = (1 + 2;", &mut headless.console).err().unwrap().contains("line 2"));
+
+    let mut script = Script::load("on_frame(|| { loop { } });", &mut headless.console).unwrap();
+    assert!(script.on_frame(&mut headless.console).unwrap_err().contains("Too many operations"));
+    let mut script = Script::load("fn held() { press(\"turbo\"); } on_frame(\"held\");", &mut headless.console).unwrap();
+    let error = script.on_frame(&mut headless.console).unwrap_err();
+    assert!(error.starts_with("In on_frame: ") && error.contains("There's no turbo button"), "{}", error);
+}
+
+#[test]
+fn runs_monitor_commands() {
+    let mut headless = common::boot_rom("script-monitor", &[]);
+    let script = r#"
+        monitor("w C000 12 34");
+        let dump = monitor("r C000 2");
+        write(0xC002, if dump == "C000: 12 34" {1} else {0});
+    "#;
+    Script::load(script, &mut headless.console).unwrap();
+    assert_eq!(headless.console.peek(None, 0xC002), Ok(1));
+
+    let error = Script::load(r#"monitor("bp 0150");"#, &mut headless.console).err().unwrap();
+    assert!(error.contains("only be set from the debugger"), "{}", error);
+}
+
+#[test]
+fn hooks_scanlines() {
+    let mut headless = common::boot_rom("script-scanlines", &[(0x150, &[0x18, 0xFE])]);
+    let script = r#"
+        on_scanline(10, |ly| write(0xFF43, ly * 4));
+        on_scanline(20, |ly| write(0xFF43, 0));
+    "#;
+    let mut script = Script::load(script, &mut headless.console).unwrap();
+    headless.console.set_raster_hooks(Some(script.raster_hooks()));
+
+    //SCX as each line starts drawing, over the second frame since the first is all V-blank after the boot ROM
+    let mut scx = [None; 154];
+    for cycle in 0..2 * 17556 {
+        headless.console.step();
+        if script.is_scanline_drawn() {
+            script.on_scanlines(&mut headless.console).unwrap();
+        }
+        let ly = headless.console.peek(None, 0xFF44).unwrap() as usize;
+        if cycle >= 17556 {
+            scx[ly].get_or_insert(headless.console.peek(None, 0xFF43).unwrap());
+        }
+    }
+    assert_eq!(&scx[5..25].iter().map(|scx| scx.unwrap()).collect::<Vec<_>>(), &[&[0; 6][..], &[40; 10], &[0; 4]].concat());
+
+    let error = Script::load("on_scanline(144, || 0);", &mut headless.console).err().unwrap();
+    assert!(error.contains("144 isn't a visible line"), "{}", error);
+}
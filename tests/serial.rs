@@ -0,0 +1,25 @@
+//What a game reads back after clocking a byte out of the link port, depending on what's plugged in
+mod common;
+
+use gb_rs::{Disconnected, Loopback, SerialDevice};
+
+//LD A, 0x5A, LDH [SB], A, LD A, 0x81, LDH [SC], A, then LDH A, [SC], BIT 7, A, JR NZ until the transfer is done,
+//then LDH A, [SB], LD B, A
+const PROGRAM: &[u8] = &[0x3E, 0x5A, 0xE0, 0x01, 0x3E, 0x81, 0xE0, 0x02, 0xF0, 0x02, 0xCB, 0x7F, 0x20, 0xFA, 0xF0, 0x01, 0x47, 0x18, 0xFE];
+
+fn received(name: &str, device: Box<dyn SerialDevice>) -> (u8, Vec<u8>) {
+    let mut headless = common::boot_rom(&format!("serial-{}", name), &[(0x150, PROGRAM)]);
+    headless.console.set_serial_device(device);
+    headless.run_frame();
+    (headless.console.cpu_registers().b, headless.serial_output().to_vec())
+}
+
+#[test]
+fn loopback_returns_sent_byte() {
+    assert_eq!(received("loopback", Box::new(Loopback)), (0x5A, vec![0x5A]));
+}
+
+#[test]
+fn disconnected_reads_high() {
+    assert_eq!(received("disconnected", Box::new(Disconnected)), (0xFF, vec![0x5A]));
+}
@@ -0,0 +1,120 @@
+//The CPU on its own against flat RAM, one instruction at a time. The vectors in tests/sm83_sample are always run, and
+//the community SM83 ones, one JSON file per opcode, when they're in tests/sm83 or where GB_RS_SM83_TESTS points
+mod common;
+
+use std::path::Path;
+
+use common::single_step::{self, Vector};
+
+//LD [n16], SP, then INC A, which sets H and keeps C
+const VECTORS: &str = r#"[
+    {
+        "name": "08 ld [n16], sp",
+        "initial": {"pc": 49152, "sp": 48879, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[49152, 8], [49153, 52], [49154, 18]]},
+        "final": {"pc": 49155, "sp": 48879, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[49152, 8], [49153, 52], [49154, 18], [4660, 239], [4661, 190]]},
+        "cycles": [[49152, 8, "r-m"], [49153, 52, "r-m"], [49154, 18, "r-m"], [4660, 239, "-wm"], [4661, 190, "-wm"]]
+    },
+    {
+        "name": "3c inc a",
+        "initial": {"pc": 336, "sp": 65534, "a": 15, "b": 1, "c": 2, "d": 3, "e": 4, "f": 16, "h": 5, "l": 6, "ime": 1, "ie": 0,
+            "ram": [[336, 60]]},
+        "final": {"pc": 337, "sp": 65534, "a": 16, "b": 1, "c": 2, "d": 3, "e": 4, "f": 48, "h": 5, "l": 6, "ime": 1, "ie": 0,
+            "ram": [[336, 60]]},
+        "cycles": [[336, 60, "r-m"]]
+    }
+]"#;
+
+//A backward JR, a NOP at 0xFFFF, and a CALL whose return address is past 0xFFFF. With overflow checks on, these
+//panic unless the program counter wraps
+const WRAPPING_VECTORS: &str = r#"[
+    {
+        "name": "18 jr e8 to itself",
+        "initial": {"pc": 336, "sp": 57342, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[336, 24], [337, 254]]},
+        "final": {"pc": 336, "sp": 57342, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[336, 24], [337, 254]]},
+        "cycles": [[336, 24, "r-m"], [337, 254, "r-m"], [null, null, "---"]]
+    },
+    {
+        "name": "00 nop at ffff",
+        "initial": {"pc": 65535, "sp": 57342, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[65535, 0]]},
+        "final": {"pc": 0, "sp": 57342, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[65535, 0]]},
+        "cycles": [[65535, 0, "r-m"]]
+    },
+    {
+        "name": "cd call n16 at fffd",
+        "initial": {"pc": 65533, "sp": 57342, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[65533, 205], [65534, 52], [65535, 18]]},
+        "final": {"pc": 4660, "sp": 57340, "a": 0, "b": 0, "c": 0, "d": 0, "e": 0, "f": 0, "h": 0, "l": 0, "ime": 0, "ie": 0,
+            "ram": [[65533, 205], [65534, 52], [65535, 18], [57341, 0], [57340, 0]]},
+        "cycles": [[65533, 205, "r-m"], [65534, 52, "r-m"], [65535, 18, "r-m"], [null, null, "---"], [57341, 0, "-wm"], [57340, 0, "-wm"]]
+    }
+]"#;
+
+fn parse(vectors: &str) -> Vec<Vector> {
+    serde_json::from_str(vectors).unwrap()
+}
+
+//Runs every file in the folder and returns how many vectors there were and a line for each file with failures
+fn run_folder(name: &str, folder: &Path) -> (usize, Vec<String>) {
+    let mut console = common::boot_rom(name, &[]).console;
+    let mut total = 0;
+    let mut failing_files = Vec::new();
+    for path in single_step::vector_files(folder) {
+        let (count, failures) = single_step::run_vectors(&mut console, &single_step::read_vectors(&path));
+        total += count;
+        if let Some(first) = failures.first() {
+            failing_files.push(format!("{}: {} of {} failed, first {}", path.display(), failures.len(), count, first));
+        }
+    }
+    (total, failing_files)
+}
+
+#[test]
+fn runs_vectors() {
+    let mut console = common::boot_rom("single-step", &[]).console;
+    let (count, failures) = single_step::run_vectors(&mut console, &parse(VECTORS));
+    assert_eq!(count, 2);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn program_counter_wraps() {
+    let mut console = common::boot_rom("single-step-wrap", &[]).console;
+    let (count, failures) = single_step::run_vectors(&mut console, &parse(WRAPPING_VECTORS));
+    assert_eq!(count, 3);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn reports_mismatches() {
+    let mut console = common::boot_rom("single-step-mismatch", &[]).console;
+    let vectors = VECTORS.replace(r#""a": 16"#, r#""a": 17"#).replace("4661, 190]]}", "4661, 191]]}");
+    let (_, failures) = single_step::run_vectors(&mut console, &parse(&vectors));
+    assert_eq!(failures, [
+        "08 ld [n16], sp: $1235 was BE, expected BF",
+        "3c inc a: a was 10, expected 11",
+    ]);
+}
+
+#[test]
+fn sample_vectors() {
+    let (total, failing_files) = run_folder("single-step-sample", &single_step::sample_folder());
+    assert!(total > 0, "No vectors in {}", single_step::sample_folder().display());
+    assert!(failing_files.is_empty(), "{} opcodes failed:\n{}", failing_files.len(), failing_files.join("\n"));
+}
+
+#[test]
+fn sm83_vectors() {
+    let Some(folder) = single_step::vector_folder() else {
+        eprintln!("SKIPPED: no SM83 vectors in tests/sm83 or GB_RS_SM83_TESTS, only the sample ones were run");
+        return;
+    };
+    let (total, failing_files) = run_folder("single-step-sm83", &folder);
+    eprintln!("Ran {} vectors", total);
+    assert!(failing_files.is_empty(), "{} opcodes failed:\n{}", failing_files.len(), failing_files.join("\n"));
+}
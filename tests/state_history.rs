@@ -0,0 +1,18 @@
+//Rewinding has to give back exactly the states that were recorded, newest first, across keyframes
+use gb_rs::savestate::{StateHistory, StateHistorySettings};
+
+#[test]
+fn pops_states_newest_first() {
+    let mut history = StateHistory::new(StateHistorySettings { enabled: true, ..Default::default() });
+    let states: Vec<Vec<u8>> = (0..75_u32).map(|i| (0..256_u32).map(|byte| (byte * 7 + i * i) as u8).collect()).collect();
+    for state in &states {
+        history.push(state.clone());
+    }
+
+    for state in states.iter().rev() {
+        assert_eq!(history.pop().as_ref(), Some(state));
+    }
+    assert_eq!(history.pop(), None);
+    assert_eq!(history.state_count(), 0);
+    assert_eq!(history.memory_usage(), 0);
+}
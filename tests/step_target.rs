@@ -0,0 +1,44 @@
+//Where the debugger's step buttons stop, checked the way the emulation thread checks them between instructions
+use gb_rs::debug_session::StepTarget;
+
+#[test]
+fn next_instruction_skips_the_current_one() {
+    let mut target = StepTarget::NextInstruction { has_started: false };
+    assert!(!target.is_reached(0x150, 0xFFFE));
+    assert!(target.is_reached(0x151, 0xFFFE));
+
+    //Paused partway through an instruction, the next check is already the next one
+    let mut target = StepTarget::NextInstruction { has_started: true };
+    assert!(target.is_reached(0x151, 0xFFFE));
+}
+
+#[test]
+fn step_over_waits_for_the_outer_return() {
+    //CALL at 0x150 with SP at FFFE, returning to 0x153
+    let mut target = StepTarget::Return { address: 0x153, stack_pointer: 0xFFFE };
+    assert!(!target.is_reached(0x200, 0xFFFC));
+    //The function calls itself from 0x150 too, and that call returns to 0x153 a level deeper
+    assert!(!target.is_reached(0x153, 0xFFFC));
+    assert!(target.is_reached(0x153, 0xFFFE));
+}
+
+#[test]
+fn step_over_untaken_call_stops_after_it() {
+    //CALL NZ with Z set falls through to the next instruction without touching the stack
+    let mut target = StepTarget::Return { address: 0x153, stack_pointer: 0xFFFE };
+    assert!(target.is_reached(0x153, 0xFFFE));
+}
+
+#[test]
+fn step_over_survives_stack_cleanup() {
+    //A function that pops its return address and jumps back leaves SP higher than it was at the call
+    let mut target = StepTarget::Return { address: 0x153, stack_pointer: 0xFFFC };
+    assert!(target.is_reached(0x153, 0xFFFE));
+}
+
+#[test]
+fn run_to_address() {
+    let mut target = StepTarget::Address(0x4000);
+    assert!(!target.is_reached(0x3FFF, 0xFFFE));
+    assert!(target.is_reached(0x4000, 0xDFF0));
+}
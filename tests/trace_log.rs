@@ -0,0 +1,38 @@
+//Instruction traces in Game Boy Doctor's format
+mod common;
+
+use gb_rs::trace_log::InstructionTrace;
+
+//LD A, 0x42, then JR to itself
+const PROGRAM: &[u8] = &[0x3E, 0x42, 0x18, 0xFE];
+
+#[test]
+fn ring_buffer_keeps_last_instructions() {
+    let mut headless = common::boot_rom("trace", &[(0x150, PROGRAM)]);
+    headless.console.set_instruction_trace(Some(InstructionTrace::ring_buffer(3)));
+    for _ in 0..64 {
+        headless.console.step();
+    }
+
+    let lines = headless.console.instruction_trace().unwrap().lines();
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line.starts_with("A:42 ") && line.ends_with(" PC:0152 PCMEM:18,FE,00,00")), "{:?}", lines);
+
+    let instruction_trace = headless.console.set_instruction_trace(None).unwrap();
+    headless.console.step();
+    assert_eq!(instruction_trace.lines(), lines);
+}
+
+#[test]
+fn starts_at_entry_point() {
+    let mut headless = common::boot_rom("trace-entry", &[(0x150, PROGRAM)]);
+    headless.console.set_instruction_trace(Some(InstructionTrace::ring_buffer(16)));
+    for _ in 0..8 {
+        headless.console.step();
+    }
+
+    let lines = headless.console.instruction_trace().unwrap().lines();
+    assert!(lines[0].ends_with(" PC:0100 PCMEM:00,C3,50,01"), "{}", lines[0]);
+    assert!(lines[0].starts_with("A:"));
+    assert!(lines[2].ends_with(" PC:0150 PCMEM:3E,42,18,FE"), "{}", lines[2]);
+}
@@ -0,0 +1,60 @@
+//CGB VRAM DMA through HDMA1-5: general transfers copy everything at once, HBlank ones a block per line
+mod common;
+
+use gb_rs::HeadlessConsole;
+
+const CGB_FLAG: u16 = 0x143;
+const SOURCE: u16 = 0x4000;
+const DESTINATION: u16 = 0x9000;
+
+//Points HDMA1-4 at SOURCE and DESTINATION, writes HDMA5 with each value in turn, then stores HDMA5 at 0xC000
+fn program(lcdc: u8, hdma5_writes: &[u8]) -> Vec<u8> {
+    let mut code = vec![
+        0x3E, lcdc, 0xE0, 0x40, //LD A, lcdc; LDH [LCDC], A
+        0x3E, (SOURCE >> 8) as u8, 0xE0, 0x51, //LD A, n8; LDH [HDMA1], A
+        0x3E, SOURCE as u8, 0xE0, 0x52, //LDH [HDMA2], A
+        0x3E, (DESTINATION >> 8) as u8, 0xE0, 0x53, //LDH [HDMA3], A
+        0x3E, DESTINATION as u8, 0xE0, 0x54, //LDH [HDMA4], A
+    ];
+    for value in hdma5_writes {
+        code.extend([0x3E, *value, 0xE0, 0x55]); //LD A, n8; LDH [HDMA5], A
+    }
+    code.extend([0xF0, 0x55, 0xEA, 0x00, 0xC0, 0x18, 0xFE]); //LDH A, [HDMA5]; LD [0xC000], A; JR -2
+    code
+}
+
+fn boot(name: &str, code: &[u8]) -> HeadlessConsole {
+    let data: Vec<u8> = (1..=0x40).collect();
+    common::boot_rom(name, &[(0x150, code), (CGB_FLAG, &[0x80]), (SOURCE, &data)])
+}
+
+fn vram(headless: &mut HeadlessConsole, length: u16) -> Vec<u8> {
+    (DESTINATION..DESTINATION + length).map(|address| headless.console.peek(None, address).unwrap()).collect()
+}
+
+#[test]
+fn general_transfer_copies_every_block() {
+    let mut headless = boot("vram-dma-general", &program(0x91, &[0x01]));
+    headless.run_frame();
+    assert_eq!(vram(&mut headless, 0x30), [(1..=0x20).collect::<Vec<u8>>(), vec![0; 0x10]].concat());
+    assert_eq!(headless.console.peek(None, 0xC000), Ok(0xFF));
+}
+
+#[test]
+fn hblank_transfer_copies_a_block_per_line() {
+    let mut headless = boot("vram-dma-hblank", &program(0x91, &[0x82]));
+    headless.run_frame();
+    headless.run_frame();
+    assert_eq!(headless.console.peek(None, 0xC000), Ok(0x02), "Nothing should be copied before an HBlank");
+    assert_eq!(vram(&mut headless, 0x40), [(1..=0x30).collect::<Vec<u8>>(), vec![0; 0x10]].concat());
+    assert_eq!(headless.console.peek(None, 0xFF55), Ok(0xFF));
+}
+
+#[test]
+fn hblank_transfer_can_be_stopped() {
+    //With the LCD off there's no HBlank, so nothing is copied before the second write stops the transfer
+    let mut headless = boot("vram-dma-stop", &program(0x00, &[0x83, 0x00]));
+    headless.run_frame();
+    assert_eq!(headless.console.peek(None, 0xC000), Ok(0x83));
+    assert_eq!(vram(&mut headless, 0x10), [0; 0x10]);
+}
@@ -0,0 +1,35 @@
+//Watchpoints that log instead of pausing
+mod common;
+
+use gb_rs::debug_session::Watchpoint;
+
+//LD A, 0x12, LD [0xC000], A, LD A, [0xC000], JR -2
+const PROGRAM: &[u8] = &[0x3E, 0x12, 0xEA, 0x00, 0xC0, 0xFA, 0x00, 0xC0, 0x18, 0xFE];
+
+#[test]
+fn logs_the_address_of_the_accessing_instruction() {
+    let mut headless = common::boot_rom("watch-log", &[(0x150, PROGRAM)]);
+    headless.console.set_watchpoints(vec![Watchpoint { start: 0xC000, end: 0xC000, on_read: true, on_write: true, is_logged: true }]);
+    for _cycle in 0..20 {
+        headless.console.step();
+    }
+
+    let entries: Vec<(u16, u16, Option<u8>)> = headless.console.take_watch_log().iter()
+        .map(|entry| (entry.program_counter, entry.address, entry.new_value))
+        .collect();
+    assert_eq!(entries, vec![(0x152, 0xC000, Some(0x12)), (0x155, 0xC000, None)]);
+    assert!(headless.console.take_io_break().is_none());
+}
+
+//The CPU looks at the opcode ahead of running it to work out its timing, which isn't an access of its own
+#[test]
+fn logs_each_fetch_once() {
+    let mut headless = common::boot_rom("watch-log-fetch", &[(0x150, PROGRAM)]);
+    headless.console.set_watchpoints(vec![Watchpoint { start: 0x150, end: 0x151, on_read: true, on_write: false, is_logged: true }]);
+    for _cycle in 0..20 {
+        headless.console.step();
+    }
+
+    let addresses: Vec<u16> = headless.console.take_watch_log().iter().map(|entry| entry.address).collect();
+    assert_eq!(addresses, vec![0x150, 0x151]);
+}